@@ -14,6 +14,9 @@
 
 #![forbid(unsafe_code)]
 
+pub mod signer;
+pub use signer::*;
+
 use snarkvm::{
     console::{network::prelude::*, types::Field},
     prelude::*,
@@ -21,16 +24,22 @@ use snarkvm::{
 
 use colored::*;
 use core::fmt;
+use std::sync::Arc;
 
 /// A helper struct for an Aleo account.
+///
+/// All signatures are produced by the account signer. If the account was initialized from
+/// an external signer, the private key and view key are not available in this process.
 #[derive(Clone, Debug)]
 pub struct Account<N: Network> {
-    /// The account private key.
-    private_key: PrivateKey<N>,
-    /// The account view key.
-    view_key: ViewKey<N>,
+    /// The account private key, if it is held in-process.
+    private_key: Option<PrivateKey<N>>,
+    /// The account view key, if the private key is held in-process.
+    view_key: Option<ViewKey<N>>,
     /// The account address.
     address: Address<N>,
+    /// The account signer.
+    signer: Arc<dyn Signer<N>>,
 }
 
 impl<N: Network> Account<N> {
@@ -39,14 +48,34 @@ impl<N: Network> Account<N> {
         Self::try_from(PrivateKey::new(rng)?)
     }
 
-    /// Returns the account private key.
-    pub const fn private_key(&self) -> &PrivateKey<N> {
-        &self.private_key
+    /// Initializes a new account from an external signer, which holds the private key.
+    pub fn from_signer(signer: Arc<dyn Signer<N>>) -> Self {
+        Self { private_key: None, view_key: None, address: signer.address(), signer }
+    }
+
+    /// Returns the account private key, if it is held in-process.
+    ///
+    /// Note: This method returns an error for an account initialized from an external signer.
+    /// It returned the private key infallibly before the external signers were introduced (in 2.2.7).
+    pub fn private_key(&self) -> Result<&PrivateKey<N>> {
+        self.private_key
+            .as_ref()
+            .ok_or_else(|| anyhow!("The private key of '{}' is held by an external signer", self.address))
     }
 
-    /// Returns the account view key.
-    pub const fn view_key(&self) -> &ViewKey<N> {
-        &self.view_key
+    /// Returns the account view key, if the private key is held in-process.
+    ///
+    /// Note: This method returns an error for an account initialized from an external signer.
+    /// It returned the view key infallibly before the external signers were introduced (in 2.2.7).
+    pub fn view_key(&self) -> Result<&ViewKey<N>> {
+        self.view_key
+            .as_ref()
+            .ok_or_else(|| anyhow!("The view key of '{}' is held by an external signer", self.address))
+    }
+
+    /// Returns `true` if the private key is held by an external signer.
+    pub const fn is_external_signer(&self) -> bool {
+        self.private_key.is_none()
     }
 
     /// Returns the account address.
@@ -56,19 +85,49 @@ impl<N: Network> Account<N> {
 }
 
 impl<N: Network> Account<N> {
-    /// Returns a signature for the given message (as field elements), using the account private key.
+    /// Returns a signature for the given message (as field elements), using the account signer.
     pub fn sign<R: Rng + CryptoRng>(&self, message: &[Field<N>], rng: &mut R) -> Result<Signature<N>> {
-        Signature::sign(&self.private_key, message, rng)
+        self.signer.sign(&SignRequest::Fields(message.to_vec()), rng)
     }
 
     /// Returns a signature for the given message (as bytes), using the account private key.
+    ///
+    /// Note: The signers never sign arbitrary bytes, so this method fails for an account initialized
+    /// from an external signer, and bypasses the slashing protection of the account signer.
+    #[deprecated(since = "2.2.7", note = "use `sign_request` with a typed, domain-separated request instead")]
     pub fn sign_bytes<R: Rng + CryptoRng>(&self, message: &[u8], rng: &mut R) -> Result<Signature<N>> {
-        Signature::sign_bytes(&self.private_key, message, rng)
+        Signature::sign_bytes(self.private_key()?, message, rng)
     }
 
-    /// Returns a signature for the given message (as bits), using the account private key.
+    /// Returns a signature for the given message (as bits), using the account signer.
     pub fn sign_bits<R: Rng + CryptoRng>(&self, message: &[bool], rng: &mut R) -> Result<Signature<N>> {
-        Signature::sign_bits(&self.private_key, message, rng)
+        self.signer.sign(&SignRequest::Bits(message.to_vec()), rng)
+    }
+
+    /// Returns a signature for the batch ID of a batch header authored by this account, using the account signer.
+    pub fn sign_batch_header<R: Rng + CryptoRng>(
+        &self,
+        round: u64,
+        batch_id: Field<N>,
+        rng: &mut R,
+    ) -> Result<Signature<N>> {
+        self.signer.sign(&SignRequest::BatchHeader { round, batch_id }, rng)
+    }
+
+    /// Returns a signature for the batch ID of a batch proposal from the given author, using the account signer.
+    pub fn sign_batch_proposal<R: Rng + CryptoRng>(
+        &self,
+        author: Address<N>,
+        round: u64,
+        batch_id: Field<N>,
+        rng: &mut R,
+    ) -> Result<Signature<N>> {
+        self.signer.sign(&SignRequest::BatchProposal { author, round, batch_id }, rng)
+    }
+
+    /// Returns a signature for the given typed request, using the account signer.
+    pub fn sign_request<R: Rng + CryptoRng>(&self, request: &SignRequest<N>, rng: &mut R) -> Result<Signature<N>> {
+        self.signer.sign(request, rng)
     }
 
     /// Verifies a signature for the given message (as fields), using the account address.
@@ -103,7 +162,8 @@ impl<N: Network> TryFrom<&PrivateKey<N>> for Account<N> {
     fn try_from(private_key: &PrivateKey<N>) -> Result<Self, Self::Error> {
         let view_key = ViewKey::try_from(private_key)?;
         let address = view_key.to_address();
-        Ok(Self { private_key: *private_key, view_key, address })
+        let signer = Arc::new(LocalSigner::new(*private_key)?);
+        Ok(Self { private_key: Some(*private_key), view_key: Some(view_key), address, signer })
     }
 }
 
@@ -146,13 +206,16 @@ impl<N: Network> FromStr for Account<N> {
 impl<N: Network> Display for Account<N> {
     /// Renders the account as a string.
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (Some(private_key), Some(view_key)) = (&self.private_key, &self.view_key) else {
+            return write!(f, " {:>12}  {}", "Address".cyan().bold(), self.address);
+        };
         write!(
             f,
             " {:>12}  {}\n {:>12}  {}\n {:>12}  {}",
             "Private Key".cyan().bold(),
-            self.private_key,
+            private_key,
             "View Key".cyan().bold(),
-            self.view_key,
+            view_key,
             "Address".cyan().bold(),
             self.address
         )
@@ -179,6 +242,38 @@ mod tests {
     }
 
     #[test]
+    fn test_sign_challenge() {
+        // Initialize the RNG.
+        let mut rng = TestRng::default();
+        // Prepare the account and challenge.
+        let account = Account::<CurrentNetwork>::new(&mut rng).unwrap();
+        let (request_nonce, response_nonce) = (rng.gen(), rng.gen());
+        let context = (0..10).map(|_| rng.gen::<u8>()).collect::<Vec<u8>>();
+        // Sign and verify.
+        let request = SignRequest::Challenge { request_nonce, response_nonce, context };
+        let signature = account.sign_request(&request, &mut rng).unwrap();
+        assert!(request.verify(&account.address(), &signature));
+    }
+
+    #[test]
+    fn test_sign_with_external_signer() {
+        // Initialize the RNG.
+        let mut rng = TestRng::default();
+        // Prepare the account from an external signer.
+        let signer = LocalSigner::<CurrentNetwork>::new(PrivateKey::new(&mut rng).unwrap()).unwrap();
+        let account = Account::from_signer(Arc::new(signer.clone()));
+        assert_eq!(account.address(), signer.address());
+        assert!(account.is_external_signer());
+        assert!(account.private_key().is_err());
+        assert!(account.view_key().is_err());
+        // Sign and verify.
+        let batch_id = Field::rand(&mut rng);
+        let signature = account.sign_batch_header(1, batch_id, &mut rng).unwrap();
+        assert!(account.verify(&[batch_id], &signature));
+    }
+
+    #[test]
+    #[allow(deprecated)]
     fn test_sign_bytes() {
         // Initialize the RNG.
         let mut rng = TestRng::default();
@@ -188,6 +283,10 @@ mod tests {
         // Sign and verify.
         let signature = account.sign_bytes(&message, &mut rng).unwrap();
         assert!(account.verify_bytes(&message, &signature));
+
+        // Ensure an account initialized from an external signer refuses to sign raw bytes.
+        let signer = LocalSigner::<CurrentNetwork>::new(PrivateKey::new(&mut rng).unwrap()).unwrap();
+        assert!(Account::from_signer(Arc::new(signer)).sign_bytes(&message, &mut rng).is_err());
     }
 
     #[test]
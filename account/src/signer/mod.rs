@@ -0,0 +1,345 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod slashing;
+pub use slashing::*;
+
+#[cfg(unix)]
+mod unix;
+#[cfg(unix)]
+pub use unix::*;
+
+use snarkvm::{
+    console::{network::prelude::*, types::Field},
+    prelude::*,
+};
+
+use core::fmt::Debug;
+use std::net::SocketAddr;
+
+/// The maximum number of elements in a message to be signed.
+pub const MAX_SIGN_REQUEST_LENGTH: u32 = 1 << 20;
+/// The domain separator of a challenge response that binds a handshake context.
+pub const CHALLENGE_DOMAIN: &[u8] = b"snarkos-challenge";
+/// The domain separator of an address record.
+pub const ADDRESS_RECORD_DOMAIN: &[u8] = b"snarkos-address-record";
+
+/// An object-safe RNG, which may be passed to a signer.
+pub trait SignerRng: RngCore + CryptoRng {}
+
+impl<R: RngCore + CryptoRng> SignerRng for R {}
+
+/// A request to sign a message.
+///
+/// The typed batch requests allow a signer to enforce slashing protection,
+/// as it learns the round (and author) that the signature is for. The typed byte requests
+/// are encoded by the signer itself, with a domain separator, so it never signs arbitrary bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SignRequest<N: Network> {
+    /// A message, as field elements.
+    Fields(Vec<Field<N>>),
+    /// A message, as bits.
+    Bits(Vec<bool>),
+    /// The batch ID of a batch header authored by the signer, for the given round.
+    BatchHeader { round: u64, batch_id: Field<N> },
+    /// The batch ID of a batch proposal from the given author, for the given round.
+    BatchProposal { author: Address<N>, round: u64, batch_id: Field<N> },
+    /// The nonces of a handshake challenge response, along with the handshake context it binds (if any).
+    Challenge { request_nonce: u64, response_nonce: u64, context: Vec<u8> },
+    /// The record of the listener IP of the given address, at the given UNIX timestamp (in seconds).
+    AddressRecord { address: Address<N>, listener_ip: SocketAddr, timestamp: i64 },
+}
+
+impl<N: Network> SignRequest<N> {
+    /// Returns a signature for this request, using the given private key.
+    pub fn sign_with<R: Rng + CryptoRng>(&self, private_key: &PrivateKey<N>, rng: &mut R) -> Result<Signature<N>> {
+        match self {
+            Self::Fields(message) => Signature::sign(private_key, message, rng),
+            Self::Bits(message) => Signature::sign_bits(private_key, message, rng),
+            Self::BatchHeader { batch_id, .. } | Self::BatchProposal { batch_id, .. } => {
+                Signature::sign(private_key, &[*batch_id], rng)
+            }
+            Self::Challenge { .. } | Self::AddressRecord { .. } => {
+                Signature::sign_bytes(private_key, &self.message_bytes()?, rng)
+            }
+        }
+    }
+
+    /// Returns `true` if the given signature is valid for this request, from the given address.
+    pub fn verify(&self, address: &Address<N>, signature: &Signature<N>) -> bool {
+        match self {
+            Self::Fields(message) => signature.verify(address, message),
+            Self::Bits(message) => signature.verify_bits(address, message),
+            Self::BatchHeader { batch_id, .. } | Self::BatchProposal { batch_id, .. } => {
+                signature.verify(address, &[*batch_id])
+            }
+            Self::Challenge { .. } | Self::AddressRecord { .. } => match self.message_bytes() {
+                Ok(message) => signature.verify_bytes(address, &message),
+                Err(_) => false,
+            },
+        }
+    }
+
+    /// Returns the signed bytes of a typed byte request.
+    ///
+    /// A challenge without a context signs only the two nonces, which is the message of the legacy handshake.
+    /// As every other byte message is prefixed with its domain separator, no two requests share a message.
+    fn message_bytes(&self) -> Result<Vec<u8>> {
+        match self {
+            Self::Challenge { request_nonce, response_nonce, context } => {
+                let nonces = [request_nonce.to_le_bytes(), response_nonce.to_le_bytes()].concat();
+                match context.is_empty() {
+                    true => Ok(nonces),
+                    false => Ok([CHALLENGE_DOMAIN, &nonces, context].concat()),
+                }
+            }
+            Self::AddressRecord { address, listener_ip, timestamp } => {
+                let mut message = ADDRESS_RECORD_DOMAIN.to_vec();
+                address.write_le(&mut message)?;
+                listener_ip.write_le(&mut message)?;
+                timestamp.write_le(&mut message)?;
+                Ok(message)
+            }
+            _ => bail!("The sign request is not a byte message"),
+        }
+    }
+}
+
+impl<N: Network> ToBytes for SignRequest<N> {
+    /// Writes the sign request to the buffer.
+    fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()> {
+        match self {
+            Self::Fields(message) => {
+                0u8.write_le(&mut writer)?;
+                u32::try_from(message.len()).map_err(error)?.write_le(&mut writer)?;
+                message.iter().try_for_each(|field| field.write_le(&mut writer))
+            }
+            Self::Bits(message) => {
+                2u8.write_le(&mut writer)?;
+                u32::try_from(message.len()).map_err(error)?.write_le(&mut writer)?;
+                message.iter().try_for_each(|bit| bit.write_le(&mut writer))
+            }
+            Self::BatchHeader { round, batch_id } => {
+                3u8.write_le(&mut writer)?;
+                round.write_le(&mut writer)?;
+                batch_id.write_le(&mut writer)
+            }
+            Self::BatchProposal { author, round, batch_id } => {
+                4u8.write_le(&mut writer)?;
+                author.write_le(&mut writer)?;
+                round.write_le(&mut writer)?;
+                batch_id.write_le(&mut writer)
+            }
+            Self::Challenge { request_nonce, response_nonce, context } => {
+                5u8.write_le(&mut writer)?;
+                request_nonce.write_le(&mut writer)?;
+                response_nonce.write_le(&mut writer)?;
+                u32::try_from(context.len()).map_err(error)?.write_le(&mut writer)?;
+                writer.write_all(context)
+            }
+            Self::AddressRecord { address, listener_ip, timestamp } => {
+                6u8.write_le(&mut writer)?;
+                address.write_le(&mut writer)?;
+                listener_ip.write_le(&mut writer)?;
+                timestamp.write_le(&mut writer)
+            }
+        }
+    }
+}
+
+impl<N: Network> FromBytes for SignRequest<N> {
+    /// Reads the sign request from the buffer.
+    fn read_le<R: Read>(mut reader: R) -> IoResult<Self> {
+        // Reads the length of a message, ensuring it is within bounds.
+        fn read_length<R: Read>(reader: R) -> IoResult<usize> {
+            let length = u32::read_le(reader)?;
+            match length <= MAX_SIGN_REQUEST_LENGTH {
+                true => Ok(length as usize),
+                false => Err(error(format!("Sign request length {length} exceeds {MAX_SIGN_REQUEST_LENGTH}"))),
+            }
+        }
+
+        match u8::read_le(&mut reader)? {
+            0 => {
+                let length = read_length(&mut reader)?;
+                let message = (0..length).map(|_| Field::read_le(&mut reader)).collect::<IoResult<Vec<_>>>()?;
+                Ok(Self::Fields(message))
+            }
+            2 => {
+                let length = read_length(&mut reader)?;
+                let message = (0..length).map(|_| bool::read_le(&mut reader)).collect::<IoResult<Vec<_>>>()?;
+                Ok(Self::Bits(message))
+            }
+            3 => {
+                let round = u64::read_le(&mut reader)?;
+                let batch_id = Field::read_le(&mut reader)?;
+                Ok(Self::BatchHeader { round, batch_id })
+            }
+            4 => {
+                let author = Address::read_le(&mut reader)?;
+                let round = u64::read_le(&mut reader)?;
+                let batch_id = Field::read_le(&mut reader)?;
+                Ok(Self::BatchProposal { author, round, batch_id })
+            }
+            5 => {
+                let request_nonce = u64::read_le(&mut reader)?;
+                let response_nonce = u64::read_le(&mut reader)?;
+                let mut context = vec![0u8; read_length(&mut reader)?];
+                reader.read_exact(&mut context)?;
+                Ok(Self::Challenge { request_nonce, response_nonce, context })
+            }
+            6 => {
+                let address = Address::read_le(&mut reader)?;
+                let listener_ip = SocketAddr::read_le(&mut reader)?;
+                let timestamp = i64::read_le(&mut reader)?;
+                Ok(Self::AddressRecord { address, listener_ip, timestamp })
+            }
+            variant => Err(error(format!("Invalid sign request variant '{variant}'"))),
+        }
+    }
+}
+
+/// A signer, which holds the private key of an account and signs messages on its behalf.
+pub trait Signer<N: Network>: Debug + Send + Sync {
+    /// Returns the address of the signer.
+    fn address(&self) -> Address<N>;
+
+    /// Returns a signature for the given request.
+    /// Note: Remote signers may ignore the given RNG, and use their own.
+    fn sign(&self, request: &SignRequest<N>, rng: &mut dyn SignerRng) -> Result<Signature<N>>;
+}
+
+/// A signer that holds the private key in-process.
+#[derive(Clone, Debug)]
+pub struct LocalSigner<N: Network> {
+    /// The private key.
+    private_key: PrivateKey<N>,
+    /// The address.
+    address: Address<N>,
+}
+
+impl<N: Network> LocalSigner<N> {
+    /// Initializes a new local signer from a private key.
+    pub fn new(private_key: PrivateKey<N>) -> Result<Self> {
+        Ok(Self { private_key, address: Address::try_from(&private_key)? })
+    }
+}
+
+impl<N: Network> Signer<N> for LocalSigner<N> {
+    /// Returns the address of the signer.
+    fn address(&self) -> Address<N> {
+        self.address
+    }
+
+    /// Returns a signature for the given request.
+    fn sign(&self, request: &SignRequest<N>, mut rng: &mut dyn SignerRng) -> Result<Signature<N>> {
+        request.sign_with(&self.private_key, &mut rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::prelude::MainnetV0;
+
+    type CurrentNetwork = MainnetV0;
+
+    /// Samples one sign request of each variant.
+    pub(crate) fn sample_sign_requests(rng: &mut TestRng) -> Vec<SignRequest<CurrentNetwork>> {
+        vec![
+            SignRequest::Fields((0..5).map(|_| Field::rand(rng)).collect()),
+            SignRequest::Bits((0..10).map(|_| rng.gen::<bool>()).collect()),
+            SignRequest::BatchHeader { round: rng.gen(), batch_id: Field::rand(rng) },
+            SignRequest::BatchProposal {
+                author: Address::try_from(PrivateKey::new(rng).unwrap()).unwrap(),
+                round: rng.gen(),
+                batch_id: Field::rand(rng),
+            },
+            SignRequest::Challenge { request_nonce: rng.gen(), response_nonce: rng.gen(), context: vec![] },
+            SignRequest::Challenge {
+                request_nonce: rng.gen(),
+                response_nonce: rng.gen(),
+                context: (0..10).map(|_| rng.gen::<u8>()).collect(),
+            },
+            SignRequest::AddressRecord {
+                address: Address::try_from(PrivateKey::new(rng).unwrap()).unwrap(),
+                listener_ip: SocketAddr::from(([1, 2, 3, 4], 5000)),
+                timestamp: rng.gen(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_sign_request_bytes() {
+        let rng = &mut TestRng::default();
+
+        for expected in sample_sign_requests(rng) {
+            let bytes = expected.to_bytes_le().unwrap();
+            assert_eq!(expected, SignRequest::read_le(&bytes[..]).unwrap());
+        }
+        // Ensure an invalid variant is rejected, including the removed untyped byte messages.
+        assert!(SignRequest::<CurrentNetwork>::read_le(&[1u8][..]).is_err());
+        assert!(SignRequest::<CurrentNetwork>::read_le(&[7u8][..]).is_err());
+    }
+
+    #[test]
+    fn test_local_signer() {
+        let rng = &mut TestRng::default();
+
+        let private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+        let signer = LocalSigner::new(private_key).unwrap();
+        assert_eq!(signer.address(), Address::try_from(&private_key).unwrap());
+
+        // Ensure the batch requests sign the batch ID.
+        let batch_id = Field::rand(rng);
+        let request = SignRequest::BatchHeader { round: 1, batch_id };
+        let signature = signer.sign(&request, rng).unwrap();
+        assert!(signature.verify(&signer.address(), &[batch_id]));
+        assert!(request.verify(&signer.address(), &signature));
+    }
+
+    #[test]
+    fn test_byte_requests_are_domain_separated() {
+        let rng = &mut TestRng::default();
+
+        let signer = LocalSigner::<CurrentNetwork>::new(PrivateKey::new(rng).unwrap()).unwrap();
+        let address = signer.address();
+
+        // Ensure a challenge without a context signs only the nonces, as in the legacy handshake.
+        let request = SignRequest::Challenge { request_nonce: 1, response_nonce: 2, context: vec![] };
+        let signature = signer.sign(&request, rng).unwrap();
+        assert!(request.verify(&address, &signature));
+        assert!(signature.verify_bytes(&address, &[1u64.to_le_bytes(), 2u64.to_le_bytes()].concat()));
+
+        // Ensure a challenge with a context is prefixed with its domain separator.
+        let request = SignRequest::Challenge { request_nonce: 1, response_nonce: 2, context: vec![3] };
+        let signature = signer.sign(&request, rng).unwrap();
+        assert!(request.verify(&address, &signature));
+        assert!(!signature.verify_bytes(&address, &[&1u64.to_le_bytes()[..], &2u64.to_le_bytes(), &[3]].concat()));
+
+        // Ensure the signature of an address record is not valid for a challenge with the same bytes.
+        let listener_ip = SocketAddr::from(([1, 2, 3, 4], 5000));
+        let request = SignRequest::AddressRecord { address, listener_ip, timestamp: 1 };
+        let signature = signer.sign(&request, rng).unwrap();
+        assert!(request.verify(&address, &signature));
+        let bytes = request.message_bytes().unwrap();
+        let (nonces, context) = bytes.split_at(16);
+        let challenge = SignRequest::Challenge {
+            request_nonce: u64::from_le_bytes(nonces[..8].try_into().unwrap()),
+            response_nonce: u64::from_le_bytes(nonces[8..].try_into().unwrap()),
+            context: context.to_vec(),
+        };
+        assert!(!challenge.verify(&address, &signature));
+    }
+}
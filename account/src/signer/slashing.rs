@@ -0,0 +1,275 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{SignRequest, Signer, SignerRng};
+use snarkvm::{
+    console::{network::prelude::*, types::Field},
+    prelude::*,
+};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write as _},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// The number of recent rounds that are tracked for each author.
+/// Requests for rounds older than this window are refused.
+pub const SLASHING_PROTECTION_ROUNDS: u64 = 200;
+
+/// The slashing protection records of a signer, which ensure that it never signs two different
+/// batch headers for the same round, nor two different batch proposals from the same author for the same round.
+///
+/// When opened from a file, each new record is appended (and synced) to the file *before* the signature is released.
+#[derive(Debug)]
+pub struct SlashingProtection<N: Network> {
+    /// The path to the records, if they are persisted.
+    path: Option<PathBuf>,
+    /// The batch IDs of the batch headers signed by this signer, keyed by round.
+    headers: BTreeMap<u64, Field<N>>,
+    /// The batch IDs of the batch proposals signed for each author, keyed by round.
+    proposals: HashMap<Address<N>, BTreeMap<u64, Field<N>>>,
+}
+
+impl<N: Network> Default for SlashingProtection<N> {
+    /// Initializes new in-memory slashing protection records.
+    fn default() -> Self {
+        Self { path: None, headers: Default::default(), proposals: Default::default() }
+    }
+}
+
+impl<N: Network> SlashingProtection<N> {
+    /// Initializes new in-memory slashing protection records.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the slashing protection records at the given path, creating the file if it does not exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut protection = Self::new();
+        // Load the existing records.
+        if path.exists() {
+            for (index, line) in BufReader::new(File::open(&path)?).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let request = Self::parse_record(&line)
+                    .map_err(|e| anyhow!("Invalid slashing protection record on line {} - {e}", index + 1))?;
+                protection.check_and_record(&request)?;
+            }
+        }
+        // Compact the records, dropping the ones outside of the tracked window.
+        let temp_path = path.with_extension("tmp");
+        {
+            let mut file = File::create(&temp_path)?;
+            for (round, batch_id) in &protection.headers {
+                writeln!(file, "header {round} {batch_id}")?;
+            }
+            for (author, records) in &protection.proposals {
+                for (round, batch_id) in records {
+                    writeln!(file, "proposal {author} {round} {batch_id}")?;
+                }
+            }
+            file.sync_all()?;
+        }
+        fs::rename(&temp_path, &path)?;
+        protection.path = Some(path);
+        Ok(protection)
+    }
+
+    /// Checks the given request against the records, and records it if it is a batch request.
+    /// Returns an error if signing the request would be slashable.
+    pub fn check_and_record(&mut self, request: &SignRequest<N>) -> Result<()> {
+        let (records, round, batch_id, record) = match request {
+            SignRequest::Fields(..) | SignRequest::Bits(..) => {
+                bail!("Refusing to sign an untyped field message - use a batch request instead")
+            }
+            // Note: The handshake challenges and address records are domain-separated by the signer,
+            // so their signatures cannot be interpreted as the signature of a batch ID.
+            SignRequest::Challenge { .. } | SignRequest::AddressRecord { .. } => return Ok(()),
+            SignRequest::BatchHeader { round, batch_id } => {
+                (&mut self.headers, *round, *batch_id, format!("header {round} {batch_id}"))
+            }
+            SignRequest::BatchProposal { author, round, batch_id } => (
+                self.proposals.entry(*author).or_default(),
+                *round,
+                *batch_id,
+                format!("proposal {author} {round} {batch_id}"),
+            ),
+        };
+
+        // If this round was already signed, only allow the same batch ID to be signed again.
+        if let Some(signed_batch_id) = records.get(&round) {
+            ensure!(
+                *signed_batch_id == batch_id,
+                "Refusing to sign batch '{batch_id}' for round {round}, as batch '{signed_batch_id}' was already signed"
+            );
+            return Ok(());
+        }
+        // Ensure the round is within the tracked window.
+        if let Some((highest_round, _)) = records.last_key_value() {
+            ensure!(
+                round.saturating_add(SLASHING_PROTECTION_ROUNDS) > *highest_round,
+                "Refusing to sign for round {round}, as it is too far behind round {highest_round}"
+            );
+        }
+
+        // Persist the record before the signature is released.
+        if let Some(path) = &self.path {
+            let mut file = OpenOptions::new().append(true).create(true).open(path)?;
+            writeln!(file, "{record}")?;
+            file.sync_data()?;
+        }
+        records.insert(round, batch_id);
+
+        // Prune the records outside of the tracked window.
+        if let Some((highest_round, _)) = records.last_key_value() {
+            let lowest_round = highest_round.saturating_sub(SLASHING_PROTECTION_ROUNDS - 1);
+            *records = records.split_off(&lowest_round);
+        }
+        Ok(())
+    }
+
+    /// Parses a record from a line of the records file.
+    fn parse_record(line: &str) -> Result<SignRequest<N>> {
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        match parts[..] {
+            ["header", round, batch_id] => {
+                Ok(SignRequest::BatchHeader { round: round.parse()?, batch_id: Field::from_str(batch_id)? })
+            }
+            ["proposal", author, round, batch_id] => Ok(SignRequest::BatchProposal {
+                author: Address::from_str(author)?,
+                round: round.parse()?,
+                batch_id: Field::from_str(batch_id)?,
+            }),
+            _ => bail!("Unknown record format '{line}'"),
+        }
+    }
+}
+
+/// A signer that enforces slashing protection before signing with the inner signer.
+#[derive(Debug)]
+pub struct ProtectedSigner<N: Network, S: Signer<N>> {
+    /// The inner signer.
+    signer: S,
+    /// The slashing protection records.
+    protection: Mutex<SlashingProtection<N>>,
+}
+
+impl<N: Network, S: Signer<N>> ProtectedSigner<N, S> {
+    /// Initializes a new protected signer.
+    pub fn new(signer: S, protection: SlashingProtection<N>) -> Self {
+        Self { signer, protection: Mutex::new(protection) }
+    }
+}
+
+impl<N: Network, S: Signer<N>> Signer<N> for ProtectedSigner<N, S> {
+    /// Returns the address of the signer.
+    fn address(&self) -> Address<N> {
+        self.signer.address()
+    }
+
+    /// Returns a signature for the given request, if it is not slashable.
+    fn sign(&self, request: &SignRequest<N>, rng: &mut dyn SignerRng) -> Result<Signature<N>> {
+        // Note: The lock is held while signing, so that concurrent requests are checked in order.
+        let mut protection = self.protection.lock().map_err(|_| anyhow!("The slashing protection lock is poisoned"))?;
+        protection.check_and_record(request)?;
+        self.signer.sign(request, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::LocalSigner;
+    use snarkvm::prelude::MainnetV0;
+
+    type CurrentNetwork = MainnetV0;
+
+    #[test]
+    fn test_conflicting_batch_headers() {
+        let rng = &mut TestRng::default();
+        let mut protection = SlashingProtection::<CurrentNetwork>::new();
+
+        let (batch_id, other_batch_id) = (Field::rand(rng), Field::rand(rng));
+        // Ensure the first header is signed, and may be signed again.
+        assert!(protection.check_and_record(&SignRequest::BatchHeader { round: 5, batch_id }).is_ok());
+        assert!(protection.check_and_record(&SignRequest::BatchHeader { round: 5, batch_id }).is_ok());
+        // Ensure a different header for the same round is refused.
+        assert!(protection.check_and_record(&SignRequest::BatchHeader { round: 5, batch_id: other_batch_id }).is_err());
+        // Ensure a different header for another round is signed.
+        assert!(protection.check_and_record(&SignRequest::BatchHeader { round: 6, batch_id: other_batch_id }).is_ok());
+        // Ensure a header for a round outside of the window is refused.
+        let round = 6 + SLASHING_PROTECTION_ROUNDS;
+        assert!(protection.check_and_record(&SignRequest::BatchHeader { round, batch_id }).is_ok());
+        assert!(protection.check_and_record(&SignRequest::BatchHeader { round: 6, batch_id: other_batch_id }).is_err());
+    }
+
+    #[test]
+    fn test_conflicting_batch_proposals() {
+        let rng = &mut TestRng::default();
+        let mut protection = SlashingProtection::<CurrentNetwork>::new();
+
+        let author = Address::try_from(PrivateKey::new(rng).unwrap()).unwrap();
+        let other_author = Address::try_from(PrivateKey::new(rng).unwrap()).unwrap();
+        let (batch_id, other_batch_id) = (Field::rand(rng), Field::rand(rng));
+
+        assert!(protection.check_and_record(&SignRequest::BatchProposal { author, round: 5, batch_id }).is_ok());
+        // Ensure a different proposal from the same author for the same round is refused.
+        let request = SignRequest::BatchProposal { author, round: 5, batch_id: other_batch_id };
+        assert!(protection.check_and_record(&request).is_err());
+        // Ensure a proposal from another author for the same round is signed.
+        let request = SignRequest::BatchProposal { author: other_author, round: 5, batch_id: other_batch_id };
+        assert!(protection.check_and_record(&request).is_ok());
+        // Ensure untyped field messages are refused.
+        assert!(protection.check_and_record(&SignRequest::Fields(vec![batch_id])).is_err());
+        assert!(protection.check_and_record(&SignRequest::Bits(vec![true])).is_err());
+        // Ensure the typed byte messages are signed.
+        let request = SignRequest::Challenge { request_nonce: 1, response_nonce: 2, context: vec![] };
+        assert!(protection.check_and_record(&request).is_ok());
+    }
+
+    #[test]
+    fn test_persisted_records() {
+        let rng = &mut TestRng::default();
+        let path = std::env::temp_dir().join(format!("snarkos-slashing-protection-{}", rng.gen::<u64>()));
+
+        let (batch_id, other_batch_id) = (Field::<CurrentNetwork>::rand(rng), Field::rand(rng));
+        {
+            let mut protection = SlashingProtection::open(&path).unwrap();
+            assert!(protection.check_and_record(&SignRequest::BatchHeader { round: 5, batch_id }).is_ok());
+        }
+        // Ensure the record is restored after reopening.
+        let mut protection = SlashingProtection::open(&path).unwrap();
+        assert!(protection.check_and_record(&SignRequest::BatchHeader { round: 5, batch_id }).is_ok());
+        assert!(protection.check_and_record(&SignRequest::BatchHeader { round: 5, batch_id: other_batch_id }).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_protected_signer() {
+        let rng = &mut TestRng::default();
+        let signer = LocalSigner::<CurrentNetwork>::new(PrivateKey::new(rng).unwrap()).unwrap();
+        let signer = ProtectedSigner::new(signer, SlashingProtection::new());
+
+        let (batch_id, other_batch_id) = (Field::rand(rng), Field::rand(rng));
+        let signature = signer.sign(&SignRequest::BatchHeader { round: 1, batch_id }, rng).unwrap();
+        assert!(signature.verify(&signer.address(), &[batch_id]));
+        assert!(signer.sign(&SignRequest::BatchHeader { round: 1, batch_id: other_batch_id }, rng).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{SignRequest, Signer, SignerRng};
+use snarkvm::{console::network::prelude::*, prelude::*};
+
+use std::{
+    fs,
+    io::{Read as _, Write as _},
+    os::unix::{
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+/// The maximum size of a frame exchanged with the signer, in bytes.
+const MAX_FRAME_SIZE: u32 = 16 * 1024 * 1024;
+/// The timeout for a request to the signer.
+const SIGNER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Writes a length-prefixed frame to the stream.
+fn write_frame(stream: &mut UnixStream, payload: &[u8]) -> Result<()> {
+    let length = u32::try_from(payload.len())?;
+    ensure!(length <= MAX_FRAME_SIZE, "Frame of {length} bytes exceeds the maximum of {MAX_FRAME_SIZE} bytes");
+    stream.write_all(&length.to_le_bytes())?;
+    stream.write_all(payload)?;
+    Ok(stream.flush()?)
+}
+
+/// Reads a length-prefixed frame from the stream.
+fn read_frame(stream: &mut UnixStream) -> Result<Vec<u8>> {
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length);
+    ensure!(length <= MAX_FRAME_SIZE, "Frame of {length} bytes exceeds the maximum of {MAX_FRAME_SIZE} bytes");
+    let mut payload = vec![0u8; length as usize];
+    stream.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+/// A signer that forwards requests to a local signer process over a Unix socket.
+///
+/// The protocol is a sequence of length-prefixed frames. Upon connecting, the signer sends its address.
+/// Then, for each sign request, the signer responds with `0` and the signature, or `1` and an error message.
+#[derive(Debug)]
+pub struct UnixSocketSigner<N: Network> {
+    /// The path to the socket.
+    path: PathBuf,
+    /// The address of the signer.
+    address: Address<N>,
+    /// The connection to the signer, if it is open.
+    stream: Mutex<Option<UnixStream>>,
+}
+
+impl<N: Network> UnixSocketSigner<N> {
+    /// Connects to the signer at the given socket path.
+    pub fn connect<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let (stream, address) = Self::open(&path)?;
+        Ok(Self { path, address, stream: Mutex::new(Some(stream)) })
+    }
+
+    /// Opens a connection to the signer, returning the stream and the address of the signer.
+    fn open(path: &Path) -> Result<(UnixStream, Address<N>)> {
+        let mut stream = UnixStream::connect(path)
+            .map_err(|e| anyhow!("Failed to connect to the signer at '{}' - {e}", path.display()))?;
+        stream.set_read_timeout(Some(SIGNER_TIMEOUT))?;
+        stream.set_write_timeout(Some(SIGNER_TIMEOUT))?;
+        let address = Address::from_bytes_le(&read_frame(&mut stream)?)?;
+        Ok((stream, address))
+    }
+
+    /// Sends the given request over the stream, and returns the signature.
+    fn request(stream: &mut UnixStream, request: &[u8]) -> Result<Result<Signature<N>>> {
+        write_frame(stream, request)?;
+        let response = read_frame(stream)?;
+        match response.split_first() {
+            Some((0, signature)) => Ok(Ok(Signature::from_bytes_le(signature)?)),
+            Some((1, message)) => {
+                Ok(Err(anyhow!("The signer refused the request - {}", String::from_utf8_lossy(message))))
+            }
+            _ => bail!("Received an invalid response from the signer"),
+        }
+    }
+}
+
+impl<N: Network> Signer<N> for UnixSocketSigner<N> {
+    /// Returns the address of the signer.
+    fn address(&self) -> Address<N> {
+        self.address
+    }
+
+    /// Returns a signature for the given request, from the signer process.
+    fn sign(&self, request: &SignRequest<N>, _rng: &mut dyn SignerRng) -> Result<Signature<N>> {
+        let request = request.to_bytes_le()?;
+        let mut stream = self.stream.lock().map_err(|_| anyhow!("The signer connection lock is poisoned"))?;
+        // Reuse the open connection. If it fails, reconnect once and retry.
+        if let Some(open_stream) = stream.as_mut() {
+            match Self::request(open_stream, &request) {
+                Ok(result) => return result,
+                Err(_) => *stream = None,
+            }
+        }
+        let (mut new_stream, address) = Self::open(&self.path)?;
+        ensure!(address == self.address, "The signer at '{}' changed its address to '{address}'", self.path.display());
+        let result = Self::request(&mut new_stream, &request)?;
+        *stream = Some(new_stream);
+        result
+    }
+}
+
+/// A local signer process, which serves sign requests over a Unix socket.
+pub struct UnixSocketSignerServer<N: Network> {
+    /// The socket listener.
+    listener: UnixListener,
+    /// The signer.
+    signer: Arc<dyn Signer<N>>,
+}
+
+impl<N: Network> UnixSocketSignerServer<N> {
+    /// Binds the signer to the given socket path.
+    ///
+    /// Note: The signer should be wrapped in a `ProtectedSigner`, to refuse slashable requests.
+    pub fn bind<P: AsRef<Path>>(path: P, signer: Arc<dyn Signer<N>>) -> Result<Self> {
+        let path = path.as_ref();
+        let listener =
+            UnixListener::bind(path).map_err(|e| anyhow!("Failed to bind the signer to '{}' - {e}", path.display()))?;
+        // Restrict the socket to the owner.
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        Ok(Self { listener, signer })
+    }
+
+    /// Serves sign requests until the listener fails, spawning a thread for each connection.
+    pub fn run(self) -> Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let signer = self.signer.clone();
+            // Note: The connection is closed on the first error, including when the peer disconnects.
+            thread::spawn(move || Self::handle_connection(stream, signer));
+        }
+        Ok(())
+    }
+
+    /// Serves the sign requests of a single connection.
+    fn handle_connection(mut stream: UnixStream, signer: Arc<dyn Signer<N>>) -> Result<()> {
+        // Send the address of the signer.
+        write_frame(&mut stream, &signer.address().to_bytes_le()?)?;
+        loop {
+            let request = read_frame(&mut stream)?;
+            let response = match SignRequest::<N>::from_bytes_le(&request)
+                .and_then(|request| signer.sign(&request, &mut rand::thread_rng()))
+            {
+                Ok(signature) => [&[0u8][..], &signature.to_bytes_le()?].concat(),
+                Err(e) => [&[1u8][..], e.to_string().as_bytes()].concat(),
+            };
+            write_frame(&mut stream, &response)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::{tests::sample_sign_requests, LocalSigner, ProtectedSigner, SlashingProtection};
+    use snarkvm::prelude::MainnetV0;
+
+    type CurrentNetwork = MainnetV0;
+
+    /// Starts a signer server at a temporary path, returning the path.
+    fn start_server(signer: Arc<dyn Signer<CurrentNetwork>>, rng: &mut TestRng) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("snarkos-signer-{}.sock", rng.gen::<u64>()));
+        let server = UnixSocketSignerServer::bind(&path, signer).unwrap();
+        thread::spawn(move || server.run());
+        path
+    }
+
+    #[test]
+    fn test_unix_socket_signer() {
+        let rng = &mut TestRng::default();
+        let local_signer = LocalSigner::new(PrivateKey::new(rng).unwrap()).unwrap();
+        let path = start_server(Arc::new(local_signer.clone()), rng);
+
+        let signer = UnixSocketSigner::<CurrentNetwork>::connect(&path).unwrap();
+        assert_eq!(signer.address(), local_signer.address());
+
+        // Ensure every request is signed by the remote signer.
+        for request in sample_sign_requests(rng) {
+            let signature = signer.sign(&request, rng).unwrap();
+            assert!(request.verify(&signer.address(), &signature));
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_unix_socket_signer_refuses_slashable_requests() {
+        let rng = &mut TestRng::default();
+        let local_signer = LocalSigner::new(PrivateKey::new(rng).unwrap()).unwrap();
+        let path = start_server(Arc::new(ProtectedSigner::new(local_signer, SlashingProtection::new())), rng);
+
+        let signer = UnixSocketSigner::<CurrentNetwork>::connect(&path).unwrap();
+        let (batch_id, other_batch_id) = (Field::rand(rng), Field::rand(rng));
+        assert!(signer.sign(&SignRequest::BatchHeader { round: 1, batch_id }, rng).is_ok());
+        // Ensure the conflicting header is refused, and the connection remains usable.
+        assert!(signer.sign(&SignRequest::BatchHeader { round: 1, batch_id: other_batch_id }, rng).is_err());
+        assert!(signer.sign(&SignRequest::BatchHeader { round: 2, batch_id: other_batch_id }, rng).is_ok());
+        std::fs::remove_file(path).unwrap();
+    }
+}
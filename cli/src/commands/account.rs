@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::start::check_permissions;
 use snarkvm::{
     console::{
         account::{Address, PrivateKey, Signature},
@@ -92,6 +93,21 @@ pub enum Account {
         #[clap(short = 'r', long)]
         raw: bool,
     },
+    /// Starts an external signer, which signs on behalf of a validator over a Unix socket
+    Signer {
+        /// Specify the network of the private key to sign with
+        #[clap(default_value = "0", long = "network")]
+        network: u16,
+        /// Specify the path to a file containing the account private key of the validator
+        #[clap(long = "private-key-file")]
+        private_key_file: String,
+        /// Specify the path of the Unix socket to listen on
+        #[clap(long = "socket")]
+        socket: String,
+        /// Specify the path of the slashing protection records
+        #[clap(long = "slashing-protection")]
+        slashing_protection: String,
+    },
 }
 
 /// Parse a raw Aleo input into fields
@@ -155,6 +171,22 @@ impl Account {
                     unknown_id => bail!("Unknown network ID ({unknown_id})"),
                 }
             }
+            Self::Signer { network, private_key_file, socket, slashing_protection } => {
+                let path = private_key_file.parse::<PathBuf>().map_err(|e| anyhow!("Invalid path - {e}"))?;
+                check_permissions(&path)?;
+                let key = std::fs::read_to_string(path)?.trim().to_string();
+                let socket = socket.parse::<PathBuf>().map_err(|e| anyhow!("Invalid path - {e}"))?;
+                let slashing_protection =
+                    slashing_protection.parse::<PathBuf>().map_err(|e| anyhow!("Invalid path - {e}"))?;
+
+                // Start the signer for the specified network.
+                match network {
+                    MainnetV0::ID => Self::start_signer::<MainnetV0>(key, socket, slashing_protection),
+                    TestnetV0::ID => Self::start_signer::<TestnetV0>(key, socket, slashing_protection),
+                    CanaryV0::ID => Self::start_signer::<CanaryV0>(key, socket, slashing_protection),
+                    unknown_id => bail!("Unknown network ID ({unknown_id})"),
+                }
+            }
         }
     }
 
@@ -211,14 +243,14 @@ impl Account {
                     return Ok(account.to_string());
                 }
                 display_string_discreetly(
-                    &format!("{:>12}  {}", "Private Key".cyan().bold(), account.private_key()),
+                    &format!("{:>12}  {}", "Private Key".cyan().bold(), account.private_key()?),
                     "### Do not share or lose this private key! Press any key to complete. ###",
                 )
                 .unwrap();
                 let account_info = format!(
                     " {:>12}  {}\n {:>12}  {}",
                     "View Key".cyan().bold(),
-                    account.view_key(),
+                    account.view_key()?,
                     "Address".cyan().bold(),
                     account.address()
                 );
@@ -252,14 +284,14 @@ impl Account {
             return Ok(account.to_string());
         }
         display_string_discreetly(
-            &format!("{:>12}  {}", "Private Key".cyan().bold(), account.private_key()),
+            &format!("{:>12}  {}", "Private Key".cyan().bold(), account.private_key()?),
             "### Do not share or lose this private key! Press any key to complete. ###",
         )
         .unwrap();
         let account_info = format!(
             " {:>12}  {}\n {:>12}  {}",
             "View Key".cyan().bold(),
-            account.view_key(),
+            account.view_key()?,
             "Address".cyan().bold(),
             account.address()
         );
@@ -322,6 +354,32 @@ impl Account {
             false => bail!("❌ The signature is invalid"),
         }
     }
+
+    /// Starts an external signer for the given private key, which serves sign requests over a Unix socket.
+    #[cfg(target_family = "unix")]
+    fn start_signer<N: Network>(key: String, socket: PathBuf, slashing_protection: PathBuf) -> Result<String> {
+        use snarkos_account::{LocalSigner, ProtectedSigner, Signer, SlashingProtection, UnixSocketSignerServer};
+        use std::sync::Arc;
+
+        // Parse the private key.
+        let private_key =
+            PrivateKey::<N>::from_str(&key).map_err(|_| anyhow!("Failed to parse a valid private key"))?;
+        // Initialize the signer, with slashing protection.
+        let signer = LocalSigner::new(private_key)?;
+        let address = signer.address();
+        let signer = ProtectedSigner::new(signer, SlashingProtection::open(&slashing_protection)?);
+        // Serve the sign requests.
+        let server = UnixSocketSignerServer::bind(&socket, Arc::new(signer))?;
+        println!("🔏 Signing for {} on '{}'...", address.to_string().bold(), socket.display());
+        server.run()?;
+        Ok("The signer has stopped".to_string())
+    }
+
+    /// Starts an external signer for the given private key, which serves sign requests over a Unix socket.
+    #[cfg(not(target_family = "unix"))]
+    fn start_signer<N: Network>(_key: String, _socket: PathBuf, _slashing_protection: PathBuf) -> Result<String> {
+        bail!("The external signer is only supported on Unix")
+    }
 }
 
 // Print the string to an alternate screen, so that the string won't been printed to the terminal.
@@ -0,0 +1,311 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use snarkos_account::Account;
+use snarkos_node::{
+    bft::events::{CaptureProtocol, CaptureReader, CaptureRecord, Direction, Event},
+    router::messages::{
+        Capabilities,
+        ChallengeRequest,
+        ChallengeResponse,
+        Compression,
+        Message,
+        MessageCodec,
+        NodeType,
+    },
+};
+use snarkvm::{
+    console::network::{CanaryV0, MainnetV0, Network, TestnetV0},
+    ledger::narwhal::Data,
+    prelude::{Field, FromBytes, Zero},
+};
+
+use anyhow::{anyhow, bail, ensure, Result};
+use clap::Parser;
+use colored::Colorize;
+use futures_util::{SinkExt, StreamExt};
+use rand::Rng;
+use std::{
+    fmt::Write,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use time::OffsetDateTime;
+use tokio::{net::TcpStream, runtime};
+use tokio_util::codec::Framed;
+
+/// The duration to wait for the responses of the node after the last replayed message.
+const REPLAY_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Commands to inspect and replay the router messages captured by a node (see `snarkos start --capture`).
+///
+/// The BFT events between validators are not captured, as replaying them requires the key of a committee member.
+#[derive(Debug, Parser)]
+pub enum Capture {
+    /// Prints the messages of a capture file.
+    Print {
+        /// Specify the network of the capture
+        #[clap(default_value = "0", long = "network")]
+        network: u16,
+        /// Specify the path to the capture file
+        #[clap(long = "path")]
+        path: PathBuf,
+        /// Only print the messages exchanged with the given peer
+        #[clap(long = "peer")]
+        peer: Option<SocketAddr>,
+        /// Print the contents of the messages, in addition to their names
+        #[clap(long)]
+        verbose: bool,
+    },
+    /// Replays the router messages received by the capturing node against a node under test.
+    Replay {
+        /// Specify the network of the capture
+        #[clap(default_value = "0", long = "network")]
+        network: u16,
+        /// Specify the path to the capture file
+        #[clap(long = "path")]
+        path: PathBuf,
+        /// Specify the IP address and port of the node under test
+        #[clap(long = "node")]
+        node: SocketAddr,
+        /// Only replay the messages received from the given peer
+        #[clap(long = "peer")]
+        peer: Option<SocketAddr>,
+        /// Replay the messages with their original delays, instead of all at once
+        #[clap(long)]
+        realtime: bool,
+    },
+}
+
+impl Capture {
+    /// Parses the capture command.
+    pub fn parse(self) -> Result<String> {
+        let network = match &self {
+            Self::Print { network, .. } | Self::Replay { network, .. } => *network,
+        };
+        match network {
+            MainnetV0::ID => self.execute::<MainnetV0>(),
+            TestnetV0::ID => self.execute::<TestnetV0>(),
+            CanaryV0::ID => self.execute::<CanaryV0>(),
+            unknown_id => bail!("Unknown network ID ({unknown_id})"),
+        }
+    }
+
+    /// Executes the capture command for the given network.
+    fn execute<N: Network>(self) -> Result<String> {
+        match self {
+            Self::Print { path, peer, verbose, .. } => Self::print::<N>(&path, peer, verbose),
+            Self::Replay { path, node, peer, realtime, .. } => {
+                // Initialize the runtime.
+                let runtime = runtime::Builder::new_current_thread().enable_all().build()?;
+                runtime.block_on(Self::replay::<N>(&path, node, peer, realtime))
+            }
+        }
+    }
+
+    /// Returns the records of the given capture file, optionally only the ones exchanged with the given peer.
+    fn read_records(path: &Path, peer: Option<SocketAddr>) -> Result<Vec<CaptureRecord>> {
+        let reader = CaptureReader::open(path)
+            .map_err(|error| anyhow!("Failed to open the capture file '{}' - {error}", path.display()))?;
+        let mut records = Vec::new();
+        for record in reader {
+            match record {
+                Ok(record) if peer.map_or(true, |peer| record.peer == peer) => records.push(record),
+                Ok(_) => continue,
+                // Note: The last record may be truncated, if the node crashed while writing it.
+                Err(error) => {
+                    eprintln!("Stopped reading the capture file at an invalid record - {error}");
+                    break;
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    /// Prints the records of the given capture file.
+    fn print<N: Network>(path: &Path, peer: Option<SocketAddr>, verbose: bool) -> Result<String> {
+        let records = Self::read_records(path, peer)?;
+
+        let mut output = String::new();
+        writeln!(output, "{} {}", "Capture".bold(), format!("(in \"{}\")", path.display()).dimmed())?;
+        writeln!(output, "  Records: {}\n", records.len())?;
+
+        for record in &records {
+            let timestamp = OffsetDateTime::from_unix_timestamp_nanos(record.timestamp as i128 * 1000)?;
+            let direction = match record.direction {
+                Direction::Inbound => "<-".green(),
+                Direction::Outbound => "->".cyan(),
+            };
+            // Decode the message, which may fail if the peer sent an invalid one.
+            let (protocol, contents) = match record.protocol {
+                CaptureProtocol::Router => {
+                    ("router", Message::<N>::read_le(&record.payload[..]).map(|m| (m.name(), format!("{m:?}"))))
+                }
+                CaptureProtocol::Bft => {
+                    ("bft", Event::<N>::read_le(&record.payload[..]).map(|e| (e.name(), format!("{e:?}"))))
+                }
+            };
+            let name = match &contents {
+                Ok((name, _)) => name.bold(),
+                Err(error) => format!("Invalid message ({} bytes) - {error}", record.payload.len()).red(),
+            };
+            writeln!(output, "{timestamp} {direction} {} [{protocol}] {name}", record.peer)?;
+            if let (true, Ok((_, contents))) = (verbose, &contents) {
+                writeln!(output, "    {}", contents.dimmed())?;
+            }
+        }
+
+        Ok(output.trim_end().to_string())
+    }
+
+    /// Replays the router messages received by the capturing node against the given node.
+    ///
+    /// The handshake is performed anew as a prover, as the captured one was signed by the capturing node,
+    /// so the captured handshake messages are skipped. The messages that fail to deserialize are skipped too.
+    async fn replay<N: Network>(
+        path: &Path,
+        node_ip: SocketAddr,
+        peer: Option<SocketAddr>,
+        realtime: bool,
+    ) -> Result<String> {
+        // Collect the messages to replay.
+        let mut messages = Vec::new();
+        let mut num_skipped = 0;
+        for record in Self::read_records(path, peer)? {
+            if record.protocol != CaptureProtocol::Router || record.direction != Direction::Inbound {
+                continue;
+            }
+            match Message::<N>::read_le(&record.payload[..]) {
+                Ok(Message::ChallengeRequest(..) | Message::ChallengeResponse(..)) => continue,
+                Ok(message) => messages.push((record.timestamp, message)),
+                Err(_) => num_skipped += 1,
+            }
+        }
+        ensure!(!messages.is_empty(), "The capture file contains no router messages to replay");
+        if num_skipped > 0 {
+            eprintln!("Skipping {num_skipped} messages that fail to deserialize");
+        }
+
+        // Connect to the node, and perform the handshake.
+        let stream = TcpStream::connect(node_ip).await?;
+        let mut framed = Framed::new(stream, MessageCodec::<N>::handshake());
+        let (version, compression) = Self::handshake(&mut framed).await?;
+        println!("Connected to '{node_ip}' (version {version}), replaying {} messages\n", messages.len());
+
+        // Switch to the codec of the connection.
+        *framed.codec_mut() = MessageCodec::default().with_compression(compression).with_version(version);
+        let (mut sink, mut stream) = framed.split();
+
+        // Print the messages sent by the node, until it disconnects.
+        let reader = tokio::spawn(async move {
+            let mut num_received = 0;
+            while let Some(message) = stream.next().await {
+                match message {
+                    Ok(Message::Disconnect(disconnect)) => {
+                        return format!("The node disconnected with reason {:?}", disconnect.reason);
+                    }
+                    Ok(message) => {
+                        num_received += 1;
+                        println!("{} {}", "<-".green(), message.name());
+                    }
+                    Err(error) => return format!("Failed to read from the node - {error}"),
+                }
+            }
+            format!("The node closed the connection ({num_received} messages received)")
+        });
+
+        // Replay the messages.
+        let mut num_sent = 0;
+        let mut previous_timestamp = messages[0].0;
+        for (timestamp, message) in messages {
+            if realtime {
+                tokio::time::sleep(Duration::from_micros(timestamp.saturating_sub(previous_timestamp))).await;
+                previous_timestamp = timestamp;
+            }
+            let name = message.name();
+            if let Err(error) = sink.send(message).await {
+                eprintln!("Failed to send '{name}' - {error}");
+                break;
+            }
+            num_sent += 1;
+            println!("{} {}", "->".cyan(), name.bold());
+        }
+
+        // Wait for the responses of the node.
+        let outcome = match tokio::time::timeout(REPLAY_GRACE_PERIOD, reader).await {
+            Ok(outcome) => outcome?,
+            Err(_) => "The node is still connected".to_string(),
+        };
+        Ok(format!("\nReplayed {num_sent} messages. {outcome}"))
+    }
+
+    /// Performs the handshake with the node as a prover, returning the negotiated version
+    /// and the compression negotiated with the node.
+    async fn handshake<N: Network>(framed: &mut Framed<TcpStream, MessageCodec<N>>) -> Result<(u32, Compression)> {
+        let rng = &mut rand::thread_rng();
+        let account = Account::<N>::new(rng)?;
+        let listener_port = framed.get_ref().local_addr()?.port();
+
+        // Send the challenge request.
+        let our_nonce = rng.gen();
+        let our_request = ChallengeRequest::new(listener_port, NodeType::Prover, account.address(), our_nonce);
+        framed.send(Message::ChallengeRequest(our_request)).await?;
+
+        // Receive the challenge response and the challenge request of the node.
+        let node_response = match framed.next().await {
+            Some(Ok(Message::ChallengeResponse(response))) => response,
+            message => bail!("Expected a challenge response from the node, received {}", Self::describe(message)),
+        };
+        let node_request = match framed.next().await {
+            Some(Ok(Message::ChallengeRequest(request))) => request,
+            message => bail!("Expected a challenge request from the node, received {}", Self::describe(message)),
+        };
+        let Some(version) = Message::<N>::negotiate_version(node_request.version) else {
+            bail!("The node is on an unsupported version ({})", node_request.version)
+        };
+        framed.codec_mut().set_version(version);
+
+        // Send the challenge response. The genesis header is taken from the node, as the capture
+        // is replayed against a node of the same network.
+        let response_nonce: u64 = rng.gen();
+        let our_capabilities = (version >= Message::<N>::CAPABILITIES_VERSION).then(Capabilities::supported);
+        let our_version = (version >= Message::<N>::NEGOTIATION_VERSION).then_some(version);
+        let request =
+            ChallengeResponse::<N>::signing_request(node_request.nonce, response_nonce, our_capabilities, our_version);
+        let our_response = ChallengeResponse {
+            genesis_header: node_response.genesis_header,
+            restrictions_id: Field::zero(), // Provers may bypass restrictions.
+            signature: Data::Object(account.sign_request(&request, rng)?),
+            nonce: response_nonce,
+            capabilities: our_capabilities,
+            version: our_version,
+        };
+        framed.send(Message::ChallengeResponse(our_response)).await?;
+
+        let compression =
+            Compression::new(our_capabilities.unwrap_or_default(), node_response.capabilities.unwrap_or_default());
+        Ok((version, compression))
+    }
+
+    /// Describes the given message received during the handshake.
+    fn describe<N: Network>(message: Option<std::io::Result<Message<N>>>) -> String {
+        match message {
+            Some(Ok(Message::Disconnect(disconnect))) => format!("a disconnect ({:?})", disconnect.reason),
+            Some(Ok(message)) => format!("'{}'", message.name()),
+            Some(Err(error)) => format!("an invalid message ({error})"),
+            None => "nothing".to_string(),
+        }
+    }
+}
@@ -15,12 +15,24 @@
 mod account;
 pub use account::*;
 
+mod capture;
+pub use capture::*;
+
 mod clean;
 pub use clean::*;
 
 mod developer;
 pub use developer::*;
 
+mod proposal_cache;
+pub use proposal_cache::*;
+
+mod repair;
+pub use repair::*;
+
+mod snapshot;
+pub use snapshot::*;
+
 mod start;
 pub use start::*;
 
@@ -53,10 +65,18 @@ pub struct CLI {
 pub enum Command {
     #[clap(subcommand)]
     Account(Account),
+    #[clap(subcommand)]
+    Capture(Capture),
     #[clap(name = "clean")]
     Clean(Clean),
     #[clap(subcommand)]
     Developer(Developer),
+    #[clap(name = "proposal-cache")]
+    ProposalCache(ProposalCache),
+    #[clap(name = "repair")]
+    Repair(Repair),
+    #[clap(name = "snapshot")]
+    Snapshot(Snapshot),
     #[clap(name = "start")]
     Start(Box<Start>),
     #[clap(name = "update")]
@@ -68,8 +88,12 @@ impl Command {
     pub fn parse(self) -> Result<String> {
         match self {
             Self::Account(command) => command.parse(),
+            Self::Capture(command) => command.parse(),
             Self::Clean(command) => command.parse(),
             Self::Developer(command) => command.parse(),
+            Self::ProposalCache(command) => command.parse(),
+            Self::Repair(command) => command.parse(),
+            Self::Snapshot(command) => command.parse(),
             Self::Start(command) => command.parse(),
             Self::Update(command) => command.parse(),
         }
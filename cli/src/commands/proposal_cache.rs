@@ -0,0 +1,94 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use snarkos_node::bft::helpers::{proposal_cache_path, ProposalCache as Cache};
+use snarkvm::console::network::{CanaryV0, MainnetV0, Network, TestnetV0};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::Colorize;
+use std::{fmt::Write, path::PathBuf};
+
+/// Prints the contents of the proposal cache of a validator.
+#[derive(Debug, Parser)]
+pub struct ProposalCache {
+    /// Specify the network of the proposal cache.
+    #[clap(default_value = "0", long = "network")]
+    pub network: u16,
+    /// Enables development mode, specify the unique ID of the local node.
+    #[clap(long)]
+    pub dev: Option<u16>,
+    /// Specify the path to a proposal cache file.
+    #[clap(long = "path")]
+    pub path: Option<PathBuf>,
+}
+
+impl ProposalCache {
+    /// Prints the contents of the proposal cache.
+    pub fn parse(self) -> Result<String> {
+        match self.network {
+            MainnetV0::ID => self.inspect::<MainnetV0>(),
+            TestnetV0::ID => self.inspect::<TestnetV0>(),
+            CanaryV0::ID => self.inspect::<CanaryV0>(),
+            unknown_id => bail!("Unknown network ID ({unknown_id})"),
+        }
+    }
+
+    /// Reads the proposal cache, and returns a summary of its contents.
+    fn inspect<N: Network>(&self) -> Result<String> {
+        let path = self.path.clone().unwrap_or_else(|| proposal_cache_path(N::ID, self.dev));
+        if !path.exists() {
+            bail!("No proposal cache was found at {}", path.display());
+        }
+        let (cache, version) = Cache::<N>::read_from(&path)?;
+
+        let mut output = String::new();
+        writeln!(output, "{} {}", "Proposal cache".bold(), format!("(in \"{}\")", path.display()).dimmed())?;
+        writeln!(output, "  Format version: {version}")?;
+        writeln!(output, "  Latest round: {}", cache.latest_round())?;
+
+        match cache.proposal() {
+            Some(proposal) => {
+                writeln!(output, "\n{}", "Proposal".bold())?;
+                writeln!(output, "  Batch ID: {}", proposal.batch_id())?;
+                writeln!(output, "  Author: {}", proposal.batch_header().author())?;
+                writeln!(output, "  Round: {}", proposal.round())?;
+                writeln!(output, "  Timestamp: {}", proposal.timestamp())?;
+                writeln!(output, "  Transmissions: {}", proposal.transmissions().len())?;
+                writeln!(output, "  Signers: {}", proposal.signers().len())?;
+            }
+            None => writeln!(output, "\n{} none", "Proposal:".bold())?,
+        }
+
+        writeln!(output, "\n{} ({})", "Signed proposals".bold(), cache.signed_proposals().0.len())?;
+        for (author, (round, batch_id, _)) in &cache.signed_proposals().0 {
+            writeln!(output, "  Round {round} - batch {batch_id} from {author}")?;
+        }
+
+        writeln!(output, "\n{} ({})", "Pending certificates".bold(), cache.pending_certificates().len())?;
+        for certificate in cache.pending_certificates() {
+            writeln!(
+                output,
+                "  Round {} - batch {} from {} ({} transmissions, {} signatures)",
+                certificate.round(),
+                certificate.batch_id(),
+                certificate.author(),
+                certificate.transmission_ids().len(),
+                certificate.signatures().len(),
+            )?;
+        }
+
+        Ok(output.trim_end().to_string())
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use snarkos_node::bft::storage_service::{BFTPersistentStorage, RepairChecks, StorageService};
+use snarkvm::{
+    console::network::{CanaryV0, MainnetV0, Network, TestnetV0},
+    ledger::{
+        narwhal::TransmissionID,
+        store::{helpers::rocksdb::ConsensusDB, ConsensusStore},
+    },
+    prelude::Field,
+};
+
+use aleo_std::StorageMode;
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::Colorize;
+use std::{collections::HashSet, path::PathBuf};
+
+/// Verifies the transmissions in the BFT storage of a validator, and removes the orphans if requested.
+#[derive(Debug, Parser)]
+pub struct Repair {
+    /// Specify the network of the storage.
+    #[clap(default_value = "0", long = "network")]
+    pub network: u16,
+    /// Enables development mode, specify the unique ID of the local node.
+    #[clap(long)]
+    pub dev: Option<u16>,
+    /// Specify the path to a directory containing the ledger
+    #[clap(long = "path")]
+    pub path: Option<PathBuf>,
+    /// Remove the orphans; by default, the storage is only verified.
+    #[clap(long = "apply")]
+    pub apply: bool,
+}
+
+impl Repair {
+    /// Verifies the BFT storage, and repairs it if `--apply` is set.
+    /// Note: The node must not be running, as it holds the storage.
+    pub fn parse(self) -> Result<String> {
+        match self.network {
+            MainnetV0::ID => self.repair::<MainnetV0>(),
+            TestnetV0::ID => self.repair::<TestnetV0>(),
+            CanaryV0::ID => self.repair::<CanaryV0>(),
+            unknown_id => bail!("Unknown network ID ({unknown_id})"),
+        }
+    }
+
+    /// Verifies the transmissions against the persisted certificates and the ledger, and removes the orphans.
+    fn repair<N: Network>(&self) -> Result<String> {
+        let storage_mode = match &self.path {
+            Some(path) => StorageMode::Custom(path.clone()),
+            None => StorageMode::from(self.dev),
+        };
+        // Ensure the storage exists, so that an empty storage is not created.
+        let path = aleo_std::aleo_ledger_dir(N::ID, storage_mode.clone());
+        let path_string = format!("(in \"{}\")", path.display()).dimmed();
+        if !path.exists() {
+            bail!("No snarkOS node storage was found {path_string}");
+        }
+
+        // Open the BFT storage and the ledger storage.
+        let storage = BFTPersistentStorage::<N>::open(storage_mode.clone())?;
+        let store = ConsensusStore::<N, ConsensusDB<N>>::open(storage_mode)?;
+
+        // Note: The node is not running, so the known certificates are the ones persisted in the BFT storage,
+        // and the ones committed in the ledger.
+        let certificate_ids: HashSet<_> =
+            storage.get_certificates().iter().map(|certificate| certificate.id()).collect();
+        let is_known_certificate = |certificate_id: &Field<N>| {
+            certificate_ids.contains(certificate_id)
+                || store.block_store().contains_certificate(certificate_id).unwrap_or(false)
+        };
+        let is_in_ledger = |transmission_id: &TransmissionID<N>| match transmission_id {
+            TransmissionID::Ratification => false,
+            TransmissionID::Solution(solution_id, _) => {
+                store.block_store().contains_solution_id(solution_id).unwrap_or(false)
+            }
+            TransmissionID::Transaction(transaction_id, _) => {
+                store.block_store().contains_transaction_id(transaction_id).unwrap_or(false)
+            }
+        };
+        // Note: The storage is not modified if no certificates are persisted, as the references cannot be verified.
+        let apply = self.apply && !certificate_ids.is_empty();
+        let report = storage.repair(&RepairChecks {
+            is_known_certificate: &is_known_certificate,
+            is_in_ledger: &is_in_ledger,
+            dry_run: !apply,
+        });
+
+        match (report.is_consistent(), self.apply, apply) {
+            (true, _, _) => Ok(format!("✅ The snarkOS node storage is consistent {path_string}\n{report}")),
+            (false, false, _) => Ok(format!(
+                "⚠️  The snarkOS node storage has orphans {path_string}\n{report}\nRun with --apply to remove them"
+            )),
+            (false, true, false) => Ok(format!(
+                "⚠️  The snarkOS node storage has no certificates, so its orphans were not removed {path_string}\n{report}"
+            )),
+            (false, true, true) => Ok(format!("✅ Repaired the snarkOS node storage {path_string}\n{report}")),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use snarkos_node::create_snapshot;
+use snarkvm::console::network::{CanaryV0, MainnetV0, Network, TestnetV0};
+
+use aleo_std::StorageMode;
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::Colorize;
+use std::path::PathBuf;
+
+/// Creates a snapshot of the ledger of a node, to bootstrap other nodes with `snarkos start --snapshot`.
+#[derive(Debug, Parser)]
+pub struct Snapshot {
+    /// Specify the network of the ledger.
+    #[clap(default_value = "0", long = "network")]
+    pub network: u16,
+    /// Enables development mode, specify the unique ID of the local node.
+    #[clap(long)]
+    pub dev: Option<u16>,
+    /// Specify the path to a directory containing the ledger
+    #[clap(long = "path")]
+    pub path: Option<PathBuf>,
+    /// Specify the path to the directory to write the snapshot to
+    #[clap(long = "output")]
+    pub output: PathBuf,
+}
+
+impl Snapshot {
+    /// Creates a snapshot of the ledger.
+    /// Note: The node must not be running, as it holds the storage.
+    pub fn parse(self) -> Result<String> {
+        match self.network {
+            MainnetV0::ID => self.create::<MainnetV0>(),
+            TestnetV0::ID => self.create::<TestnetV0>(),
+            CanaryV0::ID => self.create::<CanaryV0>(),
+            unknown_id => bail!("Unknown network ID ({unknown_id})"),
+        }
+    }
+
+    /// Creates a snapshot of the ledger, and returns the trusted hash to bootstrap from it.
+    fn create<N: Network>(&self) -> Result<String> {
+        let storage_mode = match &self.path {
+            Some(path) => StorageMode::Custom(path.clone()),
+            None => StorageMode::from(self.dev),
+        };
+        let manifest = create_snapshot::<N>(storage_mode, &self.output)?;
+        Ok(format!(
+            "✅ Created a snapshot at block {} {}\n   Bootstrap a node with `snarkos start --snapshot {} --snapshot-hash {}`",
+            manifest.height,
+            format!("(in \"{}\")", self.output.display()).dimmed(),
+            self.output.display(),
+            manifest.block_hash.bold(),
+        ))
+    }
+}
@@ -14,7 +14,15 @@
 
 use snarkos_account::Account;
 use snarkos_display::Display;
-use snarkos_node::{bft::MEMORY_POOL_PORT, router::messages::NodeType, Node};
+use snarkos_node::{
+    bft::{
+        events::{enable_capture, Capture},
+        MEMORY_POOL_PORT,
+    },
+    router::{messages::NodeType, BootstrapConfig, RouterConfig},
+    tcp::Config,
+    Node,
+};
 use snarkvm::{
     console::{
         account::{Address, PrivateKey},
@@ -32,7 +40,7 @@ use snarkvm::{
 };
 
 use aleo_std::StorageMode;
-use anyhow::{bail, ensure, Result};
+use anyhow::{anyhow, bail, ensure, Result};
 use clap::Parser;
 use colored::Colorize;
 use core::str::FromStr;
@@ -85,6 +93,9 @@ pub struct Start {
     /// Specify this node as a client
     #[clap(long = "client")]
     pub client: bool,
+    /// Specify this node as a light node
+    #[clap(long = "light")]
+    pub light: bool,
 
     /// Specify the account private key of the node
     #[clap(long = "private-key")]
@@ -92,6 +103,9 @@ pub struct Start {
     /// Specify the path to a file containing the account private key of the node
     #[clap(long = "private-key-file")]
     pub private_key_file: Option<PathBuf>,
+    /// Specify the path to the Unix socket of an external signer, which holds the private key of the validator
+    #[clap(long = "signer-socket")]
+    pub signer_socket: Option<PathBuf>,
 
     /// Specify the IP address and port for the node server
     #[clap(long = "node")]
@@ -105,6 +119,28 @@ pub struct Start {
     /// Specify the IP address and port of the validator(s) to connect to
     #[clap(default_value = "", long = "validators")]
     pub validators: String,
+    /// Specify the IP address (or host name) and port of the bootstrap peer(s), replacing the default ones
+    #[clap(default_value = "", long = "bootstrap-peers")]
+    pub bootstrap_peers: String,
+    /// Specify the domain(s) of the DNS seed(s), whose A and AAAA records are the peers to connect to
+    #[clap(default_value = "", long = "dns-seeds")]
+    pub dns_seeds: String,
+    /// Specify the IP address and port of the SOCKS5 proxy to establish outbound peer connections through
+    #[clap(long = "proxy")]
+    pub proxy: Option<SocketAddr>,
+    /// Specify the IP address and port at which peers can reach the node server, if it differs from the node server
+    /// (e.g. behind a proxy, which otherwise advertises no listener)
+    #[clap(long = "external-addr")]
+    pub external_addr: Option<SocketAddr>,
+    /// Specify the maximum number of connected peers from the same subnet (/24 for IPv4, /48 for IPv6) [default: 2]
+    #[clap(long = "max-peers-per-subnet")]
+    pub max_peers_per_subnet: Option<usize>,
+    /// Specify the maximum rate (in bytes per second) at which messages are sent to all the peers combined
+    #[clap(long = "max-send-rate")]
+    pub max_send_rate: Option<u64>,
+    /// Specify the maximum rate (in bytes per second) at which messages are sent to a single peer
+    #[clap(long = "max-send-rate-per-peer")]
+    pub max_send_rate_per_peer: Option<u64>,
     /// If the flag is set, a node will allow untrusted peers to connect
     #[clap(long = "allow-external-peers")]
     pub allow_external_peers: bool,
@@ -118,6 +154,9 @@ pub struct Start {
     /// If the flag is set, the node will not initialize the REST server
     #[clap(long)]
     pub norest: bool,
+    /// Specify the URL(s) of the REST server(s) of full nodes, which a light node fetches the state paths from
+    #[clap(default_value = "", long = "rest-peers")]
+    pub rest_peers: String,
 
     /// If the flag is set, the node will not render the display
     #[clap(long)]
@@ -131,6 +170,13 @@ pub struct Start {
     /// Enables the metrics exporter
     #[clap(default_value = "false", long = "metrics")]
     pub metrics: bool,
+    /// Specify the path to the file where the router messages will be captured, for debugging purposes
+    /// (the BFT events between validators are not captured, as they cannot be replayed)
+    #[clap(long = "capture")]
+    pub capture: Option<PathBuf>,
+    /// Specify the maximum size of the capture file in MiB, after which it is rotated
+    #[clap(default_value = "64", long = "capture-size")]
+    pub capture_size: u64,
 
     /// Specify the path to a directory containing the storage database for the ledger
     #[clap(long = "storage")]
@@ -141,6 +187,13 @@ pub struct Start {
     /// If the flag is set, the node will not prefetch from a CDN
     #[clap(long)]
     pub nocdn: bool,
+    /// Specify the path to a ledger snapshot to bootstrap the node from, if its ledger is empty;
+    /// the blocks are verified, but the finalize state (e.g. balances and stake) is trusted from the snapshot provider
+    #[clap(long = "snapshot", requires = "snapshot_hash")]
+    pub snapshot: Option<PathBuf>,
+    /// Specify the trusted hash of the checkpoint block of the ledger snapshot
+    #[clap(long = "snapshot-hash", requires = "snapshot")]
+    pub snapshot_hash: Option<String>,
 
     /// Enables development mode, specify a unique ID for this node
     #[clap(long)]
@@ -246,17 +299,49 @@ impl Start {
         }
     }
 
+    /// Returns the TCP configuration shared by the router and the gateway, with the proxy and the send rates.
+    fn parse_tcp_config(&self) -> Config {
+        Config {
+            proxy: self.proxy,
+            max_send_rate: self.max_send_rate,
+            max_send_rate_per_peer: self.max_send_rate_per_peer,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the bootstrap peers and DNS seeds, from the given configurations.
+    fn parse_bootstrap(&self) -> Result<BootstrapConfig> {
+        let split = |entries: &str| {
+            entries.split(',').map(str::trim).filter(|entry| !entry.is_empty()).map(String::from).collect()
+        };
+        BootstrapConfig::new(split(&self.bootstrap_peers), split(&self.dns_seeds))
+    }
+
+    /// Returns the URLs of the REST servers that a light node fetches the state paths from.
+    fn parse_rest_peers(&self) -> Vec<String> {
+        self.rest_peers.split(',').map(str::trim).filter(|url| !url.is_empty()).map(String::from).collect()
+    }
+
     /// Returns the CDN to prefetch initial blocks from, from the given configurations.
     fn parse_cdn(&self) -> Option<String> {
         // Determine if the node type is not declared.
-        let is_no_node_type = !(self.validator || self.prover || self.client);
+        let is_no_node_type = !(self.validator || self.prover || self.client || self.light);
 
         // Disable CDN if:
         //  1. The node is in development mode.
         //  2. The user has explicitly disabled CDN.
-        //  3. The node is a prover (no need to sync).
+        //  3. The node is a prover or light node (no need to sync blocks).
         //  4. The node type is not declared (defaults to client) (no need to sync).
-        if self.dev.is_some() || self.cdn.is_empty() || self.nocdn || self.prover || is_no_node_type {
+        //  5. The node is a validator bootstrapped from a snapshot (the blocks after the checkpoint are BFT-synced).
+        let is_snapshot_validator = self.validator && self.snapshot.is_some();
+        if self.dev.is_some()
+            || self.cdn.is_empty()
+            || self.nocdn
+            || self.prover
+            || self.light
+            || is_no_node_type
+            || is_snapshot_validator
+        {
             None
         }
         // Enable the CDN otherwise.
@@ -268,6 +353,16 @@ impl Start {
     /// Read the private key directly from an argument or from a filesystem location,
     /// returning the Aleo account.
     fn parse_private_key<N: Network>(&self) -> Result<Account<N>> {
+        // If an external signer is provided, connect to it instead.
+        if let Some(path) = &self.signer_socket {
+            ensure!(self.validator, "The '--signer-socket' argument is only supported for validators");
+            ensure!(
+                self.private_key.is_none() && self.private_key_file.is_none() && self.dev.is_none(),
+                "Cannot use '--signer-socket' with '--private-key', '--private-key-file', or '--dev'"
+            );
+            return Self::connect_signer(path);
+        }
+
         match self.dev {
             None => match (&self.private_key, &self.private_key_file) {
                 // Parse the private key directly.
@@ -277,8 +372,8 @@ impl Start {
                     check_permissions(path)?;
                     Account::from_str(std::fs::read_to_string(path)?.trim())
                 }
-                // Ensure the private key is provided to the CLI, except for clients or nodes in development mode.
-                (None, None) => match self.client {
+                // Ensure the private key is provided, except for clients, light nodes, or nodes in development mode.
+                (None, None) => match self.client || self.light {
                     true => Account::new(&mut rand::thread_rng()),
                     false => bail!("Missing the '--private-key' or '--private-key-file' argument"),
                 },
@@ -304,6 +399,19 @@ impl Start {
         }
     }
 
+    /// Connects to the external signer at the given socket path, returning the Aleo account.
+    #[cfg(target_family = "unix")]
+    fn connect_signer<N: Network>(path: &PathBuf) -> Result<Account<N>> {
+        let signer = snarkos_account::UnixSocketSigner::connect(path)?;
+        Ok(Account::from_signer(Arc::new(signer)))
+    }
+
+    /// Connects to the external signer at the given socket path, returning the Aleo account.
+    #[cfg(not(target_family = "unix"))]
+    fn connect_signer<N: Network>(_path: &PathBuf) -> Result<Account<N>> {
+        bail!("The '--signer-socket' argument is only supported on Unix")
+    }
+
     /// Updates the configurations if the node is in development mode.
     fn parse_development(
         &mut self,
@@ -346,6 +454,19 @@ impl Start {
         Ok(())
     }
 
+    /// Restores the ledger from the snapshot, if one is specified.
+    fn parse_snapshot<N: Network>(&self, storage_mode: &StorageMode) -> Result<()> {
+        if let (Some(snapshot), Some(snapshot_hash)) = (&self.snapshot, &self.snapshot_hash) {
+            // Parse the trusted hash of the checkpoint block.
+            let trusted_hash = N::BlockHash::from_str(snapshot_hash)
+                .map_err(|_| anyhow!("Invalid snapshot hash '{snapshot_hash}'"))?;
+            // Restore the ledger from the snapshot.
+            let height = snarkos_node::restore_snapshot::<N>(snapshot, trusted_hash, storage_mode.clone())?;
+            println!("📦 Bootstrapped the ledger from the snapshot at block {}.\n", height.to_string().bold());
+        }
+        Ok(())
+    }
+
     /// Returns an alternative genesis block if the node is in development mode.
     /// Otherwise, returns the actual genesis block.
     fn parse_genesis<N: Network>(&self) -> Result<Block<N>> {
@@ -484,6 +605,8 @@ impl Start {
             NodeType::Validator
         } else if self.prover {
             NodeType::Prover
+        } else if self.light {
+            NodeType::Light
         } else {
             NodeType::Client
         }
@@ -501,6 +624,16 @@ impl Start {
         let mut trusted_validators = self.parse_trusted_validators()?;
         // Parse the development configurations.
         self.parse_development(&mut trusted_peers, &mut trusted_validators)?;
+        // Parse the bootstrap peers and DNS seeds.
+        let bootstrap = self.parse_bootstrap()?;
+        // Initialize the router configuration.
+        let router_config = RouterConfig {
+            tcp: self.parse_tcp_config(),
+            bootstrap,
+            external_addr: self.external_addr,
+            max_peers_per_subnet: self.max_peers_per_subnet,
+            ..Default::default()
+        };
 
         // Parse the CDN.
         let cdn = self.parse_cdn();
@@ -561,11 +694,20 @@ impl Start {
             metrics::initialize_metrics();
         }
 
+        // Initialize the capture of the network messages.
+        if let Some(path) = &self.capture {
+            let capture = Capture::new(path, self.capture_size * 1024 * 1024, Capture::DEFAULT_MAX_FILES)
+                .map_err(|error| anyhow!("Failed to create the capture file '{}' - {error}", path.display()))?;
+            enable_capture(capture);
+        }
+
         // Initialize the storage mode.
         let storage_mode = match &self.storage {
             Some(path) => StorageMode::Custom(path.clone()),
             None => StorageMode::from(self.dev),
         };
+        // Restore the ledger from the snapshot, if one is specified.
+        self.parse_snapshot::<N>(&storage_mode)?;
 
         // Determine whether to generate background transactions in dev mode.
         let dev_txs = match self.dev {
@@ -581,9 +723,10 @@ impl Start {
 
         // Initialize the node.
         match node_type {
-            NodeType::Validator => Node::new_validator(node_ip, self.bft, rest_ip, self.rest_rps, account, &trusted_peers, &trusted_validators, genesis, cdn, storage_mode, self.allow_external_peers, dev_txs, shutdown.clone()).await,
-            NodeType::Prover => Node::new_prover(node_ip, account, &trusted_peers, genesis, storage_mode, shutdown.clone()).await,
-            NodeType::Client => Node::new_client(node_ip, rest_ip, self.rest_rps, account, &trusted_peers, genesis, cdn, storage_mode, shutdown).await,
+            NodeType::Validator => Node::new_validator(node_ip, self.bft, rest_ip, self.rest_rps, account, &trusted_peers, router_config, &trusted_validators, genesis, cdn, storage_mode, self.allow_external_peers, dev_txs, shutdown.clone()).await,
+            NodeType::Prover => Node::new_prover(node_ip, account, &trusted_peers, router_config, genesis, storage_mode, shutdown.clone()).await,
+            NodeType::Client => Node::new_client(node_ip, rest_ip, self.rest_rps, account, &trusted_peers, router_config, genesis, cdn, storage_mode, shutdown).await,
+            NodeType::Light => Node::new_light(node_ip, rest_ip, self.rest_rps, self.parse_rest_peers(), account, &trusted_peers, router_config, genesis, storage_mode, shutdown).await,
         }
     }
 
@@ -616,7 +759,7 @@ impl Start {
     }
 }
 
-fn check_permissions(path: &PathBuf) -> Result<(), snarkvm::prelude::Error> {
+pub(crate) fn check_permissions(path: &PathBuf) -> Result<(), snarkvm::prelude::Error> {
     #[cfg(target_family = "unix")]
     {
         use std::os::unix::fs::PermissionsExt;
@@ -790,6 +933,54 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_parse_bootstrap() {
+        let config = Start::try_parse_from(["snarkos"].iter()).unwrap();
+        assert!(config.parse_bootstrap().unwrap().is_empty());
+
+        let config = Start::try_parse_from(
+            ["snarkos", "--bootstrap-peers", "1.2.3.4:5, peer.example.com:4130", "--dns-seeds", "seed.example.com"]
+                .iter(),
+        )
+        .unwrap();
+        let bootstrap = config.parse_bootstrap().unwrap();
+        assert_eq!(bootstrap.peers(), ["1.2.3.4:5".to_string(), "peer.example.com:4130".to_string()]);
+        assert_eq!(bootstrap.dns_seeds(), ["seed.example.com".to_string()]);
+
+        let config = Start::try_parse_from(["snarkos", "--bootstrap-peers", "peer.example.com"].iter()).unwrap();
+        assert!(config.parse_bootstrap().is_err());
+    }
+
+    #[test]
+    fn test_parse_tcp_config() {
+        let config = Start::try_parse_from(["snarkos"].iter()).unwrap().parse_tcp_config();
+        assert_eq!((config.max_send_rate, config.max_send_rate_per_peer), (None, None));
+
+        let config = Start::try_parse_from(
+            ["snarkos", "--max-send-rate", "1000000", "--max-send-rate-per-peer", "100000"].iter(),
+        )
+        .unwrap()
+        .parse_tcp_config();
+        assert_eq!((config.max_send_rate, config.max_send_rate_per_peer), (Some(1_000_000), Some(100_000)));
+    }
+
+    #[test]
+    fn test_parse_light() {
+        let config = Start::try_parse_from(["snarkos", "--light"].iter()).unwrap();
+        assert_eq!(config.parse_node_type(), NodeType::Light);
+        assert!(config.parse_cdn().is_none());
+        assert!(config.parse_rest_peers().is_empty());
+
+        let config = Start::try_parse_from(
+            ["snarkos", "--light", "--rest-peers", "http://1.2.3.4:3030, https://api.example.com/"].iter(),
+        )
+        .unwrap();
+        assert_eq!(config.parse_rest_peers(), [
+            "http://1.2.3.4:3030".to_string(),
+            "https://api.example.com/".to_string()
+        ]);
+    }
+
     #[test]
     fn test_parse_cdn() {
         // Validator (Prod)
@@ -802,6 +993,12 @@ mod tests {
         let config =
             Start::try_parse_from(["snarkos", "--validator", "--private-key", "aleo1xx", "--cdn", ""].iter()).unwrap();
         assert!(config.parse_cdn().is_none());
+        let config = Start::try_parse_from(
+            ["snarkos", "--validator", "--private-key", "aleo1xx", "--snapshot", "PATH", "--snapshot-hash", "HASH"]
+                .iter(),
+        )
+        .unwrap();
+        assert!(config.parse_cdn().is_none());
 
         // Validator (Dev)
         let config =
@@ -1014,4 +1211,19 @@ mod tests {
             panic!("Unexpected result of clap parsing!");
         }
     }
+
+    #[test]
+    fn clap_snarkos_start_snapshot() {
+        // Ensure the snapshot requires a trusted hash, and vice versa.
+        assert!(CLI::try_parse_from(["snarkos", "start", "--snapshot", "PATH"]).is_err());
+        assert!(CLI::try_parse_from(["snarkos", "start", "--snapshot-hash", "HASH"]).is_err());
+
+        let cli = CLI::parse_from(["snarkos", "start", "--snapshot", "PATH", "--snapshot-hash", "HASH"]);
+        if let Command::Start(start) = cli.command {
+            assert_eq!(start.snapshot, Some(PathBuf::from("PATH")));
+            assert_eq!(start.snapshot_hash.as_deref(), Some("HASH"));
+        } else {
+            panic!("Unexpected result of clap parsing!");
+        }
+    }
 }
@@ -0,0 +1,108 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+/// A record, signed by a validator, of the listener IP at which it accepts connections.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AddressRecord<N: Network> {
+    /// The address of the validator.
+    pub address: Address<N>,
+    /// The listener IP of the validator.
+    pub listener_ip: SocketAddr,
+    /// The UNIX timestamp (in seconds) at which the record was signed.
+    pub timestamp: i64,
+    /// The signature of the validator on the record.
+    pub signature: Signature<N>,
+}
+
+impl<N: Network> AddressRecord<N> {
+    /// Initializes a new address record.
+    pub const fn new(address: Address<N>, listener_ip: SocketAddr, timestamp: i64, signature: Signature<N>) -> Self {
+        Self { address, listener_ip, timestamp, signature }
+    }
+
+    /// Returns the request that the validator signs for the given address record.
+    pub const fn signing_request(address: Address<N>, listener_ip: SocketAddr, timestamp: i64) -> SignRequest<N> {
+        SignRequest::AddressRecord { address, listener_ip, timestamp }
+    }
+
+    /// Returns `true` if the signature is valid for the address record.
+    pub fn verify(&self) -> bool {
+        Self::signing_request(self.address, self.listener_ip, self.timestamp).verify(&self.address, &self.signature)
+    }
+}
+
+impl<N: Network> ToBytes for AddressRecord<N> {
+    fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()> {
+        self.address.write_le(&mut writer)?;
+        self.listener_ip.write_le(&mut writer)?;
+        self.timestamp.write_le(&mut writer)?;
+        self.signature.write_le(&mut writer)
+    }
+}
+
+impl<N: Network> FromBytes for AddressRecord<N> {
+    fn read_le<R: Read>(mut reader: R) -> IoResult<Self> {
+        let address = Address::read_le(&mut reader)?;
+        let listener_ip = SocketAddr::read_le(&mut reader)?;
+        let timestamp = i64::read_le(&mut reader)?;
+        let signature = Signature::read_le(&mut reader)?;
+        Ok(Self { address, listener_ip, timestamp, signature })
+    }
+}
+
+#[cfg(test)]
+pub mod prop_tests {
+    use crate::{validators_response::prop_tests::any_valid_socket_addr, AddressRecord};
+
+    use bytes::{Buf, BufMut, BytesMut};
+    use proptest::prelude::{any, BoxedStrategy, Strategy};
+    use snarkvm::{
+        prelude::{Address, PrivateKey, TestRng},
+        utilities::{FromBytes, ToBytes},
+    };
+    use test_strategy::proptest;
+
+    type CurrentNetwork = snarkvm::prelude::MainnetV0;
+
+    pub fn any_address_record() -> BoxedStrategy<AddressRecord<CurrentNetwork>> {
+        (any::<u64>(), any_valid_socket_addr(), any::<i64>())
+            .prop_map(|(seed, listener_ip, timestamp)| {
+                let rng = &mut TestRng::fixed(seed);
+                let private_key = PrivateKey::new(rng).unwrap();
+                let address = Address::try_from(&private_key).unwrap();
+                let request = AddressRecord::signing_request(address, listener_ip, timestamp);
+                let signature = request.sign_with(&private_key, rng).unwrap();
+                AddressRecord::new(address, listener_ip, timestamp, signature)
+            })
+            .boxed()
+    }
+
+    #[proptest]
+    fn address_record_roundtrip(#[strategy(any_address_record())] record: AddressRecord<CurrentNetwork>) {
+        let mut bytes = BytesMut::default().writer();
+        record.write_le(&mut bytes).unwrap();
+        let decoded = AddressRecord::<CurrentNetwork>::read_le(&mut bytes.into_inner().reader()).unwrap();
+        assert!(decoded.verify());
+        assert_eq![decoded, record];
+    }
+
+    #[proptest]
+    fn address_record_tampered(#[strategy(any_address_record())] record: AddressRecord<CurrentNetwork>) {
+        let mut tampered = record.clone();
+        tampered.timestamp = record.timestamp.wrapping_add(1);
+        assert!(!tampered.verify());
+    }
+}
@@ -14,11 +14,61 @@
 
 use super::*;
 
+/// The marker in the lower half of a response nonce, which signals that the responding node supports encryption.
+const ENCRYPTION_MARKER: u32 = 0x454e_4352;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ChallengeResponse<N: Network> {
     pub restrictions_id: Field<N>,
     pub signature: Data<Signature<N>>,
     pub nonce: u64,
+    /// The session handshake message, if the connection is encrypted.
+    ///
+    /// Note: This field is only written if it is set, so that peers on older versions can read the response.
+    pub session_message: Option<Vec<u8>>,
+    /// The capabilities of the node, if the receiver is known to read them.
+    ///
+    /// Note: This field is only written if it is set, so that peers on older versions can read the response.
+    /// The capabilities are advertised in the response rather than the request, as the version of the peer
+    /// is only known once its challenge request is received.
+    pub capabilities: Option<Capabilities>,
+}
+
+impl<N: Network> ChallengeResponse<N> {
+    /// Returns a response nonce to the given request nonce, from the given random value.
+    ///
+    /// The lower half of the nonce signals that this node supports encryption. As the response nonce is signed
+    /// on every version, a man-in-the-middle cannot pass this node off as an older version to force plaintext.
+    pub fn new_nonce(request_nonce: u64, random: u64) -> u64 {
+        (random & !u64::from(u32::MAX)) | u64::from(request_nonce as u32 ^ ENCRYPTION_MARKER)
+    }
+
+    /// Returns `true` if the given response nonce signals that the responding node supports encryption.
+    pub fn is_encryption_signaled(request_nonce: u64, response_nonce: u64) -> bool {
+        response_nonce as u32 == request_nonce as u32 ^ ENCRYPTION_MARKER
+    }
+
+    /// Returns the request that is signed in a challenge response to a challenge request.
+    ///
+    /// If both nodes support encryption, the request also binds the version of the challenge request, the version
+    /// and capabilities of the responding node, and the session handshake, so that none of them can be rewritten.
+    pub fn signing_request(
+        request_nonce: u64,
+        request_version: u32,
+        response_nonce: u64,
+        response_version: u32,
+        capabilities: Option<Capabilities>,
+        binding: &[u8],
+    ) -> SignRequest<N> {
+        let mut context = Vec::new();
+        if request_version.min(response_version) >= Event::<N>::ENCRYPTED_TRANSPORT_VERSION {
+            context.extend_from_slice(&request_version.to_le_bytes());
+            context.extend_from_slice(&response_version.to_le_bytes());
+            context.push(capabilities.unwrap_or_default().bits());
+            context.extend_from_slice(binding);
+        }
+        SignRequest::Challenge { request_nonce, response_nonce, context }
+    }
 }
 
 impl<N: Network> EventTrait for ChallengeResponse<N> {
@@ -34,6 +84,18 @@ impl<N: Network> ToBytes for ChallengeResponse<N> {
         self.restrictions_id.write_le(&mut writer)?;
         self.signature.write_le(&mut writer)?;
         self.nonce.write_le(&mut writer)?;
+        match (&self.session_message, &self.capabilities) {
+            (Some(session_message), capabilities) => {
+                u16::try_from(session_message.len()).map_err(error)?.write_le(&mut writer)?;
+                writer.write_all(session_message)?;
+                if let Some(capabilities) = capabilities {
+                    capabilities.write_le(&mut writer)?;
+                }
+            }
+            (None, None) => (),
+            // Note: Every version that reads the capabilities also encrypts the connection.
+            (None, Some(_)) => return Err(error("The capabilities require a session message")),
+        }
         Ok(())
     }
 }
@@ -43,14 +105,35 @@ impl<N: Network> FromBytes for ChallengeResponse<N> {
         let restrictions_id = Field::read_le(&mut reader)?;
         let signature = Data::read_le(&mut reader)?;
         let nonce = u64::read_le(&mut reader)?;
-
-        Ok(Self { restrictions_id, signature, nonce })
+        // Read the session message, if there are bytes remaining.
+        let mut length = [0u8; 2];
+        let session_message = match reader.read(&mut length[..1])? {
+            0 => None,
+            _ => {
+                reader.read_exact(&mut length[1..])?;
+                let length = u16::from_le_bytes(length) as usize;
+                if length > MAX_SESSION_MESSAGE_SIZE {
+                    return Err(error(format!("Session message of {length} bytes exceeds {MAX_SESSION_MESSAGE_SIZE}")));
+                }
+                let mut session_message = vec![0u8; length];
+                reader.read_exact(&mut session_message)?;
+                Some(session_message)
+            }
+        };
+        // Read the capabilities, if there are bytes remaining.
+        let mut capabilities = [0u8; 1];
+        let capabilities = match reader.read(&mut capabilities)? {
+            0 => None,
+            _ => Some(Capabilities::read_le(&capabilities[..])?),
+        };
+
+        Ok(Self { restrictions_id, signature, nonce, session_message, capabilities })
     }
 }
 
 #[cfg(test)]
 pub mod prop_tests {
-    use crate::ChallengeResponse;
+    use crate::{Capabilities, ChallengeResponse, Event};
     use snarkvm::{
         console::prelude::{FromBytes, ToBytes},
         ledger::narwhal::Data,
@@ -80,11 +163,19 @@ pub mod prop_tests {
     }
 
     pub fn any_challenge_response() -> BoxedStrategy<ChallengeResponse<CurrentNetwork>> {
-        (any_signature(), any::<u64>())
-            .prop_map(|(sig, nonce)| ChallengeResponse {
+        let session_message = proptest::collection::vec(any::<u8>(), 0..64);
+        (any_signature(), any::<u64>(), proptest::option::of((session_message, any::<Option<bool>>())))
+            .prop_map(|(sig, nonce, trailing)| ChallengeResponse {
                 restrictions_id: any_restrictions_id(),
                 signature: Data::Object(sig),
                 nonce,
+                session_message: trailing.as_ref().map(|(session_message, _)| session_message.clone()),
+                capabilities: trailing.and_then(|(_, capabilities)| capabilities).map(
+                    |is_supported| match is_supported {
+                        true => Capabilities::supported(),
+                        false => Capabilities::empty(),
+                    },
+                ),
             })
             .boxed()
     }
@@ -100,5 +191,36 @@ pub mod prop_tests {
             original.signature.deserialize_blocking().unwrap(),
             deserialized.signature.deserialize_blocking().unwrap()
         );
+        assert_eq!(original.session_message, deserialized.session_message);
+        assert_eq!(original.capabilities, deserialized.capabilities);
+    }
+
+    #[proptest]
+    fn new_nonce_signals_encryption(request_nonce: u64, random: u64, other_nonce: u64) {
+        let nonce = ChallengeResponse::<CurrentNetwork>::new_nonce(request_nonce, random);
+        assert!(ChallengeResponse::<CurrentNetwork>::is_encryption_signaled(request_nonce, nonce));
+        assert_eq!(nonce >> 32, random >> 32);
+        if other_nonce as u32 != request_nonce as u32 {
+            assert!(!ChallengeResponse::<CurrentNetwork>::is_encryption_signaled(other_nonce, nonce));
+        }
+    }
+
+    #[test]
+    fn signing_request_binds_versions_and_capabilities() {
+        type Response = ChallengeResponse<CurrentNetwork>;
+        let (old, new) = (Event::<CurrentNetwork>::MINIMUM_VERSION, Event::<CurrentNetwork>::VERSION);
+        let capabilities = Some(Capabilities::supported());
+
+        // Ensure only the nonces are signed if either node does not support encryption.
+        assert_eq!(
+            Response::signing_request(1, old, 2, new, capabilities, b"session"),
+            Response::signing_request(1, new, 2, old, None, &[])
+        );
+        // Ensure the versions, the capabilities and the session are signed if both nodes support encryption.
+        let data = Response::signing_request(1, new, 2, new, capabilities, b"session");
+        assert_ne!(data, Response::signing_request(1, new - 1, 2, new, capabilities, b"session"));
+        assert_ne!(data, Response::signing_request(1, new, 2, new - 1, capabilities, b"session"));
+        assert_ne!(data, Response::signing_request(1, new, 2, new, None, b"session"));
+        assert_ne!(data, Response::signing_request(1, new, 2, new, capabilities, b"other"));
     }
 }
@@ -0,0 +1,422 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::Bytes;
+use once_cell::sync::OnceCell;
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    net::{Ipv6Addr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError},
+    },
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::*;
+
+/// The magic bytes at the start of a capture file.
+const CAPTURE_MAGIC: [u8; 8] = *b"SNOSCAPT";
+/// The version of the capture file format.
+pub const CAPTURE_VERSION: u16 = 1;
+/// The size of a record header, in bytes.
+const RECORD_HEADER_SIZE: usize = 8 + 1 + 1 + 16 + 2 + 4;
+/// The maximum size of a recorded payload, in bytes.
+const MAXIMUM_PAYLOAD_SIZE: u32 = 256 * 1024 * 1024; // 256 MiB
+/// The maximum number of records waiting to be written, beyond which new records are dropped.
+const MAXIMUM_PENDING_RECORDS: usize = 1 << 14;
+
+/// The capture of the node, if it is enabled.
+static CAPTURE: OnceCell<Capture> = OnceCell::new();
+
+/// Enables the capture of the router messages of the node. Returns `false` if it was already enabled.
+///
+/// Note: The BFT events are not captured, as they cannot be replayed without the key of a committee member.
+pub fn enable_capture(capture: Capture) -> bool {
+    CAPTURE.set(capture).is_ok()
+}
+
+/// Returns the capture of the node, if it is enabled.
+pub fn capture() -> Option<&'static Capture> {
+    CAPTURE.get()
+}
+
+/// The direction of a captured message, from the perspective of the capturing node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// The message was received from the peer.
+    Inbound = 0,
+    /// The message was sent to the peer.
+    Outbound = 1,
+}
+
+/// The protocol of a captured message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureProtocol {
+    /// A router `Message`.
+    Router = 0,
+    /// A BFT `Event`, which is only found in the capture files of earlier versions.
+    Bft = 1,
+}
+
+/// A single captured message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaptureRecord {
+    /// The UNIX timestamp at which the message was captured, in microseconds.
+    pub timestamp: u64,
+    /// The direction of the message.
+    pub direction: Direction,
+    /// The protocol of the message.
+    pub protocol: CaptureProtocol,
+    /// The (ambiguous) address of the peer.
+    pub peer: SocketAddr,
+    /// The serialized (i.e. decrypted and decompressed) message.
+    pub payload: Bytes,
+}
+
+impl CaptureRecord {
+    /// Writes the record into the given writer.
+    fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let ip = match self.peer {
+            SocketAddr::V4(addr) => addr.ip().to_ipv6_mapped(),
+            SocketAddr::V6(addr) => *addr.ip(),
+        };
+        writer.write_all(&self.timestamp.to_le_bytes())?;
+        writer.write_all(&[self.direction as u8, self.protocol as u8])?;
+        writer.write_all(&ip.octets())?;
+        writer.write_all(&self.peer.port().to_le_bytes())?;
+        writer.write_all(&(self.payload.len() as u32).to_le_bytes())?;
+        writer.write_all(&self.payload)
+    }
+
+    /// Reads the next record from the given reader, or returns `None` if the reader is at the end.
+    fn read_from<R: Read>(mut reader: R) -> io::Result<Option<Self>> {
+        let mut header = [0u8; RECORD_HEADER_SIZE];
+        // Distinguish the end of the file from a truncated record.
+        let num_read = reader.read(&mut header)?;
+        if num_read == 0 {
+            return Ok(None);
+        }
+        reader.read_exact(&mut header[num_read..])?;
+
+        let invalid = |error: &str| io::Error::new(io::ErrorKind::InvalidData, error.to_string());
+        let timestamp = u64::from_le_bytes(header[0..8].try_into().unwrap());
+        let direction = match header[8] {
+            0 => Direction::Inbound,
+            1 => Direction::Outbound,
+            _ => return Err(invalid("Invalid direction in the capture record")),
+        };
+        let protocol = match header[9] {
+            0 => CaptureProtocol::Router,
+            1 => CaptureProtocol::Bft,
+            _ => return Err(invalid("Invalid protocol in the capture record")),
+        };
+        let ip = Ipv6Addr::from(<[u8; 16]>::try_from(&header[10..26]).unwrap()).to_canonical();
+        let port = u16::from_le_bytes(header[26..28].try_into().unwrap());
+        let length = u32::from_le_bytes(header[28..32].try_into().unwrap());
+        if length > MAXIMUM_PAYLOAD_SIZE {
+            return Err(invalid("The capture record exceeds the maximum size"));
+        }
+        let mut payload = vec![0u8; length as usize];
+        reader.read_exact(&mut payload)?;
+
+        Ok(Some(Self { timestamp, direction, protocol, peer: SocketAddr::new(ip, port), payload: payload.into() }))
+    }
+}
+
+/// A command for the writer of the capture.
+enum CaptureCommand {
+    /// Writes the record into the capture file.
+    Record(CaptureRecord),
+    /// Notifies the sender once the preceding records are written.
+    Flush(SyncSender<()>),
+}
+
+/// The file that is currently written to, and its size in bytes.
+struct CaptureFile {
+    writer: BufWriter<File>,
+    size: u64,
+}
+
+/// Records the messages and events of the node into a capture file.
+///
+/// The records are written by a dedicated thread, so that recording never blocks the connections on the disk.
+/// If the writer falls behind by more than `MAXIMUM_PENDING_RECORDS`, the new records are dropped.
+///
+/// Once the capture file exceeds the maximum size, it is rotated: the file at `path` is renamed to `path.1`,
+/// the file at `path.1` to `path.2`, and so on, keeping at most `max_files` files (including the current one).
+/// An existing capture file is rotated on startup, so that the capture of a previous run is preserved.
+pub struct Capture {
+    /// The path of the current capture file.
+    path: PathBuf,
+    /// The sender of the commands for the writer.
+    sender: SyncSender<CaptureCommand>,
+    /// The number of records dropped, as the writer fell behind.
+    num_dropped: AtomicU64,
+}
+
+impl Capture {
+    /// The default maximum number of capture files to keep.
+    pub const DEFAULT_MAX_FILES: usize = 4;
+    /// The default maximum size of a capture file, in bytes.
+    pub const DEFAULT_MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+    // 64 MiB
+
+    /// Initializes a new capture, writing into the file at the given path.
+    pub fn new<P: AsRef<Path>>(path: P, max_file_size: u64, max_files: usize) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let max_files = max_files.max(1);
+        // Preserve the capture of a previous run.
+        if path.exists() {
+            Self::rotate_files(&path, max_files)?;
+        }
+        let file = Self::create_file(&path)?;
+        // Spawn the writer, which stops once the capture is dropped.
+        let (sender, receiver) = mpsc::sync_channel(MAXIMUM_PENDING_RECORDS);
+        let writer = CaptureWriter { path: path.clone(), max_file_size, max_files, file };
+        thread::Builder::new().name("capture".to_string()).spawn(move || writer.run(receiver))?;
+        Ok(Self { path, sender, num_dropped: Default::default() })
+    }
+
+    /// Returns the path of the current capture file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records the given serialized message, exchanged with the given peer.
+    pub fn record(&self, direction: Direction, protocol: CaptureProtocol, peer: SocketAddr, payload: &[u8]) {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_micros() as u64;
+        let record = CaptureRecord { timestamp, direction, protocol, peer, payload: Bytes::copy_from_slice(payload) };
+
+        match self.sender.try_send(CaptureCommand::Record(record)) {
+            Ok(()) => (),
+            Err(TrySendError::Full(_)) => {
+                // Warn on the first dropped record, and then periodically.
+                let num_dropped = self.num_dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                if num_dropped % 10_000 == 1 {
+                    warn!("Dropped {num_dropped} records, as the capture file '{}' is too slow", self.path.display());
+                }
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                trace!("Skipped a record, as the capture file '{}' is no longer written", self.path.display())
+            }
+        }
+    }
+
+    /// Waits until the records recorded so far are written into the capture file.
+    pub fn flush(&self) {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        if self.sender.send(CaptureCommand::Flush(sender)).is_ok() {
+            let _ = receiver.recv();
+        }
+    }
+
+    /// Creates a new capture file at the given path, and writes its header.
+    fn create_file(path: &Path) -> io::Result<CaptureFile> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(&CAPTURE_MAGIC)?;
+        writer.write_all(&CAPTURE_VERSION.to_le_bytes())?;
+        writer.flush()?;
+        Ok(CaptureFile { writer, size: (CAPTURE_MAGIC.len() + 2) as u64 })
+    }
+
+    /// Shifts the capture files at the given path by one, removing the oldest one.
+    fn rotate_files(path: &Path, max_files: usize) -> io::Result<()> {
+        let rotated_path = |index: usize| PathBuf::from(format!("{}.{index}", path.display()));
+        // Remove the oldest capture file, if it exists.
+        let oldest_path = rotated_path(max_files - 1);
+        if max_files > 1 && oldest_path.exists() {
+            fs::remove_file(&oldest_path)?;
+        }
+        for index in (1..max_files.saturating_sub(1)).rev() {
+            if rotated_path(index).exists() {
+                fs::rename(rotated_path(index), rotated_path(index + 1))?;
+            }
+        }
+        match max_files > 1 {
+            true => fs::rename(path, rotated_path(1)),
+            false => fs::remove_file(path),
+        }
+    }
+}
+
+/// Writes the records of a capture on a dedicated thread.
+struct CaptureWriter {
+    /// The path of the current capture file.
+    path: PathBuf,
+    /// The maximum size of a capture file, in bytes.
+    max_file_size: u64,
+    /// The maximum number of capture files to keep.
+    max_files: usize,
+    /// The current capture file.
+    file: CaptureFile,
+}
+
+impl CaptureWriter {
+    /// Writes the received records, until the capture is dropped.
+    fn run(mut self, receiver: Receiver<CaptureCommand>) {
+        while let Ok(mut command) = receiver.recv() {
+            loop {
+                match command {
+                    CaptureCommand::Record(record) => {
+                        if let Err(error) = self.write_record(&record) {
+                            warn!("Failed to write to the capture file '{}' - {error}", self.path.display());
+                        }
+                    }
+                    CaptureCommand::Flush(sender) => {
+                        self.flush();
+                        let _ = sender.send(());
+                    }
+                }
+                // Drain the pending records, before flushing them at once.
+                command = match receiver.try_recv() {
+                    Ok(command) => command,
+                    Err(TryRecvError::Empty | TryRecvError::Disconnected) => break,
+                };
+            }
+            // Flush once idle, so that the capture is complete if the node crashes.
+            self.flush();
+        }
+    }
+
+    /// Writes the given record into the current capture file, rotating it if it exceeds the maximum size.
+    fn write_record(&mut self, record: &CaptureRecord) -> io::Result<()> {
+        if self.file.size >= self.max_file_size {
+            self.file.writer.flush()?;
+            Capture::rotate_files(&self.path, self.max_files)?;
+            self.file = Capture::create_file(&self.path)?;
+        }
+        record.write_to(&mut self.file.writer)?;
+        self.file.size += (RECORD_HEADER_SIZE + record.payload.len()) as u64;
+        Ok(())
+    }
+
+    /// Flushes the buffered records into the current capture file.
+    fn flush(&mut self) {
+        if let Err(error) = self.file.writer.flush() {
+            warn!("Failed to flush the capture file '{}' - {error}", self.path.display());
+        }
+    }
+}
+
+/// Reads the records of a capture file.
+pub struct CaptureReader {
+    reader: BufReader<File>,
+}
+
+impl CaptureReader {
+    /// Opens the capture file at the given path.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut header = [0u8; CAPTURE_MAGIC.len() + 2];
+        reader.read_exact(&mut header)?;
+        if header[..CAPTURE_MAGIC.len()] != CAPTURE_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "The file is not a capture file"));
+        }
+        let version = u16::from_le_bytes([header[8], header[9]]);
+        if version != CAPTURE_VERSION {
+            let error = format!("Unsupported capture format version {version} (expected {CAPTURE_VERSION})");
+            return Err(io::Error::new(io::ErrorKind::InvalidData, error));
+        }
+        Ok(Self { reader })
+    }
+}
+
+impl Iterator for CaptureReader {
+    type Item = io::Result<CaptureRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        CaptureRecord::read_from(&mut self.reader).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a unique path for a capture file.
+    fn sample_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("snarkos-capture-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn test_capture_roundtrip() {
+        let path = sample_path("roundtrip");
+        let capture = Capture::new(&path, Capture::DEFAULT_MAX_FILE_SIZE, Capture::DEFAULT_MAX_FILES).unwrap();
+
+        let peer_v4 = SocketAddr::from(([127, 0, 0, 1], 4130));
+        let peer_v6 = SocketAddr::from((Ipv6Addr::LOCALHOST, 5000));
+        capture.record(Direction::Inbound, CaptureProtocol::Router, peer_v4, b"ping");
+        capture.record(Direction::Outbound, CaptureProtocol::Bft, peer_v6, &[]);
+        capture.flush();
+
+        let records = CaptureReader::open(&path).unwrap().collect::<io::Result<Vec<_>>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].direction, Direction::Inbound);
+        assert_eq!(records[0].protocol, CaptureProtocol::Router);
+        assert_eq!(records[0].peer, peer_v4);
+        assert_eq!(&records[0].payload[..], b"ping");
+        assert_eq!(records[1].direction, Direction::Outbound);
+        assert_eq!(records[1].protocol, CaptureProtocol::Bft);
+        assert_eq!(records[1].peer, peer_v6);
+        assert!(records[1].payload.is_empty());
+        assert!(records[0].timestamp <= records[1].timestamp);
+
+        // Ensure a truncated record is rejected.
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        let records = CaptureReader::open(&path).unwrap().collect::<Vec<_>>();
+        assert!(records[0].is_ok());
+        assert!(records[1].is_err());
+    }
+
+    #[test]
+    fn test_capture_rotation() {
+        let path = sample_path("rotation");
+        let rotated_path = |index: usize| PathBuf::from(format!("{}.{index}", path.display()));
+        let peer = SocketAddr::from(([127, 0, 0, 1], 4130));
+
+        // Ensure the capture file is rotated once it exceeds the maximum size.
+        let capture = Capture::new(&path, 128, 3).unwrap();
+        for i in 0..6u8 {
+            capture.record(Direction::Inbound, CaptureProtocol::Router, peer, &[i; 32]);
+        }
+        capture.flush();
+        let payloads = |path: &Path| {
+            CaptureReader::open(path).unwrap().map(|record| record.unwrap().payload[0]).collect::<Vec<_>>()
+        };
+        assert_eq!(payloads(&path), vec![4, 5]);
+        assert_eq!(payloads(&rotated_path(1)), vec![2, 3]);
+        assert_eq!(payloads(&rotated_path(2)), vec![0, 1]);
+        assert!(!rotated_path(3).exists());
+
+        // Ensure the capture of a previous run is preserved.
+        let capture = Capture::new(&path, 128, 3).unwrap();
+        capture.record(Direction::Inbound, CaptureProtocol::Router, peer, &[6; 32]);
+        capture.flush();
+        assert_eq!(payloads(&path), vec![6]);
+        assert_eq!(payloads(&rotated_path(1)), vec![4, 5]);
+        assert_eq!(payloads(&rotated_path(2)), vec![2, 3]);
+        assert!(!rotated_path(3).exists());
+
+        // Ensure an invalid file is rejected.
+        fs::write(&path, b"not a capture").unwrap();
+        assert!(CaptureReader::open(&path).is_err());
+    }
+}
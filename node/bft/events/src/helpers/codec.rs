@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::{compress_frame, decompress_frame, Compression, Session, SessionCipher};
 use crate::Event;
 use snarkvm::prelude::{FromBytes, Network, ToBytes};
 
@@ -28,6 +29,12 @@ const MAX_EVENT_SIZE: usize = 256 * 1024 * 1024; // 256 MiB
 /// The codec used to decode and encode network `Event`s.
 pub struct EventCodec<N: Network> {
     codec: LengthDelimitedCodec,
+    /// The cipher of the session, if the connection is encrypted.
+    cipher: Option<SessionCipher>,
+    /// The compression of the connection.
+    compression: Compression,
+    /// The negotiated version of the event protocol of the connection.
+    version: u32,
     _phantom: PhantomData<N>,
 }
 
@@ -37,12 +44,50 @@ impl<N: Network> EventCodec<N> {
         codec.codec.set_max_frame_length(MAX_HANDSHAKE_SIZE);
         codec
     }
+
+    /// Initializes a codec that encrypts and decrypts every event with the given session.
+    pub fn encrypted(session: Session) -> Self {
+        let mut codec = Self::default();
+        codec.cipher = Some(SessionCipher::new(session));
+        codec.codec.set_max_frame_length(codec.max_frame_length());
+        codec
+    }
+
+    /// Sets the compression of the connection.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self.codec.set_max_frame_length(self.max_frame_length());
+        self
+    }
+
+    /// Sets the negotiated version of the event protocol of the connection.
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Returns the negotiated version of the event protocol of the connection.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the maximum length of an inbound frame, which includes the frame tag and the encryption overhead.
+    fn max_frame_length(&self) -> usize {
+        let length = MAX_EVENT_SIZE + self.compression.inbound as usize;
+        match self.cipher {
+            Some(_) => length + SessionCipher::overhead(length),
+            None => length,
+        }
+    }
 }
 
 impl<N: Network> Default for EventCodec<N> {
     fn default() -> Self {
         Self {
             codec: LengthDelimitedCodec::builder().max_frame_length(MAX_EVENT_SIZE).little_endian().new_codec(),
+            cipher: None,
+            compression: Default::default(),
+            version: Event::<N>::VERSION,
             _phantom: Default::default(),
         }
     }
@@ -52,15 +97,56 @@ impl<N: Network> Encoder<Event<N>> for EventCodec<N> {
     type Error = std::io::Error;
 
     fn encode(&mut self, event: Event<N>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        // Ensure the event is supported by the negotiated version.
+        if !event.is_supported_by(self.version) {
+            let error = format!("'{}' is not supported by version {}", event.name(), self.version);
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, error));
+        }
+
         // Serialize the payload directly into dst.
         event
             .write_le(&mut dst.writer())
             // This error should never happen, the conversion is for greater compatibility.
             .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "serialization error"))?;
 
-        let serialized_event = dst.split_to(dst.len()).freeze();
+        let serialized_event = dst.split_to(dst.len());
+
+        // Compress the event, if the peer accepts compressed frames.
+        let serialized_event = match self.compression.outbound {
+            true => {
+                let mut frame = BytesMut::new();
+                let _ratio = compress_frame(&serialized_event, &mut frame)?;
+                #[cfg(feature = "metrics")]
+                if let Some(ratio) = _ratio {
+                    metrics::histogram(metrics::bft::COMPRESSION_RATIO, ratio);
+                    let saved_bytes = serialized_event.len().saturating_sub(frame.len());
+                    metrics::increment_gauge(metrics::bft::COMPRESSION_SAVED_BYTES, saved_bytes as f64);
+                }
+                frame
+            }
+            false => serialized_event,
+        };
+
+        // Encrypt the event, if the connection is encrypted.
+        let serialized_event = match &mut self.cipher {
+            Some(cipher) => {
+                let mut encrypted_event = BytesMut::new();
+                cipher.encrypt(&serialized_event, &mut encrypted_event)?;
+                encrypted_event.freeze()
+            }
+            None => serialized_event.freeze(),
+        };
+
+        self.codec.encode(serialized_event, dst)?;
+
+        #[cfg(feature = "metrics")]
+        {
+            // Note: The destination only holds the frame of this event, as its contents were split off above.
+            metrics::increment_counter_by_type(metrics::bft::EVENTS_SENT, event.type_name(), 1);
+            metrics::increment_counter_by_type(metrics::bft::EVENT_BYTES_SENT, event.type_name(), dst.len() as u64);
+        }
 
-        self.codec.encode(serialized_event, dst)
+        Ok(())
     }
 }
 
@@ -70,15 +156,47 @@ impl<N: Network> Decoder for EventCodec<N> {
 
     fn decode(&mut self, source: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
         // Decode a frame containing bytes belonging to an event.
+        #[cfg(feature = "metrics")]
+        let initial_len = source.len();
         let bytes = match self.codec.decode(source)? {
             Some(bytes) => bytes,
             None => return Ok(None),
         };
+        #[cfg(feature = "metrics")]
+        let frame_len = initial_len - source.len();
+
+        // Decrypt the event, if the connection is encrypted.
+        let bytes = match &mut self.cipher {
+            Some(cipher) => cipher.decrypt(&bytes)?,
+            None => bytes,
+        };
+
+        // Decompress the event, if the connection accepts compressed frames.
+        let bytes = match self.compression.inbound {
+            true => decompress_frame(bytes, MAX_EVENT_SIZE)?,
+            false => bytes,
+        };
 
         // Convert the bytes to an event, or fail if it is not valid.
         let reader = bytes.reader();
         match Event::read_le(reader) {
-            Ok(event) => Ok(Some(event)),
+            // Ensure the event is supported by the negotiated version.
+            Ok(event) if !event.is_supported_by(self.version) => {
+                error!("Received '{}', which is not supported by version {}", event.name(), self.version);
+                Err(std::io::ErrorKind::InvalidData.into())
+            }
+            Ok(event) => {
+                #[cfg(feature = "metrics")]
+                {
+                    metrics::increment_counter_by_type(metrics::bft::EVENTS_RECEIVED, event.type_name(), 1);
+                    metrics::increment_counter_by_type(
+                        metrics::bft::EVENT_BYTES_RECEIVED,
+                        event.type_name(),
+                        frame_len as u64,
+                    );
+                }
+                Ok(Some(event))
+            }
             Err(error) => {
                 error!("Failed to deserialize an event: {}", error);
                 Err(std::io::ErrorKind::InvalidData.into())
@@ -90,7 +208,7 @@ impl<N: Network> Decoder for EventCodec<N> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::prop_tests::any_event;
+    use crate::{helpers::session::tests::sample_sessions, prop_tests::any_event};
     use test_strategy::proptest;
 
     type CurrentNetwork = snarkvm::prelude::MainnetV0;
@@ -108,4 +226,48 @@ mod tests {
     fn event_roundtrip(#[strategy(any_event())] event: Event<CurrentNetwork>) {
         assert_roundtrip(event)
     }
+
+    #[proptest]
+    fn encrypted_event_roundtrip(#[strategy(any_event())] event: Event<CurrentNetwork>) {
+        let (initiator, responder) = sample_sessions();
+        let mut encoder = EventCodec::<CurrentNetwork>::encrypted(initiator);
+        let mut decoder = EventCodec::<CurrentNetwork>::encrypted(responder);
+        let mut encoded_event = BytesMut::new();
+
+        assert!(encoder.encode(event.clone(), &mut encoded_event).is_ok());
+        // Ensure the event cannot be decoded without the session.
+        assert!(EventCodec::<CurrentNetwork>::default().decode(&mut encoded_event.clone()).is_err());
+        let decoded = decoder.decode(&mut encoded_event).unwrap().unwrap();
+        assert_eq!(decoded.to_bytes_le().unwrap(), event.to_bytes_le().unwrap());
+    }
+
+    #[proptest]
+    fn compressed_event_roundtrip(#[strategy(any_event())] event: Event<CurrentNetwork>) {
+        let (initiator, responder) = sample_sessions();
+        let compression = Compression { outbound: true, inbound: true };
+        let mut encoder = EventCodec::<CurrentNetwork>::encrypted(initiator).with_compression(compression);
+        let mut decoder = EventCodec::<CurrentNetwork>::encrypted(responder).with_compression(compression);
+        let mut encoded_event = BytesMut::new();
+
+        assert!(encoder.encode(event.clone(), &mut encoded_event).is_ok());
+        let decoded = decoder.decode(&mut encoded_event).unwrap().unwrap();
+        assert_eq!(decoded.to_bytes_le().unwrap(), event.to_bytes_le().unwrap());
+    }
+
+    #[proptest]
+    fn versioned_event_roundtrip(#[strategy(any_event())] event: Event<CurrentNetwork>) {
+        // Ensure the events supported by the negotiated version are encoded and decoded.
+        let version = Event::<CurrentNetwork>::negotiate_version(Event::<CurrentNetwork>::MINIMUM_VERSION).unwrap();
+        let mut codec = EventCodec::<CurrentNetwork>::default().with_version(version);
+        let mut encoded_event = BytesMut::new();
+        assert!(codec.encode(event.clone(), &mut encoded_event).is_ok());
+        let decoded = codec.decode(&mut encoded_event).unwrap().unwrap();
+        assert_eq!(decoded.to_bytes_le().unwrap(), event.to_bytes_le().unwrap());
+
+        // Ensure the events unsupported by the negotiated version are rejected.
+        let mut codec = EventCodec::<CurrentNetwork>::default().with_version(event.minimum_version() - 1);
+        assert!(codec.encode(event.clone(), &mut encoded_event).is_err());
+        EventCodec::<CurrentNetwork>::default().encode(event, &mut encoded_event).unwrap();
+        assert!(codec.decode(&mut encoded_event).is_err());
+    }
 }
@@ -0,0 +1,191 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use snarkvm::prelude::{FromBytes, ToBytes};
+
+use bytes::{BufMut, BytesMut};
+use std::io::{self, Read, Write};
+
+/// The minimum size of a payload to be compressed, in bytes.
+pub const COMPRESSION_THRESHOLD: usize = 4 * 1024; // 4 KiB
+/// The zstd compression level, which favors speed over ratio.
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// The tag of a frame whose payload is not compressed.
+const UNCOMPRESSED_FRAME: u8 = 0;
+/// The tag of a frame whose payload is compressed with zstd.
+const ZSTD_FRAME: u8 = 1;
+
+/// The capabilities that a node advertises in its challenge response.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Capabilities(u8);
+
+impl Capabilities {
+    /// The node accepts compressed frames.
+    pub const COMPRESSION: Self = Self(1);
+
+    /// Returns the capabilities with no flags set.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Returns the capabilities supported by this node.
+    pub const fn supported() -> Self {
+        Self::COMPRESSION
+    }
+
+    /// Returns the flags of the capabilities.
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Returns `true` if all the flags of `other` are set.
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl ToBytes for Capabilities {
+    fn write_le<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.0.write_le(writer)
+    }
+}
+
+impl FromBytes for Capabilities {
+    fn read_le<R: Read>(reader: R) -> io::Result<Self> {
+        // Note: Unknown flags are kept, so that newer peers may advertise more capabilities.
+        Ok(Self(u8::read_le(reader)?))
+    }
+}
+
+/// The compression of a connection, in each direction.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Compression {
+    /// Whether the outbound frames are tagged, and compressed above the threshold.
+    pub outbound: bool,
+    /// Whether the inbound frames are tagged, and may be compressed.
+    pub inbound: bool,
+}
+
+impl Compression {
+    /// Returns the compression of a connection, given the capabilities advertised by this node and by the peer.
+    ///
+    /// A node only compresses the frames it sends to a peer that advertised compression,
+    /// so each direction is enabled by the receiver.
+    pub const fn new(ours: Capabilities, theirs: Capabilities) -> Self {
+        Self { outbound: theirs.contains(Capabilities::COMPRESSION), inbound: ours.contains(Capabilities::COMPRESSION) }
+    }
+}
+
+/// Writes the given payload as a tagged frame into the destination.
+/// The payload is compressed if it is above the threshold, and if compression makes it smaller.
+///
+/// Returns the compression ratio, if the payload was compressed.
+pub fn compress_frame(payload: &[u8], dst: &mut BytesMut) -> io::Result<Option<f64>> {
+    if payload.len() >= COMPRESSION_THRESHOLD {
+        let compressed = zstd::bulk::compress(payload, COMPRESSION_LEVEL)?;
+        if compressed.len() < payload.len() {
+            dst.reserve(1 + compressed.len());
+            dst.put_u8(ZSTD_FRAME);
+            dst.put_slice(&compressed);
+            return Ok(Some(payload.len() as f64 / compressed.len() as f64));
+        }
+    }
+    dst.reserve(1 + payload.len());
+    dst.put_u8(UNCOMPRESSED_FRAME);
+    dst.put_slice(payload);
+    Ok(None)
+}
+
+/// Returns the payload of the given tagged frame, decompressing it if needed.
+/// Returns an error if the decompressed payload exceeds the given maximum size.
+pub fn decompress_frame(mut frame: BytesMut, max_size: usize) -> io::Result<BytesMut> {
+    let invalid_data = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+    if frame.is_empty() {
+        return Err(invalid_data("Received an empty frame".to_string()));
+    }
+    match frame.split_to(1)[0] {
+        UNCOMPRESSED_FRAME => Ok(frame),
+        ZSTD_FRAME => {
+            // Note: The decoder is bounded, so that a small frame cannot decompress into an arbitrarily large payload.
+            let mut payload = BytesMut::new().writer();
+            let decoder = zstd::stream::read::Decoder::new(&frame[..])?;
+            io::copy(&mut decoder.take(max_size as u64 + 1), &mut payload)?;
+            let payload = payload.into_inner();
+            match payload.len() <= max_size {
+                true => Ok(payload),
+                false => Err(invalid_data(format!("Decompressed frame exceeds the maximum of {max_size} bytes"))),
+            }
+        }
+        tag => Err(invalid_data(format!("Unknown frame tag '{tag}'"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compression_negotiation() {
+        let none = Capabilities::empty();
+        let all = Capabilities::supported();
+
+        assert_eq!(Compression::new(all, all), Compression { outbound: true, inbound: true });
+        assert_eq!(Compression::new(all, none), Compression { outbound: false, inbound: true });
+        assert_eq!(Compression::new(none, all), Compression { outbound: true, inbound: false });
+        assert_eq!(Compression::new(none, none), Compression::default());
+    }
+
+    #[test]
+    fn test_frame_roundtrip() {
+        // Ensure small payloads are sent uncompressed, and large payloads are compressed.
+        for (payload, is_compressed) in [(vec![7u8; 10], false), (vec![7u8; 10 * COMPRESSION_THRESHOLD], true)] {
+            let mut frame = BytesMut::new();
+            assert_eq!(compress_frame(&payload, &mut frame).unwrap().is_some(), is_compressed);
+            assert_eq!(frame[0], if is_compressed { ZSTD_FRAME } else { UNCOMPRESSED_FRAME });
+            assert_eq!(&decompress_frame(frame, payload.len()).unwrap()[..], &payload[..]);
+        }
+    }
+
+    #[test]
+    fn test_incompressible_payload() {
+        // Ensure a payload that does not shrink is sent uncompressed.
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        let payload = (0..2 * COMPRESSION_THRESHOLD)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect::<Vec<_>>();
+        let mut frame = BytesMut::new();
+        assert!(compress_frame(&payload, &mut frame).unwrap().is_none());
+        assert_eq!(&decompress_frame(frame, payload.len()).unwrap()[..], &payload[..]);
+    }
+
+    #[test]
+    fn test_decompression_limit() {
+        let payload = vec![0u8; 100 * COMPRESSION_THRESHOLD];
+        let mut frame = BytesMut::new();
+        assert!(compress_frame(&payload, &mut frame).unwrap().is_some());
+        // Ensure a frame that decompresses beyond the maximum size is rejected.
+        assert!(decompress_frame(frame.clone(), payload.len() - 1).is_err());
+        assert!(decompress_frame(frame, payload.len()).is_ok());
+        // Ensure invalid frames are rejected.
+        assert!(decompress_frame(BytesMut::new(), payload.len()).is_err());
+        assert!(decompress_frame(BytesMut::from(&[2u8, 0][..]), payload.len()).is_err());
+    }
+}
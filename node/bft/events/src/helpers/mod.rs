@@ -12,5 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod capture;
+pub use capture::*;
+
 mod codec;
 pub use codec::*;
+
+mod compression;
+pub use compression::*;
+
+
+mod session;
+pub use session::*;
@@ -0,0 +1,183 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::BytesMut;
+use snow::{Builder, HandshakeState, StatelessTransportState};
+use std::{io, sync::Arc};
+
+/// The Noise protocol used to encrypt the connections between validators.
+///
+/// The `NN` pattern only exchanges ephemeral keys; the session is authenticated by the challenge
+/// responses, in which each validator signs the Noise handshake with its Aleo account.
+pub const NOISE_PARAMS: &str = "Noise_NN_25519_ChaChaPoly_BLAKE2s";
+/// The maximum size of a session handshake message, in bytes.
+pub const MAX_SESSION_MESSAGE_SIZE: usize = 1024;
+
+/// The maximum size of a Noise message, in bytes.
+const NOISE_MAX_MESSAGE_SIZE: usize = 65535;
+/// The size of the authentication tag of a Noise message, in bytes.
+const NOISE_TAG_SIZE: usize = 16;
+/// The maximum size of the payload of a Noise message, in bytes.
+const NOISE_MAX_PAYLOAD_SIZE: usize = NOISE_MAX_MESSAGE_SIZE - NOISE_TAG_SIZE;
+
+/// Converts a Noise error into an I/O error.
+fn noise_error(error: snow::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Noise error: {error}"))
+}
+
+/// The state of a session handshake, which is carried by the challenge responses.
+pub struct SessionHandshake {
+    state: HandshakeState,
+}
+
+impl SessionHandshake {
+    /// Initializes the handshake for the side that sends the first session message.
+    pub fn initiator() -> io::Result<Self> {
+        let params = NOISE_PARAMS.parse().map_err(noise_error)?;
+        Ok(Self { state: Builder::new(params).build_initiator().map_err(noise_error)? })
+    }
+
+    /// Initializes the handshake for the side that receives the first session message.
+    pub fn responder() -> io::Result<Self> {
+        let params = NOISE_PARAMS.parse().map_err(noise_error)?;
+        Ok(Self { state: Builder::new(params).build_responder().map_err(noise_error)? })
+    }
+
+    /// Returns the next handshake message to send.
+    pub fn write_message(&mut self) -> io::Result<Vec<u8>> {
+        let mut message = vec![0u8; MAX_SESSION_MESSAGE_SIZE];
+        let length = self.state.write_message(&[], &mut message).map_err(noise_error)?;
+        message.truncate(length);
+        Ok(message)
+    }
+
+    /// Processes the given handshake message from the peer.
+    pub fn read_message(&mut self, message: &[u8]) -> io::Result<()> {
+        let mut payload = vec![0u8; MAX_SESSION_MESSAGE_SIZE];
+        self.state.read_message(message, &mut payload).map_err(noise_error)?;
+        Ok(())
+    }
+
+    /// Returns the hash of the handshake so far, which binds all the exchanged handshake messages.
+    pub fn handshake_hash(&self) -> &[u8] {
+        self.state.get_handshake_hash()
+    }
+
+    /// Returns the session, once the handshake is complete.
+    pub fn into_session(self) -> io::Result<Session> {
+        Ok(Session(Arc::new(self.state.into_stateless_transport_mode().map_err(noise_error)?)))
+    }
+}
+
+/// An established session, shared by the encoder and the decoder of a connection.
+#[derive(Clone)]
+pub struct Session(Arc<StatelessTransportState>);
+
+/// A cipher for one direction of a session.
+pub(crate) struct SessionCipher {
+    /// The session.
+    session: Session,
+    /// The nonce of the next message.
+    nonce: u64,
+}
+
+impl SessionCipher {
+    /// Initializes a new cipher for the given session.
+    pub(crate) fn new(session: Session) -> Self {
+        Self { session, nonce: 0 }
+    }
+
+    /// Returns the number of bytes added by encrypting a payload of the given size.
+    pub(crate) const fn overhead(payload_size: usize) -> usize {
+        payload_size.div_ceil(NOISE_MAX_PAYLOAD_SIZE) * NOISE_TAG_SIZE
+    }
+
+    /// Encrypts the given payload into the destination, in chunks of up to the maximum Noise message size.
+    pub(crate) fn encrypt(&mut self, payload: &[u8], dst: &mut BytesMut) -> io::Result<()> {
+        dst.reserve(payload.len() + Self::overhead(payload.len()));
+        let mut message = vec![0u8; NOISE_MAX_MESSAGE_SIZE];
+        for chunk in payload.chunks(NOISE_MAX_PAYLOAD_SIZE) {
+            let length = self.session.0.write_message(self.nonce, chunk, &mut message).map_err(noise_error)?;
+            self.nonce += 1;
+            dst.extend_from_slice(&message[..length]);
+        }
+        Ok(())
+    }
+
+    /// Decrypts the given chunks of Noise messages, returning the payload.
+    pub(crate) fn decrypt(&mut self, ciphertext: &[u8]) -> io::Result<BytesMut> {
+        let mut payload = BytesMut::with_capacity(ciphertext.len());
+        let mut chunk_payload = vec![0u8; NOISE_MAX_MESSAGE_SIZE];
+        for chunk in ciphertext.chunks(NOISE_MAX_MESSAGE_SIZE) {
+            let length = self.session.0.read_message(self.nonce, chunk, &mut chunk_payload).map_err(noise_error)?;
+            self.nonce += 1;
+            payload.extend_from_slice(&chunk_payload[..length]);
+        }
+        Ok(payload)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Performs a session handshake, returning the sessions of the initiator and the responder.
+    pub(crate) fn sample_sessions() -> (Session, Session) {
+        let mut initiator = SessionHandshake::initiator().unwrap();
+        let mut responder = SessionHandshake::responder().unwrap();
+        responder.read_message(&initiator.write_message().unwrap()).unwrap();
+        initiator.read_message(&responder.write_message().unwrap()).unwrap();
+        // Ensure both sides bound the same handshake.
+        assert_eq!(initiator.handshake_hash(), responder.handshake_hash());
+        (initiator.into_session().unwrap(), responder.into_session().unwrap())
+    }
+
+    #[test]
+    fn test_session_roundtrip() {
+        let (initiator, responder) = sample_sessions();
+        let (mut encryptor, mut decryptor) = (SessionCipher::new(initiator), SessionCipher::new(responder));
+
+        // Ensure payloads of various sizes, including multi-chunk payloads, are recovered in order.
+        for size in [1, NOISE_MAX_PAYLOAD_SIZE, NOISE_MAX_PAYLOAD_SIZE + 1, 3 * NOISE_MAX_MESSAGE_SIZE] {
+            let payload = (0..size).map(|i| i as u8).collect::<Vec<_>>();
+            let mut ciphertext = BytesMut::new();
+            encryptor.encrypt(&payload, &mut ciphertext).unwrap();
+            assert_eq!(ciphertext.len(), size + SessionCipher::overhead(size));
+            assert_eq!(&decryptor.decrypt(&ciphertext).unwrap()[..], &payload[..]);
+        }
+    }
+
+    #[test]
+    fn test_session_rejects_tampering() {
+        let (initiator, responder) = sample_sessions();
+        let (mut encryptor, mut decryptor) = (SessionCipher::new(initiator), SessionCipher::new(responder));
+
+        let mut ciphertext = BytesMut::new();
+        encryptor.encrypt(b"transmission", &mut ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        assert!(decryptor.decrypt(&ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_session_rejects_replay() {
+        let (initiator, responder) = sample_sessions();
+        let (mut encryptor, mut decryptor) = (SessionCipher::new(initiator), SessionCipher::new(responder));
+
+        let mut ciphertext = BytesMut::new();
+        encryptor.encrypt(b"transmission", &mut ciphertext).unwrap();
+        assert!(decryptor.decrypt(&ciphertext).is_ok());
+        // Ensure the same message cannot be replayed, as the nonce has advanced.
+        assert!(decryptor.decrypt(&ciphertext).is_err());
+    }
+}
@@ -14,6 +14,9 @@
 
 #![forbid(unsafe_code)]
 
+mod address_record;
+pub use address_record::AddressRecord;
+
 mod batch_certified;
 pub use batch_certified::BatchCertified;
 
@@ -48,7 +51,7 @@ mod helpers;
 pub use helpers::*;
 
 mod primary_ping;
-pub use primary_ping::PrimaryPing;
+pub use primary_ping::{PingTiming, PrimaryPing};
 
 mod transmission_request;
 pub use transmission_request::TransmissionRequest;
@@ -65,6 +68,7 @@ pub use validators_response::ValidatorsResponse;
 mod worker_ping;
 pub use worker_ping::WorkerPing;
 
+use snarkos_account::SignRequest;
 use snarkos_node_sync_locators::BlockLocators;
 use snarkvm::{
     console::prelude::{error, FromBytes, Network, Read, ToBytes, Write},
@@ -116,8 +120,29 @@ impl<N: Network> From<DisconnectReason> for Event<N> {
 }
 
 impl<N: Network> Event<N> {
-    /// The version of the event protocol; it can be incremented in order to force users to update.
-    pub const VERSION: u32 = 7;
+    /// The first version of the event protocol that carries the signed address records in the validators response.
+    pub const ADDRESS_RECORDS_VERSION: u32 = 11;
+    /// The first version of the event protocol that advertises capabilities in the challenge response.
+    pub const CAPABILITIES_VERSION: u32 = 9;
+    /// The first version of the event protocol that encrypts the connections with a session.
+    pub const ENCRYPTED_TRANSPORT_VERSION: u32 = 8;
+    /// The minimum version of the event protocol that is accepted from peers.
+    ///
+    /// Note: Once the committee has upgraded, this should be raised to `ENCRYPTED_TRANSPORT_VERSION`,
+    /// so that connections can no longer be downgraded to plaintext.
+    pub const MINIMUM_VERSION: u32 = 7;
+    /// The first version of the event protocol that carries the ping timing in the primary ping.
+    pub const PING_TIMING_VERSION: u32 = 10;
+    /// The latest version of the event protocol; peers on older versions (down to `MINIMUM_VERSION`) are still
+    /// supported, so it can be incremented without disconnecting them.
+    pub const VERSION: u32 = 11;
+
+    /// Returns the version of the event protocol to use with a peer that supports up to the given version,
+    /// or `None` if the peer's version is outside of the supported range.
+    pub fn negotiate_version(peer_version: u32) -> Option<u32> {
+        let version = peer_version.min(Self::VERSION);
+        (version >= Self::MINIMUM_VERSION).then_some(version)
+    }
 
     /// Returns the event name.
     #[inline]
@@ -142,6 +167,59 @@ impl<N: Network> Event<N> {
         }
     }
 
+    /// Returns the event type, i.e. the name of the event variant, which is independent of its contents.
+    #[inline]
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::BatchPropose(..) => "BatchPropose",
+            Self::BatchSignature(..) => "BatchSignature",
+            Self::BatchCertified(..) => "BatchCertified",
+            Self::BlockRequest(..) => "BlockRequest",
+            Self::BlockResponse(..) => "BlockResponse",
+            Self::CertificateRequest(..) => "CertificateRequest",
+            Self::CertificateResponse(..) => "CertificateResponse",
+            Self::ChallengeRequest(..) => "ChallengeRequest",
+            Self::ChallengeResponse(..) => "ChallengeResponse",
+            Self::Disconnect(..) => "Disconnect",
+            Self::PrimaryPing(..) => "PrimaryPing",
+            Self::TransmissionRequest(..) => "TransmissionRequest",
+            Self::TransmissionResponse(..) => "TransmissionResponse",
+            Self::ValidatorsRequest(..) => "ValidatorsRequest",
+            Self::ValidatorsResponse(..) => "ValidatorsResponse",
+            Self::WorkerPing(..) => "WorkerPing",
+        }
+    }
+
+    /// Returns the first version of the event protocol that supports the event.
+    /// An event is only sent to the peers that negotiated this version or a newer one.
+    #[inline]
+    pub fn minimum_version(&self) -> u32 {
+        match self {
+            Self::BatchPropose(..)
+            | Self::BatchSignature(..)
+            | Self::BatchCertified(..)
+            | Self::BlockRequest(..)
+            | Self::BlockResponse(..)
+            | Self::CertificateRequest(..)
+            | Self::CertificateResponse(..)
+            | Self::ChallengeRequest(..)
+            | Self::ChallengeResponse(..)
+            | Self::Disconnect(..)
+            | Self::PrimaryPing(..)
+            | Self::TransmissionRequest(..)
+            | Self::TransmissionResponse(..)
+            | Self::ValidatorsRequest(..)
+            | Self::ValidatorsResponse(..)
+            | Self::WorkerPing(..) => Self::MINIMUM_VERSION,
+        }
+    }
+
+    /// Returns `true` if the event is supported by the given version of the event protocol.
+    #[inline]
+    pub fn is_supported_by(&self, version: u32) -> bool {
+        version >= self.minimum_version()
+    }
+
     /// Returns the event ID.
     #[inline]
     pub fn id(&self) -> u16 {
@@ -243,6 +321,17 @@ mod tests {
             format!("Unknown event ID")
         );
     }
+
+    #[test]
+    fn test_type_name() {
+        let single = Event::<CurrentNetwork>::BlockRequest(crate::BlockRequest { start_height: 0, end_height: 1 });
+        let range = Event::<CurrentNetwork>::BlockRequest(crate::BlockRequest { start_height: 0, end_height: 5 });
+        // Ensure the type name is independent of the event contents, unlike the event name.
+        assert_ne!(single.name(), range.name());
+        assert_eq!(single.type_name(), "BlockRequest");
+        assert_eq!(range.type_name(), "BlockRequest");
+        assert_eq!(Event::<CurrentNetwork>::from(crate::DisconnectReason::NoReasonGiven).type_name(), "Disconnect");
+    }
 }
 
 #[cfg(test)]
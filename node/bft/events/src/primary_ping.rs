@@ -19,6 +19,18 @@ pub struct PrimaryPing<N: Network> {
     pub version: u32,
     pub block_locators: BlockLocators<N>,
     pub primary_certificate: Data<BatchCertificate<N>>,
+    /// The timing of the ping, which is only sent to peers that support it.
+    pub timing: Option<PingTiming>,
+}
+
+/// The timing of a primary ping, used to measure the round-trip time between validators.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PingTiming {
+    /// The time at which the ping was sent, in milliseconds on the clock of the sender.
+    pub sent_at: u64,
+    /// The `sent_at` of the latest ping received from the recipient,
+    /// and the number of milliseconds elapsed since it was received.
+    pub echo: Option<(u64, u64)>,
 }
 
 impl<N: Network> PrimaryPing<N> {
@@ -28,7 +40,13 @@ impl<N: Network> PrimaryPing<N> {
         block_locators: BlockLocators<N>,
         primary_certificate: Data<BatchCertificate<N>>,
     ) -> Self {
-        Self { version, block_locators, primary_certificate }
+        Self { version, block_locators, primary_certificate, timing: None }
+    }
+
+    /// Sets the timing of the ping.
+    pub fn with_timing(mut self, timing: Option<PingTiming>) -> Self {
+        self.timing = timing;
+        self
     }
 }
 
@@ -55,6 +73,11 @@ impl<N: Network> ToBytes for PrimaryPing<N> {
         self.block_locators.write_le(&mut writer)?;
         // Write the primary certificate.
         self.primary_certificate.write_le(&mut writer)?;
+        // Write the timing, if it is set.
+        if let Some(timing) = &self.timing {
+            1u8.write_le(&mut writer)?;
+            timing.write_le(&mut writer)?;
+        }
 
         Ok(())
     }
@@ -68,15 +91,47 @@ impl<N: Network> FromBytes for PrimaryPing<N> {
         let block_locators = BlockLocators::read_le(&mut reader)?;
         // Read the primary certificate.
         let primary_certificate = Data::read_le(&mut reader)?;
+        // Read the timing, if there are bytes remaining.
+        let mut has_timing = [0u8; 1];
+        let timing = match reader.read(&mut has_timing)? {
+            0 => None,
+            _ => match has_timing[0] {
+                1 => Some(PingTiming::read_le(&mut reader)?),
+                flag => return Err(error(format!("Invalid ping timing flag '{flag}'"))),
+            },
+        };
 
         // Return the ping event.
-        Ok(Self::new(version, block_locators, primary_certificate))
+        Ok(Self::new(version, block_locators, primary_certificate).with_timing(timing))
+    }
+}
+
+impl ToBytes for PingTiming {
+    fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()> {
+        self.sent_at.write_le(&mut writer)?;
+        self.echo.is_some().write_le(&mut writer)?;
+        if let Some((echo_sent_at, echo_delay)) = self.echo {
+            echo_sent_at.write_le(&mut writer)?;
+            echo_delay.write_le(&mut writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromBytes for PingTiming {
+    fn read_le<R: Read>(mut reader: R) -> IoResult<Self> {
+        let sent_at = u64::read_le(&mut reader)?;
+        let echo = match bool::read_le(&mut reader)? {
+            true => Some((u64::read_le(&mut reader)?, u64::read_le(&mut reader)?)),
+            false => None,
+        };
+        Ok(Self { sent_at, echo })
     }
 }
 
 #[cfg(test)]
 pub mod prop_tests {
-    use crate::{certificate_response::prop_tests::any_batch_certificate, PrimaryPing};
+    use crate::{certificate_response::prop_tests::any_batch_certificate, PingTiming, PrimaryPing};
     use snarkos_node_sync_locators::{test_helpers::sample_block_locators, BlockLocators};
     use snarkvm::utilities::{FromBytes, ToBytes};
 
@@ -90,10 +145,16 @@ pub mod prop_tests {
         any::<u32>().prop_map(sample_block_locators).boxed()
     }
 
+    pub fn any_ping_timing() -> BoxedStrategy<Option<PingTiming>> {
+        any::<Option<(u64, Option<(u64, u64)>)>>()
+            .prop_map(|timing| timing.map(|(sent_at, echo)| PingTiming { sent_at, echo }))
+            .boxed()
+    }
+
     pub fn any_primary_ping() -> BoxedStrategy<PrimaryPing<CurrentNetwork>> {
-        (any::<u32>(), any_block_locators(), any_batch_certificate())
-            .prop_map(|(version, block_locators, batch_certificate)| {
-                PrimaryPing::from((version, block_locators, batch_certificate.clone()))
+        (any::<u32>(), any_block_locators(), any_batch_certificate(), any_ping_timing())
+            .prop_map(|(version, block_locators, batch_certificate, timing)| {
+                PrimaryPing::from((version, block_locators, batch_certificate.clone())).with_timing(timing)
             })
             .boxed()
     }
@@ -105,6 +166,7 @@ pub mod prop_tests {
         let decoded = PrimaryPing::<CurrentNetwork>::read_le(&mut bytes.into_inner().reader()).unwrap();
         assert_eq!(primary_ping.version, decoded.version);
         assert_eq!(primary_ping.block_locators, decoded.block_locators);
+        assert_eq!(primary_ping.timing, decoded.timing);
         assert_eq!(
             primary_ping.primary_certificate.deserialize_blocking().unwrap(),
             decoded.primary_certificate.deserialize_blocking().unwrap(),
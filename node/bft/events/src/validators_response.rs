@@ -17,6 +17,15 @@ use super::*;
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ValidatorsResponse<N: Network> {
     pub validators: IndexMap<SocketAddr, Address<N>>,
+    /// The signed address records of the validators, if the receiver is known to read them.
+    pub records: Vec<AddressRecord<N>>,
+}
+
+impl<N: Network> ValidatorsResponse<N> {
+    /// The maximum number of address records that can be sent in a single event.
+    pub const MAXIMUM_NUMBER_OF_RECORDS: usize = 200;
+    /// The maximum number of validators that can be sent in a single event.
+    pub const MAXIMUM_NUMBER_OF_VALIDATORS: usize = 200;
 }
 
 impl<N: Network> EventTrait for ValidatorsResponse<N> {
@@ -29,6 +38,13 @@ impl<N: Network> EventTrait for ValidatorsResponse<N> {
 
 impl<N: Network> ToBytes for ValidatorsResponse<N> {
     fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()> {
+        // Ensure the number of validators and address records is within the maximum.
+        if self.validators.len() > Self::MAXIMUM_NUMBER_OF_VALIDATORS {
+            return Err(error(format!("Too many validators: {}", self.validators.len())));
+        }
+        if self.records.len() > Self::MAXIMUM_NUMBER_OF_RECORDS {
+            return Err(error(format!("Too many address records: {}", self.records.len())));
+        }
         // Write the number of validators.
         u16::try_from(self.validators.len()).map_err(error)?.write_le(&mut writer)?;
         // Write the validators.
@@ -36,6 +52,13 @@ impl<N: Network> ToBytes for ValidatorsResponse<N> {
             socket_addr.write_le(&mut writer)?;
             address.write_le(&mut writer)?;
         }
+        // Write the address records, if there are any.
+        if !self.records.is_empty() {
+            u16::try_from(self.records.len()).map_err(error)?.write_le(&mut writer)?;
+            for record in &self.records {
+                record.write_le(&mut writer)?;
+            }
+        }
         Ok(())
     }
 }
@@ -44,6 +67,10 @@ impl<N: Network> FromBytes for ValidatorsResponse<N> {
     fn read_le<R: Read>(mut reader: R) -> IoResult<Self> {
         // Read the number of validators.
         let num_validators = u16::read_le(&mut reader)?;
+        // Ensure the number of validators is within the maximum.
+        if num_validators as usize > Self::MAXIMUM_NUMBER_OF_VALIDATORS {
+            return Err(error(format!("Too many validators: {num_validators}")));
+        }
         // Read the validators.
         let mut validators = IndexMap::with_capacity(num_validators as usize);
         for _ in 0..num_validators {
@@ -51,18 +78,40 @@ impl<N: Network> FromBytes for ValidatorsResponse<N> {
             let address = Address::<N>::read_le(&mut reader)?;
             validators.insert(socket_addr, address);
         }
-        Ok(Self { validators })
+        // Read the address records, if there are bytes remaining.
+        let mut num_records = [0u8; 2];
+        let num_records = match reader.read(&mut num_records)? {
+            0 => 0,
+            1 => {
+                reader.read_exact(&mut num_records[1..])?;
+                u16::from_le_bytes(num_records)
+            }
+            _ => u16::from_le_bytes(num_records),
+        };
+        // Ensure the number of address records is within the maximum.
+        if num_records as usize > Self::MAXIMUM_NUMBER_OF_RECORDS {
+            return Err(error(format!("Too many address records: {num_records}")));
+        }
+        let mut records = Vec::with_capacity(num_records as usize);
+        for _ in 0..num_records {
+            records.push(AddressRecord::read_le(&mut reader)?);
+        }
+        Ok(Self { validators, records })
     }
 }
 
 #[cfg(test)]
 pub mod prop_tests {
-    use crate::{challenge_request::prop_tests::any_valid_address, ValidatorsResponse};
+    use crate::{
+        address_record::prop_tests::any_address_record,
+        challenge_request::prop_tests::any_valid_address,
+        ValidatorsResponse,
+    };
 
     use bytes::{Buf, BufMut, BytesMut};
     use indexmap::IndexMap;
     use proptest::{
-        collection::hash_map,
+        collection::{hash_map, vec},
         prelude::{any, BoxedStrategy, Strategy},
     };
     use snarkvm::{
@@ -85,7 +134,9 @@ pub mod prop_tests {
     }
 
     pub fn any_validators_response() -> BoxedStrategy<ValidatorsResponse<CurrentNetwork>> {
-        any_index_map().prop_map(|map| ValidatorsResponse { validators: map }).boxed()
+        (any_index_map(), vec(any_address_record(), 0..3))
+            .prop_map(|(validators, records)| ValidatorsResponse { validators, records })
+            .boxed()
     }
 
     #[proptest]
@@ -97,4 +148,15 @@ pub mod prop_tests {
         let decoded = ValidatorsResponse::<CurrentNetwork>::read_le(&mut bytes.into_inner().reader()).unwrap();
         assert_eq![decoded, validators_response];
     }
+
+    #[test]
+    fn validators_response_too_many_validators() {
+        // Ensure an excessive number of validators is rejected before reading them.
+        let num_validators = ValidatorsResponse::<CurrentNetwork>::MAXIMUM_NUMBER_OF_VALIDATORS as u16 + 1;
+        assert!(ValidatorsResponse::<CurrentNetwork>::read_le(&num_validators.to_le_bytes()[..]).is_err());
+        // Ensure an excessive number of address records is rejected before reading them.
+        let num_records = ValidatorsResponse::<CurrentNetwork>::MAXIMUM_NUMBER_OF_RECORDS as u16 + 1;
+        let bytes = [0u16.to_le_bytes(), num_records.to_le_bytes()].concat();
+        assert!(ValidatorsResponse::<CurrentNetwork>::read_le(&bytes[..]).is_err());
+    }
 }
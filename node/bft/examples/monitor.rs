@@ -12,19 +12,65 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use axum::{routing::get, Router};
+use axum::{
+    extract::{Path, RawQuery},
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
 use std::net::SocketAddr;
 use tokio::net::TcpListener;
 use tower_http::services::{ServeDir, ServeFile};
 
 const SERVER_URL: &str = "127.0.0.1:6060";
+/// The base port of the node servers, as started by `simple_node`.
+const NODE_SERVER_BASE_PORT: u16 = 3000;
+
+/// Forwards the request to the server of the given node, and returns its response.
+async fn forward(node_id: u16, path: &str, query: Option<String>, content_type: &'static str) -> Response {
+    // Construct the URL of the node server.
+    let mut url = format!("http://127.0.0.1:{}{path}", NODE_SERVER_BASE_PORT + node_id);
+    if let Some(query) = query {
+        url.push_str(&format!("?{query}"));
+    }
+    // Send the request to the node.
+    let response = match reqwest::get(&url).await {
+        Ok(response) => response,
+        Err(error) => {
+            return (StatusCode::BAD_GATEWAY, format!("Failed to reach node {node_id} - {error}")).into_response()
+        }
+    };
+    // Forward the response of the node.
+    let status = StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    match response.text().await {
+        Ok(body) => (status, [(CONTENT_TYPE, content_type)], body).into_response(),
+        Err(error) => {
+            (StatusCode::BAD_GATEWAY, format!("Failed to read from node {node_id} - {error}")).into_response()
+        }
+    }
+}
+
+/// Returns a snapshot of the DAG of the given node as JSON.
+async fn get_dag(Path(node_id): Path<u16>, RawQuery(query): RawQuery) -> Response {
+    forward(node_id, "/dag", query, "application/json").await
+}
+
+/// Returns a snapshot of the DAG of the given node in the Graphviz DOT format.
+async fn get_dag_dot(Path(node_id): Path<u16>, RawQuery(query): RawQuery) -> Response {
+    forward(node_id, "/dag/dot", query, "text/vnd.graphviz").await
+}
 
 async fn start_server() {
     // Serve the 'assets/' directory.
     let serve_dir = ServeDir::new("assets").fallback(ServeFile::new("assets/index.html"));
 
     // Initialize the routes.
-    let router = Router::new().route("/", get(|| async { "Hello, World!" })).fallback_service(serve_dir);
+    let router = Router::new()
+        .route("/", get(|| async { "Hello, World!" }))
+        .route("/nodes/:id/dag", get(get_dag))
+        .route("/nodes/:id/dag/dot", get(get_dag_dot))
+        .fallback_service(serve_dir);
 
     // Run the server.
     println!("Starting server at '{SERVER_URL}'...");
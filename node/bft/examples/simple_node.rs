@@ -42,7 +42,7 @@ use snarkvm::{
 use ::bytes::Bytes;
 use anyhow::{anyhow, ensure, Error, Result};
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Response},
     routing::get,
@@ -52,6 +52,7 @@ use axum_extra::response::ErasedJson;
 use clap::{Parser, ValueEnum};
 use indexmap::IndexMap;
 use rand::{CryptoRng, Rng, SeedableRng};
+use serde::Deserialize;
 use std::{
     collections::HashMap,
     net::SocketAddr,
@@ -139,7 +140,8 @@ pub async fn start_bft(
     // Initialize the consensus receiver handler.
     consensus_handler(consensus_receiver);
     // Initialize the BFT instance.
-    let mut bft = BFT::<CurrentNetwork>::new(account, storage, ledger, ip, &trusted_validators, dev)?;
+    let mut bft =
+        BFT::<CurrentNetwork>::new(account, storage, ledger, ip, &trusted_validators, dev, Default::default())?;
     // Run the BFT instance.
     bft.run(Some(consensus_sender), sender.clone(), receiver).await?;
     // Retrieve the BFT's primary.
@@ -176,7 +178,8 @@ pub async fn start_primary(
     // Initialize the trusted validators.
     let trusted_validators = trusted_validators(node_id, num_nodes, peers);
     // Initialize the primary instance.
-    let mut primary = Primary::<CurrentNetwork>::new(account, storage, ledger, ip, &trusted_validators, dev)?;
+    let mut primary =
+        Primary::<CurrentNetwork>::new(account, storage, ledger, ip, &trusted_validators, dev, Default::default())?;
     // Run the primary instance.
     primary.run(None, sender.clone(), receiver).await?;
     // Handle OS signals.
@@ -192,7 +195,7 @@ fn create_ledger(
     committee: Committee<snarkvm::prelude::MainnetV0>,
     node_id: u16,
 ) -> Arc<TranslucentLedgerService<snarkvm::prelude::MainnetV0, ConsensusMemory<snarkvm::prelude::MainnetV0>>> {
-    let gen_key = account.private_key();
+    let gen_key = account.private_key().unwrap();
     let public_balance_per_validator =
         (CurrentNetwork::STARTING_SUPPLY - (num_nodes as u64) * MIN_VALIDATOR_STAKE) / (num_nodes as u64);
     let mut balances = IndexMap::<Address<CurrentNetwork>, u64>::new();
@@ -473,6 +476,38 @@ async fn get_certificates_for_round(
     Ok(ErasedJson::pretty(node.primary.storage().get_certificates_for_round(round)))
 }
 
+/// The number of rounds to export from the DAG, if no range is given.
+const DEFAULT_DAG_EXPORT_ROUNDS: u64 = 10;
+
+/// The `get_dag` query object.
+#[derive(Deserialize)]
+struct DagRange {
+    /// The starting round (inclusive).
+    start: Option<u64>,
+    /// The ending round (inclusive).
+    end: Option<u64>,
+}
+
+/// Returns a snapshot of the DAG for the given rounds, defaulting to the most recent rounds.
+fn export_dag(node: &NodeState, range: DagRange) -> Result<snarkos_node_bft::helpers::DAGExport<CurrentNetwork>> {
+    let Some(bft) = &node.bft else {
+        return Err(anyhow!("BFT is not enabled"));
+    };
+    let end = range.end.unwrap_or_else(|| node.primary.current_round());
+    let start = range.start.unwrap_or_else(|| end.saturating_sub(DEFAULT_DAG_EXPORT_ROUNDS - 1));
+    bft.export_dag(start, end)
+}
+
+/// Returns a snapshot of the DAG as JSON.
+async fn get_dag(State(node): State<NodeState>, Query(range): Query<DagRange>) -> Result<ErasedJson, RestError> {
+    Ok(ErasedJson::pretty(export_dag(&node, range)?))
+}
+
+/// Returns a snapshot of the DAG in the Graphviz DOT format.
+async fn get_dag_dot(State(node): State<NodeState>, Query(range): Query<DagRange>) -> Result<String, RestError> {
+    Ok(export_dag(&node, range)?.to_dot())
+}
+
 /// Starts up a local server for monitoring the node.
 async fn start_server(bft: Option<BFT<CurrentNetwork>>, primary: Primary<CurrentNetwork>, node_id: u16) {
     // Initialize the routes.
@@ -481,6 +516,8 @@ async fn start_server(bft: Option<BFT<CurrentNetwork>>, primary: Primary<Current
         .route("/leader", get(get_leader))
         .route("/round/current", get(get_current_round))
         .route("/certificates/:round", get(get_certificates_for_round))
+        .route("/dag", get(get_dag))
+        .route("/dag/dot", get(get_dag_dot))
         // Pass in the `NodeState` to access state.
         .with_state(NodeState { bft, primary });
 
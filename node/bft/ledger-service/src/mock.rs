@@ -33,12 +33,19 @@ use tracing::*;
 pub struct MockLedgerService<N: Network> {
     committee: Committee<N>,
     height_to_round_and_hash: Mutex<BTreeMap<u32, (u64, N::BlockHash)>>,
+    genesis: Option<Block<N>>,
 }
 
 impl<N: Network> MockLedgerService<N> {
     /// Initializes a new mock ledger service.
     pub fn new(committee: Committee<N>) -> Self {
-        Self { committee, height_to_round_and_hash: Default::default() }
+        Self { committee, height_to_round_and_hash: Default::default(), genesis: None }
+    }
+
+    /// Initializes a new mock ledger service, which holds the given genesis block.
+    pub fn new_with_genesis(committee: Committee<N>, genesis: Block<N>) -> Self {
+        let height_to_hash = BTreeMap::from([(0, (genesis.round(), genesis.hash()))]);
+        Self { committee, height_to_round_and_hash: Mutex::new(height_to_hash), genesis: Some(genesis) }
     }
 
     /// Initializes a new mock ledger service at the specified height.
@@ -47,7 +54,7 @@ impl<N: Network> MockLedgerService<N> {
         for i in 0..=height {
             height_to_hash.insert(i, (i as u64 * 2, Field::<N>::from_u32(i).into()));
         }
-        Self { committee, height_to_round_and_hash: Mutex::new(height_to_hash) }
+        Self { committee, height_to_round_and_hash: Mutex::new(height_to_hash), genesis: None }
     }
 }
 
@@ -63,9 +70,12 @@ impl<N: Network> LedgerService<N> for MockLedgerService<N> {
         self.height_to_round_and_hash.lock().last_key_value().map(|(height, _)| *height).unwrap_or(0)
     }
 
-    /// Returns the latest block in the ledger.
+    /// Returns the latest block in the ledger, if it is the genesis block.
     fn latest_block(&self) -> Block<N> {
-        unreachable!("MockLedgerService does not support latest_block")
+        match (&self.genesis, self.latest_block_height()) {
+            (Some(genesis), 0) => genesis.clone(),
+            _ => unreachable!("MockLedgerService does not support latest_block"),
+        }
     }
 
     /// Returns the latest restrictions ID in the ledger.
@@ -120,15 +130,18 @@ impl<N: Network> LedgerService<N> for MockLedgerService<N> {
         }
     }
 
-    /// Returns the block for the given block height.
-    fn get_block(&self, _height: u32) -> Result<Block<N>> {
-        unreachable!("MockLedgerService does not support get_block")
+    /// Returns the block for the given block height, if it is the genesis block.
+    fn get_block(&self, height: u32) -> Result<Block<N>> {
+        match (&self.genesis, height) {
+            (Some(genesis), 0) => Ok(genesis.clone()),
+            _ => unreachable!("MockLedgerService does not support get_block"),
+        }
     }
 
-    /// Returns the blocks in the given block range.
+    /// Returns the blocks in the given block range, if it only contains the genesis block.
     /// The range is inclusive of the start and exclusive of the end.
-    fn get_blocks(&self, _heights: Range<u32>) -> Result<Vec<Block<N>>> {
-        unreachable!("MockLedgerService does not support get_blocks")
+    fn get_blocks(&self, heights: Range<u32>) -> Result<Vec<Block<N>>> {
+        heights.map(|height| self.get_block(height)).collect()
     }
 
     /// Returns the solution for the given solution ID.
@@ -19,6 +19,8 @@ use crate::{
         now,
         BFTReceiver,
         ConsensusSender,
+        DAGExport,
+        ExportedCertificate,
         PrimaryReceiver,
         PrimarySender,
         Storage,
@@ -29,6 +31,7 @@ use crate::{
 };
 use snarkos_account::Account;
 use snarkos_node_bft_ledger_service::LedgerService;
+use snarkos_node_tcp::Config;
 use snarkvm::{
     console::account::Address,
     ledger::{
@@ -84,9 +87,10 @@ impl<N: Network> BFT<N> {
         ip: Option<SocketAddr>,
         trusted_validators: &[SocketAddr],
         dev: Option<u16>,
+        tcp_config: Config,
     ) -> Result<Self> {
         Ok(Self {
-            primary: Primary::new(account, storage, ledger, ip, trusted_validators, dev)?,
+            primary: Primary::new(account, storage, ledger, ip, trusted_validators, dev, tcp_config)?,
             dag: Default::default(),
             leader_certificate: Default::default(),
             leader_certificate_timer: Default::default(),
@@ -149,6 +153,49 @@ impl<N: Network> BFT<N> {
     }
 }
 
+impl<N: Network> BFT<N> {
+    /// Returns a snapshot of the DAG and storage certificates for the given rounds (inclusive), for debugging purposes.
+    pub fn export_dag(&self, start_round: u64, end_round: u64) -> Result<DAGExport<N>> {
+        // Ensure the round range is valid.
+        ensure!(start_round <= end_round, "Invalid round range ({start_round} > {end_round})");
+        // Ensure the round range is bounded.
+        let max_rounds = self.storage().max_gc_rounds();
+        ensure!(end_round - start_round < max_rounds, "Cannot export more than {max_rounds} rounds of the DAG");
+
+        // Retrieve the last committed round.
+        let last_committed_round = self.dag.read().last_committed_round();
+        // Initialize a list for the exported certificates.
+        let mut certificates = Vec::new();
+        for round in start_round..=end_round {
+            // Initialize a map for the exported certificates of this round.
+            let mut exported = IndexMap::<Field<N>, ExportedCertificate<N>>::new();
+            // Retrieve the certificates from storage.
+            for certificate in self.storage().get_certificates_for_round(round) {
+                let entry = exported.entry(certificate.id()).or_insert_with(|| ExportedCertificate::new(&certificate));
+                entry.in_storage = true;
+            }
+            // Retrieve the certificates from the DAG.
+            for certificate in self.dag.read().get_certificates_for_round(round).unwrap_or_default().into_values() {
+                let entry = exported.entry(certificate.id()).or_insert_with(|| ExportedCertificate::new(&certificate));
+                entry.in_dag = true;
+            }
+            // Determine the leader of the round, if the round is even.
+            let leader = match round % 2 == 0 && round > 0 {
+                true => self.ledger().get_committee_lookback_for_round(round).and_then(|c| c.get_leader(round)).ok(),
+                false => None,
+            };
+            // Update the committed and leader flags.
+            for certificate in exported.values_mut() {
+                certificate.is_committed = self.dag.read().is_recently_committed(round, certificate.id)
+                    || self.ledger().contains_certificate(&certificate.id).unwrap_or(false);
+                certificate.is_leader = leader == Some(certificate.author);
+            }
+            certificates.extend(exported.into_values());
+        }
+        Ok(DAGExport { start_round, end_round, last_committed_round, certificates })
+    }
+}
+
 impl<N: Network> BFT<N> {
     /// Returns the number of unconfirmed transmissions.
     pub fn num_unconfirmed_transmissions(&self) -> usize {
@@ -322,7 +369,13 @@ impl<N: Network> BFT<N> {
         let leader_certificate = current_certificates.iter().find(|certificate| certificate.author() == leader);
         *self.leader_certificate.write() = leader_certificate.cloned();
 
-        self.is_even_round_ready_for_next_round(current_certificates, committee_lookback, current_round)
+        // Determine if the BFT is ready to update to the next round.
+        let is_ready = self.is_even_round_ready_for_next_round(current_certificates, committee_lookback, current_round);
+        // If the round is advancing without the leader certificate, record the leader as skipped.
+        if is_ready && self.leader_certificate.read().is_none() {
+            self.primary.participation().record_leader_skipped(current_round, leader);
+        }
+        is_ready
     }
 
     /// Returns 'true' if the quorum threshold `(2f + 1)` is reached for this round under one of the following conditions:
@@ -588,6 +641,8 @@ impl<N: Network> BFT<N> {
         for leader_certificate in leader_certificates.into_iter().rev() {
             // Retrieve the leader certificate round.
             let leader_round = leader_certificate.round();
+            // Retrieve the leader certificate author.
+            let leader_certificate_author = leader_certificate.author();
             // Compute the commit subdag.
             let commit_subdag = match self.order_dag_with_dfs::<ALLOW_LEDGER_ACCESS>(leader_certificate) {
                 Ok(subdag) => subdag,
@@ -703,6 +758,12 @@ impl<N: Network> BFT<N> {
             for certificate in commit_subdag.values().flatten() {
                 dag_write.commit(certificate, self.storage().max_gc_rounds());
             }
+            drop(dag_write);
+
+            // Record the committed leader certificate in the participation statistics.
+            if !IS_SYNCING {
+                self.primary.participation().record_leader_committed(leader_round, leader_certificate_author);
+            }
         }
 
         // Perform garbage collection based on the latest committed leader round.
@@ -862,7 +923,7 @@ impl<N: Network> BFT<N> {
     /// This method commits all the certificates into the DAG.
     /// Note that there is no need to insert the certificates into the DAG, because these certificates
     /// already exist in the ledger and therefore do not need to be re-ordered into future committed subdags.
-    async fn sync_bft_dag_at_bootup(&self, certificates: Vec<BatchCertificate<N>>) {
+    pub(crate) async fn sync_bft_dag_at_bootup(&self, certificates: Vec<BatchCertificate<N>>) {
         // Acquire the BFT write lock.
         let mut dag = self.dag.write();
 
@@ -964,7 +1025,7 @@ mod tests {
         // Initialize the account.
         let account = Account::new(rng)?;
         // Initialize the BFT.
-        let bft = BFT::new(account.clone(), storage.clone(), ledger.clone(), None, &[], None)?;
+        let bft = BFT::new(account.clone(), storage.clone(), ledger.clone(), None, &[], None, Default::default())?;
         assert!(bft.is_timer_expired());
         // Ensure this call succeeds on an odd round.
         let result = bft.is_leader_quorum_or_nonleaders_available(1);
@@ -999,7 +1060,7 @@ mod tests {
         assert_eq!(storage.max_gc_rounds(), 10);
 
         // Initialize the BFT.
-        let bft = BFT::new(account, storage, ledger, None, &[], None)?;
+        let bft = BFT::new(account, storage, ledger, None, &[], None, Default::default())?;
         assert!(bft.is_timer_expired()); // 0 + 5 < now()
 
         // Store is at round 1, and we are checking for round 2.
@@ -1021,7 +1082,7 @@ mod tests {
         assert_eq!(storage.max_gc_rounds(), 10);
 
         // Initialize the BFT.
-        let bft = BFT::new(account, storage, ledger, None, &[], None)?;
+        let bft = BFT::new(account, storage, ledger, None, &[], None, Default::default())?;
         assert!(bft.is_timer_expired()); // 0 + 5 < now()
 
         // Ensure this call fails on an even round.
@@ -1061,7 +1122,7 @@ mod tests {
         // Initialize the account.
         let account = Account::new(rng)?;
         // Initialize the BFT.
-        let bft = BFT::new(account.clone(), storage.clone(), ledger.clone(), None, &[], None)?;
+        let bft = BFT::new(account.clone(), storage.clone(), ledger.clone(), None, &[], None, Default::default())?;
         // Set the leader certificate.
         let leader_certificate = sample_batch_certificate_for_round(2, rng);
         *bft.leader_certificate.write() = Some(leader_certificate);
@@ -1073,7 +1134,8 @@ mod tests {
         assert!(result);
 
         // Initialize a new BFT.
-        let bft_timer = BFT::new(account.clone(), storage.clone(), ledger.clone(), None, &[], None)?;
+        let bft_timer =
+            BFT::new(account.clone(), storage.clone(), ledger.clone(), None, &[], None, Default::default())?;
         // If the leader certificate is not set and the timer has not expired, we are not ready for the next round.
         let result = bft_timer.is_even_round_ready_for_next_round(certificates.clone(), committee.clone(), 2);
         if !bft_timer.is_timer_expired() {
@@ -1104,7 +1166,7 @@ mod tests {
         assert_eq!(storage.max_gc_rounds(), 10);
 
         // Initialize the BFT.
-        let bft = BFT::new(account, storage, ledger, None, &[], None)?;
+        let bft = BFT::new(account, storage, ledger, None, &[], None, Default::default())?;
 
         // Ensure this call fails on an odd round.
         let result = bft.update_leader_certificate_to_even_round(1);
@@ -1122,7 +1184,7 @@ mod tests {
         assert_eq!(storage.max_gc_rounds(), 10);
 
         // Initialize the BFT.
-        let bft = BFT::new(account, storage, ledger, None, &[], None)?;
+        let bft = BFT::new(account, storage, ledger, None, &[], None, Default::default())?;
 
         // Ensure this call succeeds on an even round.
         let result = bft.update_leader_certificate_to_even_round(6);
@@ -1174,7 +1236,7 @@ mod tests {
 
         // Initialize the BFT.
         let account = Account::new(rng)?;
-        let bft = BFT::new(account, storage.clone(), ledger, None, &[], None)?;
+        let bft = BFT::new(account, storage.clone(), ledger, None, &[], None, Default::default())?;
 
         // Set the leader certificate.
         *bft.leader_certificate.write() = Some(leader_certificate);
@@ -1212,7 +1274,7 @@ mod tests {
             // Initialize the storage.
             let storage = Storage::new(ledger.clone(), Arc::new(BFTMemoryService::new()), 1);
             // Initialize the BFT.
-            let bft = BFT::new(account.clone(), storage, ledger.clone(), None, &[], None)?;
+            let bft = BFT::new(account.clone(), storage, ledger.clone(), None, &[], None, Default::default())?;
 
             // Insert a mock DAG in the BFT.
             *bft.dag.write() = crate::helpers::dag::test_helpers::mock_dag_with_modified_last_committed_round(3);
@@ -1242,7 +1304,7 @@ mod tests {
             // Initialize the storage.
             let storage = Storage::new(ledger.clone(), Arc::new(BFTMemoryService::new()), 1);
             // Initialize the BFT.
-            let bft = BFT::new(account, storage, ledger, None, &[], None)?;
+            let bft = BFT::new(account, storage, ledger, None, &[], None, Default::default())?;
 
             // Insert a mock DAG in the BFT.
             *bft.dag.write() = crate::helpers::dag::test_helpers::mock_dag_with_modified_last_committed_round(2);
@@ -1300,7 +1362,7 @@ mod tests {
         /* Test missing previous certificate. */
 
         // Initialize the BFT.
-        let bft = BFT::new(account, storage, ledger, None, &[], None)?;
+        let bft = BFT::new(account, storage, ledger, None, &[], None, Default::default())?;
 
         // The expected error message.
         let error_msg = format!(
@@ -1361,7 +1423,7 @@ mod tests {
 
         // Initialize the BFT.
         let account = Account::new(rng)?;
-        let bft = BFT::new(account, storage.clone(), ledger, None, &[], None)?;
+        let bft = BFT::new(account, storage.clone(), ledger, None, &[], None, Default::default())?;
         // Insert a mock DAG in the BFT.
         *bft.dag.write() = crate::helpers::dag::test_helpers::mock_dag_with_modified_last_committed_round(commit_round);
 
@@ -1427,7 +1489,7 @@ mod tests {
 
         // Initialize the BFT.
         let account = Account::new(rng)?;
-        let bft = BFT::new(account.clone(), storage, ledger.clone(), None, &[], None)?;
+        let bft = BFT::new(account.clone(), storage, ledger.clone(), None, &[], None, Default::default())?;
 
         // Insert a mock DAG in the BFT.
         *bft.dag.write() = crate::helpers::dag::test_helpers::mock_dag_with_modified_last_committed_round(commit_round);
@@ -1445,7 +1507,7 @@ mod tests {
         // Initialize a new instance of storage.
         let storage_2 = Storage::new(ledger.clone(), Arc::new(BFTMemoryService::new()), max_gc_rounds);
         // Initialize a new instance of BFT.
-        let bootup_bft = BFT::new(account, storage_2, ledger, None, &[], None)?;
+        let bootup_bft = BFT::new(account, storage_2, ledger, None, &[], None, Default::default())?;
 
         // Sync the BFT DAG at bootup.
         bootup_bft.sync_bft_dag_at_bootup(certificates.clone()).await;
@@ -1599,7 +1661,7 @@ mod tests {
 
         // Initialize the BFT without bootup.
         let account = Account::new(rng)?;
-        let bft = BFT::new(account.clone(), storage, ledger.clone(), None, &[], None)?;
+        let bft = BFT::new(account.clone(), storage, ledger.clone(), None, &[], None, Default::default())?;
 
         // Insert a mock DAG in the BFT without bootup.
         *bft.dag.write() = crate::helpers::dag::test_helpers::mock_dag_with_modified_last_committed_round(0);
@@ -1624,7 +1686,8 @@ mod tests {
         let bootup_storage = Storage::new(ledger.clone(), Arc::new(BFTMemoryService::new()), max_gc_rounds);
 
         // Initialize a new instance of BFT with bootup.
-        let bootup_bft = BFT::new(account, bootup_storage.clone(), ledger.clone(), None, &[], None)?;
+        let bootup_bft =
+            BFT::new(account, bootup_storage.clone(), ledger.clone(), None, &[], None, Default::default())?;
 
         // Sync the BFT DAG at bootup.
         bootup_bft.sync_bft_dag_at_bootup(pre_shutdown_certificates.clone()).await;
@@ -1802,7 +1865,8 @@ mod tests {
         }
         // Initialize the bootup BFT.
         let account = Account::new(rng)?;
-        let bootup_bft = BFT::new(account.clone(), storage.clone(), ledger.clone(), None, &[], None)?;
+        let bootup_bft =
+            BFT::new(account.clone(), storage.clone(), ledger.clone(), None, &[], None, Default::default())?;
         // Insert a mock DAG in the BFT without bootup.
         *bootup_bft.dag.write() = crate::helpers::dag::test_helpers::mock_dag_with_modified_last_committed_round(0);
         // Sync the BFT DAG at bootup.
@@ -12,9 +12,26 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "adversary")]
+use crate::helpers::AdversaryMode;
 use crate::{
     events::{EventCodec, PrimaryPing},
-    helpers::{assign_to_worker, Cache, PrimarySender, Resolver, Storage, SyncSender, WorkerSender},
+    helpers::{
+        address_book_path,
+        assign_to_worker,
+        now,
+        secure_rng,
+        AddressBook,
+        Cache,
+        CommitteeTracker,
+        PeerScores,
+        PrimarySender,
+        Resolver,
+        Storage,
+        SyncSender,
+        WorkerSender,
+        ADDRESS_RECORD_REFRESH_INTERVAL_IN_SECS,
+    },
     spawn_blocking,
     Worker,
     CONTEXT,
@@ -23,16 +40,21 @@ use crate::{
 };
 use snarkos_account::Account;
 use snarkos_node_bft_events::{
+    AddressRecord,
     BlockRequest,
     BlockResponse,
+    Capabilities,
     CertificateRequest,
     CertificateResponse,
     ChallengeRequest,
     ChallengeResponse,
+    Compression,
     DataBlocks,
     DisconnectReason,
     Event,
     EventTrait,
+    Session,
+    SessionHandshake,
     TransmissionRequest,
     TransmissionResponse,
     ValidatorsRequest,
@@ -43,11 +65,13 @@ use snarkos_node_sync::{communication_service::CommunicationService, MAX_BLOCKS_
 use snarkos_node_tcp::{
     is_bogon_ip,
     is_unspecified_or_broadcast_ip,
-    protocols::{Disconnect, Handshake, OnConnect, Reading, Writing},
+    protocols::{Disconnect, Handshake, MessagePriority, OnConnect, Reading, Writing},
+    BoxedStream,
     Config,
     Connection,
     ConnectionSide,
     Tcp,
+    TrafficStats,
     P2P,
 };
 use snarkvm::{
@@ -64,9 +88,16 @@ use futures::SinkExt;
 use indexmap::{IndexMap, IndexSet};
 use parking_lot::{Mutex, RwLock};
 use rand::seq::{IteratorRandom, SliceRandom};
-use std::{collections::HashSet, future::Future, io, net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    future::Future,
+    io,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 use tokio::{
-    net::TcpStream,
     sync::{oneshot, OnceCell},
     task::{self, JoinHandle},
 };
@@ -85,8 +116,6 @@ const RESTRICTED_INTERVAL: i64 = (MAX_CONNECTION_ATTEMPTS as u64 * MAX_BATCH_DEL
 
 /// The minimum number of validators to maintain a connection to.
 const MIN_CONNECTED_VALIDATORS: usize = 175;
-/// The maximum number of validators to send in a validators response event.
-const MAX_VALIDATORS_TO_SEND: usize = 200;
 
 /// Part of the Gateway API that deals with networking.
 /// This is a separate trait to allow for easier testing/mocking.
@@ -94,6 +123,7 @@ const MAX_VALIDATORS_TO_SEND: usize = 200;
 pub trait Transport<N: Network>: Send + Sync {
     async fn send(&self, peer_ip: SocketAddr, event: Event<N>) -> Option<oneshot::Receiver<io::Result<()>>>;
     fn broadcast(&self, event: Event<N>);
+    fn peer_scores(&self) -> Arc<PeerScores>;
 }
 
 #[derive(Clone)]
@@ -119,6 +149,22 @@ pub struct Gateway<N: Network> {
     /// prevent simultaneous "two-way" connections between two peers (i.e. both nodes simultaneously
     /// attempt to connect to each other). This set is used to prevent this from happening.
     connecting_peers: Arc<Mutex<IndexSet<SocketAddr>>>,
+    /// The encrypted sessions of the connected peers, keyed by connection address.
+    sessions: Arc<Mutex<HashMap<SocketAddr, Session>>>,
+    /// The compression of the connected peers, keyed by connection address.
+    compression: Arc<Mutex<HashMap<SocketAddr, Compression>>>,
+    /// The event protocol versions negotiated with the connected peers, keyed by connection address.
+    versions: Arc<Mutex<HashMap<SocketAddr, u32>>>,
+    /// The round-trip times and fetch success rates of the connected peers.
+    peer_scores: Arc<PeerScores>,
+    /// The tracker of the changes of the committee lookback.
+    committee_tracker: Arc<CommitteeTracker<N>>,
+    /// The signed address records of the validators.
+    address_book: Arc<AddressBook<N>>,
+    /// The path where the address book is persisted, if it is persisted.
+    address_book_path: Option<PathBuf>,
+    /// The latest address record that this node signed about itself.
+    address_record: Arc<RwLock<Option<AddressRecord<N>>>>,
     /// The primary sender.
     primary_sender: Arc<OnceCell<PrimarySender<N>>>,
     /// The worker senders.
@@ -129,6 +175,9 @@ pub struct Gateway<N: Network> {
     handles: Arc<Mutex<Vec<JoinHandle<()>>>>,
     /// The development mode.
     dev: Option<u16>,
+    /// The Byzantine behaviours of the node, for testing purposes only.
+    #[cfg(feature = "adversary")]
+    adversary_mode: Arc<RwLock<AdversaryMode>>,
 }
 
 impl<N: Network> Gateway<N> {
@@ -140,6 +189,7 @@ impl<N: Network> Gateway<N> {
         ip: Option<SocketAddr>,
         trusted_validators: &[SocketAddr],
         dev: Option<u16>,
+        tcp_config: Config,
     ) -> Result<Self> {
         // Initialize the gateway IP.
         let ip = match (ip, dev) {
@@ -148,7 +198,25 @@ impl<N: Network> Gateway<N> {
             (Some(ip), _) => ip,
         };
         // Initialize the TCP stack.
-        let tcp = Tcp::new(Config::new(ip, Committee::<N>::MAX_COMMITTEE_SIZE));
+        // Note: The listener address and the maximum number of connections of the given configuration are overridden.
+        let tcp = Tcp::new(Config {
+            listener_ip: Some(ip.ip()),
+            desired_listening_port: Some(ip.port()),
+            max_connections: Committee::<N>::MAX_COMMITTEE_SIZE,
+            ..tcp_config
+        });
+        // Initialize the committee tracker.
+        let committee_tracker = Arc::new(CommitteeTracker::new(account.address()));
+        // Load the address book of the validators, if it exists.
+        // Note: The address book is not persisted in the unit tests, so that they never touch the production file.
+        let address_book_path = (dev.is_some() || !cfg!(test)).then(|| address_book_path(N::ID, dev));
+        let address_book = match &address_book_path {
+            Some(path) if path.exists() => AddressBook::load(path).unwrap_or_else(|error| {
+                warn!("{error}");
+                AddressBook::new()
+            }),
+            _ => AddressBook::new(),
+        };
         // Return the gateway.
         Ok(Self {
             account,
@@ -160,11 +228,21 @@ impl<N: Network> Gateway<N> {
             trusted_validators: trusted_validators.iter().copied().collect(),
             connected_peers: Default::default(),
             connecting_peers: Default::default(),
+            sessions: Default::default(),
+            compression: Default::default(),
+            versions: Default::default(),
+            peer_scores: Default::default(),
+            committee_tracker,
+            address_book: Arc::new(address_book),
+            address_book_path,
+            address_record: Default::default(),
             primary_sender: Default::default(),
             worker_senders: Default::default(),
             sync_sender: Default::default(),
             handles: Default::default(),
             dev,
+            #[cfg(feature = "adversary")]
+            adversary_mode: Default::default(),
         })
     }
 
@@ -266,6 +344,19 @@ impl<N: Network> Gateway<N> {
         self.dev
     }
 
+    /// Returns the Byzantine behaviours of the node.
+    #[cfg(feature = "adversary")]
+    pub fn adversary_mode(&self) -> AdversaryMode {
+        *self.adversary_mode.read()
+    }
+
+    /// Sets the Byzantine behaviours of the node.
+    #[cfg(feature = "adversary")]
+    pub fn set_adversary_mode(&self, mode: AdversaryMode) {
+        warn!("Setting the adversary mode of the node to {mode:?}");
+        *self.adversary_mode.write() = mode;
+    }
+
     /// Returns the IP address of this node.
     pub fn local_ip(&self) -> SocketAddr {
         self.tcp.listening_addr().expect("The TCP listener is not enabled")
@@ -287,6 +378,29 @@ impl<N: Network> Gateway<N> {
         &self.resolver
     }
 
+    /// Returns the compression of the connection with the given peer IP.
+    pub fn compression(&self, peer_ip: SocketAddr) -> Compression {
+        self.resolver
+            .get_ambiguous(peer_ip)
+            .and_then(|peer_addr| self.compression.lock().get(&peer_addr).copied())
+            .unwrap_or_default()
+    }
+
+    /// Returns the round-trip times and fetch success rates of the connected peers.
+    pub fn peer_scores(&self) -> &Arc<PeerScores> {
+        &self.peer_scores
+    }
+
+    /// Returns the signed address records of the validators.
+    pub fn address_book(&self) -> &Arc<AddressBook<N>> {
+        &self.address_book
+    }
+
+    /// Returns the tracker of the changes of the committee lookback.
+    pub fn committee_tracker(&self) -> &Arc<CommitteeTracker<N>> {
+        &self.committee_tracker
+    }
+
     /// Returns the primary sender.
     pub fn primary_sender(&self) -> &PrimarySender<N> {
         self.primary_sender.get().expect("Primary sender not set in gateway")
@@ -373,6 +487,88 @@ impl<N: Network> Gateway<N> {
         }
     }
 
+    /// Returns `true` if this node should attempt to connect to the given validator.
+    fn is_connectable_validator(&self, validator_ip: SocketAddr, validator_address: Address<N>) -> bool {
+        if self.dev.is_some() {
+            // Ensure the validator IP is not this node.
+            if self.is_local_ip(validator_ip) {
+                return false;
+            }
+        } else {
+            // Ensure the validator IP is not this node and is well-formed.
+            if !self.is_valid_peer_ip(validator_ip) {
+                return false;
+            }
+        }
+        // Ensure the validator address is not this node.
+        if self.account.address() == validator_address {
+            return false;
+        }
+        // Ensure the validator IP is not already connected or connecting.
+        if self.is_connected_ip(validator_ip) || self.is_connecting_ip(validator_ip) {
+            return false;
+        }
+        // Ensure the validator address is not already connected.
+        if self.is_connected_address(validator_address) {
+            return false;
+        }
+        // Ensure the validator address is an authorized validator.
+        self.is_authorized_validator_address(validator_address)
+    }
+
+    /// Returns the address record of this node, signing a new one if the latest one is outdated.
+    ///
+    /// Returns `None` if this node listens on an unspecified IP, as the other validators could not connect to it.
+    /// Note: To publish its address record, a validator must set its listener IP (`--bft`) to its public IP.
+    fn address_record(&self) -> Option<AddressRecord<N>> {
+        let listener_ip = self.local_ip();
+        if is_unspecified_or_broadcast_ip(listener_ip.ip()) {
+            return None;
+        }
+        // Return the latest address record, if it is recent enough.
+        let timestamp = now();
+        if let Some(record) = self.address_record.read().as_ref() {
+            if record.listener_ip == listener_ip
+                && timestamp - record.timestamp < ADDRESS_RECORD_REFRESH_INTERVAL_IN_SECS
+            {
+                return Some(record.clone());
+            }
+        }
+        // Sign a new address record.
+        let request = AddressRecord::<N>::signing_request(self.account.address(), listener_ip, timestamp);
+        match self.account.sign_request(&request, &mut secure_rng()) {
+            Ok(signature) => {
+                let record = AddressRecord::new(self.account.address(), listener_ip, timestamp, signature);
+                *self.address_record.write() = Some(record.clone());
+                Some(record)
+            }
+            Err(error) => {
+                warn!("{CONTEXT} Failed to sign the address record of this node - {error}");
+                None
+            }
+        }
+    }
+
+    /// Inserts the given address record, received from the given peer, into the address book.
+    /// Returns `true` if the address record is valid and newer than the known address record of the validator.
+    fn insert_address_record(&self, peer_ip: SocketAddr, record: AddressRecord<N>) -> bool {
+        // Ensure the address record is not for this node.
+        if record.address == self.account.address() {
+            return false;
+        }
+        // Ensure the listener IP is well-formed, unless in development mode.
+        if self.dev.is_none() && !self.is_valid_peer_ip(record.listener_ip) {
+            return false;
+        }
+        match self.address_book.insert(record, |address| self.is_authorized_validator_address(address)) {
+            Ok(is_inserted) => is_inserted,
+            Err(error) => {
+                debug!("{CONTEXT} Skipping an address record from '{peer_ip}' - {error}");
+                false
+            }
+        }
+    }
+
     /// Returns the maximum number of connected peers.
     pub fn max_connected_peers(&self) -> usize {
         self.tcp.config().max_connections as usize
@@ -393,6 +589,23 @@ impl<N: Network> Gateway<N> {
         &self.connected_peers
     }
 
+    /// Returns the traffic of the connected peers, by event type.
+    pub fn connected_traffic(&self) -> Vec<(SocketAddr, BTreeMap<&'static str, TrafficStats>)> {
+        self.connected_peers
+            .read()
+            .iter()
+            .filter_map(|peer_ip| {
+                let peer_addr = self.resolver.get_ambiguous(*peer_ip)?;
+                Some((*peer_ip, self.tcp.known_peers().get(peer_addr)?.traffic()))
+            })
+            .collect()
+    }
+
+    /// Returns the traffic of all the connections, by event type.
+    pub fn traffic(&self) -> BTreeMap<&'static str, TrafficStats> {
+        self.tcp.stats().traffic()
+    }
+
     /// Attempts to connect to the given peer IP.
     pub fn connect(&self, peer_ip: SocketAddr) -> Option<JoinHandle<()>> {
         // Return early if the attempt is against the protocol rules.
@@ -472,6 +685,8 @@ impl<N: Network> Gateway<N> {
         self.resolver.insert_peer(peer_ip, peer_addr, address);
         // Add a transmission for this peer in the connected peers.
         self.connected_peers.write().insert(peer_ip);
+        // Start scoring the peer.
+        self.peer_scores.insert_peer(peer_ip);
         #[cfg(feature = "metrics")]
         self.update_metrics();
     }
@@ -484,6 +699,8 @@ impl<N: Network> Gateway<N> {
         self.resolver.insert_peer(peer_ip, peer_addr, address);
         // Add a transmission for this peer in the connected peers.
         self.connected_peers.write().insert(peer_ip);
+        // Start scoring the peer.
+        self.peer_scores.insert_peer(peer_ip);
     }
 
     /// Removes the connected peer and adds them to the candidate peers.
@@ -501,10 +718,31 @@ impl<N: Network> Gateway<N> {
         self.resolver.remove_peer(peer_ip);
         // Remove this peer from the connected peers, if it exists.
         self.connected_peers.write().shift_remove(&peer_ip);
+        // Stop scoring the peer.
+        self.peer_scores.remove_peer(peer_ip);
         #[cfg(feature = "metrics")]
         self.update_metrics();
     }
 
+    /// Broadcasts the given primary ping to all connected peers,
+    /// with the ping timing for each of the peers that support it.
+    pub fn broadcast_primary_ping(&self, ping: PrimaryPing<N>) {
+        // Ensure there are connected peers.
+        if self.number_of_connected_peers() > 0 {
+            let self_ = self.clone();
+            let connected_peers = self.connected_peers.read().clone();
+            tokio::spawn(async move {
+                // Iterate through all connected peers.
+                for peer_ip in connected_peers {
+                    // Set the ping timing for the peer.
+                    let ping = ping.clone().with_timing(self_.peer_scores.ping_timing(peer_ip));
+                    // Send the event to the peer.
+                    let _ = Transport::send(&self_, peer_ip, Event::PrimaryPing(ping)).await;
+                }
+            });
+        }
+    }
+
     /// Sends the given event to specified peer.
     ///
     /// This function returns as soon as the event is queued to be sent,
@@ -518,6 +756,12 @@ impl<N: Network> Gateway<N> {
         };
         // Retrieve the event name.
         let name = event.name();
+        // Ensure the event is supported by the version negotiated with the peer.
+        let version = self.versions.lock().get(&peer_addr).copied().unwrap_or(Event::<N>::VERSION);
+        if !event.is_supported_by(version) {
+            debug!("{CONTEXT} Skipping '{name}' to '{peer_ip}' (unsupported by version {version})");
+            return None;
+        }
         // Send the event to the peer.
         trace!("{CONTEXT} Sending '{name}' to '{peer_ip}'");
         let result = self.unicast(peer_addr, event);
@@ -666,13 +910,16 @@ impl<N: Network> Gateway<N> {
                 bail!("{CONTEXT} {:?}", disconnect.reason)
             }
             Event::PrimaryPing(ping) => {
-                let PrimaryPing { version, block_locators, primary_certificate } = ping;
+                let PrimaryPing { version, block_locators, primary_certificate, timing } = ping;
 
                 // Ensure the event version is not outdated.
-                if version < Event::<N>::VERSION {
+                if Event::<N>::negotiate_version(version).is_none() {
                     bail!("Dropping '{peer_ip}' on event version {version} (outdated)");
                 }
 
+                // Update the round-trip time of the peer.
+                self.peer_scores.process_ping(peer_ip, version >= Event::<N>::PING_TIMING_VERSION, timing);
+
                 // If a sync sender was provided, update the peer locators.
                 if let Some(sync_sender) = self.sync_sender.get() {
                     // Check the block locators are valid, and update the validators in the sync module.
@@ -686,6 +933,11 @@ impl<N: Network> Gateway<N> {
                 Ok(())
             }
             Event::TransmissionRequest(request) => {
+                // If the adversary mode refuses transmission requests, ignore the request.
+                #[cfg(feature = "adversary")]
+                if self.adversary_mode().refuse_transmission_requests {
+                    return Ok(());
+                }
                 // TODO (howardwu): Add rate limiting checks on this event, on a per-peer basis.
                 // Determine the worker ID.
                 let Ok(worker_id) = assign_to_worker(request.transmission_id, self.num_workers()) else {
@@ -723,30 +975,56 @@ impl<N: Network> Gateway<N> {
                     }
                 };
                 // Shuffle the connected peers.
-                connected_peers.shuffle(&mut rand::thread_rng());
+                connected_peers.shuffle(&mut secure_rng());
+                // Determine if the peer is able to read the address records.
+                let supports_address_records = self
+                    .resolver
+                    .get_ambiguous(peer_ip)
+                    .and_then(|peer_addr| self.versions.lock().get(&peer_addr).copied())
+                    .map_or(false, |version| version >= Event::<N>::ADDRESS_RECORDS_VERSION);
 
                 let self_ = self.clone();
                 tokio::spawn(async move {
                     // Initialize the validators.
-                    let mut validators = IndexMap::with_capacity(MAX_VALIDATORS_TO_SEND);
+                    let mut validators = IndexMap::with_capacity(ValidatorsResponse::<N>::MAXIMUM_NUMBER_OF_VALIDATORS);
                     // Iterate over the validators.
-                    for validator_ip in connected_peers.into_iter().take(MAX_VALIDATORS_TO_SEND) {
+                    for validator_ip in
+                        connected_peers.into_iter().take(ValidatorsResponse::<N>::MAXIMUM_NUMBER_OF_VALIDATORS)
+                    {
                         // Retrieve the validator address.
                         if let Some(validator_address) = self_.resolver.get_address(validator_ip) {
                             // Add the validator to the list of validators.
                             validators.insert(validator_ip, validator_address);
                         }
                     }
+                    // Initialize the address records, starting with the address record of this node.
+                    let records = match supports_address_records {
+                        true => self_
+                            .address_record()
+                            .into_iter()
+                            .chain(self_.address_book.records())
+                            .take(ValidatorsResponse::<N>::MAXIMUM_NUMBER_OF_RECORDS)
+                            .collect(),
+                        false => Vec::new(),
+                    };
                     // Send the validators response to the peer.
-                    let event = Event::ValidatorsResponse(ValidatorsResponse { validators });
+                    let event = Event::ValidatorsResponse(ValidatorsResponse { validators, records });
                     Transport::send(&self_, peer_ip, event).await;
                 });
                 Ok(())
             }
             Event::ValidatorsResponse(response) => {
-                let ValidatorsResponse { validators } = response;
+                let ValidatorsResponse { validators, records } = response;
                 // Ensure the number of validators is not too large.
-                ensure!(validators.len() <= MAX_VALIDATORS_TO_SEND, "{CONTEXT} Received too many validators");
+                ensure!(
+                    validators.len() <= ValidatorsResponse::<N>::MAXIMUM_NUMBER_OF_VALIDATORS,
+                    "{CONTEXT} Received too many validators"
+                );
+                // Ensure the number of address records is not too large.
+                ensure!(
+                    records.len() <= ValidatorsResponse::<N>::MAXIMUM_NUMBER_OF_RECORDS,
+                    "{CONTEXT} Received too many address records"
+                );
                 // Ensure the cache contains a validators request for this peer.
                 if !self.cache.contains_outbound_validators_request(peer_ip) {
                     bail!("{CONTEXT} Received validators response from '{peer_ip}' without a validators request")
@@ -754,45 +1032,30 @@ impl<N: Network> Gateway<N> {
                 // Decrement the number of validators requests for this peer.
                 self.cache.decrement_outbound_validators_requests(peer_ip);
 
-                // If the number of connected validators is less than the minimum, connect to more validators.
-                if self.number_of_connected_peers() < MIN_CONNECTED_VALIDATORS {
-                    // Attempt to connect to any validators that are not already connected.
-                    let self_ = self.clone();
-                    tokio::spawn(async move {
-                        for (validator_ip, validator_address) in validators {
-                            if self_.dev.is_some() {
-                                // Ensure the validator IP is not this node.
-                                if self_.is_local_ip(validator_ip) {
-                                    continue;
-                                }
-                            } else {
-                                // Ensure the validator IP is not this node and is well-formed.
-                                if !self_.is_valid_peer_ip(validator_ip) {
-                                    continue;
-                                }
-                            }
+                let self_ = self.clone();
+                tokio::spawn(async move {
+                    // Insert the valid address records into the address book.
+                    // Note: The signed listener IPs are preferred over the unsigned ones, when connecting.
+                    let mut candidates = IndexMap::with_capacity(records.len() + validators.len());
+                    for record in records {
+                        if self_.insert_address_record(peer_ip, record.clone()) {
+                            candidates.insert(record.listener_ip, record.address);
+                        }
+                    }
+                    for (validator_ip, validator_address) in validators {
+                        candidates.entry(validator_ip).or_insert(validator_address);
+                    }
 
-                            // Ensure the validator address is not this node.
-                            if self_.account.address() == validator_address {
-                                continue;
+                    // If the number of connected validators is less than the minimum, connect to more validators.
+                    if self_.number_of_connected_peers() < MIN_CONNECTED_VALIDATORS {
+                        // Attempt to connect to any validators that are not already connected.
+                        for (validator_ip, validator_address) in candidates {
+                            if self_.is_connectable_validator(validator_ip, validator_address) {
+                                self_.connect(validator_ip);
                             }
-                            // Ensure the validator IP is not already connected or connecting.
-                            if self_.is_connected_ip(validator_ip) || self_.is_connecting_ip(validator_ip) {
-                                continue;
-                            }
-                            // Ensure the validator address is not already connected.
-                            if self_.is_connected_address(validator_address) {
-                                continue;
-                            }
-                            // Ensure the validator address is an authorized validator.
-                            if !self_.is_authorized_validator_address(validator_address) {
-                                continue;
-                            }
-                            // Attempt to connect to the validator.
-                            self_.connect(validator_ip);
                         }
-                    });
-                }
+                    }
+                });
                 Ok(())
             }
             Event::WorkerPing(ping) => {
@@ -862,6 +1125,10 @@ impl<N: Network> Gateway<N> {
         self.handles.lock().iter().for_each(|handle| handle.abort());
         // Close the listener.
         self.tcp.shut_down().await;
+        // Store the address book.
+        if let Some(handle) = self.store_address_book() {
+            let _ = handle.await;
+        }
     }
 }
 
@@ -871,8 +1138,12 @@ impl<N: Network> Gateway<N> {
         self.log_connected_validators();
         // Keep the trusted validators connected.
         self.handle_trusted_validators();
+        // Track the changes of the committee lookback.
+        self.handle_committee_transitions();
         // Removes any validators that not in the current committee.
         self.handle_unauthorized_validators();
+        // Prune and store the address book.
+        self.handle_address_book();
         // If the number of connected validators is less than the minimum, send a `ValidatorsRequest`.
         self.handle_min_connected_validators();
     }
@@ -913,6 +1184,23 @@ impl<N: Network> Gateway<N> {
         }
     }
 
+    /// This function tracks the changes of the committee lookback, and of this node's membership in it.
+    fn handle_committee_transitions(&self) {
+        // Retrieve the current round.
+        let current_round = self.storage.current_round();
+        // Retrieve the committee lookback for the current round, and the latest committee.
+        let committees = self
+            .ledger
+            .get_committee_lookback_for_round(current_round)
+            .and_then(|committee_lookback| Ok((committee_lookback, self.ledger.current_committee()?)));
+        match committees {
+            Ok((committee_lookback, latest_committee)) => {
+                self.committee_tracker.update(current_round, &committee_lookback, &latest_committee);
+            }
+            Err(e) => debug!("{CONTEXT} Unable to track the committee for round {current_round} - {e}"),
+        }
+    }
+
     /// This function attempts to disconnect any validators that are not in the current committee.
     fn handle_unauthorized_validators(&self) {
         let self_ = self.clone();
@@ -932,11 +1220,38 @@ impl<N: Network> Gateway<N> {
         });
     }
 
-    /// This function sends a `ValidatorsRequest` to a random validator,
-    /// if the number of connected validators is less than the minimum.
+    /// This function removes the expired and unauthorized address records, and stores the address book if it changed.
+    fn handle_address_book(&self) {
+        self.address_book.prune(|address| self.is_authorized_validator_address(address));
+        self.store_address_book();
+    }
+
+    /// Stores the address book on a blocking thread, if it is persisted and changed since it was last stored.
+    fn store_address_book(&self) -> Option<JoinHandle<()>> {
+        let path = self.address_book_path.clone()?;
+        if !self.address_book.is_dirty() {
+            return None;
+        }
+        let address_book = self.address_book.clone();
+        Some(task::spawn_blocking(move || {
+            if let Err(error) = address_book.store(&path) {
+                warn!("{CONTEXT} {error}");
+            }
+        }))
+    }
+
+    /// This function connects to the validators in the address book, and sends a `ValidatorsRequest`
+    /// to a random validator, if the number of connected validators is less than the minimum.
     fn handle_min_connected_validators(&self) {
         // If the number of connected validators is less than the minimum, send a `ValidatorsRequest`.
         if self.number_of_connected_peers() < MIN_CONNECTED_VALIDATORS {
+            // Attempt to connect to the validators in the address book, which allows a restarted
+            // validator to reconnect to the committee without any trusted validators.
+            for record in self.address_book.records() {
+                if self.is_connectable_validator(record.listener_ip, record.address) {
+                    self.connect(record.listener_ip);
+                }
+            }
             // Retrieve the connected validators.
             let validators = self.connected_peers().read().clone();
             // If there are no validator IPs to connect to, return early.
@@ -944,7 +1259,7 @@ impl<N: Network> Gateway<N> {
                 return;
             }
             // Select a random validator IP.
-            if let Some(validator_ip) = validators.into_iter().choose(&mut rand::thread_rng()) {
+            if let Some(validator_ip) = validators.into_iter().choose(&mut secure_rng()) {
                 let self_ = self.clone();
                 tokio::spawn(async move {
                     // Increment the number of outbound validators requests for this validator.
@@ -1017,6 +1332,11 @@ impl<N: Network> Transport<N> for Gateway<N> {
             });
         }
     }
+
+    /// Returns the round-trip times and fetch success rates of the connected peers.
+    fn peer_scores(&self) -> Arc<PeerScores> {
+        self.peer_scores.clone()
+    }
 }
 
 impl<N: Network> P2P for Gateway<N> {
@@ -1039,8 +1359,21 @@ impl<N: Network> Reading for Gateway<N> {
 
     /// Creates a [`Decoder`] used to interpret messages from the network.
     /// The `side` param indicates the connection side **from the node's perspective**.
-    fn codec(&self, _peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        // If a session was established during the handshake, decrypt the messages.
+        // If the node advertised compression during the handshake, decompress the messages.
+        let codec = match self.sessions.lock().get(&peer_addr) {
+            Some(session) => EventCodec::encrypted(session.clone()),
+            None => Default::default(),
+        };
+        codec
+            .with_compression(self.compression.lock().get(&peer_addr).copied().unwrap_or_default())
+            .with_version(self.versions.lock().get(&peer_addr).copied().unwrap_or(Event::<N>::VERSION))
+    }
+
+    /// Returns the type of the given inbound event, to account for the traffic by event type.
+    fn message_type(&self, event: &Self::Message) -> Option<&'static str> {
+        Some(event.type_name())
     }
 
     /// Processes a message received from the network.
@@ -1074,8 +1407,35 @@ impl<N: Network> Writing for Gateway<N> {
 
     /// Creates an [`Encoder`] used to write the outbound messages to the target stream.
     /// The `side` parameter indicates the connection side **from the node's perspective**.
-    fn codec(&self, _peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        // If a session was established during the handshake, encrypt the messages.
+        // If the peer advertised compression during the handshake, compress the messages.
+        let codec = match self.sessions.lock().get(&peer_addr) {
+            Some(session) => EventCodec::encrypted(session.clone()),
+            None => Default::default(),
+        };
+        codec
+            .with_compression(self.compression.lock().get(&peer_addr).copied().unwrap_or_default())
+            .with_version(self.versions.lock().get(&peer_addr).copied().unwrap_or(Event::<N>::VERSION))
+    }
+
+    /// Returns the type of the given outbound event, to account for the traffic by event type.
+    fn message_type(&self, event: &Self::Message) -> Option<&'static str> {
+        Some(event.type_name())
+    }
+
+    /// Returns the priority class of the given outbound event.
+    /// The batch events and pings are sent ahead of the fetched data, and the blocks are sent last.
+    fn message_priority(&self, event: &Self::Message) -> MessagePriority {
+        match event {
+            Event::BatchPropose(_)
+            | Event::BatchSignature(_)
+            | Event::BatchCertified(_)
+            | Event::PrimaryPing(_)
+            | Event::Disconnect(_) => MessagePriority::High,
+            Event::BlockResponse(_) => MessagePriority::Bulk,
+            _ => MessagePriority::Normal,
+        }
     }
 }
 
@@ -1083,6 +1443,10 @@ impl<N: Network> Writing for Gateway<N> {
 impl<N: Network> Disconnect for Gateway<N> {
     /// Any extra operations to be performed during a disconnect.
     async fn handle_disconnect(&self, peer_addr: SocketAddr) {
+        // Remove the session, the compression, and the event protocol version of the connection.
+        self.sessions.lock().remove(&peer_addr);
+        self.compression.lock().remove(&peer_addr);
+        self.versions.lock().remove(&peer_addr);
         if let Some(peer_ip) = self.resolver.get_listener(peer_addr) {
             self.remove_connected_peer(peer_ip);
 
@@ -1177,7 +1541,7 @@ macro_rules! expect_event {
 
 /// Send the given message to the peer.
 async fn send_event<N: Network>(
-    framed: &mut Framed<&mut TcpStream, EventCodec<N>>,
+    framed: &mut Framed<&mut BoxedStream, EventCodec<N>>,
     peer_addr: SocketAddr,
     event: Event<N>,
 ) -> io::Result<()> {
@@ -1192,8 +1556,8 @@ impl<N: Network> Gateway<N> {
         peer_addr: SocketAddr,
         peer_ip: Option<SocketAddr>,
         restrictions_id: Field<N>,
-        stream: &'a mut TcpStream,
-    ) -> io::Result<(SocketAddr, Framed<&mut TcpStream, EventCodec<N>>)> {
+        stream: &'a mut BoxedStream,
+    ) -> io::Result<(SocketAddr, Framed<&mut BoxedStream, EventCodec<N>>)> {
         // This value is immediately guaranteed to be present, so it can be unwrapped.
         let peer_ip = peer_ip.unwrap();
 
@@ -1201,7 +1565,7 @@ impl<N: Network> Gateway<N> {
         let mut framed = Framed::new(stream, EventCodec::<N>::handshake());
 
         // Initialize an RNG.
-        let rng = &mut rand::rngs::OsRng;
+        let rng = &mut secure_rng();
 
         /* Step 1: Send the challenge request. */
 
@@ -1218,9 +1582,20 @@ impl<N: Network> Gateway<N> {
         // Listen for the challenge request message.
         let peer_request = expect_event!(Event::ChallengeRequest, framed, peer_addr);
 
+        // Retrieve the peer's session message, which is signed along with the nonces.
+        let peer_session_message = peer_response.session_message.clone();
+        // Retrieve the capabilities advertised by the peer.
+        let peer_capabilities = peer_response.capabilities.unwrap_or_default();
         // Verify the challenge response. If a disconnect reason was returned, send the disconnect message and abort.
         if let Some(reason) = self
-            .verify_challenge_response(peer_addr, peer_request.address, peer_response, restrictions_id, our_nonce)
+            .verify_challenge_response(
+                peer_addr,
+                &peer_request,
+                peer_response,
+                restrictions_id,
+                our_nonce,
+                peer_session_message.as_deref().unwrap_or_default(),
+            )
             .await
         {
             send_event(&mut framed, peer_addr, reason.into()).await?;
@@ -1232,19 +1607,67 @@ impl<N: Network> Gateway<N> {
             return Err(error(format!("Dropped '{peer_addr}' for reason: {reason:?}")));
         }
 
+        // If the peer supports encrypted connections, ensure it started a session, to prevent downgrades.
+        let is_encrypted = peer_request.version >= Event::<N>::ENCRYPTED_TRANSPORT_VERSION;
+        if is_encrypted != peer_session_message.is_some() {
+            warn!(
+                "{CONTEXT} Gateway handshake with '{peer_addr}' failed (unexpected session on version {})",
+                peer_request.version
+            );
+            let reason = DisconnectReason::ProtocolViolation;
+            send_event(&mut framed, peer_addr, reason.into()).await?;
+            return Err(error(format!("Dropped '{peer_addr}' for reason: {reason:?}")));
+        }
+        // Process the peer's session message, and prepare our own.
+        let session_handshake = match peer_session_message {
+            Some(peer_session_message) => {
+                let mut session_handshake = SessionHandshake::responder()?;
+                session_handshake.read_message(&peer_session_message)?;
+                let our_session_message = session_handshake.write_message()?;
+                Some((session_handshake, our_session_message))
+            }
+            None => None,
+        };
+
         /* Step 3: Send the challenge response. */
 
-        // Sign the counterparty nonce.
-        let response_nonce: u64 = rng.gen();
-        let data = [peer_request.nonce.to_le_bytes(), response_nonce.to_le_bytes()].concat();
-        let Ok(our_signature) = self.account.sign_bytes(&data, rng) else {
+        // Advertise our capabilities, if the peer is able to read them.
+        let our_capabilities = Self::response_capabilities(peer_request.version);
+        // Sign the counterparty nonce, along with the versions, our capabilities, and the session handshake (if any).
+        let response_nonce = ChallengeResponse::<N>::new_nonce(peer_request.nonce, rng.gen());
+        let binding = session_handshake.as_ref().map(|(handshake, _)| handshake.handshake_hash()).unwrap_or_default();
+        let request = ChallengeResponse::<N>::signing_request(
+            peer_request.nonce,
+            peer_request.version,
+            response_nonce,
+            Event::<N>::VERSION,
+            our_capabilities,
+            binding,
+        );
+        let Ok(our_signature) = self.account.sign_request(&request, rng) else {
             return Err(error(format!("Failed to sign the challenge request nonce from '{peer_addr}'")));
         };
         // Send the challenge response.
-        let our_response =
-            ChallengeResponse { restrictions_id, signature: Data::Object(our_signature), nonce: response_nonce };
+        let (session_handshake, session_message) = session_handshake.unzip();
+        let our_response = ChallengeResponse {
+            restrictions_id,
+            signature: Data::Object(our_signature),
+            nonce: response_nonce,
+            session_message,
+            capabilities: our_capabilities,
+        };
         send_event(&mut framed, peer_addr, Event::ChallengeResponse(our_response)).await?;
 
+        // Store the session, which encrypts the connection once the handshake is complete.
+        if let Some(session_handshake) = session_handshake {
+            self.sessions.lock().insert(peer_addr, session_handshake.into_session()?);
+        }
+        // Store the compression of the connection, as negotiated by the capabilities.
+        let compression = Compression::new(our_capabilities.unwrap_or_default(), peer_capabilities);
+        self.compression.lock().insert(peer_addr, compression);
+        // Store the event protocol version negotiated with the peer.
+        let version = Event::<N>::negotiate_version(peer_request.version).unwrap_or(Event::<N>::MINIMUM_VERSION);
+        self.versions.lock().insert(peer_addr, version);
         // Add the peer to the gateway.
         self.insert_connected_peer(peer_ip, peer_addr, peer_request.address);
 
@@ -1257,8 +1680,8 @@ impl<N: Network> Gateway<N> {
         peer_addr: SocketAddr,
         peer_ip: &mut Option<SocketAddr>,
         restrictions_id: Field<N>,
-        stream: &'a mut TcpStream,
-    ) -> io::Result<(SocketAddr, Framed<&mut TcpStream, EventCodec<N>>)> {
+        stream: &'a mut BoxedStream,
+    ) -> io::Result<(SocketAddr, Framed<&mut BoxedStream, EventCodec<N>>)> {
         // Construct the stream.
         let mut framed = Framed::new(stream, EventCodec::<N>::handshake());
 
@@ -1289,17 +1712,39 @@ impl<N: Network> Gateway<N> {
         /* Step 2: Send the challenge response followed by own challenge request. */
 
         // Initialize an RNG.
-        let rng = &mut rand::rngs::OsRng;
+        let rng = &mut secure_rng();
 
-        // Sign the counterparty nonce.
-        let response_nonce: u64 = rng.gen();
-        let data = [peer_request.nonce.to_le_bytes(), response_nonce.to_le_bytes()].concat();
-        let Ok(our_signature) = self.account.sign_bytes(&data, rng) else {
+        // If the peer supports encrypted connections, start a session.
+        let mut session_handshake = match peer_request.version >= Event::<N>::ENCRYPTED_TRANSPORT_VERSION {
+            true => Some(SessionHandshake::initiator()?),
+            false => None,
+        };
+        let session_message = session_handshake.as_mut().map(|handshake| handshake.write_message()).transpose()?;
+
+        // Advertise our capabilities, if the peer is able to read them.
+        let our_capabilities = Self::response_capabilities(peer_request.version);
+        // Sign the counterparty nonce, along with the versions, our capabilities, and the session message (if any).
+        let response_nonce = ChallengeResponse::<N>::new_nonce(peer_request.nonce, rng.gen());
+        let binding = session_message.as_deref().unwrap_or_default();
+        let request = ChallengeResponse::<N>::signing_request(
+            peer_request.nonce,
+            peer_request.version,
+            response_nonce,
+            Event::<N>::VERSION,
+            our_capabilities,
+            binding,
+        );
+        let Ok(our_signature) = self.account.sign_request(&request, rng) else {
             return Err(error(format!("Failed to sign the challenge request nonce from '{peer_addr}'")));
         };
         // Send the challenge response.
-        let our_response =
-            ChallengeResponse { restrictions_id, signature: Data::Object(our_signature), nonce: response_nonce };
+        let our_response = ChallengeResponse {
+            restrictions_id,
+            signature: Data::Object(our_signature),
+            nonce: response_nonce,
+            session_message,
+            capabilities: our_capabilities,
+        };
         send_event(&mut framed, peer_addr, Event::ChallengeResponse(our_response)).await?;
 
         // Sample a random nonce.
@@ -1312,14 +1757,42 @@ impl<N: Network> Gateway<N> {
 
         // Listen for the challenge response message.
         let peer_response = expect_event!(Event::ChallengeResponse, framed, peer_addr);
-        // Verify the challenge response. If a disconnect reason was returned, send the disconnect message and abort.
+        // Retrieve the capabilities advertised by the peer.
+        let peer_capabilities = peer_response.capabilities.unwrap_or_default();
+        // If a session was started, complete it with the peer's session message.
+        if let Some(session_handshake) = &mut session_handshake {
+            let is_completed = match &peer_response.session_message {
+                Some(peer_session_message) => session_handshake.read_message(peer_session_message).is_ok(),
+                None => false,
+            };
+            if !is_completed {
+                warn!("{CONTEXT} Gateway handshake with '{peer_addr}' failed (missing or invalid session message)");
+                let reason = DisconnectReason::InvalidChallengeResponse;
+                send_event(&mut framed, peer_addr, reason.into()).await?;
+                return Err(error(format!("Dropped '{peer_addr}' for reason: {reason:?}")));
+            }
+        }
+        // Verify the challenge response, which signs the completed session handshake (if any).
+        // If a disconnect reason was returned, send the disconnect message and abort.
+        let binding =
+            session_handshake.as_ref().map(|handshake| handshake.handshake_hash().to_vec()).unwrap_or_default();
         if let Some(reason) = self
-            .verify_challenge_response(peer_addr, peer_request.address, peer_response, restrictions_id, our_nonce)
+            .verify_challenge_response(peer_addr, &peer_request, peer_response, restrictions_id, our_nonce, &binding)
             .await
         {
             send_event(&mut framed, peer_addr, reason.into()).await?;
             return Err(error(format!("Dropped '{peer_addr}' for reason: {reason:?}")));
         }
+        // Store the session, which encrypts the connection once the handshake is complete.
+        if let Some(session_handshake) = session_handshake {
+            self.sessions.lock().insert(peer_addr, session_handshake.into_session()?);
+        }
+        // Store the compression of the connection, as negotiated by the capabilities.
+        let compression = Compression::new(our_capabilities.unwrap_or_default(), peer_capabilities);
+        self.compression.lock().insert(peer_addr, compression);
+        // Store the event protocol version negotiated with the peer.
+        let version = Event::<N>::negotiate_version(peer_request.version).unwrap_or(Event::<N>::MINIMUM_VERSION);
+        self.versions.lock().insert(peer_addr, version);
         // Add the peer to the gateway.
         self.insert_connected_peer(peer_ip, peer_addr, peer_request.address);
 
@@ -1330,8 +1803,8 @@ impl<N: Network> Gateway<N> {
     fn verify_challenge_request(&self, peer_addr: SocketAddr, event: &ChallengeRequest<N>) -> Option<DisconnectReason> {
         // Retrieve the components of the challenge request.
         let &ChallengeRequest { version, listener_port: _, address, nonce: _ } = event;
-        // Ensure the event protocol version is not outdated.
-        if version < Event::<N>::VERSION {
+        // Ensure the event protocol version is within the supported range.
+        if Event::<N>::negotiate_version(version).is_none() {
             warn!("{CONTEXT} Gateway is dropping '{peer_addr}' on version {version} (outdated)");
             return Some(DisconnectReason::OutdatedClientVersion);
         }
@@ -1348,17 +1821,22 @@ impl<N: Network> Gateway<N> {
         None
     }
 
-    /// Verifies the given challenge response. Returns a disconnect reason if the response is invalid.
+    /// Verifies the given challenge response from the sender of the given challenge request.
+    /// Returns a disconnect reason if the response is invalid.
+    ///
+    /// The `binding` is the session handshake data that the peer signed along with the nonces, if any.
     async fn verify_challenge_response(
         &self,
         peer_addr: SocketAddr,
-        peer_address: Address<N>,
+        peer_request: &ChallengeRequest<N>,
         response: ChallengeResponse<N>,
         expected_restrictions_id: Field<N>,
         expected_nonce: u64,
+        binding: &[u8],
     ) -> Option<DisconnectReason> {
         // Retrieve the components of the challenge response.
-        let ChallengeResponse { restrictions_id, signature, nonce } = response;
+        let ChallengeResponse { restrictions_id, signature, nonce, session_message: _, capabilities } = response;
+        let &ChallengeRequest { version: peer_version, address: peer_address, .. } = peer_request;
 
         // Verify the restrictions ID.
         if restrictions_id != expected_restrictions_id {
@@ -1370,13 +1848,35 @@ impl<N: Network> Gateway<N> {
             warn!("{CONTEXT} Gateway handshake with '{peer_addr}' failed (cannot deserialize the signature)");
             return Some(DisconnectReason::InvalidChallengeResponse);
         };
-        // Verify the signature.
-        if !signature.verify_bytes(&peer_address, &[expected_nonce.to_le_bytes(), nonce.to_le_bytes()].concat()) {
+        // Verify the signature, which binds the versions of both nodes, and the capabilities of the peer.
+        let request = ChallengeResponse::<N>::signing_request(
+            expected_nonce,
+            Event::<N>::VERSION,
+            nonce,
+            peer_version,
+            capabilities,
+            binding,
+        );
+        if !request.verify(&peer_address, &signature) {
             warn!("{CONTEXT} Gateway handshake with '{peer_addr}' failed (invalid signature)");
             return Some(DisconnectReason::InvalidChallengeResponse);
         }
+        // Ensure the peer does not support encryption, if it claims an older version.
+        // Note: As the response nonce is signed, this refuses plaintext even if both versions were rewritten.
+        if peer_version < Event::<N>::ENCRYPTED_TRANSPORT_VERSION
+            && ChallengeResponse::<N>::is_encryption_signaled(expected_nonce, nonce)
+        {
+            warn!("{CONTEXT} Gateway handshake with '{peer_addr}' failed (downgraded version {peer_version})");
+            return Some(DisconnectReason::ProtocolViolation);
+        }
         None
     }
+
+    /// Returns the capabilities to advertise in the challenge response to a peer on the given version,
+    /// if the peer is able to read them.
+    fn response_capabilities(peer_version: u32) -> Option<Capabilities> {
+        (peer_version >= Event::<N>::CAPABILITIES_VERSION).then(Capabilities::supported)
+    }
 }
 
 #[cfg(test)]
@@ -1463,6 +1963,7 @@ mod prop_tests {
                         address.ip(),
                         &[],
                         address.port(),
+                        Default::default(),
                     )
                     .unwrap()
                 })
@@ -1507,9 +2008,16 @@ mod prop_tests {
         let (storage, _, private_key, dev) = input;
         let account = Account::try_from(private_key).unwrap();
 
-        let gateway =
-            Gateway::new(account.clone(), storage.clone(), storage.ledger().clone(), dev.ip(), &[], dev.port())
-                .unwrap();
+        let gateway = Gateway::new(
+            account.clone(),
+            storage.clone(),
+            storage.ledger().clone(),
+            dev.ip(),
+            &[],
+            dev.port(),
+            Default::default(),
+        )
+        .unwrap();
         let tcp_config = gateway.tcp().config();
         assert_eq!(tcp_config.listener_ip, Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
         assert_eq!(tcp_config.desired_listening_port, Some(MEMORY_POOL_PORT + dev.port().unwrap()));
@@ -1524,9 +2032,16 @@ mod prop_tests {
         let (storage, _, private_key, dev) = input;
         let account = Account::try_from(private_key).unwrap();
 
-        let gateway =
-            Gateway::new(account.clone(), storage.clone(), storage.ledger().clone(), dev.ip(), &[], dev.port())
-                .unwrap();
+        let gateway = Gateway::new(
+            account.clone(),
+            storage.clone(),
+            storage.ledger().clone(),
+            dev.ip(),
+            &[],
+            dev.port(),
+            Default::default(),
+        )
+        .unwrap();
         let tcp_config = gateway.tcp().config();
         if let Some(socket_addr) = dev.ip() {
             assert_eq!(tcp_config.listener_ip, Some(socket_addr.ip()));
@@ -1551,8 +2066,16 @@ mod prop_tests {
         let worker_storage = storage.clone();
         let account = Account::try_from(private_key).unwrap();
 
-        let gateway =
-            Gateway::new(account, storage.clone(), storage.ledger().clone(), dev.ip(), &[], dev.port()).unwrap();
+        let gateway = Gateway::new(
+            account,
+            storage.clone(),
+            storage.ledger().clone(),
+            dev.ip(),
+            &[],
+            dev.port(),
+            Default::default(),
+        )
+        .unwrap();
 
         let (primary_sender, _) = init_primary_channels();
 
@@ -1616,8 +2139,16 @@ mod prop_tests {
         // Initialize the storage.
         let storage = Storage::new(ledger.clone(), Arc::new(BFTMemoryService::new()), max_gc_rounds);
         // Initialize the gateway.
-        let gateway =
-            Gateway::new(account.clone(), storage.clone(), ledger.clone(), dev.ip(), &[], dev.port()).unwrap();
+        let gateway = Gateway::new(
+            account.clone(),
+            storage.clone(),
+            ledger.clone(),
+            dev.ip(),
+            &[],
+            dev.port(),
+            Default::default(),
+        )
+        .unwrap();
         // Insert certificate to the storage.
         for certificate in certificates.iter() {
             storage.testing_only_insert_certificate_testing_only(certificate.clone());
@@ -0,0 +1,305 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{helpers::now, MAX_TIMESTAMP_DELTA_IN_SECS};
+use snarkos_node_bft_events::AddressRecord;
+use snarkos_node_persistence::{decode_file, encode_file, node_file_path, write_atomically};
+use snarkvm::{
+    console::{account::Address, network::Network},
+    prelude::{anyhow, bail, ensure, error, FromBytes, IoResult, Read, Result, ToBytes, Write},
+};
+
+use aleo_std::StorageMode;
+use indexmap::IndexMap;
+use parking_lot::{Mutex, RwLock};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// The magic bytes at the start of an address book file.
+const ADDRESS_BOOK_MAGIC: [u8; 8] = *b"SNOSADDR";
+/// The current version of the address book file format.
+pub const ADDRESS_BOOK_VERSION: u16 = 1;
+
+/// The maximum age of an address record, after which it is discarded.
+pub const MAX_ADDRESS_RECORD_AGE_IN_SECS: i64 = 7 * 24 * 60 * 60; // seconds
+/// The interval at which a validator signs a new address record about itself.
+pub const ADDRESS_RECORD_REFRESH_INTERVAL_IN_SECS: i64 = 60 * 60; // seconds
+
+/// Returns the path where the address book file may be stored.
+pub fn address_book_path(network: u16, dev: Option<u16>) -> PathBuf {
+    const ADDRESS_BOOK_FILE_NAME: &str = "validator-address-book";
+
+    node_file_path(ADDRESS_BOOK_FILE_NAME, network, &StorageMode::from(dev))
+}
+
+/// The address book of the validators, which maps each validator address to the latest address record
+/// that the validator signed about its listener IP.
+#[derive(Debug)]
+pub struct AddressBook<N: Network> {
+    /// The latest address record of each validator.
+    records: RwLock<IndexMap<Address<N>, AddressRecord<N>>>,
+    /// Whether the address book changed since it was last stored.
+    is_dirty: AtomicBool,
+    /// The lock serializing the stores, so that an older snapshot never replaces a newer one.
+    store_lock: Mutex<()>,
+}
+
+impl<N: Network> Default for AddressBook<N> {
+    /// Initializes a new, empty address book.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: Network> AddressBook<N> {
+    /// Initializes a new, empty address book.
+    pub fn new() -> Self {
+        Self { records: Default::default(), is_dirty: Default::default(), store_lock: Default::default() }
+    }
+
+    /// Returns the number of address records.
+    pub fn len(&self) -> usize {
+        self.records.read().len()
+    }
+
+    /// Returns `true` if there are no address records.
+    pub fn is_empty(&self) -> bool {
+        self.records.read().is_empty()
+    }
+
+    /// Returns `true` if the address book changed since it was last stored.
+    pub fn is_dirty(&self) -> bool {
+        self.is_dirty.load(Ordering::SeqCst)
+    }
+
+    /// Returns the address record of the given validator, if it exists.
+    pub fn get(&self, address: &Address<N>) -> Option<AddressRecord<N>> {
+        self.records.read().get(address).cloned()
+    }
+
+    /// Returns the address records.
+    pub fn records(&self) -> Vec<AddressRecord<N>> {
+        self.records.read().values().cloned().collect()
+    }
+
+    /// Inserts the given address record, if it is valid, and newer than the record of the same validator.
+    /// Returns `true` if the address record was inserted.
+    ///
+    /// An address record is valid if its signature is valid, its timestamp is neither in the future nor expired,
+    /// and the validator is authorized (i.e. a member of the committee).
+    pub fn insert(&self, record: AddressRecord<N>, is_authorized: impl Fn(Address<N>) -> bool) -> Result<bool> {
+        // Ensure the record is neither in the future nor expired.
+        let now = now();
+        ensure!(record.timestamp <= now + MAX_TIMESTAMP_DELTA_IN_SECS, "The address record is in the future");
+        ensure!(record.timestamp >= now - MAX_ADDRESS_RECORD_AGE_IN_SECS, "The address record is expired");
+        // Skip the record, if it is not newer than the existing record.
+        // Note: This check happens before the signature is verified, as stale records are commonly re-gossiped.
+        if self.records.read().get(&record.address).map_or(false, |existing| existing.timestamp >= record.timestamp) {
+            return Ok(false);
+        }
+        // Ensure the validator is authorized.
+        ensure!(is_authorized(record.address), "The address record is for an unauthorized validator");
+        // Ensure the signature is valid.
+        ensure!(record.verify(), "The address record has an invalid signature");
+
+        // Insert the record, if it is still newer than the existing record.
+        let mut records = self.records.write();
+        if records.get(&record.address).map_or(false, |existing| existing.timestamp >= record.timestamp) {
+            return Ok(false);
+        }
+        records.insert(record.address, record);
+        self.is_dirty.store(true, Ordering::SeqCst);
+        Ok(true)
+    }
+
+    /// Removes the address records that are expired, or for validators that are no longer authorized.
+    pub fn prune(&self, is_authorized: impl Fn(Address<N>) -> bool) {
+        let threshold = now() - MAX_ADDRESS_RECORD_AGE_IN_SECS;
+        let mut records = self.records.write();
+        let num_records = records.len();
+        records.retain(|address, record| record.timestamp >= threshold && is_authorized(*address));
+        if records.len() != num_records {
+            self.is_dirty.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+impl<N: Network> AddressBook<N> {
+    /// Loads the address book from the given file, keeping only the valid address records.
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) => bail!("Couldn't read the address book stored at {} - {err}", path.display()),
+        };
+        let records = Self::from_file_bytes(&bytes)
+            .map_err(|err| anyhow!("Couldn't deserialize the address book stored at {} - {err}", path.display()))?;
+
+        // Keep the records that are valid and not expired.
+        // Note: The committee is checked once the address book is used, as the ledger may still be syncing.
+        let threshold = now() - MAX_ADDRESS_RECORD_AGE_IN_SECS;
+        let records: IndexMap<_, _> = records
+            .into_iter()
+            .filter(|record| record.timestamp >= threshold && record.verify())
+            .map(|record| (record.address, record))
+            .collect();
+
+        info!("Loaded {} validator address records from {}", records.len(), path.display());
+        Ok(Self { records: RwLock::new(records), is_dirty: Default::default(), store_lock: Default::default() })
+    }
+
+    /// Stores the address book to the given file, if it changed since it was last stored.
+    ///
+    /// The address book is written to a temporary file which is then renamed,
+    /// so that an interrupted write never leaves a partial address book behind.
+    pub fn store(&self, path: &Path) -> Result<()> {
+        let _store_lock = self.store_lock.lock();
+        if !self.is_dirty.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        debug!("Storing the validator address book to {}...", path.display());
+
+        // Serialize the address book.
+        let bytes = match self.to_file_bytes() {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                self.is_dirty.store(true, Ordering::SeqCst);
+                return Err(err);
+            }
+        };
+        // Store the address book to the file system.
+        if let Err(err) = write_atomically(path, &bytes) {
+            self.is_dirty.store(true, Ordering::SeqCst);
+            bail!("Couldn't write the address book to {} - {err}", path.display());
+        }
+        Ok(())
+    }
+
+    /// Returns the contents of an address book file, which is the header followed by the payload.
+    fn to_file_bytes(&self) -> Result<Vec<u8>> {
+        Ok(encode_file(&ADDRESS_BOOK_MAGIC, ADDRESS_BOOK_VERSION, &self.to_bytes_le()?))
+    }
+
+    /// Returns the address records, from the contents of an address book file.
+    fn from_file_bytes(bytes: &[u8]) -> Result<Vec<AddressRecord<N>>> {
+        let (version, payload) = decode_file(&ADDRESS_BOOK_MAGIC, bytes)?;
+        ensure!(version == ADDRESS_BOOK_VERSION, "Unsupported address book version {version}");
+        Ok(Self::read_records(payload)?)
+    }
+
+    /// Reads the address records from the given payload.
+    fn read_records<R: Read>(mut reader: R) -> IoResult<Vec<AddressRecord<N>>> {
+        // Read the number of records.
+        let num_records = u32::read_le(&mut reader)?;
+        // Read the records.
+        let mut records = Vec::new();
+        for _ in 0..num_records {
+            records.push(AddressRecord::read_le(&mut reader)?);
+        }
+        Ok(records)
+    }
+}
+
+impl<N: Network> ToBytes for AddressBook<N> {
+    fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()> {
+        let records = self.records.read();
+        // Write the number of records.
+        u32::try_from(records.len()).map_err(error)?.write_le(&mut writer)?;
+        // Write the records.
+        for record in records.values() {
+            record.write_le(&mut writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkos_account::Account;
+    use snarkvm::prelude::TestRng;
+
+    use std::net::SocketAddr;
+
+    type CurrentNetwork = snarkvm::prelude::MainnetV0;
+
+    fn sample_record(
+        account: &Account<CurrentNetwork>,
+        timestamp: i64,
+        rng: &mut TestRng,
+    ) -> AddressRecord<CurrentNetwork> {
+        let listener_ip = SocketAddr::from(([1, 2, 3, 4], 5000));
+        let request = AddressRecord::signing_request(account.address(), listener_ip, timestamp);
+        let signature = account.sign_request(&request, rng).unwrap();
+        AddressRecord::new(account.address(), listener_ip, timestamp, signature)
+    }
+
+    #[test]
+    fn test_insert() {
+        let rng = &mut TestRng::default();
+        let account = Account::<CurrentNetwork>::new(rng).unwrap();
+        let book = AddressBook::new();
+        let now = now();
+
+        // Ensure invalid records are rejected.
+        let authorized = |_| true;
+        assert!(book.insert(sample_record(&account, now + 2 * MAX_TIMESTAMP_DELTA_IN_SECS, rng), authorized).is_err());
+        assert!(
+            book.insert(sample_record(&account, now - 2 * MAX_ADDRESS_RECORD_AGE_IN_SECS, rng), authorized).is_err()
+        );
+        assert!(book.insert(sample_record(&account, now, rng), |_| false).is_err());
+        let mut tampered = sample_record(&account, now, rng);
+        tampered.listener_ip = SocketAddr::from(([5, 6, 7, 8], 5000));
+        assert!(book.insert(tampered, authorized).is_err());
+        assert!(book.is_empty());
+
+        // Ensure only newer records replace the existing record.
+        let record = sample_record(&account, now - 10, rng);
+        assert!(book.insert(record.clone(), authorized).unwrap());
+        assert!(!book.insert(record.clone(), authorized).unwrap());
+        assert!(!book.insert(sample_record(&account, now - 20, rng), authorized).unwrap());
+        let newer = sample_record(&account, now, rng);
+        assert!(book.insert(newer.clone(), authorized).unwrap());
+        assert_eq!(book.records(), vec![newer]);
+
+        // Ensure unauthorized validators are pruned.
+        book.prune(|_| false);
+        assert!(book.is_empty());
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let rng = &mut TestRng::default();
+        let book = AddressBook::new();
+        for _ in 0..3 {
+            let account = Account::<CurrentNetwork>::new(rng).unwrap();
+            assert!(book.insert(sample_record(&account, now(), rng), |_| true).unwrap());
+        }
+
+        let path = std::env::temp_dir().join(format!("snarkos-address-book-test-{}", std::process::id()));
+        book.store(&path).unwrap();
+        let loaded = AddressBook::<CurrentNetwork>::load(&path).unwrap();
+        assert_eq!(loaded.records(), book.records());
+
+        // Ensure a corrupted address book is rejected.
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        fs::write(&path, bytes).unwrap();
+        assert!(AddressBook::<CurrentNetwork>::load(&path).is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The number of random certificate requests sent to each peer per flood interval.
+pub const ADVERSARY_FLOOD_REQUESTS_PER_PEER: usize = 64;
+/// The interval between floods of certificate requests, in milliseconds.
+pub const ADVERSARY_FLOOD_INTERVAL_IN_MS: u64 = 500;
+
+/// The set of Byzantine behaviours that a validator may exhibit, for testing purposes only.
+///
+/// By default, every behaviour is disabled and the validator acts honestly.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AdversaryMode {
+    /// If `true`, the validator never signs batch proposals from its peers.
+    pub withhold_signatures: bool,
+    /// If `true`, the validator sends conflicting batch proposals for the same round to different peers.
+    pub equivocate_proposals: bool,
+    /// If `true`, the validator sends its oldest stored certificate to its peers after each proposal.
+    pub send_stale_certificates: bool,
+    /// If `true`, the validator sends a self-signed certificate for a future round to its peers after each proposal.
+    pub send_future_certificates: bool,
+    /// If `true`, the validator ignores all transmission requests from its peers.
+    pub refuse_transmission_requests: bool,
+    /// If `true`, the validator periodically floods its peers with requests for unknown certificates.
+    pub flood_certificate_requests: bool,
+}
+
+impl AdversaryMode {
+    /// Returns an adversary mode with every behaviour enabled.
+    pub const fn all() -> Self {
+        Self {
+            withhold_signatures: true,
+            equivocate_proposals: true,
+            send_stale_certificates: true,
+            send_future_certificates: true,
+            refuse_transmission_requests: true,
+            flood_certificate_requests: true,
+        }
+    }
+
+    /// Returns `true` if any behaviour is enabled.
+    pub const fn is_byzantine(&self) -> bool {
+        self.withhold_signatures
+            || self.equivocate_proposals
+            || self.send_stale_certificates
+            || self.send_future_certificates
+            || self.refuse_transmission_requests
+            || self.flood_certificate_requests
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adversary_mode() {
+        assert!(!AdversaryMode::default().is_byzantine());
+        assert!(AdversaryMode::all().is_byzantine());
+        assert!(AdversaryMode { refuse_transmission_requests: true, ..Default::default() }.is_byzantine());
+    }
+}
@@ -0,0 +1,275 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::helpers::now;
+use snarkvm::{
+    console::{account::Address, types::Field},
+    ledger::committee::Committee,
+    prelude::Network,
+};
+
+use indexmap::IndexMap;
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// The maximum number of committee transitions to keep.
+const MAX_COMMITTEE_TRANSITIONS: usize = 100;
+
+/// A change of the committee lookback, as observed by this node.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(bound = "")]
+pub struct CommitteeTransition<N: Network> {
+    /// The round at which the change was observed.
+    pub round: u64,
+    /// The timestamp at which the change was observed.
+    pub timestamp: i64,
+    /// The ID of the previous committee lookback.
+    pub previous_committee_id: Field<N>,
+    /// The ID of the new committee lookback.
+    pub committee_id: Field<N>,
+    /// The starting round of the new committee lookback.
+    pub starting_round: u64,
+    /// The members that joined the committee, with their stake.
+    pub added: IndexMap<Address<N>, u64>,
+    /// The members that left the committee, with their previous stake.
+    pub removed: IndexMap<Address<N>, u64>,
+    /// The change in stake of the members that remained in the committee, if it changed.
+    pub stake_deltas: IndexMap<Address<N>, i64>,
+    /// The total stake of the previous committee lookback.
+    pub previous_total_stake: u64,
+    /// The total stake of the new committee lookback.
+    pub total_stake: u64,
+}
+
+impl<N: Network> CommitteeTransition<N> {
+    /// Returns the transition from the given previous committee to the given committee.
+    pub fn new(round: u64, previous: &Committee<N>, committee: &Committee<N>) -> Self {
+        let added = committee
+            .members()
+            .keys()
+            .filter(|address| !previous.is_committee_member(**address))
+            .map(|address| (*address, committee.get_stake(*address)))
+            .collect();
+        let removed = previous
+            .members()
+            .keys()
+            .filter(|address| !committee.is_committee_member(**address))
+            .map(|address| (*address, previous.get_stake(*address)))
+            .collect();
+        let stake_deltas = committee
+            .members()
+            .keys()
+            .filter(|address| previous.is_committee_member(**address))
+            .map(|address| (*address, committee.get_stake(*address) as i64 - previous.get_stake(*address) as i64))
+            .filter(|(_, delta)| *delta != 0)
+            .collect();
+        Self {
+            round,
+            timestamp: now(),
+            previous_committee_id: previous.id(),
+            committee_id: committee.id(),
+            starting_round: committee.starting_round(),
+            added,
+            removed,
+            stake_deltas,
+            previous_total_stake: previous.total_stake(),
+            total_stake: committee.total_stake(),
+        }
+    }
+}
+
+/// An upcoming change of this node's membership in the committee lookback.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(bound = "")]
+pub struct MembershipChange<N: Network> {
+    /// The address of this node.
+    pub address: Address<N>,
+    /// `true` if this node is joining the committee, and `false` if it is leaving.
+    pub is_joining: bool,
+    /// The ID of the latest committee, which becomes the committee lookback.
+    pub committee_id: Field<N>,
+    /// The round from which the latest committee becomes the committee lookback.
+    pub effective_round: u64,
+}
+
+/// The tracker of the changes of the committee lookback, and of this node's membership.
+#[derive(Debug)]
+pub struct CommitteeTracker<N: Network> {
+    /// The address of this node.
+    address: Address<N>,
+    /// The latest observed committee lookback.
+    committee_lookback: RwLock<Option<Committee<N>>>,
+    /// The most recent committee transitions.
+    transitions: RwLock<VecDeque<CommitteeTransition<N>>>,
+    /// The upcoming change of this node's membership, if any.
+    upcoming: RwLock<Option<MembershipChange<N>>>,
+}
+
+impl<N: Network> CommitteeTracker<N> {
+    /// Initializes a new instance of the committee tracker for the given address.
+    pub fn new(address: Address<N>) -> Self {
+        Self {
+            address,
+            committee_lookback: Default::default(),
+            transitions: Default::default(),
+            upcoming: Default::default(),
+        }
+    }
+
+    /// Returns the most recent committee transitions, oldest first.
+    pub fn transitions(&self) -> Vec<CommitteeTransition<N>> {
+        self.transitions.read().iter().cloned().collect()
+    }
+
+    /// Returns the upcoming change of this node's membership, if any.
+    pub fn upcoming(&self) -> Option<MembershipChange<N>> {
+        self.upcoming.read().clone()
+    }
+
+    /// Updates the tracker with the committee lookback for the given round, and the latest committee.
+    /// Returns the committee transition, if the committee lookback changed.
+    pub fn update(
+        &self,
+        round: u64,
+        committee_lookback: &Committee<N>,
+        latest_committee: &Committee<N>,
+    ) -> Option<CommitteeTransition<N>> {
+        self.update_upcoming(committee_lookback, latest_committee);
+
+        // Replace the committee lookback, if it changed.
+        let previous = {
+            let mut current = self.committee_lookback.write();
+            if current.as_ref().map(|committee| committee.id()) == Some(committee_lookback.id()) {
+                return None;
+            }
+            current.replace(committee_lookback.clone())
+        }?;
+
+        // Log the transition.
+        let transition = CommitteeTransition::new(round, &previous, committee_lookback);
+        info!(
+            "The committee lookback changed at round {round} - {} joined, {} left, {} changed stake (total stake {} -> {})",
+            transition.added.len(),
+            transition.removed.len(),
+            transition.stake_deltas.len(),
+            transition.previous_total_stake,
+            transition.total_stake,
+        );
+        for (address, stake) in &transition.added {
+            info!("Validator '{address}' joined the committee with {stake} microcredits");
+        }
+        for (address, stake) in &transition.removed {
+            info!("Validator '{address}' left the committee with {stake} microcredits, and will be disconnected");
+        }
+        for (address, delta) in &transition.stake_deltas {
+            debug!("Validator '{address}' changed stake by {delta} microcredits");
+        }
+        #[cfg(feature = "metrics")]
+        metrics::increment_counter(metrics::bft::COMMITTEE_TRANSITIONS);
+
+        // Store the transition.
+        let mut transitions = self.transitions.write();
+        transitions.push_back(transition.clone());
+        while transitions.len() > MAX_COMMITTEE_TRANSITIONS {
+            transitions.pop_front();
+        }
+        Some(transition)
+    }
+
+    /// Updates the upcoming change of this node's membership, warning once per change.
+    fn update_upcoming(&self, committee_lookback: &Committee<N>, latest_committee: &Committee<N>) {
+        let is_member = committee_lookback.is_committee_member(self.address);
+        let will_be_member = latest_committee.is_committee_member(self.address);
+        // If the membership is unchanged, clear the upcoming change.
+        if is_member == will_be_member {
+            self.upcoming.write().take();
+            return;
+        }
+        let change = MembershipChange {
+            address: self.address,
+            is_joining: will_be_member,
+            committee_id: latest_committee.id(),
+            effective_round: latest_committee.starting_round().saturating_add(Committee::<N>::COMMITTEE_LOOKBACK_RANGE),
+        };
+        // Warn if the change is new.
+        let mut upcoming = self.upcoming.write();
+        if upcoming.as_ref() != Some(&change) {
+            match change.is_joining {
+                true => warn!("This validator will join the committee at round {}", change.effective_round),
+                false => warn!(
+                    "This validator will leave the committee at round {}, and will be disconnected by the other validators",
+                    change.effective_round
+                ),
+            }
+            *upcoming = Some(change);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::{
+        ledger::committee::MIN_VALIDATOR_STAKE,
+        prelude::{Rng, TestRng},
+    };
+
+    type CurrentNetwork = snarkvm::prelude::MainnetV0;
+
+    fn sample_committee(starting_round: u64, members: &[(Address<CurrentNetwork>, u64)]) -> Committee<CurrentNetwork> {
+        let members = members.iter().map(|(address, stake)| (*address, (*stake, false, 0))).collect();
+        Committee::new(starting_round, members).unwrap()
+    }
+
+    #[test]
+    fn test_committee_transitions() {
+        let rng = &mut TestRng::default();
+        let addresses: Vec<Address<CurrentNetwork>> = (0..5).map(|_| Address::new(rng.gen())).collect();
+        let (me, stake) = (addresses[4], MIN_VALIDATOR_STAKE);
+        let tracker = CommitteeTracker::new(me);
+
+        // Initialize the committees, where this node joins in the second committee.
+        let members = addresses[..4].iter().map(|address| (*address, stake)).collect::<Vec<_>>();
+        let first = sample_committee(0, &members);
+        let second = sample_committee(10, &[
+            (addresses[0], stake),
+            (addresses[1], stake * 2),
+            (addresses[2], stake),
+            (me, stake),
+        ]);
+
+        // Ensure the first committee is not a transition.
+        assert!(tracker.update(1, &first, &first).is_none());
+        assert!(tracker.upcoming().is_none());
+
+        // Ensure this node is warned ahead of joining.
+        assert!(tracker.update(12, &first, &second).is_none());
+        let upcoming = tracker.upcoming().unwrap();
+        assert!(upcoming.is_joining);
+        assert_eq!(upcoming.effective_round, 10 + Committee::<CurrentNetwork>::COMMITTEE_LOOKBACK_RANGE);
+
+        // Ensure the transition records the added and removed members, and the stake deltas.
+        let transition = tracker.update(upcoming.effective_round + 1, &second, &second).unwrap();
+        assert_eq!(transition.added, IndexMap::from([(me, stake)]));
+        assert_eq!(transition.removed, IndexMap::from([(addresses[3], stake)]));
+        assert_eq!(transition.stake_deltas, IndexMap::from([(addresses[1], stake as i64)]));
+        assert_eq!(transition.total_stake, transition.previous_total_stake + stake);
+        assert!(tracker.upcoming().is_none());
+
+        // Ensure the same committee lookback is not a transition.
+        assert!(tracker.update(upcoming.effective_round + 3, &second, &second).is_none());
+        assert_eq!(tracker.transitions(), vec![transition]);
+    }
+}
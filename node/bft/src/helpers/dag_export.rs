@@ -0,0 +1,195 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::helpers::fmt_id;
+use snarkvm::{
+    console::types::{Address, Field},
+    ledger::narwhal::BatchCertificate,
+    prelude::Network,
+};
+
+use serde::Serialize;
+use std::fmt::Write;
+
+/// A snapshot of a single batch certificate, for debugging purposes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(bound = "")]
+pub struct ExportedCertificate<N: Network> {
+    /// The certificate ID.
+    pub id: Field<N>,
+    /// The batch ID.
+    pub batch_id: Field<N>,
+    /// The round of the certificate.
+    pub round: u64,
+    /// The author of the certificate.
+    pub author: Address<N>,
+    /// The previous certificate IDs.
+    pub previous_certificate_ids: Vec<Field<N>>,
+    /// The number of transmissions in the certificate.
+    pub num_transmissions: usize,
+    /// `true` if the certificate is in the DAG.
+    pub in_dag: bool,
+    /// `true` if the certificate is in storage.
+    pub in_storage: bool,
+    /// `true` if the certificate has been committed.
+    pub is_committed: bool,
+    /// `true` if the author is the leader of the (even) round.
+    pub is_leader: bool,
+}
+
+impl<N: Network> ExportedCertificate<N> {
+    /// Initializes a new exported certificate, with all flags unset.
+    pub fn new(certificate: &BatchCertificate<N>) -> Self {
+        Self {
+            id: certificate.id(),
+            batch_id: certificate.batch_id(),
+            round: certificate.round(),
+            author: certificate.author(),
+            previous_certificate_ids: certificate.previous_certificate_ids().iter().copied().collect(),
+            num_transmissions: certificate.transmission_ids().len(),
+            in_dag: false,
+            in_storage: false,
+            is_committed: false,
+            is_leader: false,
+        }
+    }
+}
+
+/// A snapshot of the DAG and storage rounds, for debugging purposes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(bound = "")]
+pub struct DAGExport<N: Network> {
+    /// The starting round (inclusive).
+    pub start_round: u64,
+    /// The ending round (inclusive).
+    pub end_round: u64,
+    /// The last committed round in the DAG.
+    pub last_committed_round: u64,
+    /// The certificates, ordered by round.
+    pub certificates: Vec<ExportedCertificate<N>>,
+}
+
+impl<N: Network> DAGExport<N> {
+    /// Returns the export in the Graphviz DOT format.
+    ///
+    /// Each round is drawn as a cluster, with edges from each certificate to its previous certificates.
+    /// Committed certificates are filled, leader certificates are drawn with a bold border,
+    /// and certificates that are only in storage (and not in the DAG) are dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        // Note: Writing to a `String` is infallible.
+        let _ = writeln!(dot, "digraph dag {{");
+        let _ = writeln!(dot, "  rankdir=BT;");
+        let _ = writeln!(dot, "  node [shape=box, fontname=\"monospace\"];");
+        let _ = writeln!(
+            dot,
+            "  label=\"rounds {}..={} (last committed round {})\";",
+            self.start_round, self.end_round, self.last_committed_round
+        );
+
+        // Write the certificates, grouped by round.
+        let mut current_round = None;
+        for certificate in &self.certificates {
+            if current_round != Some(certificate.round) {
+                if current_round.is_some() {
+                    let _ = writeln!(dot, "  }}");
+                }
+                current_round = Some(certificate.round);
+                let _ = writeln!(dot, "  subgraph cluster_round_{} {{", certificate.round);
+                let _ = writeln!(dot, "    label=\"round {}\";", certificate.round);
+                let _ = writeln!(dot, "    rank=same;");
+            }
+            // Determine the style of the certificate.
+            let mut styles = vec![];
+            if certificate.is_committed {
+                styles.push("filled");
+            }
+            if certificate.is_leader {
+                styles.push("bold");
+            }
+            if !certificate.in_dag {
+                styles.push("dashed");
+            }
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [label=\"{}\\n{}\\n{} transmissions\", style=\"{}\", fillcolor=\"palegreen\"];",
+                certificate.id,
+                fmt_id(certificate.id),
+                fmt_id(certificate.author),
+                certificate.num_transmissions,
+                styles.join(",")
+            );
+        }
+        if current_round.is_some() {
+            let _ = writeln!(dot, "  }}");
+        }
+
+        // Write the edges to the previous certificates.
+        for certificate in &self.certificates {
+            for previous_certificate_id in &certificate.previous_certificate_ids {
+                let _ = writeln!(dot, "  \"{}\" -> \"{previous_certificate_id}\";", certificate.id);
+            }
+        }
+
+        let _ = writeln!(dot, "}}");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::{
+        ledger::narwhal::batch_certificate::test_helpers::sample_batch_certificate_for_round,
+        prelude::MainnetV0,
+        utilities::TestRng,
+    };
+
+    type CurrentNetwork = MainnetV0;
+
+    #[test]
+    fn test_to_dot() {
+        let rng = &mut TestRng::default();
+
+        // Sample certificates for two rounds.
+        let certificate_1 = sample_batch_certificate_for_round(1, rng);
+        let certificate_2 = sample_batch_certificate_for_round(2, rng);
+
+        let mut exported_1 = ExportedCertificate::<CurrentNetwork>::new(&certificate_1);
+        exported_1.is_committed = true;
+        let mut exported_2 = ExportedCertificate::<CurrentNetwork>::new(&certificate_2);
+        exported_2.is_leader = true;
+        exported_2.in_dag = true;
+
+        let export = DAGExport {
+            start_round: 1,
+            end_round: 2,
+            last_committed_round: 1,
+            certificates: vec![exported_1, exported_2],
+        };
+        let dot = export.to_dot();
+
+        // Ensure every round has a cluster.
+        assert!(dot.starts_with("digraph dag {"));
+        assert!(dot.contains("subgraph cluster_round_1 {"));
+        assert!(dot.contains("subgraph cluster_round_2 {"));
+        // Ensure the styles are set.
+        assert!(dot.contains("style=\"filled,dashed\""));
+        assert!(dot.contains("style=\"bold\""));
+        // Ensure every edge is present.
+        for previous_certificate_id in certificate_2.previous_certificate_ids() {
+            assert!(dot.contains(&format!("\"{}\" -> \"{previous_certificate_id}\";", certificate_2.id())));
+        }
+    }
+}
@@ -12,18 +12,38 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod address_book;
+pub use address_book::*;
+
+#[cfg(feature = "adversary")]
+pub mod adversary;
+#[cfg(feature = "adversary")]
+pub use adversary::*;
+
 pub mod cache;
 pub use cache::*;
 
 pub mod channels;
 pub use channels::*;
 
+pub mod committee_tracker;
+pub use committee_tracker::*;
+
 pub mod dag;
 pub use dag::*;
 
+pub mod dag_export;
+pub use dag_export::*;
+
+pub mod participation;
+pub use participation::*;
+
 pub mod partition;
 pub use partition::*;
 
+pub mod peer_scores;
+pub use peer_scores::*;
+
 pub mod pending;
 pub use pending::*;
 
@@ -39,6 +59,9 @@ pub use ready::*;
 pub mod resolver;
 pub use resolver::*;
 
+pub mod rng;
+pub use rng::*;
+
 pub mod signed_proposals;
 pub use signed_proposals::*;
 
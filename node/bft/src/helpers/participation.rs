@@ -0,0 +1,339 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::helpers::Proposal;
+use snarkvm::{console::account::Address, ledger::committee::Committee, prelude::Network};
+
+use indexmap::{IndexMap, IndexSet};
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// The participation statistics of a single validator, as observed by this node.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ValidatorParticipation {
+    /// The number of batches proposed by the validator.
+    pub batches_proposed: u64,
+    /// The number of batches by the validator that were certified.
+    pub batches_certified: u64,
+    /// The number of signatures requested from the validator for our proposals.
+    pub signatures_requested: u64,
+    /// The number of signatures received from the validator for our proposals.
+    pub signatures_received: u64,
+    /// The number of leader certificates by the validator that were committed.
+    pub leader_certificates_committed: u64,
+    /// The number of rounds the validator was the leader, and the round advanced without its certificate.
+    pub leader_certificates_skipped: u64,
+    /// The number of certified batches by the validator that were included in the certification latency.
+    pub certified_batches_measured: u64,
+    /// The mean time (in seconds) between a batch being proposed and it being certified, excluding synced certificates.
+    pub mean_certification_latency_secs: f64,
+}
+
+/// The participation record of a single round, as observed by this node.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(bound = "")]
+pub struct RoundParticipation<N: Network> {
+    /// The validators that proposed a batch in the round.
+    pub proposers: IndexSet<Address<N>>,
+    /// The validators whose batch was certified in the round.
+    pub certified: IndexSet<Address<N>>,
+    /// The validators that signed our proposal in the round.
+    pub signers: IndexSet<Address<N>>,
+    /// The validators that did not sign our proposal in the round.
+    pub nonsigners: IndexSet<Address<N>>,
+    /// The leader of the round, if the round is even and the leader was determined.
+    pub leader: Option<Address<N>>,
+    /// Whether the leader certificate was committed (`Some(true)`), skipped (`Some(false)`), or is pending (`None`).
+    pub leader_committed: Option<bool>,
+}
+
+/// The per-validator and per-round participation statistics of the BFT.
+#[derive(Debug)]
+pub struct ParticipationStats<N: Network> {
+    /// The participation statistics for each validator.
+    validators: RwLock<IndexMap<Address<N>, ValidatorParticipation>>,
+    /// The participation records for the most recent rounds (up to GC).
+    rounds: RwLock<BTreeMap<u64, RoundParticipation<N>>>,
+    /// The maximum number of rounds to keep participation records for.
+    max_gc_rounds: u64,
+}
+
+impl<N: Network> ParticipationStats<N> {
+    /// Initializes a new instance of the participation statistics.
+    pub fn new(max_gc_rounds: u64) -> Self {
+        Self { validators: Default::default(), rounds: Default::default(), max_gc_rounds }
+    }
+
+    /// Returns the participation statistics for each validator.
+    pub fn validators(&self) -> IndexMap<Address<N>, ValidatorParticipation> {
+        self.validators.read().clone()
+    }
+
+    /// Returns the participation statistics for the given validator, if they exist.
+    pub fn get_validator(&self, address: &Address<N>) -> Option<ValidatorParticipation> {
+        self.validators.read().get(address).copied()
+    }
+
+    /// Returns the participation records for the most recent rounds.
+    pub fn rounds(&self) -> BTreeMap<u64, RoundParticipation<N>> {
+        self.rounds.read().clone()
+    }
+
+    /// Returns the participation record for the given round, if it exists.
+    pub fn get_round(&self, round: u64) -> Option<RoundParticipation<N>> {
+        self.rounds.read().get(&round).cloned()
+    }
+}
+
+impl<N: Network> ParticipationStats<N> {
+    /// Records that the given author proposed a batch in the given round.
+    pub fn record_batch_proposed(&self, round: u64, author: Address<N>) {
+        // Insert the author into the round, skipping if the proposal was already recorded.
+        if !self.update_round(round, |record| record.proposers.insert(author)) {
+            return;
+        }
+        // Update the validator statistics.
+        self.validators.write().entry(author).or_default().batches_proposed += 1;
+    }
+
+    /// Records that the batch of the given author was certified in the given round.
+    ///
+    /// If `latency` is given, it is the time (in seconds) between the batch header timestamp
+    /// and the certificate being received, and is included in the certification latency.
+    /// Synced certificates should pass `None`, as the time they were received says nothing about the author.
+    pub fn record_batch_certified(&self, round: u64, author: Address<N>, latency: Option<i64>) {
+        // Insert the author into the round, skipping if the certificate was already recorded.
+        if !self.update_round(round, |record| record.certified.insert(author)) {
+            return;
+        }
+        // Update the validator statistics.
+        let mut validators = self.validators.write();
+        let stats = validators.entry(author).or_default();
+        stats.batches_certified += 1;
+
+        // Update the certification latency, if it was measured.
+        if let Some(latency) = latency {
+            let latency = latency.max(0) as f64;
+            stats.certified_batches_measured += 1;
+            stats.mean_certification_latency_secs +=
+                (latency - stats.mean_certification_latency_secs) / stats.certified_batches_measured as f64;
+
+            #[cfg(feature = "metrics")]
+            metrics::histogram(metrics::bft::CERTIFICATION_LATENCY, latency);
+        }
+    }
+
+    /// Records the signatures that were requested and received for our proposal,
+    /// once the proposal is either certified or expired.
+    pub fn record_proposal_signatures(&self, proposal: &Proposal<N>, committee: &Committee<N>) {
+        // Retrieve the proposal round.
+        let round = proposal.round();
+        // Retrieve the proposal author.
+        let author = proposal.batch_header().author();
+        // Determine the signers and non-signers of the proposal.
+        let nonsigners = proposal.nonsigners(committee);
+        let signers = committee
+            .members()
+            .keys()
+            .filter(|address| **address != author && !nonsigners.contains(*address))
+            .copied()
+            .collect::<IndexSet<_>>();
+
+        // Update the round, skipping if the signatures were already recorded.
+        let is_new = self.update_round(round, |record| {
+            if !record.signers.is_empty() || !record.nonsigners.is_empty() {
+                return false;
+            }
+            record.signers = signers.clone();
+            record.nonsigners = nonsigners.iter().copied().collect();
+            true
+        });
+        if !is_new {
+            return;
+        }
+
+        // Update the validator statistics.
+        let mut validators = self.validators.write();
+        for address in nonsigners.iter() {
+            validators.entry(*address).or_default().signatures_requested += 1;
+        }
+        for address in signers.iter() {
+            let stats = validators.entry(*address).or_default();
+            stats.signatures_requested += 1;
+            stats.signatures_received += 1;
+        }
+
+        #[cfg(feature = "metrics")]
+        {
+            metrics::increment_counter_by(
+                metrics::bft::PROPOSAL_SIGNATURES_REQUESTED,
+                (signers.len() + nonsigners.len()) as u64,
+            );
+            metrics::increment_counter_by(metrics::bft::PROPOSAL_SIGNATURES_RECEIVED, signers.len() as u64);
+        }
+    }
+
+    /// Records that the leader certificate for the given even round was committed.
+    pub fn record_leader_committed(&self, round: u64, leader: Address<N>) {
+        // Update the round, retrieving the previous outcome.
+        let mut previous = None;
+        let is_new = self.update_round(round, |record| {
+            previous = record.leader_committed;
+            record.leader = Some(leader);
+            record.leader_committed = Some(true);
+            previous != Some(true)
+        });
+        if !is_new {
+            return;
+        }
+        // Update the validator statistics.
+        let mut validators = self.validators.write();
+        let stats = validators.entry(leader).or_default();
+        // Note: A skipped leader certificate may still be committed later, through a path from a subsequent leader.
+        if previous == Some(false) {
+            stats.leader_certificates_skipped = stats.leader_certificates_skipped.saturating_sub(1);
+        }
+        stats.leader_certificates_committed += 1;
+
+        #[cfg(feature = "metrics")]
+        metrics::increment_counter(metrics::bft::LEADERS_COMMITTED);
+    }
+
+    /// Records that the given even round advanced without the certificate of the given leader.
+    pub fn record_leader_skipped(&self, round: u64, leader: Address<N>) {
+        // Update the round, skipping if the leader outcome was already recorded.
+        let is_new = self.update_round(round, |record| {
+            if record.leader_committed.is_some() {
+                return false;
+            }
+            record.leader = Some(leader);
+            record.leader_committed = Some(false);
+            true
+        });
+        if !is_new {
+            return;
+        }
+        // Update the validator statistics.
+        self.validators.write().entry(leader).or_default().leader_certificates_skipped += 1;
+
+        #[cfg(feature = "metrics")]
+        metrics::increment_counter(metrics::bft::LEADERS_SKIPPED);
+    }
+}
+
+impl<N: Network> ParticipationStats<N> {
+    /// Updates the record for the given round with the given closure, returning the closure's result.
+    /// Rounds that are at or below the GC round are ignored, and return `false`.
+    fn update_round(&self, round: u64, f: impl FnOnce(&mut RoundParticipation<N>) -> bool) -> bool {
+        let mut rounds = self.rounds.write();
+        // Determine the latest round.
+        let latest_round = rounds.keys().next_back().copied().unwrap_or_default().max(round);
+        // Compute the GC round.
+        let gc_round = latest_round.saturating_sub(self.max_gc_rounds);
+        // If the round is at or below the GC round, skip it.
+        if round <= gc_round && gc_round > 0 {
+            return false;
+        }
+        // Update the record for the round.
+        let result = f(rounds.entry(round).or_default());
+        // Remove the records for rounds at or below the GC round.
+        rounds.retain(|r, _| *r > gc_round || gc_round == 0);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::{console::account::PrivateKey, prelude::MainnetV0, utilities::TestRng};
+
+    type CurrentNetwork = MainnetV0;
+
+    fn sample_address(rng: &mut TestRng) -> Address<CurrentNetwork> {
+        Address::try_from(PrivateKey::new(rng).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_record_batches() {
+        let rng = &mut TestRng::default();
+        let stats = ParticipationStats::<CurrentNetwork>::new(10);
+        let author = sample_address(rng);
+
+        // Record a proposal, twice.
+        stats.record_batch_proposed(1, author);
+        stats.record_batch_proposed(1, author);
+        assert_eq!(stats.get_validator(&author).unwrap().batches_proposed, 1);
+        assert_eq!(stats.get_validator(&author).unwrap().batches_certified, 0);
+
+        // Record the certificate, twice.
+        stats.record_batch_certified(1, author, Some(4));
+        stats.record_batch_certified(1, author, Some(8));
+        let validator = stats.get_validator(&author).unwrap();
+        assert_eq!(validator.batches_certified, 1);
+        assert_eq!(validator.mean_certification_latency_secs, 4.0);
+        assert!(stats.get_round(1).unwrap().certified.contains(&author));
+
+        // Record a synced certificate, which is excluded from the certification latency.
+        stats.record_batch_certified(2, author, None);
+        let validator = stats.get_validator(&author).unwrap();
+        assert_eq!(validator.batches_certified, 2);
+        assert_eq!(validator.certified_batches_measured, 1);
+        assert_eq!(validator.mean_certification_latency_secs, 4.0);
+    }
+
+    #[test]
+    fn test_record_leaders() {
+        let rng = &mut TestRng::default();
+        let stats = ParticipationStats::<CurrentNetwork>::new(10);
+        let leader = sample_address(rng);
+
+        // Skip the leader, twice.
+        stats.record_leader_skipped(2, leader);
+        stats.record_leader_skipped(2, leader);
+        let validator = stats.get_validator(&leader).unwrap();
+        assert_eq!(validator.leader_certificates_skipped, 1);
+        assert_eq!(validator.leader_certificates_committed, 0);
+        assert_eq!(stats.get_round(2).unwrap().leader_committed, Some(false));
+
+        // Commit the skipped leader certificate.
+        stats.record_leader_committed(2, leader);
+        stats.record_leader_committed(2, leader);
+        let validator = stats.get_validator(&leader).unwrap();
+        assert_eq!(validator.leader_certificates_skipped, 0);
+        assert_eq!(validator.leader_certificates_committed, 1);
+
+        // Skipping a committed leader certificate has no effect.
+        stats.record_leader_skipped(2, leader);
+        assert_eq!(stats.get_round(2).unwrap().leader_committed, Some(true));
+    }
+
+    #[test]
+    fn test_garbage_collection() {
+        let rng = &mut TestRng::default();
+        let stats = ParticipationStats::<CurrentNetwork>::new(2);
+        let author = sample_address(rng);
+
+        stats.record_batch_proposed(1, author);
+        stats.record_batch_proposed(2, author);
+        stats.record_batch_proposed(5, author);
+        // Rounds at or below the GC round are removed.
+        assert_eq!(stats.rounds().keys().copied().collect::<Vec<_>>(), vec![5]);
+        // Rounds at or below the GC round are ignored.
+        stats.record_batch_proposed(3, author);
+        assert!(stats.get_round(3).is_none());
+        // The validator statistics are retained.
+        assert_eq!(stats.get_validator(&author).unwrap().batches_proposed, 3);
+    }
+}
@@ -0,0 +1,242 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::MAX_FETCH_TIMEOUT_IN_MS;
+use snarkos_node_bft_events::PingTiming;
+
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::{collections::HashMap, net::SocketAddr, time::Instant};
+
+/// The round-trip time assumed for a validator that has not been measured yet, in milliseconds.
+const DEFAULT_RTT_IN_MS: f64 = 200.0;
+/// The weight of a new sample in the smoothed round-trip time and success rate.
+const SMOOTHING_FACTOR: f64 = 0.125;
+/// The lowest success rate used to compute the expected latency, so that it stays bounded.
+const MIN_SUCCESS_RATE: f64 = 0.05;
+/// The expected latency above which a fetch request is hedged, in milliseconds.
+pub const HEDGE_THRESHOLD_IN_MS: f64 = 500.0;
+/// The maximum number of hedged requests sent for a single fetch.
+pub const MAX_HEDGED_REQUESTS: usize = 2;
+
+/// The score of a connected validator, used to select the targets of fetch requests.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub struct PeerScore {
+    /// The smoothed round-trip time, in milliseconds, if it was measured.
+    pub rtt_ms: Option<f64>,
+    /// The smoothed rate of fetch requests that were fulfilled by the validator.
+    pub success_rate: f64,
+    /// The number of fetch requests fulfilled by the validator.
+    pub num_successes: u64,
+    /// The number of fetch requests that the validator failed to fulfill in time.
+    pub num_failures: u64,
+    /// Whether the validator sends and echoes the ping timing.
+    #[serde(skip)]
+    supports_timing: bool,
+    /// The `sent_at` of the latest ping received from the validator, and the instant it was received.
+    #[serde(skip)]
+    last_ping: Option<(u64, Instant)>,
+}
+
+impl Default for PeerScore {
+    fn default() -> Self {
+        Self {
+            rtt_ms: None,
+            success_rate: 1.0,
+            num_successes: 0,
+            num_failures: 0,
+            supports_timing: false,
+            last_ping: None,
+        }
+    }
+}
+
+impl PeerScore {
+    /// Returns the expected latency of a fetch request to the validator, in milliseconds.
+    /// An unreliable validator is penalized, as a request to it is likely to be retried.
+    pub fn expected_latency_ms(&self) -> f64 {
+        self.rtt_ms.unwrap_or(DEFAULT_RTT_IN_MS) / self.success_rate.max(MIN_SUCCESS_RATE)
+    }
+}
+
+/// The scores of the connected validators, keyed by peer IP.
+#[derive(Debug)]
+pub struct PeerScores {
+    /// The map of `peer IP` to `score` for the connected validators.
+    scores: RwLock<HashMap<SocketAddr, PeerScore>>,
+    /// The instant from which the `sent_at` of the outgoing pings is measured.
+    start: Instant,
+}
+
+impl Default for PeerScores {
+    /// Initializes a new instance of the peer scores.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PeerScores {
+    /// Initializes a new instance of the peer scores.
+    pub fn new() -> Self {
+        Self { scores: Default::default(), start: Instant::now() }
+    }
+
+    /// Inserts the given peer IP, once the validator is connected.
+    pub fn insert_peer(&self, peer_ip: SocketAddr) {
+        self.scores.write().entry(peer_ip).or_default();
+    }
+
+    /// Removes the given peer IP, once the validator is disconnected.
+    pub fn remove_peer(&self, peer_ip: SocketAddr) {
+        self.scores.write().remove(&peer_ip);
+    }
+
+    /// Returns the score of the given peer IP, if the validator is connected.
+    pub fn get(&self, peer_ip: SocketAddr) -> Option<PeerScore> {
+        self.scores.read().get(&peer_ip).copied()
+    }
+
+    /// Returns the scores of the connected validators.
+    pub fn to_map(&self) -> HashMap<SocketAddr, PeerScore> {
+        self.scores.read().clone()
+    }
+
+    /// Returns the timing to include in the next ping to the given peer IP,
+    /// or `None` if the validator does not support it.
+    pub fn ping_timing(&self, peer_ip: SocketAddr) -> Option<PingTiming> {
+        let score = self.get(peer_ip).filter(|score| score.supports_timing)?;
+        let echo = score.last_ping.map(|(sent_at, received_at)| (sent_at, received_at.elapsed().as_millis() as u64));
+        Some(PingTiming { sent_at: self.start.elapsed().as_millis() as u64, echo })
+    }
+
+    /// Processes a ping from the given peer IP, updating the round-trip time if the ping echoes our own ping.
+    pub fn process_ping(&self, peer_ip: SocketAddr, supports_timing: bool, timing: Option<PingTiming>) {
+        let now = self.start.elapsed().as_millis() as u64;
+        let mut scores = self.scores.write();
+        let Some(score) = scores.get_mut(&peer_ip) else {
+            return;
+        };
+        score.supports_timing = supports_timing;
+        let Some(timing) = timing else {
+            return;
+        };
+        score.last_ping = Some((timing.sent_at, Instant::now()));
+        // Compute the round-trip time, excluding the time the ping was held by the peer.
+        if let Some((sent_at, delay)) = timing.echo {
+            // Ensure the echo is plausible, as the peer may echo an arbitrary value.
+            if let Some(rtt) = now.checked_sub(sent_at).and_then(|elapsed| elapsed.checked_sub(delay)) {
+                if rtt <= MAX_FETCH_TIMEOUT_IN_MS {
+                    score.rtt_ms = Some(match score.rtt_ms {
+                        Some(rtt_ms) => rtt_ms + SMOOTHING_FACTOR * (rtt as f64 - rtt_ms),
+                        None => rtt as f64,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Records the outcome of a fetch request to the given peer IP.
+    pub fn record_fetch(&self, peer_ip: SocketAddr, is_success: bool) {
+        if let Some(score) = self.scores.write().get_mut(&peer_ip) {
+            let outcome = if is_success { 1.0 } else { 0.0 };
+            score.success_rate += SMOOTHING_FACTOR * (outcome - score.success_rate);
+            match is_success {
+                true => score.num_successes += 1,
+                false => score.num_failures += 1,
+            }
+        }
+    }
+
+    /// Returns the peer IPs to hedge a fetch request to the given peer IP with, best first.
+    ///
+    /// A request is hedged if the expected latency of the peer exceeds the threshold, to the validators
+    /// that are expected to be faster, excluding the given peer IPs that were already sent a request.
+    pub fn hedge_targets(&self, peer_ip: SocketAddr, exclude: impl Fn(SocketAddr) -> bool) -> Vec<SocketAddr> {
+        let scores = self.scores.read();
+        // Determine the expected latency of the peer.
+        let latency = scores.get(&peer_ip).map_or(DEFAULT_RTT_IN_MS, PeerScore::expected_latency_ms);
+        if latency <= HEDGE_THRESHOLD_IN_MS {
+            return Vec::new();
+        }
+        // Rank the validators that are expected to be faster.
+        let mut candidates = scores
+            .iter()
+            .filter(|(ip, _)| **ip != peer_ip && !exclude(**ip))
+            .map(|(ip, score)| (*ip, score.expected_latency_ms()))
+            .filter(|(_, candidate_latency)| *candidate_latency < latency)
+            .collect::<Vec<_>>();
+        candidates.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        candidates.into_iter().take(MAX_HEDGED_REQUESTS).map(|(ip, _)| ip).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_peer_ip(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn test_ping_rtt() {
+        let scores = PeerScores::new();
+        let peer_ip = sample_peer_ip(1);
+
+        // Ensure the ping timing is only sent to connected peers that support it.
+        assert!(scores.ping_timing(peer_ip).is_none());
+        scores.insert_peer(peer_ip);
+        scores.process_ping(peer_ip, false, None);
+        assert!(scores.ping_timing(peer_ip).is_none());
+        scores.process_ping(peer_ip, true, Some(PingTiming { sent_at: 42, echo: None }));
+        let timing = scores.ping_timing(peer_ip).unwrap();
+        assert_eq!(timing.echo.unwrap().0, 42);
+
+        // Ensure an echo of our ping updates the round-trip time.
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        scores.process_ping(peer_ip, true, Some(PingTiming { sent_at: 43, echo: Some((timing.sent_at, 0)) }));
+        let rtt_ms = scores.get(peer_ip).unwrap().rtt_ms.unwrap();
+        assert!((20.0..MAX_FETCH_TIMEOUT_IN_MS as f64).contains(&rtt_ms));
+
+        // Ensure an implausible echo is ignored.
+        scores.process_ping(peer_ip, true, Some(PingTiming { sent_at: 44, echo: Some((u64::MAX, 0)) }));
+        assert_eq!(scores.get(peer_ip).unwrap().rtt_ms, Some(rtt_ms));
+    }
+
+    #[test]
+    fn test_hedge_targets() {
+        let scores = PeerScores::new();
+        let (slow, fast, faster, excluded) =
+            (sample_peer_ip(1), sample_peer_ip(2), sample_peer_ip(3), sample_peer_ip(4));
+        for peer_ip in [slow, fast, faster, excluded] {
+            scores.insert_peer(peer_ip);
+        }
+
+        // Ensure a reliable peer is not hedged.
+        assert!(scores.hedge_targets(slow, |_| false).is_empty());
+
+        // Ensure an unreliable peer is hedged with the faster peers, best first.
+        for _ in 0..10 {
+            scores.record_fetch(slow, false);
+            scores.record_fetch(fast, false);
+            scores.record_fetch(fast, true);
+            scores.record_fetch(faster, true);
+        }
+        assert_eq!(scores.hedge_targets(slow, |ip| ip == excluded), vec![faster, fast]);
+
+        // Ensure the disconnected peers are no longer hedged with.
+        scores.remove_peer(faster);
+        assert_eq!(scores.hedge_targets(slow, |ip| ip == excluded), vec![fast]);
+    }
+}
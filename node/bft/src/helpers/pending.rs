@@ -12,7 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::MAX_FETCH_TIMEOUT_IN_MS;
+use crate::{
+    helpers::{now, PeerScores},
+    MAX_FETCH_TIMEOUT_IN_MS,
+};
 use snarkos_node_bft_ledger_service::LedgerService;
 use snarkvm::{console::network::Network, ledger::committee::Committee};
 
@@ -23,7 +26,6 @@ use std::{
     net::SocketAddr,
     sync::Arc,
 };
-use time::OffsetDateTime;
 use tokio::sync::oneshot;
 
 /// The maximum number of seconds to wait before expiring a callback.
@@ -103,7 +105,7 @@ impl<T: Copy + Clone + PartialEq + Eq + Hash, V: Clone> Pending<T, V> {
     /// Returns the number of pending callbacks for the specified `item`.
     pub fn num_callbacks(&self, item: impl Into<T>) -> usize {
         let item = item.into();
-        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let now = now();
         // Clear the callbacks that have expired.
         self.clear_expired_callbacks_for_item(now, item);
         // Return the number of live callbacks.
@@ -113,7 +115,7 @@ impl<T: Copy + Clone + PartialEq + Eq + Hash, V: Clone> Pending<T, V> {
     /// Returns the number of pending sent requests for the specified `item`.
     pub fn num_sent_requests(&self, item: impl Into<T>) -> usize {
         let item = item.into();
-        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let now = now();
         // Clear the callbacks that have expired.
         self.clear_expired_callbacks_for_item(now, item);
         // Return the number of live callbacks.
@@ -135,7 +137,7 @@ impl<T: Copy + Clone + PartialEq + Eq + Hash, V: Clone> Pending<T, V> {
         callback: Option<(oneshot::Sender<V>, bool)>,
     ) -> bool {
         let item = item.into();
-        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let now = now();
         // Insert the peer IP and optional callback into the pending queue.
         let result = {
             // Acquire the pending lock.
@@ -165,6 +167,32 @@ impl<T: Copy + Clone + PartialEq + Eq + Hash, V: Clone> Pending<T, V> {
         result
     }
 
+    /// Selects the peer IPs to hedge the sent request for the specified `item` to the specified `peer IP` with,
+    /// and inserts them into the pending queue with a sent request. At most `num_redundant_requests` requests
+    /// are pending, and each peer can only receive one request at a time.
+    ///
+    /// Note: The hedged requests have no callback receiver, as the response fulfills the callbacks of the `item`.
+    pub fn hedge(
+        &self,
+        item: impl Into<T>,
+        peer_ip: SocketAddr,
+        peer_scores: &PeerScores,
+        num_redundant_requests: usize,
+    ) -> Vec<SocketAddr> {
+        let item = item.into();
+        // Determine how many more requests may be sent.
+        let num_remaining_requests = num_redundant_requests.saturating_sub(self.num_sent_requests(item));
+        // Select the validators that are expected to respond faster than the peer.
+        let mut peer_ips = peer_scores.hedge_targets(peer_ip, |ip| self.contains_peer_with_sent_request(item, ip));
+        peer_ips.truncate(num_remaining_requests);
+        // Insert the hedged requests into the pending queue.
+        for hedge_ip in &peer_ips {
+            let (callback_sender, _) = oneshot::channel();
+            self.insert(item, *hedge_ip, Some((callback_sender, true)));
+        }
+        peer_ips
+    }
+
     /// Removes the specified `item` from the pending queue.
     /// If the `item` exists and is removed, the peer IPs are returned.
     /// If the `item` does not exist, `None` is returned.
@@ -215,7 +243,7 @@ impl<T: Copy + Clone + PartialEq + Eq + Hash, V: Clone> Pending<T, V> {
 
     /// Removes the callbacks for all items have that expired.
     pub fn clear_expired_callbacks(&self) {
-        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let now = now();
         // Acquire the pending lock once for write access.
         let mut pending = self.pending.write();
 
@@ -425,6 +453,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_hedge() {
+        let rng = &mut TestRng::default();
+
+        // Initialize the pending queue and the peer scores.
+        let pending = Pending::<TransmissionID<CurrentNetwork>, ()>::new();
+        let peer_scores = PeerScores::new();
+
+        // Initialize the solution ID.
+        let solution_id = TransmissionID::Solution(
+            rng.gen::<u64>().into(),
+            rng.gen::<<CurrentNetwork as Network>::TransmissionChecksum>(),
+        );
+
+        // Initialize a slow peer, and faster peers.
+        let slow = SocketAddr::from(([127, 0, 0, 1], 1234));
+        let fast = [2345, 3456, 4567].map(|port| SocketAddr::from(([127, 0, 0, 1], port)));
+        peer_scores.insert_peer(slow);
+        fast.iter().for_each(|peer_ip| peer_scores.insert_peer(*peer_ip));
+
+        // Send a request to the slow peer.
+        let (callback_sender, _) = oneshot::channel();
+        assert!(pending.insert(solution_id, slow, Some((callback_sender, true))));
+
+        // Ensure the request is not hedged while the peer is reliable.
+        assert!(pending.hedge(solution_id, slow, &peer_scores, 3).is_empty());
+
+        // Ensure the request is hedged once the peer is unreliable, within the redundancy bound.
+        (0..10).for_each(|_| peer_scores.record_fetch(slow, false));
+        let hedged = pending.hedge(solution_id, slow, &peer_scores, 2);
+        assert_eq!(hedged.len(), 1);
+        assert!(pending.contains_peer_with_sent_request(solution_id, hedged[0]));
+        assert_eq!(pending.num_sent_requests(solution_id), 2);
+
+        // Ensure a peer is not sent another request.
+        let hedged_again = pending.hedge(solution_id, slow, &peer_scores, 4);
+        assert_eq!(hedged_again.len(), 2);
+        assert!(!hedged_again.contains(&hedged[0]));
+        assert_eq!(pending.num_sent_requests(solution_id), 4);
+    }
+
     #[test]
     fn test_expired_items() {
         let rng = &mut TestRng::default();
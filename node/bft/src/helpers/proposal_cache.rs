@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::helpers::{Proposal, SignedProposals};
+use snarkos_node_persistence::{decode_file, encode_file, node_file_path, write_atomically};
 
 use snarkvm::{
     console::{account::Address, network::Network, program::SUBDAG_CERTIFICATES_DEPTH},
@@ -20,25 +21,25 @@ use snarkvm::{
     prelude::{anyhow, bail, error, FromBytes, IoResult, Read, Result, ToBytes, Write},
 };
 
-use aleo_std::{aleo_ledger_dir, StorageMode};
+use aleo_std::StorageMode;
 use indexmap::IndexSet;
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The magic bytes at the start of a proposal cache file.
+const PROPOSAL_CACHE_MAGIC: [u8; 8] = *b"SNOSPROP";
+/// The current version of the proposal cache file format.
+///
+/// Version 0 is the legacy format, which is the payload without a header.
+pub const PROPOSAL_CACHE_VERSION: u16 = 1;
 
 /// Returns the path where a proposal cache file may be stored.
 pub fn proposal_cache_path(network: u16, dev: Option<u16>) -> PathBuf {
     const PROPOSAL_CACHE_FILE_NAME: &str = "current-proposal-cache";
 
-    // Obtain the path to the ledger.
-    let mut path = aleo_ledger_dir(network, StorageMode::from(dev));
-    // Go to the folder right above the ledger.
-    path.pop();
-    // Append the proposal store's file name.
-    match dev {
-        Some(id) => path.push(&format!(".{PROPOSAL_CACHE_FILE_NAME}-{}-{}", network, id)),
-        None => path.push(&format!("{PROPOSAL_CACHE_FILE_NAME}-{}", network)),
-    }
-
-    path
+    node_file_path(PROPOSAL_CACHE_FILE_NAME, network, &StorageMode::from(dev))
 }
 
 /// A helper type for the cache of proposal and signed proposals.
@@ -82,18 +83,13 @@ impl<N: Network> ProposalCache<N> {
     }
 
     /// Load the proposal cache from the file system and ensure that the proposal cache is valid.
+    /// A proposal cache in an older format is migrated to the current format.
     pub fn load(expected_signer: Address<N>, dev: Option<u16>) -> Result<Self> {
         // Construct the proposal cache file system path.
         let path = proposal_cache_path(N::ID, dev);
 
         // Deserialize the proposal cache from the file system.
-        let proposal_cache = match fs::read(&path) {
-            Ok(bytes) => match Self::from_bytes_le(&bytes) {
-                Ok(proposal_cache) => proposal_cache,
-                Err(_) => bail!("Couldn't deserialize the proposal stored at {}", path.display()),
-            },
-            Err(_) => bail!("Couldn't read the proposal stored at {}", path.display()),
-        };
+        let (proposal_cache, version) = Self::read_from(&path)?;
 
         // Ensure the proposal cache is valid.
         if !proposal_cache.is_valid(expected_signer) {
@@ -102,29 +98,95 @@ impl<N: Network> ProposalCache<N> {
 
         info!("Loaded the proposal cache from {} at round {}", path.display(), proposal_cache.latest_round);
 
+        // Migrate the proposal cache, if it was stored in an older format.
+        if version < PROPOSAL_CACHE_VERSION {
+            info!("Migrating the proposal cache from version {version} to version {PROPOSAL_CACHE_VERSION}...");
+            proposal_cache.store(dev)?;
+        }
+
         Ok(proposal_cache)
     }
 
+    /// Reads the proposal cache from the given file, returning the proposal cache and the version of its format.
+    /// Note: This does not check that the proposal cache is valid for a given signer.
+    pub fn read_from(path: &Path) -> Result<(Self, u16)> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) => bail!("Couldn't read the proposal cache stored at {} - {err}", path.display()),
+        };
+        Self::from_file_bytes(&bytes)
+            .map_err(|err| anyhow!("Couldn't deserialize the proposal cache stored at {} - {err}", path.display()))
+    }
+
     /// Store the proposal cache to the file system.
+    ///
+    /// The proposal cache is written to a temporary file which is then renamed,
+    /// so that an interrupted write never leaves a partial proposal cache behind.
     pub fn store(&self, dev: Option<u16>) -> Result<()> {
         let path = proposal_cache_path(N::ID, dev);
         info!("Storing the proposal cache to {}...", path.display());
 
         // Serialize the proposal cache.
-        let bytes = self.to_bytes_le()?;
+        let bytes = self.to_file_bytes()?;
         // Store the proposal cache to the file system.
-        fs::write(&path, bytes)
+        write_atomically(&path, &bytes)
             .map_err(|err| anyhow!("Couldn't write the proposal cache to {} - {err}", path.display()))?;
 
         Ok(())
     }
 
+    /// Returns the latest round this node was on prior to the reboot.
+    pub const fn latest_round(&self) -> u64 {
+        self.latest_round
+    }
+
+    /// Returns the latest proposal this node has created.
+    pub const fn proposal(&self) -> Option<&Proposal<N>> {
+        self.proposal.as_ref()
+    }
+
+    /// Returns the signed proposals this node has received.
+    pub const fn signed_proposals(&self) -> &SignedProposals<N> {
+        &self.signed_proposals
+    }
+
+    /// Returns the pending certificates that have not been included in the ledger.
+    pub const fn pending_certificates(&self) -> &IndexSet<BatchCertificate<N>> {
+        &self.pending_certificates
+    }
+
     /// Returns the latest round, proposal, signed proposals, and pending certificates.
     pub fn into(self) -> (u64, Option<Proposal<N>>, SignedProposals<N>, IndexSet<BatchCertificate<N>>) {
         (self.latest_round, self.proposal, self.signed_proposals, self.pending_certificates)
     }
 }
 
+impl<N: Network> ProposalCache<N> {
+    /// Returns the contents of a proposal cache file, which is the header followed by the payload.
+    fn to_file_bytes(&self) -> Result<Vec<u8>> {
+        Ok(encode_file(&PROPOSAL_CACHE_MAGIC, PROPOSAL_CACHE_VERSION, &self.to_bytes_le()?))
+    }
+
+    /// Returns the proposal cache and the version of its format, from the contents of a proposal cache file.
+    fn from_file_bytes(bytes: &[u8]) -> Result<(Self, u16)> {
+        // A file without the magic is in the legacy format, which is the payload without a header.
+        if !bytes.starts_with(&PROPOSAL_CACHE_MAGIC) {
+            return Ok((Self::migrate(0, bytes)?, 0));
+        }
+        let (version, payload) = decode_file(&PROPOSAL_CACHE_MAGIC, bytes)?;
+        Ok((Self::migrate(version, payload)?, version))
+    }
+
+    /// Deserializes the payload of the given version of the proposal cache file format.
+    fn migrate(version: u16, payload: &[u8]) -> Result<Self> {
+        match version {
+            // Note: The payload encoding has not changed since the header was introduced.
+            0 | PROPOSAL_CACHE_VERSION => Ok(Self::from_bytes_le(payload)?),
+            _ => bail!("Unsupported proposal cache version {version} (the latest is {PROPOSAL_CACHE_VERSION})"),
+        }
+    }
+}
+
 impl<N: Network> ToBytes for ProposalCache<N> {
     fn write_le<W: Write>(&self, mut writer: W) -> IoResult<()> {
         // Serialize the `latest_round`.
@@ -187,6 +249,7 @@ impl<N: Network> Default for ProposalCache<N> {
 mod tests {
     use super::*;
     use crate::helpers::{proposal::tests::sample_proposal, signed_proposals::tests::sample_signed_proposals};
+    use snarkos_node_bft_events::FILE_HEADER_SIZE;
     use snarkvm::{
         console::{account::PrivateKey, network::MainnetV0},
         ledger::narwhal::batch_certificate::test_helpers::sample_batch_certificates,
@@ -221,4 +284,45 @@ mod tests {
             assert_eq!(expected, ProposalCache::read_le(&expected_bytes[..]).unwrap());
         }
     }
+
+    #[test]
+    fn test_file_bytes() {
+        let rng = &mut TestRng::default();
+        let signer_private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+        let expected = sample_proposal_cache(&signer_private_key, rng);
+
+        // Ensure the current format roundtrips.
+        let file_bytes = expected.to_file_bytes().unwrap();
+        assert!(file_bytes.starts_with(&PROPOSAL_CACHE_MAGIC));
+        let (candidate, version) = ProposalCache::<CurrentNetwork>::from_file_bytes(&file_bytes).unwrap();
+        assert_eq!(expected, candidate);
+        assert_eq!(version, PROPOSAL_CACHE_VERSION);
+
+        // Ensure the legacy format is migrated.
+        let legacy_bytes = expected.to_bytes_le().unwrap();
+        let (candidate, version) = ProposalCache::<CurrentNetwork>::from_file_bytes(&legacy_bytes).unwrap();
+        assert_eq!(expected, candidate);
+        assert_eq!(version, 0);
+    }
+
+    #[test]
+    fn test_file_bytes_rejects_corruption() {
+        let rng = &mut TestRng::default();
+        let signer_private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
+        let file_bytes = sample_proposal_cache(&signer_private_key, rng).to_file_bytes().unwrap();
+
+        // Ensure a torn write is rejected.
+        for length in [PROPOSAL_CACHE_MAGIC.len(), FILE_HEADER_SIZE, file_bytes.len() - 1] {
+            assert!(ProposalCache::<CurrentNetwork>::from_file_bytes(&file_bytes[..length]).is_err());
+        }
+        // Ensure a corrupted payload is rejected.
+        let mut corrupted = file_bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(ProposalCache::<CurrentNetwork>::from_file_bytes(&corrupted).is_err());
+        // Ensure an unknown version is rejected.
+        let mut unknown_version = file_bytes;
+        unknown_version[PROPOSAL_CACHE_MAGIC.len()..PROPOSAL_CACHE_MAGIC.len() + 2]
+            .copy_from_slice(&(PROPOSAL_CACHE_VERSION + 1).to_le_bytes());
+        assert!(ProposalCache::<CurrentNetwork>::from_file_bytes(&unknown_version).is_err());
+    }
 }
@@ -14,7 +14,7 @@
 
 use crate::helpers::{check_timestamp_for_liveness, fmt_id};
 use snarkos_node_bft_ledger_service::LedgerService;
-use snarkos_node_bft_storage_service::StorageService;
+use snarkos_node_bft_storage_service::{RepairChecks, RepairReport, StorageService};
 use snarkvm::{
     ledger::{
         block::{Block, Transaction},
@@ -569,19 +569,12 @@ impl<N: Network> Storage<N> {
 
         // Insert the round to certificate ID entry.
         self.rounds.write().entry(round).or_default().insert((certificate_id, batch_id, author));
-        // Obtain the certificate's transmission ids.
-        let transmission_ids = certificate.transmission_ids().clone();
+        // Persist the certificate and the certificate ID for each of its transmissions, in one atomic batch.
+        self.transmissions.insert_certificate(&certificate, aborted_transmission_ids, missing_transmissions);
         // Insert the certificate.
         self.certificates.write().insert(certificate_id, certificate);
         // Insert the batch ID.
         self.batch_ids.write().insert(batch_id, round);
-        // Insert the certificate ID for each of the transmissions into storage.
-        self.transmissions.insert_transmissions(
-            certificate_id,
-            transmission_ids,
-            aborted_transmission_ids,
-            missing_transmissions,
-        );
     }
 
     /// Removes the given `certificate ID` from storage.
@@ -625,9 +618,63 @@ impl<N: Network> Storage<N> {
         self.batch_ids.write().swap_remove(&batch_id);
         // Remove the transmission entries in the certificate from storage.
         self.transmissions.remove_transmissions(&certificate_id, certificate.transmission_ids());
+        // Remove the persisted certificate.
+        self.transmissions.remove_certificate(&certificate_id);
         // Return successfully.
         true
     }
+
+    /// Returns the certificates persisted by the storage service that are not in storage, ordered by round.
+    ///
+    /// The persisted certificates at or below the GC round are removed from the storage service,
+    /// along with their references to the transmissions.
+    pub(crate) fn get_persisted_certificates(&self) -> Vec<BatchCertificate<N>> {
+        // Fetch the GC round.
+        let gc_round = self.gc_round();
+        // Retrieve the persisted certificates.
+        let mut certificates = Vec::new();
+        for certificate in self.transmissions.get_certificates() {
+            if certificate.round() <= gc_round {
+                // Remove the stale certificate from the storage service.
+                self.transmissions.remove_transmissions(&certificate.id(), certificate.transmission_ids());
+                self.transmissions.remove_certificate(&certificate.id());
+            } else if !self.contains_certificate(certificate.id()) {
+                certificates.push(certificate);
+            }
+        }
+        // Order the certificates by round, so that the previous certificates are restored first.
+        certificates.sort_by_key(|certificate| certificate.round());
+        certificates
+    }
+
+    /// Verifies the transmissions in the storage service against the certificates in storage and the ledger.
+    ///
+    /// The references to certificates that are neither in storage, nor persisted by the storage service, nor committed
+    /// in the ledger are removed, along with the transmissions and aborted transmission IDs that no longer reference
+    /// any certificate. If `dry_run` is set, or if no certificates are known (as the references cannot be verified
+    /// then), the storage service is only verified, and not modified.
+    pub fn repair_transmissions(&self, dry_run: bool) -> RepairReport {
+        // Retrieve the certificates persisted by the storage service, which may not be restored into memory yet.
+        let persisted_ids: HashSet<_> =
+            self.transmissions.get_certificates().iter().map(|certificate| certificate.id()).collect();
+        let is_known_certificate = |certificate_id: &Field<N>| {
+            self.contains_certificate(*certificate_id)
+                || persisted_ids.contains(certificate_id)
+                || self.ledger.contains_certificate(certificate_id).unwrap_or(false)
+        };
+        let is_in_ledger =
+            |transmission_id: &TransmissionID<N>| self.ledger.contains_transmission(transmission_id).unwrap_or(false);
+        // Skip the removals if no certificates are known.
+        let has_certificates = !persisted_ids.is_empty() || !self.certificates.read().is_empty();
+        if !dry_run && !has_certificates {
+            warn!("Skipping the removal of the orphaned transmissions, as no certificates are in storage");
+        }
+        self.transmissions.repair(&RepairChecks {
+            is_known_certificate: &is_known_certificate,
+            is_in_ledger: &is_in_ledger,
+            dry_run: dry_run || !has_certificates,
+        })
+    }
 }
 
 impl<N: Network> Storage<N> {
@@ -966,6 +1013,99 @@ pub(crate) mod tests {
         assert_storage(&storage, &[], &[], &[], &Default::default());
     }
 
+    #[test]
+    fn test_persisted_certificates() {
+        let rng = &mut TestRng::default();
+
+        // Sample a committee.
+        let committee = snarkvm::ledger::committee::test_helpers::sample_committee(rng);
+        // Initialize the ledger.
+        let ledger = Arc::new(MockLedgerService::new(committee));
+        // Initialize the storage service, which outlives the storage.
+        let service = Arc::new(BFTMemoryService::new());
+        // Initialize the storage.
+        let storage = Storage::<CurrentNetwork>::new(ledger.clone(), service.clone(), 1);
+
+        // Create a new certificate.
+        let certificate = snarkvm::ledger::narwhal::batch_certificate::test_helpers::sample_batch_certificate(rng);
+        // Construct the sample 'transmissions'.
+        let (missing_transmissions, _) = sample_transmissions(&certificate, rng);
+
+        // Insert the certificate.
+        storage.insert_certificate_atomic(certificate.clone(), Default::default(), missing_transmissions);
+        // Ensure the certificate is persisted, but not returned as it already exists in storage.
+        assert_eq!(service.get_certificates(), vec![certificate.clone()]);
+        assert!(storage.get_persisted_certificates().is_empty());
+
+        // Initialize a new storage with the same storage service, as on a restart.
+        let restarted = Storage::<CurrentNetwork>::new(ledger, service.clone(), 1);
+        // Ensure the persisted certificate is returned.
+        assert_eq!(restarted.get_persisted_certificates(), vec![certificate.clone()]);
+
+        // Remove the certificate.
+        assert!(storage.remove_certificate(certificate.id()));
+        // Ensure the certificate is no longer persisted.
+        assert!(service.get_certificates().is_empty());
+        assert!(restarted.get_persisted_certificates().is_empty());
+    }
+
+    #[test]
+    fn test_repair_transmissions() {
+        let rng = &mut TestRng::default();
+
+        // Sample a committee.
+        let committee = snarkvm::ledger::committee::test_helpers::sample_committee(rng);
+        // Initialize the ledger.
+        let ledger = Arc::new(MockLedgerService::new(committee));
+        // Initialize the storage service, which outlives the storage.
+        let service = Arc::new(BFTMemoryService::new());
+        // Initialize the storage.
+        let storage = Storage::<CurrentNetwork>::new(ledger.clone(), service.clone(), 1);
+
+        // Insert a certificate.
+        let certificate = snarkvm::ledger::narwhal::batch_certificate::test_helpers::sample_batch_certificate(rng);
+        let (missing_transmissions, transmissions) = sample_transmissions(&certificate, rng);
+        storage.insert_certificate_atomic(certificate.clone(), Default::default(), missing_transmissions);
+
+        // Ensure the storage is consistent.
+        let report = storage.repair_transmissions(false);
+        assert!(report.is_consistent());
+        assert_eq!(report.num_transmissions, transmissions.len());
+        assert_eq!(service.as_hashmap(), transmissions);
+
+        // Ensure the transmissions of a persisted certificate are kept, even if it is not restored in storage.
+        let restarted = Storage::<CurrentNetwork>::new(ledger.clone(), service.clone(), 1);
+        assert!(restarted.repair_transmissions(false).is_consistent());
+        assert_eq!(service.as_hashmap(), transmissions);
+
+        // Initialize a new storage with the same storage service, as after a crash that lost the certificate.
+        service.remove_certificate(&certificate.id());
+        let restarted = Storage::<CurrentNetwork>::new(ledger, service.clone(), 1);
+
+        // Ensure the orphans are not removed while no certificates are known, as the references cannot be verified.
+        let report = restarted.repair_transmissions(false);
+        assert_eq!(report.num_unconfirmed_orphans, transmissions.len());
+        assert_eq!(service.as_hashmap(), transmissions);
+
+        // Insert another certificate.
+        let other_certificate =
+            snarkvm::ledger::narwhal::batch_certificate::test_helpers::sample_batch_certificate(rng);
+        let (other_missing_transmissions, other_transmissions) = sample_transmissions(&other_certificate, rng);
+        restarted.insert_certificate_atomic(other_certificate, Default::default(), other_missing_transmissions);
+
+        // Ensure a dry run finds the orphans, but does not remove them.
+        let report = restarted.repair_transmissions(true);
+        assert!(!report.is_consistent());
+        assert_eq!(report.num_unconfirmed_orphans, transmissions.len());
+        assert_eq!(report.num_stale_references, transmissions.len());
+        assert_eq!(service.as_hashmap().len(), transmissions.len() + other_transmissions.len());
+
+        // Ensure the repair removes the orphans.
+        assert_eq!(restarted.repair_transmissions(false), report);
+        assert_eq!(service.as_hashmap(), other_transmissions);
+        assert!(restarted.repair_transmissions(false).is_consistent());
+    }
+
     #[test]
     fn test_certificate_duplicate() {
         let rng = &mut TestRng::default();
@@ -19,6 +19,11 @@ use time::OffsetDateTime;
 
 /// Returns the current UTC epoch timestamp.
 pub fn now() -> i64 {
+    // If a simulation is running, return the timestamp of its clock.
+    #[cfg(feature = "simulation")]
+    if let Some(timestamp) = crate::simulation::SimulatedClock::now() {
+        return timestamp;
+    }
     OffsetDateTime::now_utc().unix_timestamp()
 }
 
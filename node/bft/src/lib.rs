@@ -36,6 +36,9 @@ pub use gateway::*;
 mod primary;
 pub use primary::*;
 
+#[cfg(feature = "simulation")]
+pub mod simulation;
+
 mod sync;
 pub use sync::*;
 
@@ -67,12 +70,23 @@ pub const PRIMARY_PING_IN_MS: u64 = 2 * MAX_BATCH_DELAY_IN_MS; // ms
 pub const WORKER_PING_IN_MS: u64 = 4 * MAX_BATCH_DELAY_IN_MS; // ms
 
 /// A helper macro to spawn a blocking task.
+///
+/// If a simulation is running, the task runs in place instead, so that the order of the tasks only depends on its seed.
 #[macro_export]
 macro_rules! spawn_blocking {
-    ($expr:expr) => {
-        match tokio::task::spawn_blocking(move || $expr).await {
-            Ok(value) => value,
-            Err(error) => Err(anyhow::anyhow!("[tokio::spawn_blocking] {error}")),
+    ($expr:expr) => {{
+        #[allow(unused_mut)]
+        let mut task = move || $expr;
+        #[cfg(feature = "simulation")]
+        let in_place = $crate::simulation::SimulatedClock::now().is_some();
+        #[cfg(not(feature = "simulation"))]
+        let in_place = false;
+        match in_place {
+            true => task(),
+            false => match tokio::task::spawn_blocking(task).await {
+                Ok(value) => value,
+                Err(error) => Err(anyhow::anyhow!("[tokio::spawn_blocking] {error}")),
+            },
         }
-    };
+    }};
 }
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "adversary")]
+use crate::helpers::{ADVERSARY_FLOOD_INTERVAL_IN_MS, ADVERSARY_FLOOD_REQUESTS_PER_PEER};
 use crate::{
     events::{BatchPropose, BatchSignature, Event},
     helpers::{
@@ -21,7 +23,9 @@ use crate::{
         init_sync_channels,
         init_worker_channels,
         now,
+        secure_rng,
         BFTSender,
+        ParticipationStats,
         PrimaryReceiver,
         PrimarySender,
         Proposal,
@@ -44,6 +48,7 @@ use snarkos_account::Account;
 use snarkos_node_bft_events::PrimaryPing;
 use snarkos_node_bft_ledger_service::LedgerService;
 use snarkos_node_sync::DUMMY_SELF_IP;
+use snarkos_node_tcp::Config;
 use snarkvm::{
     console::{
         prelude::*,
@@ -97,6 +102,8 @@ pub struct Primary<N: Network> {
     latest_proposed_batch_timestamp: Arc<RwLock<i64>>,
     /// The recently-signed batch proposals.
     signed_proposals: Arc<RwLock<SignedProposals<N>>>,
+    /// The participation statistics of the validators.
+    participation: Arc<ParticipationStats<N>>,
     /// The spawned handles.
     handles: Arc<Mutex<Vec<JoinHandle<()>>>>,
     /// The lock for propose_batch.
@@ -115,11 +122,14 @@ impl<N: Network> Primary<N> {
         ip: Option<SocketAddr>,
         trusted_validators: &[SocketAddr],
         dev: Option<u16>,
+        tcp_config: Config,
     ) -> Result<Self> {
         // Initialize the gateway.
-        let gateway = Gateway::new(account, storage.clone(), ledger.clone(), ip, trusted_validators, dev)?;
+        let gateway = Gateway::new(account, storage.clone(), ledger.clone(), ip, trusted_validators, dev, tcp_config)?;
         // Initialize the sync module.
         let sync = Sync::new(gateway.clone(), storage.clone(), ledger.clone());
+        // Initialize the participation statistics.
+        let participation = Arc::new(ParticipationStats::new(storage.max_gc_rounds()));
 
         // Initialize the primary instance.
         Ok(Self {
@@ -132,6 +142,7 @@ impl<N: Network> Primary<N> {
             proposed_batch: Default::default(),
             latest_proposed_batch_timestamp: Default::default(),
             signed_proposals: Default::default(),
+            participation,
             handles: Default::default(),
             propose_lock: Default::default(),
         })
@@ -177,6 +188,29 @@ impl<N: Network> Primary<N> {
         }
     }
 
+    /// Restores the certificates persisted in storage that were not included in the ledger before the restart.
+    ///
+    /// Each certificate is validated and inserted into storage, and sent to the BFT to be inserted into the DAG.
+    async fn restore_persisted_certificates(&self) {
+        // Retrieve the persisted certificates, ordered by round.
+        let certificates = self.storage.get_persisted_certificates();
+        if certificates.is_empty() {
+            return;
+        }
+        let num_certificates = certificates.len();
+        let mut num_restored = 0;
+        for certificate in certificates {
+            let batch_id = certificate.batch_id();
+            // We use a dummy IP because the storage service should have persisted all the transmissions.
+            // If the certificate or one of its transmissions is invalid or missing, we simply skip the certificate.
+            match self.sync_with_certificate_from_peer::<true>(DUMMY_SELF_IP, certificate).await {
+                Ok(()) => num_restored += 1,
+                Err(err) => warn!("Failed to restore the persisted certificate {} - {err}", fmt_id(batch_id)),
+            }
+        }
+        info!("Restored {num_restored} of {num_certificates} persisted certificates");
+    }
+
     /// Run the primary instance.
     pub async fn run(
         &mut self,
@@ -222,8 +256,17 @@ impl<N: Network> Primary<N> {
         let (sync_sender, sync_receiver) = init_sync_channels();
         // Next, initialize the sync module and sync the storage from ledger.
         self.sync.initialize(bft_sender).await?;
+        // Next, restore the persisted certificates that are not yet in the ledger.
+        self.restore_persisted_certificates().await;
         // Next, load and process the proposal cache before running the sync module.
         self.load_proposal_cache().await?;
+        // Next, verify the transmissions in storage, and remove the orphans left behind by a crash.
+        let storage = self.storage.clone();
+        let report = spawn_blocking!(Ok(storage.repair_transmissions(false)))?;
+        match report.is_consistent() {
+            true => debug!("Verified the transmissions in storage - {report}"),
+            false => info!("Repaired the transmissions in storage - {report}"),
+        }
         // Next, run the sync module.
         self.sync.run(sync_receiver).await?;
         // Next, initialize the gateway.
@@ -274,6 +317,11 @@ impl<N: Network> Primary<N> {
     pub fn proposed_batch(&self) -> &Arc<ProposedBatch<N>> {
         &self.proposed_batch
     }
+
+    /// Returns the participation statistics of the validators.
+    pub const fn participation(&self) -> &Arc<ParticipationStats<N>> {
+        &self.participation
+    }
 }
 
 impl<N: Network> Primary<N> {
@@ -582,8 +630,8 @@ impl<N: Network> Primary<N> {
         /* Proceeding to sign & propose the batch. */
         info!("Proposing a batch with {} transmissions for round {round}...", transmissions.len());
 
-        // Retrieve the private key.
-        let private_key = *self.gateway.account().private_key();
+        // Retrieve the account.
+        let account = self.gateway.account().clone();
         // Retrieve the committee ID.
         let committee_id = committee_lookback.id();
         // Prepare the transmission IDs.
@@ -591,14 +639,13 @@ impl<N: Network> Primary<N> {
         // Prepare the previous batch certificate IDs.
         let previous_certificate_ids = previous_certificates.into_iter().map(|c| c.id()).collect();
         // Sign the batch header and construct the proposal.
-        let (batch_header, proposal) = spawn_blocking!(BatchHeader::new(
-            &private_key,
+        let (batch_header, proposal) = spawn_blocking!(Self::sign_batch_header(
+            &account,
             round,
             current_timestamp,
             committee_id,
             transmission_ids,
-            previous_certificate_ids,
-            &mut rand::thread_rng()
+            previous_certificate_ids
         ))
         .and_then(|batch_header| {
             Proposal::new(committee_lookback, batch_header.clone(), transmissions.clone())
@@ -612,7 +659,13 @@ impl<N: Network> Primary<N> {
             err
         })?;
         // Broadcast the batch to all validators for signing.
+        #[cfg(not(feature = "adversary"))]
         self.gateway.broadcast(Event::BatchPropose(batch_header.into()));
+        // Broadcast the batch to all validators for signing, misbehaving as configured by the adversary mode.
+        #[cfg(feature = "adversary")]
+        self.broadcast_batch_propose_as_adversary(batch_header).await;
+        // Record the batch proposal in the participation statistics.
+        self.participation.record_batch_proposed(round, self.gateway.account().address());
         // Set the timestamp of the latest proposed batch.
         *self.latest_proposed_batch_timestamp.write() = proposal.timestamp();
         // Set the proposed batch.
@@ -678,6 +731,9 @@ impl<N: Network> Primary<N> {
             );
         }
 
+        // Record the batch proposal in the participation statistics.
+        self.participation.record_batch_proposed(batch_round, batch_author);
+
         // Retrieve the cached round and batch ID for this validator.
         if let Some((signed_round, signed_batch_id, signature)) =
             self.signed_proposals.read().get(&batch_author).copied()
@@ -755,13 +811,20 @@ impl<N: Network> Primary<N> {
         // Inserts the missing transmissions into the workers.
         self.insert_missing_transmissions_into_workers(peer_ip, missing_transmissions.into_iter())?;
 
+        // If the adversary mode withholds signatures, do not sign the batch.
+        #[cfg(feature = "adversary")]
+        if self.gateway.adversary_mode().withhold_signatures {
+            debug!("Withholding the signature for a batch in round {batch_round} from '{peer_ip}'");
+            return Ok(());
+        }
+
         /* Proceeding to sign the batch. */
 
         // Retrieve the batch ID.
         let batch_id = batch_header.batch_id();
         // Sign the batch ID.
-        let account = self.gateway.account().clone();
-        let signature = spawn_blocking!(account.sign(&[batch_id], &mut rand::thread_rng()))?;
+        let (account, mut rng) = (self.gateway.account().clone(), secure_rng());
+        let signature = spawn_blocking!(account.sign_batch_proposal(batch_author, batch_round, batch_id, &mut rng))?;
 
         // Ensure the proposal has not already been signed.
         //
@@ -892,6 +955,8 @@ impl<N: Network> Primary<N> {
 
         // Retrieve the committee lookback for the round.
         let committee_lookback = self.ledger.get_committee_lookback_for_round(proposal.round())?;
+        // Record the signatures for the proposal in the participation statistics.
+        self.participation.record_proposal_signatures(&proposal, &committee_lookback);
         // Store the certified batch and broadcast it to all validators.
         // If there was an error storing the certificate, reinsert the transmissions back into the ready queue.
         if let Err(e) = self.store_and_broadcast_certificate(&proposal, &committee_lookback).await {
@@ -900,6 +965,13 @@ impl<N: Network> Primary<N> {
             return Err(e);
         }
 
+        // Record the certified batch in the participation statistics.
+        self.participation.record_batch_certified(
+            proposal.round(),
+            self.gateway.account().address(),
+            Some(now().saturating_sub(proposal.batch_header().timestamp())),
+        );
+
         #[cfg(feature = "metrics")]
         metrics::increment_gauge(metrics::bft::CERTIFIED_BATCHES, 1.0);
         Ok(())
@@ -939,8 +1011,17 @@ impl<N: Network> Primary<N> {
             bail!("Received a batch certificate for myself ({author})");
         }
 
+        // Measure the certification latency from the batch timestamp, before syncing any missing data.
+        // Note: Certificates for past rounds, or received while this node is syncing, are synced certificates,
+        // and are excluded from the certification latency.
+        let latency = match self.is_synced() && certificate_round >= self.current_round().saturating_sub(1) {
+            true => Some(now().saturating_sub(certificate.batch_header().timestamp())),
+            false => None,
+        };
         // Store the certificate, after ensuring it is valid.
         self.sync_with_certificate_from_peer::<false>(peer_ip, certificate).await?;
+        // Record the certified batch in the participation statistics.
+        self.participation.record_batch_certified(certificate_round, author, latency);
 
         // If there are enough certificates to reach quorum threshold for the certificate round,
         // then proceed to advance to the next round.
@@ -1049,7 +1130,7 @@ impl<N: Network> Primary<N> {
                     // Construct the primary ping.
                     let primary_ping = PrimaryPing::from((<Event<N>>::VERSION, block_locators, primary_certificate));
                     // Broadcast the event.
-                    self_.gateway.broadcast(Event::PrimaryPing(primary_ping));
+                    self_.gateway.broadcast_primary_ping(primary_ping);
                 }
             });
         }
@@ -1128,6 +1209,22 @@ impl<N: Network> Primary<N> {
             }
         });
 
+        // Start the flood of certificate requests, if enabled by the adversary mode.
+        #[cfg(feature = "adversary")]
+        {
+            let self_ = self.clone();
+            self.spawn(async move {
+                loop {
+                    // Sleep briefly.
+                    tokio::time::sleep(Duration::from_millis(ADVERSARY_FLOOD_INTERVAL_IN_MS)).await;
+                    // If the adversary mode floods certificate requests, send them to all connected peers.
+                    if self_.gateway.adversary_mode().flood_certificate_requests {
+                        self_.flood_certificate_requests().await;
+                    }
+                }
+            });
+        }
+
         // Process the proposed batch.
         let self_ = self.clone();
         self.spawn(async move {
@@ -1300,6 +1397,10 @@ impl<N: Network> Primary<N> {
             let proposal = self.proposed_batch.write().take();
             if let Some(proposal) = proposal {
                 debug!("Cleared expired proposal for round {}", proposal.round());
+                // Record the signatures for the expired proposal in the participation statistics.
+                if let Ok(committee_lookback) = self.ledger.get_committee_lookback_for_round(proposal.round()) {
+                    self.participation.record_proposal_signatures(&proposal, &committee_lookback);
+                }
                 self.reinsert_transmissions_into_workers(proposal.into_transmissions())?;
             }
         }
@@ -1407,10 +1508,37 @@ impl<N: Network> Primary<N> {
         }
     }
 
+    /// Returns a new batch header authored by the given account, signed by the account signer.
+    fn sign_batch_header(
+        account: &Account<N>,
+        round: u64,
+        timestamp: i64,
+        committee_id: Field<N>,
+        transmission_ids: IndexSet<TransmissionID<N>>,
+        previous_certificate_ids: IndexSet<Field<N>>,
+    ) -> Result<BatchHeader<N>> {
+        // Compute the batch ID.
+        let author = account.address();
+        let batch_id = BatchHeader::compute_batch_id(
+            author,
+            round,
+            timestamp,
+            committee_id,
+            &transmission_ids,
+            &previous_certificate_ids,
+        )?;
+        // Sign the batch ID.
+        // Note: The signer refuses to sign a different batch header for a round it has already signed.
+        let signature = account.sign_batch_header(round, batch_id, &mut secure_rng())?;
+        // Construct the batch header.
+        BatchHeader::from(author, round, timestamp, committee_id, transmission_ids, previous_certificate_ids, signature)
+    }
+
     /// Stores the certified batch and broadcasts it to all validators, returning the certificate.
     async fn store_and_broadcast_certificate(&self, proposal: &Proposal<N>, committee: &Committee<N>) -> Result<()> {
         // Create the batch certificate and transmissions.
-        let (certificate, transmissions) = tokio::task::block_in_place(|| proposal.to_certificate(committee))?;
+        let (proposal_, committee_) = (proposal.clone(), committee.clone());
+        let (certificate, transmissions) = spawn_blocking!(proposal_.to_certificate(&committee_))?;
         // Convert the transmissions into a HashMap.
         // Note: Do not change the `Proposal` to use a HashMap. The ordering there is necessary for safety.
         let transmissions = transmissions.into_iter().collect::<HashMap<_, _>>();
@@ -1733,6 +1861,103 @@ impl<N: Network> Primary<N> {
     }
 }
 
+#[cfg(feature = "adversary")]
+impl<N: Network> Primary<N> {
+    /// Broadcasts the batch proposal to all validators, misbehaving as configured by the adversary mode.
+    async fn broadcast_batch_propose_as_adversary(&self, batch_header: BatchHeader<N>) {
+        let mode = self.gateway.adversary_mode();
+        let round = batch_header.round();
+
+        // If the adversary mode equivocates proposals, send a conflicting proposal to half of the peers.
+        if mode.equivocate_proposals {
+            // Construct a conflicting batch header for the same round, with a different batch ID.
+            let account = self.gateway.account().clone();
+            let header = batch_header.clone();
+            let conflicting_header = spawn_blocking!(Self::sign_batch_header(
+                &account,
+                header.round(),
+                header.timestamp().saturating_add(1),
+                header.committee_id(),
+                header.transmission_ids().clone(),
+                header.previous_certificate_ids().clone()
+            ));
+            match conflicting_header {
+                Ok(conflicting_header) => {
+                    warn!("Equivocating the batch proposal for round {round}");
+                    // Send the original proposal to every other peer, and the conflicting proposal to the rest.
+                    let peer_ips = self.gateway.connected_peers().read().clone();
+                    for (index, peer_ip) in peer_ips.into_iter().enumerate() {
+                        let header = if index % 2 == 0 { &batch_header } else { &conflicting_header };
+                        self.gateway.send(peer_ip, Event::BatchPropose(header.clone().into())).await;
+                    }
+                }
+                Err(e) => {
+                    warn!("Failed to construct a conflicting batch header for round {round} - {e}");
+                    self.gateway.broadcast(Event::BatchPropose(batch_header.into()));
+                }
+            }
+        } else {
+            self.gateway.broadcast(Event::BatchPropose(batch_header.into()));
+        }
+
+        // If the adversary mode sends stale certificates, send the oldest stored certificate.
+        if mode.send_stale_certificates {
+            let stale_round = self.storage.gc_round().saturating_add(1);
+            if let Some(certificate) = self.storage.get_certificates_for_round(stale_round).into_iter().next() {
+                warn!("Sending a stale certificate for round {stale_round}");
+                self.gateway.broadcast(Event::BatchCertified(certificate.into()));
+            }
+        }
+
+        // If the adversary mode sends future certificates, send a self-signed certificate for a future round.
+        if mode.send_future_certificates {
+            let future_round = round.saturating_add(2);
+            match self.create_self_signed_certificate(future_round).await {
+                Ok(certificate) => {
+                    warn!("Sending a self-signed certificate for future round {future_round}");
+                    self.gateway.broadcast(Event::BatchCertified(certificate.into()));
+                }
+                Err(e) => warn!("Failed to construct a certificate for future round {future_round} - {e}"),
+            }
+        }
+    }
+
+    /// Returns an empty certificate for the given round, signed only by this validator.
+    async fn create_self_signed_certificate(&self, round: u64) -> Result<BatchCertificate<N>> {
+        let account = self.gateway.account().clone();
+        let committee_id = self.ledger.get_committee_lookback_for_round(round)?.id();
+        // Reference the certificates of the previous round that are in storage, if any.
+        let previous_certificate_ids =
+            self.storage.get_certificates_for_round(round.saturating_sub(1)).iter().map(|c| c.id()).collect();
+        spawn_blocking!({
+            let batch_header = Self::sign_batch_header(
+                &account,
+                round,
+                now(),
+                committee_id,
+                Default::default(),
+                previous_certificate_ids,
+            )?;
+            // Note: The signature is requested through the account signer, which may refuse it (e.g. a slashable one).
+            let signature =
+                account.sign_batch_proposal(account.address(), round, batch_header.batch_id(), &mut secure_rng())?;
+            BatchCertificate::from(batch_header, [signature].into_iter().collect())
+        })
+    }
+
+    /// Sends requests for random (unknown) certificates to all connected peers.
+    async fn flood_certificate_requests(&self) {
+        let peer_ips = self.gateway.connected_peers().read().clone();
+        debug!("Flooding {} peers with certificate requests", peer_ips.len());
+        for peer_ip in peer_ips {
+            for _ in 0..ADVERSARY_FLOOD_REQUESTS_PER_PEER {
+                let certificate_id = Field::<N>::rand(&mut secure_rng());
+                self.gateway.send(peer_ip, Event::CertificateRequest(certificate_id.into())).await;
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1774,7 +1999,7 @@ mod tests {
         let storage = Storage::new(ledger.clone(), Arc::new(BFTMemoryService::new()), 10);
 
         // Initialize the primary.
-        let mut primary = Primary::new(account, storage, ledger, None, &[], None).unwrap();
+        let mut primary = Primary::new(account, storage, ledger, None, &[], None, Default::default()).unwrap();
 
         // Construct a worker instance.
         primary.workers = Arc::from([Worker::new(
@@ -1840,7 +2065,7 @@ mod tests {
         let transaction_transmission_id = (&transaction_id, &transaction_checksum).into();
 
         // Retrieve the private key.
-        let private_key = author.private_key();
+        let private_key = author.private_key().unwrap();
         // Prepare the transmission IDs.
         let transmission_ids = [solution_transmission_id, transaction_transmission_id].into();
         let transmissions = [
@@ -1914,7 +2139,7 @@ mod tests {
 
         let author =
             accounts.iter().find(|&(_, acct)| acct.address() == primary_address).map(|(_, acct)| acct.clone()).unwrap();
-        let private_key = author.private_key();
+        let private_key = author.private_key().unwrap();
 
         let committee_id = Field::rand(rng);
         let (solution_id, solution) = sample_unconfirmed_solution(rng);
@@ -0,0 +1,66 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    sync::atomic::{AtomicI64, Ordering},
+    time::Duration,
+};
+use tokio::{
+    task::JoinHandle,
+    time::{interval_at, Instant},
+};
+
+/// The UNIX timestamp of the clock of the running simulation, or zero if no simulation is running.
+static TIMESTAMP: AtomicI64 = AtomicI64::new(0);
+
+/// The clock of a simulation, which ticks with the tokio timers.
+///
+/// While the clock runs, `now()` returns its timestamp to the whole process, including the blocking threads
+/// of the runtime. On a paused runtime (i.e. `#[tokio::test(start_paused = true)]`), the timestamp follows
+/// the virtual time, so that the timestamps of the batches agree with the timers of the BFT.
+pub struct SimulatedClock {
+    /// The task that advances the clock.
+    handle: JoinHandle<()>,
+}
+
+impl SimulatedClock {
+    /// Starts the clock at the given UNIX timestamp.
+    pub fn start(timestamp: i64) -> Self {
+        TIMESTAMP.store(timestamp, Ordering::SeqCst);
+        let handle = tokio::spawn(async move {
+            let period = Duration::from_secs(1);
+            let mut interval = interval_at(Instant::now() + period, period);
+            loop {
+                interval.tick().await;
+                TIMESTAMP.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        Self { handle }
+    }
+
+    /// Returns the timestamp of the clock, if a simulation is running.
+    pub fn now() -> Option<i64> {
+        match TIMESTAMP.load(Ordering::SeqCst) {
+            0 => None,
+            timestamp => Some(timestamp),
+        }
+    }
+}
+
+impl Drop for SimulatedClock {
+    fn drop(&mut self) {
+        self.handle.abort();
+        TIMESTAMP.store(0, Ordering::SeqCst);
+    }
+}
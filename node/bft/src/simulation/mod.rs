@@ -0,0 +1,224 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A simulator for the BFT.
+//!
+//! The simulator runs a committee of validators, each with its own `Storage` and `BFT` (or only `Primary`)
+//! over a `MockLedgerService`, whose gateways reach each other over an in-memory network with seeded delays
+//! and partitions. The validators run under a simulated clock, which drives both `now()` and the tokio timers.
+//!
+//! A run is reproducible from its seed: the delays of the network and every RNG of the validators are seeded by it,
+//! and the blocking tasks run in place, so the tasks are scheduled in the same order on every run.
+//! The ephemeral keys of the encrypted connections are still sampled by the operating system, which only changes
+//! the encrypted bytes, not their lengths or their delivery times.
+//!
+//! The simulation must run on a paused, current-thread runtime, i.e. `#[tokio::test(start_paused = true)]`.
+//! A single simulation runs at a time in a process, as its clock and its RNG are observed by the whole process.
+
+mod clock;
+pub use clock::*;
+
+mod network;
+pub use network::*;
+
+mod node;
+pub use node::*;
+
+mod rng;
+pub use rng::*;
+
+use crate::helpers::{address_book_path, proposal_cache_path};
+use snarkos_account::Account;
+use snarkos_node_bft_ledger_service::MockLedgerService;
+use snarkos_node_tcp::Config;
+use snarkvm::{
+    ledger::{block::Block, committee::Committee},
+    prelude::{bail, ensure, Field, FromBytes, Network, Result},
+};
+
+use std::sync::Arc;
+use tokio::sync::{Mutex, MutexGuard};
+
+/// The lock that ensures a single simulation runs at a time.
+static SIMULATION_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// The configuration of a simulation.
+#[derive(Copy, Clone, Debug)]
+pub struct SimulationConfig {
+    /// The seed of the simulated network.
+    pub seed: u64,
+    /// The configuration of the simulated network.
+    pub network: NetworkConfig,
+    /// If this is set to `true`, the BFT runs on top of the primary of each validator.
+    pub bft: bool,
+}
+
+impl SimulationConfig {
+    /// Initializes a new configuration with the given seed, and defaults for everything else.
+    pub fn new(seed: u64) -> Self {
+        Self { seed, network: Default::default(), bft: true }
+    }
+}
+
+/// A simulation of a committee of validators.
+pub struct Simulation<N: Network> {
+    /// The configuration of the simulation.
+    config: SimulationConfig,
+    /// The validators.
+    nodes: Vec<SimulatedNode<N>>,
+    /// The simulated network.
+    network: SimulatedNetwork,
+    /// The simulated clock.
+    _clock: SimulatedClock,
+    /// The simulated RNG.
+    _rng: SimulatedRng,
+    /// The guard of the simulation lock.
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl<N: Network> Simulation<N> {
+    /// Initializes a new simulation of the given validators of the given committee, without starting them.
+    ///
+    /// The ledger of each validator only holds the genesis block of the network, and the clock starts at its timestamp.
+    pub async fn new(config: SimulationConfig, accounts: Vec<Account<N>>, committee: Committee<N>) -> Result<Self> {
+        ensure!(!accounts.is_empty(), "A simulation requires at least one validator");
+        let genesis = Block::<N>::from_bytes_le(N::genesis_bytes())?;
+        // Wait for the previous simulation to end, and start the clock and the RNG.
+        let lock = SIMULATION_LOCK.lock().await;
+        let clock = SimulatedClock::start(genesis.timestamp());
+        let rng = SimulatedRng::start(config.seed);
+        let network = SimulatedNetwork::new(config.network, config.seed);
+
+        let num_validators = accounts.len();
+        let mut nodes = Vec::with_capacity(num_validators);
+        for (index, account) in accounts.into_iter().enumerate() {
+            // Initialize the ledger, which only holds the genesis block.
+            let ledger = Arc::new(MockLedgerService::new_with_genesis(committee.clone(), genesis.clone()));
+            // Remove the files left behind by a previous simulation.
+            Self::remove_files(index);
+            // Keep every validator connected to all the others.
+            let trusted_validators = (0..num_validators).filter(|i| *i != index).map(validator_ip).collect();
+            // Set the simulated transport.
+            let tcp_config = Config { transport: Arc::new(network.transport(index)), ..Default::default() };
+            nodes.push(SimulatedNode::new(index, account, ledger, trusted_validators, tcp_config, config.bft));
+        }
+
+        Ok(Self { config, nodes, network, _clock: clock, _rng: rng, _lock: lock })
+    }
+
+    /// Starts all the validators.
+    pub async fn start(&mut self) -> Result<()> {
+        for node in self.nodes.iter_mut() {
+            node.start().await?;
+        }
+        Ok(())
+    }
+
+    /// Shuts down all the validators, so that none of their tasks outlives the simulation.
+    pub async fn shut_down(&mut self) {
+        for node in self.nodes.iter_mut() {
+            node.crash().await;
+        }
+    }
+
+    /// Returns the configuration of the simulation.
+    pub const fn config(&self) -> &SimulationConfig {
+        &self.config
+    }
+
+    /// Returns the validators.
+    pub fn nodes(&self) -> &[SimulatedNode<N>] {
+        &self.nodes
+    }
+
+    /// Returns the simulated network.
+    pub const fn network(&self) -> &SimulatedNetwork {
+        &self.network
+    }
+
+    /// Splits the validators into the given groups, which are only able to communicate within themselves.
+    pub fn partition(&self, groups: &[Vec<usize>]) {
+        self.network.partition(0..self.nodes.len(), groups);
+    }
+
+    /// Isolates the given validator from all the others.
+    pub fn isolate(&self, index: usize) {
+        (0..self.nodes.len()).filter(|i| *i != index).for_each(|i| self.network.disconnect(index, i));
+    }
+
+    /// Crashes the given validator.
+    pub async fn crash(&mut self, index: usize) {
+        self.nodes[index].crash().await;
+    }
+
+    /// Restarts the given validator.
+    pub async fn restart(&mut self, index: usize) -> Result<()> {
+        self.nodes[index].start().await
+    }
+
+    /// Replaces the given validator with a fresh one, which lost all of its state, and must resync from its peers.
+    pub async fn replace(&mut self, index: usize) -> Result<()> {
+        self.nodes[index].crash().await;
+        self.nodes[index].clear_state()?;
+        Self::remove_files(index);
+        self.nodes[index].start().await
+    }
+
+    /// Returns the certificate IDs of the committed subdags of each validator.
+    pub fn committed_certificate_ids(&self) -> Vec<Vec<Vec<Field<N>>>> {
+        self.nodes
+            .iter()
+            .map(|node| {
+                node.committed_subdags()
+                    .iter()
+                    .map(|subdag| subdag.values().flatten().map(|certificate| certificate.id()).collect())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Ensures the given validators committed the same sequence of subdags, where the lagging validators
+    /// committed a prefix of it. Returns the number of subdags committed by every one of them.
+    pub fn check_consistency(&self, validators: &[usize]) -> Result<usize> {
+        let committed = self.committed_certificate_ids();
+        let committed = validators.iter().map(|index| (*index, &committed[*index])).collect::<Vec<_>>();
+        // Retrieve the longest sequence of committed subdags.
+        let Some((_, longest)) = committed.iter().max_by_key(|(_, subdags)| subdags.len()) else {
+            return Ok(0);
+        };
+        // Ensure every sequence is a prefix of the longest sequence.
+        for (index, subdags) in &committed {
+            if let Some(position) = subdags.iter().zip(longest.iter()).position(|(a, b)| a != b) {
+                bail!(
+                    "Validator {index} committed a different subdag at position {position} (seed = {})",
+                    self.config.seed
+                );
+            }
+        }
+        Ok(committed.iter().map(|(_, subdags)| subdags.len()).min().unwrap_or(0))
+    }
+
+    /// Removes the files that the given validator stores on disk.
+    fn remove_files(index: usize) {
+        let dev = Some(index as u16);
+        let _ = std::fs::remove_file(proposal_cache_path(N::ID, dev));
+        let _ = std::fs::remove_file(address_book_path(N::ID, dev));
+    }
+}
+
+impl<N: Network> Drop for Simulation<N> {
+    fn drop(&mut self) {
+        (0..self.nodes.len()).for_each(Self::remove_files);
+    }
+}
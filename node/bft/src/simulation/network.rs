@@ -0,0 +1,451 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use snarkos_node_tcp::transport::{BoxedStream, Transport, TransportListener, TransportStream};
+use snarkvm::utilities::TestRng;
+
+use bytes::Bytes;
+use parking_lot::Mutex;
+use rand::Rng;
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    pin::Pin,
+    sync::{Arc, Weak},
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{split, AsyncRead, AsyncWrite, AsyncWriteExt, DuplexStream, ReadBuf, ReadHalf},
+    sync::mpsc,
+    time::{sleep_until, Instant},
+};
+use tokio_util::sync::{CancellationToken, PollSender};
+
+/// The size (in bytes) of the buffer of each direction of a simulated connection.
+const BUFFER_SIZE: usize = 64 * 1024;
+/// The maximum number of writes of a simulated connection that are waiting to be delivered to the peer.
+const MAX_PENDING_WRITES: usize = 64;
+/// The first port assigned to the outbound connections of the simulated network.
+const FIRST_EPHEMERAL_PORT: u16 = 49152;
+
+/// The configuration of the simulated network.
+#[derive(Copy, Clone, Debug)]
+pub struct NetworkConfig {
+    /// The minimum delay of a write, in milliseconds.
+    pub min_delay_ms: u64,
+    /// The maximum delay of a write, in milliseconds.
+    pub max_delay_ms: u64,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self { min_delay_ms: 10, max_delay_ms: 100 }
+    }
+}
+
+/// An in-memory network between simulated validators, with seeded delays and partitions.
+///
+/// Each validator reaches the network through its own [`SimulatedTransport`], which is set as the transport of
+/// its gateway. Like a single host, the listeners are identified by their port only.
+#[derive(Clone, Debug)]
+pub struct SimulatedNetwork(Arc<Mutex<NetworkState>>);
+
+#[derive(Debug)]
+struct NetworkState {
+    /// The configuration of the network.
+    config: NetworkConfig,
+    /// The seeded RNG, which samples the delays.
+    rng: TestRng,
+    /// The pairs of validators that are unable to communicate, with the lower index first.
+    blocked: HashSet<(usize, usize)>,
+    /// The additional delay of each validator, in milliseconds.
+    extra_delays_ms: HashMap<usize, u64>,
+    /// The listeners, indexed by their port, along with the index of their validator.
+    listeners: HashMap<u16, (usize, mpsc::UnboundedSender<(SimulatedStream, SocketAddr)>)>,
+    /// The established connections.
+    links: Vec<Link>,
+    /// The number of ephemeral ports assigned so far.
+    num_ephemeral_ports: u32,
+}
+
+/// A connection between two validators.
+#[derive(Debug)]
+struct Link {
+    /// The indices of the validators.
+    validators: (usize, usize),
+    /// The token that resets the connection.
+    reset: CancellationToken,
+    /// The marker that is alive for as long as either side of the connection is.
+    alive: Weak<()>,
+}
+
+/// Returns the given pair of validators, with the lower index first.
+fn ordered(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+impl SimulatedNetwork {
+    /// Initializes a new fully-connected network with the given configuration and seed.
+    pub fn new(config: NetworkConfig, seed: u64) -> Self {
+        Self(Arc::new(Mutex::new(NetworkState {
+            config,
+            rng: TestRng::fixed(seed),
+            blocked: Default::default(),
+            extra_delays_ms: Default::default(),
+            listeners: Default::default(),
+            links: Default::default(),
+            num_ephemeral_ports: 0,
+        })))
+    }
+
+    /// Returns the transport through which the given validator reaches the network.
+    pub fn transport(&self, validator: usize) -> SimulatedTransport {
+        SimulatedTransport { network: self.clone(), validator }
+    }
+
+    /// Returns `true` if the given validators are able to communicate.
+    pub fn is_connected(&self, a: usize, b: usize) -> bool {
+        !self.0.lock().blocked.contains(&ordered(a, b))
+    }
+
+    /// Allows the given validators to communicate.
+    pub fn connect(&self, a: usize, b: usize) {
+        self.0.lock().blocked.remove(&ordered(a, b));
+    }
+
+    /// Prevents the given validators from communicating, resetting their connections.
+    pub fn disconnect(&self, a: usize, b: usize) {
+        let mut state = self.0.lock();
+        state.blocked.insert(ordered(a, b));
+        state.reset_blocked_links();
+    }
+
+    /// Splits the given validators into the given groups, which are only able to communicate within themselves.
+    /// The validators that are in none of the groups are isolated from everyone else.
+    pub fn partition(&self, validators: impl IntoIterator<Item = usize>, groups: &[Vec<usize>]) {
+        let validators = validators.into_iter().collect::<Vec<_>>();
+        // Determine the group of each validator.
+        let group_of = |validator: &usize| groups.iter().position(|group| group.contains(validator));
+        let mut state = self.0.lock();
+        for (i, a) in validators.iter().enumerate() {
+            for b in &validators[i + 1..] {
+                match group_of(a).is_some() && group_of(a) == group_of(b) {
+                    true => state.blocked.remove(&ordered(*a, *b)),
+                    false => state.blocked.insert(ordered(*a, *b)),
+                };
+            }
+        }
+        state.reset_blocked_links();
+    }
+
+    /// Heals all partitions in the network.
+    pub fn heal(&self) {
+        self.0.lock().blocked.clear();
+    }
+
+    /// Sets the additional delay for all writes to and from the given validator.
+    pub fn set_extra_delay(&self, validator: usize, extra_delay: Duration) {
+        self.0.lock().extra_delays_ms.insert(validator, extra_delay.as_millis() as u64);
+    }
+
+    /// Samples the delay of a write between the given validators.
+    fn sample_delay(&self, from: usize, to: usize) -> Duration {
+        let mut state = self.0.lock();
+        let NetworkConfig { min_delay_ms, max_delay_ms } = state.config;
+        let extra_delay_ms = |validator| state.extra_delays_ms.get(&validator).copied().unwrap_or(0);
+        let extra_delay_ms = extra_delay_ms(from) + extra_delay_ms(to);
+        let delay_ms = state.rng.gen_range(min_delay_ms..=max_delay_ms.max(min_delay_ms));
+        Duration::from_millis(delay_ms + extra_delay_ms)
+    }
+}
+
+impl NetworkState {
+    /// Resets the connections between the validators that are unable to communicate,
+    /// and forgets the connections that are closed.
+    fn reset_blocked_links(&mut self) {
+        let blocked = &self.blocked;
+        self.links.retain(|link| {
+            if blocked.contains(&ordered(link.validators.0, link.validators.1)) {
+                link.reset.cancel();
+            }
+            !link.reset.is_cancelled() && link.alive.strong_count() > 0
+        });
+    }
+}
+
+/// The transport through which a simulated validator reaches the [`SimulatedNetwork`].
+#[derive(Clone, Debug)]
+pub struct SimulatedTransport {
+    /// The network.
+    network: SimulatedNetwork,
+    /// The index of the validator.
+    validator: usize,
+}
+
+#[async_trait::async_trait]
+impl Transport for SimulatedTransport {
+    async fn connect(&self, addr: SocketAddr, local_ip: Option<IpAddr>) -> io::Result<BoxedStream> {
+        let mut state = self.network.0.lock();
+        // Retrieve the listener, if there is one.
+        let (peer, listener) = state.listeners.get(&addr.port()).cloned().ok_or(io::ErrorKind::ConnectionRefused)?;
+        // Ensure the validators are able to communicate.
+        if state.blocked.contains(&ordered(self.validator, peer)) {
+            return Err(io::ErrorKind::ConnectionRefused.into());
+        }
+        // Assign the address the connection originates from.
+        let port = FIRST_EPHEMERAL_PORT + (state.num_ephemeral_ports % (u16::MAX - FIRST_EPHEMERAL_PORT) as u32) as u16;
+        state.num_ephemeral_ports += 1;
+        let ip = local_ip.filter(|ip| !ip.is_unspecified()).unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        let local_addr = SocketAddr::new(ip, port);
+        // Create the connection, and deliver its other side to the listener.
+        let (reset, alive) = (CancellationToken::new(), Arc::new(()));
+        state.links.push(Link {
+            validators: (self.validator, peer),
+            reset: reset.clone(),
+            alive: Arc::downgrade(&alive),
+        });
+        drop(state);
+        let (a, b) = tokio::io::duplex(BUFFER_SIZE);
+        let stream = SimulatedStream::new(a, local_addr, (self.validator, peer), &self.network, &reset, &alive);
+        let peer_stream = SimulatedStream::new(b, addr, (peer, self.validator), &self.network, &reset, &alive);
+        listener.send((peer_stream, local_addr)).map_err(|_| io::ErrorKind::ConnectionRefused)?;
+        Ok(Box::new(stream))
+    }
+
+    async fn bind(&self, addr: SocketAddr) -> io::Result<Box<dyn TransportListener>> {
+        let mut state = self.network.0.lock();
+        // Ensure the port is available.
+        // Note: The validators of a simulation listen on distinct, predetermined ports.
+        if addr.port() == 0 || state.listeners.contains_key(&addr.port()) {
+            return Err(io::ErrorKind::AddrInUse.into());
+        }
+        // Register the listener.
+        let (sender, receiver) = mpsc::unbounded_channel();
+        state.listeners.insert(addr.port(), (self.validator, sender));
+
+        Ok(Box::new(SimulatedListener {
+            local_addr: addr,
+            receiver: tokio::sync::Mutex::new(receiver),
+            network: self.network.clone(),
+        }))
+    }
+}
+
+/// A listener of the [`SimulatedNetwork`]; it is unregistered once dropped.
+struct SimulatedListener {
+    /// The address of the listener.
+    local_addr: SocketAddr,
+    /// The inbound connections.
+    receiver: tokio::sync::Mutex<mpsc::UnboundedReceiver<(SimulatedStream, SocketAddr)>>,
+    /// The network the listener is registered with.
+    network: SimulatedNetwork,
+}
+
+#[async_trait::async_trait]
+impl TransportListener for SimulatedListener {
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.local_addr)
+    }
+
+    async fn accept(&self) -> io::Result<(BoxedStream, SocketAddr)> {
+        match self.receiver.lock().await.recv().await {
+            Some((stream, addr)) => Ok((Box::new(stream), addr)),
+            None => Err(io::ErrorKind::NotConnected.into()),
+        }
+    }
+}
+
+impl Drop for SimulatedListener {
+    fn drop(&mut self) {
+        self.network.0.lock().listeners.remove(&self.local_addr.port());
+    }
+}
+
+/// One side of a connection of the [`SimulatedNetwork`].
+///
+/// Every write is delivered to the peer after a delay sampled by the network, in the order of the writes.
+/// Once the connection is reset (i.e. by a partition), both sides fail with [`io::ErrorKind::ConnectionReset`].
+struct SimulatedStream {
+    /// The local address of the stream.
+    local_addr: SocketAddr,
+    /// The indices of this validator and of the peer.
+    validators: (usize, usize),
+    /// The network.
+    network: SimulatedNetwork,
+    /// The reading half of the stream.
+    reader: ReadHalf<DuplexStream>,
+    /// The queue of writes to be delivered to the peer, along with their delivery time; `None` once shut down.
+    writer: Option<PollSender<(Instant, Bytes)>>,
+    /// The delivery time of the latest write.
+    latest_deadline: Instant,
+    /// The token that resets the connection.
+    reset: CancellationToken,
+    /// The marker that keeps the connection known to the network.
+    _alive: Arc<()>,
+}
+
+impl SimulatedStream {
+    /// Wraps one side of a duplex stream, spawning the task that delivers its writes to the peer.
+    fn new(
+        stream: DuplexStream,
+        local_addr: SocketAddr,
+        validators: (usize, usize),
+        network: &SimulatedNetwork,
+        reset: &CancellationToken,
+        alive: &Arc<()>,
+    ) -> Self {
+        let (reader, mut writer) = split(stream);
+        let (sender, mut receiver) = mpsc::channel::<(Instant, Bytes)>(MAX_PENDING_WRITES);
+        let reset_ = reset.clone();
+        tokio::spawn(async move {
+            let deliver = async {
+                while let Some((deadline, bytes)) = receiver.recv().await {
+                    sleep_until(deadline).await;
+                    if writer.write_all(&bytes).await.is_err() {
+                        break;
+                    }
+                }
+            };
+            // Deliver the writes until the stream is shut down, or the connection is reset.
+            tokio::select! {
+                biased;
+                _ = deliver => {},
+                _ = reset_.cancelled() => {},
+            }
+            // Signal the end of the stream to the peer.
+            let _ = writer.shutdown().await;
+        });
+        Self {
+            local_addr,
+            validators,
+            network: network.clone(),
+            reader,
+            writer: Some(PollSender::new(sender)),
+            latest_deadline: Instant::now(),
+            reset: reset.clone(),
+            _alive: alive.clone(),
+        }
+    }
+}
+
+impl TransportStream for SimulatedStream {
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.local_addr)
+    }
+}
+
+impl AsyncRead for SimulatedStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.reset.is_cancelled() {
+            return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+        }
+        Pin::new(&mut this.reader).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for SimulatedStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.reset.is_cancelled() {
+            return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+        }
+        let Some(writer) = &mut this.writer else { return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())) };
+        // Wait until the queue has room for the write.
+        if ready!(writer.poll_reserve(cx)).is_err() {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        // Queue the write for delivery after the sampled delay, but never before the previous writes.
+        let delay = this.network.sample_delay(this.validators.0, this.validators.1);
+        this.latest_deadline = this.latest_deadline.max(Instant::now() + delay);
+        match writer.send_item((this.latest_deadline, Bytes::copy_from_slice(buf))) {
+            Ok(()) => Poll::Ready(Ok(buf.len())),
+            Err(_) => Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().writer = None;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::io::AsyncReadExt;
+
+    /// Returns the localhost address with the given port.
+    fn localhost(port: u16) -> SocketAddr {
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)
+    }
+
+    #[test]
+    fn test_partition_and_heal() {
+        let network = SimulatedNetwork::new(NetworkConfig::default(), 0);
+        assert!(network.is_connected(0, 3));
+
+        // Partition the network, leaving validator 3 isolated.
+        network.partition(0..4, &[vec![0, 1], vec![2]]);
+        assert!(network.is_connected(0, 1));
+        assert!(!network.is_connected(0, 2));
+        assert!(!network.is_connected(2, 3));
+        assert!(!network.is_connected(1, 3));
+
+        // Reconnect a single pair of validators.
+        network.connect(2, 3);
+        assert!(network.is_connected(3, 2));
+        assert!(!network.is_connected(1, 2));
+
+        // Heal the network.
+        network.heal();
+        assert!(network.is_connected(0, 2));
+        assert!(network.is_connected(1, 3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_delays_and_resets() {
+        let network = SimulatedNetwork::new(NetworkConfig { min_delay_ms: 10, max_delay_ms: 20 }, 0);
+        network.set_extra_delay(1, Duration::from_millis(100));
+        let listener = network.transport(1).bind(localhost(5001)).await.unwrap();
+
+        // Ensure the writes are delivered after the sampled delay.
+        let mut stream = network.transport(0).connect(localhost(5001), None).await.unwrap();
+        let (mut peer_stream, _) = listener.accept().await.unwrap();
+        let start = Instant::now();
+        stream.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        peer_stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        assert!((110..=120).contains(&start.elapsed().as_millis()));
+
+        // Ensure a partition resets the connection, and refuses new ones.
+        network.disconnect(0, 1);
+        assert_eq!(peer_stream.read(&mut buf).await.unwrap_err().kind(), io::ErrorKind::ConnectionReset);
+        assert_eq!(stream.write_all(b"ping").await.unwrap_err().kind(), io::ErrorKind::ConnectionReset);
+        let error = network.transport(0).connect(localhost(5001), None).await.err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionRefused);
+
+        // Ensure the validators reconnect once the partition heals.
+        network.heal();
+        assert!(network.transport(0).connect(localhost(5001), None).await.is_ok());
+    }
+}
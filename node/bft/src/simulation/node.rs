@@ -0,0 +1,243 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    helpers::{
+        init_consensus_channels,
+        init_primary_channels,
+        proposal_cache_path,
+        ConsensusReceiver,
+        PrimarySender,
+        Storage,
+    },
+    Primary,
+    BFT,
+    MEMORY_POOL_PORT,
+};
+use snarkos_account::Account;
+use snarkos_node_bft_ledger_service::LedgerService;
+use snarkos_node_bft_storage_service::BFTMemoryService;
+use snarkos_node_tcp::Config;
+use snarkvm::{
+    ledger::narwhal::{BatchHeader, Subdag},
+    prelude::{ensure, Address, Network, Result},
+};
+
+use parking_lot::Mutex;
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+};
+use tokio::task::JoinHandle;
+
+/// Returns the gateway address of the simulated validator with the given index.
+pub fn validator_ip(index: usize) -> SocketAddr {
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), MEMORY_POOL_PORT + index as u16)
+}
+
+/// A simulated validator, which runs a `BFT` (or only its `Primary`) over the simulated network.
+///
+/// The ledger, the storage service, and the committed subdags outlive a crash, as they would on disk;
+/// everything else is lost when the validator crashes.
+pub struct SimulatedNode<N: Network> {
+    /// The index of the validator.
+    index: usize,
+    /// The account of the validator.
+    account: Account<N>,
+    /// The ledger service.
+    ledger: Arc<dyn LedgerService<N>>,
+    /// The storage service, which stands in for the persistent storage.
+    storage_service: Arc<BFTMemoryService<N>>,
+    /// The gateway addresses of the other validators.
+    trusted_validators: Vec<SocketAddr>,
+    /// The configuration of the TCP stack, which sets the simulated transport.
+    tcp_config: Config,
+    /// If this is set to `true`, the BFT runs on top of the primary.
+    run_bft: bool,
+    /// The committed subdags, in order, which stand in for the blocks of the ledger.
+    committed: Arc<Mutex<Vec<Subdag<N>>>>,
+    /// The running process, if the validator is online.
+    process: Option<Process<N>>,
+}
+
+/// The state of a running validator.
+struct Process<N: Network> {
+    /// The primary.
+    primary: Primary<N>,
+    /// The BFT, if it runs on top of the primary.
+    bft: Option<BFT<N>>,
+    /// The sender of the primary channels.
+    primary_sender: PrimarySender<N>,
+    /// The task that commits the subdags, in place of consensus.
+    consensus_handle: Option<JoinHandle<()>>,
+}
+
+impl<N: Network> SimulatedNode<N> {
+    /// Initializes a new simulated validator, without starting it.
+    pub fn new(
+        index: usize,
+        account: Account<N>,
+        ledger: Arc<dyn LedgerService<N>>,
+        trusted_validators: Vec<SocketAddr>,
+        tcp_config: Config,
+        run_bft: bool,
+    ) -> Self {
+        Self {
+            index,
+            account,
+            ledger,
+            storage_service: Arc::new(BFTMemoryService::new()),
+            trusted_validators,
+            tcp_config,
+            run_bft,
+            committed: Default::default(),
+            process: None,
+        }
+    }
+
+    /// Returns the index of the validator.
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the address of the validator.
+    pub fn address(&self) -> Address<N> {
+        self.account.address()
+    }
+
+    /// Returns `true` if the validator is online.
+    pub fn is_online(&self) -> bool {
+        self.process.is_some()
+    }
+
+    /// Returns the primary, if the validator is online.
+    pub fn primary(&self) -> Option<&Primary<N>> {
+        self.process.as_ref().map(|process| &process.primary)
+    }
+
+    /// Returns the BFT, if the validator is online and runs the BFT.
+    pub fn bft(&self) -> Option<&BFT<N>> {
+        self.process.as_ref().and_then(|process| process.bft.as_ref())
+    }
+
+    /// Returns the sender of the primary channels, if the validator is online.
+    pub fn primary_sender(&self) -> Option<&PrimarySender<N>> {
+        self.process.as_ref().map(|process| &process.primary_sender)
+    }
+
+    /// Returns the current round of the validator, or `0` if the validator is offline.
+    pub fn current_round(&self) -> u64 {
+        self.primary().map_or(0, |primary| primary.current_round())
+    }
+
+    /// Returns the committed subdags, in order.
+    pub fn committed_subdags(&self) -> Vec<Subdag<N>> {
+        self.committed.lock().clone()
+    }
+
+    /// Starts the validator, restoring the committed subdags into the DAG of the BFT.
+    pub async fn start(&mut self) -> Result<()> {
+        ensure!(self.process.is_none(), "Validator {} is already running", self.index);
+
+        // Initialize the storage, over the storage service that outlived any previous run.
+        let max_gc_rounds = BatchHeader::<N>::MAX_GC_ROUNDS as u64;
+        let storage = Storage::new(self.ledger.clone(), self.storage_service.clone(), max_gc_rounds);
+        // Initialize the primary channels.
+        let (primary_sender, primary_receiver) = init_primary_channels();
+        let (account, ledger, dev) = (self.account.clone(), self.ledger.clone(), Some(self.index as u16));
+
+        let process = match self.run_bft {
+            true => {
+                let mut bft =
+                    BFT::new(account, storage, ledger, None, &self.trusted_validators, dev, self.tcp_config.clone())?;
+                // Restore the committed certificates into the DAG, as a validator does from its ledger at bootup.
+                let certificates = self
+                    .committed
+                    .lock()
+                    .iter()
+                    .flat_map(|subdag| subdag.values().flatten().cloned())
+                    .collect::<Vec<_>>();
+                bft.sync_bft_dag_at_bootup(certificates).await;
+                // Commit the subdags, in place of consensus.
+                let (consensus_sender, consensus_receiver) = init_consensus_channels();
+                let consensus_handle = self.commit_subdags(consensus_receiver);
+                // Run the BFT.
+                bft.run(Some(consensus_sender), primary_sender.clone(), primary_receiver).await?;
+                Process {
+                    primary: bft.primary().clone(),
+                    bft: Some(bft),
+                    primary_sender,
+                    consensus_handle: Some(consensus_handle),
+                }
+            }
+            false => {
+                let mut primary = Primary::new(
+                    account,
+                    storage,
+                    ledger,
+                    None,
+                    &self.trusted_validators,
+                    dev,
+                    self.tcp_config.clone(),
+                )?;
+                // Run the primary.
+                primary.run(None, primary_sender.clone(), primary_receiver).await?;
+                Process { primary, bft: None, primary_sender, consensus_handle: None }
+            }
+        };
+        self.process = Some(process);
+        Ok(())
+    }
+
+    /// Crashes the validator; the state that is not on disk, including the proposal cache, is lost.
+    pub async fn crash(&mut self) {
+        if let Some(process) = self.process.take() {
+            match &process.bft {
+                Some(bft) => bft.shut_down().await,
+                None => process.primary.shut_down().await,
+            }
+            if let Some(handle) = process.consensus_handle {
+                handle.abort();
+            }
+            // Discard the proposal cache stored by the shutdown, which a crashed validator would not have stored.
+            let _ = std::fs::remove_file(proposal_cache_path(N::ID, Some(self.index as u16)));
+        }
+    }
+
+    /// Clears the state that outlives a crash, i.e. the storage service and the committed subdags.
+    pub fn clear_state(&mut self) -> Result<()> {
+        ensure!(self.process.is_none(), "Validator {} is running", self.index);
+        self.storage_service = Arc::new(BFTMemoryService::new());
+        self.committed.lock().clear();
+        Ok(())
+    }
+
+    /// Spawns the task that appends the subdags committed by the BFT to the committed subdags.
+    fn commit_subdags(&self, consensus_receiver: ConsensusReceiver<N>) -> JoinHandle<()> {
+        let ConsensusReceiver { mut rx_consensus_subdag } = consensus_receiver;
+        let committed = self.committed.clone();
+        tokio::spawn(async move {
+            while let Some((subdag, _transmissions, callback)) = rx_consensus_subdag.recv().await {
+                {
+                    let mut committed = committed.lock();
+                    // Skip the subdags that were committed before a restart, as the ledger would.
+                    if committed.last().map_or(true, |latest| subdag.anchor_round() > latest.anchor_round()) {
+                        committed.push(subdag);
+                    }
+                }
+                callback.send(Ok(())).ok();
+            }
+        })
+    }
+}
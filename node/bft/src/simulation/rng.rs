@@ -0,0 +1,43 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use parking_lot::{const_mutex, Mutex};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// The RNG of the running simulation, or `None` if no simulation is running.
+static RNG: Mutex<Option<StdRng>> = const_mutex(None);
+
+/// The RNG of a simulation, which seeds every RNG sampled by the validators (see `secure_rng()`).
+///
+/// As the simulation runs on a single thread, and the blocking tasks run in place, the RNGs are sampled
+/// in the same order on every run, so a seed reproduces the same signatures, shuffles, and choices.
+pub struct SimulatedRng;
+
+impl SimulatedRng {
+    /// Seeds the RNG with the given seed.
+    pub fn start(seed: u64) -> Self {
+        *RNG.lock() = Some(StdRng::seed_from_u64(seed));
+        Self
+    }
+
+    /// Returns a new RNG, seeded by the RNG of the simulation, if a simulation is running.
+    pub fn fork() -> Option<StdRng> {
+        RNG.lock().as_mut().map(|rng| StdRng::from_seed(rng.gen()))
+    }
+}
+
+impl Drop for SimulatedRng {
+    fn drop(&mut self) {
+        *RNG.lock() = None;
+    }
+}
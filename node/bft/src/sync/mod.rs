@@ -589,12 +589,23 @@ impl<N: Network> Sync<N> {
         // Insert the certificate ID into the pending queue.
         self.pending.insert(certificate_id, peer_ip, Some((callback_sender, should_send_request)));
 
+        // The peers that were sent a request by this call.
+        let mut requested_peers = Vec::new();
         // If the number of requests is less than or equal to the redundancy factor, send the certificate request to the peer.
         if should_send_request {
             // Send the certificate request to the peer.
             if self.gateway.send(peer_ip, Event::CertificateRequest(certificate_id.into())).await.is_none() {
                 bail!("Unable to fetch batch certificate {certificate_id} - failed to send request")
             }
+            requested_peers.push(peer_ip);
+            // If the peer is expected to be slow, hedge the request with faster validators.
+            let peer_scores = self.gateway.peer_scores();
+            for hedge_ip in self.pending.hedge(certificate_id, peer_ip, peer_scores, num_redundant_requests) {
+                debug!("Hedging request for certificate {} to '{peer_ip}' with '{hedge_ip}'", fmt_id(certificate_id));
+                if self.gateway.send(hedge_ip, Event::CertificateRequest(certificate_id.into())).await.is_some() {
+                    requested_peers.push(hedge_ip);
+                }
+            }
         } else {
             debug!(
                 "Skipped sending request for certificate {} to '{peer_ip}' ({num_sent_requests} redundant requests)",
@@ -606,7 +617,12 @@ impl<N: Network> Sync<N> {
             // If the certificate was fetched, return it.
             Ok(result) => Ok(result?),
             // If the certificate was not fetched, return an error.
-            Err(e) => bail!("Unable to fetch certificate {} - (timeout) {e}", fmt_id(certificate_id)),
+            Err(e) => {
+                // Record the failed requests.
+                let peer_scores = self.gateway.peer_scores();
+                requested_peers.into_iter().for_each(|ip| peer_scores.record_fetch(ip, false));
+                bail!("Unable to fetch certificate {} - (timeout) {e}", fmt_id(certificate_id))
+            }
         }
     }
 
@@ -631,6 +647,10 @@ impl<N: Network> Sync<N> {
         // If the peer IP exists, finish the pending request.
         if exists {
             // TODO: Validate the certificate.
+            // Record the fulfilled request.
+            if self.pending.contains_peer_with_sent_request(certificate.id(), peer_ip) {
+                self.gateway.peer_scores().record_fetch(peer_ip, true);
+            }
             // Remove the certificate ID from the pending queue.
             self.pending.remove(certificate.id(), Some(certificate));
         }
@@ -697,12 +717,12 @@ mod tests {
         // Create a genesis block with a seeded RNG to reproduce the same genesis private keys.
         let seed: u64 = rng.gen();
         let genesis_rng = &mut TestRng::from_seed(seed);
-        let genesis = VM::from(store).unwrap().genesis_beacon(account.private_key(), genesis_rng).unwrap();
+        let genesis = VM::from(store).unwrap().genesis_beacon(account.private_key().unwrap(), genesis_rng).unwrap();
 
         // Extract the private keys from the genesis committee by using the same RNG to sample private keys.
         let genesis_rng = &mut TestRng::from_seed(seed);
         let private_keys = [
-            *account.private_key(),
+            *account.private_key().unwrap(),
             PrivateKey::new(genesis_rng)?,
             PrivateKey::new(genesis_rng)?,
             PrivateKey::new(genesis_rng)?,
@@ -870,7 +890,15 @@ mod tests {
             Default::default(),
         ));
         // Initialize the gateway.
-        let gateway = Gateway::new(account.clone(), storage.clone(), syncing_ledger.clone(), None, &[], None)?;
+        let gateway = Gateway::new(
+            account.clone(),
+            storage.clone(),
+            syncing_ledger.clone(),
+            None,
+            &[],
+            None,
+            Default::default(),
+        )?;
         // Initialize the sync module.
         let sync = Sync::new(gateway.clone(), storage.clone(), syncing_ledger.clone());
         // Try to sync block 1.
@@ -905,12 +933,12 @@ mod tests {
         // Create a genesis block with a seeded RNG to reproduce the same genesis private keys.
         let seed: u64 = rng.gen();
         let genesis_rng = &mut TestRng::from_seed(seed);
-        let genesis = VM::from(store).unwrap().genesis_beacon(account.private_key(), genesis_rng).unwrap();
+        let genesis = VM::from(store).unwrap().genesis_beacon(account.private_key().unwrap(), genesis_rng).unwrap();
 
         // Extract the private keys from the genesis committee by using the same RNG to sample private keys.
         let genesis_rng = &mut TestRng::from_seed(seed);
         let private_keys = [
-            *account.private_key(),
+            *account.private_key().unwrap(),
             PrivateKey::new(genesis_rng)?,
             PrivateKey::new(genesis_rng)?,
             PrivateKey::new(genesis_rng)?,
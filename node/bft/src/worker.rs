@@ -14,7 +14,7 @@
 
 use crate::{
     events::{Event, TransmissionRequest, TransmissionResponse},
-    helpers::{fmt_id, max_redundant_requests, Pending, Ready, Storage, WorkerReceiver},
+    helpers::{fmt_id, max_redundant_requests, secure_rng, Pending, Ready, Storage, WorkerReceiver},
     spawn_blocking,
     ProposedBatch,
     Transport,
@@ -232,7 +232,7 @@ impl<N: Network> Worker<N> {
             .ready
             .transmission_ids()
             .into_iter()
-            .choose_multiple(&mut rand::thread_rng(), Self::MAX_TRANSMISSIONS_PER_WORKER_PING)
+            .choose_multiple(&mut secure_rng(), Self::MAX_TRANSMISSIONS_PER_WORKER_PING)
             .into_iter()
             .collect::<IndexSet<_>>();
 
@@ -453,12 +453,27 @@ impl<N: Network> Worker<N> {
         // Insert the transmission ID into the pending queue.
         self.pending.insert(transmission_id, peer_ip, Some((callback_sender, should_send_request)));
 
+        // The peers that were sent a request by this call.
+        let mut requested_peers = Vec::new();
         // If the number of requests is less than or equal to the the redundancy factor, send the transmission request to the peer.
         if should_send_request {
             // Send the transmission request to the peer.
             if self.gateway.send(peer_ip, Event::TransmissionRequest(transmission_id.into())).await.is_none() {
                 bail!("Unable to fetch transmission - failed to send request")
             }
+            requested_peers.push(peer_ip);
+            // If the peer is expected to be slow, hedge the request with faster validators.
+            let peer_scores = self.gateway.peer_scores();
+            for hedge_ip in self.pending.hedge(transmission_id, peer_ip, &peer_scores, num_redundant_requests) {
+                debug!(
+                    "Hedging request for transmission {}.{} to '{peer_ip}' with '{hedge_ip}'",
+                    fmt_id(transmission_id),
+                    fmt_id(transmission_id.checksum().unwrap_or_default()).dimmed()
+                );
+                if self.gateway.send(hedge_ip, Event::TransmissionRequest(transmission_id.into())).await.is_some() {
+                    requested_peers.push(hedge_ip);
+                }
+            }
         } else {
             debug!(
                 "Skipped sending request for transmission {}.{} to '{peer_ip}' ({num_sent_requests} redundant requests)",
@@ -471,7 +486,12 @@ impl<N: Network> Worker<N> {
             // If the transmission was fetched, return it.
             Ok(result) => Ok((transmission_id, result?)),
             // If the transmission was not fetched, return an error.
-            Err(e) => bail!("Unable to fetch transmission - (timeout) {e}"),
+            Err(e) => {
+                // Record the failed requests.
+                let peer_scores = self.gateway.peer_scores();
+                requested_peers.into_iter().for_each(|ip| peer_scores.record_fetch(ip, false));
+                bail!("Unable to fetch transmission - (timeout) {e}")
+            }
         }
     }
 
@@ -486,6 +506,10 @@ impl<N: Network> Worker<N> {
             // Ensure the transmission is not a fee and matches the transmission ID.
             match self.ledger.ensure_transmission_is_well_formed(transmission_id, &mut transmission) {
                 Ok(()) => {
+                    // Record the fulfilled request.
+                    if self.pending.contains_peer_with_sent_request(transmission_id, peer_ip) {
+                        self.gateway.peer_scores().record_fetch(peer_ip, true);
+                    }
                     // Remove the transmission ID from the pending queue.
                     self.pending.remove(transmission_id, Some(transmission));
                 }
@@ -523,7 +547,7 @@ impl<N: Network> Worker<N> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::helpers::CALLBACK_EXPIRATION_IN_SECS;
+    use crate::helpers::{PeerScores, CALLBACK_EXPIRATION_IN_SECS};
     use snarkos_node_bft_ledger_service::LedgerService;
     use snarkos_node_bft_storage_service::BFTMemoryService;
     use snarkvm::{
@@ -550,6 +574,7 @@ mod tests {
         #[async_trait]
         impl<N:Network> Transport<N> for Gateway<N> {
             fn broadcast(&self, event: Event<N>);
+            fn peer_scores(&self) -> Arc<PeerScores>;
             async fn send(&self, peer_ip: SocketAddr, event: Event<N>) -> Option<oneshot::Receiver<io::Result<()>>>;
         }
     }
@@ -671,6 +696,7 @@ mod tests {
         let committee_clone = committee.clone();
         // Setup the mock gateway and ledger.
         let mut gateway = MockGateway::default();
+        gateway.expect_peer_scores().returning(|| Arc::new(PeerScores::new()));
         gateway.expect_send().returning(|_, _| {
             let (_tx, rx) = oneshot::channel();
             Some(rx)
@@ -711,6 +737,7 @@ mod tests {
         let committee_clone = committee.clone();
         // Setup the mock gateway and ledger.
         let mut gateway = MockGateway::default();
+        gateway.expect_peer_scores().returning(|| Arc::new(PeerScores::new()));
         gateway.expect_send().returning(|_, _| {
             let (_tx, rx) = oneshot::channel();
             Some(rx)
@@ -748,6 +775,7 @@ mod tests {
         let committee_clone = committee.clone();
         // Setup the mock gateway and ledger.
         let mut gateway = MockGateway::default();
+        gateway.expect_peer_scores().returning(|| Arc::new(PeerScores::new()));
         gateway.expect_send().returning(|_, _| {
             let (_tx, rx) = oneshot::channel();
             Some(rx)
@@ -785,6 +813,7 @@ mod tests {
         let committee_clone = committee.clone();
         // Setup the mock gateway and ledger.
         let mut gateway = MockGateway::default();
+        gateway.expect_peer_scores().returning(|| Arc::new(PeerScores::new()));
         gateway.expect_send().returning(|_, _| {
             let (_tx, rx) = oneshot::channel();
             Some(rx)
@@ -822,6 +851,7 @@ mod tests {
         let committee_clone = committee.clone();
         // Setup the mock gateway and ledger.
         let mut gateway = MockGateway::default();
+        gateway.expect_peer_scores().returning(|| Arc::new(PeerScores::new()));
         gateway.expect_send().returning(|_, _| {
             let (_tx, rx) = oneshot::channel();
             Some(rx)
@@ -859,6 +889,7 @@ mod tests {
         let committee_clone = committee.clone();
         // Setup the mock gateway and ledger.
         let mut gateway = MockGateway::default();
+        gateway.expect_peer_scores().returning(|| Arc::new(PeerScores::new()));
         gateway.expect_send().returning(|_, _| {
             let (_tx, rx) = oneshot::channel();
             Some(rx)
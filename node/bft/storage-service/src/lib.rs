@@ -25,5 +25,8 @@ pub mod persistent;
 #[cfg(feature = "persistent")]
 pub use persistent::*;
 
+pub mod repair;
+pub use repair::*;
+
 pub mod traits;
 pub use traits::*;
@@ -12,9 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::StorageService;
+use crate::{RepairChecks, RepairReport, StorageService};
 use snarkvm::{
-    ledger::narwhal::{BatchHeader, Transmission, TransmissionID},
+    ledger::narwhal::{BatchCertificate, BatchHeader, Transmission, TransmissionID},
     prelude::{bail, Field, Network, Result},
 };
 
@@ -30,6 +30,8 @@ pub struct BFTMemoryService<N: Network> {
     transmissions: RwLock<IndexMap<TransmissionID<N>, (Transmission<N>, IndexSet<Field<N>>)>>,
     /// The map of `aborted transmission ID` to `certificate IDs` entries.
     aborted_transmission_ids: RwLock<IndexMap<TransmissionID<N>, IndexSet<Field<N>>>>,
+    /// The map of `certificate ID` to `certificate` entries.
+    certificates: RwLock<IndexMap<Field<N>, BatchCertificate<N>>>,
 }
 
 impl<N: Network> Default for BFTMemoryService<N> {
@@ -42,7 +44,11 @@ impl<N: Network> Default for BFTMemoryService<N> {
 impl<N: Network> BFTMemoryService<N> {
     /// Initializes a new BFT in-memory storage service.
     pub fn new() -> Self {
-        Self { transmissions: Default::default(), aborted_transmission_ids: Default::default() }
+        Self {
+            transmissions: Default::default(),
+            aborted_transmission_ids: Default::default(),
+            certificates: Default::default(),
+        }
     }
 }
 
@@ -194,6 +200,60 @@ impl<N: Network> StorageService<N> for BFTMemoryService<N> {
         }
     }
 
+    /// Inserts the given certificate, and its certificate ID for each of its transmission IDs, into storage.
+    fn insert_certificate(
+        &self,
+        certificate: &BatchCertificate<N>,
+        aborted_transmission_ids: HashSet<TransmissionID<N>>,
+        missing_transmissions: HashMap<TransmissionID<N>, Transmission<N>>,
+    ) {
+        // Insert the certificate ID for each of the transmissions.
+        self.insert_transmissions(
+            certificate.id(),
+            certificate.transmission_ids().clone(),
+            aborted_transmission_ids,
+            missing_transmissions,
+        );
+        // Insert the certificate.
+        self.certificates.write().insert(certificate.id(), certificate.clone());
+    }
+
+    /// Removes the certificate for the given certificate ID from storage.
+    fn remove_certificate(&self, certificate_id: &Field<N>) {
+        self.certificates.write().shift_remove(certificate_id);
+    }
+
+    /// Returns the certificates in storage.
+    fn get_certificates(&self) -> Vec<BatchCertificate<N>> {
+        self.certificates.read().values().cloned().collect()
+    }
+
+    /// Verifies the certificate IDs referenced by the transmissions and aborted transmission IDs in storage.
+    ///
+    /// The references to unknown certificates are removed, along with the entries that no longer reference any certificate.
+    fn repair(&self, checks: &RepairChecks<N>) -> RepairReport {
+        let mut report = RepairReport::default();
+        // Verify the transmissions.
+        self.transmissions.write().retain(|transmission_id, (transmission, certificate_ids)| {
+            let mut verified_ids = certificate_ids.clone();
+            let keep = report.check_transmission(transmission_id, transmission, &mut verified_ids, checks);
+            if !checks.dry_run {
+                *certificate_ids = verified_ids;
+            }
+            keep || checks.dry_run
+        });
+        // Verify the aborted transmission IDs.
+        self.aborted_transmission_ids.write().retain(|_, certificate_ids| {
+            let mut verified_ids = certificate_ids.clone();
+            let keep = report.check_aborted_transmission_id(&mut verified_ids, checks);
+            if !checks.dry_run {
+                *certificate_ids = verified_ids;
+            }
+            keep || checks.dry_run
+        });
+        report
+    }
+
     /// Returns a HashMap over the `(transmission ID, (transmission, certificate IDs))` entries.
     #[cfg(any(test, feature = "test"))]
     fn as_hashmap(&self) -> HashMap<TransmissionID<N>, (Transmission<N>, IndexSet<Field<N>>)> {
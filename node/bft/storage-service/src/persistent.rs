@@ -12,12 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::StorageService;
+use crate::{RepairChecks, RepairReport, StorageService};
 use snarkvm::{
     ledger::{
-        narwhal::{BatchHeader, Transmission, TransmissionID},
+        narwhal::{BatchCertificate, BatchHeader, Transmission, TransmissionID},
         store::{
             cow_to_cloned,
+            cow_to_copied,
             helpers::{
                 rocksdb::{
                     internal::{self, BFTMap, Database, MapID},
@@ -39,6 +40,22 @@ use std::{
 };
 use tracing::error;
 
+/// The IDs of the BFT maps that are defined in snarkOS, rather than in snarkVM.
+///
+/// Note: The IDs are allocated from `0xB000`, above the range of the map IDs defined in snarkVM.
+/// They are persisted in the ledger database, and must never be reordered or reused.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u16)]
+pub enum BFTStorageMap {
+    Certificates = 0xB000,
+}
+
+impl From<BFTStorageMap> for u16 {
+    fn from(map: BFTStorageMap) -> u16 {
+        map as u16
+    }
+}
+
 /// A BFT persistent storage service.
 #[derive(Debug)]
 pub struct BFTPersistentStorage<N: Network> {
@@ -46,6 +63,8 @@ pub struct BFTPersistentStorage<N: Network> {
     transmissions: DataMap<TransmissionID<N>, (Transmission<N>, IndexSet<Field<N>>)>,
     /// The map of `aborted transmission ID` to `certificate IDs` entries.
     aborted_transmission_ids: DataMap<TransmissionID<N>, IndexSet<Field<N>>>,
+    /// The map of `certificate ID` to `certificate` entries, for the certificates that are not yet garbage collected.
+    certificates: DataMap<Field<N>, BatchCertificate<N>>,
 }
 
 impl<N: Network> BFTPersistentStorage<N> {
@@ -55,9 +74,10 @@ impl<N: Network> BFTPersistentStorage<N> {
             transmissions: internal::RocksDB::open_map(N::ID, storage_mode.clone(), MapID::BFT(BFTMap::Transmissions))?,
             aborted_transmission_ids: internal::RocksDB::open_map(
                 N::ID,
-                storage_mode,
+                storage_mode.clone(),
                 MapID::BFT(BFTMap::AbortedTransmissionIDs),
             )?,
+            certificates: internal::RocksDB::open_map(N::ID, storage_mode, BFTStorageMap::Certificates)?,
         })
     }
 
@@ -71,12 +91,28 @@ impl<N: Network> BFTPersistentStorage<N> {
                 MapID::BFT(BFTMap::Transmissions),
             )?,
             aborted_transmission_ids: internal::RocksDB::open_map_testing(
-                temp_dir,
+                temp_dir.clone(),
                 dev,
                 MapID::BFT(BFTMap::AbortedTransmissionIDs),
             )?,
+            certificates: internal::RocksDB::open_map_testing(temp_dir, dev, BFTStorageMap::Certificates)?,
         })
     }
+
+    /// Starts an atomic batch across the maps, which share the same database.
+    fn start_atomic(&self) {
+        self.certificates.start_atomic();
+        self.transmissions.start_atomic();
+        self.aborted_transmission_ids.start_atomic();
+    }
+
+    /// Finishes the atomic batch across the maps, which writes all of their updates at once.
+    fn finish_atomic(&self) -> Result<()> {
+        // Note: The batch is written by the outermost call, so the maps are finished in the reverse order.
+        self.aborted_transmission_ids.finish_atomic()?;
+        self.transmissions.finish_atomic()?;
+        self.certificates.finish_atomic()
+    }
 }
 
 impl<N: Network> StorageService<N> for BFTPersistentStorage<N> {
@@ -291,10 +327,113 @@ impl<N: Network> StorageService<N> for BFTPersistentStorage<N> {
         }
     }
 
+    /// Inserts the given certificate, and its certificate ID for each of its transmission IDs, into storage.
+    ///
+    /// The certificate and the transmissions are written in one atomic batch,
+    /// so that a restart never restores a certificate without its transmissions.
+    fn insert_certificate(
+        &self,
+        certificate: &BatchCertificate<N>,
+        aborted_transmission_ids: HashSet<TransmissionID<N>>,
+        missing_transmissions: HashMap<TransmissionID<N>, Transmission<N>>,
+    ) {
+        let certificate_id = certificate.id();
+        // Start the atomic batch.
+        self.start_atomic();
+        // Insert the certificate.
+        if let Err(e) = self.certificates.insert(certificate_id, certificate.clone()) {
+            error!("Failed to insert certificate {certificate_id} into storage - {e}");
+        }
+        // Insert the certificate ID for each of the transmissions.
+        self.insert_transmissions(
+            certificate_id,
+            certificate.transmission_ids().clone(),
+            aborted_transmission_ids,
+            missing_transmissions,
+        );
+        // Write the atomic batch.
+        if let Err(e) = self.finish_atomic() {
+            error!("Failed to write certificate {certificate_id} and its transmissions into storage - {e}");
+        }
+    }
+
+    /// Removes the certificate for the given certificate ID from storage.
+    fn remove_certificate(&self, certificate_id: &Field<N>) {
+        if let Err(e) = self.certificates.remove(certificate_id) {
+            error!("Failed to remove certificate {certificate_id} from storage - {e}");
+        }
+    }
+
+    /// Returns the certificates in storage.
+    fn get_certificates(&self) -> Vec<BatchCertificate<N>> {
+        self.certificates.values_confirmed().map(|certificate| cow_to_cloned!(certificate)).collect()
+    }
+
+    /// Verifies the certificate IDs referenced by the transmissions and aborted transmission IDs in storage.
+    ///
+    /// The references to unknown certificates are removed, along with the entries that no longer reference any certificate.
+    fn repair(&self, checks: &RepairChecks<N>) -> RepairReport {
+        let mut report = RepairReport::default();
+
+        // Note: The updates are collected first, so that the maps are not modified while they are iterated.
+        // Verify the transmissions.
+        let mut transmission_updates = Vec::new();
+        for (transmission_id, entry) in self.transmissions.iter_confirmed() {
+            let transmission_id = cow_to_copied!(transmission_id);
+            let (transmission, mut certificate_ids) = cow_to_cloned!(entry);
+            let num_references = certificate_ids.len();
+            match report.check_transmission(&transmission_id, &transmission, &mut certificate_ids, checks) {
+                true if certificate_ids.len() != num_references => {
+                    transmission_updates.push((transmission_id, Some((transmission, certificate_ids))))
+                }
+                true => (),
+                false => transmission_updates.push((transmission_id, None)),
+            }
+        }
+        // Verify the aborted transmission IDs.
+        let mut aborted_updates = Vec::new();
+        for (transmission_id, entry) in self.aborted_transmission_ids.iter_confirmed() {
+            let transmission_id = cow_to_copied!(transmission_id);
+            let mut certificate_ids = cow_to_cloned!(entry);
+            let num_references = certificate_ids.len();
+            match report.check_aborted_transmission_id(&mut certificate_ids, checks) {
+                true if certificate_ids.len() != num_references => {
+                    aborted_updates.push((transmission_id, Some(certificate_ids)))
+                }
+                true => (),
+                false => aborted_updates.push((transmission_id, None)),
+            }
+        }
+
+        // If this is a dry run, do not modify the storage.
+        if checks.dry_run {
+            return report;
+        }
+        // Apply the updates.
+        for (transmission_id, update) in transmission_updates {
+            let result = match update {
+                Some(entry) => self.transmissions.insert(transmission_id, entry),
+                None => self.transmissions.remove(&transmission_id),
+            };
+            if let Err(e) = result {
+                error!("Failed to repair transmission {transmission_id} in storage - {e}");
+            }
+        }
+        for (transmission_id, update) in aborted_updates {
+            let result = match update {
+                Some(certificate_ids) => self.aborted_transmission_ids.insert(transmission_id, certificate_ids),
+                None => self.aborted_transmission_ids.remove(&transmission_id),
+            };
+            if let Err(e) = result {
+                error!("Failed to repair aborted transmission ID {transmission_id} in storage - {e}");
+            }
+        }
+        report
+    }
+
     /// Returns a HashMap over the `(transmission ID, (transmission, certificate IDs))` entries.
     #[cfg(any(test, feature = "test"))]
     fn as_hashmap(&self) -> HashMap<TransmissionID<N>, (Transmission<N>, IndexSet<Field<N>>)> {
-        use snarkvm::ledger::store::cow_to_copied;
         self.transmissions.iter_confirmed().map(|(k, v)| (cow_to_copied!(k), cow_to_cloned!(v))).collect()
     }
 }
@@ -0,0 +1,136 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use snarkvm::{
+    ledger::narwhal::{Transmission, TransmissionID},
+    prelude::{Field, Network, ToBytes},
+};
+
+use indexmap::IndexSet;
+use std::fmt;
+
+/// The checks used to verify the entries of a storage service.
+pub struct RepairChecks<'a, N: Network> {
+    /// Returns `true` if the given certificate ID is known, i.e. its certificate is in storage.
+    pub is_known_certificate: &'a dyn Fn(&Field<N>) -> bool,
+    /// Returns `true` if the given transmission ID is in the ledger.
+    pub is_in_ledger: &'a dyn Fn(&TransmissionID<N>) -> bool,
+    /// If `true`, the storage service is only verified, and not modified.
+    pub dry_run: bool,
+}
+
+/// The outcome of verifying and repairing a storage service.
+/// The orphans and stale references are removed, unless the repair is a dry run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// The number of transmissions that were verified.
+    pub num_transmissions: usize,
+    /// The number of aborted transmission IDs that were verified.
+    pub num_aborted_transmission_ids: usize,
+    /// The number of references to unknown certificates.
+    pub num_stale_references: usize,
+    /// The number of orphaned transmissions that are in the ledger.
+    pub num_committed_orphans: usize,
+    /// The number of orphaned transmissions that are not in the ledger.
+    pub num_unconfirmed_orphans: usize,
+    /// The number of orphaned aborted transmission IDs.
+    pub num_aborted_orphans: usize,
+    /// The total size of the orphaned transmissions, in bytes.
+    pub orphaned_bytes: usize,
+    /// The total size of the transmissions that were kept, in bytes.
+    pub retained_bytes: usize,
+}
+
+impl RepairReport {
+    /// Returns `true` if no orphans or stale references were found.
+    pub const fn is_consistent(&self) -> bool {
+        self.num_stale_references == 0
+            && self.num_committed_orphans == 0
+            && self.num_unconfirmed_orphans == 0
+            && self.num_aborted_orphans == 0
+    }
+
+    /// Verifies the given transmission entry, removing the references to unknown certificates.
+    /// Returns `true` if the entry should be kept, and `false` if it is an orphan.
+    pub(crate) fn check_transmission<N: Network>(
+        &mut self,
+        transmission_id: &TransmissionID<N>,
+        transmission: &Transmission<N>,
+        certificate_ids: &mut IndexSet<Field<N>>,
+        checks: &RepairChecks<N>,
+    ) -> bool {
+        self.num_transmissions += 1;
+        let size = transmission.to_bytes_le().map(|bytes| bytes.len()).unwrap_or_default();
+        self.remove_stale_references(certificate_ids, checks);
+        match certificate_ids.is_empty() {
+            true => {
+                match (checks.is_in_ledger)(transmission_id) {
+                    true => self.num_committed_orphans += 1,
+                    false => self.num_unconfirmed_orphans += 1,
+                }
+                self.orphaned_bytes += size;
+                false
+            }
+            false => {
+                self.retained_bytes += size;
+                true
+            }
+        }
+    }
+
+    /// Verifies the given aborted transmission ID entry, removing the references to unknown certificates.
+    /// Returns `true` if the entry should be kept, and `false` if it is an orphan.
+    pub(crate) fn check_aborted_transmission_id<N: Network>(
+        &mut self,
+        certificate_ids: &mut IndexSet<Field<N>>,
+        checks: &RepairChecks<N>,
+    ) -> bool {
+        self.num_aborted_transmission_ids += 1;
+        self.remove_stale_references(certificate_ids, checks);
+        if certificate_ids.is_empty() {
+            self.num_aborted_orphans += 1;
+        }
+        !certificate_ids.is_empty()
+    }
+
+    /// Removes the references to unknown certificates from the given set.
+    fn remove_stale_references<N: Network>(
+        &mut self,
+        certificate_ids: &mut IndexSet<Field<N>>,
+        checks: &RepairChecks<N>,
+    ) {
+        let num_references = certificate_ids.len();
+        certificate_ids.retain(|certificate_id| (checks.is_known_certificate)(certificate_id));
+        self.num_stale_references += num_references - certificate_ids.len();
+    }
+}
+
+impl fmt::Display for RepairReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "verified {} transmissions ({} bytes retained) and {} aborted transmission IDs, with {} stale certificate \
+             references, {} committed and {} unconfirmed orphaned transmissions ({} bytes), and {} orphaned aborted \
+             transmission IDs",
+            self.num_transmissions,
+            self.retained_bytes,
+            self.num_aborted_transmission_ids,
+            self.num_stale_references,
+            self.num_committed_orphans,
+            self.num_unconfirmed_orphans,
+            self.orphaned_bytes,
+            self.num_aborted_orphans,
+        )
+    }
+}
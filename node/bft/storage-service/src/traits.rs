@@ -12,8 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::{RepairChecks, RepairReport};
 use snarkvm::{
-    ledger::narwhal::{BatchHeader, Transmission, TransmissionID},
+    ledger::narwhal::{BatchCertificate, BatchHeader, Transmission, TransmissionID},
     prelude::{Field, Network, Result},
 };
 
@@ -53,6 +54,28 @@ pub trait StorageService<N: Network>: Debug + Send + Sync {
     /// If the transmission no longer references any certificate IDs, the entry is removed from storage.
     fn remove_transmissions(&self, certificate_id: &Field<N>, transmission_ids: &IndexSet<TransmissionID<N>>);
 
+    /// Inserts the given certificate, and its certificate ID for each of its transmission IDs, into storage,
+    /// so that they can be restored after a restart.
+    ///
+    /// The certificate and the transmissions are written atomically.
+    fn insert_certificate(
+        &self,
+        certificate: &BatchCertificate<N>,
+        aborted_transmission_ids: HashSet<TransmissionID<N>>,
+        missing_transmissions: HashMap<TransmissionID<N>, Transmission<N>>,
+    );
+
+    /// Removes the certificate for the given certificate ID from storage.
+    fn remove_certificate(&self, certificate_id: &Field<N>);
+
+    /// Returns the certificates in storage.
+    fn get_certificates(&self) -> Vec<BatchCertificate<N>>;
+
+    /// Verifies the certificate IDs referenced by the transmissions and aborted transmission IDs in storage.
+    ///
+    /// The references to unknown certificates are removed, along with the entries that no longer reference any certificate.
+    fn repair(&self, checks: &RepairChecks<N>) -> RepairReport;
+
     /// Returns a HashMap over the `(transmission ID, (transmission, certificate IDs))` entries.
     #[cfg(any(test, feature = "test"))]
     fn as_hashmap(&self) -> HashMap<TransmissionID<N>, (Transmission<N>, IndexSet<Field<N>>)>;
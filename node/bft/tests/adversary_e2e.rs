@@ -0,0 +1,128 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "adversary")]
+
+#[allow(dead_code)]
+mod common;
+#[allow(dead_code)]
+mod components;
+
+use crate::common::{
+    primary::{TestNetwork, TestNetworkConfig},
+    utils::wait_until,
+};
+use snarkos_node_bft::{helpers::AdversaryMode, MAX_BATCH_DELAY_IN_MS};
+use std::time::Duration;
+use tokio::time::sleep;
+
+const N: u16 = 4;
+const TRANSMISSION_INTERVAL_MS: u64 = 10;
+
+/// Starts a network of `N` validators, in which the given validators misbehave with the given mode.
+async fn start_network_with_adversaries(adversaries: &[u16], mode: AdversaryMode) -> TestNetwork {
+    let mut network = TestNetwork::new(TestNetworkConfig {
+        num_nodes: N,
+        bft: true,
+        connect_all: true,
+        fire_transmissions: Some(TRANSMISSION_INTERVAL_MS),
+        // Set this to Some(0..=4) to see the logs.
+        log_level: None,
+        log_connections: false,
+    })
+    .await;
+    network.start().await;
+    for id in adversaries {
+        network.set_adversary_mode(*id, mode);
+    }
+    network
+}
+
+/// Asserts that the honest validators make progress, never accept conflicting certificates, and commit
+/// the same subdags (which stand in for their ledgers), while a single (f = 1) validator misbehaves with the given mode.
+async fn assert_liveness_and_safety_with_one_adversary(mode: AdversaryMode) {
+    const TARGET_ROUND: u64 = 10;
+    const HONEST: [u16; 3] = [1, 2, 3];
+
+    let network = start_network_with_adversaries(&[0], mode).await;
+
+    // Ensure the honest validators reach the target round.
+    wait_until(Duration::from_secs(60), || network.is_round_reached_by(&HONEST, TARGET_ROUND)).await;
+    // Ensure the honest validators never accepted an equivocating certificate.
+    assert!(network.is_equivocation_free(&HONEST));
+    // Ensure the honest validators committed identical subdags.
+    assert!(network.num_consistently_committed(&HONEST) > 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_liveness_with_withheld_signatures() {
+    assert_liveness_and_safety_with_one_adversary(AdversaryMode { withhold_signatures: true, ..Default::default() })
+        .await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_liveness_with_equivocated_proposals() {
+    assert_liveness_and_safety_with_one_adversary(AdversaryMode { equivocate_proposals: true, ..Default::default() })
+        .await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_liveness_with_stale_and_future_certificates() {
+    assert_liveness_and_safety_with_one_adversary(AdversaryMode {
+        send_stale_certificates: true,
+        send_future_certificates: true,
+        ..Default::default()
+    })
+    .await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_liveness_with_refused_transmission_requests() {
+    assert_liveness_and_safety_with_one_adversary(AdversaryMode {
+        refuse_transmission_requests: true,
+        ..Default::default()
+    })
+    .await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_liveness_with_flooded_certificate_requests() {
+    assert_liveness_and_safety_with_one_adversary(AdversaryMode {
+        flood_certificate_requests: true,
+        ..Default::default()
+    })
+    .await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_liveness_with_all_behaviours() {
+    assert_liveness_and_safety_with_one_adversary(AdversaryMode::all()).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_safety_with_too_many_adversaries() {
+    const HONEST: [u16; 2] = [2, 3];
+
+    // With more than f faulty validators, liveness is not guaranteed, but safety must still hold.
+    let network = start_network_with_adversaries(&[0, 1], AdversaryMode::all()).await;
+
+    // Let the network run for a while.
+    for _ in 0..10 {
+        sleep(Duration::from_millis(MAX_BATCH_DELAY_IN_MS * 2)).await;
+        // Ensure the honest validators never accepted an equivocating certificate.
+        assert!(network.is_equivocation_free(&HONEST));
+        // Ensure the honest validators never committed diverging subdags.
+        network.num_consistently_committed(&HONEST);
+    }
+}
@@ -17,14 +17,16 @@ mod common;
 #[allow(dead_code)]
 mod components;
 
-use crate::common::primary::{TestNetwork, TestNetworkConfig};
-use deadline::deadline;
+use crate::common::{
+    primary::{TestNetwork, TestNetworkConfig},
+    utils::wait_until,
+};
 use itertools::Itertools;
 use snarkos_node_bft::MAX_FETCH_TIMEOUT_IN_MS;
 use std::time::Duration;
 use tokio::time::sleep;
 
-#[tokio::test(flavor = "multi_thread")]
+#[tokio::test(start_paused = true)]
 #[ignore = "long-running e2e test"]
 async fn test_state_coherence() {
     const N: u16 = 4;
@@ -36,16 +38,23 @@ async fn test_state_coherence() {
         connect_all: true,
         fire_transmissions: Some(TRANSMISSION_INTERVAL_MS),
         // Set this to Some(0..=4) to see the logs.
-        log_level: Some(0),
-        log_connections: true,
-    });
+        log_level: None,
+        log_connections: false,
+    })
+    .await;
 
     network.start().await;
 
-    std::future::pending::<()>().await;
+    // Let the network run for a while.
+    const TARGET_ROUND: u64 = 20;
+    wait_until(Duration::from_secs(120), || network.is_round_reached(TARGET_ROUND)).await;
+
+    // Check the nodes agree on the committees and on what they committed.
+    assert!(network.is_committee_coherent(1..=TARGET_ROUND));
+    assert!(network.num_consistently_committed(&[0, 1, 2, 3]) > 0);
 }
 
-#[tokio::test(flavor = "multi_thread")]
+#[tokio::test(start_paused = true)]
 #[ignore = "fails"]
 async fn test_resync() {
     // Start N nodes, connect them and start the cannons for each.
@@ -57,41 +66,30 @@ async fn test_resync() {
         connect_all: true,
         fire_transmissions: Some(TRANSMISSION_INTERVAL_MS),
         // Set this to Some(0..=4) to see the logs.
-        log_level: Some(0),
+        log_level: None,
         log_connections: false,
-    });
+    })
+    .await;
     network.start().await;
 
     // Let the nodes advance through the rounds.
     const BREAK_ROUND: u64 = 4;
-    let network_clone = network.clone();
-    deadline!(Duration::from_secs(20), move || { network_clone.is_round_reached(BREAK_ROUND) });
+    wait_until(Duration::from_secs(20), || network.is_round_reached(BREAK_ROUND)).await;
 
     network.disconnect(N).await;
 
-    let mut spare_network = TestNetwork::new(TestNetworkConfig {
-        num_nodes: N,
-        bft: true,
-        connect_all: false,
-        fire_transmissions: None,
-        log_level: None,
-        log_connections: false,
-    });
-    spare_network.start().await;
-
+    // Replace all the nodes but the first with fresh ones, which have to resync.
     for i in 1..N {
-        let spare_validator = spare_network.validators.get(&i).cloned().unwrap();
-        network.validators.insert(i, spare_validator);
+        network.simulation.replace(i as usize).await.unwrap();
     }
 
     network.connect_all().await;
 
     const RECOVERY_ROUND: u64 = 8;
-    let network_clone = network.clone();
-    deadline!(Duration::from_secs(20), move || { network_clone.is_round_reached(RECOVERY_ROUND) });
+    wait_until(Duration::from_secs(20), || network.is_round_reached(RECOVERY_ROUND)).await;
 }
 
-#[tokio::test(flavor = "multi_thread")]
+#[tokio::test(start_paused = true)]
 async fn test_quorum_threshold() {
     // Start N nodes but don't connect them.
     const N: u16 = 4;
@@ -105,12 +103,13 @@ async fn test_quorum_threshold() {
         // Set this to Some(0..=4) to see the logs.
         log_level: None,
         log_connections: true,
-    });
+    })
+    .await;
     network.start().await;
 
     // Check each node is at round 1 (0 is genesis).
-    for validators in network.validators.values() {
-        assert_eq!(validators.primary.current_round(), 1);
+    for primary in network.primaries() {
+        assert_eq!(primary.current_round(), 1);
     }
 
     // Start the cannons for node 0.
@@ -119,8 +118,8 @@ async fn test_quorum_threshold() {
     sleep(Duration::from_millis(MAX_FETCH_TIMEOUT_IN_MS)).await;
 
     // Check each node is still at round 1.
-    for validator in network.validators.values() {
-        assert_eq!(validator.primary.current_round(), 1);
+    for primary in network.primaries() {
+        assert_eq!(primary.current_round(), 1);
     }
 
     // Connect the first two nodes and start the cannons for node 1.
@@ -130,8 +129,8 @@ async fn test_quorum_threshold() {
     sleep(Duration::from_millis(MAX_FETCH_TIMEOUT_IN_MS)).await;
 
     // Check each node is still at round 1.
-    for validator in network.validators.values() {
-        assert_eq!(validator.primary.current_round(), 1);
+    for primary in network.primaries() {
+        assert_eq!(primary.current_round(), 1);
     }
 
     // Connect the third node and start the cannons for it.
@@ -141,10 +140,10 @@ async fn test_quorum_threshold() {
 
     // Check the nodes reach quorum and advance through the rounds.
     const TARGET_ROUND: u64 = 4;
-    deadline!(Duration::from_secs(20), move || { network.is_round_reached(TARGET_ROUND) });
+    wait_until(Duration::from_secs(20), || network.is_round_reached(TARGET_ROUND)).await;
 }
 
-#[tokio::test(flavor = "multi_thread")]
+#[tokio::test(start_paused = true)]
 async fn test_quorum_break() {
     // Start N nodes, connect them and start the cannons for each.
     const N: u16 = 4;
@@ -157,14 +156,13 @@ async fn test_quorum_break() {
         // Set this to Some(0..=4) to see the logs.
         log_level: None,
         log_connections: true,
-    });
+    })
+    .await;
     network.start().await;
 
     // Check the nodes have started advancing through the rounds.
     const TARGET_ROUND: u64 = 4;
-    // Note: cloning the network is fine because the primaries it wraps are `Arc`ed.
-    let network_clone = network.clone();
-    deadline!(Duration::from_secs(20), move || { network_clone.is_round_reached(TARGET_ROUND) });
+    wait_until(Duration::from_secs(20), || network.is_round_reached(TARGET_ROUND)).await;
 
     // Break the quorum by disconnecting two nodes.
     const NUM_NODES: u16 = 2;
@@ -174,7 +172,7 @@ async fn test_quorum_break() {
     assert!(network.is_halted().await);
 }
 
-#[tokio::test(flavor = "multi_thread")]
+#[tokio::test(start_paused = true)]
 async fn test_leader_election_consistency() {
     // The minimum and maximum rounds to check for leader consistency.
     // From manual experimentation, the minimum round that works is 4.
@@ -193,31 +191,30 @@ async fn test_leader_election_consistency() {
         // Set this to Some(0..=4) to see the logs.
         log_level: None,
         log_connections: true,
-    });
+    })
+    .await;
     network.start().await;
 
     // Wait for starting round to be reached
-    let cloned_network = network.clone();
-    deadline!(Duration::from_secs(60), move || { cloned_network.is_round_reached(STARTING_ROUND) });
+    wait_until(Duration::from_secs(60), || network.is_round_reached(STARTING_ROUND)).await;
 
     // Check that validators agree about leaders in every even round
     for target_round in (STARTING_ROUND..=MAX_ROUND).step_by(2) {
-        let cloned_network = network.clone();
-        deadline!(Duration::from_secs(20), move || { cloned_network.is_round_reached(target_round) });
+        wait_until(Duration::from_secs(20), || network.is_round_reached(target_round)).await;
 
         // Get all validators in the network
-        let validators = network.validators.values().collect_vec();
+        let validators = network.simulation.nodes();
 
         // Get leaders of all validators in the current round
         let mut leaders = Vec::new();
         for validator in validators.iter() {
-            if validator.primary.current_round() == target_round {
-                let bft = validator.bft.get().unwrap();
+            if validator.current_round() == target_round {
+                let bft = validator.bft().unwrap();
                 if let Some(leader) = bft.leader() {
                     // Validator is a live object - just because it's
                     // been on the current round above doesn't mean
                     // that's still the case
-                    if validator.primary.current_round() == target_round {
+                    if validator.current_round() == target_round {
                         leaders.push(leader);
                     }
                 }
@@ -231,7 +228,7 @@ async fn test_leader_election_consistency() {
     }
 }
 
-#[tokio::test(flavor = "multi_thread")]
+#[tokio::test(start_paused = true)]
 #[ignore = "run multiple times to see failure"]
 async fn test_transient_break() {
     // Start N nodes, connect them and start the cannons for each.
@@ -243,23 +240,22 @@ async fn test_transient_break() {
         connect_all: true,
         fire_transmissions: Some(TRANSMISSION_INTERVAL_MS),
         // Set this to Some(0..=4) to see the logs.
-        log_level: Some(6),
+        log_level: None,
         log_connections: false,
-    });
+    })
+    .await;
     network.start().await;
 
     // Check the nodes have started advancing through the rounds.
     const FIRST_BREAK_ROUND: u64 = 10;
-    let network_clone = network.clone();
-    deadline!(Duration::from_secs(60), move || { network_clone.is_round_reached(FIRST_BREAK_ROUND) });
+    wait_until(Duration::from_secs(60), || network.is_round_reached(FIRST_BREAK_ROUND)).await;
 
     // Disconnect the last node.
     network.disconnect_one(3).await;
 
     // Check the nodes have started advancing through the rounds.
     const SECOND_BREAK_ROUND: u64 = 25;
-    let network_clone = network.clone();
-    deadline!(Duration::from_secs(80), move || { network_clone.is_round_reached(SECOND_BREAK_ROUND) });
+    wait_until(Duration::from_secs(80), || network.is_round_reached(SECOND_BREAK_ROUND)).await;
 
     // Disconnect another node, break quorum.
     network.disconnect_one(2).await;
@@ -271,6 +267,5 @@ async fn test_transient_break() {
     network.connect_one(3).await;
 
     const RECOVERY_ROUND: u64 = 30;
-    let network_clone = network.clone();
-    deadline!(Duration::from_secs(60), move || { network_clone.is_round_reached(RECOVERY_ROUND) });
+    wait_until(Duration::from_secs(60), || network.is_round_reached(RECOVERY_ROUND)).await;
 }
@@ -15,26 +15,24 @@
 use crate::common::{
     utils::{fire_unconfirmed_solutions, fire_unconfirmed_transactions, initialize_logger},
     CurrentNetwork,
-    TranslucentLedgerService,
 };
 use snarkos_account::Account;
+#[cfg(feature = "adversary")]
+use snarkos_node_bft::helpers::AdversaryMode;
 use snarkos_node_bft::{
-    helpers::{init_primary_channels, PrimarySender, Storage},
+    simulation::{validator_ip, NetworkConfig, Simulation, SimulationConfig},
     Primary,
-    BFT,
     MAX_BATCH_DELAY_IN_MS,
 };
-use snarkos_node_bft_storage_service::BFTMemoryService;
+use snarkos_node_bft_ledger_service::LedgerService;
 use snarkvm::{
     console::{
         account::{Address, PrivateKey},
         algorithms::{Hash, BHP256},
-        network::Network,
     },
     ledger::{
         block::Block,
         committee::{Committee, MIN_VALIDATOR_STAKE},
-        narwhal::BatchHeader,
         store::{helpers::memory::ConsensusMemory, ConsensusStore},
         Ledger,
     },
@@ -46,12 +44,7 @@ use aleo_std::StorageMode;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use parking_lot::Mutex;
-use std::{
-    collections::HashMap,
-    ops::RangeBounds,
-    sync::{Arc, OnceLock},
-    time::Duration,
-};
+use std::{collections::HashMap, ops::RangeBounds, sync::OnceLock, time::Duration};
 use tokio::{task::JoinHandle, time::sleep};
 use tracing::*;
 
@@ -73,141 +66,58 @@ pub struct TestNetworkConfig {
     pub log_connections: bool,
 }
 
-/// A test network.
-#[derive(Clone)]
+/// A test network, which runs the validators in a simulation.
+///
+/// Note: The tests must run on a paused runtime, i.e. `#[tokio::test(start_paused = true)]`,
+/// so that the network advances in the simulated time.
 pub struct TestNetwork {
     /// The configuration for the test network.
     pub config: TestNetworkConfig,
-    /// A map of node IDs to validators in the network.
-    pub validators: HashMap<u16, TestValidator>,
-}
-
-/// A test validator.
-#[derive(Clone)]
-pub struct TestValidator {
-    /// The ID of the validator.
-    pub id: u16,
-    /// The primary instance. When the BFT is enabled this is a clone of the BFT primary.
-    pub primary: Primary<CurrentNetwork>,
-    /// The channel sender of the primary.
-    pub primary_sender: Option<PrimarySender<CurrentNetwork>>,
-    /// The BFT instance. This is only set if the BFT is enabled.
-    pub bft: OnceLock<BFT<CurrentNetwork>>,
-    /// The tokio handles of all long-running tasks associated with the validator (incl. cannons).
-    pub handles: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    /// The simulation running the validators.
+    pub simulation: Simulation<CurrentNetwork>,
+    /// The tokio handles of the cannons and loggers.
+    pub handles: Vec<JoinHandle<()>>,
 }
 
 pub type CurrentLedger = Ledger<CurrentNetwork, ConsensusMemory<CurrentNetwork>>;
 
-impl TestValidator {
-    pub fn fire_transmissions(&mut self, interval_ms: u64) {
-        let solution_handle = fire_unconfirmed_solutions(self.primary_sender.as_mut().unwrap(), self.id, interval_ms);
-        let transaction_handle =
-            fire_unconfirmed_transactions(self.primary_sender.as_mut().unwrap(), self.id, interval_ms);
-
-        self.handles.lock().push(solution_handle);
-        self.handles.lock().push(transaction_handle);
-    }
-
-    pub fn log_connections(&mut self) {
-        let self_clone = self.clone();
-        self.handles.lock().push(tokio::task::spawn(async move {
-            loop {
-                let connections = self_clone.primary.gateway().connected_peers().read().clone();
-                info!("{} connections", connections.len());
-                for connection in connections {
-                    debug!("  {}", connection);
-                }
-                sleep(Duration::from_secs(5)).await;
-            }
-        }));
-    }
-}
-
 impl TestNetwork {
     // Creates a new test network with the given configuration.
-    pub fn new(config: TestNetworkConfig) -> Self {
-        let mut rng = TestRng::default();
+    pub async fn new(config: TestNetworkConfig) -> Self {
+        Self::new_with_network(config, Default::default(), 0).await
+    }
+
+    // Creates a new test network with the given configuration, over a network with the given conditions,
+    // whose simulation is seeded with the given seed.
+    pub async fn new_with_network(config: TestNetworkConfig, network: NetworkConfig, seed: u64) -> Self {
+        let mut rng = TestRng::fixed(0);
 
         if let Some(log_level) = config.log_level {
             initialize_logger(log_level);
         }
 
         let (accounts, committee) = new_test_committee(config.num_nodes, &mut rng);
-        let bonded_balances: IndexMap<_, _> = committee
-            .members()
-            .iter()
-            .map(|(address, (amount, _, _))| (*address, (*address, *address, *amount)))
-            .collect();
-        let gen_key = *accounts[0].private_key();
-        let public_balance_per_validator = (CurrentNetwork::STARTING_SUPPLY
-            - (config.num_nodes as u64) * MIN_VALIDATOR_STAKE)
-            / (config.num_nodes as u64);
-        let mut balances = IndexMap::<Address<CurrentNetwork>, u64>::new();
-        for account in accounts.iter() {
-            balances.insert(account.address(), public_balance_per_validator);
-        }
-
-        let mut validators = HashMap::with_capacity(config.num_nodes as usize);
-        for (id, account) in accounts.into_iter().enumerate() {
-            let gen_ledger =
-                genesis_ledger(gen_key, committee.clone(), balances.clone(), bonded_balances.clone(), &mut rng);
-            let ledger = Arc::new(TranslucentLedgerService::new(gen_ledger, Default::default()));
-            let storage = Storage::new(
-                ledger.clone(),
-                Arc::new(BFTMemoryService::new()),
-                BatchHeader::<CurrentNetwork>::MAX_GC_ROUNDS as u64,
-            );
-
-            let (primary, bft) = if config.bft {
-                let bft = BFT::<CurrentNetwork>::new(account, storage, ledger, None, &[], Some(id as u16)).unwrap();
-                (bft.primary().clone(), Some(bft))
-            } else {
-                let primary =
-                    Primary::<CurrentNetwork>::new(account, storage, ledger, None, &[], Some(id as u16)).unwrap();
-                (primary, None)
-            };
-
-            let test_validator = TestValidator {
-                id: id as u16,
-                primary,
-                primary_sender: None,
-                bft: OnceLock::new(),
-                handles: Default::default(),
-            };
-            if let Some(bft) = bft {
-                assert!(test_validator.bft.set(bft).is_ok());
-            }
-            validators.insert(id as u16, test_validator);
+        let simulation_config = SimulationConfig { seed, network, bft: config.bft };
+        let simulation = Simulation::new(simulation_config, accounts, committee).await.unwrap();
+        // Isolate the nodes, unless they are to be connected from the start.
+        if !config.connect_all {
+            simulation.partition(&[]);
         }
 
-        Self { config, validators }
+        Self { config, simulation, handles: Default::default() }
     }
 
     // Starts each node in the network.
     pub async fn start(&mut self) {
-        for validator in self.validators.values_mut() {
-            let (primary_sender, primary_receiver) = init_primary_channels();
-            validator.primary_sender = Some(primary_sender.clone());
-
-            // let ledger_service = validator.primary.ledger().clone();
-            // let sync = BlockSync::new(BlockSyncMode::Gateway, ledger_service);
-            // sync.try_block_sync(validator.primary.gateway()).await.unwrap();
-
-            if let Some(bft) = validator.bft.get_mut() {
-                // Setup the channels and start the bft.
-                bft.run(None, primary_sender, primary_receiver).await.unwrap();
-            } else {
-                // Setup the channels and start the primary.
-                validator.primary.run(None, primary_sender, primary_receiver).await.unwrap();
-            }
+        self.simulation.start().await.unwrap();
 
+        for id in 0..self.config.num_nodes {
             if let Some(interval_ms) = self.config.fire_transmissions {
-                validator.fire_transmissions(interval_ms);
+                self.fire_transmissions_at(id, interval_ms);
             }
 
             if self.config.log_connections {
-                validator.log_connections();
+                self.log_connections(id);
             }
         }
 
@@ -216,51 +126,70 @@ impl TestNetwork {
         }
     }
 
+    // Returns the primary of the given node.
+    pub fn primary(&self, id: u16) -> &Primary<CurrentNetwork> {
+        self.simulation.nodes()[id as usize].primary().expect("The node is offline")
+    }
+
+    // Returns the primaries of the online nodes.
+    pub fn primaries(&self) -> impl Iterator<Item = &Primary<CurrentNetwork>> {
+        self.simulation.nodes().iter().filter_map(|node| node.primary())
+    }
+
+    // Shuts down the cannons, the loggers, and the nodes.
+    pub async fn shut_down(&mut self) {
+        self.handles.drain(..).for_each(|handle| handle.abort());
+        self.simulation.shut_down().await;
+    }
+
     // Starts the solution and transaction cannons for node.
     pub fn fire_transmissions_at(&mut self, id: u16, interval_ms: u64) {
-        self.validators.get_mut(&id).unwrap().fire_transmissions(interval_ms);
+        let sender = self.simulation.nodes()[id as usize].primary_sender().expect("The node is offline");
+        self.handles.push(fire_unconfirmed_solutions(sender, id, interval_ms));
+        self.handles.push(fire_unconfirmed_transactions(sender, id, interval_ms));
+    }
+
+    // Logs the number of connections of the node every 5 seconds.
+    fn log_connections(&mut self, id: u16) {
+        let gateway = self.primary(id).gateway().clone();
+        self.handles.push(tokio::task::spawn(async move {
+            loop {
+                let connections = gateway.connected_peers().read().clone();
+                info!("{} connections", connections.len());
+                for connection in connections {
+                    debug!("  {}", connection);
+                }
+                sleep(Duration::from_secs(5)).await;
+            }
+        }));
     }
 
     // Connects a node to another node.
     pub async fn connect_validators(&self, first_id: u16, second_id: u16) {
-        let first_validator = self.validators.get(&first_id).unwrap();
-        let second_validator_ip = self.validators.get(&second_id).unwrap().primary.gateway().local_ip();
-        first_validator.primary.gateway().connect(second_validator_ip);
+        self.simulation.network().connect(first_id as usize, second_id as usize);
+        self.primary(first_id).gateway().connect(validator_ip(second_id as usize));
         // Give the connection time to be established.
         sleep(Duration::from_millis(100)).await;
     }
 
     // Connects all nodes to each other.
     pub async fn connect_all(&self) {
-        for (validator, other_validator) in self.validators.values().tuple_combinations() {
-            // Connect to the node.
-            let ip = other_validator.primary.gateway().local_ip();
-            validator.primary.gateway().connect(ip);
-            // Give the connection time to be established.
-            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        for (first_id, second_id) in (0..self.config.num_nodes).tuple_combinations() {
+            self.connect_validators(first_id, second_id).await;
         }
     }
 
     // Connects a specific node to all other nodes.
     pub async fn connect_one(&self, id: u16) {
-        let target_validator = self.validators.get(&id).unwrap();
-        let target_ip = target_validator.primary.gateway().local_ip();
-        for validator in self.validators.values() {
-            if validator.id != id {
-                // Connect to the node.
-                validator.primary.gateway().connect(target_ip);
-                // Give the connection time to be established.
-                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
-            }
+        for other_id in (0..self.config.num_nodes).filter(|other_id| *other_id != id) {
+            self.connect_validators(other_id, id).await;
         }
     }
 
     // Disconnects N nodes from all other nodes.
     pub async fn disconnect(&self, num_nodes: u16) {
-        for validator in self.validators.values().take(num_nodes as usize) {
-            for peer_ip in validator.primary.gateway().connected_peers().read().iter() {
-                validator.primary.gateway().disconnect(*peer_ip);
-            }
+        for id in 0..num_nodes {
+            self.simulation.isolate(id as usize);
         }
 
         // Give the connections time to be closed.
@@ -269,10 +198,7 @@ impl TestNetwork {
 
     // Disconnects a specific node from all other nodes.
     pub async fn disconnect_one(&self, id: u16) {
-        let target_validator = self.validators.get(&id).unwrap();
-        for peer_ip in target_validator.primary.gateway().connected_peers().read().iter() {
-            target_validator.primary.gateway().disconnect(*peer_ip);
-        }
+        self.simulation.isolate(id as usize);
 
         // Give the connections time to be closed.
         sleep(Duration::from_millis(100)).await;
@@ -280,15 +206,15 @@ impl TestNetwork {
 
     // Checks if at least 2f + 1 nodes have reached the given round.
     pub fn is_round_reached(&self, round: u64) -> bool {
-        let quorum_threshold = self.validators.len() / 2 + 1;
-        self.validators.values().filter(|v| v.primary.current_round() >= round).count() >= quorum_threshold
+        let quorum_threshold = self.config.num_nodes as usize / 2 + 1;
+        self.primaries().filter(|primary| primary.current_round() >= round).count() >= quorum_threshold
     }
 
     // Checks if all the nodes have stopped progressing.
     pub async fn is_halted(&self) -> bool {
-        let halt_round = self.validators.values().map(|v| v.primary.current_round()).max().unwrap();
+        let halt_round = self.primaries().map(|primary| primary.current_round()).max().unwrap();
         sleep(Duration::from_millis(MAX_BATCH_DELAY_IN_MS * 2)).await;
-        self.validators.values().all(|v| v.primary.current_round() <= halt_round)
+        self.primaries().all(|primary| primary.current_round() <= halt_round)
     }
 
     // Checks if the committee is coherent in storage for all nodes (not quorum) over a range of
@@ -299,9 +225,9 @@ impl TestNetwork {
     {
         for round in rounds_range.into_iter() {
             let mut last: Option<Committee<CurrentNetwork>> = None;
-            for validator in self.validators.values() {
+            for primary in self.primaries() {
                 // Round might be in future, in case validator didn't get to it.
-                if let Ok(committee) = validator.primary.ledger().get_committee_for_round(round) {
+                if let Ok(committee) = primary.ledger().get_committee_for_round(round) {
                     match last.clone() {
                         None => last = Some(committee),
                         Some(first) => {
@@ -324,9 +250,46 @@ impl TestNetwork {
         T: RangeBounds<u64> + IntoIterator<Item = u64>,
     {
         rounds_range.into_iter().all(|round| {
-            self.validators.values().map(|v| v.primary.storage().get_certificates_for_round(round)).dedup().count() == 1
+            self.primaries().map(|primary| primary.storage().get_certificates_for_round(round)).dedup().count() == 1
         })
     }
+
+    // Checks that the given nodes committed the same subdags, returning the number committed by all of them.
+    pub fn num_consistently_committed(&self, ids: &[u16]) -> usize {
+        let ids = ids.iter().map(|id| *id as usize).collect::<Vec<_>>();
+        self.simulation.check_consistency(&ids).unwrap()
+    }
+}
+
+#[cfg(feature = "adversary")]
+impl TestNetwork {
+    // Sets the adversary mode of the given node.
+    pub fn set_adversary_mode(&self, id: u16, mode: AdversaryMode) {
+        self.primary(id).gateway().set_adversary_mode(mode);
+    }
+
+    // Checks if all the given nodes have reached the given round.
+    pub fn is_round_reached_by(&self, ids: &[u16], round: u64) -> bool {
+        ids.iter().all(|id| self.primary(*id).current_round() >= round)
+    }
+
+    // Checks that, across the storage of all the given nodes, no author has more than one certificate
+    // in any round (i.e. that no equivocating certificate was ever accepted).
+    pub fn is_equivocation_free(&self, ids: &[u16]) -> bool {
+        let mut certificates = HashMap::new();
+        for id in ids {
+            let storage = self.primary(*id).storage();
+            for round in storage.gc_round()..=storage.current_round() {
+                for certificate in storage.get_certificates_for_round(round) {
+                    let certificate_id = *certificates.entry((round, certificate.author())).or_insert(certificate.id());
+                    if certificate_id != certificate.id() {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
 }
 
 // Initializes a new test committee.
@@ -45,7 +45,11 @@ use ::bytes::Bytes;
 use indexmap::IndexMap;
 use parking_lot::RwLock;
 use rand::Rng;
-use tokio::{sync::oneshot, task::JoinHandle, time::sleep};
+use tokio::{
+    sync::oneshot,
+    task::JoinHandle,
+    time::{sleep, Instant},
+};
 use tracing::*;
 use tracing_subscriber::{
     layer::{Layer, SubscriberExt},
@@ -85,6 +89,17 @@ pub fn initialize_logger(verbosity: u8) {
         .try_init();
 }
 
+/// Waits until the given condition holds, checking it every 100 milliseconds, and panics on timeout.
+///
+/// Unlike a blocking deadline, this yields to the runtime, so that it also works on a paused runtime.
+pub async fn wait_until(timeout: Duration, condition: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !condition() {
+        assert!(start.elapsed() < timeout, "The condition was not met within {timeout:?}");
+        sleep(Duration::from_millis(100)).await;
+    }
+}
+
 /// Fires *fake* unconfirmed solutions at the node.
 pub fn fire_unconfirmed_solutions(
     sender: &PrimarySender<CurrentNetwork>,
@@ -189,7 +204,7 @@ pub fn sample_ledger(
     let num_nodes = committee.num_members();
     let bonded_balances: IndexMap<_, _> =
         committee.members().iter().map(|(address, (amount, _, _))| (*address, (*address, *address, *amount))).collect();
-    let gen_key = *accounts[0].private_key();
+    let gen_key = *accounts[0].private_key().unwrap();
     let public_balance_per_validator =
         (CurrentNetwork::STARTING_SUPPLY - (num_nodes as u64) * MIN_VALIDATOR_STAKE) / (num_nodes as u64);
     let mut balances = IndexMap::<Address<CurrentNetwork>, u64>::new();
@@ -214,7 +229,7 @@ pub fn sample_gateway<N: Network>(
     ledger: Arc<TranslucentLedgerService<N, ConsensusMemory<N>>>,
 ) -> Gateway<N> {
     // Initialize the gateway.
-    Gateway::new(account, storage, ledger, None, &[], None).unwrap()
+    Gateway::new(account, storage, ledger, None, &[], None, Default::default()).unwrap()
 }
 
 /// Samples a new worker with the given ledger.
@@ -21,13 +21,21 @@ use crate::common::{
     utils::{sample_gateway, sample_ledger, sample_storage},
     CurrentNetwork,
 };
-use snarkos_account::Account;
-use snarkos_node_bft::{helpers::init_primary_channels, Gateway};
-use snarkos_node_bft_events::{ChallengeRequest, ChallengeResponse, Disconnect, DisconnectReason, Event, WorkerPing};
-use snarkos_node_tcp::P2P;
+use snarkos_account::{Account, SignRequest};
+use snarkos_node_bft::{helpers::init_primary_channels, simulation::SimulatedNetwork, Gateway};
+use snarkos_node_bft_events::{
+    ChallengeRequest,
+    ChallengeResponse,
+    Compression,
+    Disconnect,
+    DisconnectReason,
+    Event,
+    WorkerPing,
+};
+use snarkos_node_tcp::{Config, P2P};
 use snarkvm::{ledger::narwhal::Data, prelude::TestRng};
 
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 use deadline::deadline;
 use rand::Rng;
@@ -213,18 +221,23 @@ async fn handshake_responder_side_invalid_challenge_response() {
     let listener_port = test_peer.listening_addr().port();
     let address = accounts.get(1).unwrap().address();
     let our_nonce = rng.gen();
-    let version = Event::<CurrentNetwork>::VERSION;
+    // Use the minimum version, so that the connection is not encrypted.
+    let version = Event::<CurrentNetwork>::MINIMUM_VERSION;
     let challenge_request = ChallengeRequest { version, listener_port, address, nonce: our_nonce };
 
     // Send the challenge request.
     let _ = test_peer.unicast(gateway.local_ip(), Event::ChallengeRequest(challenge_request));
 
     // Receive the gateway's challenge response.
-    let (peer_addr, Event::ChallengeResponse(ChallengeResponse { restrictions_id, signature, nonce })) =
-        test_peer.recv_timeout(Duration::from_secs(1)).await
+    let (
+        peer_addr,
+        Event::ChallengeResponse(ChallengeResponse { restrictions_id, signature, nonce, session_message, .. }),
+    ) = test_peer.recv_timeout(Duration::from_secs(1)).await
     else {
         panic!("Expected challenge response")
     };
+    // Check the gateway did not start a session.
+    assert!(session_message.is_none());
 
     // Check the sender is the gateway.
     assert_eq!(peer_addr, gateway.local_ip());
@@ -235,6 +248,8 @@ async fn handshake_responder_side_invalid_challenge_response() {
             &[our_nonce.to_le_bytes(), nonce.to_le_bytes()].concat()
         )
     );
+    // Check the gateway signaled that it supports encryption.
+    assert!(ChallengeResponse::<CurrentNetwork>::is_encryption_signaled(our_nonce, nonce));
 
     // Receive the gateway's challenge request.
     let (peer_addr, Event::ChallengeRequest(challenge_request)) = test_peer.recv_timeout(Duration::from_secs(1)).await
@@ -243,20 +258,21 @@ async fn handshake_responder_side_invalid_challenge_response() {
     };
     // Check the version, listener port and address are correct.
     assert_eq!(peer_addr, gateway.local_ip());
-    assert_eq!(challenge_request.version, version);
+    assert_eq!(challenge_request.version, Event::<CurrentNetwork>::VERSION);
     assert_eq!(challenge_request.listener_port, gateway.local_ip().port());
     assert_eq!(challenge_request.address, accounts.first().unwrap().address());
 
     // Send the challenge response with an invalid signature.
     let response_nonce = rng.gen();
+    let request = SignRequest::Challenge { request_nonce: challenge_request.nonce, response_nonce, context: vec![] };
     let _ = test_peer.unicast(
         gateway.local_ip(),
         Event::ChallengeResponse(ChallengeResponse {
             restrictions_id,
-            signature: Data::Object(
-                accounts.get(2).unwrap().sign_bytes(&challenge_request.nonce.to_le_bytes(), &mut rng).unwrap(),
-            ),
+            signature: Data::Object(accounts.get(2).unwrap().sign_request(&request, &mut rng).unwrap()),
             nonce: response_nonce,
+            session_message: None,
+            capabilities: None,
         }),
     );
 
@@ -270,3 +286,172 @@ async fn handshake_responder_side_invalid_challenge_response() {
     assert!(gateway.connected_peers().read().is_empty());
     assert_eq!(gateway.tcp().num_connected(), 0);
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handshake_responder_side_missing_session_message() {
+    const NUM_NODES: u16 = 4;
+
+    let mut rng = TestRng::default();
+    let (accounts, gateway) = new_test_gateway(NUM_NODES, &mut rng).await;
+    let mut test_peer = TestPeer::new().await;
+
+    // Initiate a connection with the gateway.
+    assert!(test_peer.connect(gateway.local_ip()).await.is_ok());
+
+    // Check the connection has been registered.
+    let gateway_clone = gateway.clone();
+    deadline!(Duration::from_secs(1), move || gateway_clone.tcp().num_connecting() == 1);
+
+    // Use the address from the second peer in the list, the test peer will use the first.
+    let listener_port = test_peer.listening_addr().port();
+    let address = accounts.get(1).unwrap().address();
+    let our_nonce = rng.gen();
+    let version = Event::<CurrentNetwork>::ENCRYPTED_TRANSPORT_VERSION;
+    let challenge_request = ChallengeRequest { version, listener_port, address, nonce: our_nonce };
+
+    // Send the challenge request.
+    let _ = test_peer.unicast(gateway.local_ip(), Event::ChallengeRequest(challenge_request));
+
+    // Receive the gateway's challenge response.
+    let (_, Event::ChallengeResponse(ChallengeResponse { restrictions_id, signature, nonce, session_message, .. })) =
+        test_peer.recv_timeout(Duration::from_secs(1)).await
+    else {
+        panic!("Expected challenge response")
+    };
+    // Check the gateway started a session, and signed its session message along with the nonces and versions.
+    let session_message = session_message.expect("Expected a session message");
+    let request = ChallengeResponse::<CurrentNetwork>::signing_request(
+        our_nonce,
+        version,
+        nonce,
+        Event::<CurrentNetwork>::VERSION,
+        None,
+        &session_message,
+    );
+    assert!(request.verify(&accounts.first().unwrap().address(), &signature.deserialize_blocking().unwrap()));
+
+    // Receive the gateway's challenge request.
+    let (_, Event::ChallengeRequest(challenge_request)) = test_peer.recv_timeout(Duration::from_secs(1)).await else {
+        panic!("Expected challenge request")
+    };
+
+    // Send a valid challenge response, without completing the session.
+    let response_nonce = ChallengeResponse::<CurrentNetwork>::new_nonce(challenge_request.nonce, rng.gen());
+    let request = ChallengeResponse::<CurrentNetwork>::signing_request(
+        challenge_request.nonce,
+        challenge_request.version,
+        response_nonce,
+        version,
+        None,
+        &[],
+    );
+    let _ = test_peer.unicast(
+        gateway.local_ip(),
+        Event::ChallengeResponse(ChallengeResponse {
+            restrictions_id,
+            signature: Data::Object(accounts.get(1).unwrap().sign_request(&request, &mut rng).unwrap()),
+            nonce: response_nonce,
+            session_message: None,
+            capabilities: None,
+        }),
+    );
+
+    // Check the test peer has been removed from the gateway's connecting peers.
+    let gateway_clone = gateway.clone();
+    deadline!(Duration::from_secs(1), move || gateway_clone.tcp().num_connecting() == 0);
+    // Check the test peer hasn't been added to the gateway's connected peers.
+    assert!(gateway.connected_peers().read().is_empty());
+    assert_eq!(gateway.tcp().num_connected(), 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handshake_responder_side_downgraded_version() {
+    const NUM_NODES: u16 = 4;
+
+    let mut rng = TestRng::default();
+    let (accounts, gateway) = new_test_gateway(NUM_NODES, &mut rng).await;
+    let mut test_peer = TestPeer::new().await;
+
+    // Initiate a connection with the gateway.
+    assert!(test_peer.connect(gateway.local_ip()).await.is_ok());
+
+    // Check the connection has been registered.
+    let gateway_clone = gateway.clone();
+    deadline!(Duration::from_secs(1), move || gateway_clone.tcp().num_connecting() == 1);
+
+    // Claim the minimum version, as if a man-in-the-middle rewrote the version of the test peer.
+    let listener_port = test_peer.listening_addr().port();
+    let address = accounts.get(1).unwrap().address();
+    let version = Event::<CurrentNetwork>::MINIMUM_VERSION;
+    let challenge_request = ChallengeRequest { version, listener_port, address, nonce: rng.gen() };
+
+    // Send the challenge request.
+    let _ = test_peer.unicast(gateway.local_ip(), Event::ChallengeRequest(challenge_request));
+
+    // Receive the gateway's challenge response, which does not start a session.
+    let (_, Event::ChallengeResponse(ChallengeResponse { restrictions_id, session_message, .. })) =
+        test_peer.recv_timeout(Duration::from_secs(1)).await
+    else {
+        panic!("Expected challenge response")
+    };
+    assert!(session_message.is_none());
+
+    // Receive the gateway's challenge request.
+    let (_, Event::ChallengeRequest(challenge_request)) = test_peer.recv_timeout(Duration::from_secs(1)).await else {
+        panic!("Expected challenge request")
+    };
+
+    // Send a validly signed challenge response, which signals that the test peer supports encryption.
+    let response_nonce = ChallengeResponse::<CurrentNetwork>::new_nonce(challenge_request.nonce, rng.gen());
+    let request = SignRequest::Challenge { request_nonce: challenge_request.nonce, response_nonce, context: vec![] };
+    let _ = test_peer.unicast(
+        gateway.local_ip(),
+        Event::ChallengeResponse(ChallengeResponse {
+            restrictions_id,
+            signature: Data::Object(accounts.get(1).unwrap().sign_request(&request, &mut rng).unwrap()),
+            nonce: response_nonce,
+            session_message: None,
+            capabilities: None,
+        }),
+    );
+
+    // Check the gateway refused the plaintext connection.
+    let gateway_clone = gateway.clone();
+    deadline!(Duration::from_secs(1), move || gateway_clone.tcp().num_connecting() == 0);
+    assert!(gateway.connected_peers().read().is_empty());
+    assert_eq!(gateway.tcp().num_connected(), 0);
+}
+
+/* Capabilities */
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handshake_negotiates_compression_in_both_directions() {
+    const NUM_NODES: u16 = 4;
+
+    let mut rng = TestRng::default();
+    let (accounts, committee) = new_test_committee(NUM_NODES, &mut rng);
+    // Connect the gateways over an in-memory network.
+    let network = SimulatedNetwork::new(Default::default(), 0);
+    let mut gateways = Vec::new();
+    for (id, account) in accounts.iter().take(2).enumerate() {
+        let ledger = sample_ledger(&accounts, &committee, &mut rng);
+        let storage = sample_storage(ledger.clone());
+        let tcp_config = Config { transport: Arc::new(network.transport(id)), ..Default::default() };
+        let gateway = Gateway::new(account.clone(), storage, ledger, None, &[], Some(id as u16), tcp_config).unwrap();
+        let (primary_tx, _primary_rx) = init_primary_channels();
+        gateway.run(primary_tx, [].into(), None).await;
+        gateways.push(gateway);
+    }
+
+    // Connect the first gateway to the second one.
+    gateways[0].connect(gateways[1].local_ip());
+    let gateways_clone = gateways.clone();
+    deadline!(Duration::from_secs(5), move || gateways_clone
+        .iter()
+        .all(|gateway| gateway.number_of_connected_peers() == 1));
+
+    // Ensure both sides compress the frames they send, and accept compressed frames.
+    let expected = Compression { outbound: true, inbound: true };
+    assert_eq!(gateways[0].compression(gateways[1].local_ip()), expected);
+    assert_eq!(gateways[1].compression(gateways[0].local_ip()), expected);
+}
@@ -15,15 +15,17 @@
 #[allow(dead_code)]
 mod common;
 
-use crate::common::primary::{TestNetwork, TestNetworkConfig};
+use crate::common::{
+    primary::{TestNetwork, TestNetworkConfig},
+    utils::wait_until,
+};
 use snarkos_node_bft::MAX_FETCH_TIMEOUT_IN_MS;
 
 use std::time::Duration;
 
-use deadline::deadline;
 use tokio::time::sleep;
 
-#[tokio::test(flavor = "multi_thread")]
+#[tokio::test(start_paused = true)]
 #[ignore = "long-running e2e test"]
 async fn test_state_coherence() {
     const N: u16 = 4;
@@ -35,19 +37,23 @@ async fn test_state_coherence() {
         connect_all: true,
         fire_transmissions: Some(TRANSMISSION_INTERVAL_MS),
         // Set this to Some(0..=4) to see the logs.
-        log_level: Some(0),
-        log_connections: true,
-    });
+        log_level: None,
+        log_connections: false,
+    })
+    .await;
 
     network.start().await;
 
-    // TODO(nkls): the easiest would be to assert on the anchor or bullshark's output, once
-    // implemented.
+    // Let the network run for a while.
+    const TARGET_ROUND: u64 = 20;
+    wait_until(Duration::from_secs(120), || network.is_round_reached(TARGET_ROUND)).await;
 
-    std::future::pending::<()>().await;
+    // Check the nodes agree on the committees and on the certificates of the completed rounds.
+    assert!(network.is_committee_coherent(1..TARGET_ROUND));
+    assert!(network.is_certificate_round_coherent(1..TARGET_ROUND - 1));
 }
 
-#[tokio::test(flavor = "multi_thread")]
+#[tokio::test(start_paused = true)]
 async fn test_quorum_threshold() {
     // Start N nodes but don't connect them.
     const N: u16 = 4;
@@ -61,12 +67,13 @@ async fn test_quorum_threshold() {
         // Set this to Some(0..=4) to see the logs.
         log_level: None,
         log_connections: true,
-    });
+    })
+    .await;
     network.start().await;
 
     // Check each node is at round 1 (0 is genesis).
-    for validators in network.validators.values() {
-        assert_eq!(validators.primary.current_round(), 1);
+    for primary in network.primaries() {
+        assert_eq!(primary.current_round(), 1);
     }
 
     // Start the cannons for node 0.
@@ -75,8 +82,8 @@ async fn test_quorum_threshold() {
     sleep(Duration::from_millis(MAX_FETCH_TIMEOUT_IN_MS)).await;
 
     // Check each node is still at round 1.
-    for validator in network.validators.values() {
-        assert_eq!(validator.primary.current_round(), 1);
+    for primary in network.primaries() {
+        assert_eq!(primary.current_round(), 1);
     }
 
     // Connect the first two nodes and start the cannons for node 1.
@@ -86,8 +93,8 @@ async fn test_quorum_threshold() {
     sleep(Duration::from_millis(MAX_FETCH_TIMEOUT_IN_MS)).await;
 
     // Check each node is still at round 1.
-    for validator in network.validators.values() {
-        assert_eq!(validator.primary.current_round(), 1);
+    for primary in network.primaries() {
+        assert_eq!(primary.current_round(), 1);
     }
 
     // Connect the third node and start the cannons for it.
@@ -97,10 +104,10 @@ async fn test_quorum_threshold() {
 
     // Check the nodes reach quorum and advance through the rounds.
     const TARGET_ROUND: u64 = 4;
-    deadline!(Duration::from_secs(20), move || { network.is_round_reached(TARGET_ROUND) });
+    wait_until(Duration::from_secs(20), || network.is_round_reached(TARGET_ROUND)).await;
 }
 
-#[tokio::test(flavor = "multi_thread")]
+#[tokio::test(start_paused = true)]
 async fn test_quorum_break() {
     // Start N nodes, connect them and start the cannons for each.
     const N: u16 = 4;
@@ -113,14 +120,13 @@ async fn test_quorum_break() {
         // Set this to Some(0..=4) to see the logs.
         log_level: None,
         log_connections: true,
-    });
+    })
+    .await;
     network.start().await;
 
     // Check the nodes have started advancing through the rounds.
     const TARGET_ROUND: u64 = 4;
-    // Note: cloning the network is fine because the primaries it wraps are `Arc`ed.
-    let network_clone = network.clone();
-    deadline!(Duration::from_secs(20), move || { network_clone.is_round_reached(TARGET_ROUND) });
+    wait_until(Duration::from_secs(20), || network.is_round_reached(TARGET_ROUND)).await;
 
     // Break the quorum by disconnecting two nodes.
     const NUM_NODES: u16 = 2;
@@ -130,7 +136,7 @@ async fn test_quorum_break() {
     assert!(network.is_halted().await);
 }
 
-#[tokio::test(flavor = "multi_thread")]
+#[tokio::test(start_paused = true)]
 async fn test_storage_coherence() {
     // Start N nodes, connect them and start the cannons for each.
     const N: u16 = 4;
@@ -143,14 +149,13 @@ async fn test_storage_coherence() {
         // Set this to Some(0..=4) to see the logs.
         log_level: None,
         log_connections: true,
-    });
+    })
+    .await;
     network.start().await;
 
     // Check the nodes have started advancing through the rounds.
     const TARGET_ROUND: u64 = 12;
-    // Note: cloning the network is fine because the primaries it wraps are `Arc`ed.
-    let network_clone = network.clone();
-    deadline!(Duration::from_secs(40), move || { network_clone.is_round_reached(TARGET_ROUND) });
+    wait_until(Duration::from_secs(40), || network.is_round_reached(TARGET_ROUND)).await;
 
     // Check the committee is coherent across the network up to the target round. We skip the
     // genesis round.
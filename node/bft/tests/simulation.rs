@@ -0,0 +1,169 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[allow(dead_code)]
+mod common;
+
+use crate::common::{
+    primary::{TestNetwork, TestNetworkConfig},
+    utils::wait_until,
+    CurrentNetwork,
+};
+use snarkos_node_bft::{helpers::now, simulation::NetworkConfig};
+use snarkvm::prelude::Field;
+
+use std::time::Duration;
+use tokio::time::sleep;
+
+const N: u16 = 4;
+const TRANSMISSION_INTERVAL_MS: u64 = 10;
+
+/// Initializes a simulated network of `N` validators with the given seed, which are connected and fire transmissions.
+async fn start_network(network: NetworkConfig, seed: u64) -> TestNetwork {
+    let mut network = TestNetwork::new_with_network(
+        TestNetworkConfig {
+            num_nodes: N,
+            bft: true,
+            connect_all: true,
+            fire_transmissions: Some(TRANSMISSION_INTERVAL_MS),
+            // Set this to Some(0..=4) to see the logs.
+            log_level: None,
+            log_connections: false,
+        },
+        network,
+        seed,
+    )
+    .await;
+    network.start().await;
+    network
+}
+
+/// Runs a simulation with the given seed until the target round, and returns the certificate IDs
+/// of the subdags committed by each validator.
+async fn run_until_round(seed: u64, round: u64) -> Vec<Vec<Vec<Field<CurrentNetwork>>>> {
+    let mut network = start_network(Default::default(), seed).await;
+    wait_until(Duration::from_secs(120), || network.is_round_reached(round)).await;
+    let committed = network.simulation.committed_certificate_ids();
+    network.shut_down().await;
+    committed
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_clock_follows_virtual_time() {
+    let network = start_network(Default::default(), 0).await;
+
+    // Ensure the clock starts at the genesis timestamp, and ticks with the virtual time.
+    let genesis_timestamp = network.primary(0).ledger().latest_block().timestamp();
+    let start = now();
+    assert!(start >= genesis_timestamp);
+    sleep(Duration::from_millis(5_500)).await;
+    assert!((start + 5..=start + 6).contains(&now()));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_commits_consistently() {
+    let network = start_network(Default::default(), 0).await;
+
+    // Ensure the validators commit the same subdags.
+    const TARGET_ROUND: u64 = 20;
+    wait_until(Duration::from_secs(120), || network.is_round_reached(TARGET_ROUND)).await;
+    assert!(network.num_consistently_committed(&[0, 1, 2, 3]) > 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_commits_consistently_with_message_delays() {
+    let network = start_network(NetworkConfig { min_delay_ms: 50, max_delay_ms: 500 }, 0).await;
+    // Slow down a validator further.
+    network.simulation.network().set_extra_delay(3, Duration::from_millis(300));
+
+    // Ensure the validators still commit the same subdags.
+    const TARGET_ROUND: u64 = 12;
+    wait_until(Duration::from_secs(180), || network.is_round_reached(TARGET_ROUND)).await;
+    assert!(network.num_consistently_committed(&[0, 1, 2, 3]) > 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_minority_partition() {
+    let network = start_network(Default::default(), 0).await;
+
+    const FIRST_ROUND: u64 = 6;
+    wait_until(Duration::from_secs(60), || network.is_round_reached(FIRST_ROUND)).await;
+
+    // Partition a validator away; the majority keeps a quorum, and makes progress.
+    network.simulation.partition(&[vec![0, 1, 2], vec![3]]);
+    let committed = network.num_consistently_committed(&[0, 1, 2]);
+    const SECOND_ROUND: u64 = 16;
+    wait_until(Duration::from_secs(120), || network.is_round_reached(SECOND_ROUND)).await;
+    assert!(network.num_consistently_committed(&[0, 1, 2]) > committed);
+
+    // Heal the partition, and ensure the validators keep committing the same subdags.
+    network.connect_all().await;
+    const THIRD_ROUND: u64 = 24;
+    wait_until(Duration::from_secs(120), || network.is_round_reached(THIRD_ROUND)).await;
+    assert!(network.num_consistently_committed(&[0, 1, 2, 3]) > 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_even_partition() {
+    let network = start_network(Default::default(), 0).await;
+
+    const FIRST_ROUND: u64 = 6;
+    wait_until(Duration::from_secs(60), || network.is_round_reached(FIRST_ROUND)).await;
+
+    // Partition the validators in halves; neither half has a quorum, so the network halts.
+    network.simulation.partition(&[vec![0, 1], vec![2, 3]]);
+    sleep(Duration::from_secs(1)).await;
+    assert!(network.is_halted().await);
+
+    // Heal the partition, and ensure the network resumes and commits the same subdags.
+    network.connect_all().await;
+    let halt_round = network.primaries().map(|primary| primary.current_round()).max().unwrap();
+    wait_until(Duration::from_secs(120), || network.is_round_reached(halt_round + 8)).await;
+    assert!(network.num_consistently_committed(&[0, 1, 2, 3]) > 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_crash_and_restart() {
+    let mut network = start_network(Default::default(), 0).await;
+
+    const FIRST_ROUND: u64 = 6;
+    wait_until(Duration::from_secs(60), || network.is_round_reached(FIRST_ROUND)).await;
+
+    // Crash a validator; the others keep a quorum, and make progress.
+    network.simulation.crash(3).await;
+    const SECOND_ROUND: u64 = 12;
+    wait_until(Duration::from_secs(120), || network.is_round_reached(SECOND_ROUND)).await;
+
+    // Restart the validator, and ensure the validators keep committing the same subdags.
+    network.simulation.restart(3).await.unwrap();
+    network.fire_transmissions_at(3, TRANSMISSION_INTERVAL_MS);
+    network.connect_one(3).await;
+    const THIRD_ROUND: u64 = 20;
+    wait_until(Duration::from_secs(120), || network.is_round_reached(THIRD_ROUND)).await;
+    assert!(network.num_consistently_committed(&[0, 1, 2, 3]) > 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_same_seed_commits_identically() {
+    const SEED: u64 = 7;
+    const TARGET_ROUND: u64 = 16;
+
+    // Run the same simulation twice.
+    let first = run_until_round(SEED, TARGET_ROUND).await;
+    let second = run_until_round(SEED, TARGET_ROUND).await;
+
+    // Ensure every validator committed, and committed the exact same subdags in both runs.
+    assert!(first.iter().all(|subdags| !subdags.is_empty()));
+    assert_eq!(first, second);
+}
@@ -33,6 +33,7 @@ use snarkos_node_bft::{
 };
 use snarkos_node_bft_ledger_service::LedgerService;
 use snarkos_node_bft_storage_service::BFTPersistentStorage;
+use snarkos_node_tcp::Config;
 use snarkvm::{
     ledger::{
         block::Transaction,
@@ -115,6 +116,7 @@ impl<N: Network> Consensus<N> {
         ip: Option<SocketAddr>,
         trusted_validators: &[SocketAddr],
         storage_mode: StorageMode,
+        tcp_config: Config,
     ) -> Result<Self> {
         // Recover the development ID, if it is present.
         let dev = match storage_mode {
@@ -126,7 +128,7 @@ impl<N: Network> Consensus<N> {
         // Initialize the Narwhal storage.
         let storage = NarwhalStorage::new(ledger.clone(), transmissions, BatchHeader::<N>::MAX_GC_ROUNDS as u64);
         // Initialize the BFT.
-        let bft = BFT::new(account, storage, ledger.clone(), ip, trusted_validators, dev)?;
+        let bft = BFT::new(account, storage, ledger.clone(), ip, trusted_validators, dev, tcp_config)?;
         // Return the consensus.
         Ok(Self {
             ledger,
@@ -57,6 +57,17 @@ pub fn initialize_metrics() {
     }
 }
 
+/// Increments the counter of the given name by the given value.
+pub fn increment_counter_by(name: &'static str, value: u64) {
+    ::metrics::counter!(name).increment(value);
+}
+
+/// Increments the counter of the given name for the given message type by the given value.
+/// The message type is attached as the `type` label, so that the traffic can be broken down by message type.
+pub fn increment_counter_by_type(name: &'static str, message_type: &'static str, value: u64) {
+    ::metrics::counter!(name, "type" => message_type).increment(value);
+}
+
 pub fn update_block_metrics<N: Network>(block: &Block<N>) {
     use snarkvm::ledger::ConfirmedTransaction;
 
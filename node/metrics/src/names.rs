@@ -12,9 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-pub(super) const COUNTER_NAMES: [&str; 2] = [bft::LEADERS_ELECTED, consensus::STALE_UNCONFIRMED_TRANSMISSIONS];
+pub(super) const COUNTER_NAMES: [&str; 7] = [
+    bft::COMMITTEE_TRANSITIONS,
+    bft::LEADERS_ELECTED,
+    bft::LEADERS_COMMITTED,
+    bft::LEADERS_SKIPPED,
+    bft::PROPOSAL_SIGNATURES_REQUESTED,
+    bft::PROPOSAL_SIGNATURES_RECEIVED,
+    consensus::STALE_UNCONFIRMED_TRANSMISSIONS,
+];
 
-pub(super) const GAUGE_NAMES: [&str; 26] = [
+pub(super) const GAUGE_NAMES: [&str; 28] = [
     bft::CONNECTED,
     bft::CONNECTING,
     bft::LAST_STORED_ROUND,
@@ -23,6 +31,7 @@ pub(super) const GAUGE_NAMES: [&str; 26] = [
     bft::HEIGHT,
     bft::LAST_COMMITTED_ROUND,
     bft::IS_SYNCED,
+    bft::COMPRESSION_SAVED_BYTES,
     blocks::SOLUTIONS,
     blocks::TRANSACTIONS,
     blocks::ACCEPTED_DEPLOY,
@@ -40,23 +49,42 @@ pub(super) const GAUGE_NAMES: [&str; 26] = [
     router::CONNECTED,
     router::CANDIDATE,
     router::RESTRICTED,
+    router::COMPRESSION_SAVED_BYTES,
     tcp::TCP_TASKS,
 ];
 
-pub(super) const HISTOGRAM_NAMES: [&str; 3] =
-    [bft::COMMIT_ROUNDS_LATENCY, consensus::CERTIFICATE_COMMIT_LATENCY, consensus::BLOCK_LATENCY];
+pub(super) const HISTOGRAM_NAMES: [&str; 6] = [
+    bft::COMMIT_ROUNDS_LATENCY,
+    bft::CERTIFICATION_LATENCY,
+    bft::COMPRESSION_RATIO,
+    consensus::CERTIFICATE_COMMIT_LATENCY,
+    consensus::BLOCK_LATENCY,
+    router::COMPRESSION_RATIO,
+];
 
 pub mod bft {
     pub const COMMIT_ROUNDS_LATENCY: &str = "snarkos_bft_commit_rounds_latency_secs"; // <-- This one doesn't even make sense.
+    pub const COMMITTEE_TRANSITIONS: &str = "snarkos_bft_committee_transitions_total";
     pub const CONNECTED: &str = "snarkos_bft_connected_total";
     pub const CONNECTING: &str = "snarkos_bft_connecting_total";
     pub const LAST_STORED_ROUND: &str = "snarkos_bft_last_stored_round";
     pub const LEADERS_ELECTED: &str = "snarkos_bft_leaders_elected_total";
+    pub const LEADERS_COMMITTED: &str = "snarkos_bft_leaders_committed_total";
+    pub const LEADERS_SKIPPED: &str = "snarkos_bft_leaders_skipped_total";
     pub const PROPOSAL_ROUND: &str = "snarkos_bft_primary_proposal_round";
     pub const CERTIFIED_BATCHES: &str = "snarkos_bft_primary_certified_batches";
+    pub const CERTIFICATION_LATENCY: &str = "snarkos_bft_certification_latency_secs";
+    pub const PROPOSAL_SIGNATURES_REQUESTED: &str = "snarkos_bft_primary_proposal_signatures_requested_total";
+    pub const PROPOSAL_SIGNATURES_RECEIVED: &str = "snarkos_bft_primary_proposal_signatures_received_total";
     pub const HEIGHT: &str = "snarkos_bft_height_total";
     pub const LAST_COMMITTED_ROUND: &str = "snarkos_bft_last_committed_round";
     pub const IS_SYNCED: &str = "snarkos_bft_is_synced";
+    pub const COMPRESSION_RATIO: &str = "snarkos_bft_compression_ratio";
+    pub const COMPRESSION_SAVED_BYTES: &str = "snarkos_bft_compression_saved_bytes_total";
+    pub const EVENTS_SENT: &str = "snarkos_bft_events_sent_total";
+    pub const EVENTS_RECEIVED: &str = "snarkos_bft_events_received_total";
+    pub const EVENT_BYTES_SENT: &str = "snarkos_bft_event_bytes_sent_total";
+    pub const EVENT_BYTES_RECEIVED: &str = "snarkos_bft_event_bytes_received_total";
 }
 
 pub mod blocks {
@@ -87,6 +115,12 @@ pub mod router {
     pub const CONNECTED: &str = "snarkos_router_connected_total";
     pub const CANDIDATE: &str = "snarkos_router_candidate_total";
     pub const RESTRICTED: &str = "snarkos_router_restricted_total";
+    pub const COMPRESSION_RATIO: &str = "snarkos_router_compression_ratio";
+    pub const COMPRESSION_SAVED_BYTES: &str = "snarkos_router_compression_saved_bytes_total";
+    pub const MESSAGES_SENT: &str = "snarkos_router_messages_sent_total";
+    pub const MESSAGES_RECEIVED: &str = "snarkos_router_messages_received_total";
+    pub const MESSAGE_BYTES_SENT: &str = "snarkos_router_message_bytes_sent_total";
+    pub const MESSAGE_BYTES_RECEIVED: &str = "snarkos_router_message_bytes_received_total";
 }
 
 pub mod tcp {
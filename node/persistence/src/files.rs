@@ -0,0 +1,158 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use aleo_std::{aleo_ledger_dir, StorageMode};
+use anyhow::{ensure, Result};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// The size of the magic bytes at the start of a node file.
+pub const FILE_MAGIC_SIZE: usize = 8;
+/// The size of a node file header, which is the magic, the version, and the SHA-256 checksum of the payload.
+pub const FILE_HEADER_SIZE: usize = FILE_MAGIC_SIZE + 2 + 32;
+
+/// Returns the path where the node file with the given name may be stored, in the folder right above the ledger.
+pub fn node_file_path(file_name: &str, network: u16, storage_mode: &StorageMode) -> PathBuf {
+    // Obtain the path to the ledger.
+    let mut path = aleo_ledger_dir(network, storage_mode.clone());
+    // Go to the folder right above the ledger.
+    path.pop();
+    // Append the file name.
+    match storage_mode {
+        StorageMode::Development(id) => path.push(format!(".{file_name}-{network}-{id}")),
+        StorageMode::Production | StorageMode::Custom(..) => path.push(format!("{file_name}-{network}")),
+    }
+
+    path
+}
+
+/// Returns the contents of a node file, which is the header followed by the payload.
+pub fn encode_file(magic: &[u8; FILE_MAGIC_SIZE], version: u16, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(FILE_HEADER_SIZE + payload.len());
+    bytes.extend_from_slice(magic);
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend_from_slice(&Sha256::digest(payload));
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+/// Returns the version of the format and the payload, from the contents of a node file with the given magic.
+/// Note: The version is not checked, as the caller may support (and migrate) older versions.
+pub fn decode_file<'a>(magic: &[u8; FILE_MAGIC_SIZE], bytes: &'a [u8]) -> Result<(u16, &'a [u8])> {
+    // Ensure the header is complete.
+    ensure!(bytes.starts_with(magic), "The file header is missing");
+    ensure!(bytes.len() >= FILE_HEADER_SIZE, "The file header is truncated ({} bytes)", bytes.len());
+    let (header, payload) = bytes.split_at(FILE_HEADER_SIZE);
+    let (version, checksum) = header[FILE_MAGIC_SIZE..].split_at(2);
+    let version = u16::from_le_bytes([version[0], version[1]]);
+    // Ensure the payload matches the checksum, which detects torn writes and corruption.
+    ensure!(
+        Sha256::digest(payload).as_slice() == checksum,
+        "The file checksum does not match (version {version}, {} bytes)",
+        payload.len()
+    );
+    Ok((version, payload))
+}
+
+/// Writes the given bytes to a temporary file, and renames it to the given path once it is durable,
+/// so that an interrupted write never leaves a partial file behind.
+///
+/// Each write uses its own temporary file, so that concurrent writes to the same path never interleave,
+/// and the last rename wins.
+pub fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    /// The counter of the writes in this process, which makes the temporary file names unique.
+    static NUM_WRITES: AtomicU64 = AtomicU64::new(0);
+
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(format!(".{}.{}.tmp", std::process::id(), NUM_WRITES.fetch_add(1, Ordering::Relaxed)));
+    let temp_path = PathBuf::from(temp_path);
+
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temp_path, path)
+    })();
+    // Remove the temporary file, if the write failed.
+    if let Err(error) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(error);
+    }
+
+    // Persist the rename, on platforms that support syncing directories.
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        fs::File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAGIC: [u8; FILE_MAGIC_SIZE] = *b"SNOSTEST";
+
+    #[test]
+    fn test_file_roundtrip() {
+        let bytes = encode_file(&MAGIC, 3, b"payload");
+        assert_eq!(decode_file(&MAGIC, &bytes).unwrap(), (3, &b"payload"[..]));
+
+        // Ensure a file with another magic is rejected.
+        assert!(decode_file(b"SNOSELSE", &bytes).is_err());
+        // Ensure a torn write is rejected.
+        for length in [MAGIC.len(), FILE_HEADER_SIZE, bytes.len() - 1] {
+            assert!(decode_file(&MAGIC, &bytes[..length]).is_err());
+        }
+        // Ensure a corrupted payload is rejected.
+        let mut corrupted = bytes;
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(decode_file(&MAGIC, &corrupted).is_err());
+    }
+
+    #[test]
+    fn test_write_atomically() {
+        let path = std::env::temp_dir().join(format!("snarkos-persistence-test-{}", std::process::id()));
+        write_atomically(&path, b"first").unwrap();
+        write_atomically(&path, b"second").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_write_atomically_concurrently() {
+        let path = std::env::temp_dir().join(format!("snarkos-persistence-concurrent-test-{}", std::process::id()));
+        // Write the same path from several threads, with contents of different lengths.
+        let contents = (1..=8).map(|i| vec![i as u8; i * 4096]).collect::<Vec<_>>();
+        std::thread::scope(|scope| {
+            for bytes in &contents {
+                let path = &path;
+                scope.spawn(move || {
+                    for _ in 0..16 {
+                        write_atomically(path, bytes).unwrap();
+                    }
+                });
+            }
+        });
+        // Ensure the file holds one of the writes in full, rather than a mix of them.
+        assert!(contents.contains(&fs::read(&path).unwrap()));
+        fs::remove_file(path).unwrap();
+    }
+}
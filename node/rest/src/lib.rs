@@ -20,6 +20,9 @@ extern crate tracing;
 mod helpers;
 pub use helpers::*;
 
+mod light;
+pub use light::*;
+
 mod routes;
 
 use snarkos_node_consensus::Consensus;
@@ -133,6 +136,10 @@ impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
 
             // All the endpoints before the call to `route_layer` are protected with JWT auth.
             .route(&format!("/{network}/node/address"), get(Self::get_node_address))
+            .route(&format!("/{network}/bft/dag"), get(Self::get_bft_dag))
+            .route(&format!("/{network}/bft/peers/scores"), get(Self::get_bft_peer_scores))
+            .route(&format!("/{network}/bft/participation"), get(Self::get_bft_participation))
+            .route(&format!("/{network}/bft/committee/transitions"), get(Self::get_bft_committee_transitions))
             .route_layer(middleware::from_fn(auth_middleware))
 
             // ----------------- DEPRECATED ROUTES -----------------
@@ -0,0 +1,278 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use snarkos_node_router::SYNC_LENIENCY;
+use snarkos_node_sync::HeaderSync;
+use snarkvm::{
+    console::program::StatePath,
+    prelude::{block::Transaction, LimitedWriter, ToBytes, Zero},
+};
+
+use anyhow::{bail, ensure};
+use std::time::Duration;
+
+/// The timeout in seconds for a query to an upstream REST server.
+const UPSTREAM_TIMEOUT_IN_SECS: u64 = 10;
+
+/// A REST API server for a light node.
+///
+/// The block heights, hashes, and headers are served from the verified header chain. The state paths are
+/// fetched from the upstream REST servers (i.e. full nodes), and are only returned if they verify against
+/// a block in the header chain. Queries that require the full ledger (e.g. mapping values) are not served,
+/// as they cannot be verified against the block headers.
+#[derive(Clone)]
+pub struct LightRest<N: Network, R: Routing<N>> {
+    /// The header sync module.
+    header_sync: Arc<HeaderSync<N>>,
+    /// The node (routing).
+    routing: Arc<R>,
+    /// The base URLs of the upstream REST servers.
+    upstream: Arc<Vec<String>>,
+    /// The HTTP client for the upstream REST servers.
+    client: reqwest::Client,
+    /// The server handles.
+    handles: Arc<Mutex<Vec<JoinHandle<()>>>>,
+}
+
+impl<N: Network, R: Routing<N>> LightRest<N, R> {
+    /// Initializes a new instance of the server.
+    pub async fn start(
+        rest_ip: SocketAddr,
+        rest_rps: u32,
+        upstream: Vec<String>,
+        header_sync: Arc<HeaderSync<N>>,
+        routing: Arc<R>,
+    ) -> Result<Self> {
+        // Initialize the HTTP client.
+        let client = reqwest::Client::builder().timeout(Duration::from_secs(UPSTREAM_TIMEOUT_IN_SECS)).build()?;
+        // Remove the trailing slashes from the upstream URLs.
+        let upstream = upstream.into_iter().map(|url| url.trim_end_matches('/').to_string()).collect();
+        // Initialize the server.
+        let mut server =
+            Self { header_sync, routing, upstream: Arc::new(upstream), client, handles: Default::default() };
+        // Spawn the server.
+        server.spawn_server(rest_ip, rest_rps).await?;
+        // Return the server.
+        Ok(server)
+    }
+
+    /// Returns the handles.
+    pub const fn handles(&self) -> &Arc<Mutex<Vec<JoinHandle<()>>>> {
+        &self.handles
+    }
+}
+
+impl<N: Network, R: Routing<N>> LightRest<N, R> {
+    async fn spawn_server(&mut self, rest_ip: SocketAddr, rest_rps: u32) -> Result<()> {
+        let cors = CorsLayer::new()
+            .allow_origin(Any)
+            .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+            .allow_headers([CONTENT_TYPE]);
+
+        // Log the REST rate limit per IP.
+        debug!("REST rate limit per IP - {rest_rps} RPS");
+
+        // Prepare the rate limiting setup.
+        let governor_config = Box::new(
+            GovernorConfigBuilder::default()
+                .per_second(1)
+                .burst_size(rest_rps)
+                .error_handler(|error| Response::new(error.to_string().into()))
+                .finish()
+                .expect("Couldn't set up rate limiting for the REST server!"),
+        );
+
+        // Get the network being used.
+        let network = network_name::<N>()?;
+
+        let router = axum::Router::new()
+            // GET ../block/..
+            .route(&format!("/{network}/block/height/latest"), get(Self::get_block_height_latest))
+            .route(&format!("/{network}/block/hash/latest"), get(Self::get_block_hash_latest))
+
+            // GET ../header/..
+            .route(&format!("/{network}/header/latest"), get(Self::get_header_latest))
+            .route(&format!("/{network}/header/:height"), get(Self::get_header))
+
+            // POST ../transaction/broadcast
+            .route(&format!("/{network}/transaction/broadcast"), post(Self::transaction_broadcast))
+
+            // GET ../peers/..
+            .route(&format!("/{network}/peers/count"), get(Self::get_peers_count))
+            .route(&format!("/{network}/peers/all"), get(Self::get_peers_all))
+
+            // GET misc endpoints.
+            .route(&format!("/{network}/height/:hash"), get(Self::get_height))
+            .route(&format!("/{network}/statePath/:commitment"), get(Self::get_state_path_for_commitment))
+
+            // Pass in `LightRest` to make things convenient.
+            .with_state(self.clone())
+            // Enable tower-http tracing.
+            .layer(TraceLayer::new_for_http())
+            // Custom logging.
+            .layer(middleware::from_fn(log_middleware))
+            // Enable CORS.
+            .layer(cors)
+            // Cap body size at 512KiB.
+            .layer(DefaultBodyLimit::max(512 * 1024))
+            .layer(GovernorLayer {
+                // We can leak this because it is created only once and it persists.
+                config: Box::leak(governor_config),
+            });
+
+        let rest_listener = TcpListener::bind(rest_ip).await?;
+        self.handles.lock().push(tokio::spawn(async move {
+            axum::serve(rest_listener, router.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+                .expect("couldn't start rest server");
+        }));
+        Ok(())
+    }
+}
+
+impl<N: Network, R: Routing<N>> LightRest<N, R> {
+    // GET /<network>/block/height/latest
+    async fn get_block_height_latest(State(rest): State<Self>) -> ErasedJson {
+        ErasedJson::pretty(rest.header_sync.latest_height())
+    }
+
+    // GET /<network>/block/hash/latest
+    async fn get_block_hash_latest(State(rest): State<Self>) -> ErasedJson {
+        ErasedJson::pretty(rest.header_sync.latest_hash())
+    }
+
+    // GET /<network>/header/latest
+    async fn get_header_latest(State(rest): State<Self>) -> ErasedJson {
+        ErasedJson::pretty(rest.header_sync.latest_header())
+    }
+
+    // GET /<network>/header/{height}
+    async fn get_header(State(rest): State<Self>, Path(height): Path<u32>) -> Result<ErasedJson, RestError> {
+        match rest.header_sync.get_header(height) {
+            Some(header) => Ok(ErasedJson::pretty(header)),
+            None => Err(RestError(format!("Header {height} has not been synced"))),
+        }
+    }
+
+    // GET /<network>/height/{blockHash}
+    async fn get_height(State(rest): State<Self>, Path(hash): Path<N::BlockHash>) -> Result<ErasedJson, RestError> {
+        match rest.header_sync.get_height(&hash) {
+            Some(height) => Ok(ErasedJson::pretty(height)),
+            None => Err(RestError(format!("Block '{hash}' has not been synced"))),
+        }
+    }
+
+    // GET /<network>/statePath/{commitment}
+    async fn get_state_path_for_commitment(
+        State(rest): State<Self>,
+        Path(commitment): Path<Field<N>>,
+    ) -> Result<ErasedJson, RestError> {
+        Ok(ErasedJson::pretty(rest.fetch_state_path(commitment).await?))
+    }
+
+    // GET /<network>/peers/count
+    async fn get_peers_count(State(rest): State<Self>) -> ErasedJson {
+        ErasedJson::pretty(rest.routing.router().number_of_connected_peers())
+    }
+
+    // GET /<network>/peers/all
+    async fn get_peers_all(State(rest): State<Self>) -> ErasedJson {
+        ErasedJson::pretty(rest.routing.router().connected_peers())
+    }
+
+    // POST /<network>/transaction/broadcast
+    async fn transaction_broadcast(
+        State(rest): State<Self>,
+        Json(tx): Json<Transaction<N>>,
+    ) -> Result<ErasedJson, RestError> {
+        // Do not process the transaction if the node is too far behind.
+        if rest.routing.num_blocks_behind() > SYNC_LENIENCY {
+            return Err(RestError(format!("Unable to broadcast transaction '{}' (node is syncing)", fmt_id(tx.id()))));
+        }
+
+        // If the transaction exceeds the transaction size limit, return an error.
+        let buffer = Vec::with_capacity(3000);
+        if tx.write_le(LimitedWriter::new(buffer, N::MAX_TRANSACTION_SIZE)).is_err() {
+            return Err(RestError("Transaction size exceeds the byte limit".to_string()));
+        }
+
+        // Prepare the unconfirmed transaction message.
+        let tx_id = tx.id();
+        let message = Message::UnconfirmedTransaction(UnconfirmedTransaction {
+            transaction_id: tx_id,
+            transaction: Data::Object(tx),
+        });
+
+        // Broadcast the transaction.
+        rest.routing.propagate(message, &[]);
+
+        Ok(ErasedJson::pretty(tx_id))
+    }
+}
+
+impl<N: Network, R: Routing<N>> LightRest<N, R> {
+    /// Fetches the state path for the given commitment from the upstream REST servers,
+    /// returning the first state path that verifies against the header chain.
+    async fn fetch_state_path(&self, commitment: Field<N>) -> Result<StatePath<N>> {
+        ensure!(!self.upstream.is_empty(), "No upstream REST servers are configured");
+        let network = network_name::<N>()?;
+
+        for base_url in self.upstream.iter() {
+            let url = format!("{base_url}/{network}/statePath/{commitment}");
+            // Fetch the state path.
+            let state_path = match self.get_json::<StatePath<N>>(&url).await {
+                Ok(state_path) => state_path,
+                Err(error) => {
+                    warn!("Failed to fetch the state path from '{base_url}' - {error}");
+                    continue;
+                }
+            };
+            // Verify the state path.
+            match self.verify_state_path(&state_path, commitment) {
+                Ok(()) => return Ok(state_path),
+                Err(error) => warn!("Received an invalid state path from '{base_url}' - {error}"),
+            }
+        }
+        bail!("Unable to fetch a valid state path for commitment '{commitment}'")
+    }
+
+    /// Fetches and deserializes the JSON response from the given URL.
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let response = self.client.get(url).send().await?.error_for_status()?;
+        Ok(serde_json::from_str(&response.text().await?)?)
+    }
+
+    /// Ensures the given state path is valid for the commitment, and is rooted in a block of the header chain.
+    fn verify_state_path(&self, state_path: &StatePath<N>, commitment: Field<N>) -> Result<()> {
+        // Ensure the state path is for the given commitment.
+        ensure!(state_path.transition_leaf().id() == commitment, "The state path is for a different commitment");
+        // Ensure the state path is internally consistent, including the block hash.
+        state_path.verify(true, Field::zero())?;
+        // Ensure the block of the state path is in the header chain.
+        let block_hash = state_path.block_hash();
+        ensure!(self.header_sync.get_height(&block_hash).is_some(), "Block '{block_hash}' has not been synced");
+        Ok(())
+    }
+}
+
+/// Returns the name of the network, as used in the REST routes.
+fn network_name<N: Network>() -> Result<&'static str> {
+    match N::ID {
+        snarkvm::console::network::MainnetV0::ID => Ok("mainnet"),
+        snarkvm::console::network::TestnetV0::ID => Ok("testnet"),
+        snarkvm::console::network::CanaryV0::ID => Ok("canary"),
+        unknown_id => bail!("Unknown network ID ({unknown_id})"),
+    }
+}
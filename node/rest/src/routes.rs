@@ -19,10 +19,12 @@ use snarkvm::{
     prelude::{block::Transaction, Address, Identifier, LimitedWriter, Plaintext, ToBytes},
 };
 
+use axum::response::IntoResponse;
 use indexmap::IndexMap;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::HashMap;
 
 /// The `get_blocks` query object.
 #[derive(Deserialize, Serialize)]
@@ -33,6 +35,17 @@ pub(crate) struct BlockRange {
     end: u32,
 }
 
+/// The `get_bft_dag` query object.
+#[derive(Deserialize, Serialize)]
+pub(crate) struct DagRange {
+    /// The starting round (inclusive).
+    start: u64,
+    /// The ending round (inclusive).
+    end: u64,
+    /// The export format, either `json` (default) or `dot`.
+    format: Option<String>,
+}
+
 /// The `get_mapping_value` query object.
 #[derive(Deserialize, Serialize)]
 pub(crate) struct Metadata {
@@ -209,6 +222,61 @@ impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
         }
     }
 
+    // GET /<network>/bft/participation
+    pub(crate) async fn get_bft_participation(State(rest): State<Self>) -> Result<ErasedJson, RestError> {
+        match rest.consensus {
+            Some(consensus) => {
+                let participation = consensus.bft().primary().participation();
+                Ok(ErasedJson::pretty(json!({
+                    "validators": participation.validators(),
+                    "rounds": participation.rounds(),
+                })))
+            }
+            None => Err(RestError("Route isn't available for this node type".to_string())),
+        }
+    }
+
+    // GET /<network>/bft/committee/transitions
+    pub(crate) async fn get_bft_committee_transitions(State(rest): State<Self>) -> Result<ErasedJson, RestError> {
+        match rest.consensus {
+            Some(consensus) => {
+                let committee_tracker = consensus.bft().primary().gateway().committee_tracker();
+                Ok(ErasedJson::pretty(json!({
+                    "transitions": committee_tracker.transitions(),
+                    "upcoming": committee_tracker.upcoming(),
+                })))
+            }
+            None => Err(RestError("Route isn't available for this node type".to_string())),
+        }
+    }
+
+    // GET /<network>/bft/peers/scores
+    pub(crate) async fn get_bft_peer_scores(State(rest): State<Self>) -> Result<ErasedJson, RestError> {
+        match rest.consensus {
+            Some(consensus) => Ok(ErasedJson::pretty(consensus.bft().primary().gateway().peer_scores().to_map())),
+            None => Err(RestError("Route isn't available for this node type".to_string())),
+        }
+    }
+
+    // GET /<network>/bft/dag?start={start_round}&end={end_round}
+    // GET /<network>/bft/dag?start={start_round}&end={end_round}&format={json|dot}
+    pub(crate) async fn get_bft_dag(
+        State(rest): State<Self>,
+        Query(dag_range): Query<DagRange>,
+    ) -> Result<Response, RestError> {
+        let Some(consensus) = rest.consensus else {
+            return Err(RestError("Route isn't available for this node type".to_string()));
+        };
+        // Export the DAG for the given rounds.
+        let export = consensus.bft().export_dag(dag_range.start, dag_range.end)?;
+        // Return the export in the requested format.
+        match dag_range.format.as_deref() {
+            None | Some("json") => Ok(ErasedJson::pretty(export).into_response()),
+            Some("dot") => Ok((StatusCode::OK, [(CONTENT_TYPE, "text/vnd.graphviz")], export.to_dot()).into_response()),
+            Some(format) => Err(RestError(format!("Unsupported DAG export format '{format}'"))),
+        }
+    }
+
     // GET /<network>/program/{programID}
     pub(crate) async fn get_program(
         State(rest): State<Self>,
@@ -311,7 +379,36 @@ impl<N: Network, C: ConsensusStorage<N>, R: Routing<N>> Rest<N, C, R> {
 
     // GET /<network>/peers/all/metrics
     pub(crate) async fn get_peers_all_metrics(State(rest): State<Self>) -> ErasedJson {
-        ErasedJson::pretty(rest.routing.router().connected_metrics())
+        let router = rest.routing.router();
+        // Retrieve the traffic of the connected peers, by message type.
+        let mut traffic = router.connected_traffic().into_iter().collect::<HashMap<_, _>>();
+        let peers = router
+            .connected_metrics()
+            .into_iter()
+            .map(|(peer_ip, node_type)| {
+                json!({
+                    "ip": peer_ip,
+                    "node_type": node_type,
+                    "traffic": traffic.remove(&peer_ip).unwrap_or_default(),
+                })
+            })
+            .collect::<Vec<_>>();
+        // Retrieve the traffic of the validators, by event type, if the node is a validator.
+        let bft = rest.consensus.as_ref().map(|consensus| {
+            let gateway = consensus.bft().primary().gateway();
+            let peers = gateway
+                .connected_traffic()
+                .into_iter()
+                .map(|(peer_ip, traffic)| json!({ "ip": peer_ip, "traffic": traffic }))
+                .collect::<Vec<_>>();
+            json!({ "peers": peers, "traffic": gateway.traffic() })
+        });
+
+        ErasedJson::pretty(json!({
+            "peers": peers,
+            "traffic": router.traffic(),
+            "bft": bft,
+        }))
     }
 
     // GET /<network>/node/address
@@ -83,11 +83,12 @@ pub mod prop_tests {
     }
 
     pub fn any_node_type() -> BoxedStrategy<NodeType> {
-        (0..=2)
+        (0..=3)
             .prop_map(|id| match id {
                 0 => NodeType::Client,
                 1 => NodeType::Prover,
                 2 => NodeType::Validator,
+                3 => NodeType::Light,
                 _ => unreachable!(),
             })
             .boxed()
@@ -14,6 +14,7 @@
 
 use super::*;
 
+use snarkos_account::SignRequest;
 use snarkvm::{
     ledger::narwhal::Data,
     prelude::{Field, FromBytes, ToBytes},
@@ -27,6 +28,65 @@ pub struct ChallengeResponse<N: Network> {
     pub restrictions_id: Field<N>,
     pub signature: Data<Signature<N>>,
     pub nonce: u64,
+    /// The capabilities of the node, from `CAPABILITIES_VERSION` onwards.
+    ///
+    /// The capabilities are advertised in the response rather than the request, as the version of the peer
+    /// is only known once its challenge request is received.
+    pub capabilities: Option<Capabilities>,
+    /// The negotiated version of the network protocol, from `NEGOTIATION_VERSION` onwards.
+    pub version: Option<u32>,
+}
+
+impl<N: Network> ChallengeResponse<N> {
+    /// Returns the request that is signed in a challenge response to the given request nonce.
+    ///
+    /// If the response carries the negotiated version, the request also binds the version and the capabilities,
+    /// so that neither of them can be rewritten.
+    pub fn signing_request(
+        request_nonce: u64,
+        response_nonce: u64,
+        capabilities: Option<Capabilities>,
+        version: Option<u32>,
+    ) -> SignRequest<N> {
+        let context = match version {
+            Some(version) => [&[capabilities.unwrap_or_default().bits()][..], &version.to_le_bytes()].concat(),
+            None => vec![],
+        };
+        SignRequest::Challenge { request_nonce, response_nonce, context }
+    }
+
+    /// Writes the response in the format of the given negotiated version of the network protocol.
+    pub fn write_versioned<W: io::Write>(&self, version: u32, mut writer: W) -> io::Result<()> {
+        self.genesis_header.write_le(&mut writer)?;
+        self.restrictions_id.write_le(&mut writer)?;
+        self.signature.write_le(&mut writer)?;
+        self.nonce.write_le(&mut writer)?;
+        if version >= Message::<N>::CAPABILITIES_VERSION {
+            self.capabilities.unwrap_or_default().write_le(&mut writer)?;
+        }
+        if version >= Message::<N>::NEGOTIATION_VERSION {
+            self.version.unwrap_or(version).write_le(&mut writer)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a response in the format of the given negotiated version of the network protocol.
+    pub fn read_versioned<R: io::Read>(version: u32, mut reader: R) -> io::Result<Self> {
+        let genesis_header = Header::read_le(&mut reader)?;
+        let restrictions_id = Field::read_le(&mut reader)?;
+        let signature = Data::read_le(&mut reader)?;
+        let nonce = u64::read_le(&mut reader)?;
+        let capabilities = match version >= Message::<N>::CAPABILITIES_VERSION {
+            true => Some(Capabilities::read_le(&mut reader)?),
+            false => None,
+        };
+        let version = match version >= Message::<N>::NEGOTIATION_VERSION {
+            true => Some(u32::read_le(&mut reader)?),
+            false => None,
+        };
+
+        Ok(Self { genesis_header, restrictions_id, signature, nonce, capabilities, version })
+    }
 }
 
 impl<N: Network> MessageTrait for ChallengeResponse<N> {
@@ -37,29 +97,60 @@ impl<N: Network> MessageTrait for ChallengeResponse<N> {
     }
 }
 
+/// Note: A response is only serialized without a version when the version is not yet negotiated,
+/// i.e. when it is received by the initiator of the handshake. The fields that are set are written,
+/// and read back if there are bytes remaining.
 impl<N: Network> ToBytes for ChallengeResponse<N> {
     fn write_le<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
         self.genesis_header.write_le(&mut writer)?;
         self.restrictions_id.write_le(&mut writer)?;
         self.signature.write_le(&mut writer)?;
-        self.nonce.write_le(&mut writer)
+        self.nonce.write_le(&mut writer)?;
+        match (&self.capabilities, &self.version) {
+            (Some(capabilities), version) => {
+                capabilities.write_le(&mut writer)?;
+                if let Some(version) = version {
+                    version.write_le(&mut writer)?;
+                }
+            }
+            (None, None) => (),
+            // Note: Every version that reads the negotiated version also reads the capabilities.
+            (None, Some(_)) => return Err(error("The negotiated version requires the capabilities")),
+        }
+        Ok(())
     }
 }
 
 impl<N: Network> FromBytes for ChallengeResponse<N> {
     fn read_le<R: io::Read>(mut reader: R) -> io::Result<Self> {
-        Ok(Self {
-            genesis_header: Header::read_le(&mut reader)?,
-            restrictions_id: Field::read_le(&mut reader)?,
-            signature: Data::read_le(&mut reader)?,
-            nonce: u64::read_le(reader)?,
-        })
+        let genesis_header = Header::read_le(&mut reader)?;
+        let restrictions_id = Field::read_le(&mut reader)?;
+        let signature = Data::read_le(&mut reader)?;
+        let nonce = u64::read_le(&mut reader)?;
+        // Read the capabilities, if there are bytes remaining.
+        let mut capabilities = [0u8; 1];
+        let capabilities = match reader.read(&mut capabilities)? {
+            0 => None,
+            _ => Some(Capabilities::read_le(&capabilities[..])?),
+        };
+        // Read the negotiated version, if there are bytes remaining.
+        let mut version = [0u8; 4];
+        let version = match reader.read(&mut version[..1])? {
+            0 => None,
+            _ => {
+                reader.read_exact(&mut version[1..])?;
+                Some(u32::from_le_bytes(version))
+            }
+        };
+
+        Ok(Self { genesis_header, restrictions_id, signature, nonce, capabilities, version })
     }
 }
 
 #[cfg(test)]
 pub mod prop_tests {
-    use crate::ChallengeResponse;
+    use crate::{Capabilities, ChallengeResponse, Message};
+    use snarkos_account::SignRequest;
     use snarkvm::{
         console::prelude::{FromBytes, ToBytes},
         ledger::{ledger_test_helpers::sample_genesis_block, narwhal::Data},
@@ -93,12 +184,17 @@ pub mod prop_tests {
     }
 
     pub fn any_challenge_response() -> BoxedStrategy<ChallengeResponse<CurrentNetwork>> {
-        (any_genesis_header(), any_signature(), any::<u64>())
-            .prop_map(|(genesis_header, sig, nonce)| ChallengeResponse {
+        (any_genesis_header(), any_signature(), any::<u64>(), any::<Option<(bool, Option<u32>)>>())
+            .prop_map(|(genesis_header, sig, nonce, trailing)| ChallengeResponse {
                 genesis_header,
                 restrictions_id: any_restrictions_id(),
                 signature: Data::Object(sig),
                 nonce,
+                capabilities: trailing.map(|(is_compressed, _)| match is_compressed {
+                    true => Capabilities::supported(),
+                    false => Capabilities::empty(),
+                }),
+                version: trailing.and_then(|(_, version)| version),
             })
             .boxed()
     }
@@ -112,9 +208,43 @@ pub mod prop_tests {
             ChallengeResponse::read_le(buf.into_inner().reader()).unwrap();
 
         assert_eq!(original.genesis_header, deserialized.genesis_header);
+        assert_eq!(original.capabilities, deserialized.capabilities);
+        assert_eq!(original.version, deserialized.version);
         assert_eq!(
             original.signature.deserialize_blocking().unwrap(),
             deserialized.signature.deserialize_blocking().unwrap()
         );
     }
+
+    #[proptest]
+    fn challenge_response_versioned_roundtrip(
+        #[strategy(any_challenge_response())] original: ChallengeResponse<CurrentNetwork>,
+        #[strategy(Message::<CurrentNetwork>::MINIMUM_VERSION..=Message::<CurrentNetwork>::VERSION)] version: u32,
+    ) {
+        let mut buf = BytesMut::default().writer();
+        original.write_versioned(version, &mut buf).unwrap();
+        let bytes = buf.into_inner();
+
+        // Ensure the fields are read back strictly by the version, and not by the bytes remaining.
+        let deserialized = ChallengeResponse::<CurrentNetwork>::read_versioned(version, &bytes[..]).unwrap();
+        let has_capabilities = version >= Message::<CurrentNetwork>::CAPABILITIES_VERSION;
+        let has_version = version >= Message::<CurrentNetwork>::NEGOTIATION_VERSION;
+        assert_eq!(deserialized.capabilities.is_some(), has_capabilities);
+        assert_eq!(deserialized.version, has_version.then(|| original.version.unwrap_or(version)));
+    }
+
+    #[test]
+    fn signing_request_binds_version_and_capabilities() {
+        type Response = ChallengeResponse<CurrentNetwork>;
+        let capabilities = Some(Capabilities::supported());
+        let version = Some(Message::<CurrentNetwork>::VERSION);
+
+        // Ensure only the nonces are signed without a negotiated version, as in the legacy handshake.
+        let request = Response::signing_request(1, 2, capabilities, None);
+        assert_eq!(request, SignRequest::Challenge { request_nonce: 1, response_nonce: 2, context: vec![] });
+        // Ensure the version and the capabilities are signed with a negotiated version.
+        let request = Response::signing_request(1, 2, capabilities, version);
+        assert_ne!(request, Response::signing_request(1, 2, capabilities, version.map(|v| v - 1)));
+        assert_ne!(request, Response::signing_request(1, 2, Some(Capabilities::empty()), version));
+    }
 }
@@ -0,0 +1,244 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+use snarkvm::{
+    ledger::{authority::Authority, committee::Committee, narwhal::Data},
+    prelude::{block::Block, FromBytes, ToBytes},
+};
+
+use anyhow::{bail, ensure, Result};
+use std::borrow::Cow;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeaderResponse<N: Network> {
+    /// The original header request.
+    pub request: HeaderRequest,
+    /// The headers.
+    pub headers: Data<DataHeaders<N>>,
+}
+
+impl<N: Network> MessageTrait for HeaderResponse<N> {
+    /// Returns the message name.
+    #[inline]
+    fn name(&self) -> Cow<'static, str> {
+        let start = self.request.start_height;
+        let end = self.request.end_height;
+        match start + 1 == end {
+            true => format!("HeaderResponse {start}"),
+            false => format!("HeaderResponse {start}..{end}"),
+        }
+        .into()
+    }
+}
+
+impl<N: Network> ToBytes for HeaderResponse<N> {
+    fn write_le<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        self.request.write_le(&mut writer)?;
+        self.headers.write_le(writer)
+    }
+}
+
+impl<N: Network> FromBytes for HeaderResponse<N> {
+    fn read_le<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let request = HeaderRequest::read_le(&mut reader)?;
+        let headers = Data::read_le(reader)?;
+        Ok(Self { request, headers })
+    }
+}
+
+/// The header of a block, with the previous block hash and the authority (i.e. the certificates) of the block.
+/// This is sufficient to recompute the block hash, to link the header to the previous one, and to verify
+/// the certificates against the committee. If the block updated the committee, the new committee is included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CertifiedHeader<N: Network> {
+    /// The hash of the previous block.
+    pub previous_hash: N::BlockHash,
+    /// The header of the block.
+    pub header: Header<N>,
+    /// The authority of the block.
+    pub authority: Authority<N>,
+    /// The committee after the block, if it was updated by the block.
+    pub committee: Option<Committee<N>>,
+}
+
+impl<N: Network> From<&Block<N>> for CertifiedHeader<N> {
+    /// Initializes a new certified header from the given block.
+    fn from(block: &Block<N>) -> Self {
+        Self {
+            previous_hash: block.previous_hash(),
+            header: *block.header(),
+            authority: block.authority().clone(),
+            committee: None,
+        }
+    }
+}
+
+impl<N: Network> CertifiedHeader<N> {
+    /// Returns the block height.
+    pub fn height(&self) -> u32 {
+        self.header.height()
+    }
+}
+
+impl<N: Network> ToBytes for CertifiedHeader<N> {
+    fn write_le<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        self.previous_hash.write_le(&mut writer)?;
+        self.header.write_le(&mut writer)?;
+        self.authority.write_le(&mut writer)?;
+        match &self.committee {
+            Some(committee) => {
+                1u8.write_le(&mut writer)?;
+                committee.write_le(&mut writer)
+            }
+            None => 0u8.write_le(&mut writer),
+        }
+    }
+}
+
+impl<N: Network> FromBytes for CertifiedHeader<N> {
+    fn read_le<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let previous_hash = N::BlockHash::read_le(&mut reader)?;
+        let header = Header::read_le(&mut reader)?;
+        let authority = Authority::read_le(&mut reader)?;
+        let committee = match u8::read_le(&mut reader)? {
+            0 => None,
+            1 => Some(Committee::read_le(&mut reader)?),
+            _ => return Err(error("Invalid committee flag in the certified header")),
+        };
+        Ok(Self { previous_hash, header, authority, committee })
+    }
+}
+
+/// A wrapper for a list of certified headers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataHeaders<N: Network>(pub Vec<CertifiedHeader<N>>);
+
+impl<N: Network> DataHeaders<N> {
+    /// The maximum number of headers that can be sent in a single message.
+    pub const MAXIMUM_NUMBER_OF_HEADERS: u8 = 32;
+
+    /// Ensures that the headers are well-formed in a header response.
+    pub fn ensure_response_is_well_formed(
+        &self,
+        peer_ip: SocketAddr,
+        start_height: u32,
+        end_height: u32,
+    ) -> Result<()> {
+        // Ensure the headers are not empty.
+        ensure!(!self.0.is_empty(), "Peer '{peer_ip}' sent an empty header response ({start_height}..{end_height})");
+        // Check that the headers are sequentially ordered.
+        if !self.0.windows(2).all(|w| w[0].height() + 1 == w[1].height()) {
+            bail!("Peer '{peer_ip}' sent an invalid header response (headers are not sequentially ordered)")
+        }
+
+        // Retrieve the start (inclusive) and end (exclusive) block height.
+        let candidate_start_height = self.first().map(|h| h.height()).unwrap_or(0);
+        let candidate_end_height = 1 + self.last().map(|h| h.height()).unwrap_or(0);
+        // Check that the range matches the header request.
+        if start_height != candidate_start_height || end_height != candidate_end_height {
+            bail!("Peer '{peer_ip}' sent an invalid header response (range does not match header request)")
+        }
+        Ok(())
+    }
+}
+
+impl<N: Network> std::ops::Deref for DataHeaders<N> {
+    type Target = Vec<CertifiedHeader<N>>;
+
+    /// Returns the list of headers.
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<N: Network> ToBytes for DataHeaders<N> {
+    /// Writes the headers to the given writer.
+    #[inline]
+    fn write_le<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        // Prepare the number of headers.
+        let num_headers = self.0.len();
+        // Ensure that the number of headers is within the allowed range.
+        if num_headers > Self::MAXIMUM_NUMBER_OF_HEADERS as usize {
+            return Err(error("Header response exceeds maximum number of headers"));
+        }
+        // Write the number of headers.
+        (num_headers as u8).write_le(&mut writer)?;
+        // Write the headers.
+        self.0.iter().try_for_each(|header| header.write_le(&mut writer))
+    }
+}
+
+impl<N: Network> FromBytes for DataHeaders<N> {
+    /// Reads the headers from the given reader.
+    #[inline]
+    fn read_le<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        // Read the number of headers.
+        let num_headers = u8::read_le(&mut reader)?;
+        // Ensure that the number of headers is within the allowed range.
+        if num_headers > Self::MAXIMUM_NUMBER_OF_HEADERS {
+            return Err(error("Header response exceeds maximum number of headers"));
+        }
+        // Read the headers.
+        let headers = (0..num_headers).map(|_| CertifiedHeader::read_le(&mut reader)).collect::<Result<Vec<_>, _>>()?;
+        Ok(Self(headers))
+    }
+}
+
+#[cfg(test)]
+pub mod prop_tests {
+    use crate::{
+        block_response::prop_tests::any_block,
+        header_request::prop_tests::any_header_request,
+        CertifiedHeader,
+        DataHeaders,
+        HeaderResponse,
+    };
+    use snarkvm::{
+        prelude::narwhal::Data,
+        utilities::{FromBytes, ToBytes},
+    };
+
+    use bytes::{Buf, BufMut, BytesMut};
+    use proptest::{
+        collection::vec,
+        prelude::{BoxedStrategy, Strategy},
+    };
+    use test_strategy::proptest;
+
+    type CurrentNetwork = snarkvm::prelude::MainnetV0;
+
+    pub fn any_data_headers() -> BoxedStrategy<DataHeaders<CurrentNetwork>> {
+        vec(any_block().prop_map(|block| CertifiedHeader::from(&block)), 0..=1).prop_map(DataHeaders).boxed()
+    }
+
+    pub fn any_header_response() -> BoxedStrategy<HeaderResponse<CurrentNetwork>> {
+        (any_header_request(), any_data_headers())
+            .prop_map(|(request, data_headers)| HeaderResponse { request, headers: Data::Object(data_headers) })
+            .boxed()
+    }
+
+    #[proptest]
+    fn header_response_roundtrip(#[strategy(any_header_response())] header_response: HeaderResponse<CurrentNetwork>) {
+        let mut bytes = BytesMut::default().writer();
+        header_response.write_le(&mut bytes).unwrap();
+        let decoded = HeaderResponse::<CurrentNetwork>::read_le(&mut bytes.into_inner().reader()).unwrap();
+        assert_eq!(header_response.request, decoded.request);
+        assert_eq!(
+            header_response.headers.deserialize_blocking().unwrap(),
+            decoded.headers.deserialize_blocking().unwrap(),
+        );
+    }
+}
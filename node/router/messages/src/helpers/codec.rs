@@ -12,11 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::Message;
+use crate::{Compression, Message};
+use snarkos_node_bft_events::{capture, compress_frame, decompress_frame, Capture, CaptureProtocol, Direction};
 use snarkvm::prelude::{FromBytes, Network, ToBytes};
 
 use ::bytes::{Buf, BufMut, BytesMut};
 use core::marker::PhantomData;
+use std::net::SocketAddr;
 use tokio_util::codec::{Decoder, Encoder, LengthDelimitedCodec};
 
 /// The maximum size of a message that can be transmitted during the handshake.
@@ -28,21 +30,67 @@ pub(crate) const MAXIMUM_MESSAGE_SIZE: usize = 128 * 1024 * 1024; // 128 MiB
 /// The codec used to decode and encode network `Message`s.
 pub struct MessageCodec<N: Network> {
     codec: LengthDelimitedCodec,
+    /// The compression of the connection.
+    compression: Compression,
+    /// The negotiated version of the network protocol of the connection, once it is known.
+    version: Option<u32>,
+    /// The capture and the peer address of the connection, if the capture is enabled.
+    capture: Option<(&'static Capture, SocketAddr)>,
     _phantom: PhantomData<N>,
 }
 
 impl<N: Network> MessageCodec<N> {
+    /// Initializes the codec of the handshake, in which the version is not yet negotiated.
     pub fn handshake() -> Self {
-        let mut codec = Self::default();
+        let mut codec = Self { version: None, ..Default::default() };
         codec.codec.set_max_frame_length(MAXIMUM_HANDSHAKE_MESSAGE_SIZE);
         codec
     }
+
+    /// Sets the compression of the connection.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        // Account for the frame tag of the inbound frames.
+        self.codec.set_max_frame_length(MAXIMUM_MESSAGE_SIZE + compression.inbound as usize);
+        self
+    }
+
+    /// Sets the negotiated version of the network protocol of the connection.
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.set_version(version);
+        self
+    }
+
+    /// Sets the negotiated version of the network protocol of the connection, once it is known in the handshake.
+    pub fn set_version(&mut self, version: u32) {
+        self.version = Some(version);
+    }
+
+    /// Returns the negotiated version of the network protocol of the connection, if it is known.
+    pub fn version(&self) -> Option<u32> {
+        self.version
+    }
+
+    /// Returns the version that gates the messages of the connection.
+    /// Note: Before the version is negotiated, only the messages of the minimum version are exchanged.
+    fn gating_version(&self) -> u32 {
+        self.version.unwrap_or(Message::<N>::MINIMUM_VERSION)
+    }
+
+    /// Records the messages of the connection with the given (ambiguous) peer address, if the capture is enabled.
+    pub fn with_capture(mut self, peer_addr: SocketAddr) -> Self {
+        self.capture = capture().map(|capture| (capture, peer_addr));
+        self
+    }
 }
 
 impl<N: Network> Default for MessageCodec<N> {
     fn default() -> Self {
         Self {
             codec: LengthDelimitedCodec::builder().max_frame_length(MAXIMUM_MESSAGE_SIZE).little_endian().new_codec(),
+            compression: Default::default(),
+            version: Some(Message::<N>::VERSION),
+            capture: None,
             _phantom: Default::default(),
         }
     }
@@ -52,15 +100,58 @@ impl<N: Network> Encoder<Message<N>> for MessageCodec<N> {
     type Error = std::io::Error;
 
     fn encode(&mut self, message: Message<N>, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        // Serialize the payload directly into dst.
-        message
-            .write_le(&mut dst.writer())
+        // Ensure the message is supported by the negotiated version.
+        let version = self.gating_version();
+        if !message.is_supported_by(version) {
+            let error = format!("'{}' is not supported by version {version}", message.name());
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, error));
+        }
+
+        // Serialize the payload directly into dst, in the format of the negotiated version.
+        match self.version {
+            Some(version) => message.write_versioned(version, &mut dst.writer()),
+            None => message.write_le(&mut dst.writer()),
+        }
             // This error should never happen, the conversion is for greater compatibility.
             .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "serialization error"))?;
 
-        let serialized_message = dst.split_to(dst.len()).freeze();
+        let serialized_message = dst.split_to(dst.len());
 
-        self.codec.encode(serialized_message, dst)
+        // Record the message, if the capture is enabled.
+        if let Some((capture, peer_addr)) = self.capture {
+            capture.record(Direction::Outbound, CaptureProtocol::Router, peer_addr, &serialized_message);
+        }
+
+        // Compress the message, if the peer accepts compressed frames.
+        let serialized_message = match self.compression.outbound {
+            true => {
+                let mut frame = BytesMut::new();
+                let _ratio = compress_frame(&serialized_message, &mut frame)?;
+                #[cfg(feature = "metrics")]
+                if let Some(ratio) = _ratio {
+                    metrics::histogram(metrics::router::COMPRESSION_RATIO, ratio);
+                    let saved_bytes = serialized_message.len().saturating_sub(frame.len());
+                    metrics::increment_gauge(metrics::router::COMPRESSION_SAVED_BYTES, saved_bytes as f64);
+                }
+                frame
+            }
+            false => serialized_message,
+        };
+
+        self.codec.encode(serialized_message.freeze(), dst)?;
+
+        #[cfg(feature = "metrics")]
+        {
+            // Note: The destination only holds the frame of this message, as its contents were split off above.
+            metrics::increment_counter_by_type(metrics::router::MESSAGES_SENT, message.type_name(), 1);
+            metrics::increment_counter_by_type(
+                metrics::router::MESSAGE_BYTES_SENT,
+                message.type_name(),
+                dst.len() as u64,
+            );
+        }
+
+        Ok(())
     }
 }
 
@@ -70,17 +161,52 @@ impl<N: Network> Decoder for MessageCodec<N> {
 
     fn decode(&mut self, source: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
         // Decode a frame containing bytes belonging to a message.
+        #[cfg(feature = "metrics")]
+        let initial_len = source.len();
         let bytes = match self.codec.decode(source)? {
             Some(bytes) => bytes,
             None => return Ok(None),
         };
+        #[cfg(feature = "metrics")]
+        let frame_len = initial_len - source.len();
+
+        // Decompress the message, if the connection accepts compressed frames.
+        let bytes = match self.compression.inbound {
+            true => decompress_frame(bytes, MAXIMUM_MESSAGE_SIZE)?,
+            false => bytes,
+        };
+
+        // Record the message, if the capture is enabled; this includes the messages that fail to deserialize.
+        if let Some((capture, peer_addr)) = self.capture {
+            capture.record(Direction::Inbound, CaptureProtocol::Router, peer_addr, &bytes);
+        }
 
         Self::Item::check_size(&bytes)?;
 
-        // Convert the bytes to a message, or fail if it is not valid.
+        // Convert the bytes to a message in the format of the negotiated version, or fail if it is not valid.
         let reader = bytes.reader();
-        match Message::read_le(reader) {
-            Ok(message) => Ok(Some(message)),
+        let message = match self.version {
+            Some(version) => Message::read_versioned(version, reader),
+            None => Message::read_le(reader),
+        };
+        match message {
+            // Ensure the message is supported by the negotiated version.
+            Ok(message) if !message.is_supported_by(self.gating_version()) => {
+                warn!("Received '{}', which is not supported by version {}", message.name(), self.gating_version());
+                Err(std::io::ErrorKind::InvalidData.into())
+            }
+            Ok(message) => {
+                #[cfg(feature = "metrics")]
+                {
+                    metrics::increment_counter_by_type(metrics::router::MESSAGES_RECEIVED, message.type_name(), 1);
+                    metrics::increment_counter_by_type(
+                        metrics::router::MESSAGE_BYTES_RECEIVED,
+                        message.type_name(),
+                        frame_len as u64,
+                    );
+                }
+                Ok(Some(message))
+            }
             Err(error) => {
                 warn!("Failed to deserialize a message - {}", error);
                 Err(std::io::ErrorKind::InvalidData.into())
@@ -94,7 +220,13 @@ mod tests {
     use super::*;
 
     use crate::{
+        challenge_response::prop_tests::any_challenge_response,
+        header_request::prop_tests::any_header_request,
+        inventory_announcement::prop_tests::any_inventory_announcement,
         unconfirmed_transaction::prop_tests::{any_large_unconfirmed_transaction, any_unconfirmed_transaction},
+        ChallengeResponse,
+        HeaderRequest,
+        InventoryAnnouncement,
         UnconfirmedTransaction,
     };
 
@@ -111,6 +243,104 @@ mod tests {
         assert!(codec.decode(&mut bytes).is_ok());
     }
 
+    #[proptest]
+    fn compressed_unconfirmed_transaction(
+        #[strategy(any_unconfirmed_transaction())] tx: UnconfirmedTransaction<CurrentNetwork>,
+    ) {
+        let mut bytes = BytesMut::new();
+        let compression = Compression { outbound: true, inbound: true };
+        let mut codec = MessageCodec::<CurrentNetwork>::default().with_compression(compression);
+        let message = Message::UnconfirmedTransaction(tx);
+        assert!(codec.encode(message.clone(), &mut bytes).is_ok());
+        let decoded = codec.decode(&mut bytes).unwrap().unwrap();
+        assert_eq!(decoded.to_bytes_le().unwrap(), message.to_bytes_le().unwrap());
+    }
+
+    #[test]
+    fn negotiated_version() {
+        // Ensure the version is negotiated down to the older of the two, within the supported range.
+        let version = Message::<CurrentNetwork>::VERSION;
+        let minimum_version = Message::<CurrentNetwork>::MINIMUM_VERSION;
+        assert_eq!(Message::<CurrentNetwork>::negotiate_version(version + 1), Some(version));
+        assert_eq!(Message::<CurrentNetwork>::negotiate_version(version), Some(version));
+        assert_eq!(Message::<CurrentNetwork>::negotiate_version(minimum_version), Some(minimum_version));
+        assert_eq!(Message::<CurrentNetwork>::negotiate_version(minimum_version - 1), None);
+
+        // Ensure the messages supported by the negotiated version are encoded and decoded.
+        let mut bytes = BytesMut::new();
+        let mut codec = MessageCodec::<CurrentNetwork>::default().with_version(minimum_version);
+        let message = Message::PeerRequest(crate::PeerRequest);
+        assert!(message.is_supported_by(minimum_version));
+        codec.encode(message.clone(), &mut bytes).unwrap();
+        assert_eq!(codec.decode(&mut bytes).unwrap(), Some(message));
+
+        // Ensure the messages unsupported by the negotiated version are rejected.
+        let mut codec = MessageCodec::<CurrentNetwork>::default().with_version(minimum_version - 1);
+        let message = Message::PeerRequest(crate::PeerRequest);
+        assert!(!message.is_supported_by(minimum_version - 1));
+        let error = codec.encode(message.clone(), &mut bytes).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        MessageCodec::<CurrentNetwork>::default().with_version(minimum_version).encode(message, &mut bytes).unwrap();
+        let error = codec.decode(&mut bytes).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[proptest]
+    fn challenge_response_format(#[strategy(any_challenge_response())] response: ChallengeResponse<CurrentNetwork>) {
+        let minimum_version = Message::<CurrentNetwork>::MINIMUM_VERSION;
+        let message = Message::ChallengeResponse(response);
+
+        // Ensure the response is encoded in the format of the negotiated version, without the later fields.
+        let mut bytes = BytesMut::new();
+        MessageCodec::<CurrentNetwork>::default()
+            .with_version(minimum_version)
+            .encode(message.clone(), &mut bytes)
+            .unwrap();
+        let mut other_bytes = bytes.clone();
+        let decoded =
+            MessageCodec::<CurrentNetwork>::default().with_version(minimum_version).decode(&mut bytes).unwrap();
+        let Some(Message::ChallengeResponse(decoded)) = decoded else { panic!("Expected a challenge response") };
+        assert_eq!((decoded.capabilities, decoded.version), (None, None));
+
+        // Ensure a response in the format of an older version is rejected on a newer version.
+        let mut codec = MessageCodec::<CurrentNetwork>::default();
+        assert!(codec.decode(&mut other_bytes).is_err());
+    }
+
+    #[proptest]
+    fn inventory_announcement(
+        #[strategy(any_inventory_announcement())] announcement: InventoryAnnouncement<CurrentNetwork>,
+    ) {
+        let inventory_version = Message::<CurrentNetwork>::INVENTORY_VERSION;
+        let message = Message::InventoryAnnouncement(announcement);
+
+        // Ensure the announcement is not encoded for the versions preceding inventory announcements.
+        let mut bytes = BytesMut::new();
+        let mut codec = MessageCodec::<CurrentNetwork>::default().with_version(inventory_version - 1);
+        assert!(codec.encode(message.clone(), &mut bytes).is_err());
+
+        // Ensure the announcement is encoded and decoded for the versions supporting inventory announcements.
+        let mut codec = MessageCodec::<CurrentNetwork>::default().with_version(inventory_version);
+        codec.encode(message.clone(), &mut bytes).unwrap();
+        assert_eq!(codec.decode(&mut bytes).unwrap(), Some(message));
+    }
+
+    #[proptest]
+    fn header_request(#[strategy(any_header_request())] request: HeaderRequest) {
+        let header_version = Message::<CurrentNetwork>::HEADER_VERSION;
+        let message = Message::<CurrentNetwork>::HeaderRequest(request);
+
+        // Ensure the request is not encoded for the versions preceding header requests.
+        let mut bytes = BytesMut::new();
+        let mut codec = MessageCodec::<CurrentNetwork>::default().with_version(header_version - 1);
+        assert!(codec.encode(message.clone(), &mut bytes).is_err());
+
+        // Ensure the request is encoded and decoded for the versions supporting header requests.
+        let mut codec = MessageCodec::<CurrentNetwork>::default().with_version(header_version);
+        codec.encode(message.clone(), &mut bytes).unwrap();
+        assert_eq!(codec.decode(&mut bytes).unwrap(), Some(message));
+    }
+
     #[proptest(ProptestConfig { cases : 10, ..ProptestConfig::default() })]
     fn overly_large_unconfirmed_transaction(
         #[strategy(any_large_unconfirmed_transaction())] tx: UnconfirmedTransaction<CurrentNetwork>,
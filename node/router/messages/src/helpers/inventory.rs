@@ -0,0 +1,84 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use snarkvm::prelude::{error, puzzle::SolutionID, FromBytes, Network, ToBytes};
+
+use std::{fmt, io};
+
+/// An item of the inventory of a node, which is announced to peers by its ID before its contents are sent.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum InventoryItem<N: Network> {
+    /// An unconfirmed transaction.
+    Transaction(N::TransactionID),
+    /// An unconfirmed solution.
+    Solution(SolutionID<N>),
+}
+
+impl<N: Network> InventoryItem<N> {
+    /// The maximum number of items in an inventory announcement or request.
+    pub const MAXIMUM_NUMBER_OF_ITEMS: usize = 256;
+}
+
+impl<N: Network> fmt::Display for InventoryItem<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transaction(transaction_id) => write!(f, "transaction '{transaction_id}'"),
+            Self::Solution(solution_id) => write!(f, "solution '{solution_id}'"),
+        }
+    }
+}
+
+impl<N: Network> ToBytes for InventoryItem<N> {
+    fn write_le<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        match self {
+            Self::Transaction(transaction_id) => {
+                0u8.write_le(&mut writer)?;
+                transaction_id.write_le(&mut writer)
+            }
+            Self::Solution(solution_id) => {
+                1u8.write_le(&mut writer)?;
+                solution_id.write_le(&mut writer)
+            }
+        }
+    }
+}
+
+impl<N: Network> FromBytes for InventoryItem<N> {
+    fn read_le<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        match u8::read_le(&mut reader)? {
+            0 => Ok(Self::Transaction(N::TransactionID::read_le(&mut reader)?)),
+            1 => Ok(Self::Solution(SolutionID::read_le(&mut reader)?)),
+            _ => Err(error("Invalid inventory item type")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{inventory_announcement::prop_tests::any_inventory_item, InventoryItem};
+    use snarkvm::utilities::{FromBytes, ToBytes};
+
+    use bytes::{Buf, BufMut, BytesMut};
+    use test_strategy::proptest;
+
+    type CurrentNetwork = snarkvm::prelude::MainnetV0;
+
+    #[proptest]
+    fn inventory_item_roundtrip(#[strategy(any_inventory_item())] item: InventoryItem<CurrentNetwork>) {
+        let mut bytes = BytesMut::default().writer();
+        item.write_le(&mut bytes).unwrap();
+        let decoded = InventoryItem::read_le(&mut bytes.into_inner().reader()).unwrap();
+        assert_eq!(decoded, item);
+    }
+}
@@ -18,5 +18,8 @@ pub use codec::MessageCodec;
 mod disconnect;
 pub use disconnect::DisconnectReason;
 
+mod inventory;
+pub use inventory::InventoryItem;
+
 mod node_type;
 pub use node_type::*;
@@ -26,6 +26,8 @@ pub enum NodeType {
     Prover,
     /// A validator is a full node, capable of validating blocks.
     Validator,
+    /// A light node syncs and verifies only the block headers, capable of serving verified queries.
+    Light,
 }
 
 impl NodeType {
@@ -35,6 +37,7 @@ impl NodeType {
             Self::Client => "a client node",
             Self::Prover => "a prover node",
             Self::Validator => "a validator node",
+            Self::Light => "a light node",
         }
     }
 
@@ -52,6 +55,11 @@ impl NodeType {
     pub const fn is_validator(&self) -> bool {
         matches!(self, Self::Validator)
     }
+
+    /// Returns `true` if the node type is a light node.
+    pub const fn is_light(&self) -> bool {
+        matches!(self, Self::Light)
+    }
 }
 
 impl core::fmt::Display for NodeType {
@@ -60,6 +68,7 @@ impl core::fmt::Display for NodeType {
             Self::Client => "Client",
             Self::Prover => "Prover",
             Self::Validator => "Validator",
+            Self::Light => "Light",
         })
     }
 }
@@ -76,6 +85,7 @@ impl FromBytes for NodeType {
             0 => Ok(Self::Client),
             1 => Ok(Self::Prover),
             2 => Ok(Self::Validator),
+            3 => Ok(Self::Light),
             _ => Err(error("Invalid node type")),
         }
     }
@@ -0,0 +1,109 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+use snarkvm::prelude::{FromBytes, ToBytes};
+
+use std::borrow::Cow;
+
+/// Announces the IDs of unconfirmed transactions and solutions, which the peer may then fetch
+/// with an `InventoryRequest` if it has not seen them yet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InventoryAnnouncement<N: Network> {
+    pub items: Vec<InventoryItem<N>>,
+}
+
+impl<N: Network> MessageTrait for InventoryAnnouncement<N> {
+    /// Returns the message name.
+    #[inline]
+    fn name(&self) -> Cow<'static, str> {
+        "InventoryAnnouncement".into()
+    }
+}
+
+impl<N: Network> ToBytes for InventoryAnnouncement<N> {
+    fn write_le<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        // Return error if the number of items exceeds the maximum.
+        if self.items.len() > InventoryItem::<N>::MAXIMUM_NUMBER_OF_ITEMS {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Too many items: {}", self.items.len())));
+        }
+
+        (self.items.len() as u16).write_le(&mut writer)?;
+        for item in self.items.iter() {
+            item.write_le(&mut writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<N: Network> FromBytes for InventoryAnnouncement<N> {
+    fn read_le<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let count = u16::read_le(&mut reader)? as usize;
+        // Ensure the number of items is within the maximum.
+        if count > InventoryItem::<N>::MAXIMUM_NUMBER_OF_ITEMS {
+            return Err(error(format!("Too many items: {count}")));
+        }
+        let mut items = Vec::with_capacity(count);
+        for _ in 0..count {
+            items.push(InventoryItem::read_le(&mut reader)?);
+        }
+
+        Ok(Self { items })
+    }
+}
+
+#[cfg(test)]
+pub mod prop_tests {
+    use crate::{unconfirmed_solution::prop_tests::any_solution_id, InventoryAnnouncement, InventoryItem};
+    use snarkvm::prelude::{Field, FromBytes, Network, ToBytes, Uniform};
+
+    use bytes::{Buf, BufMut, BytesMut};
+    use proptest::{
+        collection::vec,
+        prelude::{BoxedStrategy, Just, Strategy},
+        prop_oneof,
+    };
+    use test_strategy::proptest;
+
+    type CurrentNetwork = snarkvm::prelude::MainnetV0;
+
+    pub fn any_inventory_item() -> BoxedStrategy<InventoryItem<CurrentNetwork>> {
+        prop_oneof![
+            Just(0).prop_perturb(|_, mut rng| InventoryItem::Transaction(
+                <CurrentNetwork as Network>::TransactionID::from(Field::rand(&mut rng))
+            )),
+            any_solution_id().prop_map(InventoryItem::Solution),
+        ]
+        .boxed()
+    }
+
+    pub fn any_inventory_items() -> BoxedStrategy<Vec<InventoryItem<CurrentNetwork>>> {
+        vec(any_inventory_item(), 0..=InventoryItem::<CurrentNetwork>::MAXIMUM_NUMBER_OF_ITEMS).boxed()
+    }
+
+    pub fn any_inventory_announcement() -> BoxedStrategy<InventoryAnnouncement<CurrentNetwork>> {
+        any_inventory_items().prop_map(|items| InventoryAnnouncement { items }).boxed()
+    }
+
+    #[proptest]
+    fn inventory_announcement_roundtrip(
+        #[strategy(any_inventory_announcement())] announcement: InventoryAnnouncement<CurrentNetwork>,
+    ) {
+        let mut bytes = BytesMut::default().writer();
+        announcement.write_le(&mut bytes).unwrap();
+        let decoded = InventoryAnnouncement::read_le(&mut bytes.into_inner().reader()).unwrap();
+        assert_eq!(decoded, announcement);
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+use snarkvm::prelude::{FromBytes, ToBytes};
+
+use std::borrow::Cow;
+
+/// Requests the unconfirmed transactions and solutions with the given IDs, which were previously announced
+/// by the peer in an `InventoryAnnouncement`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InventoryRequest<N: Network> {
+    pub items: Vec<InventoryItem<N>>,
+}
+
+impl<N: Network> MessageTrait for InventoryRequest<N> {
+    /// Returns the message name.
+    #[inline]
+    fn name(&self) -> Cow<'static, str> {
+        "InventoryRequest".into()
+    }
+}
+
+impl<N: Network> ToBytes for InventoryRequest<N> {
+    fn write_le<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        // Return error if the number of items exceeds the maximum.
+        if self.items.len() > InventoryItem::<N>::MAXIMUM_NUMBER_OF_ITEMS {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Too many items: {}", self.items.len())));
+        }
+
+        (self.items.len() as u16).write_le(&mut writer)?;
+        for item in self.items.iter() {
+            item.write_le(&mut writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<N: Network> FromBytes for InventoryRequest<N> {
+    fn read_le<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let count = u16::read_le(&mut reader)? as usize;
+        // Ensure the number of items is within the maximum.
+        if count > InventoryItem::<N>::MAXIMUM_NUMBER_OF_ITEMS {
+            return Err(error(format!("Too many items: {count}")));
+        }
+        let mut items = Vec::with_capacity(count);
+        for _ in 0..count {
+            items.push(InventoryItem::read_le(&mut reader)?);
+        }
+
+        Ok(Self { items })
+    }
+}
+
+#[cfg(test)]
+pub mod prop_tests {
+    use crate::{inventory_announcement::prop_tests::any_inventory_items, InventoryRequest};
+    use snarkvm::utilities::{FromBytes, ToBytes};
+
+    use bytes::{Buf, BufMut, BytesMut};
+    use proptest::prelude::{BoxedStrategy, Strategy};
+    use test_strategy::proptest;
+
+    type CurrentNetwork = snarkvm::prelude::MainnetV0;
+
+    pub fn any_inventory_request() -> BoxedStrategy<InventoryRequest<CurrentNetwork>> {
+        any_inventory_items().prop_map(|items| InventoryRequest { items }).boxed()
+    }
+
+    #[proptest]
+    fn inventory_request_roundtrip(#[strategy(any_inventory_request())] request: InventoryRequest<CurrentNetwork>) {
+        let mut bytes = BytesMut::default().writer();
+        request.write_le(&mut bytes).unwrap();
+        let decoded = InventoryRequest::read_le(&mut bytes.into_inner().reader()).unwrap();
+        assert_eq!(decoded, request);
+    }
+}
@@ -35,6 +35,18 @@ pub use challenge_response::ChallengeResponse;
 mod disconnect;
 pub use disconnect::Disconnect;
 
+mod header_request;
+pub use header_request::HeaderRequest;
+
+mod header_response;
+pub use header_response::{CertifiedHeader, DataHeaders, HeaderResponse};
+
+mod inventory_announcement;
+pub use inventory_announcement::InventoryAnnouncement;
+
+mod inventory_request;
+pub use inventory_request::InventoryRequest;
+
 mod peer_request;
 pub use peer_request::PeerRequest;
 
@@ -59,7 +71,7 @@ pub use unconfirmed_solution::UnconfirmedSolution;
 mod unconfirmed_transaction;
 pub use unconfirmed_transaction::UnconfirmedTransaction;
 
-pub use snarkos_node_bft_events::DataBlocks;
+pub use snarkos_node_bft_events::{Capabilities, Compression, DataBlocks};
 
 use snarkos_node_sync_locators::BlockLocators;
 use snarkvm::prelude::{
@@ -101,6 +113,10 @@ pub enum Message<N: Network> {
     PuzzleResponse(PuzzleResponse<N>),
     UnconfirmedSolution(UnconfirmedSolution<N>),
     UnconfirmedTransaction(UnconfirmedTransaction<N>),
+    InventoryAnnouncement(InventoryAnnouncement<N>),
+    InventoryRequest(InventoryRequest<N>),
+    HeaderRequest(HeaderRequest),
+    HeaderResponse(HeaderResponse<N>),
 }
 
 impl<N: Network> From<DisconnectReason> for Message<N> {
@@ -110,8 +126,26 @@ impl<N: Network> From<DisconnectReason> for Message<N> {
 }
 
 impl<N: Network> Message<N> {
-    /// The version of the network protocol; it can be incremented in order to force users to update.
-    pub const VERSION: u32 = 16;
+    /// The first version of the network protocol that advertises capabilities in the challenge response.
+    pub const CAPABILITIES_VERSION: u32 = 17;
+    /// The first version of the network protocol that serves block headers to light nodes.
+    pub const HEADER_VERSION: u32 = 20;
+    /// The first version of the network protocol that gossips transactions and solutions by inventory announcements.
+    pub const INVENTORY_VERSION: u32 = 19;
+    /// The minimum version of the network protocol that is accepted from peers.
+    pub const MINIMUM_VERSION: u32 = 16;
+    /// The first version of the network protocol that carries the negotiated version in the challenge response.
+    pub const NEGOTIATION_VERSION: u32 = 18;
+    /// The latest version of the network protocol; peers on older versions (down to `MINIMUM_VERSION`) are still
+    /// supported, so it can be incremented without disconnecting them.
+    pub const VERSION: u32 = 20;
+
+    /// Returns the version of the network protocol to use with a peer that supports up to the given version,
+    /// or `None` if the peer's version is outside of the supported range.
+    pub fn negotiate_version(peer_version: u32) -> Option<u32> {
+        let version = peer_version.min(Self::VERSION);
+        (version >= Self::MINIMUM_VERSION).then_some(version)
+    }
 
     /// Returns the message name.
     #[inline]
@@ -130,6 +164,34 @@ impl<N: Network> Message<N> {
             Self::PuzzleResponse(message) => message.name(),
             Self::UnconfirmedSolution(message) => message.name(),
             Self::UnconfirmedTransaction(message) => message.name(),
+            Self::InventoryAnnouncement(message) => message.name(),
+            Self::InventoryRequest(message) => message.name(),
+            Self::HeaderRequest(message) => message.name(),
+            Self::HeaderResponse(message) => message.name(),
+        }
+    }
+
+    /// Returns the message type, i.e. the name of the message variant, which is independent of its contents.
+    #[inline]
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::BlockRequest(..) => "BlockRequest",
+            Self::BlockResponse(..) => "BlockResponse",
+            Self::ChallengeRequest(..) => "ChallengeRequest",
+            Self::ChallengeResponse(..) => "ChallengeResponse",
+            Self::Disconnect(..) => "Disconnect",
+            Self::PeerRequest(..) => "PeerRequest",
+            Self::PeerResponse(..) => "PeerResponse",
+            Self::Ping(..) => "Ping",
+            Self::Pong(..) => "Pong",
+            Self::PuzzleRequest(..) => "PuzzleRequest",
+            Self::PuzzleResponse(..) => "PuzzleResponse",
+            Self::UnconfirmedSolution(..) => "UnconfirmedSolution",
+            Self::UnconfirmedTransaction(..) => "UnconfirmedTransaction",
+            Self::InventoryAnnouncement(..) => "InventoryAnnouncement",
+            Self::InventoryRequest(..) => "InventoryRequest",
+            Self::HeaderRequest(..) => "HeaderRequest",
+            Self::HeaderResponse(..) => "HeaderResponse",
         }
     }
 
@@ -150,9 +212,52 @@ impl<N: Network> Message<N> {
             Self::PuzzleResponse(..) => 10,
             Self::UnconfirmedSolution(..) => 11,
             Self::UnconfirmedTransaction(..) => 12,
+            Self::InventoryAnnouncement(..) => 13,
+            Self::InventoryRequest(..) => 14,
+            Self::HeaderRequest(..) => 15,
+            Self::HeaderResponse(..) => 16,
+        }
+    }
+
+    /// Returns the first version of the network protocol that supports the message.
+    /// A message is only sent to the peers that negotiated this version or a newer one.
+    #[inline]
+    pub fn minimum_version(&self) -> u32 {
+        match self {
+            Self::BlockRequest(..)
+            | Self::BlockResponse(..)
+            | Self::ChallengeRequest(..)
+            | Self::ChallengeResponse(..)
+            | Self::Disconnect(..)
+            | Self::PeerRequest(..)
+            | Self::PeerResponse(..)
+            | Self::Ping(..)
+            | Self::Pong(..)
+            | Self::PuzzleRequest(..)
+            | Self::PuzzleResponse(..)
+            | Self::UnconfirmedSolution(..)
+            | Self::UnconfirmedTransaction(..) => Self::MINIMUM_VERSION,
+            Self::InventoryAnnouncement(..) | Self::InventoryRequest(..) => Self::INVENTORY_VERSION,
+            Self::HeaderRequest(..) | Self::HeaderResponse(..) => Self::HEADER_VERSION,
+        }
+    }
+
+    /// Returns the inventory item of the message, if it is an unconfirmed transaction or solution.
+    #[inline]
+    pub fn inventory_item(&self) -> Option<InventoryItem<N>> {
+        match self {
+            Self::UnconfirmedSolution(message) => Some(InventoryItem::Solution(message.solution_id)),
+            Self::UnconfirmedTransaction(message) => Some(InventoryItem::Transaction(message.transaction_id)),
+            _ => None,
         }
     }
 
+    /// Returns `true` if the message is supported by the given version of the network protocol.
+    #[inline]
+    pub fn is_supported_by(&self, version: u32) -> bool {
+        version >= self.minimum_version()
+    }
+
     /// Checks the message byte length. To be used before deserialization.
     pub fn check_size(bytes: &[u8]) -> io::Result<()> {
         // Store the length to be checked against the max message size for each variant.
@@ -176,15 +281,29 @@ impl<N: Network> Message<N> {
     }
 }
 
-impl<N: Network> ToBytes for Message<N> {
-    fn write_le<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+impl<N: Network> Message<N> {
+    /// Writes the message in the format of the given negotiated version of the network protocol.
+    pub fn write_versioned<W: io::Write>(&self, version: u32, writer: W) -> io::Result<()> {
+        self.write_with(Some(version), writer)
+    }
+
+    /// Reads a message in the format of the given negotiated version of the network protocol.
+    pub fn read_versioned<R: io::Read>(version: u32, reader: R) -> io::Result<Self> {
+        Self::read_with(Some(version), reader)
+    }
+
+    /// Writes the message in the format of the given negotiated version, if it is known.
+    fn write_with<W: io::Write>(&self, version: Option<u32>, mut writer: W) -> io::Result<()> {
         self.id().write_le(&mut writer)?;
 
         match self {
             Self::BlockRequest(message) => message.write_le(writer),
             Self::BlockResponse(message) => message.write_le(writer),
             Self::ChallengeRequest(message) => message.write_le(writer),
-            Self::ChallengeResponse(message) => message.write_le(writer),
+            Self::ChallengeResponse(message) => match version {
+                Some(version) => message.write_versioned(version, writer),
+                None => message.write_le(writer),
+            },
             Self::Disconnect(message) => message.write_le(writer),
             Self::PeerRequest(message) => message.write_le(writer),
             Self::PeerResponse(message) => message.write_le(writer),
@@ -194,12 +313,15 @@ impl<N: Network> ToBytes for Message<N> {
             Self::PuzzleResponse(message) => message.write_le(writer),
             Self::UnconfirmedSolution(message) => message.write_le(writer),
             Self::UnconfirmedTransaction(message) => message.write_le(writer),
+            Self::InventoryAnnouncement(message) => message.write_le(writer),
+            Self::InventoryRequest(message) => message.write_le(writer),
+            Self::HeaderRequest(message) => message.write_le(writer),
+            Self::HeaderResponse(message) => message.write_le(writer),
         }
     }
-}
 
-impl<N: Network> FromBytes for Message<N> {
-    fn read_le<R: io::Read>(mut reader: R) -> io::Result<Self> {
+    /// Reads a message in the format of the given negotiated version, if it is known.
+    fn read_with<R: io::Read>(version: Option<u32>, mut reader: R) -> io::Result<Self> {
         // Read the event ID.
         let mut id_bytes = [0u8; 2];
         reader.read_exact(&mut id_bytes)?;
@@ -210,7 +332,10 @@ impl<N: Network> FromBytes for Message<N> {
             0 => Self::BlockRequest(BlockRequest::read_le(&mut reader)?),
             1 => Self::BlockResponse(BlockResponse::read_le(&mut reader)?),
             2 => Self::ChallengeRequest(ChallengeRequest::read_le(&mut reader)?),
-            3 => Self::ChallengeResponse(ChallengeResponse::read_le(&mut reader)?),
+            3 => Self::ChallengeResponse(match version {
+                Some(version) => ChallengeResponse::read_versioned(version, &mut reader)?,
+                None => ChallengeResponse::read_le(&mut reader)?,
+            }),
             4 => Self::Disconnect(Disconnect::read_le(&mut reader)?),
             5 => Self::PeerRequest(PeerRequest::read_le(&mut reader)?),
             6 => Self::PeerResponse(PeerResponse::read_le(&mut reader)?),
@@ -220,7 +345,11 @@ impl<N: Network> FromBytes for Message<N> {
             10 => Self::PuzzleResponse(PuzzleResponse::read_le(&mut reader)?),
             11 => Self::UnconfirmedSolution(UnconfirmedSolution::read_le(&mut reader)?),
             12 => Self::UnconfirmedTransaction(UnconfirmedTransaction::read_le(&mut reader)?),
-            13.. => return Err(error("Unknown message ID {id}")),
+            13 => Self::InventoryAnnouncement(InventoryAnnouncement::read_le(&mut reader)?),
+            14 => Self::InventoryRequest(InventoryRequest::read_le(&mut reader)?),
+            15 => Self::HeaderRequest(HeaderRequest::read_le(&mut reader)?),
+            16 => Self::HeaderResponse(HeaderResponse::read_le(&mut reader)?),
+            17.. => return Err(error("Unknown message ID {id}")),
         };
 
         // Ensure that there are no "dangling" bytes.
@@ -231,3 +360,15 @@ impl<N: Network> FromBytes for Message<N> {
         Ok(message)
     }
 }
+
+impl<N: Network> ToBytes for Message<N> {
+    fn write_le<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        self.write_with(None, writer)
+    }
+}
+
+impl<N: Network> FromBytes for Message<N> {
+    fn read_le<R: io::Read>(reader: R) -> io::Result<Self> {
+        Self::read_with(None, reader)
+    }
+}
@@ -13,12 +13,20 @@
 // limitations under the License.
 
 use crate::{
-    messages::{ChallengeRequest, ChallengeResponse, DisconnectReason, Message, MessageCodec, MessageTrait},
+    messages::{
+        Capabilities,
+        ChallengeRequest,
+        ChallengeResponse,
+        DisconnectReason,
+        Message,
+        MessageCodec,
+        MessageTrait,
+    },
     NodeType,
     Peer,
     Router,
 };
-use snarkos_node_tcp::{ConnectionSide, Tcp, P2P};
+use snarkos_node_tcp::{BoxedStream, ConnectionSide, Tcp, P2P};
 use snarkvm::{
     ledger::narwhal::Data,
     prelude::{block::Header, error, Address, Field, Network},
@@ -28,7 +36,6 @@ use anyhow::{bail, Result};
 use futures::SinkExt;
 use rand::{rngs::OsRng, Rng};
 use std::{io, net::SocketAddr};
-use tokio::net::TcpStream;
 use tokio_stream::StreamExt;
 use tokio_util::codec::Framed;
 
@@ -72,7 +79,7 @@ macro_rules! expect_message {
 
 /// Send the given message to the peer.
 async fn send<N: Network>(
-    framed: &mut Framed<&mut TcpStream, MessageCodec<N>>,
+    framed: &mut Framed<&mut BoxedStream, MessageCodec<N>>,
     peer_addr: SocketAddr,
     message: Message<N>,
 ) -> io::Result<()> {
@@ -85,11 +92,11 @@ impl<N: Network> Router<N> {
     pub async fn handshake<'a>(
         &'a self,
         peer_addr: SocketAddr,
-        stream: &'a mut TcpStream,
+        stream: &'a mut BoxedStream,
         peer_side: ConnectionSide,
         genesis_header: Header<N>,
         restrictions_id: Field<N>,
-    ) -> io::Result<(SocketAddr, Framed<&mut TcpStream, MessageCodec<N>>)> {
+    ) -> io::Result<(SocketAddr, Framed<&mut BoxedStream, MessageCodec<N>>)> {
         // If this is an inbound connection, we log it, but don't know the listening address yet.
         // Otherwise, we can immediately register the listening address.
         let mut peer_ip = if peer_side == ConnectionSide::Initiator {
@@ -125,14 +132,14 @@ impl<N: Network> Router<N> {
         &'a self,
         peer_addr: SocketAddr,
         peer_ip: &mut Option<SocketAddr>,
-        stream: &'a mut TcpStream,
+        stream: &'a mut BoxedStream,
         genesis_header: Header<N>,
         restrictions_id: Field<N>,
-    ) -> io::Result<(SocketAddr, Framed<&mut TcpStream, MessageCodec<N>>)> {
+    ) -> io::Result<(SocketAddr, Framed<&mut BoxedStream, MessageCodec<N>>)> {
         // This value is immediately guaranteed to be present, so it can be unwrapped.
         let peer_ip = peer_ip.unwrap();
         // Construct the stream.
-        let mut framed = Framed::new(stream, MessageCodec::<N>::handshake());
+        let mut framed = Framed::new(stream, MessageCodec::<N>::handshake().with_capture(peer_addr));
 
         // Initialize an RNG.
         let rng = &mut OsRng;
@@ -142,7 +149,7 @@ impl<N: Network> Router<N> {
         // Sample a random nonce.
         let our_nonce = rng.gen();
         // Send a challenge request to the peer.
-        let our_request = ChallengeRequest::new(self.local_ip().port(), self.node_type, self.address(), our_nonce);
+        let our_request = ChallengeRequest::new(self.advertised_port(), self.node_type, self.address(), our_nonce);
         send(&mut framed, peer_addr, Message::ChallengeRequest(our_request)).await?;
 
         /* Step 2: Receive the peer's challenge response followed by the challenge request. */
@@ -152,6 +159,19 @@ impl<N: Network> Router<N> {
         // Listen for the challenge request message.
         let peer_request = expect_message!(Message::ChallengeRequest, framed, peer_addr);
 
+        // Verify the challenge request. If a disconnect reason was returned, send the disconnect message and abort.
+        if let Some(reason) = self.verify_challenge_request(peer_addr, &peer_request) {
+            self.peer_store.record_disconnect(peer_ip, reason);
+            send(&mut framed, peer_addr, reason.into()).await?;
+            return Err(error(format!("Dropped '{peer_addr}' for reason: {reason:?}")));
+        }
+        // Negotiate the version, which determines the format of the remaining messages.
+        // Note: The peer response was read before the version was known, and its format is verified below.
+        let version = Message::<N>::negotiate_version(peer_request.version).unwrap_or(Message::<N>::MINIMUM_VERSION);
+        framed.codec_mut().set_version(version);
+
+        // Retrieve the capabilities advertised by the peer.
+        let peer_capabilities = peer_response.capabilities.unwrap_or_default();
         // Verify the challenge response. If a disconnect reason was returned, send the disconnect message and abort.
         if let Some(reason) = self
             .verify_challenge_response(
@@ -162,23 +182,26 @@ impl<N: Network> Router<N> {
                 genesis_header,
                 restrictions_id,
                 our_nonce,
+                version,
             )
             .await
         {
-            send(&mut framed, peer_addr, reason.into()).await?;
-            return Err(error(format!("Dropped '{peer_addr}' for reason: {reason:?}")));
-        }
-        // Verify the challenge request. If a disconnect reason was returned, send the disconnect message and abort.
-        if let Some(reason) = self.verify_challenge_request(peer_addr, &peer_request) {
+            self.peer_store.record_disconnect(peer_ip, reason);
             send(&mut framed, peer_addr, reason.into()).await?;
             return Err(error(format!("Dropped '{peer_addr}' for reason: {reason:?}")));
         }
         /* Step 3: Send the challenge response. */
 
+        // Sign the counterparty nonce, along with the negotiated version and our capabilities (if supported).
         let response_nonce: u64 = rng.gen();
-        let data = [peer_request.nonce.to_le_bytes(), response_nonce.to_le_bytes()].concat();
-        // Sign the counterparty nonce.
-        let Ok(our_signature) = self.account.sign_bytes(&data, rng) else {
+        let our_capabilities = Self::response_capabilities(version);
+        let request = ChallengeResponse::<N>::signing_request(
+            peer_request.nonce,
+            response_nonce,
+            our_capabilities,
+            Self::response_version(version),
+        );
+        let Ok(our_signature) = self.account.sign_request(&request, rng) else {
             return Err(error(format!("Failed to sign the challenge request nonce from '{peer_addr}'")));
         };
         // Send the challenge response.
@@ -187,11 +210,13 @@ impl<N: Network> Router<N> {
             restrictions_id,
             signature: Data::Object(our_signature),
             nonce: response_nonce,
+            capabilities: our_capabilities,
+            version: Self::response_version(version),
         };
         send(&mut framed, peer_addr, Message::ChallengeResponse(our_response)).await?;
 
-        // Add the peer to the router.
-        self.insert_connected_peer(Peer::new(peer_ip, &peer_request), peer_addr);
+        // Add the peer to the router, with the negotiated version.
+        self.insert_connected_peer(Peer::new(peer_ip, &peer_request, version, peer_capabilities, true), peer_addr);
 
         Ok((peer_ip, framed))
     }
@@ -201,20 +226,24 @@ impl<N: Network> Router<N> {
         &'a self,
         peer_addr: SocketAddr,
         peer_ip: &mut Option<SocketAddr>,
-        stream: &'a mut TcpStream,
+        stream: &'a mut BoxedStream,
         genesis_header: Header<N>,
         restrictions_id: Field<N>,
-    ) -> io::Result<(SocketAddr, Framed<&mut TcpStream, MessageCodec<N>>)> {
+    ) -> io::Result<(SocketAddr, Framed<&mut BoxedStream, MessageCodec<N>>)> {
         // Construct the stream.
-        let mut framed = Framed::new(stream, MessageCodec::<N>::handshake());
+        let mut framed = Framed::new(stream, MessageCodec::<N>::handshake().with_capture(peer_addr));
 
         /* Step 1: Receive the challenge request. */
 
         // Listen for the challenge request message.
         let peer_request = expect_message!(Message::ChallengeRequest, framed, peer_addr);
 
-        // Obtain the peer's listening address.
-        *peer_ip = Some(SocketAddr::new(peer_addr.ip(), peer_request.listener_port));
+        // Obtain the peer's listening address; a peer that does not advertise a listener (e.g. as it connects through
+        // a proxy) is identified by the address of the connection instead.
+        *peer_ip = Some(match peer_request.listener_port {
+            0 => peer_addr,
+            listener_port => SocketAddr::new(peer_addr.ip(), listener_port),
+        });
         let peer_ip = peer_ip.unwrap();
 
         // Knowing the peer's listening address, ensure it is allowed to connect.
@@ -223,18 +252,29 @@ impl<N: Network> Router<N> {
         }
         // Verify the challenge request. If a disconnect reason was returned, send the disconnect message and abort.
         if let Some(reason) = self.verify_challenge_request(peer_addr, &peer_request) {
+            self.peer_store.record_disconnect(peer_ip, reason);
             send(&mut framed, peer_addr, reason.into()).await?;
             return Err(error(format!("Dropped '{peer_addr}' for reason: {reason:?}")));
         }
+        // Negotiate the version, which determines the format of the remaining messages.
+        let version = Message::<N>::negotiate_version(peer_request.version).unwrap_or(Message::<N>::MINIMUM_VERSION);
+        framed.codec_mut().set_version(version);
+
         /* Step 2: Send the challenge response followed by own challenge request. */
 
         // Initialize an RNG.
         let rng = &mut OsRng;
 
-        // Sign the counterparty nonce.
+        // Sign the counterparty nonce, along with the negotiated version and our capabilities (if supported).
         let response_nonce: u64 = rng.gen();
-        let data = [peer_request.nonce.to_le_bytes(), response_nonce.to_le_bytes()].concat();
-        let Ok(our_signature) = self.account.sign_bytes(&data, rng) else {
+        let our_capabilities = Self::response_capabilities(version);
+        let request = ChallengeResponse::<N>::signing_request(
+            peer_request.nonce,
+            response_nonce,
+            our_capabilities,
+            Self::response_version(version),
+        );
+        let Ok(our_signature) = self.account.sign_request(&request, rng) else {
             return Err(error(format!("Failed to sign the challenge request nonce from '{peer_addr}'")));
         };
         // Send the challenge response.
@@ -243,19 +283,23 @@ impl<N: Network> Router<N> {
             restrictions_id,
             signature: Data::Object(our_signature),
             nonce: response_nonce,
+            capabilities: our_capabilities,
+            version: Self::response_version(version),
         };
         send(&mut framed, peer_addr, Message::ChallengeResponse(our_response)).await?;
 
         // Sample a random nonce.
         let our_nonce = rng.gen();
         // Send the challenge request.
-        let our_request = ChallengeRequest::new(self.local_ip().port(), self.node_type, self.address(), our_nonce);
+        let our_request = ChallengeRequest::new(self.advertised_port(), self.node_type, self.address(), our_nonce);
         send(&mut framed, peer_addr, Message::ChallengeRequest(our_request)).await?;
 
         /* Step 3: Receive the challenge response. */
 
         // Listen for the challenge response message.
         let peer_response = expect_message!(Message::ChallengeResponse, framed, peer_addr);
+        // Retrieve the capabilities advertised by the peer.
+        let peer_capabilities = peer_response.capabilities.unwrap_or_default();
         // Verify the challenge response. If a disconnect reason was returned, send the disconnect message and abort.
         if let Some(reason) = self
             .verify_challenge_response(
@@ -266,14 +310,16 @@ impl<N: Network> Router<N> {
                 genesis_header,
                 restrictions_id,
                 our_nonce,
+                version,
             )
             .await
         {
+            self.peer_store.record_disconnect(peer_ip, reason);
             send(&mut framed, peer_addr, reason.into()).await?;
             return Err(error(format!("Dropped '{peer_addr}' for reason: {reason:?}")));
         }
-        // Add the peer to the router.
-        self.insert_connected_peer(Peer::new(peer_ip, &peer_request), peer_addr);
+        // Add the peer to the router, with the negotiated version.
+        self.insert_connected_peer(Peer::new(peer_ip, &peer_request, version, peer_capabilities, false), peer_addr);
 
         Ok((peer_ip, framed))
     }
@@ -292,6 +338,15 @@ impl<N: Network> Router<N> {
         if self.is_connected(&peer_ip) {
             bail!("Dropping connection request from '{peer_ip}' (already connected)")
         }
+        // Ensure the subnet of the peer is not full.
+        if self.is_subnet_full(&peer_ip) {
+            bail!("Dropping connection request from '{peer_ip}' (too many peers from the same subnet)")
+        }
+        // Ensure the inbound connections do not take the slots reserved for outbound connections.
+        let max_inbound_peers = self.max_connected_peers().saturating_sub(self.number_of_reserved_outbound_peers());
+        if !self.is_trusted(&peer_ip) && self.number_of_connected_inbound_peers() >= max_inbound_peers {
+            bail!("Dropping connection request from '{peer_ip}' (maximum inbound peers reached)")
+        }
         // Only allow trusted peers to connect if allow_external_peers is set
         if !self.allow_external_peers() && !self.is_trusted(&peer_ip) {
             bail!("Dropping connection request from '{peer_ip}' (untrusted)")
@@ -323,8 +378,8 @@ impl<N: Network> Router<N> {
         // Retrieve the components of the challenge request.
         let &ChallengeRequest { version, listener_port: _, node_type: _, address: _, nonce: _ } = message;
 
-        // Ensure the message protocol version is not outdated.
-        if version < Message::<N>::VERSION {
+        // Ensure the message protocol version is within the supported range.
+        if Message::<N>::negotiate_version(version).is_none() {
             warn!("Dropping '{peer_addr}' on version {version} (outdated)");
             return Some(DisconnectReason::OutdatedClientVersion);
         }
@@ -342,17 +397,36 @@ impl<N: Network> Router<N> {
         expected_genesis_header: Header<N>,
         expected_restrictions_id: Field<N>,
         expected_nonce: u64,
+        negotiated_version: u32,
     ) -> Option<DisconnectReason> {
         // Retrieve the components of the challenge response.
-        let ChallengeResponse { genesis_header, restrictions_id, signature, nonce } = response;
+        let ChallengeResponse { genesis_header, restrictions_id, signature, nonce, capabilities, version } = response;
+
+        // Verify the challenge response is in the format of the negotiated version.
+        if capabilities.is_some() != Self::response_capabilities(negotiated_version).is_some() {
+            warn!("Handshake with '{peer_addr}' failed (malformed response for version {negotiated_version})");
+            return Some(DisconnectReason::InvalidChallengeResponse);
+        }
+        // Verify the peer negotiated the same version.
+        let expected_version = Self::response_version(negotiated_version);
+        if version != expected_version {
+            warn!(
+                "Handshake with '{peer_addr}' failed (negotiated version {version:?}, expected {expected_version:?})"
+            );
+            return Some(DisconnectReason::InvalidChallengeResponse);
+        }
 
         // Verify the challenge response, by checking that the block header matches.
         if genesis_header != expected_genesis_header {
             warn!("Handshake with '{peer_addr}' failed (incorrect block header)");
             return Some(DisconnectReason::InvalidChallengeResponse);
         }
-        // Verify the restrictions ID.
-        if !peer_node_type.is_prover() && !self.node_type.is_prover() && restrictions_id != expected_restrictions_id {
+        // Verify the restrictions ID (provers and light nodes do not maintain a ledger to derive it from).
+        let is_ledgerless = |node_type: NodeType| node_type.is_prover() || node_type.is_light();
+        if !is_ledgerless(peer_node_type)
+            && !is_ledgerless(self.node_type)
+            && restrictions_id != expected_restrictions_id
+        {
             warn!("Handshake with '{peer_addr}' failed (incorrect restrictions ID)");
             return Some(DisconnectReason::InvalidChallengeResponse);
         }
@@ -361,11 +435,23 @@ impl<N: Network> Router<N> {
             warn!("Handshake with '{peer_addr}' failed (cannot deserialize the signature)");
             return Some(DisconnectReason::InvalidChallengeResponse);
         };
-        // Verify the signature.
-        if !signature.verify_bytes(&peer_address, &[expected_nonce.to_le_bytes(), nonce.to_le_bytes()].concat()) {
+        // Verify the signature, which binds the negotiated version and the capabilities of the peer (if supported).
+        let request = ChallengeResponse::<N>::signing_request(expected_nonce, nonce, capabilities, version);
+        if !request.verify(&peer_address, &signature) {
             warn!("Handshake with '{peer_addr}' failed (invalid signature)");
             return Some(DisconnectReason::InvalidChallengeResponse);
         }
         None
     }
+
+    /// Returns the capabilities to advertise in the challenge response on the given negotiated version,
+    /// if the version carries them.
+    pub(crate) fn response_capabilities(version: u32) -> Option<Capabilities> {
+        (version >= Message::<N>::CAPABILITIES_VERSION).then(Capabilities::supported)
+    }
+
+    /// Returns the negotiated version to include in the challenge response, if the version carries it.
+    fn response_version(version: u32) -> Option<u32> {
+        (version >= Message::<N>::NEGOTIATION_VERSION).then_some(version)
+    }
 }
@@ -14,13 +14,18 @@
 
 use crate::{
     messages::{DisconnectReason, Message, PeerRequest},
+    subnet,
     Outbound,
     Router,
 };
 use snarkvm::prelude::Network;
 
 use colored::Colorize;
-use rand::{prelude::IteratorRandom, rngs::OsRng};
+use rand::{
+    prelude::{IteratorRandom, SliceRandom},
+    rngs::OsRng,
+};
+use std::{collections::HashSet, net::SocketAddr};
 
 /// A helper function to compute the maximum of two numbers.
 /// See Rust issue 92391: https://github.com/rust-lang/rust/issues/92391.
@@ -42,6 +47,8 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
     const MAXIMUM_NUMBER_OF_PEERS: usize = 21;
     /// The maximum number of provers to maintain connections with.
     const MAXIMUM_NUMBER_OF_PROVERS: usize = Self::MAXIMUM_NUMBER_OF_PEERS / 4;
+    /// The fraction (as a divisor) of the least reputable connected peers, among which a peer is evicted periodically.
+    const EVICTION_CANDIDATES_DIVISOR: usize = 3;
 
     /// Handles the heartbeat request.
     fn heartbeat(&self) {
@@ -50,16 +57,22 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
 
         // Remove any stale connected peers.
         self.remove_stale_connected_peers();
-        // Remove the oldest connected peer.
-        self.remove_oldest_connected_peer();
+        // Remove the least reputable connected peer.
+        self.remove_least_reputable_connected_peer();
         // Keep the number of connected peers within the allowed range.
         self.handle_connected_peers();
+        // Keep the reserved outbound connections filled with peers from diverse subnets.
+        self.handle_diverse_peers();
         // Keep the bootstrap peers within the allowed range.
         self.handle_bootstrap_peers();
         // Keep the trusted peers connected.
         self.handle_trusted_peers();
         // Keep the puzzle request up to date.
         self.handle_puzzle_request();
+        // Request the inventory items whose requests timed out from their next announcers.
+        self.send_next_inventory_requests();
+        // Persist the peer store.
+        self.handle_peer_store();
     }
 
     /// TODO (howardwu): Consider checking minimum number of validators, to exclude clients and provers.
@@ -99,9 +112,10 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
         }
     }
 
-    /// This function removes the oldest connected peer, to keep the connections fresh.
+    /// This function removes one of the least reputable connected peers, to keep the connections fresh.
+    /// The peer is chosen at random among the least reputable ones, so that the eviction is not predictable.
     /// This function only triggers if the router is above the minimum number of connected peers.
-    fn remove_oldest_connected_peer(&self) {
+    fn remove_least_reputable_connected_peer(&self) {
         // Skip if the router is at or below the minimum number of connected peers.
         if self.router().number_of_connected_peers() <= Self::MINIMUM_NUMBER_OF_PEERS {
             return;
@@ -117,23 +131,29 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
         // Retrieve the bootstrap peers.
         let bootstrap = self.router().bootstrap_peers();
 
-        // Find the oldest connected peer, that is neither trusted nor a bootstrap peer.
-        let oldest_peer = self
+        // Retrieve the connected peers that are neither trusted nor bootstrap peers.
+        let mut candidates = self
             .router()
             .get_connected_peers()
             .iter()
             .filter(|peer| !trusted.contains(&peer.ip()) && !bootstrap.contains(&peer.ip()))
             .filter(|peer| !self.router().cache.contains_inbound_block_request(&peer.ip())) // Skip if the peer is syncing.
             .filter(|peer| self.is_block_synced() || self.router().cache.num_outbound_block_requests(&peer.ip()) == 0) // Skip if you are syncing from this peer.
-            .min_by_key(|peer| peer.last_seen())
-            .map(|peer| peer.ip());
+            .map(|peer| peer.ip())
+            .collect::<Vec<_>>();
+        // Sort the candidates by reputation, from the least reputable.
+        self.router().peer_store().sort_by_reputation(&mut candidates);
+        candidates.reverse();
+        // Choose a random peer among the least reputable candidates.
+        let num_least_reputable = candidates.len().div_ceil(Self::EVICTION_CANDIDATES_DIVISOR);
+        let least_reputable_peer = candidates[..num_least_reputable].choose(&mut OsRng).copied();
 
-        // Disconnect from the oldest connected peer, if one exists.
-        if let Some(oldest) = oldest_peer {
-            info!("Disconnecting from '{oldest}' (periodic refresh of peers)");
-            let _ = self.send(oldest, Message::Disconnect(DisconnectReason::PeerRefresh.into()));
+        // Disconnect from the chosen peer, if one exists.
+        if let Some(peer_ip) = least_reputable_peer {
+            info!("Disconnecting from '{peer_ip}' (periodic refresh of peers)");
+            let _ = self.send(peer_ip, Message::Disconnect(DisconnectReason::PeerRefresh.into()));
             // Disconnect from this peer.
-            self.router().disconnect(oldest);
+            self.router().disconnect(peer_ip);
         }
     }
 
@@ -163,22 +183,18 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
             let trusted = self.router().trusted_peers();
             // Retrieve the bootstrap peers.
             let bootstrap = self.router().bootstrap_peers();
-
-            // Initialize an RNG.
-            let rng = &mut OsRng;
-
             // Determine the provers to disconnect from.
-            let prover_ips_to_disconnect = self
+            let prover_ips = self
                 .router()
                 .connected_provers()
                 .into_iter()
                 .filter(|peer_ip| !trusted.contains(peer_ip) && !bootstrap.contains(peer_ip))
-                .choose_multiple(rng, num_surplus_provers);
+                .collect::<Vec<_>>();
+            let prover_ips_to_disconnect = self.router().select_peers_to_evict(prover_ips, num_surplus_provers);
 
             // TODO (howardwu): As a validator, prioritize disconnecting from clients.
-            //  Remove RNG, pick the `n` oldest nodes.
             // Determine the clients and validators to disconnect from.
-            let peer_ips_to_disconnect = self
+            let peer_ips = self
                 .router()
                 .get_connected_peers()
                 .into_iter()
@@ -190,7 +206,8 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
                         None
                     }
                 })
-                .choose_multiple(rng, num_surplus_clients_validators);
+                .collect::<Vec<_>>();
+            let peer_ips_to_disconnect = self.router().select_peers_to_evict(peer_ips, num_surplus_clients_validators);
 
             // Proceed to send disconnect requests to these peers.
             for peer_ip in peer_ips_to_disconnect.into_iter().chain(prover_ips_to_disconnect) {
@@ -219,8 +236,12 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
             // Initialize an RNG.
             let rng = &mut OsRng;
 
-            // Attempt to connect to more peers.
-            for peer_ip in self.router().candidate_peers().into_iter().choose_multiple(rng, num_deficient) {
+            // Shuffle the candidate peers, so that the peers with equal reputation are dialed at random.
+            let mut candidate_peers = self.router().candidate_peers().into_iter().collect::<Vec<_>>();
+            candidate_peers.shuffle(rng);
+            self.router().peer_store().sort_by_reputation(&mut candidate_peers);
+            // Attempt to connect to more peers, starting with the most reputable.
+            for peer_ip in candidate_peers.into_iter().take(num_deficient) {
                 self.router().connect(peer_ip);
             }
             if self.router().allow_external_peers() {
@@ -232,8 +253,56 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
         }
     }
 
-    /// This function keeps the number of bootstrap peers within the allowed range.
+    /// This function keeps the connection slots reserved for outbound connections filled,
+    /// by connecting to the most reputable peers of the peer store from subnets the node is not connected to.
+    fn handle_diverse_peers(&self) {
+        // Skip if the node is not requesting peers.
+        if !self.router().allow_external_peers() {
+            return;
+        }
+        // Compute the number of missing outbound peers.
+        let num_missing = self
+            .router()
+            .number_of_reserved_outbound_peers()
+            .saturating_sub(self.router().number_of_connected_outbound_peers());
+        // Skip if there are no missing outbound peers, or the node is at the maximum number of connected peers.
+        if num_missing == 0 || self.router().number_of_connected_peers() >= self.router().max_connected_peers() {
+            return;
+        }
+
+        // Retrieve the subnets of the connected peers.
+        let mut subnets =
+            self.router().connected_peers().iter().map(|peer_ip| subnet(peer_ip.ip())).collect::<HashSet<_>>();
+        // Select the most reputable peer of each subnet the node is not connected to.
+        let diverse_peers = self
+            .router()
+            .peer_store()
+            .reputable_peers()
+            .into_iter()
+            .filter(|peer_ip| {
+                !self.router().is_connected(peer_ip)
+                    && !self.router().is_connecting(peer_ip)
+                    && !self.router().is_restricted(peer_ip)
+            })
+            .filter(|peer_ip| subnets.insert(subnet(peer_ip.ip())))
+            .take(num_missing)
+            .collect::<Vec<_>>();
+
+        // Attempt to connect to the diverse peers.
+        for peer_ip in diverse_peers {
+            debug!("Connecting to '{peer_ip}' (diverse outbound peer)");
+            self.router().connect(peer_ip);
+        }
+    }
+
+    /// This function keeps the number of bootstrap peers within the allowed range,
+    /// and adds the peers discovered through the DNS seeds to the candidate peers.
     fn handle_bootstrap_peers(&self) {
+        // Add the peers discovered through the DNS seeds to the candidate peers.
+        let seed_peers = self.router().take_seed_peers();
+        if self.router().allow_external_peers() && !seed_peers.is_empty() {
+            self.router().insert_candidate_peers(&seed_peers);
+        }
         // Split the bootstrap peers into connected and candidate lists.
         let mut connected_bootstrap = Vec::new();
         let mut candidate_bootstrap = Vec::new();
@@ -283,4 +352,15 @@ pub trait Heartbeat<N: Network>: Outbound<N> {
     fn handle_puzzle_request(&self) {
         // No-op
     }
+
+    /// This function persists the peer store, if it changed.
+    fn handle_peer_store(&self) {
+        // Skip the store if nothing changed since the last one.
+        if !self.router().peer_store().is_dirty() {
+            return;
+        }
+        // Store the peer store in the background, as it blocks on disk I/O.
+        let router = self.router().clone();
+        tokio::spawn(async move { router.store_peer_store().await });
+    }
 }
@@ -0,0 +1,272 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{bail, Result};
+use parking_lot::{Mutex, RwLock};
+use std::{
+    io,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+/// The port of the peers discovered through a DNS seed, if the seed does not specify one.
+pub const DEFAULT_SEED_PORT: u16 = 4130;
+
+/// A resolver of host names, which is replaceable by a local stand-in for testing.
+#[async_trait]
+pub trait DnsResolver: Send + Sync {
+    /// Returns the IP addresses (from the A and AAAA records) of the given host name.
+    async fn lookup(&self, host: &str) -> io::Result<Vec<IpAddr>>;
+}
+
+/// The resolver of host names of the operating system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemDnsResolver;
+
+#[async_trait]
+impl DnsResolver for SystemDnsResolver {
+    /// Returns the IP addresses (from the A and AAAA records) of the given host name.
+    async fn lookup(&self, host: &str) -> io::Result<Vec<IpAddr>> {
+        Ok(tokio::net::lookup_host((host, 0)).await?.map(|addr| addr.ip()).collect())
+    }
+}
+
+/// The configured bootstrap peers and DNS seeds of the node.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BootstrapConfig {
+    /// The bootstrap peers, as `host:port`, where the host is an IP address or a host name.
+    peers: Vec<String>,
+    /// The DNS seeds, as `host` or `host:port`, whose A and AAAA records are candidate peers.
+    dns_seeds: Vec<String>,
+}
+
+impl BootstrapConfig {
+    /// Initializes a new bootstrap configuration, ensuring the given peers and DNS seeds are well-formed.
+    pub fn new(peers: Vec<String>, dns_seeds: Vec<String>) -> Result<Self> {
+        for peer in &peers {
+            if let Err(error) = parse_host_port(peer, None) {
+                bail!("The bootstrap peer '{peer}' is malformed - {error}");
+            }
+        }
+        for seed in &dns_seeds {
+            if let Err(error) = parse_host_port(seed, Some(DEFAULT_SEED_PORT)) {
+                bail!("The DNS seed '{seed}' is malformed - {error}");
+            }
+        }
+        Ok(Self { peers, dns_seeds })
+    }
+
+    /// Returns the configured bootstrap peers.
+    pub fn peers(&self) -> &[String] {
+        &self.peers
+    }
+
+    /// Returns the configured DNS seeds.
+    pub fn dns_seeds(&self) -> &[String] {
+        &self.dns_seeds
+    }
+
+    /// Returns `true` if there are no configured bootstrap peers and DNS seeds.
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty() && self.dns_seeds.is_empty()
+    }
+}
+
+/// Parses the given `host:port` into the host and the port, using the given default port if the port is missing.
+/// An IPv6 host must be enclosed in brackets when the port is specified.
+fn parse_host_port(entry: &str, default_port: Option<u16>) -> Result<(String, u16)> {
+    // Parse the entry as a socket address.
+    if let Ok(addr) = entry.parse::<SocketAddr>() {
+        return Ok((addr.ip().to_string(), addr.port()));
+    }
+    // Parse the entry as an IP address without a port.
+    if let (Ok(ip), Some(port)) = (entry.parse::<IpAddr>(), default_port) {
+        return Ok((ip.to_string(), port));
+    }
+    // Parse the entry as a host name, with or without a port.
+    let (host, port) = match (entry.rsplit_once(':'), default_port) {
+        (Some((host, port)), _) => match port.parse::<u16>() {
+            Ok(port) => (host, port),
+            Err(_) => bail!("invalid port '{port}'"),
+        },
+        (None, Some(port)) => (entry, port),
+        (None, None) => bail!("missing port"),
+    };
+    // Ensure the host name is well-formed.
+    let is_valid_label = |label: &str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+    };
+    if host.len() > 253 || !host.trim_end_matches('.').split('.').all(is_valid_label) {
+        bail!("invalid host name '{host}'");
+    }
+    Ok((host.to_string(), port))
+}
+
+/// The bootstrap peers and the peers discovered through the DNS seeds, which are resolved periodically.
+pub struct Bootstrap {
+    /// The configured bootstrap peers and DNS seeds.
+    config: BootstrapConfig,
+    /// The resolver of host names.
+    dns_resolver: Arc<dyn DnsResolver>,
+    /// The resolved bootstrap peers.
+    peers: RwLock<Vec<SocketAddr>>,
+    /// The peers discovered through the DNS seeds since they were last taken.
+    seed_peers: Mutex<Vec<SocketAddr>>,
+}
+
+impl Bootstrap {
+    /// Initializes a new instance with the given configuration and resolver of host names.
+    pub fn new(config: BootstrapConfig, dns_resolver: Arc<dyn DnsResolver>) -> Self {
+        Self { config, dns_resolver, peers: Default::default(), seed_peers: Default::default() }
+    }
+
+    /// Returns the configured bootstrap peers and DNS seeds.
+    pub fn config(&self) -> &BootstrapConfig {
+        &self.config
+    }
+
+    /// Returns the resolved bootstrap peers.
+    pub fn peers(&self) -> Vec<SocketAddr> {
+        self.peers.read().clone()
+    }
+
+    /// Returns the peers discovered through the DNS seeds since this method was last called.
+    pub fn take_seed_peers(&self) -> Vec<SocketAddr> {
+        std::mem::take(&mut *self.seed_peers.lock())
+    }
+
+    /// Resolves the bootstrap peers and the DNS seeds.
+    /// If none of the bootstrap peers resolve, the previously resolved bootstrap peers are kept.
+    pub async fn refresh(&self) {
+        // Resolve the bootstrap peers.
+        let mut peers = Vec::new();
+        for peer in &self.config.peers {
+            peers.extend(self.resolve(peer, None).await);
+        }
+        peers.sort_unstable();
+        peers.dedup();
+        if !peers.is_empty() {
+            *self.peers.write() = peers;
+        }
+
+        // Resolve the DNS seeds.
+        let mut seed_peers = Vec::new();
+        for seed in &self.config.dns_seeds {
+            let addrs = self.resolve(seed, Some(DEFAULT_SEED_PORT)).await;
+            debug!("Discovered {} peers through the DNS seed '{seed}'", addrs.len());
+            seed_peers.extend(addrs);
+        }
+        self.seed_peers.lock().extend(seed_peers);
+    }
+
+    /// Resolves the given `host:port` to its socket addresses, using the given default port if the port is missing.
+    async fn resolve(&self, entry: &str, default_port: Option<u16>) -> Vec<SocketAddr> {
+        let (host, port) = match parse_host_port(entry, default_port) {
+            Ok((host, port)) => (host, port),
+            Err(error) => {
+                warn!("Unable to parse '{entry}' - {error}");
+                return vec![];
+            }
+        };
+        // Return the IP address directly, if the host is one.
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return vec![SocketAddr::new(ip, port)];
+        }
+        match self.dns_resolver.lookup(&host).await {
+            Ok(ips) => ips.into_iter().map(|ip| SocketAddr::new(ip, port)).collect(),
+            Err(error) => {
+                warn!("Unable to resolve '{host}' - {error}");
+                vec![]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// A resolver of host names from a fixed set of records.
+    #[derive(Default)]
+    struct LocalDnsResolver(RwLock<HashMap<String, Vec<IpAddr>>>);
+
+    impl LocalDnsResolver {
+        fn insert(&self, host: &str, ips: &[&str]) {
+            self.0.write().insert(host.to_string(), ips.iter().map(|ip| ip.parse().unwrap()).collect());
+        }
+    }
+
+    #[async_trait]
+    impl DnsResolver for LocalDnsResolver {
+        async fn lookup(&self, host: &str) -> io::Result<Vec<IpAddr>> {
+            self.0.read().get(host).cloned().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, host.to_string()))
+        }
+    }
+
+    #[test]
+    fn test_parse_host_port() {
+        assert_eq!(parse_host_port("1.2.3.4:5", None).unwrap(), ("1.2.3.4".to_string(), 5));
+        assert_eq!(parse_host_port("[::1]:5", None).unwrap(), ("::1".to_string(), 5));
+        assert_eq!(parse_host_port("::1", Some(4130)).unwrap(), ("::1".to_string(), 4130));
+        assert_eq!(parse_host_port("seed.example.com", Some(4130)).unwrap(), ("seed.example.com".to_string(), 4130));
+        assert_eq!(parse_host_port("peer.example.com:4133", None).unwrap(), ("peer.example.com".to_string(), 4133));
+        assert!(parse_host_port("peer.example.com", None).is_err());
+        assert!(parse_host_port("peer.example.com:port", None).is_err());
+        assert!(parse_host_port("peer_example.com:4130", None).is_err());
+        assert!(parse_host_port("", Some(4130)).is_err());
+
+        assert!(BootstrapConfig::new(vec!["peer.example.com:4130".into()], vec!["seed.example.com".into()]).is_ok());
+        assert!(BootstrapConfig::new(vec!["peer.example.com".into()], vec![]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refresh() {
+        let resolver = Arc::new(LocalDnsResolver::default());
+        resolver.insert("peer.example.com", &["10.0.0.1"]);
+        resolver.insert("seed.example.com", &["10.0.1.1", "fd00::1"]);
+        let config = BootstrapConfig::new(
+            vec!["peer.example.com:4133".into(), "10.0.0.2:4130".into(), "missing.example.com:4130".into()],
+            vec!["seed.example.com".into()],
+        )
+        .unwrap();
+        let bootstrap = Bootstrap::new(config, resolver.clone());
+
+        // Ensure the host names are resolved, and the unresolvable ones are skipped.
+        bootstrap.refresh().await;
+        assert_eq!(bootstrap.peers(), vec![
+            "10.0.0.1:4133".parse::<SocketAddr>().unwrap(),
+            "10.0.0.2:4130".parse().unwrap()
+        ]);
+        assert_eq!(bootstrap.take_seed_peers(), vec![
+            "10.0.1.1:4130".parse::<SocketAddr>().unwrap(),
+            "[fd00::1]:4130".parse().unwrap()
+        ]);
+        assert!(bootstrap.take_seed_peers().is_empty());
+
+        // Ensure a refresh picks up the changed records.
+        resolver.insert("peer.example.com", &["10.0.0.3"]);
+        resolver.insert("seed.example.com", &["10.0.1.2"]);
+        bootstrap.refresh().await;
+        assert_eq!(bootstrap.peers(), vec![
+            "10.0.0.2:4130".parse::<SocketAddr>().unwrap(),
+            "10.0.0.3:4133".parse().unwrap()
+        ]);
+        assert_eq!(bootstrap.take_seed_peers(), vec!["10.0.1.2:4130".parse::<SocketAddr>().unwrap()]);
+    }
+}
@@ -12,14 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::messages::BlockRequest;
+use crate::messages::{BlockRequest, HeaderRequest, InventoryItem};
 use snarkvm::prelude::{puzzle::SolutionID, Network};
 
 use core::hash::Hash;
 use linked_hash_map::LinkedHashMap;
 use parking_lot::RwLock;
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
+    collections::{HashMap, VecDeque},
     net::{IpAddr, SocketAddr},
 };
 use time::{Duration, OffsetDateTime};
@@ -31,6 +31,22 @@ const MAX_CACHE_SIZE: usize = 1 << 17;
 type SolutionKey<N> = (SocketAddr, SolutionID<N>);
 /// A helper containing the peer IP and transaction ID.
 type TransactionKey<N> = (SocketAddr, <N as Network>::TransactionID);
+/// A helper containing the peer IP and inventory item.
+type InventoryKey<N> = (SocketAddr, InventoryItem<N>);
+
+/// The maximum number of alternative announcers to keep for a requested inventory item.
+const MAX_ANNOUNCERS_PER_ITEM: usize = 8;
+
+/// A pending request for an inventory item.
+#[derive(Clone, Debug)]
+struct InventoryRequestState {
+    /// The timestamp of the latest request.
+    requested_at: OffsetDateTime,
+    /// The peer IP that the item was last requested from.
+    peer_ip: SocketAddr,
+    /// The other peer IPs that announced the item, in the order of their announcements.
+    announcers: VecDeque<SocketAddr>,
+}
 
 #[derive(Debug)]
 pub struct Cache<N: Network> {
@@ -42,18 +58,30 @@ pub struct Cache<N: Network> {
     seen_inbound_puzzle_requests: RwLock<HashMap<SocketAddr, VecDeque<OffsetDateTime>>>,
     /// The map of peer IPs to their recent timestamps.
     seen_inbound_block_requests: RwLock<HashMap<SocketAddr, VecDeque<OffsetDateTime>>>,
+    /// The map of peer IPs to their recent timestamps.
+    seen_inbound_header_requests: RwLock<HashMap<SocketAddr, VecDeque<OffsetDateTime>>>,
+    /// The map of peer IPs to the timestamps of the inventory items they requested.
+    seen_inbound_inventory_requests: RwLock<HashMap<SocketAddr, VecDeque<OffsetDateTime>>>,
     /// The map of solution IDs to their last seen timestamp.
     seen_inbound_solutions: RwLock<LinkedHashMap<SolutionKey<N>, OffsetDateTime>>,
     /// The map of transaction IDs to their last seen timestamp.
     seen_inbound_transactions: RwLock<LinkedHashMap<TransactionKey<N>, OffsetDateTime>>,
+    /// The map of inventory items received or propagated by this node to their last seen timestamp.
+    seen_inventory: RwLock<LinkedHashMap<InventoryItem<N>, OffsetDateTime>>,
     /// The map of peer IPs to their block requests.
-    seen_outbound_block_requests: RwLock<HashMap<SocketAddr, HashSet<BlockRequest>>>,
+    seen_outbound_block_requests: RwLock<HashMap<SocketAddr, HashMap<BlockRequest, OffsetDateTime>>>,
+    /// The map of peer IPs to their header requests.
+    seen_outbound_header_requests: RwLock<HashMap<SocketAddr, HashMap<HeaderRequest, OffsetDateTime>>>,
     /// The map of peer IPs to the number of puzzle requests.
     seen_outbound_puzzle_requests: RwLock<HashMap<SocketAddr, u32>>,
     /// The map of solution IDs to their last seen timestamp.
     seen_outbound_solutions: RwLock<LinkedHashMap<SolutionKey<N>, OffsetDateTime>>,
     /// The map of transaction IDs to their last seen timestamp.
     seen_outbound_transactions: RwLock<LinkedHashMap<TransactionKey<N>, OffsetDateTime>>,
+    /// The map of inventory items announced to (or by) peers to their last seen timestamp.
+    seen_outbound_announcements: RwLock<LinkedHashMap<InventoryKey<N>, OffsetDateTime>>,
+    /// The map of inventory items requested from peers to their pending requests.
+    seen_outbound_inventory_requests: RwLock<LinkedHashMap<InventoryItem<N>, InventoryRequestState>>,
     /// The map of peer IPs to the number of sent peer requests.
     seen_outbound_peer_requests: RwLock<HashMap<SocketAddr, u32>>,
 }
@@ -67,7 +95,10 @@ impl<N: Network> Default for Cache<N> {
 
 impl<N: Network> Cache<N> {
     const INBOUND_BLOCK_REQUEST_INTERVAL: i64 = 60;
+    const INBOUND_HEADER_REQUEST_INTERVAL: i64 = 60;
+    const INBOUND_INVENTORY_REQUEST_INTERVAL: i64 = 60;
     const INBOUND_PUZZLE_REQUEST_INTERVAL: i64 = 60;
+    const OUTBOUND_INVENTORY_REQUEST_TIMEOUT: i64 = 10;
 
     /// Initializes a new instance of the cache.
     pub fn new() -> Self {
@@ -76,12 +107,18 @@ impl<N: Network> Cache<N> {
             seen_inbound_messages: Default::default(),
             seen_inbound_puzzle_requests: Default::default(),
             seen_inbound_block_requests: Default::default(),
+            seen_inbound_header_requests: Default::default(),
+            seen_inbound_inventory_requests: Default::default(),
             seen_inbound_solutions: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
             seen_inbound_transactions: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
+            seen_inventory: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
             seen_outbound_block_requests: Default::default(),
+            seen_outbound_header_requests: Default::default(),
             seen_outbound_puzzle_requests: Default::default(),
             seen_outbound_solutions: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
             seen_outbound_transactions: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
+            seen_outbound_announcements: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
+            seen_outbound_inventory_requests: RwLock::new(LinkedHashMap::with_capacity(MAX_CACHE_SIZE)),
             seen_outbound_peer_requests: Default::default(),
         }
     }
@@ -108,6 +145,23 @@ impl<N: Network> Cache<N> {
         Self::retain_and_insert(&self.seen_inbound_block_requests, peer_ip, Self::INBOUND_BLOCK_REQUEST_INTERVAL)
     }
 
+    /// Inserts a new timestamp for the given peer IP, returning the number of recent header requests.
+    pub fn insert_inbound_header_request(&self, peer_ip: SocketAddr) -> usize {
+        Self::retain_and_insert(&self.seen_inbound_header_requests, peer_ip, Self::INBOUND_HEADER_REQUEST_INTERVAL)
+    }
+
+    /// Inserts a new timestamp for each of the inventory items requested by the given peer IP,
+    /// returning the number of recently requested items.
+    pub fn insert_inbound_inventory_request(&self, peer_ip: SocketAddr, num_items: usize) -> usize {
+        let interval_in_secs = Self::INBOUND_INVENTORY_REQUEST_INTERVAL;
+        let mut frequency = 0;
+        // Count an empty request as a single item.
+        for _ in 0..num_items.max(1) {
+            frequency = Self::retain_and_insert(&self.seen_inbound_inventory_requests, peer_ip, interval_in_secs);
+        }
+        frequency
+    }
+
     /// Inserts a solution ID into the cache, returning the previously seen timestamp if it existed.
     pub fn insert_inbound_solution(&self, peer_ip: SocketAddr, solution_id: SolutionID<N>) -> Option<OffsetDateTime> {
         Self::refresh_and_insert(&self.seen_inbound_solutions, (peer_ip, solution_id))
@@ -121,9 +175,21 @@ impl<N: Network> Cache<N> {
     ) -> Option<OffsetDateTime> {
         Self::refresh_and_insert(&self.seen_inbound_transactions, (peer_ip, transaction))
     }
+
+    /// Inserts an inventory item into the cache, returning the previously seen timestamp if it existed.
+    /// Any pending request for the item is resolved.
+    pub fn insert_inventory(&self, item: InventoryItem<N>) -> Option<OffsetDateTime> {
+        self.seen_outbound_inventory_requests.write().remove(&item);
+        Self::refresh_and_insert(&self.seen_inventory, item)
+    }
 }
 
 impl<N: Network> Cache<N> {
+    /// Returns `true` if the cache contains the inventory item.
+    pub fn contains_inventory(&self, item: &InventoryItem<N>) -> bool {
+        self.seen_inventory.read().contains_key(item)
+    }
+
     /// Returns `true` if the cache contains the block request for the given peer.
     pub fn contains_inbound_block_request(&self, peer_ip: &SocketAddr) -> bool {
         Self::retain(&self.seen_inbound_block_requests, *peer_ip, Self::INBOUND_BLOCK_REQUEST_INTERVAL) > 0
@@ -136,21 +202,44 @@ impl<N: Network> Cache<N> {
 
     /// Returns `true` if the cache contains the block request for the given peer.
     pub fn contains_outbound_block_request(&self, peer_ip: &SocketAddr, request: &BlockRequest) -> bool {
-        self.seen_outbound_block_requests.read().get(peer_ip).map(|r| r.contains(request)).unwrap_or(false)
+        self.seen_outbound_block_requests.read().get(peer_ip).map(|r| r.contains_key(request)).unwrap_or(false)
     }
 
     /// Inserts the block request for the given peer IP, returning the number of recent requests.
     pub fn insert_outbound_block_request(&self, peer_ip: SocketAddr, request: BlockRequest) -> usize {
         let mut map_write = self.seen_outbound_block_requests.write();
         let requests = map_write.entry(peer_ip).or_default();
-        requests.insert(request);
+        requests.insert(request, OffsetDateTime::now_utc());
         requests.len()
     }
 
-    /// Removes the block request for the given peer IP, returning `true` if the request was present.
-    pub fn remove_outbound_block_request(&self, peer_ip: SocketAddr, request: &BlockRequest) -> bool {
+    /// Removes the block request for the given peer IP, returning the timestamp of the request if it was present.
+    pub fn remove_outbound_block_request(&self, peer_ip: SocketAddr, request: &BlockRequest) -> Option<OffsetDateTime> {
         let mut map_write = self.seen_outbound_block_requests.write();
-        if let Some(requests) = map_write.get_mut(&peer_ip) { requests.remove(request) } else { false }
+        map_write.get_mut(&peer_ip).and_then(|requests| requests.remove(request))
+    }
+
+    /// Returns the number of recent header requests for the given peer.
+    pub fn num_outbound_header_requests(&self, peer_ip: &SocketAddr) -> usize {
+        self.seen_outbound_header_requests.read().get(peer_ip).map(|r| r.len()).unwrap_or(0)
+    }
+
+    /// Inserts the header request for the given peer IP, returning the number of recent requests.
+    pub fn insert_outbound_header_request(&self, peer_ip: SocketAddr, request: HeaderRequest) -> usize {
+        let mut map_write = self.seen_outbound_header_requests.write();
+        let requests = map_write.entry(peer_ip).or_default();
+        requests.insert(request, OffsetDateTime::now_utc());
+        requests.len()
+    }
+
+    /// Removes the header request for the given peer IP, returning the timestamp of the request if it was present.
+    pub fn remove_outbound_header_request(
+        &self,
+        peer_ip: SocketAddr,
+        request: &HeaderRequest,
+    ) -> Option<OffsetDateTime> {
+        let mut map_write = self.seen_outbound_header_requests.write();
+        map_write.get_mut(&peer_ip).and_then(|requests| requests.remove(request))
     }
 
     /// Returns `true` if the cache contains a puzzle request from the given peer.
@@ -182,6 +271,89 @@ impl<N: Network> Cache<N> {
         Self::refresh_and_insert(&self.seen_outbound_transactions, (peer_ip, transaction))
     }
 
+    /// Inserts an inventory item announced to (or by) the given peer into the cache,
+    /// returning the previously seen timestamp if it existed.
+    pub fn insert_outbound_announcement(&self, peer_ip: SocketAddr, item: InventoryItem<N>) -> Option<OffsetDateTime> {
+        Self::refresh_and_insert(&self.seen_outbound_announcements, (peer_ip, item))
+    }
+
+    /// Inserts the inventory request for the given item announced by the given peer, returning `true`
+    /// if the item was not already requested within the timeout (in which case it should be requested from the peer).
+    /// Otherwise, the peer is kept as an alternative announcer, to request the item from if the pending request times out.
+    pub fn insert_outbound_inventory_request(&self, peer_ip: SocketAddr, item: InventoryItem<N>) -> bool {
+        // Fetch the current timestamp.
+        let now = OffsetDateTime::now_utc();
+
+        let mut map_write = self.seen_outbound_inventory_requests.write();
+        match map_write.get_mut(&item) {
+            // If the item is pending, keep the peer as an alternative announcer.
+            Some(state) if now - state.requested_at <= Duration::seconds(Self::OUTBOUND_INVENTORY_REQUEST_TIMEOUT) => {
+                if state.peer_ip != peer_ip
+                    && !state.announcers.contains(&peer_ip)
+                    && state.announcers.len() < MAX_ANNOUNCERS_PER_ITEM
+                {
+                    state.announcers.push_back(peer_ip);
+                }
+                false
+            }
+            // If the request timed out, request the item from the peer instead.
+            Some(state) => {
+                state.requested_at = now;
+                state.peer_ip = peer_ip;
+                state.announcers.retain(|announcer| *announcer != peer_ip);
+                true
+            }
+            None => {
+                // Insert the new request.
+                map_write.insert(item, InventoryRequestState {
+                    requested_at: now,
+                    peer_ip,
+                    announcers: VecDeque::new(),
+                });
+                // Enforce the maximum cache size.
+                while map_write.len() > MAX_CACHE_SIZE {
+                    map_write.pop_front();
+                }
+                true
+            }
+        }
+    }
+
+    /// Returns the inventory items whose requests timed out, each paired with the next connected peer that announced it,
+    /// and marks them as requested from that peer. The items without any remaining announcers are forgotten.
+    pub fn next_outbound_inventory_requests(
+        &self,
+        is_connected: impl Fn(&SocketAddr) -> bool,
+    ) -> Vec<(SocketAddr, InventoryItem<N>)> {
+        // Fetch the current timestamp.
+        let now = OffsetDateTime::now_utc();
+
+        let mut map_write = self.seen_outbound_inventory_requests.write();
+        let mut requests = Vec::new();
+        let mut exhausted = Vec::new();
+        for (item, state) in map_write.iter_mut() {
+            // Skip the requests that are still pending.
+            if now - state.requested_at <= Duration::seconds(Self::OUTBOUND_INVENTORY_REQUEST_TIMEOUT) {
+                continue;
+            }
+            // Select the next announcer that is still connected.
+            let next_peer = std::iter::from_fn(|| state.announcers.pop_front()).find(|peer_ip| is_connected(peer_ip));
+            match next_peer {
+                Some(peer_ip) => {
+                    state.requested_at = now;
+                    state.peer_ip = peer_ip;
+                    requests.push((peer_ip, *item));
+                }
+                None => exhausted.push(*item),
+            }
+        }
+        // Forget the items that no peer is left to request from.
+        for item in exhausted {
+            map_write.remove(&item);
+        }
+        requests
+    }
+
     /// Returns `true` if the cache contains a peer request from the given peer.
     pub fn contains_outbound_peer_request(&self, peer_ip: SocketAddr) -> bool {
         self.seen_outbound_peer_requests.read().get(&peer_ip).map(|r| *r > 0).unwrap_or(false)
@@ -408,6 +580,99 @@ mod tests {
         assert_eq!(cache.seen_outbound_transactions.read().len(), 1);
     }
 
+    #[test]
+    fn test_inbound_inventory_request() {
+        let cache = Cache::<CurrentNetwork>::default();
+        let peer_ip = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1234);
+
+        // Check that the requested items are counted.
+        assert_eq!(cache.insert_inbound_inventory_request(peer_ip, 3), 3);
+        assert_eq!(cache.insert_inbound_inventory_request(peer_ip, 2), 5);
+
+        // Check that an empty request is counted as a request.
+        assert_eq!(cache.insert_inbound_inventory_request(peer_ip, 0), 6);
+    }
+
+    #[test]
+    fn test_outbound_inventory_request() {
+        let cache = Cache::<CurrentNetwork>::default();
+        let peer_ip = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1234);
+        let item = InventoryItem::Solution(SolutionID::<CurrentNetwork>::from(123456789));
+
+        // Check that the cache is empty.
+        assert_eq!(cache.seen_outbound_inventory_requests.read().len(), 0);
+
+        // Request the item.
+        assert!(cache.insert_outbound_inventory_request(peer_ip, item));
+
+        // Check that the item is not requested again while the request is pending.
+        assert!(!cache.insert_outbound_inventory_request(peer_ip, item));
+        assert_eq!(cache.seen_outbound_inventory_requests.read().len(), 1);
+
+        // Check that the item is requested again once the request times out.
+        let timeout = Duration::seconds(Cache::<CurrentNetwork>::OUTBOUND_INVENTORY_REQUEST_TIMEOUT + 1);
+        cache.seen_outbound_inventory_requests.write().get_mut(&item).unwrap().requested_at -= timeout;
+        assert!(cache.insert_outbound_inventory_request(peer_ip, item));
+        assert_eq!(cache.seen_outbound_inventory_requests.read().len(), 1);
+
+        // Check that the request is resolved once the item is received.
+        cache.insert_inventory(item);
+        assert_eq!(cache.seen_outbound_inventory_requests.read().len(), 0);
+    }
+
+    #[test]
+    fn test_next_outbound_inventory_requests() {
+        let cache = Cache::<CurrentNetwork>::default();
+        let peer_ips: Vec<_> = (0..4).map(|port| SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port)).collect();
+        let item = InventoryItem::Solution(SolutionID::<CurrentNetwork>::from(123456789));
+        let timeout = Duration::seconds(Cache::<CurrentNetwork>::OUTBOUND_INVENTORY_REQUEST_TIMEOUT + 1);
+
+        // Request the item from the first peer, and keep the others as announcers.
+        assert!(cache.insert_outbound_inventory_request(peer_ips[0], item));
+        for peer_ip in &peer_ips {
+            assert!(!cache.insert_outbound_inventory_request(*peer_ip, item));
+        }
+
+        // Check that the item is not requested from another peer while the request is pending.
+        assert!(cache.next_outbound_inventory_requests(|_| true).is_empty());
+
+        // Check that the item is requested from the next announcer once the request times out.
+        cache.seen_outbound_inventory_requests.write().get_mut(&item).unwrap().requested_at -= timeout;
+        assert_eq!(cache.next_outbound_inventory_requests(|_| true), vec![(peer_ips[1], item)]);
+        assert!(cache.next_outbound_inventory_requests(|_| true).is_empty());
+
+        // Check that the disconnected announcers are skipped.
+        cache.seen_outbound_inventory_requests.write().get_mut(&item).unwrap().requested_at -= timeout;
+        assert_eq!(cache.next_outbound_inventory_requests(|peer_ip| *peer_ip != peer_ips[2]), vec![(
+            peer_ips[3],
+            item
+        )]);
+
+        // Check that the item is forgotten once no announcers are left.
+        cache.seen_outbound_inventory_requests.write().get_mut(&item).unwrap().requested_at -= timeout;
+        assert!(cache.next_outbound_inventory_requests(|_| true).is_empty());
+        assert_eq!(cache.seen_outbound_inventory_requests.read().len(), 0);
+    }
+
+    #[test]
+    fn test_outbound_header_request() {
+        let cache = Cache::<CurrentNetwork>::default();
+        let peer_ip = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1234);
+        let request = HeaderRequest { start_height: 1, end_height: 33 };
+
+        // Check that the cache is empty.
+        assert_eq!(cache.num_outbound_header_requests(&peer_ip), 0);
+        assert!(cache.remove_outbound_header_request(peer_ip, &request).is_none());
+
+        // Insert the header request.
+        assert_eq!(cache.insert_outbound_header_request(peer_ip, request), 1);
+        assert_eq!(cache.num_outbound_header_requests(&peer_ip), 1);
+
+        // Remove the header request.
+        assert!(cache.remove_outbound_header_request(peer_ip, &request).is_some());
+        assert_eq!(cache.num_outbound_header_requests(&peer_ip), 0);
+    }
+
     #[test]
     fn test_outbound_peer_request() {
         let cache = Cache::<CurrentNetwork>::default();
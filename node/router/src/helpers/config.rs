@@ -0,0 +1,37 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::BootstrapConfig;
+use snarkos_node_tcp::Config;
+
+use std::{net::SocketAddr, path::PathBuf};
+
+/// The configuration of the router, beyond the node's own address and type.
+#[derive(Clone, Debug, Default)]
+pub struct RouterConfig {
+    /// The configuration of the TCP stack, such as its transport and proxy.
+    /// Note: The listener address and the maximum number of connections are set by the router.
+    pub tcp: Config,
+    /// The configured bootstrap peers and DNS seeds.
+    pub bootstrap: BootstrapConfig,
+    /// The path of the peer store, if it is persisted.
+    pub peer_store_path: Option<PathBuf>,
+    /// The address at which the peers can reach the listener of the node, if it differs from the listener address
+    /// (e.g. behind a proxy or a port mapping).
+    /// Note: The peers see the IP of the connection, so only the port is advertised in the handshake.
+    pub external_addr: Option<SocketAddr>,
+    /// The maximum number of connected peers permitted from the same subnet, if it differs from the default.
+    /// Note: The trusted, bootstrap, loopback and private peers, and the peers in development mode, are exempt.
+    pub max_peers_per_subnet: Option<usize>,
+}
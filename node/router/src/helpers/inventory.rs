@@ -0,0 +1,133 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::messages::{InventoryItem, Message};
+use snarkvm::prelude::Network;
+
+use linked_hash_map::LinkedHashMap;
+use parking_lot::RwLock;
+
+/// The maximum number of unconfirmed transactions and solutions to store in the inventory.
+const MAX_INVENTORY_SIZE: usize = 1 << 10;
+
+/// The inventory of recently propagated unconfirmed transactions and solutions,
+/// which is used to serve the inventory requests of peers.
+#[derive(Debug)]
+pub struct Inventory<N: Network> {
+    /// The map of inventory items to their (serialized) messages, in insertion order.
+    messages: RwLock<LinkedHashMap<InventoryItem<N>, Message<N>>>,
+}
+
+impl<N: Network> Default for Inventory<N> {
+    /// Initializes a new instance of the inventory.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: Network> Inventory<N> {
+    /// Initializes a new instance of the inventory.
+    pub fn new() -> Self {
+        Self { messages: RwLock::new(LinkedHashMap::with_capacity(MAX_INVENTORY_SIZE)) }
+    }
+
+    /// Returns the number of items in the inventory.
+    pub fn len(&self) -> usize {
+        self.messages.read().len()
+    }
+
+    /// Returns `true` if the inventory is empty.
+    pub fn is_empty(&self) -> bool {
+        self.messages.read().is_empty()
+    }
+
+    /// Returns `true` if the inventory contains the given item.
+    pub fn contains(&self, item: &InventoryItem<N>) -> bool {
+        self.messages.read().contains_key(item)
+    }
+
+    /// Returns the message for the given item, if it is in the inventory.
+    pub fn get(&self, item: &InventoryItem<N>) -> Option<Message<N>> {
+        self.messages.read().get(item).cloned()
+    }
+
+    /// Inserts the given message into the inventory, evicting the oldest item if the inventory is full.
+    /// Returns the inventory item of the message, or `None` if it is not an unconfirmed transaction or solution.
+    pub fn insert(&self, message: &Message<N>) -> Option<InventoryItem<N>> {
+        let item = message.inventory_item()?;
+        let mut messages = self.messages.write();
+        messages.insert(item, message.clone());
+        while messages.len() > MAX_INVENTORY_SIZE {
+            messages.pop_front();
+        }
+        Some(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::{PeerRequest, UnconfirmedSolution};
+    use snarkvm::{
+        ledger::narwhal::Data,
+        prelude::{puzzle::SolutionID, MainnetV0},
+    };
+
+    type CurrentNetwork = MainnetV0;
+
+    /// Returns an unconfirmed solution message with the given ID (and a placeholder solution).
+    fn sample_message(id: u64) -> Message<CurrentNetwork> {
+        Message::UnconfirmedSolution(UnconfirmedSolution {
+            solution_id: SolutionID::from(id),
+            solution: Data::Buffer(Default::default()),
+        })
+    }
+
+    #[test]
+    fn test_insert() {
+        let inventory = Inventory::<CurrentNetwork>::default();
+        assert!(inventory.is_empty());
+
+        // Ensure only unconfirmed transactions and solutions are inserted.
+        assert!(inventory.insert(&Message::PeerRequest(PeerRequest)).is_none());
+        assert!(inventory.is_empty());
+
+        // Insert a solution.
+        let message = sample_message(1);
+        let item = inventory.insert(&message).unwrap();
+        assert_eq!(item, InventoryItem::Solution(SolutionID::from(1)));
+        assert!(inventory.contains(&item));
+        assert_eq!(inventory.get(&item), Some(message.clone()));
+
+        // Insert the same solution again.
+        assert_eq!(inventory.insert(&message), Some(item));
+        assert_eq!(inventory.len(), 1);
+    }
+
+    #[test]
+    fn test_evict_oldest() {
+        let inventory = Inventory::<CurrentNetwork>::default();
+
+        // Fill the inventory beyond its capacity.
+        for id in 0..MAX_INVENTORY_SIZE as u64 + 1 {
+            inventory.insert(&sample_message(id));
+        }
+
+        // Ensure the oldest item was evicted.
+        assert_eq!(inventory.len(), MAX_INVENTORY_SIZE);
+        assert!(!inventory.contains(&InventoryItem::Solution(SolutionID::from(0))));
+        assert!(inventory.contains(&InventoryItem::Solution(SolutionID::from(1))));
+        assert!(inventory.contains(&InventoryItem::Solution(SolutionID::from(MAX_INVENTORY_SIZE as u64))));
+    }
+}
@@ -12,11 +12,26 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod bootstrap;
+pub use bootstrap::*;
+
 mod cache;
 pub use cache::Cache;
 
+mod config;
+pub use config::*;
+
+mod inventory;
+pub use inventory::Inventory;
+
 mod peer;
 pub use peer::*;
 
+mod peer_store;
+pub use peer_store::*;
+
 mod resolver;
 pub use resolver::*;
+
+mod subnet;
+pub use subnet::*;
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::messages::{ChallengeRequest, NodeType};
+use crate::messages::{Capabilities, ChallengeRequest, NodeType};
 use snarkvm::prelude::{Address, Network};
 
 use std::{net::SocketAddr, time::Instant};
@@ -20,14 +20,21 @@ use std::{net::SocketAddr, time::Instant};
 /// The state for each connected peer.
 #[derive(Clone, Debug)]
 pub struct Peer<N: Network> {
-    /// The IP address of the peer, with the port set to the listener port.
+    /// The IP address of the peer, with the port set to the listener port
+    /// (or the address of the connection, if the peer does not advertise a listener).
     peer_ip: SocketAddr,
     /// The Aleo address of the peer.
     address: Address<N>,
     /// The node type of the peer.
     node_type: NodeType,
-    /// The message version of the peer.
+    /// The message version negotiated with the peer.
     version: u32,
+    /// The capabilities advertised by the peer.
+    capabilities: Capabilities,
+    /// `true` if this node initiated the connection to the peer.
+    is_outbound: bool,
+    /// `true` if the peer can be reached at its IP, i.e. it accepted the connection or advertised a listener.
+    is_listening: bool,
     /// The timestamp of the first message received from the peer.
     first_seen: Instant,
     /// The timestamp of the last message received from this peer.
@@ -36,18 +43,28 @@ pub struct Peer<N: Network> {
 
 impl<N: Network> Peer<N> {
     /// Initializes a new instance of `Peer`.
-    pub fn new(listening_ip: SocketAddr, challenge_request: &ChallengeRequest<N>) -> Self {
+    pub fn new(
+        listening_ip: SocketAddr,
+        challenge_request: &ChallengeRequest<N>,
+        version: u32,
+        capabilities: Capabilities,
+        is_outbound: bool,
+    ) -> Self {
         Self {
             peer_ip: listening_ip,
             address: challenge_request.address,
             node_type: challenge_request.node_type,
-            version: challenge_request.version,
+            version,
+            capabilities,
+            is_outbound,
+            is_listening: is_outbound || challenge_request.listener_port != 0,
             first_seen: Instant::now(),
             last_seen: Instant::now(),
         }
     }
 
-    /// Returns the IP address of the peer, with the port set to the listener port.
+    /// Returns the IP address of the peer, with the port set to the listener port
+    /// (or the address of the connection, if the peer does not advertise a listener).
     pub const fn ip(&self) -> SocketAddr {
         self.peer_ip
     }
@@ -77,11 +94,26 @@ impl<N: Network> Peer<N> {
         self.node_type.is_client()
     }
 
-    /// Returns the message version of the peer.
+    /// Returns the message version negotiated with the peer.
     pub const fn version(&self) -> u32 {
         self.version
     }
 
+    /// Returns the capabilities advertised by the peer.
+    pub const fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// Returns `true` if this node initiated the connection to the peer.
+    pub const fn is_outbound(&self) -> bool {
+        self.is_outbound
+    }
+
+    /// Returns `true` if the peer can be reached at its IP, i.e. it accepted the connection or advertised a listener.
+    pub const fn is_listening(&self) -> bool {
+        self.is_listening
+    }
+
     /// Returns the first seen timestamp of the peer.
     pub fn first_seen(&self) -> Instant {
         self.first_seen
@@ -0,0 +1,405 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::messages::{DisconnectReason, NodeType};
+use snarkos_node_persistence::{decode_file, encode_file, node_file_path, write_atomically};
+
+use aleo_std::StorageMode;
+use anyhow::{bail, Result};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use time::OffsetDateTime;
+
+/// The magic bytes at the start of a peer store file.
+const PEER_STORE_MAGIC: [u8; 8] = *b"SNOSPEER";
+/// The version of the peer store format.
+const PEER_STORE_VERSION: u16 = 1;
+/// The maximum number of peer records to keep.
+const MAXIMUM_PEER_RECORDS: usize = 10_000;
+/// The duration in seconds after which a peer record that was not seen is pruned.
+const MAXIMUM_PEER_RECORD_AGE_IN_SECS: i64 = 30 * 24 * 60 * 60; // 30 days
+
+/// The reputation gained for successful handshakes, scaled logarithmically.
+const HANDSHAKE_WEIGHT: f64 = 10.0;
+/// The reputation lost for each failed connection attempt, relative to the successful handshakes.
+const FAILURE_WEIGHT: f64 = 5.0;
+/// The reputation lost for each protocol violation.
+const VIOLATION_WEIGHT: f64 = 25.0;
+/// The reputation gained per served block per second, up to `MAXIMUM_THROUGHPUT_SCORE`.
+const THROUGHPUT_WEIGHT: f64 = 0.1;
+/// The maximum reputation gained for the served block throughput.
+const MAXIMUM_THROUGHPUT_SCORE: f64 = 10.0;
+/// The reputation lost per day since the peer was last seen, up to `MAXIMUM_AGE_PENALTY`.
+const AGE_PENALTY_PER_DAY: f64 = 1.0;
+/// The maximum reputation lost for the time since the peer was last seen.
+const MAXIMUM_AGE_PENALTY: f64 = 30.0;
+
+/// Returns the path where the peer store is stored.
+pub fn peer_store_path(network: u16, storage_mode: &StorageMode) -> PathBuf {
+    const PEER_STORE_FILE_NAME: &str = "peer-store";
+
+    node_file_path(PEER_STORE_FILE_NAME, network, storage_mode)
+}
+
+/// Returns the current UNIX timestamp, in seconds.
+fn now() -> i64 {
+    OffsetDateTime::now_utc().unix_timestamp()
+}
+
+/// Returns `true` if the given disconnect reason means the peer misbehaved.
+const fn is_violation(reason: DisconnectReason) -> bool {
+    matches!(
+        reason,
+        DisconnectReason::ExceededForkRange
+            | DisconnectReason::InvalidChallengeResponse
+            | DisconnectReason::InvalidForkDepth
+            | DisconnectReason::ProtocolViolation
+            | DisconnectReason::TooManyFailures
+    )
+}
+
+/// The history of a peer, as observed by this node.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PeerRecord {
+    /// The UNIX timestamp at which the peer was first seen.
+    pub first_seen: i64,
+    /// The UNIX timestamp at which the peer was last seen.
+    pub last_seen: i64,
+    /// The node type of the peer, if a handshake succeeded.
+    pub node_type: Option<NodeType>,
+    /// The number of successful handshakes with the peer.
+    pub handshakes: u32,
+    /// The number of failed connection attempts to the peer.
+    pub failures: u32,
+    /// The number of protocol violations by the peer.
+    pub violations: u32,
+    /// The number of times this node disconnected from the peer, by reason.
+    pub disconnect_reasons: BTreeMap<String, u32>,
+    /// The number of blocks served by the peer.
+    pub blocks_served: u64,
+    /// The total time in milliseconds the peer took to serve the blocks.
+    pub serving_time_in_ms: u64,
+}
+
+impl PeerRecord {
+    /// Initializes a new record for a peer seen at the given timestamp.
+    fn new(timestamp: i64) -> Self {
+        Self { first_seen: timestamp, last_seen: timestamp, ..Default::default() }
+    }
+
+    /// Returns the number of blocks served by the peer per second.
+    pub fn throughput(&self) -> f64 {
+        match self.serving_time_in_ms {
+            0 => 0.0,
+            time_in_ms => self.blocks_served as f64 * 1000.0 / time_in_ms as f64,
+        }
+    }
+
+    /// Returns the reputation of the peer at the given UNIX timestamp.
+    /// A peer without history has a reputation of zero, and a misbehaving peer a negative one.
+    pub fn reputation(&self, timestamp: i64) -> f64 {
+        // Reward the successful handshakes, with diminishing returns.
+        let mut reputation = (1.0 + self.handshakes as f64).ln() * HANDSHAKE_WEIGHT;
+        // Penalize the failed connection attempts, relative to the successful handshakes.
+        reputation -= self.failures as f64 / (1.0 + self.handshakes as f64) * FAILURE_WEIGHT;
+        // Penalize the protocol violations.
+        reputation -= self.violations as f64 * VIOLATION_WEIGHT;
+        // Reward the served block throughput.
+        reputation += (self.throughput() * THROUGHPUT_WEIGHT).min(MAXIMUM_THROUGHPUT_SCORE);
+        // Penalize the peers that have not been seen in a while.
+        let days_since_last_seen = timestamp.saturating_sub(self.last_seen).max(0) as f64 / 86_400.0;
+        reputation -= (days_since_last_seen * AGE_PENALTY_PER_DAY).min(MAXIMUM_AGE_PENALTY);
+        reputation
+    }
+}
+
+/// A store of the history of the peers, which persists across restarts.
+#[derive(Debug, Default)]
+pub struct PeerStore {
+    /// The map of peer IPs to their records.
+    records: RwLock<HashMap<SocketAddr, PeerRecord>>,
+    /// The flag indicating the records changed since they were last stored.
+    is_dirty: AtomicBool,
+    /// The lock serializing the stores, so that an older snapshot never replaces a newer one.
+    store_lock: Mutex<()>,
+}
+
+impl PeerStore {
+    /// Initializes a new, empty instance of the peer store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of peer records.
+    pub fn len(&self) -> usize {
+        self.records.read().len()
+    }
+
+    /// Returns `true` if there are no peer records.
+    pub fn is_empty(&self) -> bool {
+        self.records.read().is_empty()
+    }
+
+    /// Returns `true` if the records changed since they were last stored.
+    pub fn is_dirty(&self) -> bool {
+        self.is_dirty.load(Ordering::SeqCst)
+    }
+
+    /// Returns the record of the given peer IP, if it exists.
+    pub fn get(&self, peer_ip: &SocketAddr) -> Option<PeerRecord> {
+        self.records.read().get(peer_ip).cloned()
+    }
+
+    /// Returns the reputation of the given peer IP, which is zero for an unknown peer.
+    pub fn reputation(&self, peer_ip: &SocketAddr) -> f64 {
+        self.records.read().get(peer_ip).map_or(0.0, |record| record.reputation(now()))
+    }
+
+    /// Returns the known peer IPs with a non-negative reputation, best first.
+    pub fn reputable_peers(&self) -> Vec<SocketAddr> {
+        let timestamp = now();
+        let mut peers = self
+            .records
+            .read()
+            .iter()
+            .map(|(peer_ip, record)| (*peer_ip, record.reputation(timestamp)))
+            .filter(|(_, reputation)| *reputation >= 0.0)
+            .collect::<Vec<_>>();
+        peers.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        peers.into_iter().map(|(peer_ip, _)| peer_ip).collect()
+    }
+
+    /// Sorts the given peer IPs by reputation, best first. The order of peers with equal reputation is kept.
+    pub fn sort_by_reputation(&self, peer_ips: &mut [SocketAddr]) {
+        let timestamp = now();
+        let records = self.records.read();
+        let reputation = |peer_ip: &SocketAddr| records.get(peer_ip).map_or(0.0, |record| record.reputation(timestamp));
+        peer_ips.sort_by(|a, b| reputation(b).total_cmp(&reputation(a)));
+    }
+
+    /// Records a successful handshake with the given peer.
+    pub fn record_handshake(&self, peer_ip: SocketAddr, node_type: NodeType) {
+        self.update(peer_ip, |record| {
+            record.node_type = Some(node_type);
+            record.handshakes = record.handshakes.saturating_add(1);
+        });
+    }
+
+    /// Records the given peer was seen, e.g. when disconnecting from it.
+    pub fn record_seen(&self, peer_ip: SocketAddr) {
+        self.update(peer_ip, |_| {});
+    }
+
+    /// Records a failed connection attempt to the given peer.
+    pub fn record_failure(&self, peer_ip: SocketAddr) {
+        let timestamp = now();
+        let mut records = self.records.write();
+        let record = records.entry(peer_ip).or_insert_with(|| PeerRecord::new(timestamp));
+        record.failures = record.failures.saturating_add(1);
+        self.is_dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Records that this node disconnected from the given peer for the given reason.
+    pub fn record_disconnect(&self, peer_ip: SocketAddr, reason: DisconnectReason) {
+        self.update(peer_ip, |record| {
+            // Strip the details of the reason, to keep the number of distinct reasons bounded.
+            let reason_name = format!("{reason:?}");
+            let reason_name = reason_name.split('(').next().unwrap_or_default().to_string();
+            let count = record.disconnect_reasons.entry(reason_name).or_default();
+            *count = count.saturating_add(1);
+            if is_violation(reason) {
+                record.violations = record.violations.saturating_add(1);
+            }
+        });
+    }
+
+    /// Records the given number of blocks was served by the given peer, in the given time.
+    pub fn record_blocks_served(&self, peer_ip: SocketAddr, num_blocks: usize, elapsed: Duration) {
+        self.update(peer_ip, |record| {
+            record.blocks_served = record.blocks_served.saturating_add(num_blocks as u64);
+            record.serving_time_in_ms = record.serving_time_in_ms.saturating_add(elapsed.as_millis() as u64);
+        });
+    }
+
+    /// Updates the record of the given peer with the given function, and marks the peer as seen.
+    fn update<F: FnOnce(&mut PeerRecord)>(&self, peer_ip: SocketAddr, update_fn: F) {
+        let timestamp = now();
+        let mut records = self.records.write();
+        let record = records.entry(peer_ip).or_insert_with(|| PeerRecord::new(timestamp));
+        record.last_seen = timestamp;
+        update_fn(record);
+        self.is_dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Removes the records that were not seen in a while, and the worst records above the maximum.
+    pub fn prune(&self) {
+        self.prune_at(now());
+    }
+
+    /// Removes the records that were not seen in a while at the given UNIX timestamp,
+    /// and the worst records above the maximum.
+    fn prune_at(&self, timestamp: i64) {
+        let mut records = self.records.write();
+        let num_records = records.len();
+        records.retain(|_, record| timestamp.saturating_sub(record.last_seen) <= MAXIMUM_PEER_RECORD_AGE_IN_SECS);
+        if records.len() > MAXIMUM_PEER_RECORDS {
+            let mut reputations =
+                records.iter().map(|(peer_ip, record)| (*peer_ip, record.reputation(timestamp))).collect::<Vec<_>>();
+            reputations.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+            for (peer_ip, _) in reputations.into_iter().skip(MAXIMUM_PEER_RECORDS) {
+                records.remove(&peer_ip);
+            }
+        }
+        if records.len() != num_records {
+            self.is_dirty.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Loads the peer store from the given path.
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) => bail!("Couldn't read the peer store at {} - {err}", path.display()),
+        };
+        let (version, payload) = match decode_file(&PEER_STORE_MAGIC, &bytes) {
+            Ok(contents) => contents,
+            Err(err) => bail!("Couldn't deserialize the peer store at {} - {err}", path.display()),
+        };
+        if version != PEER_STORE_VERSION {
+            bail!("Unsupported peer store version {version} at {}", path.display());
+        }
+        let records: Vec<(SocketAddr, PeerRecord)> = match bincode::deserialize(payload) {
+            Ok(records) => records,
+            Err(err) => bail!("Couldn't deserialize the peer store at {} - {err}", path.display()),
+        };
+        let store = Self { records: RwLock::new(records.into_iter().collect()), ..Default::default() };
+        store.prune();
+        Ok(store)
+    }
+
+    /// Stores the peer store at the given path, if it changed since it was last stored.
+    ///
+    /// The peer store is written to a temporary file which is then renamed,
+    /// so that an interrupted write never leaves a partial peer store behind.
+    pub fn store(&self, path: &Path) -> Result<()> {
+        let _store_lock = self.store_lock.lock();
+        if !self.is_dirty.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let records: Vec<_> = self.records.read().iter().map(|(peer_ip, record)| (*peer_ip, record.clone())).collect();
+        let bytes = match bincode::serialize(&records) {
+            Ok(payload) => encode_file(&PEER_STORE_MAGIC, PEER_STORE_VERSION, &payload),
+            Err(err) => {
+                self.is_dirty.store(true, Ordering::SeqCst);
+                return Err(err.into());
+            }
+        };
+        // Store the peer store to the file system.
+        if let Err(err) = write_atomically(path, &bytes) {
+            self.is_dirty.store(true, Ordering::SeqCst);
+            bail!("Couldn't write the peer store to {} - {err}", path.display());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_peer_ip(id: u16) -> SocketAddr {
+        SocketAddr::from(([1, 2, 3, 4], id))
+    }
+
+    #[test]
+    fn test_reputation() {
+        let store = PeerStore::new();
+        let (good, slow, unknown, bad) = (sample_peer_ip(1), sample_peer_ip(2), sample_peer_ip(3), sample_peer_ip(4));
+
+        store.record_handshake(good, NodeType::Client);
+        store.record_blocks_served(good, 50, Duration::from_secs(1));
+        store.record_handshake(slow, NodeType::Client);
+        store.record_failure(slow);
+        store.record_handshake(bad, NodeType::Client);
+        store.record_disconnect(bad, DisconnectReason::ProtocolViolation);
+        store.record_disconnect(bad, DisconnectReason::YourPortIsClosed(4130));
+
+        // Ensure the disconnect reasons and violations are recorded.
+        let record = store.get(&bad).unwrap();
+        assert_eq!(record.violations, 1);
+        assert_eq!(record.disconnect_reasons.get("ProtocolViolation"), Some(&1));
+        assert_eq!(record.disconnect_reasons.get("YourPortIsClosed"), Some(&1));
+
+        // Ensure the peers are ranked by reputation, and the misbehaving peer is not reputable.
+        assert!(store.reputation(&good) > store.reputation(&slow));
+        assert!(store.reputation(&slow) > store.reputation(&unknown));
+        assert!(store.reputation(&unknown) > store.reputation(&bad));
+        assert_eq!(store.reputable_peers(), vec![good, slow]);
+        let mut peer_ips = [bad, unknown, slow, good];
+        store.sort_by_reputation(&mut peer_ips);
+        assert_eq!(peer_ips, [good, slow, unknown, bad]);
+
+        // Ensure the reputation decays, and old records are pruned.
+        let timestamp = store.get(&good).unwrap().last_seen;
+        let record = store.get(&good).unwrap();
+        assert!(record.reputation(timestamp + 86_400) < record.reputation(timestamp));
+        store.prune_at(timestamp + MAXIMUM_PEER_RECORD_AGE_IN_SECS + 1);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_peer_store_path() {
+        // Ensure the peer store is kept next to a custom ledger.
+        let ledger_dir = std::env::temp_dir().join("snarkos-peer-store-test-ledger");
+        let path = peer_store_path(1, &StorageMode::Custom(ledger_dir.clone()));
+        assert_eq!(path, ledger_dir.parent().unwrap().join("peer-store-1"));
+        // Ensure the development nodes have separate peer stores.
+        assert_ne!(peer_store_path(1, &StorageMode::Development(0)), peer_store_path(1, &StorageMode::Development(1)));
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let path = std::env::temp_dir().join(format!("snarkos-peer-store-test-{}", std::process::id()));
+        let store = PeerStore::new();
+        store.record_handshake(sample_peer_ip(1), NodeType::Validator);
+        store.record_blocks_served(sample_peer_ip(1), 10, Duration::from_millis(500));
+        store.record_failure(sample_peer_ip(2));
+        store.store(&path).unwrap();
+
+        // Ensure the records are restored.
+        let loaded = PeerStore::load(&path).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.get(&sample_peer_ip(1)), store.get(&sample_peer_ip(1)));
+        assert_eq!(loaded.get(&sample_peer_ip(2)), store.get(&sample_peer_ip(2)));
+
+        // Ensure a corrupted peer store is rejected.
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        fs::write(&path, bytes).unwrap();
+        assert!(PeerStore::load(&path).is_err());
+
+        // Ensure an unchanged store is not written again.
+        fs::remove_file(&path).unwrap();
+        store.store(&path).unwrap();
+        assert!(!path.exists());
+    }
+}
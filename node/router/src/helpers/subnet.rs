@@ -0,0 +1,86 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// The prefix length of an IPv4 subnet.
+const IPV4_SUBNET_PREFIX_LEN: u32 = 24;
+/// The prefix length of an IPv6 subnet.
+const IPV6_SUBNET_PREFIX_LEN: u32 = 48;
+
+/// Returns the subnet of the given IP address, which is its /24 prefix for IPv4, and its /48 prefix for IPv6.
+/// An IPv4-mapped IPv6 address is in the subnet of its IPv4 address.
+pub fn subnet(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => IpAddr::V4(ipv4_subnet(ip)),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => IpAddr::V4(ipv4_subnet(ip)),
+            None => {
+                let mask = u128::MAX << (128 - IPV6_SUBNET_PREFIX_LEN);
+                IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+            }
+        },
+    }
+}
+
+/// Returns `true` if the given IP address is in a private IPv4 range (10.0.0.0/8, 172.16.0.0/12 or 192.168.0.0/16).
+/// An IPv4-mapped IPv6 address is private if its IPv4 address is.
+pub fn is_private_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_private(),
+        IpAddr::V6(ip) => ip.to_ipv4_mapped().is_some_and(|ip| ip.is_private()),
+    }
+}
+
+/// Returns the /24 prefix of the given IPv4 address.
+fn ipv4_subnet(ip: Ipv4Addr) -> Ipv4Addr {
+    let mask = u32::MAX << (32 - IPV4_SUBNET_PREFIX_LEN);
+    Ipv4Addr::from(u32::from(ip) & mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subnet() {
+        let ip = |ip: &str| ip.parse::<IpAddr>().unwrap();
+
+        // Ensure IPv4 addresses are bucketed by their /24 prefix.
+        assert_eq!(subnet(ip("34.168.118.156")), ip("34.168.118.0"));
+        assert_eq!(subnet(ip("34.168.118.1")), subnet(ip("34.168.118.255")));
+        assert_ne!(subnet(ip("34.168.118.1")), subnet(ip("34.168.119.1")));
+
+        // Ensure IPv6 addresses are bucketed by their /48 prefix.
+        assert_eq!(subnet(ip("2001:db8:abcd:12::1")), ip("2001:db8:abcd::"));
+        assert_eq!(subnet(ip("2001:db8:abcd:12::1")), subnet(ip("2001:db8:abcd:ffff::2")));
+        assert_ne!(subnet(ip("2001:db8:abcd::1")), subnet(ip("2001:db8:abce::1")));
+
+        // Ensure IPv4-mapped IPv6 addresses are bucketed with their IPv4 address.
+        assert_eq!(subnet(ip("::ffff:34.168.118.7")), subnet(ip("34.168.118.156")));
+    }
+
+    #[test]
+    fn test_is_private_ip() {
+        let ip = |ip: &str| ip.parse::<IpAddr>().unwrap();
+
+        // Ensure the private IPv4 ranges are recognized, including through IPv4-mapped IPv6 addresses.
+        for private in ["10.1.2.3", "172.16.0.1", "172.31.255.255", "192.168.1.1", "::ffff:10.0.0.1"] {
+            assert!(is_private_ip(ip(private)), "{private}");
+        }
+        for public in ["34.168.118.156", "172.32.0.1", "192.169.0.1", "127.0.0.1", "2001:db8::1"] {
+            assert!(!is_private_ip(ip(public)), "{public}");
+        }
+    }
+}
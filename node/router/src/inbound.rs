@@ -16,7 +16,14 @@ use crate::{
     messages::{
         BlockRequest,
         BlockResponse,
+        CertifiedHeader,
         DataBlocks,
+        DataHeaders,
+        DisconnectReason,
+        HeaderRequest,
+        HeaderResponse,
+        InventoryItem,
+        InventoryRequest,
         Message,
         PeerResponse,
         Ping,
@@ -36,7 +43,8 @@ use snarkvm::prelude::{
 
 use anyhow::{anyhow, bail, Result};
 use snarkos_node_tcp::is_bogon_ip;
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Duration};
+use time::OffsetDateTime;
 use tokio::task::spawn_blocking;
 
 /// The max number of peers to send in a `PeerResponse` message.
@@ -52,6 +60,10 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
     const MAXIMUM_PUZZLE_REQUESTS_PER_INTERVAL: usize = 5;
     /// The maximum number of block requests per interval.
     const MAXIMUM_BLOCK_REQUESTS_PER_INTERVAL: usize = 256;
+    /// The maximum number of header requests per interval.
+    const MAXIMUM_HEADER_REQUESTS_PER_INTERVAL: usize = 256;
+    /// The maximum number of requested inventory items per interval.
+    const MAXIMUM_INVENTORY_ITEMS_REQUESTED_PER_INTERVAL: usize = 2048;
     /// The duration in seconds to sleep in between ping requests with a connected peer.
     const PING_SLEEP_IN_SECS: u64 = 20; // 20 seconds
     /// The time frame to enforce the `MESSAGE_LIMIT`.
@@ -109,9 +121,11 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 let BlockResponse { request, blocks } = message;
 
                 // Remove the block request, checking if this node previously sent a block request to this peer.
-                if !self.router().cache.remove_outbound_block_request(peer_ip, &request) {
+                let Some(requested_at) = self.router().cache.remove_outbound_block_request(peer_ip, &request) else {
                     bail!("Peer '{peer_ip}' is not following the protocol (unexpected block response)")
-                }
+                };
+                // Compute the time the peer took to serve the blocks.
+                let elapsed: Duration = (OffsetDateTime::now_utc() - requested_at).try_into().unwrap_or_default();
                 // Perform the deferred non-blocking deserialization of the blocks.
                 // The deserialization can take a long time (minutes). We should not be running
                 // this on a blocking task, but on a rayon thread pool.
@@ -130,12 +144,69 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 blocks.ensure_response_is_well_formed(peer_ip, request.start_height, request.end_height)?;
 
                 // Process the block response.
+                let num_blocks = blocks.0.len();
                 let node = self.clone();
                 match spawn_blocking(move || node.block_response(peer_ip, blocks.0)).await? {
-                    true => Ok(()),
+                    true => {
+                        // Record the served blocks in the peer store.
+                        self.router().peer_store().record_blocks_served(peer_ip, num_blocks, elapsed);
+                        Ok(())
+                    }
                     false => bail!("Peer '{peer_ip}' sent an invalid block response"),
                 }
             }
+            Message::HeaderRequest(message) => {
+                let HeaderRequest { start_height, end_height } = &message;
+                // Insert the header request for the peer, and fetch the recent frequency.
+                let frequency = self.router().cache.insert_inbound_header_request(peer_ip);
+                // Check if the number of header requests is within the limit.
+                if frequency > Self::MAXIMUM_HEADER_REQUESTS_PER_INTERVAL {
+                    bail!("Peer '{peer_ip}' is not following the protocol (excessive header requests)")
+                }
+                // Ensure the header request is well-formed.
+                if start_height >= end_height {
+                    bail!("Header request from '{peer_ip}' has an invalid range ({start_height}..{end_height})")
+                }
+                // Ensure that the header request is within the allowed bounds.
+                if end_height - start_height > DataHeaders::<N>::MAXIMUM_NUMBER_OF_HEADERS as u32 {
+                    bail!("Header request from '{peer_ip}' has an excessive range ({start_height}..{end_height})")
+                }
+
+                let node = self.clone();
+                match spawn_blocking(move || node.header_request(peer_ip, message)).await? {
+                    true => Ok(()),
+                    false => bail!("Peer '{peer_ip}' sent an invalid header request"),
+                }
+            }
+            Message::HeaderResponse(message) => {
+                let HeaderResponse { request, headers } = message;
+
+                // Remove the header request, checking if this node previously sent a header request to this peer.
+                if self.router().cache.remove_outbound_header_request(peer_ip, &request).is_none() {
+                    bail!("Peer '{peer_ip}' is not following the protocol (unexpected header response)")
+                }
+                // Perform the deferred non-blocking deserialization of the headers.
+                let (send, recv) = tokio::sync::oneshot::channel();
+                rayon::spawn_fifo(move || {
+                    let headers = headers.deserialize_blocking().map_err(|error| anyhow!("[HeaderResponse] {error}"));
+                    let _ = send.send(headers);
+                });
+                let headers = match recv.await {
+                    Ok(Ok(headers)) => headers,
+                    Ok(Err(error)) => bail!("Peer '{peer_ip}' sent an invalid header response - {error}"),
+                    Err(error) => bail!("Peer '{peer_ip}' sent an invalid header response - {error}"),
+                };
+
+                // Ensure the header response is well-formed.
+                headers.ensure_response_is_well_formed(peer_ip, request.start_height, request.end_height)?;
+
+                // Process the header response.
+                let node = self.clone();
+                match spawn_blocking(move || node.header_response(peer_ip, headers.0)).await? {
+                    true => Ok(()),
+                    false => bail!("Peer '{peer_ip}' sent an invalid header response"),
+                }
+            }
             Message::ChallengeRequest(..) | Message::ChallengeResponse(..) => {
                 // Disconnect as the peer is not following the protocol.
                 bail!("Peer '{peer_ip}' is not following the protocol")
@@ -163,7 +234,7 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
             }
             Message::Ping(message) => {
                 // Ensure the message protocol version is not outdated.
-                if message.version < Message::<N>::VERSION {
+                if Message::<N>::negotiate_version(message.version).is_none() {
                     bail!("Dropping '{peer_ip}' on message version {} (outdated)", message.version);
                 }
 
@@ -172,9 +243,11 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                 if is_client_or_validator && message.block_locators.is_none() {
                     bail!("Peer '{peer_ip}' is a {}, but no block locators were provided", message.node_type);
                 }
-                // If the peer is a prover, ensure there are no block locators.
-                else if message.node_type.is_prover() && message.block_locators.is_some() {
-                    bail!("Peer '{peer_ip}' is a prover or client, but block locators were provided");
+                // If the peer is a prover or light node, ensure there are no block locators.
+                else if (message.node_type.is_prover() || message.node_type.is_light())
+                    && message.block_locators.is_some()
+                {
+                    bail!("Peer '{peer_ip}' is a {}, but block locators were provided", message.node_type);
                 }
 
                 // Update the connected peer.
@@ -237,6 +310,8 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                     trace!("Skipped processing unconfirmed solution '{}' (node is syncing)", message.solution_id);
                     return Ok(());
                 }
+                // Mark the solution as seen, so that it is not requested from the peers that announce it.
+                self.router().cache.insert_inventory(InventoryItem::Solution(message.solution_id));
                 // Update the timestamp for the unconfirmed solution.
                 let seen_before = self.router().cache.insert_inbound_solution(peer_ip, message.solution_id).is_some();
                 // Determine whether to propagate the solution.
@@ -267,6 +342,8 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                     trace!("Skipped processing unconfirmed transaction '{}' (node is syncing)", message.transaction_id);
                     return Ok(());
                 }
+                // Mark the transaction as seen, so that it is not requested from the peers that announce it.
+                self.router().cache.insert_inventory(InventoryItem::Transaction(message.transaction_id));
                 // Update the timestamp for the unconfirmed transaction.
                 let seen_before =
                     self.router().cache.insert_inbound_transaction(peer_ip, message.transaction_id).is_some();
@@ -292,6 +369,55 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
                     false => bail!("Peer '{peer_ip}' sent an invalid unconfirmed transaction"),
                 }
             }
+            Message::InventoryAnnouncement(message) => {
+                // Light nodes do not fetch unconfirmed transactions and solutions.
+                if self.router().node_type().is_light() {
+                    return Ok(());
+                }
+                // Do not request unconfirmed transactions and solutions if the node is too far behind.
+                if self.num_blocks_behind() > SYNC_LENIENCY {
+                    trace!("Skipped processing inventory announcement from '{peer_ip}' (node is syncing)");
+                    return Ok(());
+                }
+                // Determine the announced items to request.
+                let items: Vec<_> = message
+                    .items
+                    .into_iter()
+                    .filter(|item| {
+                        // Record that the peer has the item, so that it is not announced back to the peer.
+                        self.router().cache.insert_outbound_announcement(peer_ip, *item);
+                        // Request the item only if it is unseen, and not already requested from another peer.
+                        // Otherwise, the peer is kept as an alternative to request the item from.
+                        !self.router().cache.contains_inventory(item)
+                            && self.router().cache.insert_outbound_inventory_request(peer_ip, *item)
+                    })
+                    .collect();
+                // Request the unseen items from the peer.
+                if !items.is_empty() {
+                    self.send(peer_ip, Message::InventoryRequest(InventoryRequest { items }));
+                }
+                // Request the items whose requests timed out from their next announcers.
+                self.send_next_inventory_requests();
+                Ok(())
+            }
+            Message::InventoryRequest(message) => {
+                // Update the number of recently requested items.
+                let frequency = self.router().cache.insert_inbound_inventory_request(peer_ip, message.items.len());
+                // Check if the number of requested items is within the limit.
+                if frequency > Self::MAXIMUM_INVENTORY_ITEMS_REQUESTED_PER_INTERVAL {
+                    bail!("Peer '{peer_ip}' is not following the protocol (excessive inventory requests)")
+                }
+                // Send the requested items that are still in the inventory.
+                for item in message.items {
+                    match self.router().inventory().get(&item) {
+                        Some(message) => {
+                            self.send(peer_ip, message);
+                        }
+                        None => trace!("Unable to send {item} to '{peer_ip}' (not in the inventory)"),
+                    }
+                }
+                Ok(())
+            }
         }
     }
 
@@ -301,10 +427,22 @@ pub trait Inbound<N: Network>: Reading + Outbound<N> {
     /// Handles a `BlockResponse` message.
     fn block_response(&self, peer_ip: SocketAddr, _blocks: Vec<Block<N>>) -> bool;
 
+    /// Handles a `HeaderRequest` message.
+    fn header_request(&self, peer_ip: SocketAddr, _message: HeaderRequest) -> bool {
+        debug!("Disconnecting '{peer_ip}' for the following reason - {:?}", DisconnectReason::ProtocolViolation);
+        false
+    }
+
+    /// Handles a `HeaderResponse` message.
+    fn header_response(&self, peer_ip: SocketAddr, _headers: Vec<CertifiedHeader<N>>) -> bool {
+        debug!("Disconnecting '{peer_ip}' for the following reason - {:?}", DisconnectReason::ProtocolViolation);
+        false
+    }
+
     /// Handles a `PeerRequest` message.
     fn peer_request(&self, peer_ip: SocketAddr) -> bool {
-        // Retrieve the connected peers.
-        let peers = self.router().connected_peers();
+        // Retrieve the connected peers that can be reached at their IP.
+        let peers = self.router().connected_listening_peers();
         // Filter out invalid addresses.
         let peers = match self.router().is_dev() {
             // In development mode, relax the validity requirements to make operating devnets more flexible.
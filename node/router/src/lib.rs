@@ -38,21 +38,23 @@ pub use outbound::*;
 mod routing;
 pub use routing::*;
 
-use crate::messages::NodeType;
+use crate::messages::{Compression, InventoryItem, Message, NodeType};
 use snarkos_account::Account;
-use snarkos_node_tcp::{is_bogon_ip, is_unspecified_or_broadcast_ip, Config, Tcp};
+use snarkos_node_tcp::{is_bogon_ip, is_unspecified_or_broadcast_ip, Config, Tcp, TrafficStats};
 use snarkvm::prelude::{Address, Network, PrivateKey, ViewKey};
 
 use anyhow::{bail, Result};
 use parking_lot::{Mutex, RwLock};
+use rand::{rngs::OsRng, seq::SliceRandom};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     future::Future,
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
     ops::Deref,
+    path::PathBuf,
     str::FromStr,
     sync::Arc,
-    time::Instant,
+    time::{Duration, Instant},
 };
 use tokio::task::JoinHandle;
 
@@ -76,10 +78,14 @@ pub struct InnerRouter<N: Network> {
     account: Account<N>,
     /// The cache.
     cache: Cache<N>,
+    /// The inventory of recently propagated unconfirmed transactions and solutions.
+    inventory: Inventory<N>,
     /// The resolver.
     resolver: Resolver,
     /// The set of trusted peers.
     trusted_peers: HashSet<SocketAddr>,
+    /// The configured bootstrap peers and DNS seeds.
+    bootstrap: Bootstrap,
     /// The map of connected peer IPs to their peer handlers.
     connected_peers: RwLock<HashMap<SocketAddr, Peer<N>>>,
     /// The set of handshaking peers. While `Tcp` already recognizes the connecting IP addresses
@@ -91,6 +97,14 @@ pub struct InnerRouter<N: Network> {
     candidate_peers: RwLock<HashSet<SocketAddr>>,
     /// The set of restricted peer IPs.
     restricted_peers: RwLock<HashMap<SocketAddr, Instant>>,
+    /// The history of the peers, which persists across restarts.
+    peer_store: PeerStore,
+    /// The path of the peer store, if it is persisted.
+    peer_store_path: Option<PathBuf>,
+    /// The address at which the peers can reach the listener of the node, if it is configured.
+    external_addr: Option<SocketAddr>,
+    /// The maximum number of connected peers permitted from the same subnet.
+    max_peers_per_subnet: usize,
     /// The spawned handles.
     handles: Mutex<Vec<JoinHandle<()>>>,
     /// If the flag is set, the node will engage in P2P gossip to request more peers.
@@ -100,8 +114,12 @@ pub struct InnerRouter<N: Network> {
 }
 
 impl<N: Network> Router<N> {
+    /// The duration in seconds in between resolutions of the configured bootstrap peers and DNS seeds.
+    const BOOTSTRAP_REFRESH_INTERVAL_IN_SECS: u64 = 30 * 60;
     /// The maximum number of candidate peers permitted to be stored in the node.
     const MAXIMUM_CANDIDATE_PEERS: usize = 10_000;
+    /// The default maximum number of connected peers permitted from the same subnet (/24 for IPv4, /48 for IPv6).
+    const MAXIMUM_CONNECTED_PEERS_PER_SUBNET: usize = 2;
     /// The maximum number of connection failures permitted by an inbound connecting peer.
     const MAXIMUM_CONNECTION_FAILURES: usize = 5;
     /// The duration in seconds after which a connected peer is considered inactive or
@@ -111,33 +129,72 @@ impl<N: Network> Router<N> {
 
 impl<N: Network> Router<N> {
     /// Initializes a new `Router` instance.
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         node_ip: SocketAddr,
         node_type: NodeType,
         account: Account<N>,
         trusted_peers: &[SocketAddr],
+        config: RouterConfig,
         max_peers: u16,
         allow_external_peers: bool,
         is_dev: bool,
     ) -> Result<Self> {
+        let RouterConfig { tcp, bootstrap, peer_store_path, external_addr, max_peers_per_subnet } = config;
         // Initialize the TCP stack.
-        let tcp = Tcp::new(Config::new(node_ip, max_peers));
+        let tcp = Tcp::new(Config {
+            listener_ip: Some(node_ip.ip()),
+            desired_listening_port: Some(node_ip.port()),
+            max_connections: max_peers,
+            ..tcp
+        });
+        // Load the peer store, if it exists.
+        let peer_store = match &peer_store_path {
+            Some(path) if path.exists() => PeerStore::load(path).unwrap_or_else(|error| {
+                warn!("{error}");
+                PeerStore::new()
+            }),
+            _ => PeerStore::new(),
+        };
+        // Initialize the candidate peers with the reputable peers from the peer store.
+        let candidate_peers =
+            peer_store.reputable_peers().into_iter().take(Self::MAXIMUM_CANDIDATE_PEERS).collect::<HashSet<_>>();
+        if !candidate_peers.is_empty() {
+            debug!("Loaded {} candidate peers from the peer store", candidate_peers.len());
+        }
         // Initialize the router.
-        Ok(Self(Arc::new(InnerRouter {
+        let router = Self(Arc::new(InnerRouter {
             tcp,
             node_type,
             account,
             cache: Default::default(),
+            inventory: Default::default(),
             resolver: Default::default(),
             trusted_peers: trusted_peers.iter().copied().collect(),
+            bootstrap: Bootstrap::new(bootstrap, Arc::new(SystemDnsResolver)),
             connected_peers: Default::default(),
             connecting_peers: Default::default(),
-            candidate_peers: Default::default(),
+            candidate_peers: RwLock::new(candidate_peers),
             restricted_peers: Default::default(),
+            peer_store,
+            peer_store_path,
+            external_addr,
+            max_peers_per_subnet: max_peers_per_subnet.unwrap_or(Self::MAXIMUM_CONNECTED_PEERS_PER_SUBNET),
             handles: Default::default(),
             allow_external_peers,
             is_dev,
-        })))
+        }));
+        // Periodically resolve the configured bootstrap peers and DNS seeds.
+        if !router.bootstrap.config().is_empty() {
+            let router_ = router.clone();
+            router.spawn(async move {
+                loop {
+                    router_.bootstrap.refresh().await;
+                    tokio::time::sleep(Duration::from_secs(Self::BOOTSTRAP_REFRESH_INTERVAL_IN_SECS)).await;
+                }
+            });
+        }
+        Ok(router)
     }
 }
 
@@ -162,6 +219,7 @@ impl<N: Network> Router<N> {
                 // If the connection was not allowed, log the error.
                 Err(error) => {
                     router.connecting_peers.lock().remove(&peer_ip);
+                    router.peer_store.record_failure(peer_ip);
                     warn!("Unable to connect to '{peer_ip}' - {error}");
                     false
                 }
@@ -175,6 +233,10 @@ impl<N: Network> Router<N> {
         if self.is_local_ip(&peer_ip) {
             bail!("Dropping connection attempt to '{peer_ip}' (attempted to self-connect)")
         }
+        // Ensure the peer IP is not the proxy, as it would connect to itself.
+        if self.proxy() == Some(peer_ip) {
+            bail!("Dropping connection attempt to '{peer_ip}' (attempted to connect to the proxy)")
+        }
         // Ensure the node does not surpass the maximum number of peer connections.
         if self.number_of_connected_peers() >= self.max_connected_peers() {
             bail!("Dropping connection attempt to '{peer_ip}' (maximum peers reached)")
@@ -183,6 +245,10 @@ impl<N: Network> Router<N> {
         if self.is_connected(&peer_ip) {
             bail!("Dropping connection attempt to '{peer_ip}' (already connected)")
         }
+        // Ensure the subnet of the peer is not full.
+        if self.is_subnet_full(&peer_ip) {
+            bail!("Dropping connection attempt to '{peer_ip}' (too many peers from the same subnet)")
+        }
         // Ensure the peer is not restricted.
         if self.is_restricted(&peer_ip) {
             bail!("Dropping connection attempt to '{peer_ip}' (restricted)")
@@ -219,15 +285,36 @@ impl<N: Network> Router<N> {
         self.tcp.listening_addr().expect("The TCP listener is not enabled")
     }
 
+    /// Returns the listener port advertised to the peers in the handshake, which is the port of the configured external
+    /// address, if any. Otherwise, as the peers cannot reach the listener through the proxy, none (i.e. `0`) is
+    /// advertised if a proxy is set, and the port of the listener is advertised if not.
+    pub fn advertised_port(&self) -> u16 {
+        match (self.external_addr, self.proxy()) {
+            (Some(external_addr), _) => external_addr.port(),
+            (None, Some(_)) => 0,
+            (None, None) => self.local_ip().port(),
+        }
+    }
+
     /// Returns `true` if the given IP is this node.
     pub fn is_local_ip(&self, ip: &SocketAddr) -> bool {
         *ip == self.local_ip()
+            || self.external_addr == Some(*ip)
             || (ip.ip().is_unspecified() || ip.ip().is_loopback()) && ip.port() == self.local_ip().port()
     }
 
-    /// Returns `true` if the given IP is not this node, is not a bogon address, and is not unspecified.
+    /// Returns the address of the SOCKS5 proxy through which outbound connections are established, if any.
+    /// Note that peers see this node's outbound connections as coming from the proxy, not from `local_ip`.
+    pub fn proxy(&self) -> Option<SocketAddr> {
+        self.tcp.config().proxy
+    }
+
+    /// Returns `true` if the given IP is not this node, is not the proxy, is not a bogon address, and is not unspecified.
     pub fn is_valid_peer_ip(&self, ip: &SocketAddr) -> bool {
-        !self.is_local_ip(ip) && !is_bogon_ip(ip.ip()) && !is_unspecified_or_broadcast_ip(ip.ip())
+        !self.is_local_ip(ip)
+            && self.proxy() != Some(*ip)
+            && !is_bogon_ip(ip.ip())
+            && !is_unspecified_or_broadcast_ip(ip.ip())
     }
 
     /// Returns the node type.
@@ -236,12 +323,12 @@ impl<N: Network> Router<N> {
     }
 
     /// Returns the account private key of the node.
-    pub fn private_key(&self) -> &PrivateKey<N> {
+    pub fn private_key(&self) -> Result<&PrivateKey<N>> {
         self.account.private_key()
     }
 
     /// Returns the account view key of the node.
-    pub fn view_key(&self) -> &ViewKey<N> {
+    pub fn view_key(&self) -> Result<&ViewKey<N>> {
         self.account.view_key()
     }
 
@@ -265,6 +352,26 @@ impl<N: Network> Router<N> {
         self.resolver.get_listener(peer_addr)
     }
 
+    /// Returns the compression of the connection with the given (ambiguous) peer address.
+    pub fn compression(&self, peer_addr: &SocketAddr) -> Compression {
+        self.resolve_to_listener(peer_addr)
+            .and_then(|peer_ip| self.get_connected_peer(&peer_ip))
+            .map(|peer| {
+                let our_capabilities = Self::response_capabilities(peer.version()).unwrap_or_default();
+                Compression::new(our_capabilities, peer.capabilities())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the negotiated message version of the connection with the given (ambiguous) peer address,
+    /// or the minimum version if the peer is not connected.
+    pub fn version(&self, peer_addr: &SocketAddr) -> u32 {
+        self.resolve_to_listener(peer_addr)
+            .and_then(|peer_ip| self.get_connected_peer(&peer_ip))
+            .map(|peer| peer.version())
+            .unwrap_or(Message::<N>::MINIMUM_VERSION)
+    }
+
     /// Returns the (ambiguous) peer address from the listener IP address.
     pub fn resolve_to_ambiguous(&self, peer_ip: &SocketAddr) -> Option<SocketAddr> {
         self.resolver.get_ambiguous(peer_ip)
@@ -309,6 +416,56 @@ impl<N: Network> Router<N> {
         self.trusted_peers.contains(ip)
     }
 
+    /// Returns `true` if the given peer IP is exempt from the limit of peers per subnet, i.e. if it is a trusted,
+    /// bootstrap, loopback or private peer, or if the node is in development mode.
+    pub fn is_subnet_exempt(&self, peer_ip: &SocketAddr) -> bool {
+        self.is_dev
+            || self.is_trusted(peer_ip)
+            || peer_ip.ip().is_loopback()
+            || is_private_ip(peer_ip.ip())
+            || self.bootstrap_peers().contains(peer_ip)
+    }
+
+    /// Returns `true` if the node is connected or connecting to the maximum number of other peers
+    /// from the subnet of the given peer IP. The exempt peers are not limited (see [`Self::is_subnet_exempt`]).
+    pub fn is_subnet_full(&self, peer_ip: &SocketAddr) -> bool {
+        if self.is_subnet_exempt(peer_ip) {
+            return false;
+        }
+        self.number_of_peers_in_subnet(peer_ip) >= self.max_peers_per_subnet
+    }
+
+    /// Returns the given number of peers to disconnect from, among the given connected peer IPs: the peers in the
+    /// most crowded subnets go first, so as to protect the diversity, followed by the least reputable ones.
+    /// The exempt peers (see [`Self::is_subnet_exempt`]) do not count towards the crowding of their subnet.
+    pub fn select_peers_to_evict(&self, mut peer_ips: Vec<SocketAddr>, num_peers: usize) -> Vec<SocketAddr> {
+        // Count the connected peers in each subnet.
+        let mut subnet_sizes = HashMap::<IpAddr, usize>::new();
+        for peer_ip in self.connected_peers().iter().filter(|peer_ip| !self.is_subnet_exempt(peer_ip)) {
+            *subnet_sizes.entry(subnet(peer_ip.ip())).or_default() += 1;
+        }
+        let subnet_size = |peer_ip: &SocketAddr| match self.is_subnet_exempt(peer_ip) {
+            true => 0,
+            false => subnet_sizes.get(&subnet(peer_ip.ip())).copied().unwrap_or_default(),
+        };
+
+        // Shuffle the peers, so that the peers with equal reputation are disconnected at random.
+        peer_ips.shuffle(&mut OsRng);
+        self.peer_store.sort_by_reputation(&mut peer_ips);
+        // Protect the diversity, by disconnecting from the peers in the most crowded subnets first.
+        peer_ips.sort_by_key(subnet_size);
+        peer_ips.into_iter().rev().take(num_peers).collect()
+    }
+
+    /// Returns the number of other peers the node is connected or connecting to, from the subnet of the given peer IP.
+    pub fn number_of_peers_in_subnet(&self, peer_ip: &SocketAddr) -> usize {
+        let peer_subnet = subnet(peer_ip.ip());
+        let is_in_subnet = |ip: &SocketAddr| ip != peer_ip && subnet(ip.ip()) == peer_subnet;
+        let num_connected = self.connected_peers.read().keys().filter(|ip| is_in_subnet(ip)).count();
+        let num_connecting = self.connecting_peers.lock().iter().filter(|ip| is_in_subnet(ip)).count();
+        num_connected + num_connecting
+    }
+
     /// Returns the maximum number of connected peers.
     pub fn max_connected_peers(&self) -> usize {
         self.tcp.config().max_connections as usize
@@ -319,6 +476,22 @@ impl<N: Network> Router<N> {
         self.connected_peers.read().len()
     }
 
+    /// Returns the number of connected peers that this node connected to.
+    pub fn number_of_connected_outbound_peers(&self) -> usize {
+        self.connected_peers.read().values().filter(|peer| peer.is_outbound()).count()
+    }
+
+    /// Returns the number of connected peers that connected to this node.
+    pub fn number_of_connected_inbound_peers(&self) -> usize {
+        self.connected_peers.read().values().filter(|peer| !peer.is_outbound()).count()
+    }
+
+    /// Returns the number of connection slots reserved for the peers this node connects to,
+    /// which is a quarter of the maximum number of connected peers.
+    pub fn number_of_reserved_outbound_peers(&self) -> usize {
+        self.max_connected_peers() / 4
+    }
+
     /// Returns the number of connected validators.
     pub fn number_of_connected_validators(&self) -> usize {
         self.connected_peers.read().values().filter(|peer| peer.is_validator()).count()
@@ -359,6 +532,11 @@ impl<N: Network> Router<N> {
         self.connected_peers.read().keys().copied().collect()
     }
 
+    /// Returns the list of connected peers that can be reached at their IP, i.e. the ones that can be shared.
+    pub fn connected_listening_peers(&self) -> Vec<SocketAddr> {
+        self.connected_peers.read().values().filter(|peer| peer.is_listening()).map(|peer| peer.ip()).collect()
+    }
+
     /// Returns the list of connected validators.
     pub fn connected_validators(&self) -> Vec<SocketAddr> {
         self.connected_peers.read().iter().filter(|(_, peer)| peer.is_validator()).map(|(ip, _)| *ip).collect()
@@ -389,10 +567,54 @@ impl<N: Network> Router<N> {
         &self.trusted_peers
     }
 
+    /// Returns the inventory of recently propagated unconfirmed transactions and solutions.
+    pub fn inventory(&self) -> &Inventory<N> {
+        &self.inventory
+    }
+
+    /// Inserts the given message into the inventory and marks it as seen, if it is an unconfirmed
+    /// transaction or solution, returning its inventory item.
+    pub fn insert_inventory(&self, message: &Message<N>) -> Option<InventoryItem<N>> {
+        let item = self.inventory.insert(message)?;
+        self.cache.insert_inventory(item);
+        Some(item)
+    }
+
+    /// Returns the peer store.
+    pub fn peer_store(&self) -> &PeerStore {
+        &self.peer_store
+    }
+
+    /// Stores the peer store on a blocking thread, if it is persisted and changed since it was last stored.
+    pub async fn store_peer_store(&self) {
+        let Some(path) = self.peer_store_path.clone() else {
+            return;
+        };
+        let router = self.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            router.peer_store.prune();
+            router.peer_store.store(&path)
+        })
+        .await;
+        match result {
+            Ok(Ok(())) => (),
+            Ok(Err(error)) => warn!("{error}"),
+            Err(error) => warn!("Couldn't store the peer store - {error}"),
+        }
+    }
+
+    /// Returns the peers discovered through the configured DNS seeds since this method was last called.
+    pub fn take_seed_peers(&self) -> Vec<SocketAddr> {
+        self.bootstrap.take_seed_peers()
+    }
+
     /// Returns the list of bootstrap peers.
     #[allow(clippy::if_same_then_else)]
     pub fn bootstrap_peers(&self) -> Vec<SocketAddr> {
-        if cfg!(feature = "test") || self.is_dev {
+        if !self.bootstrap.config().peers().is_empty() {
+            // The configured bootstrap peers replace the default ones.
+            self.bootstrap.peers()
+        } else if cfg!(feature = "test") || self.is_dev {
             // Development testing contains no bootstrap peers.
             vec![]
         } else if N::ID == snarkvm::console::network::MainnetV0::ID {
@@ -427,6 +649,23 @@ impl<N: Network> Router<N> {
         self.connected_peers.read().iter().map(|(ip, peer)| (*ip, peer.node_type())).collect()
     }
 
+    /// Returns the traffic of the connected peers, by message type.
+    pub fn connected_traffic(&self) -> Vec<(SocketAddr, BTreeMap<&'static str, TrafficStats>)> {
+        self.connected_peers
+            .read()
+            .keys()
+            .filter_map(|peer_ip| {
+                let peer_addr = self.resolve_to_ambiguous(peer_ip)?;
+                Some((*peer_ip, self.tcp.known_peers().get(peer_addr)?.traffic()))
+            })
+            .collect()
+    }
+
+    /// Returns the traffic of all the connections, by message type.
+    pub fn traffic(&self) -> BTreeMap<&'static str, TrafficStats> {
+        self.tcp.stats().traffic()
+    }
+
     #[cfg(feature = "metrics")]
     fn update_metrics(&self) {
         metrics::gauge(metrics::router::CONNECTED, self.connected_peers.read().len() as f64);
@@ -437,6 +676,10 @@ impl<N: Network> Router<N> {
     /// Inserts the given peer into the connected peers.
     pub fn insert_connected_peer(&self, peer: Peer<N>, peer_addr: SocketAddr) {
         let peer_ip = peer.ip();
+        // Record the successful handshake in the peer store, if the peer can be reached again.
+        if peer.is_listening() {
+            self.peer_store.record_handshake(peer_ip, peer.node_type());
+        }
         // Adds a bidirectional map between the listener address and (ambiguous) peer address.
         self.resolver.insert_peer(peer_ip, peer_addr);
         // Add an entry for this `Peer` in the connected peers.
@@ -511,9 +754,20 @@ impl<N: Network> Router<N> {
         // Removes the bidirectional map between the listener address and (ambiguous) peer address.
         self.resolver.remove_peer(&peer_ip);
         // Remove this peer from the connected peers, if it exists.
-        self.connected_peers.write().remove(&peer_ip);
-        // Add the peer to the candidate peers.
-        self.candidate_peers.write().insert(peer_ip);
+        let peer = self.connected_peers.write().remove(&peer_ip);
+        match peer {
+            // Forget the peer if it cannot be reached again.
+            Some(peer) if !peer.is_listening() => (),
+            // Record the last time the peer was seen in the peer store, and add it to the candidate peers.
+            Some(_) => {
+                self.peer_store.record_seen(peer_ip);
+                self.candidate_peers.write().insert(peer_ip);
+            }
+            // Add the peer to the candidate peers.
+            None => {
+                self.candidate_peers.write().insert(peer_ip);
+            }
+        }
         #[cfg(feature = "metrics")]
         self.update_metrics();
     }
@@ -544,5 +798,7 @@ impl<N: Network> Router<N> {
         self.handles.lock().iter().for_each(|handle| handle.abort());
         // Close the listener.
         self.tcp.shut_down().await;
+        // Store the peer store.
+        self.store_peer_store().await;
     }
 }
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use crate::{
-    messages::{Message, Ping},
+    messages::{InventoryAnnouncement, InventoryItem, InventoryRequest, Message, Ping},
     Router,
 };
 use snarkos_node_sync_locators::BlockLocators;
@@ -21,7 +21,7 @@ use snarkos_node_tcp::protocols::Writing;
 use snarkvm::prelude::Network;
 use std::io;
 
-use std::net::SocketAddr;
+use std::{collections::HashMap, net::SocketAddr};
 use tokio::sync::oneshot;
 
 pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
@@ -61,6 +61,10 @@ pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
         if let Message::BlockRequest(request) = message {
             self.router().cache.insert_outbound_block_request(peer_ip, request);
         }
+        // If the message type is a header request, add it to the cache.
+        if let Message::HeaderRequest(request) = message {
+            self.router().cache.insert_outbound_header_request(peer_ip, request);
+        }
         // If the message type is a puzzle request, increment the cache.
         if matches!(message, Message::PuzzleRequest(_)) {
             self.router().cache.increment_outbound_puzzle_requests(peer_ip);
@@ -69,6 +73,10 @@ pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
         if matches!(message, Message::PeerRequest(_)) {
             self.router().cache.increment_outbound_peer_requests(peer_ip);
         }
+        // If the message type is a disconnect, record the reason in the peer store.
+        if let Message::Disconnect(disconnect) = &message {
+            self.router().peer_store().record_disconnect(peer_ip, disconnect.reason);
+        }
         // Retrieve the message name.
         let name = message.name();
         // Send the message to the peer.
@@ -101,13 +109,16 @@ pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
         //     }
         // }
 
+        // Store the unconfirmed transaction or solution, to serve the peers that request it.
+        let item = self.router().insert_inventory(&message);
+
         // Prepare the peers to send to.
         let connected_peers = self.router().connected_peers();
         let peers = connected_peers.iter().filter(|peer_ip| !excluded_peers.contains(peer_ip));
 
         // Iterate through all peers that are not the sender and excluded peers.
         for peer_ip in peers {
-            self.send(*peer_ip, message.clone());
+            self.announce_or_send(*peer_ip, &message, item);
         }
     }
 
@@ -129,22 +140,64 @@ pub trait Outbound<N: Network>: Writing<Message = Message<N>> {
         //     }
         // }
 
+        // Store the unconfirmed transaction or solution, to serve the validators that request it.
+        let item = self.router().insert_inventory(&message);
+
         // Prepare the peers to send to.
         let connected_validators = self.router().connected_validators();
         let peers = connected_validators.iter().filter(|peer_ip| !excluded_peers.contains(peer_ip));
 
         // Iterate through all validators that are not the sender and excluded validators.
         for peer_ip in peers {
-            self.send(*peer_ip, message.clone());
+            self.announce_or_send(*peer_ip, &message, item);
+        }
+    }
+
+    /// Sends the given message to the specified peer. If the message is the given inventory item
+    /// and the peer supports inventory announcements, only its ID is announced to the peer instead.
+    fn announce_or_send(&self, peer_ip: SocketAddr, message: &Message<N>, item: Option<InventoryItem<N>>) {
+        if let Some(item) = item {
+            let announcement = Message::InventoryAnnouncement(InventoryAnnouncement { items: vec![item] });
+            let peer = self.router().get_connected_peer(&peer_ip);
+            if peer.is_some_and(|peer| announcement.is_supported_by(peer.version())) {
+                // Announce the item, unless it was already announced to (or by) the peer.
+                if self.router().cache.insert_outbound_announcement(peer_ip, item).is_none() {
+                    self.send(peer_ip, announcement);
+                }
+                return;
+            }
+        }
+        self.send(peer_ip, message.clone());
+    }
+
+    /// Requests the inventory items whose requests timed out from the next connected peers that announced them.
+    fn send_next_inventory_requests(&self) {
+        // Group the items by the peer to request them from.
+        let mut requests = HashMap::<SocketAddr, Vec<InventoryItem<N>>>::new();
+        let next_requests = self.router().cache.next_outbound_inventory_requests(|ip| self.router().is_connected(ip));
+        for (peer_ip, item) in next_requests {
+            requests.entry(peer_ip).or_default().push(item);
+        }
+        // Request the items from each peer.
+        for (peer_ip, items) in requests {
+            for items in items.chunks(InventoryItem::<N>::MAXIMUM_NUMBER_OF_ITEMS) {
+                self.send(peer_ip, Message::InventoryRequest(InventoryRequest { items: items.to_vec() }));
+            }
         }
     }
 
     /// Returns `true` if the message can be sent.
     fn can_send(&self, peer_ip: SocketAddr, message: &Message<N>) -> bool {
         // Ensure the peer is connected before sending.
-        if !self.router().is_connected(&peer_ip) {
+        let Some(peer) = self.router().get_connected_peer(&peer_ip) else {
             warn!("Attempted to send to a non-connected peer {peer_ip}");
             return false;
+        };
+        // Ensure the message is supported by the negotiated version of the peer.
+        let version = peer.version();
+        if !message.is_supported_by(version) {
+            debug!("Skipping '{}' to '{peer_ip}' (unsupported by version {version})", message.name());
+            return false;
         }
         // Determine whether to send the message.
         match message {
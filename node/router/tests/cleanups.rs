@@ -20,6 +20,7 @@ use peak_alloc::PeakAlloc;
 use snarkos_node_router::Routing;
 use snarkos_node_tcp::{
     protocols::{Disconnect, Handshake},
+    MemoryTransport,
     P2P,
 };
 use snarkvm::{prelude::Rng, utilities::TestRng};
@@ -37,13 +38,14 @@ async fn test_connection_cleanups() {
     // Initialize an Rng.
     let mut rng = TestRng::default();
 
-    // Create 2 routers of random types.
+    // Create 2 routers of random types, on the same in-memory network.
+    let network = MemoryTransport::default();
     let mut nodes = Vec::with_capacity(2);
     for _ in 0..2 {
         let node = match rng.gen_range(0..3) % 3 {
-            0 => client(0, 1).await,
-            1 => prover(0, 1).await,
-            2 => validator(0, 1, &[], true).await,
+            0 => client(&network, 0, 1).await,
+            1 => prover(&network, 0, 1).await,
+            2 => validator(&network, 0, 1, &[], true).await,
             _ => unreachable!(),
         };
 
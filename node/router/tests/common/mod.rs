@@ -20,10 +20,12 @@ use std::{
     env,
     net::{IpAddr, Ipv4Addr, SocketAddr},
     str::FromStr,
+    sync::Arc,
 };
 
 use snarkos_account::Account;
-use snarkos_node_router::{messages::NodeType, Router};
+use snarkos_node_router::{messages::NodeType, Router, RouterConfig};
+use snarkos_node_tcp::{Config, MemoryTransport};
 use snarkvm::prelude::{block::Block, FromBytes, MainnetV0 as CurrentNetwork, Network};
 
 /// A helper macro to print the TCP listening address, along with the connected and connecting peers.
@@ -68,14 +70,21 @@ pub fn initialize_logger(level: u8) {
     let _ = tracing_subscriber::fmt().with_env_filter(filter).with_target(level == 3).try_init();
 }
 
+/// Returns a router configuration over the given in-memory network, so that the routers of a test don't compete
+/// for the ports of the host.
+pub fn router_config(network: &MemoryTransport) -> RouterConfig {
+    RouterConfig { tcp: Config { transport: Arc::new(network.clone()), ..Default::default() }, ..Default::default() }
+}
+
 /// Initializes a client router. Setting the `listening_port = 0` will result in a random port being assigned.
 #[allow(dead_code)]
-pub async fn client(listening_port: u16, max_peers: u16) -> TestRouter<CurrentNetwork> {
+pub async fn client(network: &MemoryTransport, listening_port: u16, max_peers: u16) -> TestRouter<CurrentNetwork> {
     Router::new(
         SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), listening_port),
         NodeType::Client,
         sample_account(),
         &[],
+        router_config(network),
         max_peers,
         true,
         true,
@@ -87,12 +96,13 @@ pub async fn client(listening_port: u16, max_peers: u16) -> TestRouter<CurrentNe
 
 /// Initializes a prover router. Setting the `listening_port = 0` will result in a random port being assigned.
 #[allow(dead_code)]
-pub async fn prover(listening_port: u16, max_peers: u16) -> TestRouter<CurrentNetwork> {
+pub async fn prover(network: &MemoryTransport, listening_port: u16, max_peers: u16) -> TestRouter<CurrentNetwork> {
     Router::new(
         SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), listening_port),
         NodeType::Prover,
         sample_account(),
         &[],
+        router_config(network),
         max_peers,
         true,
         true,
@@ -105,6 +115,7 @@ pub async fn prover(listening_port: u16, max_peers: u16) -> TestRouter<CurrentNe
 /// Initializes a validator router. Setting the `listening_port = 0` will result in a random port being assigned.
 #[allow(dead_code)]
 pub async fn validator(
+    network: &MemoryTransport,
     listening_port: u16,
     max_peers: u16,
     trusted_peers: &[SocketAddr],
@@ -115,6 +126,7 @@ pub async fn validator(
         NodeType::Validator,
         sample_account(),
         trusted_peers,
+        router_config(network),
         max_peers,
         allow_external_peers,
         true,
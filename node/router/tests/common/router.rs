@@ -114,8 +114,15 @@ impl<N: Network> Writing for TestRouter<N> {
 
     /// Creates an [`Encoder`] used to write the outbound messages to the target stream.
     /// The `side` parameter indicates the connection side **from the node's perspective**.
-    fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        MessageCodec::default()
+            .with_compression(self.router().compression(&peer_addr))
+            .with_version(self.router().version(&peer_addr))
+    }
+
+    /// Returns the type of the given outbound message, to account for the traffic by message type.
+    fn message_type(&self, message: &Self::Message) -> Option<&'static str> {
+        Some(message.type_name())
     }
 }
 
@@ -126,8 +133,15 @@ impl<N: Network> Reading for TestRouter<N> {
 
     /// Creates a [`Decoder`] used to interpret messages from the network.
     /// The `side` param indicates the connection side **from the node's perspective**.
-    fn codec(&self, _peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        MessageCodec::default()
+            .with_compression(self.router().compression(&peer_addr))
+            .with_version(self.router().version(&peer_addr))
+    }
+
+    /// Returns the type of the given inbound message, to account for the traffic by message type.
+    fn message_type(&self, message: &Self::Message) -> Option<&'static str> {
+        Some(message.type_name())
     }
 
     /// Processes a message received from the network.
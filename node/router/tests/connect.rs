@@ -15,16 +15,17 @@
 mod common;
 use common::*;
 
-use snarkos_node_tcp::{protocols::Handshake, P2P};
+use snarkos_node_tcp::{protocols::Handshake, MemoryTransport, P2P};
 
 use core::time::Duration;
 use deadline::deadline;
 
 #[tokio::test]
 async fn test_connect_without_handshake() {
-    // Create 2 routers.
-    let node0 = validator(0, 2, &[], true).await;
-    let node1 = client(0, 2).await;
+    // Create 2 routers, on the same in-memory network.
+    let network = MemoryTransport::default();
+    let node0 = validator(&network, 0, 2, &[], true).await;
+    let node1 = client(&network, 0, 2).await;
     assert_eq!(node0.number_of_connected_peers(), 0);
     assert_eq!(node1.number_of_connected_peers(), 0);
 
@@ -78,9 +79,10 @@ async fn test_connect_without_handshake() {
 
 #[tokio::test]
 async fn test_connect_with_handshake() {
-    // Create 2 routers.
-    let node0 = validator(0, 2, &[], true).await;
-    let node1 = client(0, 2).await;
+    // Create 2 routers, on the same in-memory network.
+    let network = MemoryTransport::default();
+    let node0 = validator(&network, 0, 2, &[], true).await;
+    let node1 = client(&network, 0, 2).await;
     assert_eq!(node0.number_of_connected_peers(), 0);
     assert_eq!(node1.number_of_connected_peers(), 0);
 
@@ -159,8 +161,9 @@ async fn test_connect_with_handshake() {
 
 #[tokio::test]
 async fn test_validator_connection() {
-    // Create first router and start listening.
-    let node0 = validator(0, 2, &[], false).await;
+    // Create first router and start listening, on an in-memory network.
+    let network = MemoryTransport::default();
+    let node0 = validator(&network, 0, 2, &[], false).await;
     assert_eq!(node0.number_of_connected_peers(), 0);
     node0.enable_handshake().await;
     node0.tcp().enable_listener().await.unwrap();
@@ -169,7 +172,7 @@ async fn test_validator_connection() {
     let addr0 = node0.local_ip();
 
     // Create second router, trusting the first router, and start listening.
-    let node1 = validator(0, 2, &[addr0], false).await;
+    let node1 = validator(&network, 0, 2, &[addr0], false).await;
     assert_eq!(node1.number_of_connected_peers(), 0);
     node1.enable_handshake().await;
     node1.tcp().enable_listener().await.unwrap();
@@ -223,9 +226,10 @@ async fn test_validator_connection() {
 #[ignore]
 #[tokio::test]
 async fn test_connect_simultaneously_with_handshake() {
-    // Create 2 routers.
-    let node0 = validator(0, 2, &[], true).await;
-    let node1 = client(0, 2).await;
+    // Create 2 routers, on the same in-memory network.
+    let network = MemoryTransport::default();
+    let node0 = validator(&network, 0, 2, &[], true).await;
+    let node1 = client(&network, 0, 2).await;
     assert_eq!(node0.number_of_connected_peers(), 0);
     assert_eq!(node1.number_of_connected_peers(), 0);
 
@@ -15,15 +15,16 @@
 mod common;
 use common::*;
 
-use snarkos_node_tcp::{protocols::Handshake, P2P};
+use snarkos_node_tcp::{protocols::Handshake, MemoryTransport, P2P};
 
 use core::time::Duration;
 
 #[tokio::test]
 async fn test_disconnect_without_handshake() {
-    // Create 2 routers.
-    let node0 = validator(0, 1, &[], true).await;
-    let node1 = client(0, 1).await;
+    // Create 2 routers, on the same in-memory network.
+    let network = MemoryTransport::default();
+    let node0 = validator(&network, 0, 1, &[], true).await;
+    let node1 = client(&network, 0, 1).await;
     assert_eq!(node0.number_of_connected_peers(), 0);
     assert_eq!(node1.number_of_connected_peers(), 0);
 
@@ -63,9 +64,10 @@ async fn test_disconnect_without_handshake() {
 
 #[tokio::test]
 async fn test_disconnect_with_handshake() {
-    // Create 2 routers.
-    let node0 = validator(0, 1, &[], true).await;
-    let node1 = client(0, 1).await;
+    // Create 2 routers, on the same in-memory network.
+    let network = MemoryTransport::default();
+    let node0 = validator(&network, 0, 1, &[], true).await;
+    let node1 = client(&network, 0, 1).await;
     assert_eq!(node0.number_of_connected_peers(), 0);
     assert_eq!(node1.number_of_connected_peers(), 0);
 
@@ -0,0 +1,144 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod common;
+use common::*;
+
+use snarkos_node_router::{messages::NodeType, Router, RouterConfig};
+use snarkos_node_tcp::{protocols::Handshake, Config, MemoryTransport, Transport, P2P};
+use snarkvm::prelude::MainnetV0 as CurrentNetwork;
+
+use core::time::Duration;
+use deadline::deadline;
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// The IP address from which the proxy relays the connections.
+const EXIT_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2));
+
+/// Spawns a SOCKS5 stand-in on the given in-memory network, which relays the connections from `EXIT_IP`.
+async fn spawn_proxy(network: &MemoryTransport) -> SocketAddr {
+    let listener = network.bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await.unwrap();
+    let proxy_addr = listener.local_addr().unwrap();
+    let network = network.clone();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let network = network.clone();
+            tokio::spawn(async move {
+                // Accept connecting without authentication.
+                let mut greeting = [0u8; 3];
+                stream.read_exact(&mut greeting).await.unwrap();
+                stream.write_all(&[5, 0]).await.unwrap();
+                // Read the (IPv4) target address.
+                let mut request = [0u8; 10];
+                stream.read_exact(&mut request).await.unwrap();
+                let ip = Ipv4Addr::new(request[4], request[5], request[6], request[7]);
+                let target = SocketAddr::new(IpAddr::V4(ip), u16::from_be_bytes([request[8], request[9]]));
+                // Relay the connection to the target.
+                let mut relay = network.connect(target, Some(EXIT_IP)).await.unwrap();
+                stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).await.unwrap();
+                let _ = tokio::io::copy_bidirectional(&mut stream, &mut relay).await;
+            });
+        }
+    });
+    proxy_addr
+}
+
+/// Initializes a client router, which connects to its peers through the given proxy.
+async fn proxied_client(
+    network: &MemoryTransport,
+    proxy: SocketAddr,
+    external_addr: Option<SocketAddr>,
+) -> TestRouter<CurrentNetwork> {
+    let tcp = Config { transport: Arc::new(network.clone()), proxy: Some(proxy), ..Default::default() };
+    Router::new(
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+        NodeType::Client,
+        sample_account(),
+        &[],
+        RouterConfig { tcp, external_addr, ..Default::default() },
+        2,
+        true,
+        true,
+    )
+    .await
+    .expect("couldn't create client router")
+    .into()
+}
+
+#[tokio::test]
+async fn test_proxy_advertises_no_listener() {
+    let network = MemoryTransport::default();
+    let proxy = spawn_proxy(&network).await;
+
+    // Create a router behind the proxy, and a router it connects to.
+    let node0 = proxied_client(&network, proxy, None).await;
+    let node1 = client(&network, 0, 2).await;
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Ensure the router behind the proxy does not advertise its listener, which cannot be reached through the proxy.
+    assert_eq!(node0.advertised_port(), 0);
+
+    // Connect node0 to node1, through the proxy.
+    node0.connect(node1.local_ip());
+    let node1_ = node1.clone();
+    deadline!(Duration::from_secs(5), move || node1_.number_of_connected_peers() == 1);
+
+    // Ensure node1 identifies node0 by the address of the connection, and does not share it with other peers.
+    let peer = node1.get_connected_peers().pop().unwrap();
+    assert_eq!(peer.ip().ip(), EXIT_IP);
+    assert_ne!(peer.ip().port(), node0.local_ip().port());
+    assert!(!peer.is_listening());
+    assert!(node1.connected_listening_peers().is_empty());
+
+    // Ensure node1 does not attempt to reconnect to node0 once they are disconnected.
+    node1.disconnect(peer.ip());
+    let node1_ = node1.clone();
+    deadline!(Duration::from_secs(5), move || node1_.number_of_connected_peers() == 0);
+    assert_eq!(node1.number_of_candidate_peers(), 0);
+}
+
+#[tokio::test]
+async fn test_proxy_advertises_external_addr() {
+    let network = MemoryTransport::default();
+    let proxy = spawn_proxy(&network).await;
+
+    // Create a router behind the proxy, with an external address, and a router it connects to.
+    let external_addr = SocketAddr::new(EXIT_IP, 4999);
+    let node0 = proxied_client(&network, proxy, Some(external_addr)).await;
+    let node1 = client(&network, 0, 2).await;
+    for node in [&node0, &node1] {
+        node.enable_handshake().await;
+        node.tcp().enable_listener().await.unwrap();
+    }
+
+    // Ensure the router behind the proxy advertises the port of its external address, and recognizes it as its own.
+    assert_eq!(node0.advertised_port(), external_addr.port());
+    assert!(node0.is_local_ip(&external_addr));
+
+    // Connect node0 to node1, through the proxy.
+    node0.connect(node1.local_ip());
+    let node1_ = node1.clone();
+    deadline!(Duration::from_secs(5), move || node1_.is_connected(&external_addr));
+
+    // Ensure node1 shares the external address of node0 with other peers.
+    assert!(node1.get_connected_peer(&external_addr).unwrap().is_listening());
+    assert_eq!(node1.connected_listening_peers(), vec![external_addr]);
+}
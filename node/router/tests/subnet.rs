@@ -0,0 +1,109 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod common;
+use common::*;
+
+use snarkos_node_router::{messages::NodeType, Router, RouterConfig};
+use snarkos_node_tcp::{protocols::Handshake, MemoryTransport, P2P};
+use snarkvm::prelude::MainnetV0 as CurrentNetwork;
+
+use core::time::Duration;
+use std::{
+    collections::HashSet,
+    net::{IpAddr, SocketAddr},
+};
+
+/// Initializes a listening client router at the given IP, outside of development mode,
+/// so that the limit of peers per subnet applies.
+async fn client_at(
+    network: &MemoryTransport,
+    ip: &str,
+    max_peers_per_subnet: Option<usize>,
+) -> TestRouter<CurrentNetwork> {
+    let router: TestRouter<CurrentNetwork> = Router::new(
+        SocketAddr::new(ip.parse::<IpAddr>().unwrap(), 0),
+        NodeType::Client,
+        sample_account(),
+        &[],
+        RouterConfig { max_peers_per_subnet, ..router_config(network) },
+        10,
+        true,
+        false,
+    )
+    .await
+    .expect("couldn't create client router")
+    .into();
+    router.enable_handshake().await;
+    router.tcp().enable_listener().await.unwrap();
+    router
+}
+
+/// Connects the routers at the given IPs to the given node, and returns them once their connections are settled.
+async fn connect_from(
+    network: &MemoryTransport,
+    node: &TestRouter<CurrentNetwork>,
+    ips: &[&str],
+) -> Vec<TestRouter<CurrentNetwork>> {
+    let mut peers = Vec::new();
+    for ip in ips {
+        let peer = client_at(network, ip, None).await;
+        peer.connect(node.local_ip());
+        // Sleep briefly, as the connection is either accepted or dropped.
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        peers.push(peer);
+    }
+    peers
+}
+
+#[tokio::test]
+async fn test_subnet_limit_inbound() {
+    let network = MemoryTransport::default();
+    let node = client_at(&network, "35.0.0.1", None).await;
+
+    // Ensure only the default number of peers is accepted from a public subnet.
+    connect_from(&network, &node, &["34.0.0.1", "34.0.0.2", "34.0.0.3"]).await;
+    assert_eq!(node.number_of_connected_peers(), 2);
+    assert!(node.is_subnet_full(&"34.0.0.9:4130".parse().unwrap()));
+
+    // Ensure the private subnets are exempt from the limit.
+    connect_from(&network, &node, &["10.0.0.1", "10.0.0.2", "10.0.0.3", "192.168.0.1", "192.168.0.2"]).await;
+    assert_eq!(node.number_of_connected_peers(), 7);
+    assert!(!node.is_subnet_full(&"10.0.0.9:4130".parse().unwrap()));
+}
+
+#[tokio::test]
+async fn test_subnet_limit_is_configurable() {
+    let network = MemoryTransport::default();
+    let node = client_at(&network, "35.0.0.1", Some(3)).await;
+
+    // Ensure the configured number of peers is accepted from a public subnet.
+    connect_from(&network, &node, &["34.0.0.1", "34.0.0.2", "34.0.0.3", "34.0.0.4"]).await;
+    assert_eq!(node.number_of_connected_peers(), 3);
+}
+
+#[tokio::test]
+async fn test_subnet_eviction() {
+    let network = MemoryTransport::default();
+    let node = client_at(&network, "35.0.0.1", None).await;
+
+    // Connect peers from a crowded public subnet, a crowded private subnet, and another public subnet.
+    let peers =
+        connect_from(&network, &node, &["34.0.0.1", "34.0.0.2", "10.0.0.1", "10.0.0.2", "10.0.0.3", "36.0.0.1"]).await;
+    assert_eq!(node.number_of_connected_peers(), 6);
+
+    // Ensure the peers of the crowded public subnet are evicted first, as the private peers are exempt.
+    let evicted = node.select_peers_to_evict(node.connected_peers(), 2).into_iter().collect::<HashSet<_>>();
+    assert_eq!(evicted, peers[..2].iter().map(|peer| peer.local_ip()).collect());
+}
@@ -20,10 +20,12 @@ use snarkos_node_bft::ledger_service::CoreLedgerService;
 use snarkos_node_rest::Rest;
 use snarkos_node_router::{
     messages::{Message, NodeType, UnconfirmedSolution},
+    peer_store_path,
     Heartbeat,
     Inbound,
     Outbound,
     Router,
+    RouterConfig,
     Routing,
 };
 use snarkos_node_sync::{BlockSync, BlockSyncMode};
@@ -80,6 +82,7 @@ impl<N: Network, C: ConsensusStorage<N>> Client<N, C> {
         rest_rps: u32,
         account: Account<N>,
         trusted_peers: &[SocketAddr],
+        router_config: RouterConfig,
         genesis: Block<N>,
         cdn: Option<String>,
         storage_mode: StorageMode,
@@ -115,6 +118,7 @@ impl<N: Network, C: ConsensusStorage<N>> Client<N, C> {
             NodeType::Client,
             account,
             trusted_peers,
+            RouterConfig { peer_store_path: Some(peer_store_path(N::ID, &storage_mode)), ..router_config },
             Self::MAXIMUM_NUMBER_OF_PEERS as u16,
             allow_external_peers,
             matches!(storage_mode, StorageMode::Development(_)),
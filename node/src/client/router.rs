@@ -18,7 +18,10 @@ use snarkos_node_router::{
         BlockRequest,
         BlockResponse,
         DataBlocks,
+        DataHeaders,
         DisconnectReason,
+        HeaderRequest,
+        HeaderResponse,
         MessageCodec,
         Ping,
         Pong,
@@ -28,7 +31,7 @@ use snarkos_node_router::{
     Routing,
 };
 use snarkos_node_sync::communication_service::CommunicationService;
-use snarkos_node_tcp::{Connection, ConnectionSide, Tcp};
+use snarkos_node_tcp::{protocols::MessagePriority, Connection, ConnectionSide, Tcp};
 use snarkvm::{
     ledger::narwhal::Data,
     prelude::{block::Transaction, Network},
@@ -98,8 +101,25 @@ impl<N: Network, C: ConsensusStorage<N>> Writing for Client<N, C> {
 
     /// Creates an [`Encoder`] used to write the outbound messages to the target stream.
     /// The `side` parameter indicates the connection side **from the node's perspective**.
-    fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        MessageCodec::default()
+            .with_compression(self.router.compression(&peer_addr))
+            .with_version(self.router.version(&peer_addr))
+            .with_capture(peer_addr)
+    }
+
+    /// Returns the type of the given outbound message, to account for the traffic by message type.
+    fn message_type(&self, message: &Self::Message) -> Option<&'static str> {
+        Some(message.type_name())
+    }
+
+    /// Returns the priority class of the given outbound message.
+    /// The blocks are sent after the other messages, so that a sync burst does not delay them.
+    fn message_priority(&self, message: &Self::Message) -> MessagePriority {
+        match message {
+            Message::BlockResponse(_) => MessagePriority::Bulk,
+            _ => MessagePriority::Normal,
+        }
     }
 }
 
@@ -110,8 +130,16 @@ impl<N: Network, C: ConsensusStorage<N>> Reading for Client<N, C> {
 
     /// Creates a [`Decoder`] used to interpret messages from the network.
     /// The `side` param indicates the connection side **from the node's perspective**.
-    fn codec(&self, _peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        MessageCodec::default()
+            .with_compression(self.router.compression(&peer_addr))
+            .with_version(self.router.version(&peer_addr))
+            .with_capture(peer_addr)
+    }
+
+    /// Returns the type of the given inbound message, to account for the traffic by message type.
+    fn message_type(&self, message: &Self::Message) -> Option<&'static str> {
+        Some(message.type_name())
     }
 
     /// Processes a message received from the network.
@@ -136,11 +164,15 @@ impl<N: Network, C: ConsensusStorage<N>> Client<N, C> {
         peer_addr: SocketAddr,
         message: <Client<N, C> as snarkos_node_tcp::protocols::Reading>::Message,
     ) {
+        // Determine if the peer is disconnecting, as this is not a protocol violation.
+        let is_disconnect = matches!(message, Message::Disconnect(_));
         // Process the message. Disconnect if the peer violated the protocol.
         if let Err(error) = self.inbound(peer_addr, message).await {
             if let Some(peer_ip) = self.router().resolve_to_listener(&peer_addr) {
                 warn!("Disconnecting from '{peer_ip}' - {error}");
-                Outbound::send(self, peer_ip, Message::Disconnect(DisconnectReason::ProtocolViolation.into()));
+                if !is_disconnect {
+                    Outbound::send(self, peer_ip, Message::Disconnect(DisconnectReason::ProtocolViolation.into()));
+                }
                 // Disconnect from this peer.
                 self.router().disconnect(peer_ip);
             }
@@ -214,6 +246,23 @@ impl<N: Network, C: ConsensusStorage<N>> Inbound<N> for Client<N, C> {
         true
     }
 
+    /// Handles a `HeaderRequest` message.
+    fn header_request(&self, peer_ip: SocketAddr, message: HeaderRequest) -> bool {
+        let HeaderRequest { start_height, end_height } = &message;
+
+        // Retrieve the certified headers within the requested range.
+        let headers = match crate::certified_headers(&self.ledger, *start_height..*end_height) {
+            Ok(headers) => Data::Object(DataHeaders(headers)),
+            Err(error) => {
+                error!("Failed to retrieve headers {start_height} to {end_height} from the ledger - {error}");
+                return false;
+            }
+        };
+        // Send the `HeaderResponse` message to the peer.
+        Outbound::send(self, peer_ip, Message::HeaderResponse(HeaderResponse { request: message, headers }));
+        true
+    }
+
     /// Handles a `BlockResponse` message.
     fn block_response(&self, peer_ip: SocketAddr, blocks: Vec<Block<N>>) -> bool {
         // Tries to advance with blocks from the sync module.
@@ -33,6 +33,9 @@ pub use snarkvm;
 mod client;
 pub use client::*;
 
+mod light;
+pub use light::*;
+
 mod prover;
 pub use prover::*;
 
@@ -42,6 +45,9 @@ pub use validator::*;
 mod node;
 pub use node::*;
 
+mod snapshot;
+pub use snapshot::*;
+
 mod traits;
 pub use traits::*;
 
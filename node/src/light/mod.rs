@@ -0,0 +1,263 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod router;
+
+use crate::traits::NodeInterface;
+use snarkos_account::Account;
+use snarkos_node_bft::ledger_service::ProverLedgerService;
+use snarkos_node_rest::LightRest;
+use snarkos_node_router::{
+    messages::{CertifiedHeader, DataHeaders, HeaderRequest, Message, NodeType},
+    peer_store_path,
+    Heartbeat,
+    Inbound,
+    Outbound,
+    Router,
+    RouterConfig,
+    Routing,
+};
+use snarkos_node_sync::{BlockSync, BlockSyncMode, HeaderSync};
+use snarkos_node_tcp::{
+    protocols::{Disconnect, Handshake, OnConnect, Reading, Writing},
+    P2P,
+};
+use snarkvm::{
+    console::network::Network,
+    ledger::{block::Block, store::ConsensusStorage, Ledger},
+};
+
+use aleo_std::StorageMode;
+use anyhow::Result;
+use core::time::Duration;
+use parking_lot::Mutex;
+use std::{
+    net::SocketAddr,
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+use tokio::task::JoinHandle;
+
+/// The interval in seconds between attempts to request headers from peers.
+const HEADER_SYNC_INTERVAL_IN_SECS: u64 = 1;
+/// The maximum duration in seconds to wait for a header response before requesting the headers again.
+const HEADER_REQUEST_TIMEOUT_IN_SECS: u64 = 10;
+
+/// A light node syncs and verifies only the block headers, capable of serving verified queries.
+#[derive(Clone)]
+pub struct Light<N: Network> {
+    /// The router of the node.
+    router: Router<N>,
+    /// The REST server of the node.
+    rest: Option<LightRest<N, Self>>,
+    /// The block sync module, which tracks the block locators of the peers.
+    sync: Arc<BlockSync<N>>,
+    /// The header sync module.
+    header_sync: Arc<HeaderSync<N>>,
+    /// The genesis block.
+    genesis: Block<N>,
+    /// The timestamp of the pending header request, if one was sent.
+    pending_request: Arc<Mutex<Option<Instant>>>,
+    /// The spawned handles.
+    handles: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    /// The shutdown signal.
+    shutdown: Arc<AtomicBool>,
+}
+
+impl<N: Network> Light<N> {
+    /// Initializes a new light node.
+    pub async fn new(
+        node_ip: SocketAddr,
+        rest_ip: Option<SocketAddr>,
+        rest_rps: u32,
+        rest_peers: Vec<String>,
+        account: Account<N>,
+        trusted_peers: &[SocketAddr],
+        router_config: RouterConfig,
+        genesis: Block<N>,
+        storage_mode: StorageMode,
+        shutdown: Arc<AtomicBool>,
+    ) -> Result<Self> {
+        // Initialize the signal handler.
+        let signal_node = Self::handle_signals(shutdown.clone());
+
+        // Initialize the ledger service.
+        let ledger_service = Arc::new(ProverLedgerService::new());
+        // Initialize the sync module.
+        let sync = BlockSync::new(BlockSyncMode::Router, ledger_service.clone());
+        // Initialize the header sync module.
+        let header_sync = Arc::new(HeaderSync::new(&genesis)?);
+        // Determine if the light node should allow external peers.
+        let allow_external_peers = true;
+
+        // Initialize the node router.
+        let router = Router::new(
+            node_ip,
+            NodeType::Light,
+            account,
+            trusted_peers,
+            RouterConfig { peer_store_path: Some(peer_store_path(N::ID, &storage_mode)), ..router_config },
+            Self::MAXIMUM_NUMBER_OF_PEERS as u16,
+            allow_external_peers,
+            matches!(storage_mode, StorageMode::Development(_)),
+        )
+        .await?;
+        // Initialize the node.
+        let mut node = Self {
+            router,
+            rest: None,
+            sync: Arc::new(sync),
+            header_sync,
+            genesis,
+            pending_request: Default::default(),
+            handles: Default::default(),
+            shutdown,
+        };
+
+        // Initialize the REST server.
+        if let Some(rest_ip) = rest_ip {
+            node.rest = Some(
+                LightRest::start(rest_ip, rest_rps, rest_peers, node.header_sync.clone(), Arc::new(node.clone()))
+                    .await?,
+            );
+        }
+        // Initialize the routing.
+        node.initialize_routing().await;
+        // Initialize the header sync.
+        node.initialize_header_sync();
+        // Initialize the notification message loop.
+        node.handles.lock().push(crate::start_notification_message_loop());
+        // Pass the node to the signal handler.
+        let _ = signal_node.set(node.clone());
+        // Return the node.
+        Ok(node)
+    }
+
+    /// Returns the REST server.
+    pub fn rest(&self) -> &Option<LightRest<N, Self>> {
+        &self.rest
+    }
+
+    /// Returns the header sync module.
+    pub fn header_sync(&self) -> &Arc<HeaderSync<N>> {
+        &self.header_sync
+    }
+}
+
+#[async_trait]
+impl<N: Network> NodeInterface<N> for Light<N> {
+    /// Shuts down the node.
+    async fn shut_down(&self) {
+        info!("Shutting down...");
+
+        // Shut down the header sync.
+        debug!("Shutting down the header sync...");
+        self.shutdown.store(true, Ordering::Relaxed);
+
+        // Abort the tasks.
+        debug!("Shutting down the light node...");
+        self.handles.lock().iter().for_each(|handle| handle.abort());
+
+        // Shut down the router.
+        self.router.shut_down().await;
+
+        info!("Node has shut down.");
+    }
+}
+
+impl<N: Network> Light<N> {
+    /// Initializes the header sync loop.
+    fn initialize_header_sync(&self) {
+        let node = self.clone();
+        self.handles.lock().push(tokio::spawn(async move {
+            loop {
+                // If the Ctrl-C handler registered the signal, stop the header sync.
+                if node.shutdown.load(Ordering::Relaxed) {
+                    debug!("Shutting down the header sync...");
+                    break;
+                }
+                // Request the next headers from the peers.
+                node.try_header_sync();
+                // Sleep briefly before the next attempt.
+                tokio::time::sleep(Duration::from_secs(HEADER_SYNC_INTERVAL_IN_SECS)).await;
+            }
+        }));
+    }
+
+    /// Requests the next headers from the highest peer that serves headers, unless a request is pending.
+    fn try_header_sync(&self) {
+        // Skip if a header request is still pending.
+        let mut pending_request = self.pending_request.lock();
+        if pending_request
+            .is_some_and(|requested_at| requested_at.elapsed() < Duration::from_secs(HEADER_REQUEST_TIMEOUT_IN_SECS))
+        {
+            return;
+        }
+
+        // Find the sync peers that are able to serve headers.
+        let Some((sync_peers, _)) = self.sync.find_sync_peers() else { return };
+        let is_supported = |peer_ip: &SocketAddr| {
+            let request = Message::HeaderRequest(HeaderRequest { start_height: 0, end_height: 1 });
+            self.router.get_connected_peer(peer_ip).is_some_and(|peer| request.is_supported_by(peer.version()))
+        };
+        // Choose the peer with the highest block height.
+        let Some((peer_ip, peer_height)) =
+            sync_peers.into_iter().filter(|(peer_ip, _)| is_supported(peer_ip)).max_by_key(|(_, height)| *height)
+        else {
+            return;
+        };
+
+        // Determine the range of headers to request.
+        let start_height = self.header_sync.latest_height() + 1;
+        if start_height > peer_height {
+            return;
+        }
+        let end_height = (peer_height + 1).min(start_height + DataHeaders::<N>::MAXIMUM_NUMBER_OF_HEADERS as u32);
+
+        // Request the headers from the peer.
+        debug!("Requesting headers {start_height} to {end_height} from '{peer_ip}'");
+        if Outbound::send(self, peer_ip, Message::HeaderRequest(HeaderRequest { start_height, end_height })).is_some() {
+            *pending_request = Some(Instant::now());
+        }
+    }
+}
+
+/// Returns the certified headers of the blocks within the given range of block heights,
+/// including the committee with every header whose block updated the committee.
+pub(crate) fn certified_headers<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    heights: Range<u32>,
+) -> Result<Vec<CertifiedHeader<N>>> {
+    // Retrieve the committee before the first block.
+    let mut previous_committee = match heights.start.checked_sub(1) {
+        Some(height) => ledger.get_committee(height)?,
+        None => None,
+    };
+    let mut headers = Vec::with_capacity(heights.len());
+    for block in ledger.get_blocks(heights)? {
+        // Include the committee if the block updated it.
+        let committee = ledger.get_committee(block.height())?;
+        let is_updated = committee.as_ref().map(|c| c.id()) != previous_committee.as_ref().map(|c| c.id());
+        headers.push(CertifiedHeader {
+            committee: if is_updated { committee.clone() } else { None },
+            ..CertifiedHeader::from(&block)
+        });
+        previous_committee = committee;
+    }
+    Ok(headers)
+}
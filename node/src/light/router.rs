@@ -0,0 +1,268 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+use snarkos_node_router::messages::{
+    BlockRequest,
+    CertifiedHeader,
+    DisconnectReason,
+    Message,
+    MessageCodec,
+    Ping,
+    Pong,
+    UnconfirmedSolution,
+    UnconfirmedTransaction,
+};
+use snarkos_node_tcp::{Connection, ConnectionSide, Tcp};
+use snarkvm::prelude::{
+    block::{Header, Transaction},
+    puzzle::Solution,
+    Field,
+    Network,
+    Zero,
+};
+
+use std::{io, net::SocketAddr};
+
+impl<N: Network> P2P for Light<N> {
+    /// Returns a reference to the TCP instance.
+    fn tcp(&self) -> &Tcp {
+        self.router.tcp()
+    }
+}
+
+#[async_trait]
+impl<N: Network> Handshake for Light<N> {
+    /// Performs the handshake protocol.
+    async fn perform_handshake(&self, mut connection: Connection) -> io::Result<Connection> {
+        // Perform the handshake.
+        let peer_addr = connection.addr();
+        let conn_side = connection.side();
+        let stream = self.borrow_stream(&mut connection);
+        let genesis_header = *self.genesis.header();
+        let restrictions_id = Field::zero(); // Light nodes may bypass restrictions, since they do not validate transactions.
+        self.router.handshake(peer_addr, stream, conn_side, genesis_header, restrictions_id).await?;
+
+        Ok(connection)
+    }
+}
+
+#[async_trait]
+impl<N: Network> OnConnect for Light<N>
+where
+    Self: Outbound<N>,
+{
+    async fn on_connect(&self, peer_addr: SocketAddr) {
+        // Resolve the peer address to the listener address.
+        let Some(peer_ip) = self.router.resolve_to_listener(&peer_addr) else { return };
+        // Send the first `Ping` message to the peer.
+        self.send_ping(peer_ip, None);
+    }
+}
+
+#[async_trait]
+impl<N: Network> Disconnect for Light<N> {
+    /// Any extra operations to be performed during a disconnect.
+    async fn handle_disconnect(&self, peer_addr: SocketAddr) {
+        if let Some(peer_ip) = self.router.resolve_to_listener(&peer_addr) {
+            self.sync.remove_peer(&peer_ip);
+            self.router.remove_connected_peer(peer_ip);
+        }
+    }
+}
+
+#[async_trait]
+impl<N: Network> Writing for Light<N> {
+    type Codec = MessageCodec<N>;
+    type Message = Message<N>;
+
+    /// Creates an [`Encoder`] used to write the outbound messages to the target stream.
+    /// The `side` parameter indicates the connection side **from the node's perspective**.
+    fn codec(&self, peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        MessageCodec::default()
+            .with_compression(self.router.compression(&peer_addr))
+            .with_version(self.router.version(&peer_addr))
+            .with_capture(peer_addr)
+    }
+
+    /// Returns the type of the given outbound message, to account for the traffic by message type.
+    fn message_type(&self, message: &Self::Message) -> Option<&'static str> {
+        Some(message.type_name())
+    }
+}
+
+#[async_trait]
+impl<N: Network> Reading for Light<N> {
+    type Codec = MessageCodec<N>;
+    type Message = Message<N>;
+
+    /// Creates a [`Decoder`] used to interpret messages from the network.
+    /// The `side` param indicates the connection side **from the node's perspective**.
+    fn codec(&self, peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        MessageCodec::default()
+            .with_compression(self.router.compression(&peer_addr))
+            .with_version(self.router.version(&peer_addr))
+            .with_capture(peer_addr)
+    }
+
+    /// Returns the type of the given inbound message, to account for the traffic by message type.
+    fn message_type(&self, message: &Self::Message) -> Option<&'static str> {
+        Some(message.type_name())
+    }
+
+    /// Processes a message received from the network.
+    async fn process_message(&self, peer_addr: SocketAddr, message: Self::Message) -> io::Result<()> {
+        // Determine if the peer is disconnecting, as this is not a protocol violation.
+        let is_disconnect = matches!(message, Message::Disconnect(_));
+        // Process the message. Disconnect if the peer violated the protocol.
+        if let Err(error) = self.inbound(peer_addr, message).await {
+            if let Some(peer_ip) = self.router().resolve_to_listener(&peer_addr) {
+                warn!("Disconnecting from '{peer_addr}' - {error}");
+                if !is_disconnect {
+                    Outbound::send(self, peer_ip, Message::Disconnect(DisconnectReason::ProtocolViolation.into()));
+                }
+                // Disconnect from this peer.
+                self.router().disconnect(peer_ip);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<N: Network> Routing<N> for Light<N> {}
+
+impl<N: Network> Heartbeat<N> for Light<N> {}
+
+impl<N: Network> Outbound<N> for Light<N> {
+    /// Returns a reference to the router.
+    fn router(&self) -> &Router<N> {
+        &self.router
+    }
+
+    /// Returns `true` if the node is synced up to the latest block (within the given tolerance).
+    fn is_block_synced(&self) -> bool {
+        self.num_blocks_behind() == 0
+    }
+
+    /// Returns the number of blocks this node is behind the greatest peer height.
+    fn num_blocks_behind(&self) -> u32 {
+        self.sync
+            .find_sync_peers()
+            .and_then(|(sync_peers, _)| sync_peers.into_values().max())
+            .map_or(0, |peer_height| peer_height.saturating_sub(self.header_sync.latest_height()))
+    }
+}
+
+#[async_trait]
+impl<N: Network> Inbound<N> for Light<N> {
+    /// Handles a `BlockRequest` message.
+    fn block_request(&self, peer_ip: SocketAddr, _message: BlockRequest) -> bool {
+        debug!("Disconnecting '{peer_ip}' for the following reason - {:?}", DisconnectReason::ProtocolViolation);
+        false
+    }
+
+    /// Handles a `BlockResponse` message.
+    fn block_response(&self, peer_ip: SocketAddr, _blocks: Vec<Block<N>>) -> bool {
+        debug!("Disconnecting '{peer_ip}' for the following reason - {:?}", DisconnectReason::ProtocolViolation);
+        false
+    }
+
+    /// Verifies the headers, and appends them to the header chain.
+    fn header_response(&self, peer_ip: SocketAddr, headers: Vec<CertifiedHeader<N>>) -> bool {
+        // Clear the pending header request.
+        self.pending_request.lock().take();
+        // Skip the headers if the node already advanced past them (e.g. after a timed out request).
+        if headers.first().map_or(true, |header| header.height() <= self.header_sync.latest_height()) {
+            trace!("Skipping a stale header response from '{peer_ip}'");
+            return true;
+        }
+        // Verify and append the headers.
+        match self.header_sync.insert_headers(&headers) {
+            Ok(()) => {
+                debug!("Synced headers up to block {} from '{peer_ip}'", self.header_sync.latest_height());
+                true
+            }
+            Err(error) => {
+                warn!("Peer '{peer_ip}' sent invalid headers - {error}");
+                false
+            }
+        }
+    }
+
+    /// Processes the block locators and sends back a `Pong` message.
+    fn ping(&self, peer_ip: SocketAddr, message: Ping<N>) -> bool {
+        // If block locators were provided, then update the peer in the sync pool.
+        if let Some(block_locators) = message.block_locators {
+            // Check the block locators are valid, and update the peer in the sync pool.
+            if let Err(error) = self.sync.update_peer_locators(peer_ip, block_locators) {
+                warn!("Peer '{peer_ip}' sent invalid block locators: {error}");
+                return false;
+            }
+        }
+
+        // Send a `Pong` message to the peer.
+        Outbound::send(self, peer_ip, Message::Pong(Pong { is_fork: Some(false) }));
+        true
+    }
+
+    /// Sleeps for a period and then sends a `Ping` message to the peer.
+    fn pong(&self, peer_ip: SocketAddr, _message: Pong) -> bool {
+        // Spawn an asynchronous task for the `Ping` request.
+        let self_clone = self.clone();
+        tokio::spawn(async move {
+            // Sleep for the preset time before sending a `Ping` request.
+            tokio::time::sleep(Duration::from_secs(Self::PING_SLEEP_IN_SECS)).await;
+            // Check that the peer is still connected.
+            if self_clone.router().is_connected(&peer_ip) {
+                // Send a `Ping` message to the peer.
+                self_clone.send_ping(peer_ip, None);
+            }
+        });
+        true
+    }
+
+    /// Disconnects on receipt of a `PuzzleRequest` message.
+    fn puzzle_request(&self, peer_ip: SocketAddr) -> bool {
+        debug!("Disconnecting '{peer_ip}' for the following reason - {:?}", DisconnectReason::ProtocolViolation);
+        false
+    }
+
+    /// Disconnects on receipt of a `PuzzleResponse` message.
+    fn puzzle_response(&self, peer_ip: SocketAddr, _epoch_hash: N::BlockHash, _header: Header<N>) -> bool {
+        debug!("Disconnecting '{peer_ip}' for the following reason - {:?}", DisconnectReason::ProtocolViolation);
+        false
+    }
+
+    /// Ignores the unconfirmed solution, as light nodes do not verify solutions.
+    async fn unconfirmed_solution(
+        &self,
+        _peer_ip: SocketAddr,
+        _serialized: UnconfirmedSolution<N>,
+        _solution: Solution<N>,
+    ) -> bool {
+        true
+    }
+
+    /// Ignores the unconfirmed transaction, as light nodes do not verify transactions.
+    async fn unconfirmed_transaction(
+        &self,
+        _peer_ip: SocketAddr,
+        _serialized: UnconfirmedTransaction<N>,
+        _transaction: Transaction<N>,
+    ) -> bool {
+        true
+    }
+}
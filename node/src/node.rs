@@ -12,9 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{traits::NodeInterface, Client, Prover, Validator};
+use crate::{traits::NodeInterface, Client, Light, Prover, Validator};
 use snarkos_account::Account;
-use snarkos_node_router::messages::NodeType;
+use snarkos_node_router::{messages::NodeType, RouterConfig};
 use snarkvm::prelude::{
     block::Block,
     store::helpers::{memory::ConsensusMemory, rocksdb::ConsensusDB},
@@ -38,6 +38,8 @@ pub enum Node<N: Network> {
     Prover(Arc<Prover<N, ConsensusMemory<N>>>),
     /// A client node is a full node, capable of querying with the network.
     Client(Arc<Client<N, ConsensusDB<N>>>),
+    /// A light node syncs and verifies only the block headers, capable of serving verified queries.
+    Light(Arc<Light<N>>),
 }
 
 impl<N: Network> Node<N> {
@@ -49,6 +51,7 @@ impl<N: Network> Node<N> {
         rest_rps: u32,
         account: Account<N>,
         trusted_peers: &[SocketAddr],
+        router_config: RouterConfig,
         trusted_validators: &[SocketAddr],
         genesis: Block<N>,
         cdn: Option<String>,
@@ -65,6 +68,7 @@ impl<N: Network> Node<N> {
                 rest_rps,
                 account,
                 trusted_peers,
+                router_config,
                 trusted_validators,
                 genesis,
                 cdn,
@@ -82,11 +86,14 @@ impl<N: Network> Node<N> {
         node_ip: SocketAddr,
         account: Account<N>,
         trusted_peers: &[SocketAddr],
+        router_config: RouterConfig,
         genesis: Block<N>,
         storage_mode: StorageMode,
         shutdown: Arc<AtomicBool>,
     ) -> Result<Self> {
-        Ok(Self::Prover(Arc::new(Prover::new(node_ip, account, trusted_peers, genesis, storage_mode, shutdown).await?)))
+        Ok(Self::Prover(Arc::new(
+            Prover::new(node_ip, account, trusted_peers, router_config, genesis, storage_mode, shutdown).await?,
+        )))
     }
 
     /// Initializes a new client node.
@@ -96,14 +103,56 @@ impl<N: Network> Node<N> {
         rest_rps: u32,
         account: Account<N>,
         trusted_peers: &[SocketAddr],
+        router_config: RouterConfig,
         genesis: Block<N>,
         cdn: Option<String>,
         storage_mode: StorageMode,
         shutdown: Arc<AtomicBool>,
     ) -> Result<Self> {
         Ok(Self::Client(Arc::new(
-            Client::new(node_ip, rest_ip, rest_rps, account, trusted_peers, genesis, cdn, storage_mode, shutdown)
-                .await?,
+            Client::new(
+                node_ip,
+                rest_ip,
+                rest_rps,
+                account,
+                trusted_peers,
+                router_config,
+                genesis,
+                cdn,
+                storage_mode,
+                shutdown,
+            )
+            .await?,
+        )))
+    }
+
+    /// Initializes a new light node.
+    pub async fn new_light(
+        node_ip: SocketAddr,
+        rest_ip: Option<SocketAddr>,
+        rest_rps: u32,
+        rest_peers: Vec<String>,
+        account: Account<N>,
+        trusted_peers: &[SocketAddr],
+        router_config: RouterConfig,
+        genesis: Block<N>,
+        storage_mode: StorageMode,
+        shutdown: Arc<AtomicBool>,
+    ) -> Result<Self> {
+        Ok(Self::Light(Arc::new(
+            Light::new(
+                node_ip,
+                rest_ip,
+                rest_rps,
+                rest_peers,
+                account,
+                trusted_peers,
+                router_config,
+                genesis,
+                storage_mode,
+                shutdown,
+            )
+            .await?,
         )))
     }
 
@@ -113,24 +162,27 @@ impl<N: Network> Node<N> {
             Self::Validator(validator) => validator.node_type(),
             Self::Prover(prover) => prover.node_type(),
             Self::Client(client) => client.node_type(),
+            Self::Light(light) => light.node_type(),
         }
     }
 
     /// Returns the account private key of the node.
-    pub fn private_key(&self) -> &PrivateKey<N> {
+    pub fn private_key(&self) -> Result<&PrivateKey<N>> {
         match self {
             Self::Validator(node) => node.private_key(),
             Self::Prover(node) => node.private_key(),
             Self::Client(node) => node.private_key(),
+            Self::Light(node) => node.private_key(),
         }
     }
 
     /// Returns the account view key of the node.
-    pub fn view_key(&self) -> &ViewKey<N> {
+    pub fn view_key(&self) -> Result<&ViewKey<N>> {
         match self {
             Self::Validator(node) => node.view_key(),
             Self::Prover(node) => node.view_key(),
             Self::Client(node) => node.view_key(),
+            Self::Light(node) => node.view_key(),
         }
     }
 
@@ -140,6 +192,7 @@ impl<N: Network> Node<N> {
             Self::Validator(node) => node.address(),
             Self::Prover(node) => node.address(),
             Self::Client(node) => node.address(),
+            Self::Light(node) => node.address(),
         }
     }
 
@@ -149,6 +202,7 @@ impl<N: Network> Node<N> {
             Self::Validator(node) => node.is_dev(),
             Self::Prover(node) => node.is_dev(),
             Self::Client(node) => node.is_dev(),
+            Self::Light(node) => node.is_dev(),
         }
     }
 }
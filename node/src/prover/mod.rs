@@ -19,10 +19,12 @@ use snarkos_account::Account;
 use snarkos_node_bft::ledger_service::ProverLedgerService;
 use snarkos_node_router::{
     messages::{Message, NodeType, UnconfirmedSolution},
+    peer_store_path,
     Heartbeat,
     Inbound,
     Outbound,
     Router,
+    RouterConfig,
     Routing,
 };
 use snarkos_node_sync::{BlockSync, BlockSyncMode};
@@ -90,6 +92,7 @@ impl<N: Network, C: ConsensusStorage<N>> Prover<N, C> {
         node_ip: SocketAddr,
         account: Account<N>,
         trusted_peers: &[SocketAddr],
+        router_config: RouterConfig,
         genesis: Block<N>,
         storage_mode: StorageMode,
         shutdown: Arc<AtomicBool>,
@@ -110,6 +113,7 @@ impl<N: Network, C: ConsensusStorage<N>> Prover<N, C> {
             NodeType::Prover,
             account,
             trusted_peers,
+            RouterConfig { peer_store_path: Some(peer_store_path(N::ID, &storage_mode)), ..router_config },
             Self::MAXIMUM_NUMBER_OF_PEERS as u16,
             allow_external_peers,
             matches!(storage_mode, StorageMode::Development(_)),
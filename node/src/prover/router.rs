@@ -83,8 +83,16 @@ impl<N: Network, C: ConsensusStorage<N>> Writing for Prover<N, C> {
 
     /// Creates an [`Encoder`] used to write the outbound messages to the target stream.
     /// The `side` parameter indicates the connection side **from the node's perspective**.
-    fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        MessageCodec::default()
+            .with_compression(self.router.compression(&peer_addr))
+            .with_version(self.router.version(&peer_addr))
+            .with_capture(peer_addr)
+    }
+
+    /// Returns the type of the given outbound message, to account for the traffic by message type.
+    fn message_type(&self, message: &Self::Message) -> Option<&'static str> {
+        Some(message.type_name())
     }
 }
 
@@ -95,17 +103,29 @@ impl<N: Network, C: ConsensusStorage<N>> Reading for Prover<N, C> {
 
     /// Creates a [`Decoder`] used to interpret messages from the network.
     /// The `side` param indicates the connection side **from the node's perspective**.
-    fn codec(&self, _peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+    fn codec(&self, peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+        MessageCodec::default()
+            .with_compression(self.router.compression(&peer_addr))
+            .with_version(self.router.version(&peer_addr))
+            .with_capture(peer_addr)
+    }
+
+    /// Returns the type of the given inbound message, to account for the traffic by message type.
+    fn message_type(&self, message: &Self::Message) -> Option<&'static str> {
+        Some(message.type_name())
     }
 
     /// Processes a message received from the network.
     async fn process_message(&self, peer_addr: SocketAddr, message: Self::Message) -> io::Result<()> {
+        // Determine if the peer is disconnecting, as this is not a protocol violation.
+        let is_disconnect = matches!(message, Message::Disconnect(_));
         // Process the message. Disconnect if the peer violated the protocol.
         if let Err(error) = self.inbound(peer_addr, message).await {
             if let Some(peer_ip) = self.router().resolve_to_listener(&peer_addr) {
                 warn!("Disconnecting from '{peer_addr}' - {error}");
-                Outbound::send(self, peer_ip, Message::Disconnect(DisconnectReason::ProtocolViolation.into()));
+                if !is_disconnect {
+                    Outbound::send(self, peer_ip, Message::Disconnect(DisconnectReason::ProtocolViolation.into()));
+                }
                 // Disconnect from this peer.
                 self.router().disconnect(peer_ip);
             }
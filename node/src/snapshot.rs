@@ -0,0 +1,460 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use snarkos_node_sync::compute_block_hash;
+use snarkvm::{
+    console::program::BLOCKS_DEPTH,
+    ledger::{
+        authority::Authority,
+        committee::Committee,
+        store::{
+            helpers::rocksdb::ConsensusDB,
+            BlockStorage,
+            BlockStore,
+            CommitteeStorage,
+            CommitteeStore,
+            ConsensusStore,
+        },
+    },
+    prelude::{Block, Field, FromBytes, Network, ToBits, ToBytes},
+};
+
+use aleo_std::StorageMode;
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// The version of the snapshot format.
+pub const SNAPSHOT_VERSION: u16 = 1;
+/// The name of the snapshot manifest file.
+const MANIFEST_FILE: &str = "manifest.json";
+/// The name of the snapshot file containing the checkpoint block.
+const CHECKPOINT_FILE: &str = "checkpoint.block";
+/// The name of the snapshot directory containing the ledger storage.
+const LEDGER_DIR: &str = "ledger";
+
+/// The manifest of a ledger snapshot.
+///
+/// A snapshot is a directory containing the manifest, the checkpoint block, and a copy of the ledger storage
+/// (including the finalize state) of a stopped node, whose latest block is the checkpoint block.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// The version of the snapshot format.
+    pub version: u16,
+    /// The network ID of the ledger.
+    pub network: u16,
+    /// The height of the checkpoint block.
+    pub height: u32,
+    /// The hash of the checkpoint block.
+    pub block_hash: String,
+    /// The SHA-256 checksum of each file in the snapshot, keyed by relative path.
+    pub files: BTreeMap<String, String>,
+}
+
+/// Creates a snapshot of the ledger in the given storage, at the given output directory.
+/// Note: The node must not be running, so that the ledger storage is not modified while it is copied.
+pub fn create_snapshot<N: Network>(storage_mode: StorageMode, output: &Path) -> Result<SnapshotManifest> {
+    let ledger_dir = aleo_std::aleo_ledger_dir(N::ID, storage_mode.clone());
+    ensure!(ledger_dir.exists(), "No ledger was found in \"{}\"", ledger_dir.display());
+    ensure!(!output.exists(), "The snapshot output \"{}\" already exists", output.display());
+
+    // Copy the ledger storage, before it is opened to read the checkpoint block.
+    let mut files = BTreeMap::new();
+    copy_dir(&ledger_dir, &output.join(LEDGER_DIR), Path::new(LEDGER_DIR), &mut files)?;
+
+    // Retrieve the checkpoint block, which is the latest block in the ledger.
+    let store = ConsensusStore::<N, ConsensusDB<N>>::open(storage_mode)?;
+    let Some(height) = store.block_store().max_height() else { bail!("The ledger does not contain any blocks") };
+    let Some(block_hash) = store.block_store().get_block_hash(height)? else {
+        bail!("The ledger is missing the hash of block {height}")
+    };
+    let Some(block) = store.block_store().get_block(&block_hash)? else {
+        bail!("The ledger is missing block {height} ({block_hash})")
+    };
+
+    // Write the checkpoint block.
+    let block_bytes = block.to_bytes_le()?;
+    fs::write(output.join(CHECKPOINT_FILE), &block_bytes)?;
+    files.insert(CHECKPOINT_FILE.to_string(), checksum(&block_bytes));
+
+    // Write the manifest.
+    let manifest = SnapshotManifest {
+        version: SNAPSHOT_VERSION,
+        network: N::ID,
+        height,
+        block_hash: block_hash.to_string(),
+        files,
+    };
+    fs::write(output.join(MANIFEST_FILE), serde_json::to_vec_pretty(&manifest)?)?;
+    Ok(manifest)
+}
+
+/// Restores the ledger in the given storage from the snapshot at the given path,
+/// after verifying the snapshot against the given trusted hash of the checkpoint block.
+/// Returns the height of the checkpoint block.
+///
+/// The manifest is not trusted: its file paths must stay within the snapshot, and the restored blocks are verified
+/// against the checkpoint block (see `verify_ledger`). However, the finalize state (e.g. the balances and the bonded
+/// stake) cannot be verified without replaying the blocks, so it is trusted from the provider of the snapshot.
+/// The blocks and certificates after the checkpoint are then synced from the validators by the block sync.
+///
+/// If the ledger already contains the checkpoint block, the snapshot is not restored again.
+pub fn restore_snapshot<N: Network>(
+    snapshot: &Path,
+    trusted_hash: N::BlockHash,
+    storage_mode: StorageMode,
+) -> Result<u32> {
+    // Read the manifest.
+    let manifest: SnapshotManifest = serde_json::from_slice(
+        &fs::read(snapshot.join(MANIFEST_FILE)).context("Failed to read the snapshot manifest")?,
+    )?;
+    ensure!(manifest.version == SNAPSHOT_VERSION, "Unsupported snapshot version {}", manifest.version);
+    ensure!(manifest.network == N::ID, "The snapshot is for network {}, not network {}", manifest.network, N::ID);
+    ensure!(
+        manifest.block_hash == trusted_hash.to_string(),
+        "The snapshot checkpoint ({}) does not match the trusted block hash ({trusted_hash})",
+        manifest.block_hash
+    );
+
+    // If the ledger already exists, ensure it contains the checkpoint block.
+    let ledger_dir = aleo_std::aleo_ledger_dir(N::ID, storage_mode.clone());
+    if ledger_dir.read_dir().map_or(false, |mut entries| entries.next().is_some()) {
+        ensure_checkpoint::<N>(storage_mode, manifest.height, trusted_hash).with_context(|| {
+            format!("The ledger in \"{}\" already exists and differs from the snapshot", ledger_dir.display())
+        })?;
+        info!("The ledger already contains the snapshot checkpoint at block {}", manifest.height);
+        return Ok(manifest.height);
+    }
+
+    // Ensure the file paths stay within the snapshot, and the ledger storage.
+    for file in manifest.files.keys() {
+        ensure!(is_relative_path(Path::new(file)), "The snapshot file path '{file}' is not a plain relative path");
+    }
+    // Verify the checksums of the snapshot files.
+    for (file, expected) in &manifest.files {
+        let bytes =
+            fs::read(snapshot.join(file)).with_context(|| format!("Failed to read the snapshot file '{file}'"))?;
+        ensure!(checksum(&bytes) == *expected, "The snapshot file '{file}' is corrupted");
+    }
+    // Verify the checkpoint block.
+    // Note: Deserializing the block recomputes its hash, so the block is consistent with the trusted hash.
+    let block = Block::<N>::from_bytes_le(&fs::read(snapshot.join(CHECKPOINT_FILE))?)?;
+    ensure!(block.hash() == trusted_hash, "The snapshot checkpoint block does not match the trusted block hash");
+    ensure!(block.height() == manifest.height, "The snapshot checkpoint block does not match the manifest height");
+
+    // Copy the ledger storage, only including the files that were verified.
+    info!("Restoring the ledger from the snapshot at block {} ({trusted_hash})...", manifest.height);
+    let prefix = Path::new(LEDGER_DIR);
+    for file in manifest.files.keys().map(Path::new) {
+        let Ok(relative_path) = file.strip_prefix(prefix) else {
+            continue;
+        };
+        let target = ledger_dir.join(relative_path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(snapshot.join(file), target)?;
+    }
+
+    // Ensure the restored ledger is consistent with the checkpoint block.
+    let result = ConsensusStore::<N, ConsensusDB<N>>::open(storage_mode)
+        .and_then(|store| verify_ledger(store.block_store(), store.finalize_store().committee_store(), &block));
+    if let Err(error) = result {
+        let _ = fs::remove_dir_all(&ledger_dir);
+        bail!("The restored ledger is invalid - {error}");
+    }
+    Ok(manifest.height)
+}
+
+/// Verifies the given ledger stores against the given (trusted) checkpoint block:
+/// - The ledger ends at the checkpoint block.
+/// - The block hashes are recomputed from the stored headers, back to the genesis block.
+/// - The transactions of each block match the transactions root in its header.
+/// - The block tree of the previous blocks matches the state root in the checkpoint header.
+/// - The committees in the restored finalize state match the committees of the checkpoint certificates.
+///
+/// Note: The rest of the finalize state is not verified, as the block headers do not commit to it.
+fn verify_ledger<N: Network, B: BlockStorage<N>, C: CommitteeStorage<N>>(
+    blocks: &BlockStore<N, B>,
+    committees: &CommitteeStore<N, C>,
+    checkpoint: &Block<N>,
+) -> Result<()> {
+    let height = checkpoint.height();
+    // Ensure the ledger ends at the checkpoint block.
+    ensure!(blocks.max_height() == Some(height), "The ledger does not end at the checkpoint block {height}");
+    ensure!(
+        blocks.get_block_hash(height)? == Some(checkpoint.hash()),
+        "Block {height} in the ledger is not the checkpoint block"
+    );
+
+    // Recompute the block hashes from the checkpoint block back to the genesis block.
+    let mut hashes = vec![checkpoint.hash()];
+    for height in (0..height).rev() {
+        let next_hash = hashes[hashes.len() - 1];
+        let Some(hash) = blocks.get_block_hash(height)? else { bail!("Block {height} is missing") };
+        let Some(previous_hash) = blocks.get_previous_block_hash(height + 1)? else {
+            bail!("The previous hash of block {} is missing", height + 1)
+        };
+        let Some(header) = blocks.get_block_header(&next_hash)? else {
+            bail!("The header of block {} is missing", height + 1)
+        };
+        ensure!(previous_hash == hash, "Block {} does not link to block {height}", height + 1);
+        ensure!(compute_block_hash(hash, &header)? == next_hash, "Block {} has an invalid hash", height + 1);
+        verify_transactions(blocks, &next_hash, header.transactions_root())?;
+        hashes.push(hash);
+    }
+    hashes.reverse();
+    // Ensure the transactions of the genesis block match its header.
+    // Note: The genesis hash is authenticated by the block hash of block 1, or is the checkpoint hash itself.
+    let Some(genesis_header) = blocks.get_block_header(&hashes[0])? else { bail!("The header of block 0 is missing") };
+    verify_transactions(blocks, &hashes[0], genesis_header.transactions_root())?;
+
+    // Ensure the block tree of the previous blocks matches the state root in the checkpoint header.
+    // Note: The genesis block does not have a previous state root.
+    if height > 0 {
+        let leaves = hashes[..height as usize].iter().map(|hash| hash.to_bits_le()).collect::<Vec<_>>();
+        let tree = N::merkle_tree_bhp::<BLOCKS_DEPTH>(&leaves)?;
+        ensure!(
+            *tree.root() == *checkpoint.previous_state_root(),
+            "The block tree does not match the state root of the checkpoint block"
+        );
+    }
+
+    // Ensure the committees in the finalize state match the committees that certified the checkpoint block.
+    if let Authority::Quorum(subdag) = checkpoint.authority() {
+        for certificate in subdag.values().flatten() {
+            let round = certificate.round();
+            let committee_round = round.saturating_sub(Committee::<N>::COMMITTEE_LOOKBACK_RANGE);
+            let Some(committee) = committees.get_committee_for_round(committee_round)? else {
+                bail!("The committee for round {committee_round} is missing")
+            };
+            ensure!(
+                committee.id() == certificate.batch_header().committee_id(),
+                "The committee for round {committee_round} does not match the checkpoint certificates"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Ensures the stored transactions of the given block match the given transactions root of its header.
+fn verify_transactions<N: Network, B: BlockStorage<N>>(
+    blocks: &BlockStore<N, B>,
+    block_hash: &N::BlockHash,
+    transactions_root: Field<N>,
+) -> Result<()> {
+    let Some(transactions) = blocks.get_block_transactions(block_hash)? else {
+        bail!("The transactions of block {block_hash} are missing")
+    };
+    ensure!(
+        transactions.to_transactions_root()? == transactions_root,
+        "The transactions of block {block_hash} do not match its header"
+    );
+    Ok(())
+}
+
+/// Returns `true` if the given path only consists of plain file or directory names,
+/// i.e. it is relative and does not escape its base directory.
+fn is_relative_path(path: &Path) -> bool {
+    path.components().next().is_some() && path.components().all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Ensures the ledger in the given storage contains the given checkpoint block.
+fn ensure_checkpoint<N: Network>(storage_mode: StorageMode, height: u32, trusted_hash: N::BlockHash) -> Result<()> {
+    let store = ConsensusStore::<N, ConsensusDB<N>>::open(storage_mode)?;
+    match store.block_store().get_block_hash(height)? {
+        Some(hash) if hash == trusted_hash => Ok(()),
+        Some(hash) => bail!("Block {height} has hash {hash}, expected {trusted_hash}"),
+        None => bail!("Block {height} is missing"),
+    }
+}
+
+/// Returns the hex-encoded SHA-256 checksum of the given bytes.
+fn checksum(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Recursively copies the files in the given source directory to the given target directory,
+/// and inserts their checksums keyed by their path relative to the snapshot.
+/// The lock and log files of the storage are skipped.
+fn copy_dir(source: &Path, target: &Path, prefix: &Path, files: &mut BTreeMap<String, String>) -> Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let relative_path: PathBuf = prefix.join(&name);
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target.join(&name), &relative_path, files)?;
+            continue;
+        }
+        let name = name.to_string_lossy();
+        if name == "LOCK" || name.starts_with("LOG") {
+            continue;
+        }
+        let bytes = fs::read(entry.path())?;
+        fs::write(target.join(&*name), &bytes)?;
+        files.insert(relative_path.to_string_lossy().into_owned(), checksum(&bytes));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::ledger::{ledger_test_helpers::sample_genesis_block, store::helpers::memory::ConsensusMemory, Ledger};
+
+    type CurrentNetwork = snarkvm::prelude::MainnetV0;
+
+    /// Returns a new temporary directory for the given test.
+    fn sample_dir(test: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("snarkos-snapshot-{test}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        root
+    }
+
+    /// Writes a snapshot with the given checkpoint block and ledger file to the given directory,
+    /// and returns its manifest.
+    fn write_snapshot(snapshot: &Path, block: &Block<CurrentNetwork>, ledger_file: &[u8]) -> SnapshotManifest {
+        fs::create_dir_all(snapshot.join(LEDGER_DIR)).unwrap();
+        let block_bytes = block.to_bytes_le().unwrap();
+        fs::write(snapshot.join(CHECKPOINT_FILE), &block_bytes).unwrap();
+        fs::write(snapshot.join(LEDGER_DIR).join("000001.sst"), ledger_file).unwrap();
+        let manifest = SnapshotManifest {
+            version: SNAPSHOT_VERSION,
+            network: CurrentNetwork::ID,
+            height: block.height(),
+            block_hash: block.hash().to_string(),
+            files: [
+                (CHECKPOINT_FILE.to_string(), checksum(&block_bytes)),
+                (format!("{LEDGER_DIR}/000001.sst"), checksum(ledger_file)),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        write_manifest(snapshot, &manifest);
+        manifest
+    }
+
+    /// Writes the given manifest to the snapshot at the given directory.
+    fn write_manifest(snapshot: &Path, manifest: &SnapshotManifest) {
+        fs::write(snapshot.join(MANIFEST_FILE), serde_json::to_vec_pretty(manifest).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_copy_dir() {
+        let root = sample_dir("copy");
+        let (source, target) = (root.join("source"), root.join("target"));
+        fs::create_dir_all(source.join("nested")).unwrap();
+        fs::write(source.join("000001.sst"), b"table").unwrap();
+        fs::write(source.join("nested").join("CURRENT"), b"manifest").unwrap();
+        fs::write(source.join("LOCK"), b"").unwrap();
+        fs::write(source.join("LOG.old.1"), b"log").unwrap();
+
+        let mut files = BTreeMap::new();
+        copy_dir(&source, &target, Path::new(LEDGER_DIR), &mut files).unwrap();
+
+        // Ensure the lock and log files are skipped, and the checksums match the copies.
+        assert_eq!(files.len(), 2);
+        for (file, expected) in &files {
+            let relative_path = Path::new(file).strip_prefix(LEDGER_DIR).unwrap();
+            assert_eq!(checksum(&fs::read(target.join(relative_path)).unwrap()), *expected);
+        }
+        assert!(!target.join("LOCK").exists());
+        assert!(!target.join("LOG.old.1").exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_restore_tampered_snapshot() {
+        let root = sample_dir("tampered");
+        let (snapshot, ledger) = (root.join("snapshot"), root.join("ledger"));
+        let storage_mode = StorageMode::Custom(ledger.clone());
+        let genesis = Block::<CurrentNetwork>::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap();
+        let restore = |trusted_hash| restore_snapshot::<CurrentNetwork>(&snapshot, trusted_hash, storage_mode.clone());
+
+        // Ensure a snapshot for another checkpoint is rejected.
+        let manifest = write_snapshot(&snapshot, &genesis, b"table");
+        let other_genesis = sample_genesis_block();
+        assert!(restore(other_genesis.hash()).is_err());
+
+        // Ensure a corrupted ledger file is rejected.
+        fs::write(snapshot.join(LEDGER_DIR).join("000001.sst"), b"forged").unwrap();
+        assert!(restore(genesis.hash()).is_err());
+
+        // Ensure a forged manifest, for a checkpoint block that does not match the trusted hash, is rejected.
+        let forged = write_snapshot(&snapshot, &other_genesis, b"table");
+        write_manifest(&snapshot, &SnapshotManifest { block_hash: genesis.hash().to_string(), ..forged });
+        assert!(restore(genesis.hash()).is_err());
+
+        // Ensure a snapshot for another network is rejected.
+        write_snapshot(&snapshot, &genesis, b"table");
+        write_manifest(&snapshot, &SnapshotManifest { network: CurrentNetwork::ID + 1, ..manifest });
+        assert!(restore(genesis.hash()).is_err());
+
+        // Ensure nothing was restored.
+        assert!(!ledger.exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_restore_snapshot_with_traversal_paths() {
+        let root = sample_dir("traversal");
+        let (snapshot, ledger) = (root.join("snapshot"), root.join("ledger"));
+        let storage_mode = StorageMode::Custom(ledger.clone());
+        let genesis = Block::<CurrentNetwork>::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap();
+        let manifest = write_snapshot(&snapshot, &genesis, b"table");
+
+        // Write a file that a forged manifest attempts to copy outside of the ledger.
+        fs::write(snapshot.join(LEDGER_DIR).join("escaped"), b"forged").unwrap();
+        let escaped = root.join("escaped");
+        for file in [format!("{LEDGER_DIR}/../../escaped"), "../escaped".to_string(), "/etc/hostname".to_string()] {
+            let mut files = manifest.files.clone();
+            files.insert(file.clone(), checksum(b"forged"));
+            write_manifest(&snapshot, &SnapshotManifest { files, ..manifest.clone() });
+
+            // Ensure the snapshot is rejected, and nothing was written.
+            let result = restore_snapshot::<CurrentNetwork>(&snapshot, genesis.hash(), storage_mode.clone());
+            assert!(result.unwrap_err().to_string().contains("is not a plain relative path"), "{file}");
+            assert!(!escaped.exists());
+            assert!(!ledger.exists());
+        }
+
+        // Ensure the paths are checked component by component.
+        assert!(is_relative_path(Path::new("ledger/000001.sst")));
+        assert!(!is_relative_path(Path::new("")));
+        assert!(!is_relative_path(Path::new("ledger/../ledger/000001.sst")));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_verify_ledger() {
+        let genesis = Block::<CurrentNetwork>::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap();
+        let ledger =
+            Ledger::<CurrentNetwork, ConsensusMemory<_>>::load(genesis.clone(), StorageMode::Production).unwrap();
+        let (blocks, committees) = (ledger.vm().block_store(), ledger.vm().finalize_store().committee_store());
+
+        // Ensure the ledger is consistent with its checkpoint block.
+        verify_ledger(blocks, committees, &genesis).unwrap();
+
+        // Ensure the ledger is inconsistent with another checkpoint block.
+        assert!(verify_ledger(blocks, committees, &sample_genesis_block()).is_err());
+    }
+}
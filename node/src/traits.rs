@@ -15,6 +15,7 @@
 use snarkos_node_router::{messages::NodeType, Routing};
 use snarkvm::prelude::{Address, Network, PrivateKey, ViewKey};
 
+use anyhow::Result;
 use once_cell::sync::OnceCell;
 use std::{
     future::Future,
@@ -34,12 +35,12 @@ pub trait NodeInterface<N: Network>: Routing<N> {
     }
 
     /// Returns the account private key of the node.
-    fn private_key(&self) -> &PrivateKey<N> {
+    fn private_key(&self) -> Result<&PrivateKey<N>> {
         self.router().private_key()
     }
 
     /// Returns the account view key of the node.
-    fn view_key(&self) -> &ViewKey<N> {
+    fn view_key(&self) -> Result<&ViewKey<N>> {
         self.router().view_key()
     }
 
@@ -21,15 +21,18 @@ use snarkos_node_consensus::Consensus;
 use snarkos_node_rest::Rest;
 use snarkos_node_router::{
     messages::{NodeType, PuzzleResponse, UnconfirmedSolution, UnconfirmedTransaction},
+    peer_store_path,
     Heartbeat,
     Inbound,
     Outbound,
     Router,
+    RouterConfig,
     Routing,
 };
 use snarkos_node_sync::{BlockSync, BlockSyncMode};
 use snarkos_node_tcp::{
     protocols::{Disconnect, Handshake, OnConnect, Reading, Writing},
+    Config,
     P2P,
 };
 use snarkvm::prelude::{
@@ -79,6 +82,7 @@ impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
         rest_rps: u32,
         account: Account<N>,
         trusted_peers: &[SocketAddr],
+        router_config: RouterConfig,
         trusted_validators: &[SocketAddr],
         genesis: Block<N>,
         cdn: Option<String>,
@@ -110,8 +114,16 @@ impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
         let sync = BlockSync::new(BlockSyncMode::Gateway, ledger_service.clone());
 
         // Initialize the consensus.
-        let mut consensus =
-            Consensus::new(account.clone(), ledger_service, bft_ip, trusted_validators, storage_mode.clone())?;
+        // Note: The gateway shares the transport and the send rates of the router, but never goes through its proxy.
+        let gateway_config = Config { proxy: None, ..router_config.tcp.clone() };
+        let mut consensus = Consensus::new(
+            account.clone(),
+            ledger_service,
+            bft_ip,
+            trusted_validators,
+            storage_mode.clone(),
+            gateway_config,
+        )?;
         // Initialize the primary channels.
         let (primary_sender, primary_receiver) = init_primary_channels::<N>();
         // Start the consensus.
@@ -123,6 +135,7 @@ impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
             NodeType::Validator,
             account,
             trusted_peers,
+            RouterConfig { peer_store_path: Some(peer_store_path(N::ID, &storage_mode)), ..router_config },
             Self::MAXIMUM_NUMBER_OF_PEERS as u16,
             allow_external_peers,
             matches!(storage_mode, StorageMode::Development(_)),
@@ -377,7 +390,7 @@ impl<N: Network, C: ConsensusStorage<N>> Validator<N, C> {
                 // Execute the transaction.
                 let self__ = self_.clone();
                 let transaction = match spawn_blocking!(self__.ledger.vm().execute(
-                    self__.private_key(),
+                    self__.private_key()?,
                     locator,
                     inputs.into_iter(),
                     None,
@@ -471,7 +484,7 @@ mod tests {
         // Initialize a new VM.
         let vm = VM::from(ConsensusStore::<CurrentNetwork, ConsensusMemory<CurrentNetwork>>::open(None)?)?;
         // Initialize the genesis block.
-        let genesis = vm.genesis_beacon(account.private_key(), &mut rng)?;
+        let genesis = vm.genesis_beacon(account.private_key()?, &mut rng)?;
 
         println!("Initializing validator node...");
 
@@ -482,6 +495,7 @@ mod tests {
             10,
             account,
             &[],
+            Default::default(),
             &[],
             genesis,
             None,
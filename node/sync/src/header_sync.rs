@@ -0,0 +1,505 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use snarkos_node_router::messages::CertifiedHeader;
+use snarkvm::{
+    ledger::{authority::Authority, block::Ratify, committee::Committee, narwhal::Subdag},
+    prelude::{
+        block::{Block, Header},
+        Network,
+        ToBits,
+    },
+};
+
+use anyhow::{anyhow, bail, ensure, Result};
+use parking_lot::RwLock;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// A chain of verified block headers, starting from the genesis block.
+///
+/// Each header is linked to its predecessor through the block hash, which is recomputed from the
+/// previous block hash and the header, and is bound to the certificates of its subdag through the
+/// subdag root. The certificates must be signed by a quorum of the committee lookback for their round,
+/// where the committee is tracked from the genesis block, and updated by the certified headers.
+///
+/// A committee update from a peer is only authenticated once a certificate created for it is also
+/// signed by a quorum of the previous authenticated committee. Until then, any certificate created for
+/// the committee must reach both quorums, so the chain does not advance past an unauthenticated epoch change.
+#[derive(Debug)]
+pub struct HeaderSync<N: Network> {
+    /// The list of block hashes and headers, indexed by block height.
+    headers: RwLock<Vec<(N::BlockHash, Header<N>)>>,
+    /// The map of block hashes to their block height.
+    heights: RwLock<HashMap<N::BlockHash, u32>>,
+    /// The map of starting rounds to their committee, and whether the committee is authenticated.
+    committees: RwLock<BTreeMap<u64, (Committee<N>, bool)>>,
+}
+
+impl<N: Network> HeaderSync<N> {
+    /// Initializes a new header sync from the given genesis block.
+    pub fn new(genesis: &Block<N>) -> Result<Self> {
+        // Retrieve the genesis committee.
+        let committee = genesis
+            .ratifications()
+            .iter()
+            .find_map(|ratify| match ratify {
+                Ratify::Genesis(committee, ..) => Some(*committee.clone()),
+                _ => None,
+            })
+            .ok_or_else(|| anyhow!("The genesis block is missing the genesis committee"))?;
+        Ok(Self {
+            headers: RwLock::new(vec![(genesis.hash(), *genesis.header())]),
+            heights: RwLock::new([(genesis.hash(), 0)].into_iter().collect()),
+            committees: RwLock::new([(committee.starting_round(), (committee, true))].into_iter().collect()),
+        })
+    }
+
+    /// Returns the latest block height.
+    pub fn latest_height(&self) -> u32 {
+        self.headers.read().len().saturating_sub(1) as u32
+    }
+
+    /// Returns the latest block hash.
+    pub fn latest_hash(&self) -> N::BlockHash {
+        // Note: The list always contains the genesis header.
+        self.headers.read().last().map(|(hash, _)| *hash).unwrap_or_default()
+    }
+
+    /// Returns the latest block header.
+    pub fn latest_header(&self) -> Option<Header<N>> {
+        self.headers.read().last().map(|(_, header)| *header)
+    }
+
+    /// Returns the latest authenticated committee.
+    pub fn latest_committee(&self) -> Option<Committee<N>> {
+        self.committees
+            .read()
+            .values()
+            .rev()
+            .find(|(_, is_authenticated)| *is_authenticated)
+            .map(|(committee, _)| committee.clone())
+    }
+
+    /// Returns the block hash for the given block height, if it has been synced.
+    pub fn get_hash(&self, height: u32) -> Option<N::BlockHash> {
+        self.headers.read().get(height as usize).map(|(hash, _)| *hash)
+    }
+
+    /// Returns the block header for the given block height, if it has been synced.
+    pub fn get_header(&self, height: u32) -> Option<Header<N>> {
+        self.headers.read().get(height as usize).map(|(_, header)| *header)
+    }
+
+    /// Returns the block height for the given block hash, if it has been synced.
+    pub fn get_height(&self, hash: &N::BlockHash) -> Option<u32> {
+        self.heights.read().get(hash).copied()
+    }
+
+    /// Verifies the given certified headers, and appends them to the chain.
+    /// The headers must be sequentially ordered, and start at the block height after the latest one.
+    pub fn insert_headers(&self, headers: &[CertifiedHeader<N>]) -> Result<()> {
+        // Acquire the write locks, so that concurrent responses are appended in order.
+        let mut chain = self.headers.write();
+        let mut committees = self.committees.write();
+        // Verify the headers against the latest header.
+        let mut verified = Vec::with_capacity(headers.len());
+        let (mut previous_hash, mut height) = match chain.last() {
+            Some((hash, header)) => (*hash, header.height()),
+            None => bail!("The header chain is missing the genesis header"),
+        };
+        // Note: The committee updates must be for increasing starting rounds, so they can be reverted from this round.
+        let latest_round = committees.keys().next_back().copied().unwrap_or_default();
+        let mut authenticated = Vec::new();
+        for certified in headers {
+            // Verify the header, and apply the committee updates, so that they are used for the next headers.
+            let result =
+                Self::verify_header(certified, previous_hash, height + 1, &committees).and_then(|block_hash| {
+                    authenticated.extend(Self::authenticate_committees(&mut committees, certified));
+                    Self::update_committee(&mut committees, certified).map(|_| block_hash)
+                });
+            let block_hash = match result {
+                Ok(block_hash) => block_hash,
+                Err(error) => {
+                    // Revert the committee updates and authentications of the verified headers.
+                    committees.retain(|round, _| *round <= latest_round);
+                    for round in authenticated {
+                        if let Some((_, is_authenticated)) = committees.get_mut(&round) {
+                            *is_authenticated = false;
+                        }
+                    }
+                    return Err(error);
+                }
+            };
+            verified.push((block_hash, certified.header));
+            previous_hash = block_hash;
+            height += 1;
+        }
+        // Append the verified headers.
+        let mut heights = self.heights.write();
+        for (block_hash, header) in verified {
+            heights.insert(block_hash, header.height());
+            chain.push((block_hash, header));
+        }
+        Ok(())
+    }
+
+    /// Verifies the given certified header is the successor of the given block hash,
+    /// returning the block hash of the certified header.
+    fn verify_header(
+        certified: &CertifiedHeader<N>,
+        expected_previous_hash: N::BlockHash,
+        expected_height: u32,
+        committees: &BTreeMap<u64, (Committee<N>, bool)>,
+    ) -> Result<N::BlockHash> {
+        let CertifiedHeader { previous_hash, header, authority, .. } = certified;
+        // Ensure the header is at the expected height.
+        ensure!(
+            header.height() == expected_height,
+            "Header has an incorrect height (found {}, expected {expected_height})",
+            header.height()
+        );
+        // Ensure the header links to the previous block.
+        ensure!(*previous_hash == expected_previous_hash, "Header {expected_height} has an incorrect previous hash");
+        // Ensure the header is certified by a subdag that matches the header.
+        match authority {
+            Authority::Beacon(..) => bail!("Header {expected_height} is not certified by a quorum"),
+            Authority::Quorum(subdag) => {
+                ensure!(
+                    subdag.to_subdag_root()? == header.subdag_root(),
+                    "Header {expected_height} has an incorrect subdag root"
+                );
+                ensure!(subdag.anchor_round() == header.round(), "Header {expected_height} has an incorrect round");
+                Self::verify_subdag(subdag, committees)
+                    .map_err(|error| anyhow!("Header {expected_height} has an invalid subdag - {error}"))?;
+            }
+        }
+        // Compute the block hash.
+        compute_block_hash(*previous_hash, header)
+    }
+
+    /// Verifies the leader and the certificates of the given subdag against the committee lookback of their round.
+    /// If the committee lookback is not authenticated, the certificates must also be signed by a quorum
+    /// of the previous authenticated committee.
+    fn verify_subdag(subdag: &Subdag<N>, committees: &BTreeMap<u64, (Committee<N>, bool)>) -> Result<()> {
+        // Ensure the leader certificate is authored by the leader of the anchor round.
+        let anchor_round = subdag.anchor_round();
+        let leader = get_committee_lookback(committees, anchor_round)?.0.get_leader(anchor_round)?;
+        ensure!(subdag.leader_certificate().author() == leader, "The leader certificate is not from the leader");
+
+        for certificate in subdag.values().flatten() {
+            let round = certificate.round();
+            // Retrieve the committee lookback for the certificate round.
+            let (committee_lookback, is_authenticated) = get_committee_lookback(committees, round)?;
+            // Ensure the certificate was created for the committee lookback.
+            ensure!(
+                certificate.batch_header().committee_id() == committee_lookback.id(),
+                "A certificate in round {round} is for an unknown committee"
+            );
+
+            // Ensure the author is in the committee, and signed the batch.
+            let author = certificate.author();
+            let batch_id = certificate.batch_id();
+            ensure!(committee_lookback.is_committee_member(author), "Author {author} is not in the committee");
+            ensure!(
+                certificate.batch_header().signature().verify(&author, &[batch_id]),
+                "The batch in round {round} has an invalid signature from author {author}"
+            );
+
+            // Initialize a set of the signers, including the author.
+            let mut signers = HashSet::with_capacity(certificate.signatures().len() + 1);
+            signers.insert(author);
+            for signature in certificate.signatures() {
+                // Ensure the signer is in the committee, and signed the batch.
+                let signer = signature.to_address();
+                ensure!(committee_lookback.is_committee_member(signer), "Signer {signer} is not in the committee");
+                ensure!(
+                    signature.verify(&signer, &[batch_id]),
+                    "The batch in round {round} has an invalid signature from signer {signer}"
+                );
+                signers.insert(signer);
+            }
+            // Ensure the signers have reached the quorum threshold.
+            ensure!(
+                committee_lookback.is_quorum_threshold_reached(&signers),
+                "The signatures for a batch in round {round} did not reach the quorum threshold"
+            );
+            // Ensure the signers of an unauthenticated committee have reached the quorum threshold
+            // of the previous authenticated committee, which authenticates the committee update.
+            if !is_authenticated {
+                ensure!(
+                    get_authenticated_committee(committees, round)?.is_quorum_threshold_reached(&signers),
+                    "The batch in round {round} is for an unauthenticated committee"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Authenticates the committee lookbacks of the certificates of the given (verified) certified header,
+    /// returning the starting rounds of the newly authenticated committees.
+    fn authenticate_committees(
+        committees: &mut BTreeMap<u64, (Committee<N>, bool)>,
+        certified: &CertifiedHeader<N>,
+    ) -> Vec<u64> {
+        let mut authenticated = Vec::new();
+        if let Authority::Quorum(subdag) = &certified.authority {
+            for round in subdag.keys() {
+                let committee_round = round.saturating_sub(Committee::<N>::COMMITTEE_LOOKBACK_RANGE);
+                if let Some((starting_round, (_, is_authenticated))) =
+                    committees.range_mut(..=committee_round).next_back()
+                {
+                    if !*is_authenticated {
+                        *is_authenticated = true;
+                        authenticated.push(*starting_round);
+                    }
+                }
+            }
+        }
+        authenticated
+    }
+
+    /// Applies the committee update of the given (verified) certified header, if any, as an unauthenticated committee.
+    ///
+    /// Note: The committee is only used once the committee lookback reaches its starting round,
+    /// and the certificates from then on must be created for it.
+    fn update_committee(
+        committees: &mut BTreeMap<u64, (Committee<N>, bool)>,
+        certified: &CertifiedHeader<N>,
+    ) -> Result<()> {
+        if let Some(committee) = &certified.committee {
+            let height = certified.height();
+            // Ensure the committee starts after the latest committee, and no later than the block.
+            let latest_round = committees.keys().next_back().copied().unwrap_or_default();
+            ensure!(
+                committee.starting_round() > latest_round && committee.starting_round() <= certified.header.round(),
+                "Header {height} has a committee with an invalid starting round"
+            );
+            committees.insert(committee.starting_round(), (committee.clone(), false));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the committee lookback for the given round, and whether it is authenticated,
+/// from the given map of starting rounds to their committee.
+fn get_committee_lookback<N: Network>(
+    committees: &BTreeMap<u64, (Committee<N>, bool)>,
+    round: u64,
+) -> Result<(&Committee<N>, bool)> {
+    let committee_round = round.saturating_sub(Committee::<N>::COMMITTEE_LOOKBACK_RANGE);
+    match committees.range(..=committee_round).next_back() {
+        Some((_, (committee, is_authenticated))) => Ok((committee, *is_authenticated)),
+        None => bail!("Missing the committee for round {committee_round}"),
+    }
+}
+
+/// Returns the latest authenticated committee up to the committee lookback for the given round.
+fn get_authenticated_committee<N: Network>(
+    committees: &BTreeMap<u64, (Committee<N>, bool)>,
+    round: u64,
+) -> Result<&Committee<N>> {
+    let committee_round = round.saturating_sub(Committee::<N>::COMMITTEE_LOOKBACK_RANGE);
+    match committees.range(..=committee_round).rev().find(|(_, (_, is_authenticated))| *is_authenticated) {
+        Some((_, (committee, _))) => Ok(committee),
+        None => bail!("Missing an authenticated committee for round {committee_round}"),
+    }
+}
+
+/// Returns the block hash for the given previous block hash and block header.
+pub fn compute_block_hash<N: Network>(previous_hash: N::BlockHash, header: &Header<N>) -> Result<N::BlockHash> {
+    let mut preimage = (*previous_hash).to_bits_le();
+    preimage.extend(header.to_root()?.to_bits_le());
+    Ok(N::hash_bhp1024(&preimage)?.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::{
+        ledger::{
+            committee::test_helpers::sample_committee_for_round_and_members,
+            narwhal::{BatchCertificate, BatchHeader},
+        },
+        prelude::{Address, Field, FromBytes, PrivateKey, TestRng},
+    };
+
+    use indexmap::IndexSet;
+
+    type CurrentNetwork = snarkvm::prelude::MainnetV0;
+
+    /// Loads the current network's genesis block.
+    fn sample_genesis_block() -> Block<CurrentNetwork> {
+        Block::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap()
+    }
+
+    /// Returns a subdag with a single leader certificate in the given round, signed by the given signers.
+    fn sample_subdag(
+        author: &PrivateKey<CurrentNetwork>,
+        signers: &[PrivateKey<CurrentNetwork>],
+        round: u64,
+        committee_id: Field<CurrentNetwork>,
+        rng: &mut TestRng,
+    ) -> Subdag<CurrentNetwork> {
+        let batch_header =
+            BatchHeader::new(author, round, 1, committee_id, Default::default(), Default::default(), rng).unwrap();
+        let signatures =
+            signers.iter().map(|signer| signer.sign(&[batch_header.batch_id()], rng).unwrap()).collect::<IndexSet<_>>();
+        let certificate = BatchCertificate::from(batch_header, signatures).unwrap();
+        Subdag::from([(round, [certificate].into_iter().collect())].into_iter().collect()).unwrap()
+    }
+
+    #[test]
+    fn test_compute_block_hash() {
+        let genesis = sample_genesis_block();
+        let block_hash = compute_block_hash::<CurrentNetwork>(genesis.previous_hash(), genesis.header()).unwrap();
+        assert_eq!(block_hash, genesis.hash());
+    }
+
+    #[test]
+    fn test_new() {
+        let genesis = sample_genesis_block();
+        let sync = HeaderSync::new(&genesis).unwrap();
+        assert_eq!(sync.latest_height(), 0);
+        assert_eq!(sync.latest_hash(), genesis.hash());
+        assert_eq!(sync.get_header(0), Some(*genesis.header()));
+        assert_eq!(sync.get_height(&genesis.hash()), Some(0));
+        assert_eq!(sync.get_header(1), None);
+        assert_eq!(sync.latest_committee().unwrap().starting_round(), 0);
+    }
+
+    #[test]
+    fn test_insert_invalid_headers() {
+        let genesis = sample_genesis_block();
+        let sync = HeaderSync::new(&genesis).unwrap();
+
+        // Ensure a header at the incorrect height is rejected.
+        let certified = CertifiedHeader::from(&genesis);
+        assert!(sync.insert_headers(&[certified.clone()]).is_err());
+
+        // Ensure a header with an incorrect previous hash is rejected.
+        let certified = CertifiedHeader { previous_hash: Default::default(), ..certified };
+        assert!(sync.insert_headers(&[certified]).is_err());
+
+        // Ensure the chain is unchanged.
+        assert_eq!(sync.latest_height(), 0);
+        assert_eq!(sync.latest_hash(), genesis.hash());
+    }
+
+    #[test]
+    fn test_verify_forged_subdag() {
+        let rng = &mut TestRng::default();
+
+        // Sample the committee.
+        let private_keys = (0..4).map(|_| PrivateKey::new(rng).unwrap()).collect::<Vec<_>>();
+        let addresses = private_keys.iter().map(|private_key| Address::try_from(private_key).unwrap()).collect();
+        let committee = sample_committee_for_round_and_members(0, addresses, rng);
+        let committees = [(0, (committee.clone(), true))].into_iter().collect::<BTreeMap<_, _>>();
+
+        // Retrieve the leader of the anchor round.
+        let round = 2;
+        let leader = committee.get_leader(round).unwrap();
+        let index = private_keys.iter().position(|private_key| Address::try_from(private_key).unwrap() == leader);
+        let author = private_keys[index.unwrap()];
+        let signers = private_keys.iter().filter(|private_key| **private_key != author).copied().collect::<Vec<_>>();
+
+        // Ensure a subdag certified by a quorum of the committee is accepted.
+        let subdag = sample_subdag(&author, &signers, round, committee.id(), rng);
+        assert!(HeaderSync::verify_subdag(&subdag, &committees).is_ok());
+
+        // Ensure a subdag without a quorum of signatures is rejected.
+        let subdag = sample_subdag(&author, &signers[..1], round, committee.id(), rng);
+        assert!(HeaderSync::verify_subdag(&subdag, &committees).is_err());
+
+        // Ensure a subdag signed by validators outside the committee is rejected.
+        let outsiders = (0..3).map(|_| PrivateKey::new(rng).unwrap()).collect::<Vec<_>>();
+        let subdag = sample_subdag(&author, &outsiders, round, committee.id(), rng);
+        assert!(HeaderSync::verify_subdag(&subdag, &committees).is_err());
+
+        // Ensure a subdag created by a validator outside the committee is rejected.
+        let subdag = sample_subdag(&outsiders[0], &signers, round, committee.id(), rng);
+        assert!(HeaderSync::verify_subdag(&subdag, &committees).is_err());
+
+        // Ensure a subdag created for another committee is rejected.
+        let subdag = sample_subdag(&author, &signers, round, Field::from_u64(1), rng);
+        assert!(HeaderSync::verify_subdag(&subdag, &committees).is_err());
+    }
+
+    #[test]
+    fn test_update_committee() {
+        let rng = &mut TestRng::default();
+        let genesis = sample_genesis_block();
+        let sync = HeaderSync::<CurrentNetwork>::new(&genesis).unwrap();
+        let committee = sync.latest_committee().unwrap();
+        let mut committees = sync.committees.write();
+
+        // Ensure a committee that does not start after the latest committee is rejected.
+        let certified = CertifiedHeader { committee: Some(committee.clone()), ..CertifiedHeader::from(&genesis) };
+        assert!(HeaderSync::update_committee(&mut committees, &certified).is_err());
+
+        // Ensure a committee that starts after the block is rejected.
+        let members = committee.members().keys().copied().collect();
+        let committee = sample_committee_for_round_and_members(genesis.round() + 1, members, rng);
+        let certified = CertifiedHeader { committee: Some(committee), ..CertifiedHeader::from(&genesis) };
+        assert!(HeaderSync::update_committee(&mut committees, &certified).is_err());
+        assert_eq!(committees.len(), 1);
+    }
+
+    #[test]
+    fn test_reject_forged_committee_update() {
+        let rng = &mut TestRng::default();
+        let genesis = sample_genesis_block();
+
+        // Sample the authenticated committee.
+        let private_keys = (0..4).map(|_| PrivateKey::new(rng).unwrap()).collect::<Vec<_>>();
+        let addresses = private_keys.iter().map(|private_key| Address::try_from(private_key).unwrap()).collect();
+        let committee = sample_committee_for_round_and_members(0, addresses, rng);
+        let mut committees = [(0, (committee, true))].into_iter().collect::<BTreeMap<_, _>>();
+
+        // Apply a forged committee update, with validators outside the authenticated committee.
+        // Note: The update is inserted as `update_committee` does, as the genesis header precedes its starting round.
+        let starting_round = 2;
+        let outsiders = (0..4).map(|_| PrivateKey::new(rng).unwrap()).collect::<Vec<_>>();
+        let addresses = outsiders.iter().map(|private_key| Address::try_from(private_key).unwrap()).collect();
+        let forged = sample_committee_for_round_and_members(starting_round, addresses, rng);
+        committees.insert(starting_round, (forged.clone(), false));
+
+        // Ensure a subdag certified by the forged committee is rejected, once the committee lookback reaches it.
+        let round = starting_round + Committee::<CurrentNetwork>::COMMITTEE_LOOKBACK_RANGE;
+        let leader = forged.get_leader(round).unwrap();
+        let index = outsiders.iter().position(|private_key| Address::try_from(private_key).unwrap() == leader);
+        let author = outsiders[index.unwrap()];
+        let signers = outsiders.iter().filter(|private_key| **private_key != author).copied().collect::<Vec<_>>();
+        let subdag = sample_subdag(&author, &signers, round, forged.id(), rng);
+        assert!(HeaderSync::verify_subdag(&subdag, &committees).is_err());
+
+        // Ensure the forged committee remains unauthenticated.
+        let certified = CertifiedHeader { authority: Authority::Quorum(subdag), ..CertifiedHeader::from(&genesis) };
+        assert!(HeaderSync::authenticate_committees(&mut committees, &certified).is_empty());
+        assert!(!committees[&starting_round].1);
+
+        // Ensure a committee update signed by a quorum of the authenticated committee is accepted and authenticated.
+        let addresses = private_keys.iter().map(|private_key| Address::try_from(private_key).unwrap()).collect();
+        let updated = sample_committee_for_round_and_members(starting_round, addresses, rng);
+        committees.insert(starting_round, (updated.clone(), false));
+        let leader = updated.get_leader(round).unwrap();
+        let index = private_keys.iter().position(|private_key| Address::try_from(private_key).unwrap() == leader);
+        let author = private_keys[index.unwrap()];
+        let signers = private_keys.iter().filter(|private_key| **private_key != author).copied().collect::<Vec<_>>();
+        let subdag = sample_subdag(&author, &signers, round, updated.id(), rng);
+        assert!(HeaderSync::verify_subdag(&subdag, &committees).is_ok());
+        let certified = CertifiedHeader { authority: Authority::Quorum(subdag), ..CertifiedHeader::from(&genesis) };
+        assert_eq!(HeaderSync::authenticate_committees(&mut committees, &certified), vec![starting_round]);
+        assert!(committees[&starting_round].1);
+    }
+}
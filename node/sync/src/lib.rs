@@ -23,5 +23,8 @@ pub use snarkos_node_sync_locators as locators;
 mod block_sync;
 pub use block_sync::*;
 
+mod header_sync;
+pub use header_sync::*;
+
 mod helpers;
 pub use helpers::*;
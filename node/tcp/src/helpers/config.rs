@@ -15,8 +15,11 @@
 use std::{
     io::{self, ErrorKind::*},
     net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
 };
 
+use crate::{TcpTransport, Transport};
+
 #[cfg(doc)]
 use crate::protocols::{self, Handshake, Reading, Writing};
 
@@ -54,6 +57,25 @@ pub struct Config {
     pub max_connections: u16,
     /// The maximum time (in milliseconds) allowed to establish a raw (before the [`Handshake`] protocol) TCP connection.
     pub connection_timeout_ms: u16,
+    /// The maximum rate (in bytes per second) at which messages are sent to all the peers combined.
+    ///
+    /// note: If set to `None` (or zero), the rate is unlimited. Tcp needs to implement the [`Writing`] protocol in
+    /// order for it to have any effect.
+    pub max_send_rate: Option<u64>,
+    /// The maximum rate (in bytes per second) at which messages are sent to a single peer.
+    ///
+    /// note: If set to `None` (or zero), the rate is unlimited. Tcp needs to implement the [`Writing`] protocol in
+    /// order for it to have any effect.
+    pub max_send_rate_per_peer: Option<u64>,
+    /// The address of the SOCKS5 proxy through which outbound connections are established.
+    ///
+    /// note: Peers see the connections as coming from the proxy (or from its exit), so they cannot infer this node's
+    /// listening address from them; [`Config::connection_timeout_ms`] also covers the negotiation with the proxy.
+    pub proxy: Option<SocketAddr>,
+    /// The transport used to establish the connections; see [`crate::transport`] for the available ones.
+    ///
+    /// note: The default is the TCP transport; the nodes sharing an in-memory transport share its network.
+    pub transport: Arc<dyn Transport>,
 }
 
 impl Config {
@@ -90,6 +112,10 @@ impl Default for Config {
             fatal_io_errors: vec![ConnectionReset, ConnectionAborted, BrokenPipe, InvalidData, UnexpectedEof],
             max_connections: 100,
             connection_timeout_ms: 1_000,
+            max_send_rate: None,
+            max_send_rate_per_peer: None,
+            proxy: None,
+            transport: Arc::new(TcpTransport),
         }
     }
 }
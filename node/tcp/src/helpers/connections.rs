@@ -19,11 +19,12 @@ use std::{collections::HashMap, net::SocketAddr, ops::Not};
 use parking_lot::RwLock;
 use tokio::{
     io::{AsyncRead, AsyncWrite},
-    net::TcpStream,
     sync::oneshot,
     task::JoinHandle,
 };
 
+use crate::BoxedStream;
+
 #[cfg(doc)]
 use crate::protocols::{Handshake, Reading, Writing};
 
@@ -74,7 +75,7 @@ pub struct Connection {
     /// The connection's side in relation to Tcp.
     side: ConnectionSide,
     /// Available and used only in the [`Handshake`] protocol.
-    pub(crate) stream: Option<TcpStream>,
+    pub(crate) stream: Option<BoxedStream>,
     /// Available and used only in the [`Reading`] protocol.
     pub(crate) reader: Option<Box<dyn AR>>,
     /// Available and used only in the [`Writing`] protocol.
@@ -87,7 +88,7 @@ pub struct Connection {
 
 impl Connection {
     /// Creates a [`Connection`] with placeholders for protocol-related objects.
-    pub(crate) fn new(addr: SocketAddr, stream: TcpStream, side: ConnectionSide) -> Self {
+    pub(crate) fn new(addr: SocketAddr, stream: BoxedStream, side: ConnectionSide) -> Self {
         Self {
             addr,
             stream: Some(stream),
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
 
 use parking_lot::RwLock;
 
@@ -57,10 +57,45 @@ impl KnownPeers {
         }
     }
 
+    /// Registers a submission of a message of the provided type to the given address.
+    pub fn register_sent_traffic(&self, to: SocketAddr, message_type: &'static str, size: usize) {
+        if let Some(stats) = self.0.read().get(&to) {
+            stats.register_sent_traffic(message_type, size);
+        }
+    }
+
+    /// Registers a receipt of a message of the provided type from the given address.
+    pub fn register_received_traffic(&self, from: SocketAddr, message_type: &'static str, size: usize) {
+        if let Some(stats) = self.0.read().get(&from) {
+            stats.register_received_traffic(message_type, size);
+        }
+    }
+
     /// Registers a failure associated with the given address.
     pub fn register_failure(&self, addr: SocketAddr) {
         if let Some(stats) = self.0.read().get(&addr) {
             stats.register_failure();
         }
     }
+
+    /// Registers a message queued to be sent to the given address.
+    pub fn register_queued_message(&self, to: SocketAddr) {
+        if let Some(stats) = self.0.read().get(&to) {
+            stats.register_queued_message();
+        }
+    }
+
+    /// Registers the provided number of messages to the given address that are no longer queued.
+    pub fn register_dequeued_messages(&self, to: SocketAddr, count: u64) {
+        if let Some(stats) = self.0.read().get(&to) {
+            stats.register_dequeued_messages(count);
+        }
+    }
+
+    /// Registers a message to the given address delayed by the bandwidth limits for the provided duration.
+    pub fn register_throttled_message(&self, to: SocketAddr, delay: Duration) {
+        if let Some(stats) = self.0.read().get(&to) {
+            stats.register_throttled_message(delay);
+        }
+    }
 }
@@ -21,8 +21,19 @@ pub use connections::{Connection, ConnectionSide};
 mod known_peers;
 pub use known_peers::KnownPeers;
 
+mod socks5;
+pub(crate) use socks5::socks5_connect;
+
 mod stats;
-pub use stats::Stats;
+pub use stats::{Stats, TrafficStats};
+
+mod throttle;
+pub(crate) use throttle::TokenBucket;
+
+pub mod transport;
+#[cfg(unix)]
+pub use transport::UnixTransport;
+pub use transport::{BoxedStream, LinkConditions, MemoryTransport, TcpTransport, Transport};
 
 use tracing::{debug_span, error_span, info_span, trace_span, warn_span, Span};
 
@@ -0,0 +1,245 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    io::{self, ErrorKind},
+    net::SocketAddr,
+};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The version of the SOCKS protocol.
+const SOCKS_VERSION: u8 = 5;
+/// The authentication method that requires no authentication.
+const NO_AUTHENTICATION: u8 = 0;
+/// The command that establishes a TCP connection.
+const CONNECT: u8 = 1;
+/// The type of an IPv4 address.
+const ATYP_IPV4: u8 = 1;
+/// The type of a domain name.
+const ATYP_DOMAIN: u8 = 3;
+/// The type of an IPv6 address.
+const ATYP_IPV6: u8 = 4;
+/// The reply that indicates the request succeeded.
+const SUCCEEDED: u8 = 0;
+
+/// Requests the SOCKS5 proxy on the other end of the given stream to connect to the given target address
+/// (see RFC 1928). Once this returns successfully, the stream is relayed to the target by the proxy.
+pub(crate) async fn socks5_connect<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    target: SocketAddr,
+) -> io::Result<()> {
+    // Offer the authentication methods; only connecting without authentication is supported.
+    stream.write_all(&[SOCKS_VERSION, 1, NO_AUTHENTICATION]).await?;
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply[0] != SOCKS_VERSION {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid SOCKS version {} from the proxy", reply[0]),
+        ));
+    }
+    if reply[1] != NO_AUTHENTICATION {
+        return Err(io::Error::new(ErrorKind::PermissionDenied, "the proxy requires authentication"));
+    }
+
+    // Request the connection to the target address.
+    let mut request = vec![SOCKS_VERSION, CONNECT, 0];
+    match target {
+        SocketAddr::V4(addr) => {
+            request.push(ATYP_IPV4);
+            request.extend_from_slice(&addr.ip().octets());
+        }
+        SocketAddr::V6(addr) => {
+            request.push(ATYP_IPV6);
+            request.extend_from_slice(&addr.ip().octets());
+        }
+    }
+    request.extend_from_slice(&target.port().to_be_bytes());
+    stream.write_all(&request).await?;
+
+    // Read the reply, which ends with the address the proxy bound to connect to the target.
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[0] != SOCKS_VERSION {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid SOCKS version {} from the proxy", reply[0]),
+        ));
+    }
+    if reply[1] != SUCCEEDED {
+        return Err(reply_error(reply[1], target));
+    }
+    let address_len = match reply[3] {
+        ATYP_IPV4 => 4,
+        ATYP_IPV6 => 16,
+        ATYP_DOMAIN => stream.read_u8().await? as usize,
+        atyp => {
+            return Err(io::Error::new(ErrorKind::InvalidData, format!("invalid address type {atyp} from the proxy")))
+        }
+    };
+    // Skip the bound address and port, which are of no use to the node.
+    let mut bound_addr = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound_addr).await?;
+
+    Ok(())
+}
+
+/// Returns the error corresponding to the given (unsuccessful) reply of the proxy.
+fn reply_error(reply: u8, target: SocketAddr) -> io::Error {
+    let (kind, reason) = match reply {
+        1 => (ErrorKind::Other, "general failure"),
+        2 => (ErrorKind::PermissionDenied, "connection not allowed by ruleset"),
+        3 => (ErrorKind::Other, "network unreachable"),
+        4 => (ErrorKind::Other, "host unreachable"),
+        5 => (ErrorKind::ConnectionRefused, "connection refused"),
+        6 => (ErrorKind::TimedOut, "TTL expired"),
+        7 => (ErrorKind::Unsupported, "command not supported"),
+        8 => (ErrorKind::Unsupported, "address type not supported"),
+        _ => (ErrorKind::Other, "unknown failure"),
+    };
+    io::Error::new(kind, format!("the proxy failed to connect to {target}: {reason}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, Tcp};
+
+    use parking_lot::Mutex;
+    use std::{
+        net::{IpAddr, Ipv4Addr, Ipv6Addr},
+        sync::Arc,
+    };
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// A local SOCKS5 proxy, which records the requested target addresses,
+    /// and either relays the connections to them or refuses them.
+    struct LocalProxy {
+        addr: SocketAddr,
+        targets: Arc<Mutex<Vec<SocketAddr>>>,
+        relays: Arc<Mutex<Vec<SocketAddr>>>,
+    }
+
+    impl LocalProxy {
+        async fn spawn(refuse: bool) -> Self {
+            let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let targets = Arc::new(Mutex::new(Vec::new()));
+            let relays = Arc::new(Mutex::new(Vec::new()));
+
+            let (targets_, relays_) = (targets.clone(), relays.clone());
+            tokio::spawn(async move {
+                while let Ok((mut stream, _)) = listener.accept().await {
+                    let (targets, relays) = (targets_.clone(), relays_.clone());
+                    tokio::spawn(async move {
+                        // Accept connecting without authentication.
+                        let mut greeting = [0u8; 3];
+                        stream.read_exact(&mut greeting).await.unwrap();
+                        assert_eq!(greeting, [SOCKS_VERSION, 1, NO_AUTHENTICATION]);
+                        stream.write_all(&[SOCKS_VERSION, NO_AUTHENTICATION]).await.unwrap();
+
+                        // Read the target address.
+                        let mut request = [0u8; 4];
+                        stream.read_exact(&mut request).await.unwrap();
+                        assert_eq!(request[..3], [SOCKS_VERSION, CONNECT, 0]);
+                        let ip = match request[3] {
+                            ATYP_IPV4 => {
+                                let mut octets = [0u8; 4];
+                                stream.read_exact(&mut octets).await.unwrap();
+                                IpAddr::V4(Ipv4Addr::from(octets))
+                            }
+                            ATYP_IPV6 => {
+                                let mut octets = [0u8; 16];
+                                stream.read_exact(&mut octets).await.unwrap();
+                                IpAddr::V6(Ipv6Addr::from(octets))
+                            }
+                            atyp => panic!("unexpected address type {atyp}"),
+                        };
+                        let target = SocketAddr::new(ip, stream.read_u16().await.unwrap());
+                        targets.lock().push(target);
+
+                        // Refuse the connection, if requested.
+                        if refuse {
+                            stream.write_all(&[SOCKS_VERSION, 5, 0, ATYP_IPV4, 0, 0, 0, 0, 0, 0]).await.unwrap();
+                            return;
+                        }
+
+                        // Relay the connection to the target.
+                        let mut relay = TcpStream::connect(target).await.unwrap();
+                        let bound_addr = match relay.local_addr().unwrap() {
+                            SocketAddr::V4(addr) => addr,
+                            addr => panic!("unexpected bound address {addr}"),
+                        };
+                        relays.lock().push(bound_addr.into());
+                        let mut reply = vec![SOCKS_VERSION, SUCCEEDED, 0, ATYP_IPV4];
+                        reply.extend_from_slice(&bound_addr.ip().octets());
+                        reply.extend_from_slice(&bound_addr.port().to_be_bytes());
+                        stream.write_all(&reply).await.unwrap();
+                        let _ = tokio::io::copy_bidirectional(&mut stream, &mut relay).await;
+                    });
+                }
+            });
+
+            Self { addr, targets, relays }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_through_proxy() {
+        let proxy = LocalProxy::spawn(false).await;
+
+        // Initialize the node, which connects through the proxy.
+        let tcp = Tcp::new(Config { proxy: Some(proxy.addr), ..Default::default() });
+        tcp.enable_listener().await.unwrap();
+        // Initialize the peer.
+        let peer = Tcp::new(Config {
+            listener_ip: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            desired_listening_port: Some(0),
+            ..Default::default()
+        });
+        let peer_ip = peer.enable_listener().await.unwrap();
+
+        // Connect to the peer through the proxy.
+        tcp.connect(peer_ip).await.unwrap();
+        assert!(tcp.is_connected(peer_ip));
+        assert_eq!(*proxy.targets.lock(), vec![peer_ip]);
+
+        // Ensure the peer sees the connection coming from the proxy, rather than from the node.
+        for _ in 0..10 {
+            if peer.num_connected() == 1 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert_eq!(peer.connected_addrs(), *proxy.relays.lock());
+    }
+
+    #[tokio::test]
+    async fn test_connect_refused_by_proxy() {
+        let proxy = LocalProxy::spawn(true).await;
+
+        // Initialize the node, which connects through the proxy.
+        let tcp = Tcp::new(Config { proxy: Some(proxy.addr), ..Default::default() });
+        tcp.enable_listener().await.unwrap();
+
+        // Ensure the refusal of the proxy is surfaced.
+        let target = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 4130);
+        let error = tcp.connect(target).await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ConnectionRefused);
+        assert!(!tcp.is_connected(target));
+        assert!(!tcp.is_connecting(target));
+        assert_eq!(*proxy.targets.lock(), vec![target]);
+    }
+}
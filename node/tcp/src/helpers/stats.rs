@@ -12,7 +12,26 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+    time::Duration,
+};
+
+/// The number of messages and bytes sent and received for a single message type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TrafficStats {
+    /// The number of messages sent.
+    pub msgs_sent: u64,
+    /// The number of bytes sent.
+    pub bytes_sent: u64,
+    /// The number of messages received.
+    pub msgs_received: u64,
+    /// The number of bytes received.
+    pub bytes_received: u64,
+}
 
 /// Contains statistics related to Tcp.
 #[derive(Default)]
@@ -27,6 +46,14 @@ pub struct Stats {
     bytes_received: AtomicU64,
     /// The number of failures.
     failures: AtomicU64,
+    /// The number of messages queued to be sent.
+    msgs_queued: AtomicU64,
+    /// The number of messages delayed by the bandwidth limits.
+    msgs_throttled: AtomicU64,
+    /// The total delay of the messages delayed by the bandwidth limits, in microseconds.
+    throttled_micros: AtomicU64,
+    /// The traffic of the messages, by message type.
+    traffic: Mutex<BTreeMap<&'static str, TrafficStats>>,
 }
 
 impl Stats {
@@ -51,6 +78,24 @@ impl Stats {
         self.failures.load(Relaxed)
     }
 
+    /// Returns the number of messages queued to be sent.
+    pub fn queued(&self) -> u64 {
+        self.msgs_queued.load(Relaxed)
+    }
+
+    /// Returns the number of messages delayed by the bandwidth limits and their collective delay.
+    pub fn throttled(&self) -> (u64, Duration) {
+        let msgs = self.msgs_throttled.load(Relaxed);
+        let delay = Duration::from_micros(self.throttled_micros.load(Relaxed));
+
+        (msgs, delay)
+    }
+
+    /// Returns the number of messages and bytes sent and received, by message type.
+    pub fn traffic(&self) -> BTreeMap<&'static str, TrafficStats> {
+        self.traffic.lock().clone()
+    }
+
     /// Registers a sent message of the provided `size` in bytes.
     pub fn register_sent_message(&self, size: usize) {
         self.msgs_sent.fetch_add(1, Relaxed);
@@ -63,8 +108,61 @@ impl Stats {
         self.bytes_received.fetch_add(size as u64, Relaxed);
     }
 
+    /// Registers a sent message of the provided type and `size` in bytes.
+    pub fn register_sent_traffic(&self, message_type: &'static str, size: usize) {
+        let mut traffic = self.traffic.lock();
+        let stats = traffic.entry(message_type).or_default();
+        stats.msgs_sent += 1;
+        stats.bytes_sent += size as u64;
+    }
+
+    /// Registers a received message of the provided type and `size` in bytes.
+    pub fn register_received_traffic(&self, message_type: &'static str, size: usize) {
+        let mut traffic = self.traffic.lock();
+        let stats = traffic.entry(message_type).or_default();
+        stats.msgs_received += 1;
+        stats.bytes_received += size as u64;
+    }
+
     /// Registers a failure.
     pub fn register_failure(&self) {
         self.failures.fetch_add(1, Relaxed);
     }
+
+    /// Registers a message queued to be sent.
+    pub fn register_queued_message(&self) {
+        self.msgs_queued.fetch_add(1, Relaxed);
+    }
+
+    /// Registers the provided number of messages that are no longer queued, as they were sent or dropped.
+    pub fn register_dequeued_messages(&self, count: u64) {
+        let _ = self.msgs_queued.fetch_update(Relaxed, Relaxed, |queued| Some(queued.saturating_sub(count)));
+    }
+
+    /// Registers a message delayed by the bandwidth limits for the provided duration.
+    pub fn register_throttled_message(&self, delay: Duration) {
+        self.msgs_throttled.fetch_add(1, Relaxed);
+        self.throttled_micros.fetch_add(delay.as_micros() as u64, Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traffic() {
+        let stats = Stats::default();
+        assert!(stats.traffic().is_empty());
+
+        stats.register_sent_traffic("Ping", 10);
+        stats.register_sent_traffic("Ping", 20);
+        stats.register_received_traffic("Ping", 5);
+        stats.register_received_traffic("Pong", 7);
+
+        let traffic = stats.traffic();
+        assert_eq!(traffic.len(), 2);
+        assert_eq!(traffic["Ping"], TrafficStats { msgs_sent: 2, bytes_sent: 30, msgs_received: 1, bytes_received: 5 });
+        assert_eq!(traffic["Pong"], TrafficStats { msgs_sent: 0, bytes_sent: 0, msgs_received: 1, bytes_received: 7 });
+    }
 }
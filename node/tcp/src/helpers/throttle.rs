@@ -0,0 +1,87 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tokio::time::Instant;
+
+/// A token bucket limiting the rate at which bytes are sent.
+///
+/// The bucket holds up to one second worth of tokens. A message may be sent once the bucket is not in debt,
+/// and its size is charged once it is written, so that a message larger than the bucket is only delayed.
+pub(crate) struct TokenBucket {
+    /// The number of bytes per second.
+    rate: u64,
+    /// The number of available tokens (which is negative while in debt), and the time of the last refill.
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    /// Creates a full token bucket with the given rate, in bytes per second.
+    pub(crate) fn new(rate: u64) -> Self {
+        Self { rate, state: Mutex::new((rate as f64, Instant::now())) }
+    }
+
+    /// Returns the delay until the bucket is no longer in debt.
+    pub(crate) fn delay(&self) -> Duration {
+        let mut state = self.state.lock();
+        self.refill(&mut state);
+        match state.0 >= 0.0 {
+            true => Duration::ZERO,
+            false => Duration::from_secs_f64(-state.0 / self.rate as f64),
+        }
+    }
+
+    /// Charges the given number of sent bytes.
+    pub(crate) fn consume(&self, bytes: usize) {
+        let mut state = self.state.lock();
+        self.refill(&mut state);
+        state.0 -= bytes as f64;
+    }
+
+    /// Adds the tokens accumulated since the last refill, up to the capacity of the bucket.
+    fn refill(&self, (tokens, last_refill): &mut (f64, Instant)) {
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(*last_refill).as_secs_f64();
+        *tokens = (*tokens + elapsed * self.rate as f64).min(self.rate as f64);
+        *last_refill = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_token_bucket() {
+        let bucket = TokenBucket::new(1000);
+
+        // Ensure a full bucket does not delay, even if a message exceeds the available tokens.
+        assert_eq!(bucket.delay(), Duration::ZERO);
+        bucket.consume(3000);
+
+        // Ensure the debt is repaid at the given rate.
+        assert_eq!(bucket.delay(), Duration::from_secs(2));
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(bucket.delay(), Duration::from_secs(1));
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(bucket.delay(), Duration::ZERO);
+
+        // Ensure the bucket does not accumulate more than one second worth of tokens.
+        tokio::time::advance(Duration::from_secs(10)).await;
+        bucket.consume(1500);
+        assert_eq!(bucket.delay(), Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,679 @@
+// Copyright (C) 2019-2023 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at:
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Objects associated with the transport, i.e. the means by which the connection streams are established.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU32, Ordering::*},
+        Arc,
+    },
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use parking_lot::Mutex;
+use rand::Rng;
+use tokio::{
+    io::{split, AsyncRead, AsyncWrite, AsyncWriteExt, DuplexStream, ReadBuf, ReadHalf},
+    net::{TcpListener, TcpSocket, TcpStream},
+    sync::mpsc,
+    time::{sleep_until, Instant},
+};
+use tokio_util::sync::PollSender;
+
+#[cfg(doc)]
+use crate::{Config, Tcp};
+
+/// A bidirectional stream of bytes, established by a [`Transport`].
+pub trait TransportStream: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static {
+    /// Returns the local address of the stream.
+    fn local_addr(&self) -> io::Result<SocketAddr>;
+}
+
+/// A connection stream, as established by a [`Transport`].
+pub type BoxedStream = Box<dyn TransportStream>;
+
+/// A listener for inbound connections, as bound by a [`Transport`].
+#[async_trait::async_trait]
+pub trait TransportListener: Send + Sync + 'static {
+    /// Returns the address the listener is bound to.
+    fn local_addr(&self) -> io::Result<SocketAddr>;
+
+    /// Accepts an inbound connection, returning its stream and the address of the peer.
+    async fn accept(&self) -> io::Result<(BoxedStream, SocketAddr)>;
+}
+
+/// The means by which the [`Tcp`] establishes its connections; the [`Handshake`](crate::protocols::Handshake),
+/// [`Reading`](crate::protocols::Reading) and [`Writing`](crate::protocols::Writing) protocols are oblivious to it.
+#[async_trait::async_trait]
+pub trait Transport: fmt::Debug + Send + Sync + 'static {
+    /// Establishes a connection with the given address; if `local_ip` is set, the connection originates from it.
+    async fn connect(&self, addr: SocketAddr, local_ip: Option<IpAddr>) -> io::Result<BoxedStream>;
+
+    /// Binds a listener to the given address; if the port is zero, an available one is assigned.
+    async fn bind(&self, addr: SocketAddr) -> io::Result<Box<dyn TransportListener>>;
+}
+
+/// The TCP transport, used by default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TcpTransport;
+
+impl TransportStream for TcpStream {
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        TcpStream::local_addr(self)
+    }
+}
+
+#[async_trait::async_trait]
+impl TransportListener for TcpListener {
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        TcpListener::local_addr(self)
+    }
+
+    async fn accept(&self) -> io::Result<(BoxedStream, SocketAddr)> {
+        let (stream, addr) = TcpListener::accept(self).await?;
+        Ok((Box::new(stream), addr))
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for TcpTransport {
+    async fn connect(&self, addr: SocketAddr, local_ip: Option<IpAddr>) -> io::Result<BoxedStream> {
+        let stream = if let Some(local_ip) = local_ip {
+            let sock = if local_ip.is_ipv4() { TcpSocket::new_v4()? } else { TcpSocket::new_v6()? };
+            sock.bind(SocketAddr::new(local_ip, 0))?;
+            sock.connect(addr).await?
+        } else {
+            TcpStream::connect(addr).await?
+        };
+        Ok(Box::new(stream))
+    }
+
+    async fn bind(&self, addr: SocketAddr) -> io::Result<Box<dyn TransportListener>> {
+        Ok(Box::new(TcpListener::bind(addr).await?))
+    }
+}
+
+/// The first port assigned to the listeners of the simulated transports.
+const FIRST_LISTENING_PORT: u16 = 1024;
+/// The first port assigned to the outbound connections of the simulated transports.
+const FIRST_EPHEMERAL_PORT: u16 = 49152;
+/// The maximum number of attempts to find an available listening port.
+const MAX_BIND_ATTEMPTS: usize = 64;
+
+/// Assigns the ports of the simulated transports, which have no operating system to do it for them.
+/// The listening and ephemeral ports are disjoint, so that a connection cannot be mistaken for a self-connect.
+#[derive(Debug, Default)]
+struct Ports {
+    /// The number of listening ports assigned so far.
+    listening: AtomicU32,
+    /// The number of ephemeral ports assigned so far.
+    ephemeral: AtomicU32,
+}
+
+impl Ports {
+    /// Returns the next listening port.
+    fn next_listening(&self) -> u16 {
+        let range = (FIRST_EPHEMERAL_PORT - FIRST_LISTENING_PORT) as u32;
+        FIRST_LISTENING_PORT + (self.listening.fetch_add(1, Relaxed) % range) as u16
+    }
+
+    /// Returns the next ephemeral port.
+    fn next_ephemeral(&self) -> u16 {
+        let range = (u16::MAX - FIRST_EPHEMERAL_PORT) as u32 + 1;
+        FIRST_EPHEMERAL_PORT + (self.ephemeral.fetch_add(1, Relaxed) % range) as u16
+    }
+}
+
+/// Returns the address an outbound connection of a simulated transport originates from.
+fn ephemeral_addr(local_ip: Option<IpAddr>, ports: &Ports) -> SocketAddr {
+    let ip = local_ip.filter(|ip| !ip.is_unspecified()).unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+    SocketAddr::new(ip, ports.next_ephemeral())
+}
+
+/// The simulated conditions of the links of a [`MemoryTransport`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LinkConditions {
+    /// The delay with which the written bytes are delivered to the peer.
+    pub latency: Duration,
+    /// The probability (between 0 and 1) that a write is lost.
+    ///
+    /// note: A lost write resets the connection, like a TCP connection whose segments stop getting through; the
+    /// writer gets a [`io::ErrorKind::ConnectionReset`], and the peer reaches the end of the stream. Dropping a
+    /// single write instead would break the framing of the stream, rather than drop a whole message.
+    pub loss: f64,
+}
+
+/// The size (in bytes) of the buffer of each direction of an in-memory stream.
+const MEMORY_BUFFER_SIZE: usize = 64 * 1024;
+/// The maximum number of writes of an in-memory stream that are waiting to be delivered to the peer; once it is
+/// reached, further writes wait until the peer reads, as they would on a TCP connection with a full window.
+const MEMORY_MAX_PENDING_WRITES: usize = 64;
+
+/// A stream of the [`MemoryTransport`].
+pub struct MemoryStream {
+    /// The local address of the stream.
+    local_addr: SocketAddr,
+    /// The simulated conditions of the link.
+    conditions: LinkConditions,
+    /// The reading half of the stream.
+    reader: ReadHalf<DuplexStream>,
+    /// The queue of writes to be delivered to the peer, along with their delivery time; `None` once shut down.
+    writer: Option<PollSender<(Instant, Bytes)>>,
+}
+
+impl MemoryStream {
+    /// Returns a pair of connected streams with the given local addresses.
+    fn pair(addrs: (SocketAddr, SocketAddr), conditions: LinkConditions) -> (Self, Self) {
+        let (a, b) = tokio::io::duplex(MEMORY_BUFFER_SIZE);
+        (Self::new(a, addrs.0, conditions), Self::new(b, addrs.1, conditions))
+    }
+
+    /// Wraps one side of a duplex stream, spawning the task that delivers its writes to the peer.
+    fn new(stream: DuplexStream, local_addr: SocketAddr, conditions: LinkConditions) -> Self {
+        let (reader, mut writer) = split(stream);
+        let (sender, mut receiver) = mpsc::channel::<(Instant, Bytes)>(MEMORY_MAX_PENDING_WRITES);
+        tokio::spawn(async move {
+            while let Some((deadline, bytes)) = receiver.recv().await {
+                sleep_until(deadline).await;
+                if writer.write_all(&bytes).await.is_err() {
+                    return;
+                }
+            }
+            // The stream was shut down or dropped; signal the end of the stream to the peer.
+            let _ = writer.shutdown().await;
+        });
+        Self { local_addr, conditions, reader, writer: Some(PollSender::new(sender)) }
+    }
+}
+
+impl TransportStream for MemoryStream {
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.local_addr)
+    }
+}
+
+impl AsyncRead for MemoryStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().reader).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for MemoryStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let Some(writer) = &mut this.writer else { return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())) };
+        // Wait until the queue has room for the write.
+        if ready!(writer.poll_reserve(cx)).is_err() {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        // Simulate the loss of the write, by resetting the connection.
+        if this.conditions.loss > 0.0 && rand::thread_rng().gen_bool(this.conditions.loss.min(1.0)) {
+            this.writer = None;
+            return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+        }
+        // Queue the write for delivery after the simulated latency.
+        match writer.send_item((Instant::now() + this.conditions.latency, Bytes::copy_from_slice(buf))) {
+            Ok(()) => Poll::Ready(Ok(buf.len())),
+            Err(_) => Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().writer = None;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// The inbound connections of a [`MemoryListener`].
+type MemoryConnections = mpsc::UnboundedSender<(MemoryStream, SocketAddr)>;
+
+/// An in-memory transport with simulated link conditions, which allows many nodes to run in a single process
+/// without competing for the ports of the host.
+///
+/// The clones of a `MemoryTransport` share the same network, which behaves like a single host: the listeners
+/// are identified by their port only, and any listening port can be reached through any IP address.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryTransport(Arc<InnerMemoryTransport>);
+
+#[derive(Debug, Default)]
+struct InnerMemoryTransport {
+    /// The simulated conditions of the links.
+    conditions: LinkConditions,
+    /// The listeners, indexed by their port.
+    listeners: Mutex<HashMap<u16, MemoryConnections>>,
+    /// The assigned ports.
+    ports: Ports,
+}
+
+impl MemoryTransport {
+    /// Initializes a new in-memory network with the given link conditions.
+    pub fn new(conditions: LinkConditions) -> Self {
+        Self(Arc::new(InnerMemoryTransport { conditions, ..Default::default() }))
+    }
+
+    /// Returns the simulated conditions of the links.
+    pub fn conditions(&self) -> LinkConditions {
+        self.0.conditions
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for MemoryTransport {
+    async fn connect(&self, addr: SocketAddr, local_ip: Option<IpAddr>) -> io::Result<BoxedStream> {
+        // Retrieve the listener, if there is one.
+        let listener = self.0.listeners.lock().get(&addr.port()).cloned();
+        let listener = listener.ok_or(io::ErrorKind::ConnectionRefused)?;
+        // Create the connection, and deliver its other side to the listener.
+        let local_addr = ephemeral_addr(local_ip, &self.0.ports);
+        let (stream, peer_stream) = MemoryStream::pair((local_addr, addr), self.0.conditions);
+        listener.send((peer_stream, local_addr)).map_err(|_| io::ErrorKind::ConnectionRefused)?;
+        Ok(Box::new(stream))
+    }
+
+    async fn bind(&self, addr: SocketAddr) -> io::Result<Box<dyn TransportListener>> {
+        let mut listeners = self.0.listeners.lock();
+        // Determine the listening port.
+        let port = match addr.port() {
+            0 => (0..MAX_BIND_ATTEMPTS)
+                .map(|_| self.0.ports.next_listening())
+                .find(|port| !listeners.contains_key(port))
+                .ok_or(io::ErrorKind::AddrNotAvailable)?,
+            port if listeners.contains_key(&port) => return Err(io::ErrorKind::AddrInUse.into()),
+            port => port,
+        };
+        // Register the listener.
+        let (sender, receiver) = mpsc::unbounded_channel();
+        listeners.insert(port, sender);
+
+        Ok(Box::new(MemoryListener {
+            local_addr: SocketAddr::new(addr.ip(), port),
+            receiver: tokio::sync::Mutex::new(receiver),
+            transport: self.clone(),
+        }))
+    }
+}
+
+/// A listener of the [`MemoryTransport`]; it is unregistered once dropped.
+struct MemoryListener {
+    /// The address of the listener.
+    local_addr: SocketAddr,
+    /// The inbound connections.
+    receiver: tokio::sync::Mutex<mpsc::UnboundedReceiver<(MemoryStream, SocketAddr)>>,
+    /// The transport the listener is registered with.
+    transport: MemoryTransport,
+}
+
+#[async_trait::async_trait]
+impl TransportListener for MemoryListener {
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.local_addr)
+    }
+
+    async fn accept(&self) -> io::Result<(BoxedStream, SocketAddr)> {
+        match self.receiver.lock().await.recv().await {
+            Some((stream, addr)) => Ok((Box::new(stream), addr)),
+            None => Err(io::ErrorKind::NotConnected.into()),
+        }
+    }
+}
+
+impl Drop for MemoryListener {
+    fn drop(&mut self) {
+        self.transport.0.listeners.lock().remove(&self.local_addr.port());
+    }
+}
+
+#[cfg(unix)]
+pub use unix::UnixTransport;
+
+#[cfg(unix)]
+mod unix {
+    use super::*;
+
+    use std::path::{Path, PathBuf};
+    use tokio::{
+        io::AsyncReadExt,
+        net::{UnixListener, UnixStream},
+        time::timeout,
+    };
+
+    /// The maximum time allowed for the initiator of a connection to announce its address.
+    const ANNOUNCEMENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// A transport over Unix domain sockets, which allows many nodes on a single host to run without competing
+    /// for its ports.
+    ///
+    /// The listeners are bound to sockets in the given directory, which are named after their (virtual) port,
+    /// and the initiator of a connection announces its (virtual) address to the listener upon connecting.
+    /// The nodes that share a directory should also share the `UnixTransport`, so that their ports are unique.
+    #[derive(Clone, Debug)]
+    pub struct UnixTransport(Arc<InnerUnixTransport>);
+
+    #[derive(Debug)]
+    struct InnerUnixTransport {
+        /// The directory of the sockets.
+        dir: PathBuf,
+        /// The assigned ports.
+        ports: Ports,
+    }
+
+    impl UnixTransport {
+        /// Initializes a new transport with sockets in the given directory.
+        ///
+        /// note: The path of a socket is limited to around 100 bytes on most systems.
+        pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+            Self(Arc::new(InnerUnixTransport { dir: dir.into(), ports: Default::default() }))
+        }
+
+        /// Returns the directory of the sockets.
+        pub fn dir(&self) -> &Path {
+            &self.0.dir
+        }
+
+        /// Returns the path of the socket for the given port.
+        fn socket_path(&self, port: u16) -> PathBuf {
+            self.0.dir.join(format!("{port}.sock"))
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Transport for UnixTransport {
+        async fn connect(&self, addr: SocketAddr, local_ip: Option<IpAddr>) -> io::Result<BoxedStream> {
+            let mut stream = UnixStream::connect(self.socket_path(addr.port())).await.map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => io::ErrorKind::ConnectionRefused.into(),
+                _ => e,
+            })?;
+            // Announce the address of the connection to the listener.
+            let local_addr = ephemeral_addr(local_ip, &self.0.ports);
+            write_addr(&mut stream, local_addr).await?;
+            Ok(Box::new(UnixConnection { stream, local_addr }))
+        }
+
+        async fn bind(&self, addr: SocketAddr) -> io::Result<Box<dyn TransportListener>> {
+            let bind = |port: u16| {
+                let path = self.socket_path(port);
+                UnixListener::bind(&path).map(|listener| UnixTransportListener {
+                    listener,
+                    local_addr: SocketAddr::new(addr.ip(), port),
+                    path,
+                })
+            };
+            let listener = match addr.port() {
+                0 => (0..MAX_BIND_ATTEMPTS)
+                    .map(|_| bind(self.0.ports.next_listening()))
+                    .find(|result| !matches!(result, Err(e) if e.kind() == io::ErrorKind::AddrInUse))
+                    .unwrap_or_else(|| Err(io::ErrorKind::AddrNotAvailable.into()))?,
+                port => bind(port)?,
+            };
+            Ok(Box::new(listener))
+        }
+    }
+
+    /// Writes the given address to the stream.
+    async fn write_addr(stream: &mut UnixStream, addr: SocketAddr) -> io::Result<()> {
+        let ip = match addr.ip() {
+            IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+            IpAddr::V6(ip) => ip,
+        };
+        let mut buffer = [0u8; 18];
+        buffer[..16].copy_from_slice(&ip.octets());
+        buffer[16..].copy_from_slice(&addr.port().to_be_bytes());
+        stream.write_all(&buffer).await
+    }
+
+    /// Reads an address from the stream.
+    async fn read_addr(stream: &mut UnixStream) -> io::Result<SocketAddr> {
+        let mut buffer = [0u8; 18];
+        stream.read_exact(&mut buffer).await?;
+        let mut octets = [0u8; 16];
+        octets.copy_from_slice(&buffer[..16]);
+        let ip = std::net::Ipv6Addr::from(octets).to_canonical();
+        Ok(SocketAddr::new(ip, u16::from_be_bytes([buffer[16], buffer[17]])))
+    }
+
+    /// A stream of the [`UnixTransport`].
+    struct UnixConnection {
+        /// The underlying stream.
+        stream: UnixStream,
+        /// The (virtual) local address of the stream.
+        local_addr: SocketAddr,
+    }
+
+    impl TransportStream for UnixConnection {
+        fn local_addr(&self) -> io::Result<SocketAddr> {
+            Ok(self.local_addr)
+        }
+    }
+
+    impl AsyncRead for UnixConnection {
+        fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.get_mut().stream).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for UnixConnection {
+        fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.get_mut().stream).poll_write(cx, buf)
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.get_mut().stream).poll_flush(cx)
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.get_mut().stream).poll_shutdown(cx)
+        }
+    }
+
+    /// A listener of the [`UnixTransport`]; its socket is removed once dropped.
+    struct UnixTransportListener {
+        /// The underlying listener.
+        listener: UnixListener,
+        /// The (virtual) address of the listener.
+        local_addr: SocketAddr,
+        /// The path of the socket.
+        path: PathBuf,
+    }
+
+    #[async_trait::async_trait]
+    impl TransportListener for UnixTransportListener {
+        fn local_addr(&self) -> io::Result<SocketAddr> {
+            Ok(self.local_addr)
+        }
+
+        async fn accept(&self) -> io::Result<(BoxedStream, SocketAddr)> {
+            let (mut stream, _) = self.listener.accept().await?;
+            // Read the address announced by the initiator of the connection.
+            let peer_addr = timeout(ANNOUNCEMENT_TIMEOUT, read_addr(&mut stream))
+                .await
+                .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??;
+            Ok((Box::new(UnixConnection { stream, local_addr: self.local_addr }), peer_addr))
+        }
+    }
+
+    impl Drop for UnixTransportListener {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Config, Tcp};
+    use tokio::{io::AsyncReadExt, time::timeout};
+
+    /// Returns the address of a listener on the given port.
+    fn localhost(port: u16) -> SocketAddr {
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)
+    }
+
+    /// Connects to a new listener of the given transport, returning the initiator and responder streams.
+    async fn connected_pair(transport: &dyn Transport) -> (BoxedStream, BoxedStream) {
+        let listener = transport.bind(localhost(0)).await.unwrap();
+        let listening_addr = listener.local_addr().unwrap();
+        let initiator = transport.connect(listening_addr, None).await.unwrap();
+        let (responder, initiator_addr) = listener.accept().await.unwrap();
+        assert_eq!(initiator.local_addr().unwrap(), initiator_addr);
+        assert_eq!(responder.local_addr().unwrap(), listening_addr);
+        (initiator, responder)
+    }
+
+    #[tokio::test]
+    async fn test_memory_transport() {
+        let transport = MemoryTransport::default();
+        let (mut initiator, mut responder) = connected_pair(&transport).await;
+
+        // Ensure the bytes are delivered in both directions.
+        initiator.write_all(b"ping").await.unwrap();
+        let mut buffer = [0u8; 4];
+        responder.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(&buffer, b"ping");
+        responder.write_all(b"pong").await.unwrap();
+        initiator.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(&buffer, b"pong");
+
+        // Ensure the end of the stream is delivered.
+        drop(initiator);
+        assert_eq!(responder.read(&mut buffer).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_memory_transport_ports() {
+        let transport = MemoryTransport::default();
+        let listener = transport.bind(localhost(0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // Ensure a port cannot be bound twice.
+        assert_eq!(transport.bind(localhost(port)).await.err().unwrap().kind(), io::ErrorKind::AddrInUse);
+        // Ensure the port is released once the listener is dropped.
+        drop(listener);
+        assert_eq!(
+            transport.connect(localhost(port), None).await.err().unwrap().kind(),
+            io::ErrorKind::ConnectionRefused
+        );
+        transport.bind(localhost(port)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_memory_transport_conditions() {
+        // Ensure the writes are delayed by the latency.
+        let latency = Duration::from_millis(100);
+        let transport = MemoryTransport::new(LinkConditions { latency, loss: 0.0 });
+        let (mut initiator, mut responder) = connected_pair(&transport).await;
+        let start = Instant::now();
+        initiator.write_all(b"ping").await.unwrap();
+        let mut buffer = [0u8; 4];
+        responder.read_exact(&mut buffer).await.unwrap();
+        assert!(start.elapsed() >= latency);
+
+        // Ensure a lost write resets the connection, instead of leaving a gap in the stream.
+        let transport = MemoryTransport::new(LinkConditions { latency: Duration::ZERO, loss: 1.0 });
+        let (mut initiator, mut responder) = connected_pair(&transport).await;
+        assert_eq!(initiator.write_all(b"ping").await.unwrap_err().kind(), io::ErrorKind::ConnectionReset);
+        assert_eq!(initiator.write_all(b"ping").await.unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(responder.read(&mut buffer).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_memory_transport_backpressure() {
+        let transport = MemoryTransport::default();
+        let (mut initiator, mut responder) = connected_pair(&transport).await;
+
+        // Ensure the writes stall once the peer stops reading, instead of queueing without bound.
+        let chunk = [0u8; 1024];
+        let num_chunks = 4 * (MEMORY_BUFFER_SIZE / chunk.len() + MEMORY_MAX_PENDING_WRITES);
+        let mut num_written = 0;
+        let _ = timeout(Duration::from_millis(100), async {
+            while num_written < num_chunks {
+                initiator.write_all(&chunk).await.unwrap();
+                num_written += 1;
+            }
+        })
+        .await;
+        assert!(num_written < num_chunks);
+
+        // Ensure the writes resume once the peer reads.
+        let mut buffer = vec![0u8; num_written * chunk.len()];
+        responder.read_exact(&mut buffer).await.unwrap();
+        initiator.write_all(&chunk).await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_transport() {
+        let dir = std::env::temp_dir().join(format!("snarkos-tcp-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let transport = UnixTransport::new(&dir);
+        let (mut initiator, mut responder) = connected_pair(&transport).await;
+
+        // Ensure the bytes are delivered.
+        initiator.write_all(b"ping").await.unwrap();
+        let mut buffer = [0u8; 4];
+        responder.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(&buffer, b"ping");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_over_memory_transport() {
+        let transport: Arc<dyn Transport> = Arc::new(MemoryTransport::default());
+        let config = || Config {
+            listener_ip: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            max_connections: 1,
+            transport: transport.clone(),
+            ..Default::default()
+        };
+
+        // Initialize the nodes.
+        let tcp = Tcp::new(config());
+        let node_ip = tcp.enable_listener().await.unwrap();
+        let peer = Tcp::new(config());
+        let peer_ip = peer.enable_listener().await.unwrap();
+
+        // Ensure self-connecting is not possible.
+        tcp.connect(node_ip).await.unwrap_err();
+
+        // Connect to the peer.
+        tcp.connect(peer_ip).await.unwrap();
+        assert!(tcp.is_connected(peer_ip));
+        deadline(|| peer.num_connected() == 1).await;
+    }
+
+    /// Waits until the given condition is met.
+    async fn deadline<F: Fn() -> bool>(condition: F) {
+        timeout(Duration::from_secs(1), async {
+            while !condition() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
@@ -16,7 +16,6 @@ use std::{io, time::Duration};
 
 use tokio::{
     io::{split, AsyncRead, AsyncWrite},
-    net::TcpStream,
     sync::{mpsc, oneshot},
     time::timeout,
 };
@@ -24,6 +23,7 @@ use tracing::*;
 
 use crate::{
     protocols::{ProtocolHandler, ReturnableConnection},
+    BoxedStream,
     Connection,
     P2P,
 };
@@ -97,13 +97,13 @@ where
     async fn perform_handshake(&self, conn: Connection) -> io::Result<Connection>;
 
     /// Borrows the full connection stream to be used in the implementation of [`Handshake::perform_handshake`].
-    fn borrow_stream<'a>(&self, conn: &'a mut Connection) -> &'a mut TcpStream {
+    fn borrow_stream<'a>(&self, conn: &'a mut Connection) -> &'a mut BoxedStream {
         conn.stream.as_mut().unwrap()
     }
 
     /// Assumes full control of a connection's stream in the implementation of [`Handshake::perform_handshake`], by
     /// the end of which it *must* be followed by [`Handshake::return_stream`].
-    fn take_stream(&self, conn: &mut Connection) -> TcpStream {
+    fn take_stream(&self, conn: &mut Connection) -> BoxedStream {
         conn.stream.take().unwrap()
     }
 
@@ -17,7 +17,6 @@ use crate::{protocols::Handshake, Config};
 use crate::{
     protocols::{ProtocolHandler, ReturnableConnection},
     ConnectionSide,
-    Tcp,
     P2P,
 };
 
@@ -93,6 +92,13 @@ where
     /// The `side` param indicates the connection side **from the node's perspective**.
     fn codec(&self, addr: SocketAddr, side: ConnectionSide) -> Self::Codec;
 
+    /// Returns the type of the given inbound message, which is used to account for the traffic by message type.
+    ///
+    /// The default is `None`, i.e. the traffic is not accounted for by message type.
+    fn message_type(&self, _message: &Self::Message) -> Option<&'static str> {
+        None
+    }
+
     /// Processes an inbound message. Can be used to update state, send replies etc.
     async fn process_message(&self, source: SocketAddr, message: Self::Message) -> io::Result<()>;
 }
@@ -108,7 +114,7 @@ trait ReadingInternal: Reading {
         &self,
         framed: FramedRead<T, Self::Codec>,
         addr: SocketAddr,
-    ) -> FramedRead<T, CountingCodec<Self::Codec>>;
+    ) -> FramedRead<T, CountingCodec<Self>>;
 }
 
 #[async_trait]
@@ -201,22 +207,22 @@ impl<R: Reading> ReadingInternal for R {
         &self,
         framed: FramedRead<T, Self::Codec>,
         addr: SocketAddr,
-    ) -> FramedRead<T, CountingCodec<Self::Codec>> {
-        framed.map_decoder(|codec| CountingCodec { codec, node: self.tcp().clone(), addr, acc: 0 })
+    ) -> FramedRead<T, CountingCodec<Self>> {
+        framed.map_decoder(|codec| CountingCodec { codec, reader: self.clone(), addr, acc: 0 })
     }
 }
 
 /// A wrapper [`Decoder`] that also counts the inbound messages.
-struct CountingCodec<D: Decoder> {
-    codec: D,
-    node: Tcp,
+struct CountingCodec<R: Reading> {
+    codec: R::Codec,
+    reader: R,
     addr: SocketAddr,
     acc: usize,
 }
 
-impl<D: Decoder> Decoder for CountingCodec<D> {
-    type Error = D::Error;
-    type Item = D::Item;
+impl<R: Reading> Decoder for CountingCodec<R> {
+    type Error = io::Error;
+    type Item = R::Message;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
         let initial_buf_len = src.len();
@@ -225,12 +231,17 @@ impl<D: Decoder> Decoder for CountingCodec<D> {
         let read_len = initial_buf_len - final_buf_len + self.acc;
 
         if read_len != 0 {
-            trace!(parent: self.node.span(), "read {}B from {}", read_len, self.addr);
+            let node = self.reader.tcp();
+            trace!(parent: node.span(), "read {}B from {}", read_len, self.addr);
 
-            if ret.is_some() {
+            if let Some(message) = &ret {
                 self.acc = 0;
-                self.node.known_peers().register_received_message(self.addr, read_len);
-                self.node.stats().register_received_message(read_len);
+                node.known_peers().register_received_message(self.addr, read_len);
+                node.stats().register_received_message(read_len);
+                if let Some(message_type) = self.reader.message_type(message) {
+                    node.known_peers().register_received_traffic(self.addr, message_type, read_len);
+                    node.stats().register_received_traffic(message_type, read_len);
+                }
             } else {
                 self.acc = read_len;
             }
@@ -12,7 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{any::Any, collections::HashMap, io, net::SocketAddr, sync::Arc};
+use std::{
+    any::Any,
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering::Relaxed},
+        Arc,
+    },
+    time::Duration,
+};
 
 use async_trait::async_trait;
 use futures_util::sink::SinkExt;
@@ -25,15 +35,36 @@ use tokio_util::codec::{Encoder, FramedWrite};
 use tracing::*;
 
 #[cfg(doc)]
-use crate::{protocols::Handshake, Config, Tcp};
+use crate::{protocols::Handshake, Config};
 use crate::{
     protocols::{Protocol, ProtocolHandler, ReturnableConnection},
     Connection,
     ConnectionSide,
+    Tcp,
+    TokenBucket,
     P2P,
 };
 
-type WritingSenders = Arc<RwLock<HashMap<SocketAddr, mpsc::Sender<WrappedMessage>>>>;
+type WritingSenders = Arc<RwLock<HashMap<SocketAddr, PrioritySenders>>>;
+
+/// The number of priority classes of outbound messages.
+const NUM_PRIORITIES: usize = 3;
+/// The number of messages served from each priority class per round while the classes are backlogged,
+/// so that a steady stream of higher priority messages cannot starve the lower ones.
+const PRIORITY_WEIGHTS: [usize; NUM_PRIORITIES] = [8, 4, 1];
+
+/// The priority class of an outbound message; the messages of a higher class are sent ahead of the ones
+/// of a lower class that are queued for the same connection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MessagePriority {
+    /// Latency-critical messages, e.g. consensus messages.
+    High = 0,
+    /// Regular messages.
+    #[default]
+    Normal = 1,
+    /// Large messages that are not latency-critical, e.g. sync data.
+    Bulk = 2,
+}
 
 /// Can be used to specify and enable writing, i.e. sending outbound messages. If the [`Handshake`]
 /// protocol is enabled too, it goes into force only after the handshake has been concluded.
@@ -42,8 +73,8 @@ pub trait Writing: P2P
 where
     Self: Clone + Send + Sync + 'static,
 {
-    /// The depth of per-connection queues used to send outbound messages; the greater it is, the more outbound
-    /// messages the node can enqueue. Setting it to a large value is not recommended, as doing it might
+    /// The depth of per-connection queues used to send outbound messages (one for each [`MessagePriority`]);
+    /// the greater it is, the more outbound messages the node can enqueue. Setting it to a large value is not recommended, as doing it might
     /// obscure potential issues with your implementation (like slow serialization) or network.
     ///
     /// The default value is 1024.
@@ -92,6 +123,20 @@ where
     /// The `side` param indicates the connection side **from the node's perspective**.
     fn codec(&self, addr: SocketAddr, side: ConnectionSide) -> Self::Codec;
 
+    /// Returns the priority class of the given outbound message.
+    ///
+    /// The default is [`MessagePriority::Normal`] for every message.
+    fn message_priority(&self, _message: &Self::Message) -> MessagePriority {
+        MessagePriority::Normal
+    }
+
+    /// Returns the type of the given outbound message, which is used to account for the traffic by message type.
+    ///
+    /// The default is `None`, i.e. the traffic is not accounted for by message type.
+    fn message_type(&self, _message: &Self::Message) -> Option<&'static str> {
+        None
+    }
+
     /// Sends the provided message to the specified [`SocketAddr`]. Returns as soon as the message is queued to
     /// be sent, without waiting for the actual delivery; instead, the caller is provided with a [`oneshot::Receiver`]
     /// which can be used to determine when and whether the message has been delivered.
@@ -106,10 +151,11 @@ where
         // access the protocol handler
         if let Some(handler) = self.tcp().protocols.writing.get() {
             // find the message sender for the given address
-            if let Some(sender) = handler.senders.read().get(&addr).cloned() {
+            if let Some(senders) = handler.senders.read().get(&addr).cloned() {
+                let priority = self.message_priority(&message);
                 let (msg, delivery) = WrappedMessage::new(Box::new(message));
-                sender
-                    .try_send(msg)
+                senders
+                    .try_send(self.tcp(), addr, priority, msg)
                     .map_err(|e| {
                         error!(parent: self.tcp().span(), "can't send a message to {}: {}", addr, e);
                         self.tcp().stats().register_failure();
@@ -139,9 +185,10 @@ where
         // access the protocol handler
         if let Some(handler) = self.tcp().protocols.writing.get() {
             let senders = handler.senders.read().clone();
-            for (addr, message_sender) in senders {
+            let priority = self.message_priority(&message);
+            for (addr, message_senders) in senders {
                 let (msg, _delivery) = WrappedMessage::new(Box::new(message.clone()));
-                let _ = message_sender.try_send(msg).map_err(|e| {
+                let _ = message_senders.try_send(self.tcp(), addr, priority, msg).map_err(|e| {
                     error!(parent: self.tcp().span(), "can't send a message to {}: {}", addr, e);
                     self.tcp().stats().register_failure();
                 });
@@ -192,13 +239,23 @@ impl<W: Writing> WritingInternal for W {
         let writer = conn.writer.take().expect("missing connection writer!");
         let mut framed = FramedWrite::new(writer, codec);
 
-        let (outbound_message_sender, mut outbound_message_receiver) = mpsc::channel(Self::MESSAGE_QUEUE_DEPTH);
+        let (outbound_message_senders, mut outbound_message_receivers) =
+            PrioritySenders::new(Self::MESSAGE_QUEUE_DEPTH);
+        let queued = Arc::clone(&outbound_message_senders.queued);
 
-        // register the connection's message sender with the Writing protocol handler
-        conn_senders.write().insert(addr, outbound_message_sender);
+        // register the connection's message senders with the Writing protocol handler
+        conn_senders.write().insert(addr, outbound_message_senders);
 
-        // this will automatically drop the sender upon a disconnect
-        let auto_cleanup = SenderCleanup { addr, senders: Arc::clone(conn_senders) };
+        // this will automatically drop the senders upon a disconnect
+        let auto_cleanup = SenderCleanup {
+            addr,
+            senders: Arc::clone(conn_senders),
+            queued: Arc::clone(&queued),
+            tcp: self.tcp().clone(),
+        };
+
+        // the per-connection bandwidth limit, if configured
+        let peer_limiter = self.tcp().config().max_send_rate_per_peer.filter(|rate| *rate > 0).map(TokenBucket::new);
 
         // use a channel to know when the writer task is ready
         let (tx_writer, rx_writer) = oneshot::channel();
@@ -213,14 +270,41 @@ impl<W: Writing> WritingInternal for W {
             // move the cleanup into the task that gets aborted on disconnect
             let _auto_cleanup = auto_cleanup;
 
-            while let Some(wrapped_msg) = outbound_message_receiver.recv().await {
+            while let Some(wrapped_msg) = outbound_message_receivers.recv().await {
+                queued.fetch_sub(1, Relaxed);
+                node.stats().register_dequeued_messages(1);
+                node.known_peers().register_dequeued_messages(addr, 1);
+
+                // wait until the bandwidth limits allow the message to be sent
+                let limiters = [peer_limiter.as_ref(), node.send_limiter()];
+                let mut throttled = Duration::ZERO;
+                loop {
+                    let delay = limiters.iter().flatten().map(|limiter| limiter.delay()).max().unwrap_or_default();
+                    if delay.is_zero() {
+                        break;
+                    }
+                    tokio::time::sleep(delay).await;
+                    throttled += delay;
+                }
+                if !throttled.is_zero() {
+                    node.stats().register_throttled_message(throttled);
+                    node.known_peers().register_throttled_message(addr, throttled);
+                    trace!(parent: node.span(), "throttled a message to {} for {:?}", addr, throttled);
+                }
+
                 let msg = wrapped_msg.msg.downcast().unwrap();
+                let message_type = self_clone.message_type(&msg);
 
                 match self_clone.write_to_stream(*msg, &mut framed).await {
                     Ok(len) => {
+                        limiters.iter().flatten().for_each(|limiter| limiter.consume(len));
                         let _ = wrapped_msg.delivery_notification.send(Ok(()));
                         node.known_peers().register_sent_message(addr, len);
                         node.stats().register_sent_message(len);
+                        if let Some(message_type) = message_type {
+                            node.known_peers().register_sent_traffic(addr, message_type, len);
+                            node.stats().register_sent_traffic(message_type, len);
+                        }
                         trace!(parent: node.span(), "sent {}B to {}", len, addr);
                     }
                     Err(e) => {
@@ -262,6 +346,89 @@ impl WrappedMessage {
     }
 }
 
+/// The senders of the outbound message queues of a connection, one for each priority class.
+#[derive(Clone)]
+struct PrioritySenders {
+    senders: [mpsc::Sender<WrappedMessage>; NUM_PRIORITIES],
+    /// The number of messages queued for the connection.
+    queued: Arc<AtomicU64>,
+}
+
+impl PrioritySenders {
+    /// Creates the outbound message queues of a connection with the given depth.
+    fn new(depth: usize) -> (Self, PriorityReceivers) {
+        let (high_tx, high_rx) = mpsc::channel(depth);
+        let (normal_tx, normal_rx) = mpsc::channel(depth);
+        let (bulk_tx, bulk_rx) = mpsc::channel(depth);
+
+        let senders = Self { senders: [high_tx, normal_tx, bulk_tx], queued: Default::default() };
+        let receivers = PriorityReceivers { receivers: [high_rx, normal_rx, bulk_rx], credits: PRIORITY_WEIGHTS };
+
+        (senders, receivers)
+    }
+
+    /// Queues the given message in the queue of the given priority class.
+    fn try_send(
+        &self,
+        tcp: &Tcp,
+        addr: SocketAddr,
+        priority: MessagePriority,
+        msg: WrappedMessage,
+    ) -> Result<(), mpsc::error::TrySendError<WrappedMessage>> {
+        // register the message first, as it may be sent before this method returns
+        self.queued.fetch_add(1, Relaxed);
+        tcp.stats().register_queued_message();
+        tcp.known_peers().register_queued_message(addr);
+
+        self.senders[priority as usize].try_send(msg).inspect_err(|_| {
+            self.queued.fetch_sub(1, Relaxed);
+            tcp.stats().register_dequeued_messages(1);
+            tcp.known_peers().register_dequeued_messages(addr, 1);
+        })
+    }
+}
+
+/// The receivers of the outbound message queues of a connection, one for each priority class.
+struct PriorityReceivers {
+    receivers: [mpsc::Receiver<WrappedMessage>; NUM_PRIORITIES],
+    /// The number of messages each priority class may still be served in the current round.
+    credits: [usize; NUM_PRIORITIES],
+}
+
+impl PriorityReceivers {
+    /// Receives the next message to send, using a weighted round-robin over the priority classes: within a round,
+    /// the highest priority class that is not empty and has credits left is served, and a new round starts once
+    /// none of the classes that are not empty have credits left (see [`PRIORITY_WEIGHTS`]).
+    /// Returns `None` once all the senders are dropped.
+    async fn recv(&mut self) -> Option<WrappedMessage> {
+        for _ in 0..2 {
+            for (receiver, credits) in self.receivers.iter_mut().zip(self.credits.iter_mut()) {
+                if *credits > 0 {
+                    if let Ok(msg) = receiver.try_recv() {
+                        *credits -= 1;
+                        return Some(msg);
+                    }
+                }
+            }
+            // the classes that are not empty have used up their credits; start a new round
+            self.credits = PRIORITY_WEIGHTS;
+        }
+
+        // all the queues are empty; wait for the next message
+        let [high, normal, bulk] = &mut self.receivers;
+        let (priority, msg) = tokio::select! {
+            biased;
+            Some(msg) = high.recv() => (MessagePriority::High, msg),
+            Some(msg) = normal.recv() => (MessagePriority::Normal, msg),
+            Some(msg) = bulk.recv() => (MessagePriority::Bulk, msg),
+            else => return None,
+        };
+        self.credits[priority as usize] -= 1;
+
+        Some(msg)
+    }
+}
+
 /// The handler object dedicated to the [`Writing`] protocol.
 pub(crate) struct WritingHandler {
     handler: ProtocolHandler<Connection, io::Result<Connection>>,
@@ -277,10 +444,130 @@ impl Protocol<Connection, io::Result<Connection>> for WritingHandler {
 struct SenderCleanup {
     addr: SocketAddr,
     senders: WritingSenders,
+    queued: Arc<AtomicU64>,
+    tcp: Tcp,
 }
 
 impl Drop for SenderCleanup {
     fn drop(&mut self) {
         self.senders.write().remove(&self.addr);
+
+        // the messages that are still queued will never be sent
+        let queued = self.queued.swap(0, Relaxed);
+        self.tcp.stats().register_dequeued_messages(queued);
+        self.tcp.known_peers().register_dequeued_messages(self.addr, queued);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, MemoryTransport, Transport};
+
+    use bytes::Bytes;
+    use std::net::{IpAddr, Ipv4Addr};
+    use tokio::time::Instant;
+    use tokio_util::codec::BytesCodec;
+
+    /// A node that sends raw bytes.
+    #[derive(Clone)]
+    struct WritingNode(Tcp);
+
+    impl P2P for WritingNode {
+        fn tcp(&self) -> &Tcp {
+            &self.0
+        }
+    }
+
+    #[async_trait]
+    impl Writing for WritingNode {
+        type Codec = BytesCodec;
+        type Message = Bytes;
+
+        fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
+            Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_priority_queues() {
+        let tcp = Tcp::new(Config::default());
+        let addr = SocketAddr::from(([127, 0, 0, 1], 4130));
+        let (senders, mut receivers) = PrioritySenders::new(64);
+
+        // Queue the lower priority messages before the higher priority messages.
+        for (priority, count) in
+            [(MessagePriority::Bulk, 4u8), (MessagePriority::Normal, 16), (MessagePriority::High, 40)]
+        {
+            for i in 0..count {
+                let (msg, _delivery) = WrappedMessage::new(Box::new((priority, i)));
+                senders.try_send(&tcp, addr, priority, msg).unwrap();
+            }
+        }
+        assert_eq!(tcp.stats().queued(), 60);
+
+        // Ensure every round serves each priority class according to its weight, starting with the highest one.
+        let round: usize = PRIORITY_WEIGHTS.iter().sum();
+        let mut received = Vec::new();
+        for _ in 0..2 * round {
+            let msg = receivers.recv().await.unwrap();
+            received.push(msg.msg.downcast::<(MessagePriority, u8)>().unwrap().0);
+        }
+        for round in received.chunks(round) {
+            let expected = [MessagePriority::High, MessagePriority::Normal, MessagePriority::Bulk]
+                .into_iter()
+                .zip(PRIORITY_WEIGHTS)
+                .flat_map(|(priority, weight)| vec![priority; weight])
+                .collect::<Vec<_>>();
+            assert_eq!(round, expected);
+        }
+
+        // Ensure a priority class is served beyond its weight once the other classes are empty.
+        let mut remaining = Vec::new();
+        for _ in 0..60 - 2 * round {
+            remaining.push(receivers.recv().await.unwrap().msg.downcast::<(MessagePriority, u8)>().unwrap().0);
+        }
+        assert!(remaining.ends_with(&[MessagePriority::High; 8]));
+
+        // Ensure the receivers are closed once the senders are dropped.
+        drop(senders);
+        assert!(receivers.recv().await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_send_rate_limit() {
+        const RATE: u64 = 1000;
+
+        let transport: Arc<dyn Transport> = Arc::new(MemoryTransport::default());
+        let config = |max_send_rate_per_peer| Config {
+            listener_ip: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            max_connections: 1,
+            max_send_rate_per_peer,
+            transport: transport.clone(),
+            ..Default::default()
+        };
+
+        // Initialize the nodes.
+        let node = WritingNode(Tcp::new(config(Some(RATE))));
+        node.enable_writing().await;
+        let peer = Tcp::new(config(None));
+        let peer_ip = peer.enable_listener().await.unwrap();
+        node.tcp().connect(peer_ip).await.unwrap();
+
+        // Send twice as many bytes as the bucket holds.
+        let start = Instant::now();
+        let message = Bytes::from(vec![0u8; RATE as usize / 2]);
+        let deliveries: Vec<_> = (0..4).map(|_| node.unicast(peer_ip, message.clone()).unwrap()).collect();
+        for delivery in deliveries {
+            delivery.await.unwrap().unwrap();
+        }
+
+        // Ensure the writing task delayed the last message until the debt was repaid.
+        // Note: The clock is paused, so it only advances while the tasks are waiting on timers.
+        assert!(start.elapsed() >= Duration::from_millis(500));
+        let (num_throttled, delay) = node.tcp().stats().throttled();
+        assert_eq!(num_throttled, 1);
+        assert_eq!(delay, Duration::from_millis(500));
+        assert_eq!(node.tcp().stats().sent(), (4, 2 * RATE));
     }
 }
@@ -27,21 +27,19 @@ use std::{
 
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
-use tokio::{
-    io::split,
-    net::{TcpListener, TcpStream},
-    sync::oneshot,
-    task::JoinHandle,
-    time::timeout,
-};
+use tokio::{io::split, sync::oneshot, task::JoinHandle, time::timeout};
 use tracing::*;
 
 use crate::{
     connections::{Connection, ConnectionSide, Connections},
     protocols::{Protocol, Protocols},
+    socks5_connect,
+    transport::TransportListener,
+    BoxedStream,
     Config,
     KnownPeers,
     Stats,
+    TokenBucket,
 };
 
 // A sequential numeric identifier assigned to `Tcp`s that were not provided with a name.
@@ -77,6 +75,8 @@ pub struct InnerTcp {
     known_peers: KnownPeers,
     /// Collects statistics related to the node itself.
     stats: Stats,
+    /// Limits the rate at which messages are sent to all the peers combined, if configured.
+    send_limiter: Option<TokenBucket>,
     /// The node's tasks.
     pub(crate) tasks: Mutex<Vec<JoinHandle<()>>>,
 }
@@ -92,6 +92,9 @@ impl Tcp {
         // Create a tracing span containing the node's name.
         let span = crate::helpers::create_span(config.name.as_deref().unwrap());
 
+        // Initialize the global bandwidth limit.
+        let send_limiter = config.max_send_rate.filter(|rate| *rate > 0).map(TokenBucket::new);
+
         // Initialize the Tcp stack.
         let tcp = Tcp(Arc::new(InnerTcp {
             span,
@@ -102,6 +105,7 @@ impl Tcp {
             connections: Default::default(),
             known_peers: Default::default(),
             stats: Default::default(),
+            send_limiter,
             tasks: Default::default(),
         }));
 
@@ -171,6 +175,12 @@ impl Tcp {
         &self.stats
     }
 
+    /// Returns the limiter of the rate at which messages are sent to all the peers combined, if configured.
+    #[inline]
+    pub(crate) fn send_limiter(&self) -> Option<&TokenBucket> {
+        self.send_limiter.as_ref()
+    }
+
     /// Returns the tracing [`Span`] associated with Tcp.
     #[inline]
     pub fn span(&self) -> &Span {
@@ -227,15 +237,18 @@ impl Tcp {
 
         let timeout_duration = Duration::from_millis(self.config().connection_timeout_ms.into());
 
-        // Bind the tcp socket to the configured listener ip if it's set.
-        // Otherwise default to the system's default interface.
-        let res = if let Some(listen_ip) = self.config().listener_ip {
-            let sock =
-                if listen_ip.is_ipv4() { tokio::net::TcpSocket::new_v4()? } else { tokio::net::TcpSocket::new_v6()? };
-            sock.bind(SocketAddr::new(listen_ip, 0))?;
-            timeout(timeout_duration, sock.connect(addr)).await
+        // Connect through the configured proxy if it's set. Otherwise, connect from the configured
+        // listener ip if it's set, or default to the system's default interface.
+        let transport = &self.config().transport;
+        let res = if let Some(proxy) = self.config().proxy {
+            timeout(timeout_duration, async {
+                let mut stream = transport.connect(proxy, None).await?;
+                socks5_connect(&mut stream, addr).await?;
+                Ok(stream)
+            })
+            .await
         } else {
-            timeout(timeout_duration, TcpStream::connect(addr)).await
+            timeout(timeout_duration, transport.connect(addr, self.config().listener_ip)).await
         };
 
         let stream = match res {
@@ -305,7 +318,7 @@ impl Tcp {
         let listener_ip =
             self.config().listener_ip.expect("Tcp::enable_listener was called, but Config::listener_ip is not set");
 
-        // Initialize the TCP listener.
+        // Initialize the listener.
         let listener = self.create_listener(listener_ip).await?;
 
         // Discover the port, if it was unspecified.
@@ -338,14 +351,14 @@ impl Tcp {
         Ok(listening_addr)
     }
 
-    /// Creates an instance of `TcpListener` based on the node's configuration.
-    async fn create_listener(&self, listener_ip: IpAddr) -> io::Result<TcpListener> {
-        debug!("Creating a TCP listener on {listener_ip}...");
+    /// Creates a listener of the configured transport based on the node's configuration.
+    async fn create_listener(&self, listener_ip: IpAddr) -> io::Result<Box<dyn TransportListener>> {
+        debug!("Creating a listener on {listener_ip}...");
         let listener = if let Some(port) = self.config().desired_listening_port {
             // Construct the desired listening IP address.
             let desired_listening_addr = SocketAddr::new(listener_ip, port);
             // If a desired listening port is set, try to bind to it.
-            match TcpListener::bind(desired_listening_addr).await {
+            match self.config().transport.bind(desired_listening_addr).await {
                 Ok(listener) => listener,
                 Err(e) => {
                     if self.config().allow_random_port {
@@ -354,7 +367,7 @@ impl Tcp {
                             "Trying any listening port, as the desired port is unavailable: {e}"
                         );
                         let random_available_addr = SocketAddr::new(listener_ip, 0);
-                        TcpListener::bind(random_available_addr).await?
+                        self.config().transport.bind(random_available_addr).await?
                     } else {
                         error!(parent: self.span(), "The desired listening port is unavailable: {e}");
                         return Err(e);
@@ -363,7 +376,7 @@ impl Tcp {
             }
         } else if self.config().allow_random_port {
             let random_available_addr = SocketAddr::new(listener_ip, 0);
-            TcpListener::bind(random_available_addr).await?
+            self.config().transport.bind(random_available_addr).await?
         } else {
             panic!("As 'listener_ip' is set, either 'desired_listening_port' or 'allow_random_port' must be set");
         };
@@ -372,7 +385,7 @@ impl Tcp {
     }
 
     /// Handles a new inbound connection.
-    fn handle_connection(&self, stream: TcpStream, addr: SocketAddr) {
+    fn handle_connection(&self, stream: BoxedStream, addr: SocketAddr) {
         debug!(parent: self.span(), "Received a connection from {addr}");
 
         if !self.can_add_connection() || self.is_self_connect(addr) {
@@ -425,12 +438,20 @@ impl Tcp {
     }
 
     /// Prepares the freshly acquired connection to handle the protocols the Tcp implements.
-    async fn adapt_stream(&self, stream: TcpStream, peer_addr: SocketAddr, own_side: ConnectionSide) -> io::Result<()> {
+    async fn adapt_stream(
+        &self,
+        stream: BoxedStream,
+        peer_addr: SocketAddr,
+        own_side: ConnectionSide,
+    ) -> io::Result<()> {
         self.known_peers.add(peer_addr);
 
         // Register the port seen by the peer.
         if own_side == ConnectionSide::Initiator {
-            if let Ok(addr) = stream.local_addr() {
+            if let Some(proxy) = self.config().proxy {
+                // The peer sees the proxy's address, which is unknown to the Tcp.
+                debug!(parent: self.span(), "establishing connection with {} through the proxy at {}", peer_addr, proxy);
+            } else if let Ok(addr) = stream.local_addr() {
                 debug!(
                     parent: self.span(), "establishing connection with {}; the peer is connected on port {}",
                     peer_addr, addr.port()
@@ -514,6 +535,7 @@ mod tests {
     use super::*;
 
     use std::net::{IpAddr, Ipv4Addr};
+    use tokio::net::TcpStream;
 
     #[tokio::test]
     async fn test_new() {
@@ -612,7 +634,7 @@ mod tests {
         assert!(tcp.can_add_connection());
 
         // Simulate an active connection.
-        let stream = TcpStream::connect(peer_ip).await.unwrap();
+        let stream = Box::new(TcpStream::connect(peer_ip).await.unwrap());
         tcp.connections.add(Connection::new(peer_ip, stream, ConnectionSide::Initiator));
         assert!(!tcp.can_add_connection());
 
@@ -629,7 +651,7 @@ mod tests {
         assert!(tcp.can_add_connection());
 
         // Simulate an active and a pending connection (this case should never occur).
-        let stream = TcpStream::connect(peer_ip).await.unwrap();
+        let stream = Box::new(TcpStream::connect(peer_ip).await.unwrap());
         tcp.connections.add(Connection::new(peer_ip, stream, ConnectionSide::Responder));
         tcp.connecting.lock().insert(peer_ip);
         assert!(!tcp.can_add_connection());
@@ -658,7 +680,7 @@ mod tests {
         let peer1_ip = peer1.enable_listener().await.unwrap();
 
         // Simulate an active connection.
-        let stream = TcpStream::connect(peer1_ip).await.unwrap();
+        let stream = Box::new(TcpStream::connect(peer1_ip).await.unwrap());
         tcp.connections.add(Connection::new(peer1_ip, stream, ConnectionSide::Responder));
         assert!(!tcp.can_add_connection());
         assert_eq!(tcp.num_connected(), 1);
@@ -676,7 +698,7 @@ mod tests {
         let peer2_ip = peer2.enable_listener().await.unwrap();
 
         // Handle the connection.
-        let stream = TcpStream::connect(peer2_ip).await.unwrap();
+        let stream = Box::new(TcpStream::connect(peer2_ip).await.unwrap());
         tcp.handle_connection(stream, peer2_ip);
         assert!(!tcp.can_add_connection());
         assert_eq!(tcp.num_connected(), 1);
@@ -708,7 +730,7 @@ mod tests {
         assert!(tcp.is_connecting(peer_ip));
 
         // Simulate a new connection.
-        let stream = TcpStream::connect(peer_ip).await.unwrap();
+        let stream = Box::new(TcpStream::connect(peer_ip).await.unwrap());
         tcp.adapt_stream(stream, peer_ip, ConnectionSide::Responder).await.unwrap();
         assert_eq!(tcp.num_connected(), 1);
         assert_eq!(tcp.num_connecting(), 0);
@@ -15,9 +15,11 @@
 pub mod node;
 pub mod test_peer;
 
-use std::{env, str::FromStr};
+use std::{env, str::FromStr, sync::Arc};
 
 use snarkos_account::Account;
+use snarkos_node_router::RouterConfig;
+use snarkos_node_tcp::{Config, MemoryTransport};
 use snarkvm::prelude::{block::Block, FromBytes, MainnetV0 as CurrentNetwork, Network};
 
 /// Returns a fixed account.
@@ -30,6 +32,17 @@ pub fn sample_genesis_block() -> Block<CurrentNetwork> {
     Block::<CurrentNetwork>::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap()
 }
 
+/// Returns a TCP configuration over the given in-memory network, so that the nodes of a test don't compete
+/// for the ports of the host.
+pub fn tcp_config(network: &MemoryTransport) -> Config {
+    Config { transport: Arc::new(network.clone()), ..Default::default() }
+}
+
+/// Returns a router configuration over the given in-memory network.
+pub fn router_config(network: &MemoryTransport) -> RouterConfig {
+    RouterConfig { tcp: tcp_config(network), ..Default::default() }
+}
+
 /// Enables logging in tests.
 pub fn initialise_logger(level: u8) {
     match level {
@@ -12,21 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::common::test_peer::sample_genesis_block;
+use crate::common::{router_config, test_peer::sample_genesis_block};
 use snarkos_account::Account;
 use snarkos_node::{Client, Prover, Validator};
+use snarkos_node_tcp::MemoryTransport;
 use snarkvm::prelude::{store::helpers::memory::ConsensusMemory, MainnetV0 as CurrentNetwork};
 
 use aleo_std::StorageMode;
 use std::str::FromStr;
 
-pub async fn client() -> Client<CurrentNetwork, ConsensusMemory<CurrentNetwork>> {
+pub async fn client(network: &MemoryTransport) -> Client<CurrentNetwork, ConsensusMemory<CurrentNetwork>> {
     Client::new(
         "127.0.0.1:0".parse().unwrap(),
         None,
         10,
         Account::<CurrentNetwork>::from_str("APrivateKey1zkp2oVPTci9kKcUprnbzMwq95Di1MQERpYBhEeqvkrDirK1").unwrap(),
         &[],
+        router_config(network),
         sample_genesis_block(),
         None, // No CDN.
         StorageMode::Production,
@@ -36,11 +38,12 @@ pub async fn client() -> Client<CurrentNetwork, ConsensusMemory<CurrentNetwork>>
     .expect("couldn't create client instance")
 }
 
-pub async fn prover() -> Prover<CurrentNetwork, ConsensusMemory<CurrentNetwork>> {
+pub async fn prover(network: &MemoryTransport) -> Prover<CurrentNetwork, ConsensusMemory<CurrentNetwork>> {
     Prover::new(
         "127.0.0.1:0".parse().unwrap(),
         Account::<CurrentNetwork>::from_str("APrivateKey1zkp2oVPTci9kKcUprnbzMwq95Di1MQERpYBhEeqvkrDirK1").unwrap(),
         &[],
+        router_config(network),
         sample_genesis_block(),
         StorageMode::Production,
         Default::default(),
@@ -49,7 +52,7 @@ pub async fn prover() -> Prover<CurrentNetwork, ConsensusMemory<CurrentNetwork>>
     .expect("couldn't create prover instance")
 }
 
-pub async fn validator() -> Validator<CurrentNetwork, ConsensusMemory<CurrentNetwork>> {
+pub async fn validator(network: &MemoryTransport) -> Validator<CurrentNetwork, ConsensusMemory<CurrentNetwork>> {
     Validator::new(
         "127.0.0.1:0".parse().unwrap(),
         None,
@@ -57,6 +60,7 @@ pub async fn validator() -> Validator<CurrentNetwork, ConsensusMemory<CurrentNet
         10,
         Account::<CurrentNetwork>::from_str("APrivateKey1zkp2oVPTci9kKcUprnbzMwq95Di1MQERpYBhEeqvkrDirK1").unwrap(),
         &[],
+        router_config(network),
         &[],
         sample_genesis_block(), // Should load the current network's genesis block.
         None,                   // No CDN.
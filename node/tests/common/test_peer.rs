@@ -15,7 +15,25 @@
 use snarkos_account::Account;
 use snarkos_node_router::{
     expect_message,
-    messages::{ChallengeRequest, ChallengeResponse, Message, MessageCodec, MessageTrait, NodeType},
+    messages::{
+        Capabilities,
+        ChallengeRequest,
+        ChallengeResponse,
+        Compression,
+        Message,
+        MessageCodec,
+        MessageTrait,
+        NodeType,
+    },
+};
+use snarkos_node_tcp::{
+    protocols::{Disconnect, Handshake, Reading, Writing},
+    Config,
+    Connection,
+    ConnectionSide,
+    MemoryTransport,
+    Tcp,
+    P2P,
 };
 use snarkvm::{
     ledger::narwhal::Data,
@@ -29,14 +47,6 @@ use std::{
 };
 
 use futures_util::{sink::SinkExt, TryStreamExt};
-use pea2pea::{
-    protocols::{Handshake, OnDisconnect, Reading, Writing},
-    Config,
-    Connection,
-    ConnectionSide,
-    Node,
-    Pea2Pea,
-};
 use rand::Rng;
 use tokio_util::codec::Framed;
 use tracing::*;
@@ -55,39 +65,54 @@ pub fn sample_genesis_block() -> Block<CurrentNetwork> {
 
 #[derive(Clone)]
 pub struct TestPeer {
-    node: Node,
+    tcp: Tcp,
     node_type: NodeType,
     account: Account<CurrentNetwork>,
+    /// The version of the network protocol advertised by the test peer.
+    version: u32,
 }
 
-impl Pea2Pea for TestPeer {
-    fn node(&self) -> &Node {
-        &self.node
+impl P2P for TestPeer {
+    fn tcp(&self) -> &Tcp {
+        &self.tcp
     }
 }
 
 impl TestPeer {
-    pub async fn client() -> Self {
-        Self::new(NodeType::Client, sample_account()).await
+    pub async fn client(network: &MemoryTransport) -> Self {
+        Self::new(network, NodeType::Client, sample_account()).await
+    }
+
+    pub async fn prover(network: &MemoryTransport) -> Self {
+        Self::new(network, NodeType::Prover, sample_account()).await
     }
 
-    pub async fn prover() -> Self {
-        Self::new(NodeType::Prover, sample_account()).await
+    pub async fn validator(network: &MemoryTransport) -> Self {
+        Self::new(network, NodeType::Validator, sample_account()).await
     }
 
-    pub async fn validator() -> Self {
-        Self::new(NodeType::Validator, sample_account()).await
+    pub async fn new(network: &MemoryTransport, node_type: NodeType, account: Account<CurrentNetwork>) -> Self {
+        Self::with_version(network, node_type, account, Message::<CurrentNetwork>::VERSION).await
     }
 
-    pub async fn new(node_type: NodeType, account: Account<CurrentNetwork>) -> Self {
+    /// Initializes a test peer that advertises the given version of the network protocol,
+    /// and formats its messages accordingly.
+    pub async fn with_version(
+        network: &MemoryTransport,
+        node_type: NodeType,
+        account: Account<CurrentNetwork>,
+        version: u32,
+    ) -> Self {
         let peer = Self {
-            node: Node::new(Config {
+            tcp: Tcp::new(Config {
                 max_connections: 200,
-                listener_addr: Some(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)),
-                ..Default::default()
+                listener_ip: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+                desired_listening_port: Some(0),
+                ..crate::common::tcp_config(network)
             }),
             node_type,
             account,
+            version,
         };
 
         peer.enable_handshake().await;
@@ -95,7 +120,7 @@ impl TestPeer {
         peer.enable_writing().await;
         peer.enable_disconnect().await;
 
-        peer.node().start_listening().await.unwrap();
+        peer.tcp().enable_listener().await.unwrap();
 
         peer
     }
@@ -111,19 +136,30 @@ impl TestPeer {
     pub fn address(&self) -> Address<CurrentNetwork> {
         self.account.address()
     }
-}
-
-impl Handshake for TestPeer {
-    async fn perform_handshake(&self, mut conn: Connection) -> io::Result<Connection> {
-        let rng = &mut TestRng::default();
 
-        let local_ip = self.node().listening_addr().expect("listening address should be present");
+    /// Returns the version negotiated with a node that advertises the given version.
+    fn negotiate_version(&self, peer_version: u32) -> u32 {
+        peer_version.min(self.version)
+    }
 
-        let peer_addr = conn.addr();
-        let node_side = !conn.side();
-        let stream = self.borrow_stream(&mut conn);
-        let mut framed = Framed::new(stream, MessageCodec::<CurrentNetwork>::default());
+    /// Returns the challenge request of the test peer.
+    fn challenge_request(&self, listener_port: u16, nonce: u64) -> ChallengeRequest<CurrentNetwork> {
+        ChallengeRequest {
+            version: self.version,
+            listener_port,
+            node_type: self.node_type,
+            address: self.address(),
+            nonce,
+        }
+    }
 
+    /// Returns the challenge response of the test peer, to the given challenge request.
+    fn challenge_response(
+        &self,
+        peer_request: &ChallengeRequest<CurrentNetwork>,
+        version: u32,
+        rng: &mut TestRng,
+    ) -> ChallengeResponse<CurrentNetwork> {
         // Retrieve the genesis block header.
         let genesis_header = *sample_genesis_block().header();
         // Retrieve the restrictions ID.
@@ -132,49 +168,75 @@ impl Handshake for TestPeer {
         )
         .unwrap();
 
+        // Sign the nonce, along with the negotiated version and the capabilities (if supported).
+        let response_nonce: u64 = rng.gen();
+        let capabilities = (version >= Message::<CurrentNetwork>::CAPABILITIES_VERSION).then(Capabilities::supported);
+        let version = (version >= Message::<CurrentNetwork>::NEGOTIATION_VERSION).then_some(version);
+        let request = ChallengeResponse::signing_request(peer_request.nonce, response_nonce, capabilities, version);
+        let signature = self.account().sign_request(&request, rng).unwrap();
+
+        ChallengeResponse {
+            genesis_header,
+            restrictions_id,
+            signature: Data::Object(signature),
+            nonce: response_nonce,
+            capabilities,
+            version,
+        }
+    }
+
+    /// Returns the codec of the connections, which are assumed to negotiate the version of the test peer.
+    fn connection_codec(&self) -> MessageCodec<CurrentNetwork> {
+        // Note: The nodes advertise compression in their challenge responses, if the version supports it.
+        let capabilities = match self.version >= Message::<CurrentNetwork>::CAPABILITIES_VERSION {
+            true => Capabilities::supported(),
+            false => Capabilities::empty(),
+        };
+        MessageCodec::default()
+            .with_compression(Compression::new(capabilities, capabilities))
+            .with_version(self.version)
+    }
+}
+
+#[async_trait::async_trait]
+impl Handshake for TestPeer {
+    async fn perform_handshake(&self, mut conn: Connection) -> io::Result<Connection> {
+        let rng = &mut TestRng::default();
+
+        let local_ip = self.tcp().listening_addr().expect("listening address should be present");
+
+        let peer_addr = conn.addr();
+        let node_side = !conn.side();
+        let stream = self.borrow_stream(&mut conn);
+        let mut framed = Framed::new(stream, MessageCodec::<CurrentNetwork>::handshake());
+
         // TODO(nkls): add assertions on the contents of messages.
         match node_side {
             ConnectionSide::Initiator => {
                 // Send a challenge request to the peer.
-                let our_request = ChallengeRequest::new(local_ip.port(), self.node_type(), self.address(), rng.gen());
+                let our_request = self.challenge_request(local_ip.port(), rng.gen());
                 framed.send(Message::ChallengeRequest(our_request)).await?;
 
                 // Receive the peer's challenge bundle.
                 let _peer_response = expect_message!(Message::ChallengeResponse, framed, peer_addr);
                 let peer_request = expect_message!(Message::ChallengeRequest, framed, peer_addr);
-
-                // Sign the nonce.
-                let response_nonce: u64 = rng.gen();
-                let data = [peer_request.nonce.to_le_bytes(), response_nonce.to_le_bytes()].concat();
-                let signature = self.account().sign_bytes(&data, rng).unwrap();
+                let version = self.negotiate_version(peer_request.version);
+                framed.codec_mut().set_version(version);
 
                 // Send the challenge response.
-                let our_response = ChallengeResponse {
-                    genesis_header,
-                    restrictions_id,
-                    signature: Data::Object(signature),
-                    nonce: response_nonce,
-                };
+                let our_response = self.challenge_response(&peer_request, version, rng);
                 framed.send(Message::ChallengeResponse(our_response)).await?;
             }
             ConnectionSide::Responder => {
                 // Listen for the challenge request.
                 let peer_request = expect_message!(Message::ChallengeRequest, framed, peer_addr);
-
-                // Sign the nonce.
-                let response_nonce: u64 = rng.gen();
-                let data = [peer_request.nonce.to_le_bytes(), response_nonce.to_le_bytes()].concat();
-                let signature = self.account().sign_bytes(&data, rng).unwrap();
+                let version = self.negotiate_version(peer_request.version);
+                framed.codec_mut().set_version(version);
 
                 // Send our challenge bundle.
-                let our_response = ChallengeResponse {
-                    genesis_header,
-                    restrictions_id,
-                    signature: Data::Object(signature),
-                    nonce: response_nonce,
-                };
+                let our_response = self.challenge_response(&peer_request, version, rng);
                 framed.send(Message::ChallengeResponse(our_response)).await?;
-                let our_request = ChallengeRequest::new(local_ip.port(), self.node_type(), self.address(), rng.gen());
+                let our_request = self.challenge_request(local_ip.port(), rng.gen());
                 framed.send(Message::ChallengeRequest(our_request)).await?;
 
                 // Listen for the challenge response.
@@ -186,21 +248,23 @@ impl Handshake for TestPeer {
     }
 }
 
+#[async_trait::async_trait]
 impl Writing for TestPeer {
     type Codec = MessageCodec<CurrentNetwork>;
     type Message = Message<CurrentNetwork>;
 
     fn codec(&self, _addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+        self.connection_codec()
     }
 }
 
+#[async_trait::async_trait]
 impl Reading for TestPeer {
     type Codec = MessageCodec<CurrentNetwork>;
     type Message = Message<CurrentNetwork>;
 
     fn codec(&self, _peer_addr: SocketAddr, _side: ConnectionSide) -> Self::Codec {
-        Default::default()
+        self.connection_codec()
     }
 
     async fn process_message(&self, _peer_ip: SocketAddr, _message: Self::Message) -> io::Result<()> {
@@ -208,6 +272,7 @@ impl Reading for TestPeer {
     }
 }
 
-impl OnDisconnect for TestPeer {
-    async fn on_disconnect(&self, _peer_addr: SocketAddr) {}
+#[async_trait::async_trait]
+impl Disconnect for TestPeer {
+    async fn handle_disconnect(&self, _peer_addr: SocketAddr) {}
 }
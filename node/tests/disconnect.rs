@@ -19,7 +19,7 @@ mod common;
 use common::{node::*, test_peer::TestPeer};
 
 use snarkos_node_router::Outbound;
-use snarkos_node_tcp::P2P;
+use snarkos_node_tcp::{MemoryTransport, P2P};
 
 use deadline::deadline;
 use std::time::Duration;
@@ -36,19 +36,17 @@ macro_rules! test_disconnect {
         $(#[$attr])?
         async fn $peer_type() {
             use deadline::deadline;
-            use pea2pea::Pea2Pea;
             use snarkos_node_router::Outbound;
             use snarkos_node_tcp::P2P;
             use std::time::Duration;
 
             // $crate::common::initialise_logger(2);
 
-            // Spin up a full node.
-            let node = $crate::$node_type().await;
-
-            // Spin up a test peer (synthetic node).
-            let peer = $crate::TestPeer::$peer_type().await;
-            let peer_addr = peer.node().listening_addr().unwrap();
+            // Spin up a full node, and a test peer (synthetic node), on the same in-memory network.
+            let network = snarkos_node_tcp::MemoryTransport::default();
+            let node = $crate::$node_type(&network).await;
+            let peer = $crate::TestPeer::$peer_type(&network).await;
+            let peer_addr = peer.tcp().listening_addr().unwrap();
 
             // Connect the node to the test peer.
             node.router().connect(peer_addr).unwrap().await.unwrap();
@@ -59,20 +57,20 @@ macro_rules! test_disconnect {
             let node_clone = node.clone();
             deadline!(Duration::from_secs(5), move || node_clone.tcp().num_connected() == 1);
             let peer_clone = peer.clone();
-            deadline!(Duration::from_secs(5), move || peer_clone.node().num_connected() == 1);
+            deadline!(Duration::from_secs(5), move || peer_clone.tcp().num_connected() == 1);
 
             // Disconnect.
             if $node_disconnects {
                 node.router().disconnect(node.tcp().connected_addrs()[0]).await.unwrap();
             } else {
-                peer.node().disconnect(peer.node().connected_addrs()[0]).await;
+                peer.tcp().disconnect(peer.tcp().connected_addrs()[0]).await;
             }
 
             // Check the peer counts have been updated.
             let node_clone = node.clone();
             deadline!(Duration::from_secs(5), move || node_clone.router().number_of_connected_peers() == 0);
             deadline!(Duration::from_secs(5), move || node.tcp().num_connected() == 0);
-            deadline!(Duration::from_secs(5), move || peer.node().num_connected() == 0);
+            deadline!(Duration::from_secs(5), move || peer.tcp().num_connected() == 0);
 
         }
     };
@@ -148,9 +146,10 @@ mod validator {
 async fn duplicate_disconnect_attempts() {
     // common::initialise_logger(3);
 
-    // Spin up 2 full nodes.
-    let node1 = validator().await;
-    let node2 = validator().await;
+    // Spin up 2 full nodes, on the same in-memory network.
+    let network = MemoryTransport::default();
+    let node1 = validator(&network).await;
+    let node2 = validator(&network).await;
     let addr2 = node2.tcp().listening_addr().unwrap();
 
     // Connect node1 to node2.
@@ -19,16 +19,18 @@ mod common;
 use common::{node::*, test_peer::TestPeer};
 
 use snarkos_node::{Client, Prover, Validator};
-use snarkos_node_router::Outbound;
-use snarkos_node_tcp::P2P;
+use snarkos_node_router::{
+    messages::{Capabilities, Message, NodeType},
+    Outbound,
+};
+use snarkos_node_tcp::{MemoryTransport, P2P};
 use snarkvm::prelude::{store::helpers::memory::ConsensusMemory, MainnetV0 as CurrentNetwork};
 
-use pea2pea::Pea2Pea;
-
+use deadline::deadline;
 use std::{io, net::SocketAddr, time::Duration};
 use tokio::time::sleep;
 
-// Trait to unify Pea2Pea and P2P traits.
+// Trait to unify the node types and the test peer.
 #[async_trait::async_trait]
 trait Connect {
     fn listening_addr(&self) -> SocketAddr;
@@ -63,14 +65,14 @@ impl_connect!(Client, Prover, Validator);
 #[async_trait::async_trait]
 impl Connect for TestPeer
 where
-    Self: Pea2Pea,
+    Self: P2P,
 {
     fn listening_addr(&self) -> SocketAddr {
-        self.node().listening_addr().expect("node listener should exist")
+        self.tcp().listening_addr().expect("node listener should exist")
     }
 
     async fn connect(&self, target: SocketAddr) -> io::Result<()> {
-        self.node().connect(target).await
+        self.tcp().connect(target).await
     }
 }
 
@@ -98,11 +100,10 @@ macro_rules! test_handshake {
         async fn $peer_type() {
             // $crate::common::initialise_logger(2);
 
-            // Spin up a full node.
-            let node = $crate::$node_type().await;
-
-            // Spin up a test peer (synthetic node).
-            let peer = $crate::common::test_peer::TestPeer::$peer_type().await;
+            // Spin up a full node, and a test peer (synthetic node), on the same in-memory network.
+            let network = snarkos_node_tcp::MemoryTransport::default();
+            let node = $crate::$node_type(&network).await;
+            let peer = $crate::common::test_peer::TestPeer::$peer_type(&network).await;
 
             // Sets up the connection direction as described above.
             if $is_initiator {
@@ -186,10 +187,11 @@ mod validator {
 async fn simultaneous_connection_attempt() {
     // common::initialise_logger(3);
 
-    // Spin up 2 full nodes.
-    let node1 = validator().await;
+    // Spin up 2 full nodes, on the same in-memory network.
+    let network = MemoryTransport::default();
+    let node1 = validator(&network).await;
     let addr1 = node1.listening_addr();
-    let node2 = validator().await;
+    let node2 = validator(&network).await;
     let addr2 = node2.listening_addr();
 
     // Prepare connection attempts.
@@ -245,9 +247,10 @@ async fn simultaneous_connection_attempt() {
 async fn duplicate_connection_attempts() {
     // common::initialise_logger(3);
 
-    // Spin up 2 full nodes.
-    let node1 = validator().await;
-    let node2 = validator().await;
+    // Spin up 2 full nodes, on the same in-memory network.
+    let network = MemoryTransport::default();
+    let node1 = validator(&network).await;
+    let node2 = validator(&network).await;
     let addr2 = node2.listening_addr();
 
     // Prepare connection attempts.
@@ -286,3 +289,37 @@ async fn duplicate_connection_attempts() {
     assert_eq!(node1.router().number_of_connected_peers(), 1);
     assert_eq!(node2.router().number_of_connected_peers(), 1);
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handshake_with_minimum_version_peer() {
+    // common::initialise_logger(3);
+
+    let minimum_version = Message::<CurrentNetwork>::MINIMUM_VERSION;
+    for is_initiator in [true, false] {
+        // Spin up a full node, and a test peer on the minimum version, on the same in-memory network.
+        let network = MemoryTransport::default();
+        let node = client(&network).await;
+        let account = common::test_peer::sample_account();
+        let peer = TestPeer::with_version(&network, NodeType::Client, account, minimum_version).await;
+        let peer_ip = peer.listening_addr();
+
+        // Connect the node and the test peer, in either direction.
+        if is_initiator {
+            assert_connect(node.clone(), peer.clone()).await;
+        } else {
+            assert_connect(peer.clone(), node.clone()).await;
+        }
+
+        // Ensure the node negotiated the minimum version with the test peer.
+        let node_ = node.clone();
+        deadline!(Duration::from_secs(5), move || node_.router().is_connected(&peer_ip));
+        let connected_peer = node.router().get_connected_peer(&peer_ip).unwrap();
+        assert_eq!(connected_peer.version(), minimum_version);
+        assert_eq!(connected_peer.capabilities(), Capabilities::empty());
+
+        // Ensure the connection remains open, as the messages of the node are in the format of the minimum version.
+        sleep(Duration::from_millis(500)).await;
+        assert!(node.router().is_connected(&peer_ip));
+        assert_eq!(peer.tcp().num_connected(), 1);
+    }
+}
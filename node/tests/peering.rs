@@ -20,11 +20,10 @@ use snarkos_node_router::{
     messages::{Message, PeerResponse},
     Outbound,
 };
-use snarkos_node_tcp::P2P;
+use snarkos_node_tcp::{protocols::Writing, P2P};
 
 use deadline::deadline;
 use paste::paste;
-use pea2pea::{protocols::Writing, Pea2Pea};
 use std::time::Duration;
 
 macro_rules! test_reject_unsolicited_peer_response {
@@ -33,12 +32,13 @@ macro_rules! test_reject_unsolicited_peer_response {
             paste! {
                 #[tokio::test]
                 async fn [<$node_type _rejects_unsolicited_peer_response>]() {
-                    // Spin up a full node.
-                    let node = $crate::common::node::$node_type().await;
+                    // Spin up a full node, on an in-memory network.
+                    let network = snarkos_node_tcp::MemoryTransport::default();
+                    let node = $crate::common::node::$node_type(&network).await;
 
                     // Spin up a test peer (synthetic node), it doesn't really matter what type it is.
-                    let peer = TestPeer::validator().await;
-                    let peer_addr = peer.node().listening_addr().unwrap();
+                    let peer = TestPeer::validator(&network).await;
+                    let peer_addr = peer.tcp().listening_addr().unwrap();
 
                     // Connect the node to the test peer.
                     node.router().connect(peer_addr).unwrap().await.unwrap();
@@ -49,7 +49,7 @@ macro_rules! test_reject_unsolicited_peer_response {
                     let node_clone = node.clone();
                     deadline!(Duration::from_secs(5), move || node_clone.tcp().num_connected() == 1);
                     let peer_clone = peer.clone();
-                    deadline!(Duration::from_secs(5), move || peer_clone.node().num_connected() == 1);
+                    deadline!(Duration::from_secs(5), move || peer_clone.tcp().num_connected() == 1);
 
                     // Check the candidate peers.
                     assert_eq!(node.router().number_of_candidate_peers(), 0);
@@ -59,7 +59,7 @@ macro_rules! test_reject_unsolicited_peer_response {
                     // Send a `PeerResponse` to the node.
                     assert!(
                         peer.unicast(
-                            *peer.node().connected_addrs().first().unwrap(),
+                            *peer.tcp().connected_addrs().first().unwrap(),
                             Message::PeerResponse(PeerResponse { peers: peers.clone() })
                         )
                         .is_ok()